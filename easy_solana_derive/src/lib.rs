@@ -0,0 +1,82 @@
+//! # EasySolana Derive
+//!
+//! Derive macros of EasySolana, re-exported by the `easy_solana` crate. See
+//! `easy_solana::easy_account` for how derived accounts are used.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Expr, Fields, LitStr, Type};
+
+/// Implements `easy_solana::easy_account::EasyAccount` for a struct deriving `BorshDeserialize`.
+///
+/// The struct accepts an `#[easy_account(...)]` attribute with the options:
+///
+/// - `discriminator = <expression>`: Bytes the account data starts with, an array or a constant.
+/// - `name = "<name>"`: Name of the account in errors, the struct name by default.
+///
+/// Every `String` field is trimmed of the null bytes padding it.
+#[proc_macro_derive(EasyAccount, attributes(easy_account))]
+pub fn derive_easy_account(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_easy_account(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_easy_account(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let mut discriminator: Option<Expr> = None;
+    let mut name: Option<LitStr> = None;
+    for attribute in input.attrs.iter().filter(|attribute| attribute.path().is_ident("easy_account")) {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("discriminator") {
+                discriminator = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `discriminator` or `name`"))
+            }
+        })?;
+    }
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(&input.ident, "EasyAccount can only be derived for structs"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(&input.ident, "EasyAccount requires named fields"));
+    };
+    let string_fields = fields
+        .named
+        .iter()
+        .filter(|field| is_string(&field.ty))
+        .filter_map(|field| field.ident.as_ref());
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let discriminator = discriminator.map(|discriminator| quote!(&#discriminator)).unwrap_or(quote!(&[]));
+    let name = name.map(|name| name.value()).unwrap_or_else(|| ident.to_string());
+
+    Ok(quote! {
+        impl #impl_generics ::easy_solana::easy_account::EasyAccount for #ident #type_generics #where_clause {
+            const DISCRIMINATOR: &'static [u8] = #discriminator;
+            const NAME: &'static str = #name;
+
+            fn try_from_account_data(data: &[u8]) -> ::std::result::Result<Self, ::easy_solana::error::ReadTransactionError> {
+                let mut account: Self = ::easy_solana::easy_account::deserialize_account_data::<Self>(data)?;
+                #(
+                    account.#string_fields = ::easy_solana::easy_account::trim_padding(&account.#string_fields);
+                )*
+                ::std::result::Result::Ok(account)
+            }
+        }
+    })
+}
+
+fn is_string(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => type_path.qself.is_none() && type_path.path.segments.last().is_some_and(|segment| segment.ident == "String"),
+        _ => false,
+    }
+}
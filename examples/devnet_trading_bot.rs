@@ -0,0 +1,99 @@
+//! Trading bot running against a devnet fixture: it sets up a test mint with a Raydium CPMM
+//! pool, buys the token with SOL a few times, sells everything back and cleans up its token
+//! accounts.
+//!
+//! Run with `cargo run --example devnet_trading_bot`, with these environment variables set:
+//!
+//! - `RAYDIUM_CPMM_DEVNET_PROGRAM`: Raydium CPMM program on devnet.
+//! - `RAYDIUM_CPMM_DEVNET_FEE_ACCOUNT`: Pool creation fee account of that program.
+//! - `DEVNET_PRIVATE_KEY` (optional): Base58 private key holding at least 1 SOL on devnet,
+//!   otherwise a new wallet is funded by airdrop.
+
+use easy_solana::{
+    constants::solana_programs::{sol_pubkey, token_program},
+    create_rpc_client,
+    devnet::{airdrop_sol, setup_devnet_fixture, DevnetFixtureConfig, DEVNET_RPC_URL},
+    explorer_url_for_signature,
+    get_token_balance,
+    read_transactions::associated_token_account::derive_associated_token_account_address,
+    write_transactions::{transaction_builder::TransactionBuilder, utils::send_and_confirm_transaction},
+    Cluster, Explorer, SecretKeypair
+};
+use dotenv::dotenv;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signer::Signer, transaction::Transaction};
+use std::env;
+
+const BUY_LAMPORTS: u64 = LAMPORTS_PER_SOL / 100;
+const BUYS: usize = 3;
+
+fn main() {
+    dotenv().ok();
+    let config = DevnetFixtureConfig::new(
+        &env::var("RAYDIUM_CPMM_DEVNET_PROGRAM").expect("RAYDIUM_CPMM_DEVNET_PROGRAM not set"),
+        &env::var("RAYDIUM_CPMM_DEVNET_FEE_ACCOUNT").expect("RAYDIUM_CPMM_DEVNET_FEE_ACCOUNT not set")
+    ).expect("Invalid Raydium addresses");
+    let client = create_rpc_client(DEVNET_RPC_URL);
+    let keypair = match SecretKeypair::from_env("DEVNET_PRIVATE_KEY") {
+        Ok(keypair) => keypair,
+        Err(_) => {
+            let keypair = SecretKeypair::generate();
+            println!("Funding new wallet {}", keypair.pubkey());
+            airdrop_sol(&client, &keypair.pubkey().to_string(), LAMPORTS_PER_SOL).expect("Airdrop failed, try again later");
+            keypair
+        }
+    };
+    // Sends and confirms a transaction, stopping the bot if it fails to land or lands but fails
+    let send = |action: &str, transaction: Transaction| {
+        let confirmed_transaction = send_and_confirm_transaction(&client, transaction)
+            .unwrap_or_else(|err| panic!("{} failed: {}", action, err));
+        let url = explorer_url_for_signature(confirmed_transaction.signature, &Cluster::Devnet, Explorer::Explorer);
+        match confirmed_transaction.err {
+            Some(err) => panic!("{} failed: {}\n{}", action, err, url),
+            None => println!("{}: {}", action, url),
+        }
+    };
+
+    // Test mint and pool
+    let fixture = setup_devnet_fixture(&client, &keypair, &config).expect("Unable to set up devnet fixture");
+    let mint_address = fixture.mint.to_string();
+    println!("Test mint {} trades in pool {}", mint_address, fixture.pool.pool_state);
+    let token_account_address = derive_associated_token_account_address(
+        &keypair.pubkey().to_string(),
+        &mint_address,
+        token_program()
+    ).unwrap();
+
+    // Buy
+    for _ in 0..BUYS {
+        let transaction = TransactionBuilder::new(&client, &keypair)
+            .set_compute_units(10_000)
+            .wrap_sol(BUY_LAMPORTS)
+            .unwrap()
+            .raydium_cpmm_swap(&fixture.pool, &sol_pubkey(), BUY_LAMPORTS, 1)
+            .unwrap()
+            .build()
+            .unwrap();
+        send("Buy", transaction);
+    }
+
+    // Sell everything that was not deposited into the pool, then unwrap the SOL received
+    let balance = get_token_balance(&client, &token_account_address).unwrap().balance;
+    let transaction = TransactionBuilder::new(&client, &keypair)
+        .set_compute_units(10_000)
+        .raydium_cpmm_swap(&fixture.pool, &fixture.mint, balance, 1)
+        .unwrap()
+        .unwrap_sol()
+        .unwrap()
+        .build()
+        .unwrap();
+    send("Sell", transaction);
+
+    // Cleanup, close the empty token account to reclaim its rent
+    let wallet_address = keypair.pubkey().to_string();
+    let transaction = TransactionBuilder::new(&client, &keypair)
+        .delete_associated_token_account(&mint_address, &wallet_address, token_program())
+        .unwrap()
+        .build()
+        .unwrap();
+    send("Cleanup", transaction);
+}
@@ -0,0 +1,531 @@
+//! Subcommands of the CLI, parsed from arguments and executed against a `Session`.
+
+use std::{
+    cell::{OnceCell, RefCell},
+    collections::HashMap,
+    error::Error
+};
+
+use easy_solana::{
+    constants::{pumpfun_accounts::PUMP_TOKEN_DECIMALS, solana_programs::token_program, units::BASIS_POINTS_DIVISOR},
+    explorer_url_for_signature,
+    get_bonding_curve_account,
+    get_sol_balance,
+    get_token_balance,
+    pumpfun::{
+        bonding_curve::calculate_token_price_in_sol,
+        global::{get_global_account, GlobalAccount},
+        instructions::{pump_buy_instruction, pump_sell_instruction}
+    },
+    quote_buy,
+    quote_sell,
+    read_transactions::{
+        account_cache::AccountCache,
+        associated_token_account::{derive_associated_token_account_address, get_all_token_accounts}
+    },
+    utils::{address_to_pubkey, sol_to_lamports},
+    write_transactions::{transaction_builder::TransactionBuilder, utils::send_and_confirm_transaction},
+    Cluster, Config, Explorer, SecretKeypair
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{hash::Hash, native_token::LAMPORTS_PER_SOL, signer::Signer};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+pub type CliResult<T> = Result<T, Box<dyn Error>>;
+
+/// Token accounts closed per `reclaim-rent` transaction.
+const CLOSES_PER_TRANSACTION: usize = 10;
+
+pub const USAGE: &str = "\
+Usage: easy-solana [--config <PATH>] [--profile <NAME>] [--rpc <URL|ENV_VAR>] [--keypair-env <ENV_VAR>] <COMMAND>
+
+Commands:
+  balance [ADDRESS] [--token MINT]         SOL or token balance, of the loaded wallet by default
+  portfolio [ADDRESS]                      SOL and token holdings valued in SOL
+  positions                                Non-empty token holdings of the loaded wallet
+  price MINT                               Price of a pump.fun token in SOL
+  quote buy MINT SOL_AMOUNT                Tokens received when buying a pump.fun token
+  quote sell MINT AMOUNT|all               SOL received when selling a pump.fun token
+  buy MINT SOL_AMOUNT [--slippage BPS]     Buy a pump.fun token
+  sell MINT AMOUNT|all [--slippage BPS]    Sell a pump.fun token
+  sweep DESTINATION                        Transfer all SOL to DESTINATION
+  reclaim-rent                             Close empty token accounts and reclaim their rent
+  profiles                                 Wallet profiles and their addresses
+  profiles new NAME                        Generate a keystore keypair for a new profile
+  repl                                     Interactive session keeping the client and blockhash warm
+  help                                     Show this message
+
+Options:
+  --config       TOML config of the RPC, fees, slippage and policy [default: easy-solana.toml if present]
+  --profiles     Wallet profiles file [default: profiles.toml if present]
+  --profile      Wallet profile to use instead of --keypair-env, with its RPC, fees and policy
+  --rpc          RPC URL or environment variable holding it, overrides the config [default: RPC_URL]
+  --keypair-env  Environment variable holding the base58 private key [default: PRIVATE_KEY]";
+
+/// Tokens to sell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SellAmount {
+    All,
+    Tokens(f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Balance { address: Option<String>, token: Option<String> },
+    Portfolio { address: Option<String> },
+    Positions,
+    Price { token: String },
+    QuoteBuy { token: String, sol_amount: f64 },
+    QuoteSell { token: String, amount: SellAmount },
+    Buy { token: String, sol_amount: f64, slippage_basis_points: Option<u64> },
+    Sell { token: String, amount: SellAmount, slippage_basis_points: Option<u64> },
+    Sweep { destination: String },
+    ReclaimRent,
+    Profiles,
+    NewProfile { name: String },
+    Repl,
+    Help,
+}
+
+impl Command {
+    /// Parses a subcommand and its arguments, e.g `["buy", "MINT", "0.1", "--slippage", "200"]`.
+    pub fn parse(args: &[String]) -> CliResult<Self> {
+        let (positional, flags) = split_flags(args)?;
+        let slippage_basis_points = match flags.get("slippage") {
+            Some(slippage) => Some(slippage.parse().map_err(|_| format!("Invalid slippage: {}", slippage))?),
+            None => None,
+        };
+
+        let command = match positional.as_slice() {
+            [] | ["help"] => Command::Help,
+            ["balance", rest @ ..] if rest.len() <= 1 => Command::Balance {
+                address: rest.first().map(|address| address.to_string()),
+                token: flags.get("token").map(|token| token.to_string()),
+            },
+            ["portfolio", rest @ ..] if rest.len() <= 1 => Command::Portfolio {
+                address: rest.first().map(|address| address.to_string()),
+            },
+            ["positions"] => Command::Positions,
+            ["price", token] => Command::Price { token: token.to_string() },
+            ["quote", "buy", token, sol_amount] => Command::QuoteBuy {
+                token: token.to_string(),
+                sol_amount: parse_amount(sol_amount)?,
+            },
+            ["quote", "sell", token, amount] => Command::QuoteSell {
+                token: token.to_string(),
+                amount: parse_sell_amount(amount)?,
+            },
+            ["buy", token, sol_amount] => Command::Buy {
+                token: token.to_string(),
+                sol_amount: parse_amount(sol_amount)?,
+                slippage_basis_points,
+            },
+            ["sell", token, amount] => Command::Sell {
+                token: token.to_string(),
+                amount: parse_sell_amount(amount)?,
+                slippage_basis_points,
+            },
+            ["sweep", destination] => Command::Sweep { destination: destination.to_string() },
+            ["reclaim-rent"] => Command::ReclaimRent,
+            ["profiles"] => Command::Profiles,
+            ["profiles", "new", name] => Command::NewProfile { name: name.to_string() },
+            ["repl"] => Command::Repl,
+            _ => return Err(format!("Invalid command: {}\n\n{}", args.join(" "), USAGE).into()),
+        };
+        Ok(command)
+    }
+}
+
+/// Client and wallet shared by every command.
+pub struct Session {
+    pub client: RpcClient,
+    pub keypair: Option<SecretKeypair>,
+    pub keypair_env: String,
+    /// Fees, default slippage and policy applied to every command.
+    pub config: Config,
+    /// Blockhash kept warm between commands, only set once `warm` is called.
+    cache: Option<RefCell<AccountCache>>,
+    global: OnceCell<GlobalAccount>,
+}
+
+impl Session {
+    pub fn new(client: RpcClient, keypair: Option<SecretKeypair>, keypair_env: String) -> Self {
+        Session { client, keypair, keypair_env, config: Config::default(), cache: None, global: OnceCell::new() }
+    }
+
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Opens the connection and caches the latest blockhash and the pump.fun global account, so
+    /// later commands skip those round trips.
+    pub fn warm(&mut self) -> CliResult<()> {
+        let owner = self.keypair.as_ref().map(|keypair| keypair.pubkey()).unwrap_or_default();
+        let mut cache = AccountCache::new(owner);
+        cache.prewarm(&self.client, Vec::new())?;
+        self.cache = Some(RefCell::new(cache));
+        // Clusters without pump.fun, e.g devnet, still get a warm blockhash
+        let _ = self.global_account();
+        Ok(())
+    }
+
+    /// Blockhash from the cache, refreshed once it is too old. `None` without a cache.
+    fn cached_blockhash(&self) -> CliResult<Option<Hash>> {
+        let Some(cache) = &self.cache else {
+            return Ok(None);
+        };
+        if let Some(blockhash) = cache.borrow().blockhash() {
+            return Ok(Some(blockhash));
+        }
+        // Prewarming no tokens only fetches a new blockhash
+        cache.borrow_mut().prewarm(&self.client, Vec::new())?;
+        Ok(cache.borrow().blockhash())
+    }
+
+    fn global_account(&self) -> CliResult<&GlobalAccount> {
+        if let Some(global) = self.global.get() {
+            return Ok(global);
+        }
+        let global = get_global_account(&self.client)?;
+        Ok(self.global.get_or_init(|| global))
+    }
+
+    fn keypair(&self) -> CliResult<&SecretKeypair> {
+        self.keypair
+            .as_ref()
+            .ok_or_else(|| format!("No keypair loaded, set {} to a base58 private key", self.keypair_env).into())
+    }
+
+    /// `address` if given, otherwise the address of the loaded wallet.
+    fn address_or_wallet(&self, address: &Option<String>) -> CliResult<String> {
+        match address {
+            Some(address) => Ok(address.clone()),
+            None => Ok(self.keypair()?.pubkey().to_string()),
+        }
+    }
+
+    fn explorer_url(&self, signature: &impl std::fmt::Display) -> String {
+        explorer_url_for_signature(signature, &Cluster::from_rpc_url(&self.client.url()), Explorer::Explorer)
+    }
+
+    /// Runs `command`, returning its output.
+    pub fn execute(&self, command: &Command) -> CliResult<String> {
+        match command {
+            Command::Balance { address, token } => self.balance(address, token),
+            Command::Portfolio { address } => self.portfolio(address),
+            Command::Positions => self.positions(),
+            Command::Price { token } => self.price(token),
+            Command::QuoteBuy { token, sol_amount } => self.quote_buy(token, *sol_amount),
+            Command::QuoteSell { token, amount } => self.quote_sell(token, *amount),
+            Command::Buy { token, sol_amount, slippage_basis_points } => self.buy(token, *sol_amount, slippage_basis_points.unwrap_or(self.config.trading.slippage_bps)),
+            Command::Sell { token, amount, slippage_basis_points } => self.sell(token, *amount, slippage_basis_points.unwrap_or(self.config.trading.slippage_bps)),
+            Command::Sweep { destination } => self.sweep(destination),
+            Command::ReclaimRent => self.reclaim_rent(),
+            Command::Repl => Err("Already in the REPL".into()),
+            Command::Profiles | Command::NewProfile { .. } => Err("Profiles can not be managed from a session".into()),
+            Command::Help => Ok(USAGE.to_string()),
+        }
+    }
+
+    fn balance(&self, address: &Option<String>, token: &Option<String>) -> CliResult<String> {
+        let address = self.address_or_wallet(address)?;
+        match token {
+            Some(token) => {
+                let token_account_address = derive_associated_token_account_address(&address, token, token_program())?;
+                let balance = get_token_balance(&self.client, &token_account_address)?;
+                Ok(format!("{} {}", balance.ui_amount, token))
+            }
+            None => Ok(format!("{} SOL", get_sol_balance(&self.client, &address)?)),
+        }
+    }
+
+    /// Mint, amount and value in SOL of each token account of `address`. Only tokens still on a
+    /// pump.fun bonding curve can be valued.
+    fn holdings(&self, address: &str) -> CliResult<Vec<(String, f64, Option<f64>)>> {
+        let holdings = get_all_token_accounts(&self.client, address)?
+            .into_iter()
+            .map(|token_account| {
+                let value = get_bonding_curve_account(&self.client, &token_account.mint_pubkey)
+                    .and_then(|(_, curve_state)| calculate_token_price_in_sol(&curve_state).ok())
+                    .map(|price| price * token_account.token_ui_amount);
+                (token_account.mint_pubkey, token_account.token_ui_amount, value)
+            })
+            .collect();
+        Ok(holdings)
+    }
+
+    fn portfolio(&self, address: &Option<String>) -> CliResult<String> {
+        let address = self.address_or_wallet(address)?;
+        let sol_balance = get_sol_balance(&self.client, &address)?;
+        let holdings = self.holdings(&address)?;
+        let total_sol = sol_balance + holdings.iter().filter_map(|(_, _, value)| *value).sum::<f64>();
+
+        let mut lines = vec![holding_line("Token", "Amount", "Value (SOL)")];
+        lines.push(holding_line("SOL", &sol_balance.to_string(), &format!("{:.6}", sol_balance)));
+        lines.extend(holdings.iter().map(|(mint, amount, value)| holding_line(mint, &amount.to_string(), &format_value(*value))));
+        lines.push(holding_line("Total", "", &format!("{:.6}", total_sol)));
+        Ok(lines.join("\n"))
+    }
+
+    fn positions(&self) -> CliResult<String> {
+        let address = self.keypair()?.pubkey().to_string();
+        let positions: Vec<String> = self.holdings(&address)?
+            .into_iter()
+            .filter(|(_, amount, _)| *amount > 0.0)
+            .map(|(mint, amount, value)| holding_line(&mint, &amount.to_string(), &format_value(value)))
+            .collect();
+        if positions.is_empty() {
+            return Ok("No open positions".to_string());
+        }
+        Ok(std::iter::once(holding_line("Token", "Amount", "Value (SOL)")).chain(positions).collect::<Vec<_>>().join("\n"))
+    }
+
+    fn price(&self, token: &str) -> CliResult<String> {
+        let (_, curve_state) = get_bonding_curve_account(&self.client, token)
+            .ok_or_else(|| format!("{} has no pump.fun bonding curve", token))?;
+        Ok(format!("{:.12} SOL", calculate_token_price_in_sol(&curve_state)?))
+    }
+
+    fn quote_buy(&self, token: &str, sol_amount: f64) -> CliResult<String> {
+        let (_, curve_state) = get_bonding_curve_account(&self.client, token)
+            .ok_or_else(|| format!("{} has no pump.fun bonding curve", token))?;
+        let quote = quote_buy(&curve_state, self.global_account()?, sol_to_lamports(sol_amount))?;
+        Ok(format!(
+            "{} {} for {} SOL, price impact {:.2}%",
+            ui_tokens(quote.token_amount), token, lamports_to_sol(quote.total_sol), quote.price_impact
+        ))
+    }
+
+    fn quote_sell(&self, token: &str, amount: SellAmount) -> CliResult<String> {
+        let token_amount = self.token_amount_to_sell(token, amount)?;
+        let (_, curve_state) = get_bonding_curve_account(&self.client, token)
+            .ok_or_else(|| format!("{} has no pump.fun bonding curve", token))?;
+        let quote = quote_sell(&curve_state, self.global_account()?, token_amount)?;
+        Ok(format!(
+            "{} SOL for {} {}, price impact {:.2}%",
+            lamports_to_sol(quote.total_sol), ui_tokens(token_amount), token, quote.price_impact
+        ))
+    }
+
+    /// Raw token amount of a sell, the balance of the loaded wallet for `SellAmount::All`.
+    fn token_amount_to_sell(&self, token: &str, amount: SellAmount) -> CliResult<u64> {
+        match amount {
+            SellAmount::Tokens(tokens) => Ok((tokens * 10_f64.powi(PUMP_TOKEN_DECIMALS as i32)).round() as u64),
+            SellAmount::All => {
+                let wallet_address = self.keypair()?.pubkey().to_string();
+                let token_account_address = derive_associated_token_account_address(&wallet_address, token, token_program())?;
+                Ok(get_token_balance(&self.client, &token_account_address)?.balance)
+            }
+        }
+    }
+
+    fn buy(&self, token: &str, sol_amount: f64, slippage_basis_points: u64) -> CliResult<String> {
+        let keypair = self.keypair()?;
+        let (wallet, mint) = (keypair.pubkey(), address_to_pubkey(token)?);
+        let (_, curve_state) = get_bonding_curve_account(&self.client, token)
+            .ok_or_else(|| format!("{} has no pump.fun bonding curve", token))?;
+        let quote = quote_buy(&curve_state, self.global_account()?, sol_to_lamports(sol_amount))?;
+        let max_sol_cost = quote.total_sol.saturating_mul(BASIS_POINTS_DIVISOR as u64 + slippage_basis_points) / BASIS_POINTS_DIVISOR as u64;
+
+        let mut builder = self.builder(keypair)?;
+        builder.instructions.push(create_associated_token_account_idempotent(&wallet, &wallet, &mint, &token_program()));
+        builder.instructions.push(pump_buy_instruction(&wallet, &mint, &curve_state.creator.unwrap_or_default(), quote.token_amount, max_sol_cost));
+        self.send(&builder, &format!("Bought {} {} for max {} SOL", ui_tokens(quote.token_amount), token, lamports_to_sol(max_sol_cost)))
+    }
+
+    fn sell(&self, token: &str, amount: SellAmount, slippage_basis_points: u64) -> CliResult<String> {
+        let keypair = self.keypair()?;
+        let (wallet, mint) = (keypair.pubkey(), address_to_pubkey(token)?);
+        let token_amount = self.token_amount_to_sell(token, amount)?;
+        let (_, curve_state) = get_bonding_curve_account(&self.client, token)
+            .ok_or_else(|| format!("{} has no pump.fun bonding curve", token))?;
+        let quote = quote_sell(&curve_state, self.global_account()?, token_amount)?;
+        let min_sol_output = quote.total_sol.saturating_mul((BASIS_POINTS_DIVISOR as u64).saturating_sub(slippage_basis_points)) / BASIS_POINTS_DIVISOR as u64;
+
+        let mut builder = self.builder(keypair)?;
+        builder.instructions.push(pump_sell_instruction(&wallet, &mint, &curve_state.creator.unwrap_or_default(), token_amount, min_sol_output));
+        self.send(&builder, &format!("Sold {} {} for min {} SOL", ui_tokens(token_amount), token, lamports_to_sol(min_sol_output)))
+    }
+
+    fn sweep(&self, destination: &str) -> CliResult<String> {
+        let keypair = self.keypair()?;
+        let mut builder = self.builder(keypair)?;
+        builder.transfer_all_sol(keypair, destination)?;
+        self.send(&builder, &format!("Swept SOL to {}", destination))
+    }
+
+    fn reclaim_rent(&self) -> CliResult<String> {
+        let keypair = self.keypair()?;
+        let wallet_address = keypair.pubkey().to_string();
+        // Only associated token accounts can be closed through the builder
+        let mut empty_mints = Vec::new();
+        for token_account in get_all_token_accounts(&self.client, &wallet_address)? {
+            let associated_token_account_address = derive_associated_token_account_address(&wallet_address, &token_account.mint_pubkey, token_program())?;
+            if token_account.token_amount == 0 && token_account.pubkey == associated_token_account_address {
+                empty_mints.push(token_account.mint_pubkey);
+            }
+        }
+        if empty_mints.is_empty() {
+            return Ok("No empty token accounts".to_string());
+        }
+
+        let mut lines = Vec::new();
+        for mints in empty_mints.chunks(CLOSES_PER_TRANSACTION) {
+            let mut builder = self.builder(keypair)?;
+            for mint in mints {
+                builder.delete_associated_token_account(mint, &wallet_address, token_program())?;
+            }
+            lines.push(self.send(&builder, &format!("Closed {} token accounts", mints.len()))?);
+        }
+        Ok(lines.join("\n"))
+    }
+
+    /// Builder with the fees and policy of the config.
+    fn builder<'a>(&'a self, keypair: &'a SecretKeypair) -> CliResult<TransactionBuilder<'a>> {
+        let mut builder = TransactionBuilder::new(&self.client, keypair);
+        builder.with_config(&self.config)?;
+        Ok(builder)
+    }
+
+    /// Builds, sends and confirms the transaction of `builder` with the cached blockhash if warm,
+    /// returning `summary` with a link to the transaction.
+    fn send(&self, builder: &TransactionBuilder<'_>, summary: &str) -> CliResult<String> {
+        let transaction = match self.cached_blockhash()? {
+            Some(blockhash) => builder.build_with_blockhash(blockhash)?,
+            None => builder.build()?,
+        };
+        let confirmed_transaction = send_and_confirm_transaction(&self.client, transaction)?
+            .into_result(|signature, err| format!("Transaction failed: {}\n{}", err, self.explorer_url(&signature)))?;
+        Ok(format!("{}\n{}", summary, self.explorer_url(&confirmed_transaction.signature)))
+    }
+}
+
+/// Splits `--name value` flags from positional arguments.
+fn split_flags(args: &[String]) -> CliResult<(Vec<&str>, HashMap<&str, &str>)> {
+    let mut positional = Vec::new();
+    let mut flags = HashMap::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.strip_prefix("--") {
+            Some(name) => {
+                let value = args.next().ok_or_else(|| format!("Missing value for --{}", name))?;
+                flags.insert(name, value.as_str());
+            }
+            None => positional.push(arg.as_str()),
+        }
+    }
+    Ok((positional, flags))
+}
+
+/// Row of the portfolio and positions tables.
+fn holding_line(token: &str, amount: &str, value: &str) -> String {
+    format!("{:<44}  {:>20}  {:>14}", token, amount, value)
+}
+
+fn format_value(value: Option<f64>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| format!("{:.6}", value))
+}
+
+fn parse_sell_amount(amount: &str) -> CliResult<SellAmount> {
+    match amount {
+        "all" => Ok(SellAmount::All),
+        amount => Ok(SellAmount::Tokens(parse_amount(amount)?)),
+    }
+}
+
+fn parse_amount(amount: &str) -> CliResult<f64> {
+    match amount.parse::<f64>() {
+        Ok(amount) if amount.is_finite() && amount > 0.0 => Ok(amount),
+        _ => Err(format!("Invalid amount: {}", amount).into()),
+    }
+}
+
+fn lamports_to_sol(lamports: u64) -> f64 {
+    lamports as f64 / LAMPORTS_PER_SOL as f64
+}
+
+fn ui_tokens(token_amount: u64) -> f64 {
+    token_amount as f64 / 10_f64.powi(PUMP_TOKEN_DECIMALS as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_system_interface::instruction::transfer;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert!(Command::parse(&args("")).unwrap() == Command::Help);
+        assert!(Command::parse(&args("balance")).unwrap() == Command::Balance { address: None, token: None });
+        assert!(
+            Command::parse(&args("balance WALLET --token MINT")).unwrap()
+                == Command::Balance { address: Some("WALLET".to_string()), token: Some("MINT".to_string()) }
+        );
+        assert!(
+            Command::parse(&args("buy MINT 0.5 --slippage 250")).unwrap()
+                == Command::Buy { token: "MINT".to_string(), sol_amount: 0.5, slippage_basis_points: Some(250) }
+        );
+        assert!(
+            Command::parse(&args("sell MINT all")).unwrap()
+                == Command::Sell { token: "MINT".to_string(), amount: SellAmount::All, slippage_basis_points: None }
+        );
+        assert!(Command::parse(&args("reclaim-rent")).unwrap() == Command::ReclaimRent);
+        assert!(Command::parse(&args("positions")).unwrap() == Command::Positions);
+        assert!(Command::parse(&args("profiles new sniper1")).unwrap() == Command::NewProfile { name: "sniper1".to_string() });
+        assert!(
+            Command::parse(&args("quote buy MINT 0.1")).unwrap()
+                == Command::QuoteBuy { token: "MINT".to_string(), sol_amount: 0.1 }
+        );
+        assert!(
+            Command::parse(&args("quote sell MINT 1000")).unwrap()
+                == Command::QuoteSell { token: "MINT".to_string(), amount: SellAmount::Tokens(1000.0) }
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_arguments() {
+        assert!(Command::parse(&args("buy MINT")).is_err());
+        assert!(Command::parse(&args("buy MINT -1")).is_err());
+        assert!(Command::parse(&args("sell MINT some")).is_err());
+        assert!(Command::parse(&args("buy MINT 0.1 --slippage")).is_err());
+        assert!(Command::parse(&args("quote MINT 0.1")).is_err());
+        assert!(Command::parse(&args("unknown")).is_err());
+    }
+
+    #[test]
+    fn test_execute_balance() {
+        let session = Session::new(RpcClient::new_mock("succeeds"), Some(SecretKeypair::generate()), "PRIVATE_KEY".to_string());
+        // The mock client holds 50 lamports for every account
+        assert!(session.execute(&Command::Balance { address: None, token: None }).unwrap() == "0.00000005 SOL");
+
+        let session = Session::new(RpcClient::new_mock("succeeds"), None, "PRIVATE_KEY".to_string());
+        let err = session.execute(&Command::Sweep { destination: "WALLET".to_string() }).unwrap_err();
+        assert!(err.to_string().contains("PRIVATE_KEY"));
+    }
+
+    #[test]
+    fn test_warm_caches_blockhash() {
+        let mut session = Session::new(RpcClient::new_mock("succeeds"), None, "PRIVATE_KEY".to_string());
+        assert!(session.cached_blockhash().unwrap().is_none());
+        session.warm().unwrap();
+        assert!(session.cached_blockhash().unwrap() == Some(session.client.get_latest_blockhash().unwrap()));
+    }
+
+    #[test]
+    fn failing_test_send_with_warm_blockhash_checks_policy() {
+        let mut session = Session::new(RpcClient::new_mock("succeeds"), Some(SecretKeypair::generate()), "PRIVATE_KEY".to_string());
+        session.config.policy.max_lamports_per_transaction = Some(0);
+        session.warm().unwrap();
+
+        let keypair = session.keypair().unwrap();
+        let mut builder = session.builder(keypair).unwrap();
+        builder.instructions.push(transfer(&keypair.pubkey(), &keypair.pubkey(), 1));
+        let err = session.send(&builder, "Sent").unwrap_err();
+        assert!(err.to_string().contains("more than the limit"));
+    }
+}
@@ -0,0 +1,229 @@
+//! `easy-solana` command line interface, built only on the public API of the crate.
+//!
+//! Build with `cargo install easy_solana --features cli`, then run `easy-solana help`.
+
+mod commands;
+mod repl;
+
+use std::{
+    env,
+    io::{stdin, stdout},
+    path::Path,
+    process::ExitCode
+};
+
+use commands::{CliResult, Command, Session, USAGE};
+use easy_solana::{
+    profiles::{Keystore, Profiles, KEYSTORE_DIR, PROFILES_FILE},
+    Config, SecretKeypair
+};
+use solana_sdk::signer::Signer;
+
+const DEFAULT_CONFIG: &str = "easy-solana.toml";
+const DEFAULT_KEYPAIR_ENV: &str = "PRIVATE_KEY";
+
+/// Options placed before the subcommand.
+#[derive(Debug, PartialEq)]
+struct GlobalOptions {
+    config: Option<String>,
+    profiles: Option<String>,
+    profile: Option<String>,
+    rpc: Option<String>,
+    keypair_env: String,
+}
+
+/// Splits the global options from the subcommand and its arguments.
+fn parse_global_options(args: &[String]) -> CliResult<(GlobalOptions, &[String])> {
+    let mut options = GlobalOptions {
+        config: None,
+        profiles: None,
+        profile: None,
+        rpc: None,
+        keypair_env: DEFAULT_KEYPAIR_ENV.to_string()
+    };
+    let mut rest = args;
+    loop {
+        match rest {
+            [flag, value, remaining @ ..] if flag == "--config" => {
+                options.config = Some(value.clone());
+                rest = remaining;
+            }
+            [flag, value, remaining @ ..] if flag == "--profiles" => {
+                options.profiles = Some(value.clone());
+                rest = remaining;
+            }
+            [flag, value, remaining @ ..] if flag == "--profile" => {
+                options.profile = Some(value.clone());
+                rest = remaining;
+            }
+            [flag, value, remaining @ ..] if flag == "--rpc" => {
+                options.rpc = Some(value.clone());
+                rest = remaining;
+            }
+            [flag, value, remaining @ ..] if flag == "--keypair-env" => {
+                options.keypair_env = value.clone();
+                rest = remaining;
+            }
+            [flag] if ["--config", "--profiles", "--profile", "--rpc", "--keypair-env"].contains(&flag.as_str()) => {
+                return Err(format!("Missing value for {}\n\n{}", flag, USAGE).into());
+            }
+            _ => return Ok((options, rest)),
+        }
+    }
+}
+
+/// Profiles of `--profiles`, otherwise `profiles.toml` if present.
+fn load_profiles(options: &GlobalOptions) -> CliResult<Option<Profiles>> {
+    match &options.profiles {
+        Some(path) => Ok(Some(Profiles::load(path)?)),
+        None if Path::new(PROFILES_FILE).exists() => Ok(Some(Profiles::load(PROFILES_FILE)?)),
+        None => Ok(None),
+    }
+}
+
+/// Config of `--config`, otherwise `easy-solana.toml` if present, otherwise the defaults with
+/// environment overrides. The sections set by `--profile` replace those of the config, and
+/// `--rpc` takes precedence over both.
+fn load_config(options: &GlobalOptions, profiles: Option<&Profiles>) -> CliResult<Config> {
+    let mut config = match &options.config {
+        Some(path) => Config::load(path)?,
+        None if Path::new(DEFAULT_CONFIG).exists() => Config::load(DEFAULT_CONFIG)?,
+        None => {
+            let mut config = Config::default();
+            config.apply_overrides(|key| env::var(key).ok())?;
+            config
+        }
+    };
+    if let Some(name) = &options.profile {
+        config = profiles.ok_or_else(|| format!("--profile needs {} or --profiles", PROFILES_FILE))?.config(name, &config)?;
+    }
+    if let Some(rpc) = &options.rpc {
+        config.rpc.url = rpc.clone();
+    }
+    Ok(config)
+}
+
+/// Lists the profiles with their public keys, or generates a keystore keypair for a new one.
+fn manage_profiles(command: &Command, profiles: Option<&Profiles>) -> CliResult<String> {
+    match command {
+        Command::NewProfile { name } => {
+            let keystore = profiles.map(|profiles| profiles.keystore.clone()).unwrap_or_else(|| Keystore::new(KEYSTORE_DIR));
+            let keypair = keystore.generate(name)?;
+            Ok(format!(
+                "Created {} with address {}, add [profiles.{}] to {} to use it",
+                keystore.path(name)?.display(), keypair.pubkey(), name, PROFILES_FILE
+            ))
+        }
+        _ => {
+            let profiles = profiles.ok_or_else(|| format!("No profiles, create {} or pass --profiles", PROFILES_FILE))?;
+            let lines: Vec<String> = profiles
+                .pubkeys()
+                .into_iter()
+                .map(|(name, pubkey)| format!("{}: {}", name, pubkey.unwrap_or_else(|| "keypair unavailable".to_string())))
+                .collect();
+            Ok(lines.join("\n"))
+        }
+    }
+}
+
+fn run(args: &[String]) -> CliResult<String> {
+    let (options, command_args) = parse_global_options(args)?;
+    let command = Command::parse(command_args)?;
+    if command == Command::Help {
+        return Ok(USAGE.to_string());
+    }
+
+    let profiles = load_profiles(&options)?;
+    if matches!(command, Command::Profiles | Command::NewProfile { .. }) {
+        return manage_profiles(&command, profiles.as_ref());
+    }
+
+    let config = load_config(&options, profiles.as_ref())?;
+    let (keypair, keypair_env) = match (&options.profile, &profiles) {
+        (Some(name), Some(profiles)) => (Some(profiles.keypair(name)?), format!("profile {}", name)),
+        _ => (SecretKeypair::from_env(&options.keypair_env).ok(), options.keypair_env),
+    };
+    let mut session = Session::new(config.rpc_client()?, keypair, keypair_env).with_config(config);
+    if command == Command::Repl {
+        session.warm()?;
+        repl::run_repl(&session, stdin().lock(), stdout())?;
+        return Ok(String::new());
+    }
+    session.execute(&command)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(output) => {
+            println!("{}", output);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_global_options() {
+        let args: Vec<String> = ["--rpc", "https://api.devnet.solana.com", "--keypair-env", "SNIPER", "--config", "bot.toml", "--profile", "sniper1", "price", "MINT"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let (options, rest) = parse_global_options(&args).unwrap();
+        assert!(
+            options == GlobalOptions {
+                config: Some("bot.toml".to_string()),
+                profiles: None,
+                profile: Some("sniper1".to_string()),
+                rpc: Some("https://api.devnet.solana.com".to_string()),
+                keypair_env: "SNIPER".to_string()
+            }
+        );
+        assert!(rest == &args[8..]);
+
+        assert!(parse_global_options(&args[..1]).is_err());
+        let (options, _) = parse_global_options(&args[8..]).unwrap();
+        assert!(options.rpc.is_none() && options.config.is_none());
+    }
+
+    #[test]
+    fn test_rpc_flag_overrides_config() {
+        let path = env::temp_dir().join(format!("easy-solana-cli-{}.toml", std::process::id()));
+        std::fs::write(&path, "[rpc]\nurl = \"https://api.devnet.solana.com\"\n\n[trading]\nslippage_bps = 300\n").unwrap();
+        let mut options = GlobalOptions {
+            config: Some(path.to_string_lossy().to_string()),
+            profiles: None,
+            profile: None,
+            rpc: None,
+            keypair_env: DEFAULT_KEYPAIR_ENV.to_string()
+        };
+        let config = load_config(&options, None).unwrap();
+        assert!(config.rpc.url == "https://api.devnet.solana.com");
+        assert!(config.trading.slippage_bps == 300);
+
+        options.rpc = Some("http://localhost:8899".to_string());
+        assert!(load_config(&options, None).unwrap().rpc.url == "http://localhost:8899");
+
+        // The profile replaces the trading section, the flag still wins for the RPC
+        let profiles = Profiles::from_toml_str("[profiles.sniper1]\nrpc = { url = \"PROFILE_RPC\" }\ntrading = { slippage_bps = 50 }", "").unwrap();
+        options.profile = Some("sniper1".to_string());
+        let config = load_config(&options, Some(&profiles)).unwrap();
+        assert!(config.rpc.url == "http://localhost:8899");
+        assert!(config.trading.slippage_bps == 50);
+        assert!(load_config(&options, None).is_err());
+        options.profile = Some("sniper2".to_string());
+        assert!(load_config(&options, Some(&profiles)).is_err());
+        std::fs::remove_file(path).unwrap();
+
+        options.profile = None;
+        assert!(load_config(&options, None).is_err());
+    }
+}
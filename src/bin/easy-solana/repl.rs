@@ -0,0 +1,54 @@
+//! Interactive shell running commands against one warmed `Session`, so successive trades skip
+//! loading the keypair, connecting and fetching a blockhash.
+
+use std::io::{BufRead, Write};
+
+use super::commands::{CliResult, Command, Session};
+
+const PROMPT: &str = "easy-solana> ";
+
+/// Reads commands line by line from `input` until `exit`, `quit` or the end of the input,
+/// writing their output or error to `output`. Errors of a command do not end the session.
+pub fn run_repl(session: &Session, input: impl BufRead, mut output: impl Write) -> CliResult<()> {
+    write!(output, "{}", PROMPT)?;
+    output.flush()?;
+    for line in input.lines() {
+        let args: Vec<String> = line?.split_whitespace().map(String::from).collect();
+        match args.first().map(String::as_str) {
+            Some("exit") | Some("quit") => break,
+            None => {}
+            Some(_) => {
+                let result = Command::parse(&args).and_then(|command| session.execute(&command));
+                match result {
+                    Ok(result) => writeln!(output, "{}", result)?,
+                    Err(err) => writeln!(output, "Error: {}", err)?,
+                }
+            }
+        }
+        write!(output, "{}", PROMPT)?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use easy_solana::SecretKeypair;
+    use solana_client::rpc_client::RpcClient;
+
+    #[test]
+    fn test_repl_runs_commands_until_exit() {
+        let session = Session::new(RpcClient::new_mock("succeeds"), Some(SecretKeypair::generate()), "PRIVATE_KEY".to_string());
+        let input = "balance\n\nbuy MINT\nrepl\nexit\nbalance\n";
+        let mut output = Vec::new();
+        run_repl(&session, input.as_bytes(), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.matches("0.00000005 SOL").count() == 1);
+        assert!(output.contains("Error: Invalid command: buy MINT"));
+        assert!(output.contains("Error: Already in the REPL"));
+        assert!(output.matches(PROMPT).count() == 5);
+    }
+}
@@ -0,0 +1,291 @@
+//! # Request Coalescing
+//!
+//! Single-flight layer for RPC reads. When several strategy tasks ask for the same account or
+//! bonding curve within a few milliseconds, only the first request goes out and every caller
+//! receives its result.
+
+use std::{
+    collections::HashMap,
+    io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard
+    }
+};
+use async_trait::async_trait;
+use log::debug;
+use serde_json::Value;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_client::{RpcClient, RpcClientConfig},
+    rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+    rpc_sender::{RpcSender, RpcTransportStats}
+};
+use solana_rpc_client::http_sender::HttpSender;
+use solana_sdk::commitment_config::CommitmentConfig;
+use tokio::sync::watch;
+use crate::{error::RateLimitError, utils::resolve_rpc_url};
+
+type Flight = watch::Receiver<Option<Result<Value, Arc<ClientError>>>>;
+
+/// Whether identical concurrent `request`s can share one response. Requests with side effects
+/// and custom methods, which may have some, always go out.
+pub fn is_coalescable(request: RpcRequest) -> bool {
+    !matches!(
+        request,
+        RpcRequest::SendTransaction | RpcRequest::RequestAirdrop | RpcRequest::Custom { .. }
+    )
+}
+
+/// `RpcSender` wrapper sending identical concurrent reads, same method and parameters, only once.
+///
+/// Callers joining a request in flight receive a copy of its result. Errors that can not be
+/// copied are shared as `ClientErrorKind::Custom` with the original message.
+pub struct CoalescingSender<S: RpcSender> {
+    inner: S,
+    in_flight: Mutex<HashMap<String, Flight>>,
+    coalesced: AtomicU64,
+}
+
+impl<S: RpcSender> CoalescingSender<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner, in_flight: Mutex::new(HashMap::new()), coalesced: AtomicU64::new(0) }
+    }
+
+    /// Number of requests that were answered by a request already in flight.
+    pub fn coalesced_requests(&self) -> u64 {
+        self.coalesced.load(Ordering::Relaxed)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Flight>> {
+        self.in_flight.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Ends a flight when its request completes or is cancelled, so later requests go out again.
+struct FlightGuard<'a, S: RpcSender> {
+    sender: &'a CoalescingSender<S>,
+    key: String,
+}
+
+impl<S: RpcSender> Drop for FlightGuard<'_, S> {
+    fn drop(&mut self) {
+        self.sender.lock().remove(&self.key);
+    }
+}
+
+#[async_trait]
+impl<S: RpcSender + Send + Sync> RpcSender for CoalescingSender<S> {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        if !is_coalescable(request) {
+            return self.inner.send(request, params).await;
+        }
+        let key = format!("{}:{}", request, params);
+        let joined = {
+            let mut in_flight = self.lock();
+            match in_flight.get(&key) {
+                Some(flight) => Err(flight.clone()),
+                None => {
+                    let (result, flight) = watch::channel(None);
+                    in_flight.insert(key.clone(), flight);
+                    Ok(result)
+                }
+            }
+        };
+
+        match joined {
+            Ok(shared_result) => {
+                let guard = FlightGuard { sender: self, key };
+                let result = self.inner.send(request, params).await;
+                drop(guard);
+                let shared = match &result {
+                    Ok(value) => Ok(value.clone()),
+                    Err(err) => Err(Arc::new(share_error(err))),
+                };
+                // Nobody joined if every receiver is gone
+                let _ = shared_result.send(Some(shared));
+                result
+            }
+            Err(mut flight) => {
+                self.coalesced.fetch_add(1, Ordering::Relaxed);
+                debug!("Joined {} in flight", request);
+                let shared = match flight.wait_for(Option::is_some).await {
+                    Ok(shared) => shared.clone(),
+                    // The first caller was cancelled before a response arrived
+                    Err(_) => None,
+                };
+                match shared {
+                    Some(Ok(value)) => Ok(value),
+                    Some(Err(err)) => Err(share_error(&err)),
+                    None => self.inner.send(request, params).await,
+                }
+            }
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+/// Copy of `err` for another caller of the same request.
+fn share_error(err: &ClientError) -> ClientError {
+    let kind = match err.kind() {
+        ClientErrorKind::Io(err) => ClientErrorKind::Io(io::Error::new(err.kind(), err.to_string())),
+        ClientErrorKind::TransactionError(err) => ClientErrorKind::TransactionError(err.clone()),
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, message, data }) => {
+            let data = match data {
+                RpcResponseErrorData::Empty => RpcResponseErrorData::Empty,
+                RpcResponseErrorData::SendTransactionPreflightFailure(result) => {
+                    RpcResponseErrorData::SendTransactionPreflightFailure(result.clone())
+                }
+                RpcResponseErrorData::NodeUnhealthy { num_slots_behind } => {
+                    RpcResponseErrorData::NodeUnhealthy { num_slots_behind: *num_slots_behind }
+                }
+            };
+            ClientErrorKind::RpcError(RpcError::RpcResponseError { code: *code, message: message.clone(), data })
+        }
+        ClientErrorKind::RpcError(RpcError::RpcRequestError(message)) => {
+            ClientErrorKind::RpcError(RpcError::RpcRequestError(message.clone()))
+        }
+        ClientErrorKind::RpcError(RpcError::ParseError(message)) => ClientErrorKind::RpcError(RpcError::ParseError(message.clone())),
+        ClientErrorKind::RpcError(RpcError::ForUser(message)) => ClientErrorKind::RpcError(RpcError::ForUser(message.clone())),
+        ClientErrorKind::Middleware(middleware_err) => match middleware_err.downcast_ref::<RateLimitError>() {
+            Some(limit) => ClientErrorKind::Middleware(limit.clone().into()),
+            None => ClientErrorKind::Custom(err.kind().to_string()),
+        },
+        ClientErrorKind::Custom(message) => ClientErrorKind::Custom(message.clone()),
+        _ => ClientErrorKind::Custom(err.kind().to_string()),
+    };
+    ClientError { request: err.request, kind }
+}
+
+/// Creates an RPC client like `create_rpc_client` whose identical concurrent reads are sent once.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::coalescing::create_coalescing_rpc_client;
+/// use solana_sdk::pubkey::Pubkey;
+///
+/// let client = create_coalescing_rpc_client("RPC_URL");
+/// let address: Pubkey = "CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump".parse().unwrap();
+/// // Both strategies receive the balance of a single `getBalance` request
+/// std::thread::scope(|scope| {
+///     scope.spawn(|| client.get_balance(&address));
+///     scope.spawn(|| client.get_balance(&address));
+/// });
+/// ```
+pub fn create_coalescing_rpc_client(rpc_input: &str) -> RpcClient {
+    RpcClient::new_sender(
+        CoalescingSender::new(HttpSender::new(resolve_rpc_url(rpc_input))),
+        RpcClientConfig::with_commitment(CommitmentConfig::confirmed()),
+    )
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    /// Answers after a delay with the number of requests it received, failing for `getSlot`.
+    struct SlowSender {
+        requests: AtomicU64,
+    }
+
+    #[async_trait]
+    impl RpcSender for SlowSender {
+        async fn send(&self, request: RpcRequest, _params: Value) -> ClientResult<Value> {
+            let count = self.requests.fetch_add(1, Ordering::SeqCst) + 1;
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            match request {
+                RpcRequest::GetSlot => Err(RpcError::RpcResponseError {
+                    code: -32005,
+                    message: "Node is behind".to_string(),
+                    data: RpcResponseErrorData::NodeUnhealthy { num_slots_behind: Some(40) },
+                }.into()),
+                _ => Ok(json!(count)),
+            }
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "slow".to_string()
+        }
+    }
+
+    fn sender() -> CoalescingSender<SlowSender> {
+        CoalescingSender::new(SlowSender { requests: AtomicU64::new(0) })
+    }
+
+    fn requests(sender: &CoalescingSender<SlowSender>) -> u64 {
+        sender.inner.requests.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn test_identical_reads_are_sent_once() {
+        let sender = sender();
+        let params = json!(["CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump"]);
+        let (first, second, third) = tokio::join!(
+            sender.send(RpcRequest::GetBalance, params.clone()),
+            sender.send(RpcRequest::GetBalance, params.clone()),
+            sender.send(RpcRequest::GetBalance, params.clone())
+        );
+        assert!(first.unwrap() == json!(1) && second.unwrap() == json!(1) && third.unwrap() == json!(1));
+        assert!(requests(&sender) == 1 && sender.coalesced_requests() == 2);
+
+        // Completed requests are not cached
+        assert!(sender.send(RpcRequest::GetBalance, params).await.unwrap() == json!(2));
+    }
+
+    #[tokio::test]
+    async fn test_different_or_writing_requests_are_not_coalesced() {
+        let sender = sender();
+        let (balance, other_balance, first_send, second_send) = tokio::join!(
+            sender.send(RpcRequest::GetBalance, json!(["first"])),
+            sender.send(RpcRequest::GetBalance, json!(["second"])),
+            sender.send(RpcRequest::SendTransaction, json!(["transaction"])),
+            sender.send(RpcRequest::SendTransaction, json!(["transaction"]))
+        );
+        assert!(balance.is_ok() && other_balance.is_ok() && first_send.is_ok() && second_send.is_ok());
+        assert!(requests(&sender) == 4 && sender.coalesced_requests() == 0);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_shared() {
+        let sender = sender();
+        let (first, second) = tokio::join!(
+            sender.send(RpcRequest::GetSlot, Value::Null),
+            sender.send(RpcRequest::GetSlot, Value::Null)
+        );
+        assert!(requests(&sender) == 1);
+        for err in [first.unwrap_err(), second.unwrap_err()] {
+            assert!(matches!(
+                err.kind(),
+                ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                    code: -32005,
+                    data: RpcResponseErrorData::NodeUnhealthy { num_slots_behind: Some(40) },
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_waiters_retry_when_first_caller_is_cancelled() {
+        let sender = sender();
+        let cancelled = tokio::time::timeout(Duration::from_millis(10), sender.send(RpcRequest::GetBalance, Value::Null));
+        let (cancelled, joined) = tokio::join!(cancelled, sender.send(RpcRequest::GetBalance, Value::Null));
+        assert!(cancelled.is_err());
+        assert!(joined.unwrap() == json!(2) && sender.coalesced_requests() == 1);
+    }
+}
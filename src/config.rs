@@ -0,0 +1,418 @@
+//! # Config
+//!
+//! Loads RPC endpoints, commitment, priority fee presets, slippage defaults and policy limits
+//! from a TOML file, so deployments are configured in one place instead of scattered
+//! environment variables. Environment variables prefixed with `EASY_SOLANA_` override the file.
+//!
+//! ```toml
+//! [rpc]
+//! url = "RPC_URL"                  # URL or environment variable holding it
+//! fallback_urls = ["https://api.mainnet-beta.solana.com"]
+//! commitment = "confirmed"
+//!
+//! [fees]
+//! priority = "medium"              # none (default), low, medium, high or custom
+//! compute_unit_price = 250_000     # micro-lamports per compute unit, used with "custom"
+//! compute_unit_limit = 200_000
+//!
+//! [trading]
+//! slippage_bps = 100
+//!
+//! [policy]
+//! max_lamports_per_transaction = 1_000_000_000
+//! allowed_destinations = ["ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5"]
+//! max_token_sell_bps = 5_000
+//! ```
+
+use std::{env, fs, path::Path, str::FromStr};
+
+use dotenv::dotenv;
+use serde::Deserialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
+
+use crate::{
+    error::{ConfigError, TransactionBuilderError},
+    utils::{address_to_pubkey, create_rpc_client},
+    write_transactions::{policy::TransactionPolicy, transaction_builder::TransactionBuilder}
+};
+
+/// Prefix of environment variables overriding the config file.
+pub const ENV_PREFIX: &str = "EASY_SOLANA_";
+
+/// RPC endpoints of the client.
+///
+/// ### Fields
+///
+/// - `url`: RPC URL, or the name of an environment variable holding it like `create_rpc_client`.
+/// - `fallback_urls`: Endpoints to use when `url` is unavailable, in order.
+/// - `commitment`: `processed`, `confirmed` or `finalized`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcConfig {
+    pub url: String,
+    pub fallback_urls: Vec<String>,
+    pub commitment: String,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            url: "RPC_URL".to_string(),
+            fallback_urls: Vec::new(),
+            commitment: "confirmed".to_string(),
+        }
+    }
+}
+
+/// Priority fee presets, in micro-lamports per compute unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriorityFeePreset {
+    #[default]
+    None,
+    Low,
+    Medium,
+    High,
+    /// Uses `compute_unit_price` of the fee config.
+    Custom,
+}
+
+impl PriorityFeePreset {
+    /// Compute unit price of the preset, `None` for `Custom`.
+    pub fn compute_unit_price(&self) -> Option<u64> {
+        match self {
+            PriorityFeePreset::None => Some(0),
+            PriorityFeePreset::Low => Some(10_000),
+            PriorityFeePreset::Medium => Some(100_000),
+            PriorityFeePreset::High => Some(1_000_000),
+            PriorityFeePreset::Custom => None,
+        }
+    }
+}
+
+impl FromStr for PriorityFeePreset {
+    type Err = ConfigError;
+
+    fn from_str(preset: &str) -> Result<Self, Self::Err> {
+        match preset {
+            "none" => Ok(PriorityFeePreset::None),
+            "low" => Ok(PriorityFeePreset::Low),
+            "medium" => Ok(PriorityFeePreset::Medium),
+            "high" => Ok(PriorityFeePreset::High),
+            "custom" => Ok(PriorityFeePreset::Custom),
+            _ => Err(ConfigError::InvalidValue("fees.priority".to_string(), preset.to_string())),
+        }
+    }
+}
+
+/// Priority fee and compute limit added to transactions.
+///
+/// ### Fields
+///
+/// - `priority`: Preset of the compute unit price.
+/// - `compute_unit_price`: Compute unit price in micro-lamports for the `custom` preset.
+/// - `compute_unit_limit`: Compute unit limit, the runtime default is used if `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeesConfig {
+    pub priority: PriorityFeePreset,
+    pub compute_unit_price: Option<u64>,
+    pub compute_unit_limit: Option<u32>,
+}
+
+impl FeesConfig {
+    /// Compute unit price of the preset, or the custom price. `None` if no priority fee is added.
+    pub fn compute_unit_price(&self) -> Option<u64> {
+        match self.priority.compute_unit_price() {
+            Some(0) => None,
+            Some(price) => Some(price),
+            None => self.compute_unit_price,
+        }
+    }
+}
+
+/// Defaults of trading strategies.
+///
+/// ### Fields
+///
+/// - `slippage_bps`: Slippage tolerance of trades in basis points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TradingConfig {
+    pub slippage_bps: u64,
+}
+
+impl Default for TradingConfig {
+    fn default() -> Self {
+        Self { slippage_bps: 100 }
+    }
+}
+
+/// Limits of outbound transactions, see `TransactionPolicy`. Addresses are base58 strings.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PolicyConfig {
+    pub max_lamports_per_transaction: Option<u64>,
+    pub allowed_programs: Option<Vec<String>>,
+    pub allowed_destinations: Option<Vec<String>>,
+    pub max_token_sell_bps: Option<u16>,
+}
+
+/// Configuration of the client, fees, trading and policy, see the module documentation for
+/// the file format. Missing sections and fields use their defaults.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub rpc: RpcConfig,
+    pub fees: FeesConfig,
+    pub trading: TradingConfig,
+    pub policy: PolicyConfig,
+}
+
+impl Config {
+    /// Parses a TOML config without applying environment overrides.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ConfigError::ParseError` for invalid TOML or unknown fields.
+    pub fn from_toml_str(toml: &str) -> Result<Self, ConfigError> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    /// Reads the config file at `path`, loads a `.env` file if present and applies the
+    /// `EASY_SOLANA_` environment overrides.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ConfigError::IoError` if the file can not be read, `ConfigError::ParseError`
+    /// if it is invalid and `ConfigError::InvalidValue` for invalid overrides.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use easy_solana::config::Config;
+    ///
+    /// let config = Config::load("easy-solana.toml").unwrap();
+    /// let client = config.rpc_client().unwrap();
+    /// let policy = config.transaction_policy().unwrap();
+    /// ```
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        dotenv().ok();
+        let mut config = Self::from_toml_str(&fs::read_to_string(path)?)?;
+        config.apply_overrides(|key| env::var(key).ok())?;
+        Ok(config)
+    }
+
+    /// Overrides fields with the values returned by `lookup` for these keys:
+    /// `EASY_SOLANA_RPC_URL`, `EASY_SOLANA_COMMITMENT`, `EASY_SOLANA_PRIORITY_FEE`,
+    /// `EASY_SOLANA_COMPUTE_UNIT_PRICE`, `EASY_SOLANA_COMPUTE_UNIT_LIMIT` and
+    /// `EASY_SOLANA_SLIPPAGE_BPS`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ConfigError::InvalidValue` if a value can not be parsed.
+    pub fn apply_overrides(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        let lookup = |name: &str| lookup(&format!("{}{}", ENV_PREFIX, name)).map(|value| (name.to_string(), value));
+
+        if let Some((_, url)) = lookup("RPC_URL") {
+            self.rpc.url = url;
+        }
+        if let Some((_, commitment)) = lookup("COMMITMENT") {
+            self.rpc.commitment = commitment;
+        }
+        if let Some((_, preset)) = lookup("PRIORITY_FEE") {
+            self.fees.priority = preset.parse()?;
+        }
+        if let Some((name, price)) = lookup("COMPUTE_UNIT_PRICE") {
+            self.fees.compute_unit_price = Some(parse_override(&name, &price)?);
+        }
+        if let Some((name, limit)) = lookup("COMPUTE_UNIT_LIMIT") {
+            self.fees.compute_unit_limit = Some(parse_override(&name, &limit)?);
+        }
+        if let Some((name, slippage)) = lookup("SLIPPAGE_BPS") {
+            self.trading.slippage_bps = parse_override(&name, &slippage)?;
+        }
+        Ok(())
+    }
+
+    /// Commitment of `rpc.commitment`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ConfigError::InvalidValue` for an unknown commitment level.
+    pub fn commitment(&self) -> Result<CommitmentConfig, ConfigError> {
+        CommitmentLevel::from_str(&self.rpc.commitment)
+            .map(|commitment| CommitmentConfig { commitment })
+            .map_err(|_| ConfigError::InvalidValue("rpc.commitment".to_string(), self.rpc.commitment.clone()))
+    }
+
+    /// Creates a client for `rpc.url` with the configured commitment.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ConfigError::InvalidValue` for an unknown commitment level.
+    pub fn rpc_client(&self) -> Result<RpcClient, ConfigError> {
+        let commitment = self.commitment()?;
+        let client = create_rpc_client(&self.rpc.url);
+        Ok(RpcClient::new_with_commitment(client.url(), commitment))
+    }
+
+    /// Clients of `rpc.url` followed by each fallback URL, e.g for `send_aggressive`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ConfigError::InvalidValue` for an unknown commitment level.
+    pub fn rpc_clients(&self) -> Result<Vec<RpcClient>, ConfigError> {
+        let commitment = self.commitment()?;
+        let mut clients = vec![self.rpc_client()?];
+        clients.extend(self.rpc.fallback_urls.iter().map(|url| RpcClient::new_with_commitment(create_rpc_client(url).url(), commitment)));
+        Ok(clients)
+    }
+
+    /// `TransactionPolicy` of the policy section.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ConfigError::InvalidAddress` if an allowed program or destination is invalid.
+    pub fn transaction_policy(&self) -> Result<TransactionPolicy, ConfigError> {
+        let parse_addresses = |addresses: &Option<Vec<String>>| -> Result<_, ConfigError> {
+            addresses
+                .as_ref()
+                .map(|addresses| addresses.iter().map(|address| address_to_pubkey(address)).collect::<Result<Vec<_>, _>>())
+                .transpose()
+                .map_err(ConfigError::from)
+        };
+        Ok(TransactionPolicy {
+            max_lamports_per_transaction: self.policy.max_lamports_per_transaction,
+            allowed_programs: parse_addresses(&self.policy.allowed_programs)?,
+            allowed_destinations: parse_addresses(&self.policy.allowed_destinations)?,
+            max_token_sell_bps: self.policy.max_token_sell_bps,
+        })
+    }
+}
+
+impl TransactionBuilder<'_> {
+    /// Applies the priority fee, compute limit and policy of `config`. Call it before adding
+    /// instructions so the compute budget instructions come first.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::InvalidAddress` if the policy contains an invalid address.
+    pub fn with_config(&mut self, config: &Config) -> Result<&mut Self, TransactionBuilderError> {
+        let policy = config.transaction_policy().map_err(|err| match err {
+            ConfigError::InvalidAddress(err) => TransactionBuilderError::InvalidAddress(err),
+            err => TransactionBuilderError::InstructionError(err.to_string()),
+        })?;
+        if let Some(compute_unit_limit) = config.fees.compute_unit_limit {
+            self.set_compute_limit(compute_unit_limit);
+        }
+        if let Some(compute_unit_price) = config.fees.compute_unit_price() {
+            self.set_compute_units(compute_unit_price);
+        }
+        Ok(self.with_policy(policy))
+    }
+}
+
+fn parse_override<T: FromStr>(name: &str, value: &str) -> Result<T, ConfigError> {
+    value
+        .parse()
+        .map_err(|_| ConfigError::InvalidValue(format!("{}{}", ENV_PREFIX, name), value.to_string()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use solana_sdk::signature::Keypair;
+    use crate::test_utils::mock_client;
+
+    const CONFIG: &str = r#"
+        [rpc]
+        url = "https://api.devnet.solana.com"
+        fallback_urls = ["https://devnet.helius-rpc.com"]
+        commitment = "finalized"
+
+        [fees]
+        priority = "custom"
+        compute_unit_price = 250_000
+        compute_unit_limit = 150_000
+
+        [trading]
+        slippage_bps = 300
+
+        [policy]
+        max_lamports_per_transaction = 1_000_000_000
+        allowed_destinations = ["ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5"]
+    "#;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::from_toml_str(CONFIG).unwrap();
+        assert!(config.rpc.fallback_urls.len() == 1);
+        assert!(config.commitment().unwrap() == CommitmentConfig::finalized());
+        assert!(config.fees.compute_unit_price() == Some(250_000));
+        assert!(config.trading.slippage_bps == 300);
+
+        let policy = config.transaction_policy().unwrap();
+        assert!(policy.max_lamports_per_transaction == Some(1_000_000_000));
+        assert!(policy.allowed_destinations.unwrap()[0].to_string() == "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5");
+        assert!(policy.allowed_programs.is_none());
+
+        let clients = config.rpc_clients().unwrap();
+        assert!(clients.len() == 2);
+        assert!(clients[0].url() == "https://api.devnet.solana.com");
+        assert!(clients[0].commitment() == CommitmentConfig::finalized());
+    }
+
+    #[test]
+    fn test_defaults_and_invalid_configs() {
+        let config = Config::from_toml_str("").unwrap();
+        assert!(config == Config::default());
+        assert!(config.fees.compute_unit_price().is_none());
+        assert!(config.trading.slippage_bps == 100);
+
+        assert!(matches!(Config::from_toml_str("[rpc]\nurls = []"), Err(ConfigError::ParseError(_))));
+        let config = Config::from_toml_str("[rpc]\ncommitment = \"fast\"").unwrap();
+        assert!(matches!(config.commitment(), Err(ConfigError::InvalidValue(_, _))));
+        let config = Config::from_toml_str("[policy]\nallowed_programs = [\"invalid\"]").unwrap();
+        assert!(matches!(config.transaction_policy(), Err(ConfigError::InvalidAddress(_))));
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let mut config = Config::from_toml_str(CONFIG).unwrap();
+        let env: HashMap<&str, &str> = HashMap::from([
+            ("EASY_SOLANA_RPC_URL", "http://localhost:8899"),
+            ("EASY_SOLANA_PRIORITY_FEE", "high"),
+            ("EASY_SOLANA_SLIPPAGE_BPS", "50"),
+        ]);
+        config.apply_overrides(|key| env.get(key).map(|value| value.to_string())).unwrap();
+        assert!(config.rpc.url == "http://localhost:8899");
+        assert!(config.fees.compute_unit_price() == Some(1_000_000));
+        assert!(config.trading.slippage_bps == 50);
+        // Fields without overrides keep the file values
+        assert!(config.fees.compute_unit_limit == Some(150_000));
+
+        let invalid = config.apply_overrides(|key| (key == "EASY_SOLANA_SLIPPAGE_BPS").then(|| "lots".to_string()));
+        assert!(matches!(invalid, Err(ConfigError::InvalidValue(name, _)) if name == "EASY_SOLANA_SLIPPAGE_BPS"));
+    }
+
+    #[test]
+    fn test_builder_with_config() {
+        let client = mock_client(Vec::new());
+        let keypair = Keypair::new();
+        let config = Config::from_toml_str(CONFIG).unwrap();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder.with_config(&config).unwrap();
+        assert!(builder.instructions.len() == 2);
+        assert!(builder.policy.as_ref().unwrap().max_lamports_per_transaction == Some(1_000_000_000));
+
+        let config = Config::default();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder.with_config(&config).unwrap();
+        assert!(builder.instructions.is_empty());
+        assert!(builder.policy.as_ref().unwrap().max_lamports_per_transaction.is_none());
+    }
+}
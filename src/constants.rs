@@ -22,11 +22,24 @@ pub mod solana_programs {
     pub fn rent_program() -> Pubkey {
         Pubkey::from_str("SysvarRent111111111111111111111111111111111").unwrap()
     }
+    pub fn compute_budget_program() -> Pubkey {
+        Pubkey::from_str("ComputeBudget111111111111111111111111111111").unwrap()
+    }
+    // Address without a private key, tokens sent to it can never be moved
+    pub fn incinerator() -> Pubkey {
+        Pubkey::from_str("1nc1nerator11111111111111111111111111111111").unwrap()
+    }
     pub fn sol_pubkey() -> Pubkey {
         Pubkey::from_str("So11111111111111111111111111111111111111112").unwrap()
     }
 }
 
+// Units shared by fee, slippage and margin calculations
+pub mod units {
+    // Basis points in a whole, an amount of 100 basis points is 1%
+    pub const BASIS_POINTS_DIVISOR: u128 = 10_000;
+}
+
 pub mod raydium_accounts {
     use solana_sdk::pubkey::Pubkey;
     use std::str::FromStr;
@@ -34,6 +47,23 @@ pub mod raydium_accounts {
     pub fn raydium_liquidity_pool_v4() -> Pubkey {
         Pubkey::from_str("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8").unwrap()
     }
+    pub fn raydium_cpmm_program() -> Pubkey {
+        Pubkey::from_str("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C").unwrap()
+    }
+    pub fn raydium_cpmm_create_pool_fee_account() -> Pubkey {
+        Pubkey::from_str("DNXgeM9EiiaAbaWvwjHj9fQQLAX5ZsfHyvmYUNRAdNC8").unwrap()
+    }
+    // Raydium Burn & Earn LP locker
+    pub fn raydium_lp_locker_program() -> Pubkey {
+        Pubkey::from_str("LockrWmn6K5twhz3y9w1dQERbmgSaRkfnTeTKbpofwE").unwrap()
+    }
+    pub fn streamflow_program() -> Pubkey {
+        Pubkey::from_str("strmRqUCoQUgGUan5YhzUZa6KqdzwX5L6FpUxfmKg5m").unwrap()
+    }
+    // First 8 bytes of sha256("global:initialize")
+    pub const CPMM_INITIALIZE_DISCRIMINATOR: [u8; 8] = [0xaf, 0xaf, 0x6d, 0x1f, 0x0d, 0x98, 0x9b, 0xed];
+    // First 8 bytes of sha256("global:swap_base_input")
+    pub const CPMM_SWAP_BASE_INPUT_DISCRIMINATOR: [u8; 8] = [0x8f, 0xbe, 0x5a, 0xda, 0xc4, 0x1e, 0x33, 0xde];
 }
 
 // Pumpfun program accounts
@@ -66,5 +96,169 @@ pub mod pumpfun_accounts {
             0x33, 0xe6, 0x85, 0xa4, 0x01, 0x7f, 0x83, 0xad,
         ]
     }
+    // First 8 bytes of sha256("global:create")
+    pub const CREATE_INSTRUCTION_DISCRIMINATOR: [u8; 8] = [0x18, 0x1e, 0xc8, 0x28, 0x05, 0x1c, 0x07, 0x77];
     pub const PUMP_TOKEN_DECIMALS: u32 = 6;
+    // Every pump.fun token is created with a supply of 1 billion tokens
+    pub const PUMP_TOKEN_TOTAL_SUPPLY: u64 = 1_000_000_000_000_000;
+    // Anchor account discriminator, first 8 bytes of sha256("account:BondingCurve")
+    pub const BONDING_CURVE_DISCRIMINATOR: [u8; 8] = [0x17, 0xb7, 0xf8, 0x37, 0x60, 0xd8, 0xac, 0x60];
+    // First 8 bytes of sha256("account:Global")
+    pub const GLOBAL_ACCOUNT_DISCRIMINATOR: [u8; 8] = [0xa7, 0xe8, 0xe8, 0xb1, 0xc8, 0x6c, 0x72, 0x7f];
+    // Prefix of anchor events emitted through self CPI, little endian sha256("anchor:event")[..8]
+    pub const EVENT_INSTRUCTION_TAG: [u8; 8] = [0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d];
+    // First 8 bytes of sha256("event:TradeEvent")
+    pub const TRADE_EVENT_DISCRIMINATOR: [u8; 8] = [0xbd, 0xdb, 0x7f, 0xd3, 0x4e, 0xe6, 0x61, 0xee];
+}
+
+// Jito block engine accounts
+pub mod jito_accounts {
+    use solana_sdk::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    pub const JITO_BLOCK_ENGINE_URL: &str = "https://mainnet.block-engine.jito.wtf/api/v1/bundles";
+    pub const MAX_BUNDLE_TRANSACTIONS: usize = 5;
+
+    pub fn jito_tip_accounts() -> Vec<Pubkey> {
+        [
+            "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+            "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+            "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+            "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+            "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+            "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+            "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+            "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+        ]
+        .iter()
+        .map(|address| Pubkey::from_str(address).unwrap())
+        .collect()
+    }
+}
+
+// Jupiter Lock vesting program accounts
+pub mod vesting_accounts {
+    use solana_sdk::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    pub fn token_vesting_program() -> Pubkey {
+        Pubkey::from_str("LocktDzaV1W2Bm9DeZeiyz4J9zs4fRqNiYqQyracRXw").unwrap()
+    }
+    pub fn memo_program() -> Pubkey {
+        Pubkey::from_str("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr").unwrap()
+    }
+    // First 8 bytes of sha256("global:create_vesting_escrow")
+    pub const CREATE_VESTING_ESCROW_DISCRIMINATOR: [u8; 8] = [0x17, 0x64, 0xc5, 0x5e, 0xde, 0x99, 0x26, 0x5a];
+    // First 8 bytes of sha256("global:claim")
+    pub const CLAIM_DISCRIMINATOR: [u8; 8] = [0x3e, 0xc6, 0xd6, 0xc1, 0xd5, 0x9f, 0x6c, 0xd2];
+    // First 8 bytes of sha256("global:cancel_vesting_escrow")
+    pub const CANCEL_VESTING_ESCROW_DISCRIMINATOR: [u8; 8] = [0xd9, 0xe9, 0x0d, 0x03, 0x8f, 0x65, 0x35, 0xc9];
+    // First 8 bytes of sha256("account:VestingEscrow")
+    pub const VESTING_ESCROW_DISCRIMINATOR: [u8; 8] = [0xf4, 0x77, 0xb7, 0x04, 0x49, 0x74, 0x87, 0xc3];
+    // Size of a vesting escrow account, discriminator included
+    pub const VESTING_ESCROW_SIZE: usize = 296;
+}
+
+pub mod governance_accounts {
+    use solana_sdk::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    pub fn spl_governance_program() -> Pubkey {
+        Pubkey::from_str("GovER5Lthms3bLBqWub97yVrMmEogzX7xNjdXpPPCVZw").unwrap()
+    }
+    // First byte of governance accounts, the GovernanceAccountType of the program
+    pub const TOKEN_OWNER_RECORD_V1: u8 = 2;
+    pub const VOTE_RECORD_V1: u8 = 7;
+    pub const VOTE_RECORD_V2: u8 = 12;
+    pub const REALM_V1: u8 = 1;
+    pub const REALM_V2: u8 = 16;
+    pub const TOKEN_OWNER_RECORD_V2: u8 = 17;
+}
+
+// Liquid staking tokens and the pools backing them
+pub mod stake_pool_accounts {
+    use solana_sdk::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    pub fn msol_mint() -> Pubkey {
+        Pubkey::from_str("mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So").unwrap()
+    }
+    pub fn marinade_state() -> Pubkey {
+        Pubkey::from_str("8szGkuLTAux9XMgZ2vtY39jVSowEcpBfFfD8hXSEqdGC").unwrap()
+    }
+    pub fn jitosol_mint() -> Pubkey {
+        Pubkey::from_str("J1toso1uCk3RLmjorhTtrVwY9HJ7X8V9yYac6Y7kGCPn").unwrap()
+    }
+    pub fn bsol_mint() -> Pubkey {
+        Pubkey::from_str("bSo13r4TkiE4KumL71LsHTPpL2euBYLFx6h9HP3piy1").unwrap()
+    }
+    pub fn spl_stake_pool_program() -> Pubkey {
+        Pubkey::from_str("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy").unwrap()
+    }
+    // SPL stake pool account, the pool mint, then total lamports followed by the pool token supply
+    pub const STAKE_POOL_ACCOUNT_TYPE: u8 = 1;
+    pub const STAKE_POOL_MINT_OFFSET: usize = 162;
+    pub const STAKE_POOL_TOTAL_LAMPORTS_OFFSET: usize = 258;
+    // Marinade state account, mSOL price scaled by 2^32
+    pub const MARINADE_MSOL_PRICE_OFFSET: usize = 512;
+    pub const MARINADE_PRICE_DENOMINATOR: u64 = 0x1_0000_0000;
+}
+
+// Marginfi v2 lending program
+pub mod marginfi_accounts {
+    use solana_sdk::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    pub fn marginfi_program() -> Pubkey {
+        Pubkey::from_str("MFv2hWf31Z9kbCa1snEPYctwafyhdvnV7FZnsebVacA").unwrap()
+    }
+    pub const MARGINFI_ACCOUNT_DISCRIMINATOR: [u8; 8] = [0x43, 0xb2, 0x82, 0x6d, 0x7e, 0x72, 0x1c, 0x2a];
+    pub const BANK_DISCRIMINATOR: [u8; 8] = [0x8e, 0x31, 0xa6, 0xf2, 0x32, 0x42, 0x61, 0xbc];
+    // Offset of the authority of a marginfi account, followed by its 16 balances
+    pub const MARGINFI_ACCOUNT_AUTHORITY_OFFSET: usize = 40;
+    pub const MARGINFI_BALANCE_SIZE: usize = 104;
+    pub const MARGINFI_MAX_BALANCES: usize = 16;
+}
+
+// Kamino lending program
+pub mod kamino_accounts {
+    use solana_sdk::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    pub fn kamino_lending_program() -> Pubkey {
+        Pubkey::from_str("KLend2g3cP87fffoy8q1mQqGKjrxjC8boSyAYavgmjD").unwrap()
+    }
+    pub const OBLIGATION_DISCRIMINATOR: [u8; 8] = [0xa8, 0xce, 0x8d, 0x6a, 0x58, 0x4c, 0xac, 0xa7];
+    pub const RESERVE_DISCRIMINATOR: [u8; 8] = [0x2b, 0xf2, 0xcc, 0xca, 0x1a, 0xf7, 0x3b, 0x7f];
+    // Offset of the owner of an obligation, followed by its 8 deposits
+    pub const OBLIGATION_OWNER_OFFSET: usize = 64;
+    pub const OBLIGATION_DEPOSIT_SIZE: usize = 136;
+    pub const OBLIGATION_MAX_DEPOSITS: usize = 8;
+    pub const OBLIGATION_BORROWS_OFFSET: usize = 1208;
+    pub const OBLIGATION_BORROW_SIZE: usize = 200;
+    pub const OBLIGATION_MAX_BORROWS: usize = 5;
+    // Offsets of the liquidity mint and its decimals in a reserve
+    pub const RESERVE_MINT_OFFSET: usize = 128;
+    pub const RESERVE_MINT_DECIMALS_OFFSET: usize = 272;
+}
+
+// Hot wallets of centralized exchanges, which credit deposits by the exact deposit address
+pub mod exchange_accounts {
+    use solana_sdk::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    pub fn known_exchange_wallets() -> Vec<(Pubkey, &'static str)> {
+        [
+            ("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM", "Binance"),
+            ("5tzFkiKscXHK5ZXCGbXZxdw7gTjjD1mBwuoFbhUvuAi9", "Binance"),
+            ("H8sMJSCQxfKiFTCfDR3DUMLPwcRbM61LGFJ8N4dK3WjS", "Coinbase"),
+            ("2AQdpHJ2JpcEgPiATUXjQxA8QmafFegfQwSLWSprPicm", "Coinbase"),
+            ("AC5RDfQFmDS1deWZos921JfqscXdByf8BKHs5ACWjtW2", "Bybit"),
+            ("5VCwKtCXgCJ6kit5FybXjvriW3xELsFDhYrPSqtJNmcD", "OKX"),
+            ("FWznbcNXWQuHTawe9RxvQ2LdCENssh12dsznf4RiouN5", "Kraken"),
+        ]
+        .iter()
+        .map(|(address, exchange)| (Pubkey::from_str(address).unwrap(), *exchange))
+        .collect()
+    }
 }
@@ -0,0 +1,468 @@
+//! # Deposits
+//!
+//! Building blocks of a custodial deposit system. Every user gets a deposit wallet, a keypair
+//! derived from the base keypair and the user id with `derive_deposit_keypair`, so deposit
+//! wallets can be derived again at any time without storing keypairs. The derived keypair owns
+//! the deposit address and its associated token accounts, and signs when sweeping SOL or tokens.
+//!
+//! A `DepositTracker` watches the deposit addresses and their associated token accounts of the
+//! tracked mints. It reads the transactions of every watched account since the last one seen and
+//! emits a `CreditEvent` for every incoming transfer, so deposits landing in the same poll
+//! interval as a sweep are still credited.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration
+};
+use log::warn;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    hash::hashv,
+    pubkey::Pubkey,
+    signer::{keypair::{keypair_from_seed, Keypair}, Signer}
+};
+use solana_system_interface::instruction::SystemInstruction;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token::instruction::TokenInstruction;
+use spl_token_2022::instruction::TokenInstruction as Token2022Instruction;
+use crate::{
+    error::DepositError,
+    event_channel::{event_channel, ChannelConfig, EventReceiver},
+    read_transactions::{
+        backfill::signatures_since,
+        instruction_decoder::DecodedInstruction,
+        transaction::{get_transaction, InspectedInstruction, InspectedTransaction}
+    },
+    supervisor::ShutdownSignal
+};
+
+/// Derives the deposit keypair of `user_id` from the secret key of `base`.
+///
+/// ## Errors
+///
+/// Returns `DepositError::InvalidUserId` if the user id is empty.
+pub fn derive_deposit_keypair(base: &Keypair, user_id: &str) -> Result<Keypair, DepositError> {
+    if user_id.is_empty() {
+        return Err(DepositError::InvalidUserId(user_id.to_string()));
+    }
+    let seed = hashv(&[b"deposit", base.secret_bytes(), user_id.as_bytes()]);
+    keypair_from_seed(seed.as_ref()).map_err(|_| DepositError::InvalidUserId(user_id.to_string()))
+}
+
+/// Derives the deposit address of `user_id` from the secret key of `base`.
+///
+/// ## Errors
+///
+/// Returns `DepositError::InvalidUserId` if the user id is empty.
+pub fn derive_deposit_address(base: &Keypair, user_id: &str) -> Result<Pubkey, DepositError> {
+    derive_deposit_keypair(base, user_id).map(|keypair| keypair.pubkey())
+}
+
+/// Asset credited by a deposit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DepositAsset {
+    Sol,
+    Token { mint: Pubkey },
+}
+
+/// A deposit to the address of a user.
+///
+/// ### Fields
+///
+/// - `user_id`: User owning the deposit address.
+/// - `account`: Account that received the deposit, the deposit address or one of its token accounts.
+/// - `asset`: SOL or the deposited token.
+/// - `amount`: Amount transferred in lamports or raw token amount.
+/// - `signature`: Signature of the transaction of the deposit.
+/// - `slot`: Slot of the transaction of the deposit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreditEvent {
+    pub user_id: String,
+    pub account: Pubkey,
+    pub asset: DepositAsset,
+    pub amount: u64,
+    pub signature: String,
+    pub slot: u64,
+}
+
+/// Account watched for deposits and the newest transaction already credited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WatchedAccount {
+    user_id: String,
+    asset: DepositAsset,
+    last_signature: Option<String>,
+}
+
+/// Deposit addresses of users derived from a base keypair, and the newest transaction already
+/// credited for each watched account.
+///
+/// The first poll of a new account credits the transfers of its latest page of transactions.
+/// After a restart, restore the last signatures with `set_last_signature` before polling so
+/// deposits are not credited twice. Transfers out of a watched account, e.g sweeps to a hot
+/// wallet, are ignored. Transfer fees of Token 2022 mints are not deducted from credits.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use std::{sync::Arc, time::Duration};
+/// use solana_sdk::pubkey::Pubkey;
+/// use easy_solana::{
+///     create_rpc_client,
+///     constants::solana_programs::token_program,
+///     deposits::DepositTracker,
+///     event_channel::{ChannelConfig, OverflowPolicy},
+///     supervisor::Supervisor,
+///     SecretKeypair
+/// };
+///
+/// # async fn example() {
+/// let client = Arc::new(create_rpc_client("RPC_URL"));
+/// let base_keypair = SecretKeypair::from_env("DEPOSIT_BASE_KEY").unwrap();
+/// let usdc: Pubkey = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".parse().unwrap();
+///
+/// let mut tracker = DepositTracker::new();
+/// tracker.track_mint(usdc, token_program());
+/// let deposit_address = tracker.add_user(&base_keypair, "user-1").unwrap();
+/// println!("Deposit to {}", deposit_address);
+///
+/// let supervisor = Supervisor::new();
+/// let channel = ChannelConfig::new(1_024, OverflowPolicy::Block);
+/// let (tracker, mut credits) = tracker.spawn(client, Duration::from_secs(5), channel, supervisor.shutdown_signal());
+/// while let Some(credit) = credits.recv().await {
+///     println!("Credit {} of {:?} to {}", credit.amount, credit.asset, credit.user_id);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DepositTracker {
+    mints: Vec<(Pubkey, Pubkey)>,
+    users: HashMap<String, Pubkey>,
+    accounts: HashMap<Pubkey, WatchedAccount>,
+}
+
+impl DepositTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deposit address of `user_id` derived from `base`, adding the user if it is not watched yet.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `DepositError::InvalidUserId` if the user id is empty.
+    pub fn add_user(&mut self, base: &Keypair, user_id: &str) -> Result<Pubkey, DepositError> {
+        if let Some(address) = self.users.get(user_id) {
+            return Ok(*address);
+        }
+        let address = derive_deposit_address(base, user_id)?;
+        self.users.insert(user_id.to_string(), address);
+        self.watch(address, user_id, DepositAsset::Sol);
+        for (mint, token_program) in self.mints.clone() {
+            self.watch_token_account(address, user_id, mint, token_program);
+        }
+        Ok(address)
+    }
+
+    /// Watches deposits of `mint`, owned by `token_program`, to the associated token accounts of
+    /// every deposit address.
+    pub fn track_mint(&mut self, mint: Pubkey, token_program: Pubkey) {
+        if self.mints.iter().any(|(tracked_mint, _)| *tracked_mint == mint) {
+            return;
+        }
+        self.mints.push((mint, token_program));
+        let users: Vec<(String, Pubkey)> = self.users.iter().map(|(user_id, address)| (user_id.clone(), *address)).collect();
+        for (user_id, address) in users {
+            self.watch_token_account(address, &user_id, mint, token_program);
+        }
+    }
+
+    /// Deposit address of `user_id`, `None` if the user was not added.
+    pub fn deposit_address(&self, user_id: &str) -> Option<Pubkey> {
+        self.users.get(user_id).copied()
+    }
+
+    /// Every watched account, the deposit addresses and their token accounts.
+    pub fn watched_accounts(&self) -> Vec<Pubkey> {
+        self.accounts.keys().copied().collect()
+    }
+
+    /// Newest transaction of `account` that was already credited, `None` if it is not watched or
+    /// was never polled.
+    pub fn last_signature(&self, account: &Pubkey) -> Option<&str> {
+        self.accounts.get(account).and_then(|watched| watched.last_signature.as_deref())
+    }
+
+    /// Restores the newest credited transaction of a watched account, e.g from a database after a
+    /// restart. Returns `false` if the account is not watched.
+    pub fn set_last_signature(&mut self, account: &Pubkey, signature: &str) -> bool {
+        match self.accounts.get_mut(account) {
+            Some(watched) => {
+                watched.last_signature = Some(signature.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reads the transactions of all watched accounts since the last poll and returns a credit for
+    /// every incoming transfer, oldest first per account. Failed transactions are skipped.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `DepositError::QueryError` if signatures or transactions can not be read. No last
+    /// signature is updated then, so the next poll credits the same transfers again.
+    pub fn poll(&mut self, client: &RpcClient) -> Result<Vec<CreditEvent>, DepositError> {
+        let mut credits = Vec::new();
+        let mut last_signatures = Vec::new();
+
+        for (account, watched) in &self.accounts {
+            let (newest_signature, signatures) = signatures_since(client, account, watched.last_signature.as_deref())?;
+            for signature in signatures.iter().rev() {
+                let transaction = get_transaction(client, signature)?;
+                let amount = incoming_amount(&transaction, account, watched.asset);
+                if amount > 0 {
+                    credits.push(CreditEvent {
+                        user_id: watched.user_id.clone(),
+                        account: *account,
+                        asset: watched.asset,
+                        amount,
+                        signature: transaction.signature.clone(),
+                        slot: transaction.slot,
+                    });
+                }
+            }
+            if let Some(newest_signature) = newest_signature {
+                last_signatures.push((*account, newest_signature));
+            }
+        }
+
+        for (account, signature) in last_signatures {
+            if let Some(watched) = self.accounts.get_mut(&account) {
+                watched.last_signature = Some(signature);
+            }
+        }
+        Ok(credits)
+    }
+
+    /// Polls every `interval` in the background until `shutdown`, sending credits to the returned
+    /// receiver. The tracker is shared so users and mints can be added while it is watched.
+    pub fn spawn(
+        self,
+        client: Arc<RpcClient>,
+        interval: Duration,
+        channel: ChannelConfig<CreditEvent>,
+        mut shutdown: ShutdownSignal
+    ) -> (Arc<Mutex<DepositTracker>>, EventReceiver<CreditEvent>) {
+        let tracker = Arc::new(Mutex::new(self));
+        let (sender, receiver) = event_channel(channel);
+        let task_tracker = Arc::clone(&tracker);
+
+        tokio::spawn(async move {
+            while !shutdown.is_shutdown() {
+                let poll_tracker = Arc::clone(&task_tracker);
+                let poll_client = Arc::clone(&client);
+                let result = tokio::task::spawn_blocking(move || {
+                    poll_tracker.lock().map(|mut tracker| tracker.poll(&poll_client)).ok()
+                }).await;
+                match result {
+                    Ok(Some(Ok(credits))) => {
+                        for credit in credits {
+                            if sender.send(credit).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Ok(Some(Err(err))) => warn!("Unable to poll deposits: {}", err),
+                    _ => warn!("Deposit poll panicked"),
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = shutdown.cancelled() => {}
+                }
+            }
+        });
+
+        (tracker, receiver)
+    }
+
+    fn watch_token_account(&mut self, address: Pubkey, user_id: &str, mint: Pubkey, token_program: Pubkey) {
+        let token_account = get_associated_token_address_with_program_id(&address, &mint, &token_program);
+        self.watch(token_account, user_id, DepositAsset::Token { mint });
+    }
+
+    fn watch(&mut self, account: Pubkey, user_id: &str, asset: DepositAsset) {
+        self.accounts.entry(account).or_insert_with(|| WatchedAccount {
+            user_id: user_id.to_string(),
+            asset,
+            last_signature: None,
+        });
+    }
+}
+
+/// Total of the transfers of `asset` into `account` by a successful transaction, inner
+/// instructions included.
+fn incoming_amount(transaction: &InspectedTransaction, account: &Pubkey, asset: DepositAsset) -> u64 {
+    if transaction.error.is_some() {
+        return 0;
+    }
+    transaction.instructions
+        .iter()
+        .chain(transaction.inner_instructions.iter().flat_map(|inner| &inner.instructions))
+        .filter_map(|instruction| transfer_to(instruction, asset))
+        .filter(|(destination, _)| destination == account)
+        .fold(0, |total: u64, (_, amount)| total.saturating_add(amount))
+}
+
+/// Destination and amount of an instruction transferring `asset`, `None` for other instructions.
+/// Unchecked Token 2022 transfers are deprecated but still executed by the program.
+#[allow(deprecated)]
+fn transfer_to(instruction: &InspectedInstruction, asset: DepositAsset) -> Option<(Pubkey, u64)> {
+    let account = |index: usize| instruction.accounts.get(index).copied();
+    match (asset, instruction.decode()) {
+        (DepositAsset::Sol, DecodedInstruction::System(SystemInstruction::Transfer { lamports })) => Some((account(1)?, lamports)),
+        (DepositAsset::Sol, DecodedInstruction::System(SystemInstruction::TransferWithSeed { lamports, .. })) => Some((account(2)?, lamports)),
+        (DepositAsset::Token { .. }, DecodedInstruction::Token(TokenInstruction::Transfer { amount }))
+        | (DepositAsset::Token { .. }, DecodedInstruction::Token2022(Token2022Instruction::Transfer { amount })) => Some((account(1)?, amount)),
+        (DepositAsset::Token { mint }, DecodedInstruction::Token(TokenInstruction::TransferChecked { amount, .. }))
+        | (DepositAsset::Token { mint }, DecodedInstruction::Token2022(Token2022Instruction::TransferChecked { amount, .. })) => {
+            (account(1)? == mint).then_some((account(2)?, amount))
+        }
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::{hash::Hash, instruction::Instruction, signature::Signature, transaction::Transaction};
+    use solana_system_interface::instruction::transfer;
+    use spl_token::instruction::transfer_checked;
+    use crate::{
+        constants::solana_programs::token_program,
+        test_utils::{mock_client, signatures_for_address_response, transaction_response}
+    };
+
+    fn signed_transaction(instructions: &[Instruction], signers: &[&Keypair]) -> Transaction {
+        Transaction::new_signed_with_payer(instructions, Some(&signers[0].pubkey()), signers, Hash::default())
+    }
+
+    #[test]
+    fn test_derive_deposit_keypair() {
+        let base = Keypair::new();
+        let keypair = derive_deposit_keypair(&base, "user-1").unwrap();
+        assert!(keypair.pubkey() == derive_deposit_keypair(&base, "user-1").unwrap().pubkey());
+        assert!(keypair.pubkey() == derive_deposit_address(&base, "user-1").unwrap());
+        assert!(keypair.pubkey() != derive_deposit_address(&base, "user-2").unwrap());
+        assert!(keypair.pubkey() != derive_deposit_address(&Keypair::new(), "user-1").unwrap());
+        assert!(derive_deposit_address(&base, &"a".repeat(64)).is_ok());
+        assert!(matches!(derive_deposit_address(&base, ""), Err(DepositError::InvalidUserId(_))));
+    }
+
+    #[test]
+    fn test_track_mint_watches_token_accounts_of_every_user() {
+        let base = Keypair::new();
+        let mut tracker = DepositTracker::new();
+        let mint = Pubkey::new_unique();
+        let address_1 = tracker.add_user(&base, "user-1").unwrap();
+        tracker.track_mint(mint, token_program());
+        let address_2 = tracker.add_user(&base, "user-2").unwrap();
+
+        let watched_accounts = tracker.watched_accounts();
+        assert!(watched_accounts.len() == 4);
+        for address in [address_1, address_2] {
+            let token_account = get_associated_token_address_with_program_id(&address, &mint, &token_program());
+            assert!(watched_accounts.contains(&token_account));
+        }
+        assert!(tracker.add_user(&base, "user-1").unwrap() == address_1);
+        assert!(tracker.watched_accounts().len() == 4);
+    }
+
+    #[test]
+    fn test_poll_credits_transfers_swept_in_the_same_interval() {
+        let base = Keypair::new();
+        let mut tracker = DepositTracker::new();
+        let mint = Pubkey::new_unique();
+        tracker.track_mint(mint, token_program());
+        let address = tracker.add_user(&base, "user-1").unwrap();
+        let deposit_keypair = derive_deposit_keypair(&base, "user-1").unwrap();
+        let token_address = get_associated_token_address_with_program_id(&address, &mint, &token_program());
+
+        let sender = Keypair::new();
+        let sender_token_account = Pubkey::new_unique();
+        let deposit = signed_transaction(&[
+            transfer(&sender.pubkey(), &address, 5_000),
+            transfer_checked(&token_program(), &sender_token_account, &mint, &token_address, &sender.pubkey(), &[], 1_000, 6).unwrap(),
+        ], &[&sender]);
+        // the first deposit is swept while a second one lands
+        let sweep_and_deposit = signed_transaction(&[
+            transfer(&address, &Pubkey::new_unique(), 5_000),
+            transfer(&sender.pubkey(), &address, 2_000),
+        ], &[&deposit_keypair, &sender]);
+
+        let signatures = [Signature::new_unique().to_string(), Signature::new_unique().to_string()];
+        let page = signatures_for_address_response(&[(&signatures[1], 11, Some(110), false), (&signatures[0], 10, Some(100), false)]);
+        // both watched accounts see the same transactions, oldest fetched first
+        let client = mock_client(vec![
+            (RpcRequest::GetSignaturesForAddress, page.clone()),
+            (RpcRequest::GetSignaturesForAddress, page),
+            (RpcRequest::GetSignaturesForAddress, signatures_for_address_response(&[])),
+            (RpcRequest::GetSignaturesForAddress, signatures_for_address_response(&[])),
+            (RpcRequest::GetTransaction, transaction_response(&deposit, 10, Some(100), &[])),
+            (RpcRequest::GetTransaction, transaction_response(&sweep_and_deposit, 11, Some(110), &[])),
+            (RpcRequest::GetTransaction, transaction_response(&deposit, 10, Some(100), &[])),
+            (RpcRequest::GetTransaction, transaction_response(&sweep_and_deposit, 11, Some(110), &[])),
+        ]);
+
+        let mut credits = tracker.poll(&client).unwrap();
+        credits.sort_by_key(|credit| credit.amount);
+        let amounts: Vec<(Pubkey, DepositAsset, u64, u64)> = credits
+            .iter()
+            .map(|credit| (credit.account, credit.asset, credit.amount, credit.slot))
+            .collect();
+        assert!(amounts == vec![
+            (token_address, DepositAsset::Token { mint }, 1_000, 10),
+            (address, DepositAsset::Sol, 2_000, 11),
+            (address, DepositAsset::Sol, 5_000, 10),
+        ]);
+        assert!(credits.iter().all(|credit| credit.user_id == "user-1"));
+        assert!(tracker.last_signature(&address) == Some(signatures[1].as_str()));
+        assert!(tracker.last_signature(&token_address) == Some(signatures[1].as_str()));
+
+        assert!(tracker.poll(&client).unwrap().is_empty());
+        assert!(tracker.last_signature(&address) == Some(signatures[1].as_str()));
+    }
+
+    #[test]
+    fn test_transfers_of_other_mints_are_not_credited() {
+        let sender = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let token_account = Pubkey::new_unique();
+        let transaction = signed_transaction(&[
+            transfer_checked(&token_program(), &Pubkey::new_unique(), &Pubkey::new_unique(), &token_account, &sender.pubkey(), &[], 1_000, 6).unwrap(),
+            transfer(&sender.pubkey(), &token_account, 5_000),
+        ], &[&sender]);
+        let client = mock_client(vec![(RpcRequest::GetTransaction, transaction_response(&transaction, 1, None, &[]))]);
+        let inspected = get_transaction(&client, &Signature::new_unique().to_string()).unwrap();
+        assert!(incoming_amount(&inspected, &token_account, DepositAsset::Token { mint }) == 0);
+        assert!(incoming_amount(&inspected, &token_account, DepositAsset::Sol) == 5_000);
+    }
+
+    #[test]
+    fn test_restored_signatures_are_not_credited_again() {
+        let mut tracker = DepositTracker::new();
+        let address = tracker.add_user(&Keypair::new(), "user-1").unwrap();
+        let signature = Signature::new_unique().to_string();
+        assert!(tracker.set_last_signature(&address, &signature));
+        assert!(!tracker.set_last_signature(&Pubkey::new_unique(), &signature));
+        assert!(tracker.last_signature(&address) == Some(signature.as_str()));
+
+        let client = mock_client(vec![
+            (RpcRequest::GetSignaturesForAddress, signatures_for_address_response(&[])),
+        ]);
+        assert!(tracker.poll(&client).unwrap().is_empty());
+        assert!(tracker.last_signature(&address) == Some(signature.as_str()));
+    }
+}
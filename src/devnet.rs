@@ -0,0 +1,283 @@
+//! # Devnet
+//!
+//! Fixtures to exercise the buy, sell and cleanup APIs on devnet without risking mainnet funds.
+//! `setup_devnet_fixture` creates a test mint, mints its supply to the payer and opens a
+//! Raydium CPMM pool against WSOL, which can then be traded with `raydium_cpmm_swap`.
+//!
+//! Every function checks that the client points to devnet first, so a misconfigured RPC URL
+//! can not spend mainnet SOL.
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer
+};
+use solana_system_interface::instruction::create_account;
+use spl_associated_token_account::{
+    get_associated_token_address,
+    instruction::create_associated_token_account_idempotent
+};
+use spl_token::{
+    instruction::{initialize_mint2, mint_to},
+    state::Mint
+};
+use crate::{
+    constants::{
+        solana_programs::{sol_pubkey, token_program}
+    },
+    error::{DevnetError, TransactionBuilderError},
+    explorer::Cluster,
+    raydium::cpmm::{cpmm_initialize_instruction, CpmmPoolKeys},
+    utils::address_to_pubkey,
+    write_transactions::{transaction_builder::TransactionBuilder, utils::send_and_confirm_transaction}
+};
+
+/// Public devnet RPC URL, rate limited but enough for fixtures.
+pub const DEVNET_RPC_URL: &str = "https://api.devnet.solana.com";
+/// Compute limit for opening a pool, which creates the pool, vault, LP mint and oracle accounts.
+const OPEN_POOL_COMPUTE_LIMIT: u32 = 400_000;
+
+/// Raydium deployment and amounts used by `setup_devnet_fixture`, all amounts are raw amounts
+/// without decimals. The devnet addresses of the CPMM program and its pool creation fee account
+/// are listed in the Raydium developer docs.
+///
+/// ### Fields
+///
+/// - `cpmm_program`: Raydium CPMM program on devnet.
+/// - `create_pool_fee_account`: Account receiving the pool creation fee of `cpmm_program`.
+/// - `decimals`: Decimals of the test mint.
+/// - `supply`: Tokens minted to the payer.
+/// - `pool_token_amount`: Tokens deposited into the pool, the rest stays with the payer.
+/// - `pool_lamports`: SOL deposited into the pool, the payer also pays the pool creation fee.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DevnetFixtureConfig {
+    pub cpmm_program: Pubkey,
+    pub create_pool_fee_account: Pubkey,
+    pub decimals: u8,
+    pub supply: u64,
+    pub pool_token_amount: u64,
+    pub pool_lamports: u64,
+}
+
+impl DevnetFixtureConfig {
+    /// Config for the given Raydium deployment, minting 1 billion tokens with 6 decimals and
+    /// opening the pool with 80% of the supply against 0.5 SOL.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `DevnetError::InvalidAddress` if either address is invalid.
+    pub fn new(cpmm_program: &str, create_pool_fee_account: &str) -> Result<Self, DevnetError> {
+        Ok(Self {
+            cpmm_program: address_to_pubkey(cpmm_program)?,
+            create_pool_fee_account: address_to_pubkey(create_pool_fee_account)?,
+            decimals: 6,
+            supply: 1_000_000_000_000_000,
+            pool_token_amount: 800_000_000_000_000,
+            pool_lamports: 500_000_000,
+        })
+    }
+}
+
+/// Accounts created by `setup_devnet_fixture`.
+///
+/// ### Fields
+///
+/// - `mint`: Test mint, with the payer as mint authority.
+/// - `pool`: Raydium CPMM pool of the mint against WSOL.
+/// - `signatures`: Signatures of the setup transactions, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DevnetFixture {
+    pub mint: Pubkey,
+    pub pool: CpmmPoolKeys,
+    pub signatures: Vec<Signature>,
+}
+
+/// Checks that `client` points to devnet.
+///
+/// ## Errors
+///
+/// Returns `DevnetError::NotDevnet` with the URL of the client otherwise.
+pub fn ensure_devnet(client: &RpcClient) -> Result<(), DevnetError> {
+    let url = client.url();
+    match Cluster::from_rpc_url(&url) {
+        Cluster::Devnet => Ok(()),
+        _ => Err(DevnetError::NotDevnet(url)),
+    }
+}
+
+/// Requests `lamports` from the devnet faucet and waits for the airdrop to be confirmed. The
+/// faucet is rate limited, airdrops above 2 SOL or repeated requests are likely to fail.
+///
+/// ## Errors
+///
+/// Returns `DevnetError::AirdropNotConfirmed` if the airdrop did not land in time.
+pub fn airdrop_sol(client: &RpcClient, wallet_address: &str, lamports: u64) -> Result<Signature, DevnetError> {
+    ensure_devnet(client)?;
+    let wallet = address_to_pubkey(wallet_address)?;
+    let signature = client.request_airdrop(&wallet, lamports)?;
+    client
+        .poll_for_signature(&signature)
+        .map_err(|_| DevnetError::AirdropNotConfirmed(signature))?;
+    Ok(signature)
+}
+
+/// Creates a new SPL token mint with `payer` as mint and freeze authority, returning its address.
+pub fn create_test_mint(client: &RpcClient, payer: &Keypair, decimals: u8) -> Result<Pubkey, DevnetError> {
+    ensure_devnet(client)?;
+    let mint_keypair = Keypair::new();
+    let mint = mint_keypair.pubkey();
+    let rent = client.get_minimum_balance_for_rent_exemption(Mint::LEN)?;
+    let initialize_instruction = initialize_mint2(&token_program(), &mint, &payer.pubkey(), Some(&payer.pubkey()), decimals)
+        .map_err(|err| TransactionBuilderError::InstructionError(err.to_string()))?;
+
+    let mut builder = TransactionBuilder::new(client, payer);
+    builder.instructions.push(create_account(&payer.pubkey(), &mint, rent, Mint::LEN as u64, &token_program()));
+    builder.instructions.push(initialize_instruction);
+    builder.signing_keypairs.push(&mint_keypair);
+    send_builder(client, &builder)?;
+    Ok(mint)
+}
+
+/// Mints `amount` of `mint_address` to the associated token account of `payer`, which has to be
+/// the mint authority, creating the account if it does not exist.
+pub fn mint_test_supply(client: &RpcClient, payer: &Keypair, mint_address: &str, amount: u64) -> Result<Signature, DevnetError> {
+    ensure_devnet(client)?;
+    let mint = address_to_pubkey(mint_address)?;
+    let payer_pubkey = payer.pubkey();
+    let token_account = get_associated_token_address(&payer_pubkey, &mint);
+    let mint_instruction = mint_to(&token_program(), &mint, &token_account, &payer_pubkey, &[], amount)
+        .map_err(|err| TransactionBuilderError::InstructionError(err.to_string()))?;
+
+    let mut builder = TransactionBuilder::new(client, payer);
+    builder.instructions.push(create_associated_token_account_idempotent(&payer_pubkey, &payer_pubkey, &mint, &token_program()));
+    builder.instructions.push(mint_instruction);
+    send_builder(client, &builder)
+}
+
+/// Opens a Raydium CPMM pool of `mint_address` against WSOL on devnet, depositing
+/// `config.pool_token_amount` tokens from the associated token account of `payer` and wrapping
+/// `config.pool_lamports` of SOL. The LP tokens go to `payer`.
+pub fn open_test_pool(client: &RpcClient, payer: &Keypair, mint_address: &str, config: &DevnetFixtureConfig) -> Result<(CpmmPoolKeys, Signature), DevnetError> {
+    ensure_devnet(client)?;
+    let mint = address_to_pubkey(mint_address)?;
+    let pool = CpmmPoolKeys::derive(&config.cpmm_program, 0, (mint, token_program()), (sol_pubkey(), token_program()));
+    let (init_amount_0, init_amount_1) = if pool.token_0_mint == mint {
+        (config.pool_token_amount, config.pool_lamports)
+    } else {
+        (config.pool_lamports, config.pool_token_amount)
+    };
+
+    let mut builder = TransactionBuilder::new(client, payer);
+    builder.set_compute_limit(OPEN_POOL_COMPUTE_LIMIT).wrap_sol(config.pool_lamports)?;
+    builder.instructions.push(cpmm_initialize_instruction(
+        &pool,
+        &payer.pubkey(),
+        &config.create_pool_fee_account,
+        init_amount_0,
+        init_amount_1,
+        0,
+    ));
+    let signature = send_builder(client, &builder)?;
+    Ok((pool, signature))
+}
+
+/// Creates a test mint, mints its supply to `payer` and opens a Raydium CPMM pool against WSOL.
+/// `payer` needs `pool_lamports` plus roughly 0.2 SOL for rent and the pool creation fee, use
+/// `airdrop_sol` to fund it.
+///
+/// ## Errors
+///
+/// Returns `DevnetError::NotDevnet` if `client` does not point to devnet, and
+/// `DevnetError::TransactionFailed` if any setup transaction fails.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{
+///     create_rpc_client,
+///     devnet::{airdrop_sol, setup_devnet_fixture, DevnetFixtureConfig, DEVNET_RPC_URL},
+///     SecretKeypair
+/// };
+/// use solana_sdk::signer::Signer;
+/// use std::env;
+///
+/// let client = create_rpc_client(DEVNET_RPC_URL);
+/// let payer = SecretKeypair::generate();
+/// airdrop_sol(&client, &payer.pubkey().to_string(), 1_000_000_000).unwrap();
+/// let config = DevnetFixtureConfig::new(
+///     &env::var("RAYDIUM_CPMM_DEVNET_PROGRAM").unwrap(),
+///     &env::var("RAYDIUM_CPMM_DEVNET_FEE_ACCOUNT").unwrap()
+/// ).unwrap();
+/// let fixture = setup_devnet_fixture(&client, &payer, &config).unwrap();
+/// println!("Test mint {} trades in pool {}", fixture.mint, fixture.pool.pool_state);
+/// ```
+pub fn setup_devnet_fixture(client: &RpcClient, payer: &Keypair, config: &DevnetFixtureConfig) -> Result<DevnetFixture, DevnetError> {
+    ensure_devnet(client)?;
+    let mint = create_test_mint(client, payer, config.decimals)?;
+    let mint_signature = mint_test_supply(client, payer, &mint.to_string(), config.supply)?;
+    let (pool, pool_signature) = open_test_pool(client, payer, &mint.to_string(), config)?;
+    Ok(DevnetFixture { mint, pool, signatures: vec![mint_signature, pool_signature] })
+}
+
+/// Builds, sends and confirms the transaction of `builder`.
+fn send_builder(client: &RpcClient, builder: &TransactionBuilder<'_>) -> Result<Signature, DevnetError> {
+    let confirmed_transaction = send_and_confirm_transaction(client, builder.build()?)?
+        .into_result(DevnetError::TransactionFailed)?;
+    Ok(confirmed_transaction.signature)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::mock_client;
+    use solana_rpc_client::mock_sender::MocksMap;
+
+    fn devnet_client() -> RpcClient {
+        RpcClient::new_mock_with_mocks_map(DEVNET_RPC_URL, MocksMap::default())
+    }
+
+    fn config(cpmm_program: &Pubkey) -> DevnetFixtureConfig {
+        DevnetFixtureConfig::new(&cpmm_program.to_string(), &Pubkey::new_unique().to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_refuses_non_devnet_clients() {
+        let client = mock_client(Vec::new());
+        let payer = Keypair::new();
+        assert!(matches!(ensure_devnet(&client), Err(DevnetError::NotDevnet(_))));
+        assert!(matches!(create_test_mint(&client, &payer, 6), Err(DevnetError::NotDevnet(_))));
+        assert!(matches!(
+            setup_devnet_fixture(&client, &payer, &config(&Pubkey::new_unique())),
+            Err(DevnetError::NotDevnet(_))
+        ));
+        assert!(ensure_devnet(&devnet_client()).is_ok());
+    }
+
+    #[test]
+    fn test_setup_devnet_fixture() {
+        let client = devnet_client();
+        let payer = Keypair::new();
+        let cpmm_program = Pubkey::new_unique();
+        let fixture = setup_devnet_fixture(&client, &payer, &config(&cpmm_program)).unwrap();
+
+        assert!(fixture.signatures.len() == 2);
+        assert!(fixture.pool.program_id == cpmm_program);
+        assert!([fixture.pool.token_0_mint, fixture.pool.token_1_mint].contains(&fixture.mint));
+        assert!([fixture.pool.token_0_mint, fixture.pool.token_1_mint].contains(&sol_pubkey()));
+    }
+
+    #[test]
+    fn test_config_rejects_invalid_addresses() {
+        assert!(matches!(DevnetFixtureConfig::new("invalid", &Pubkey::new_unique().to_string()), Err(DevnetError::InvalidAddress(_))));
+    }
+
+    #[test]
+    fn test_airdrop_sol() {
+        let client = devnet_client();
+        assert!(airdrop_sol(&client, &Pubkey::new_unique().to_string(), 1_000_000_000).is_ok());
+        assert!(matches!(airdrop_sol(&client, "invalid", 1), Err(DevnetError::InvalidAddress(_))));
+    }
+}
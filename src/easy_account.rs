@@ -0,0 +1,147 @@
+//! # Easy Account
+//!
+//! Standard way of adding account types. Deriving `EasyAccount` on a struct deriving
+//! `BorshDeserialize` validates the discriminator, decodes the rest of the data with Borsh,
+//! trims the null bytes padding `String` fields, and provides `fetch` to load the account.
+//!
+//! ```rust
+//! use borsh::BorshDeserialize;
+//! use easy_solana::easy_account::EasyAccount;
+//! use solana_sdk::pubkey::Pubkey;
+//!
+//! #[derive(BorshDeserialize, EasyAccount)]
+//! #[easy_account(discriminator = [1, 2, 3, 4, 5, 6, 7, 8], name = "pool")]
+//! struct Pool {
+//!     mint: Pubkey,
+//!     label: String,
+//! }
+//!
+//! let mut data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+//! data.extend_from_slice(Pubkey::default().as_ref());
+//! data.extend(borsh::to_vec("SOL\0\0\0").unwrap());
+//! let pool = Pool::try_from_account_data(&data).unwrap();
+//! assert!(pool.label == "SOL");
+//! ```
+
+use borsh::BorshDeserialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use crate::{
+    error::ReadTransactionError,
+    read_transactions::account::AccountParser
+};
+
+pub use easy_solana_derive::EasyAccount;
+
+/// Account type decoded from account data, usually implemented with `#[derive(EasyAccount)]`.
+pub trait EasyAccount: Sized {
+    /// Bytes the account data starts with, empty if the account has no discriminator.
+    const DISCRIMINATOR: &'static [u8];
+    /// Name of the account in errors.
+    const NAME: &'static str;
+
+    /// Deserializes account data.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::WrongAccountType` if the discriminator does not match,
+    /// or `ReadTransactionError::DeserializeError` if the data does not match the struct.
+    fn try_from_account_data(data: &[u8]) -> Result<Self, ReadTransactionError>;
+
+    /// Fetches and deserializes the account at `pubkey`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::RpcError` if the account can not be fetched, or the errors
+    /// of `try_from_account_data`.
+    fn fetch(client: &RpcClient, pubkey: &Pubkey) -> Result<Self, ReadTransactionError> {
+        let account = client.get_account(pubkey)?;
+        Self::try_from_account_data(&account.data)
+    }
+}
+
+impl AccountParser {
+    /// Registers `T` as a decoder of the accounts owned by `program_id`, under the name of `T`.
+    pub fn register_account<T: EasyAccount + Send + Sync + 'static>(&mut self, program_id: Pubkey) -> &mut Self {
+        self.register(program_id, T::NAME, |data| T::try_from_account_data(data).ok())
+    }
+}
+
+/// Checks the discriminator of `T` and Borsh deserializes the rest of the data. Data after the
+/// struct is ignored, as accounts are often allocated larger than their content.
+#[doc(hidden)]
+pub fn deserialize_account_data<T: EasyAccount + BorshDeserialize>(data: &[u8]) -> Result<T, ReadTransactionError> {
+    let mut remaining_data = data
+        .strip_prefix(T::DISCRIMINATOR)
+        .ok_or_else(|| ReadTransactionError::WrongAccountType(T::NAME.to_string()))?;
+    T::deserialize(&mut remaining_data).map_err(|_| ReadTransactionError::DeserializeError)
+}
+
+/// Removes the null bytes padding fixed size strings.
+#[doc(hidden)]
+pub fn trim_padding(text: &str) -> String {
+    text.trim_end_matches('\0').to_string()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use crate::{
+        read_transactions::account::{get_account_with_parser, AccountType},
+        test_utils::{account_info_response, account_with_data, mock_client}
+    };
+
+    const VAULT_DISCRIMINATOR: [u8; 8] = [9, 8, 7, 6, 5, 4, 3, 2];
+
+    #[derive(Debug, PartialEq, BorshDeserialize, EasyAccount)]
+    #[easy_account(discriminator = VAULT_DISCRIMINATOR)]
+    struct Vault {
+        owner: Pubkey,
+        name: String,
+        balance: u64,
+    }
+
+    fn vault_data(name: &str) -> Vec<u8> {
+        let mut data = VAULT_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(Pubkey::default().as_ref());
+        data.extend(borsh::to_vec(name).unwrap());
+        data.extend_from_slice(&42_u64.to_le_bytes());
+        data.extend_from_slice(&[0; 32]);
+        data
+    }
+
+    #[test]
+    fn test_derived_easy_account() {
+        let vault = Vault::try_from_account_data(&vault_data("treasury\0\0\0\0")).unwrap();
+        assert!(vault == Vault { owner: Pubkey::default(), name: "treasury".to_string(), balance: 42 });
+        assert!(Vault::NAME == "Vault");
+
+        let mut wrong_data = vault_data("treasury");
+        wrong_data[0] = 0;
+        assert!(matches!(Vault::try_from_account_data(&wrong_data), Err(ReadTransactionError::WrongAccountType(_))));
+        assert!(matches!(Vault::try_from_account_data(&VAULT_DISCRIMINATOR), Err(ReadTransactionError::DeserializeError)));
+    }
+
+    #[test]
+    fn test_fetch_and_register_easy_account() {
+        let program_id = Pubkey::new_unique();
+        let vault_account = account_with_data(program_id, vault_data("treasury"));
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&vault_account), 1)),
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&vault_account), 1)),
+        ]);
+
+        let vault = Vault::fetch(&client, &Pubkey::new_unique()).unwrap();
+        assert!(vault.balance == 42);
+
+        let mut parser = AccountParser::new();
+        parser.register_account::<Vault>(program_id);
+        let account = get_account_with_parser(&client, &Pubkey::new_unique().to_string(), &parser).unwrap();
+        match &account.account_type {
+            AccountType::Custom(custom_account) => assert!(custom_account.name == "Vault" && custom_account.downcast_ref::<Vault>().is_some()),
+            _ => panic!("Expected custom account"),
+        }
+    }
+}
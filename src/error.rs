@@ -3,7 +3,9 @@ use solana_client::{
     client_error::ClientError as RpcClientError,
     client_error::ClientErrorKind as RpcClientErrorKind
 };
-use solana_sdk::{program_error::ProgramError, pubkey::ParsePubkeyError};
+use solana_sdk::{program_error::ProgramError, pubkey::{ParsePubkeyError, Pubkey}, signature::Signature, transaction::TransactionError};
+use std::time::Duration;
+use crate::rate_limit::RpcProvider;
 
 #[derive(Error, Debug)]
 pub enum ReadTransactionError {
@@ -19,6 +21,20 @@ pub enum ReadTransactionError {
     AccountNotFound,
     #[error("Token has migrated or not from pumpfun")]
     BondingCurveError,
+    #[error("Invalid Signature")]
+    InvalidSignature,
+    #[error("Account is not a {0} account")]
+    WrongAccountType(String),
+    #[error("Unable to get quote: {0}")]
+    QuoteError(String),
+    #[error("Transaction not found")]
+    TransactionNotFound,
+    #[error("Unable to read accounts at a consistent slot after {0} attempts")]
+    InconsistentSnapshot(usize),
+    #[error("Batch requests are not supported: {0}")]
+    BatchNotSupported(String),
+    #[error("Quote math failed: {0}")]
+    MathError(#[from] MathError),
 }
 
 impl From<RpcClientError> for ReadTransactionError {
@@ -30,6 +46,14 @@ impl From<RpcClientError> for ReadTransactionError {
     }
 }
 
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathError {
+    #[error("Overflow when computing {0}")]
+    Overflow(&'static str),
+    #[error("Division by zero when computing {0}")]
+    DivisionByZero(&'static str),
+}
+
 #[derive(Error, Debug)]
 pub enum WriteTransactionError {
     #[error("Invalid Address")]
@@ -41,9 +65,19 @@ pub enum WriteTransactionError {
     #[error("Error: {0}")]
     DeleteTokenAccountError(String),
     #[error("Client Error: {0}")]
-    RpcClientError(#[from]RpcClientError),
+    RpcClientError(Box<RpcClientError>),
     #[error("Error interacting with Program: {0}")]
     ProgramError(#[from]ProgramError),
+    #[error("Transaction failed: {0}")]
+    TransactionFailed(TransactionError),
+    #[error("Transaction expired before it was confirmed")]
+    TransactionExpired,
+}
+
+impl From<RpcClientError> for WriteTransactionError {
+    fn from(err: RpcClientError) -> Self {
+        WriteTransactionError::RpcClientError(Box::new(err))
+    }
 }
 
 #[derive(Error, Debug)]
@@ -53,13 +87,37 @@ pub enum TransactionBuilderError {
     #[error("Unable to get latest blockhash")]
     LatestBlockhashError,
     #[error("Unable to create instruction: {0}")]
-    InstructionError(String)
+    InstructionError(String),
+    #[error("Client Error: {0}")]
+    RpcClientError(Box<RpcClientError>),
+    #[error("Error reading data: {0}")]
+    QueryError(#[from]ReadTransactionError),
+    #[error("Unable to simulate transaction: {0}")]
+    SimulationError(#[from]SimulationError),
+    #[error("Insufficient SOL balance to transfer")]
+    InsufficientFunds,
+    #[error("Transaction violates policy: {0}")]
+    PolicyViolation(#[from]PolicyError),
+    #[error("Simulated transaction failed: {0}")]
+    SimulationFailed(TransactionError),
+    #[error("Unsafe destination: {0}")]
+    UnsafeDestination(TransferCheckError),
+}
+
+impl From<RpcClientError> for TransactionBuilderError {
+    fn from(err: RpcClientError) -> Self {
+        TransactionBuilderError::RpcClientError(Box::new(err))
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum SimulationError {
+    #[error("Invalid Address")]
+    InvalidAddress(#[from]ParsePubkeyError),
+    #[error("Error reading data: {0}")]
+    QueryError(#[from]ReadTransactionError),
     #[error("Client Error: {0}")]
-    RpcClientError(#[from]RpcClientError),
+    RpcClientError(Box<RpcClientError>),
     #[error("Logs unavailable")]
     NoLogsAvailable,
     #[error("Units consumed unavailable.")]
@@ -68,6 +126,47 @@ pub enum SimulationError {
     NoInnerInstructionsAvailable,
 }
 
+impl From<RpcClientError> for SimulationError {
+    fn from(err: RpcClientError) -> Self {
+        SimulationError::RpcClientError(Box::new(err))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum OrchestratorError {
+    #[error("Step {0} depends on unknown step {1}")]
+    UnknownDependency(String, String),
+    #[error("Duplicate step name: {0}")]
+    DuplicateStep(String),
+    #[error("Circular dependency between steps")]
+    CircularDependency,
+    #[error("Step {0} failed to build: {1}")]
+    BuildError(String, TransactionBuilderError),
+    #[error("Step {0} failed to simulate: {1}")]
+    SimulationError(String, SimulationError),
+    #[error("Step {0} failed simulation: {1}")]
+    SimulationFailed(String, TransactionError),
+    #[error("Step {0} failed to send: {1}")]
+    SendError(String, WriteTransactionError),
+    #[error("Bundles can hold at most {0} transactions")]
+    TooManyBundleTransactions(usize),
+    #[error("Bundle Error: {0}")]
+    BundleError(#[from]BundleError),
+}
+
+#[derive(Error, Debug)]
+pub enum BundleError {
+    #[error("Request Error: {0}")]
+    RequestError(#[from]reqwest::Error),
+    #[error("Invalid Response: {0}")]
+    InvalidResponse(String),
+    #[error("Unable to serialize transaction")]
+    SerializeError,
+    #[error("Bundle failed: {0}")]
+    BundleFailed(String),
+    #[error("Bundle was not confirmed in time")]
+    Timeout,
+}
 
 #[derive(Error, Debug)]
 pub enum KeypairError {
@@ -76,6 +175,412 @@ pub enum KeypairError {
     #[error("Unable to decode base58 string to keypair")]
     Base58DecodeError,
     #[error("Unable to get keypair from bytes")]
-    InvalidKeypairBytes
+    InvalidKeypairBytes,
+    #[error("Environment variable {0} not found")]
+    EnvVarNotFound(String),
+}
+
+
+#[derive(Error, Debug)]
+pub enum PersistenceError {
+    #[error("Unable to read or write file: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Unable to serialize or deserialize data: {0}")]
+    SerializeError(#[from] serde_json::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum TransferCheckError {
+    #[error("Invalid Address")]
+    InvalidAddress(#[from]ParsePubkeyError),
+    #[error("Client Error: {0}")]
+    RpcClientError(Box<RpcClientError>),
+    #[error("Destination is not a valid token account")]
+    InvalidTokenAccount,
+    #[error("Destination token account is owned by program {0} instead of the token program")]
+    WrongProgram(Pubkey),
+    #[error("Destination token account belongs to {actual} instead of {expected}")]
+    WrongOwner { expected: Pubkey, actual: Pubkey },
+    #[error("Destination token account holds {actual} instead of {expected}")]
+    WrongMint { expected: Pubkey, actual: Pubkey },
+    #[error("Destination token account is frozen")]
+    Frozen,
+    #[error("{0} is not a token mint")]
+    InvalidMint(Pubkey),
+    #[error("Destination {0} is a token mint, which can not hold tokens")]
+    DestinationIsMint(Pubkey),
+    #[error("Destination {0} is a program")]
+    DestinationIsProgram(Pubkey),
+}
+
+impl From<RpcClientError> for TransferCheckError {
+    fn from(err: RpcClientError) -> Self {
+        TransferCheckError::RpcClientError(Box::new(err))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum RelayError {
+    #[error("Unable to serialize or deserialize transaction")]
+    SerializeError,
+    #[error("Invalid transaction")]
+    InvalidTransaction,
+    #[error("Relayer is not the fee payer of the transaction")]
+    WrongFeePayer,
+    #[error("Relayer account is used by an instruction")]
+    RelayerAccountUsed,
+    #[error("Program {0} is not allowed")]
+    ProgramNotAllowed(Pubkey),
+    #[error("Transaction has {0} instructions, more than allowed")]
+    TooManyInstructions(usize),
+    #[error("Transaction has {0} signatures, more than allowed")]
+    TooManySignatures(usize),
+    #[error("Compute unit price of {0} micro-lamports is more than allowed")]
+    ComputeUnitPriceTooHigh(u64),
+    #[error("Compute unit limit of {0} is more than allowed")]
+    ComputeUnitLimitTooHigh(u32),
+    #[error("Transaction is missing a valid signature")]
+    InvalidSignature,
+    #[error("Unable to send transaction: {0}")]
+    SendError(Box<WriteTransactionError>),
+    #[error("Transaction {0} failed: {1}")]
+    TransactionFailed(Signature, TransactionError),
 }
 
+impl From<WriteTransactionError> for RelayError {
+    fn from(err: WriteTransactionError) -> Self {
+        RelayError::SendError(Box::new(err))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum OtcSwapError {
+    #[error("Invalid Address")]
+    InvalidAddress(#[from]ParsePubkeyError),
+    #[error("Unable to serialize or deserialize swap proposal")]
+    SerializeError,
+    #[error("Unable to create instruction: {0}")]
+    InstructionError(String),
+    #[error("{0} is not a party of the swap")]
+    WrongSigner(Pubkey),
+    #[error("Swap proposal does not match the agreed terms")]
+    TermsMismatch,
+    #[error("Swap transaction does not match its terms")]
+    TransactionMismatch,
+    #[error("Swap transaction is not signed by the maker")]
+    InvalidSignature,
+    #[error("Failed to send transaction: {0}")]
+    SendError(Box<WriteTransactionError>),
+    #[error("Transaction {0} failed: {1}")]
+    TransactionFailed(Signature, TransactionError),
+}
+
+impl From<WriteTransactionError> for OtcSwapError {
+    fn from(err: WriteTransactionError) -> Self {
+        OtcSwapError::SendError(Box::new(err))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum PolicyError {
+    #[error("Program {0} is not allowed")]
+    ProgramNotAllowed(Pubkey),
+    #[error("Destination {0} is not allowed")]
+    DestinationNotAllowed(Pubkey),
+    #[error("Transaction spends {lamports} lamports, more than the limit of {limit}")]
+    SolLimitExceeded { lamports: u64, limit: u64 },
+    #[error("Selling {amount} of a balance of {balance} exceeds the sell limit")]
+    SellLimitExceeded { amount: u64, balance: u64 },
+    #[error("Account {0} is not a valid token account")]
+    InvalidTokenAccount(Pubkey),
+    #[error("Instruction of program {0} can not be checked against the allowed destinations")]
+    UncheckedInstruction(Pubkey),
+    #[error("RpcError: {0}")]
+    RpcError(String),
+}
+
+impl From<RpcClientError> for PolicyError {
+    fn from(err: RpcClientError) -> Self {
+        PolicyError::RpcError(err.to_string())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SpendingLimitError {
+    #[error("Sending {amount} {asset} from {owner} exceeds the remaining budget of {remaining}")]
+    BudgetExceeded { owner: String, asset: String, amount: u64, remaining: u64 },
+    #[error("Invalid owner address {0}")]
+    InvalidOwner(String),
+    #[error("Unable to persist outflows: {0}")]
+    PersistenceError(#[from]PersistenceError),
+}
+
+#[derive(Error, Debug)]
+pub enum KeyRotationError {
+    #[error("Invalid Address")]
+    InvalidAddress(#[from]ParsePubkeyError),
+    #[error("RpcError: {0}")]
+    RpcError(String),
+    #[error("Unable to deserialize token account")]
+    DeserializeError,
+    #[error("New wallet is the same as the old wallet")]
+    SameWallet,
+    #[error("Unable to build transaction: {0}")]
+    BuildError(#[from]TransactionBuilderError),
+    #[error("Unable to send transaction: {0}")]
+    SendError(Box<WriteTransactionError>),
+    #[error("Transaction {0} failed: {1}")]
+    TransactionFailed(Signature, TransactionError),
+}
+
+impl From<RpcClientError> for KeyRotationError {
+    fn from(err: RpcClientError) -> Self {
+        KeyRotationError::RpcError(err.to_string())
+    }
+}
+
+impl From<WriteTransactionError> for KeyRotationError {
+    fn from(err: WriteTransactionError) -> Self {
+        KeyRotationError::SendError(Box::new(err))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum DevnetError {
+    #[error("Client is not connected to devnet: {0}")]
+    NotDevnet(String),
+    #[error("Invalid Address")]
+    InvalidAddress(#[from]ParsePubkeyError),
+    #[error("RpcError: {0}")]
+    RpcError(String),
+    #[error("Airdrop {0} was not confirmed")]
+    AirdropNotConfirmed(Signature),
+    #[error("Unable to build transaction: {0}")]
+    BuildError(#[from]TransactionBuilderError),
+    #[error("Unable to send transaction: {0}")]
+    SendError(Box<WriteTransactionError>),
+    #[error("Transaction {0} failed: {1}")]
+    TransactionFailed(Signature, TransactionError),
+}
+
+impl From<RpcClientError> for DevnetError {
+    fn from(err: RpcClientError) -> Self {
+        DevnetError::RpcError(err.to_string())
+    }
+}
+
+impl From<WriteTransactionError> for DevnetError {
+    fn from(err: WriteTransactionError) -> Self {
+        DevnetError::SendError(Box::new(err))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SendError {
+    #[error("Invalid Address")]
+    InvalidAddress(#[from]ParsePubkeyError),
+    #[error("RpcError: {0}")]
+    RpcError(String),
+    #[error("{0} is not a token mint")]
+    InvalidMint(Pubkey),
+    #[error("Destination failed transfer checks: {0}")]
+    DestinationCheckFailed(#[from]TransferCheckError),
+    #[error("Unable to build transaction: {0}")]
+    BuildError(#[from]TransactionBuilderError),
+    #[error("Unable to simulate transaction: {0}")]
+    SimulationError(#[from]SimulationError),
+    #[error("Simulated transaction failed: {0}")]
+    SimulationFailed(TransactionError),
+    #[error("Unable to send transaction: {0}")]
+    SendFailed(Box<WriteTransactionError>),
+    #[error("Transaction {0} failed: {1}")]
+    TransactionFailed(Signature, TransactionError),
+}
+
+impl From<RpcClientError> for SendError {
+    fn from(err: RpcClientError) -> Self {
+        SendError::RpcError(err.to_string())
+    }
+}
+
+impl From<WriteTransactionError> for SendError {
+    fn from(err: WriteTransactionError) -> Self {
+        SendError::SendFailed(Box::new(err))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum DepositError {
+    #[error("User id {0:?} can not be empty")]
+    InvalidUserId(String),
+    #[error("RpcError: {0}")]
+    RpcError(String),
+    #[error("Error reading data: {0}")]
+    QueryError(#[from]ReadTransactionError),
+}
+
+impl From<RpcClientError> for DepositError {
+    fn from(err: RpcClientError) -> Self {
+        DepositError::RpcError(err.to_string())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum WithdrawalError {
+    #[error("Invalid Address")]
+    InvalidAddress(#[from]ParsePubkeyError),
+    #[error("RpcError: {0}")]
+    RpcError(String),
+    #[error("Unable to read nonce account: {0}")]
+    NonceError(String),
+    #[error("Invalid saved transaction: {0}")]
+    InvalidTransaction(String),
+    #[error("Failed to build transaction: {0}")]
+    BuildError(#[from]TransactionBuilderError),
+    #[error("Failed to send transaction: {0}")]
+    SendError(Box<WriteTransactionError>),
+    #[error("Failed to save withdrawals: {0}")]
+    PersistenceError(#[from]PersistenceError),
+}
+
+impl From<RpcClientError> for WithdrawalError {
+    fn from(err: RpcClientError) -> Self {
+        WithdrawalError::RpcError(err.to_string())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Unable to read config file: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Invalid config file: {0}")]
+    ParseError(#[from] toml::de::Error),
+    #[error("Invalid value for {0}: {1}")]
+    InvalidValue(String, String),
+    #[error("Invalid Address")]
+    InvalidAddress(#[from]ParsePubkeyError),
+}
+
+#[derive(Error, Debug)]
+pub enum ProfileError {
+    #[error("Unable to read or write profile: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Invalid profiles file: {0}")]
+    ParseError(#[from] toml::de::Error),
+    #[error("Unable to serialize keypair: {0}")]
+    SerializeError(#[from] serde_json::Error),
+    #[error("Profile {0} not found")]
+    ProfileNotFound(String),
+    #[error("Profile {0} already has a keypair")]
+    ProfileExists(String),
+    #[error("Invalid profile name {0}, use letters, digits, - and _")]
+    InvalidName(String),
+    #[error("Invalid keypair file {0}")]
+    InvalidKeypairFile(std::path::PathBuf),
+    #[error("Unable to load keypair: {0}")]
+    KeypairError(#[from] KeypairError),
+}
+
+#[derive(Error, Debug)]
+pub enum SupervisorError {
+    #[error("Task {0} is already supervised")]
+    DuplicateTask(String),
+    #[error("Supervisor is shutting down")]
+    ShuttingDown,
+    #[error("Tasks did not stop in time and were aborted: {0:?}")]
+    ShutdownTimeout(Vec<String>),
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum EventChannelError {
+    #[error("Receiver of the event channel was dropped")]
+    Closed,
+    #[error("Event channel is full")]
+    Full,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    #[error("Events after {requested} are no longer buffered, the oldest is {oldest}")]
+    Gap { requested: u64, oldest: u64 },
+    #[error("Sequence {requested} was not published yet, the latest is {latest}")]
+    FutureSequence { requested: u64, latest: u64 },
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Rate limited by {provider}: {message}")]
+pub struct RateLimitError {
+    pub provider: RpcProvider,
+    pub retry_after: Option<Duration>,
+    pub message: String,
+}
+
+#[derive(Error, Debug)]
+pub enum CurveInvariantError {
+    #[error("Unable to quote the bonding curve: {0}")]
+    CurveError(#[from] ReadTransactionError),
+    #[error("Buy of {requested} lamports spends {paid} lamports")]
+    Overspent { requested: u64, paid: u64 },
+    #[error("Trade decreased the curve product from {before} to {after}")]
+    ProductDecreased { before: u128, after: u128 },
+    #[error("Selling tokens bought for {paid} lamports returns {received} lamports")]
+    RoundtripProfit { paid: u64, received: u64 },
+}
+
+#[derive(Error, Debug)]
+pub enum BackfillError {
+    #[error("Invalid Address")]
+    InvalidAddress(#[from]ParsePubkeyError),
+    #[error("Unable to read history: {0}")]
+    ReadError(#[from]ReadTransactionError),
+    #[error("Checkpoint belongs to {0}")]
+    CheckpointMismatch(String),
+    #[error("Unable to handle transactions: {0}")]
+    HandlerError(String),
+    #[error("Unable to save checkpoint: {0}")]
+    PersistenceError(#[from]PersistenceError),
+}
+
+impl From<RpcClientError> for BackfillError {
+    fn from(err: RpcClientError) -> Self {
+        BackfillError::ReadError(err.into())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum IndexerError {
+    #[error("Invalid Address")]
+    InvalidAddress(#[from]ParsePubkeyError),
+    #[error("Unable to read chain data: {0}")]
+    ReadError(#[from]ReadTransactionError),
+    #[error("Unable to backfill history: {0}")]
+    BackfillError(#[from]BackfillError),
+    #[error("Unable to save index: {0}")]
+    PersistenceError(#[from]PersistenceError),
+}
+
+impl From<RpcClientError> for IndexerError {
+    fn from(err: RpcClientError) -> Self {
+        IndexerError::ReadError(err.into())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum LimitOrderError {
+    #[error("Invalid order: {0}")]
+    InvalidOrder(String),
+    #[error("Unable to read chain data: {0}")]
+    ReadError(#[from]ReadTransactionError),
+    #[error("Unable to build transaction: {0}")]
+    BuildError(#[from]TransactionBuilderError),
+    #[error("Unable to send transaction: {0}")]
+    SendError(Box<WriteTransactionError>),
+    #[error("Transaction {0} failed: {1}")]
+    TransactionFailed(Signature, TransactionError),
+}
+
+impl From<WriteTransactionError> for LimitOrderError {
+    fn from(err: WriteTransactionError) -> Self {
+        LimitOrderError::SendError(Box::new(err))
+    }
+}
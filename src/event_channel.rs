@@ -0,0 +1,340 @@
+//! # Event Channel
+//!
+//! Bounded channels for streaming APIs with a configurable behaviour once the buffer is full, so
+//! a slow consumer can not grow memory without limit during volatile markets.
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Arc, Mutex, MutexGuard}
+};
+use tokio::sync::Notify;
+use crate::error::EventChannelError;
+
+/// Whether two events have the same coalescing key.
+pub type SameKey<T> = Arc<dyn Fn(&T, &T) -> bool + Send + Sync>;
+
+/// What happens when an event is sent to a full channel.
+pub enum OverflowPolicy<T> {
+    /// The sender waits until the receiver makes room.
+    Block,
+    /// The oldest buffered event is dropped.
+    DropOldest,
+    /// A buffered event with the same key is replaced by the new one, e.g the latest price of
+    /// a token. Events with new keys are handled like `DropOldest` when the buffer is full.
+    CoalesceLatest(SameKey<T>),
+}
+
+impl<T> OverflowPolicy<T> {
+    /// `CoalesceLatest` for events with the same `key`.
+    pub fn coalesce_by<K: PartialEq>(key: impl Fn(&T) -> K + Send + Sync + 'static) -> Self {
+        OverflowPolicy::CoalesceLatest(Arc::new(move |buffered, event| key(buffered) == key(event)))
+    }
+}
+
+impl<T> Clone for OverflowPolicy<T> {
+    fn clone(&self) -> Self {
+        match self {
+            OverflowPolicy::Block => OverflowPolicy::Block,
+            OverflowPolicy::DropOldest => OverflowPolicy::DropOldest,
+            OverflowPolicy::CoalesceLatest(same_key) => OverflowPolicy::CoalesceLatest(Arc::clone(same_key)),
+        }
+    }
+}
+
+impl<T> fmt::Debug for OverflowPolicy<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverflowPolicy::Block => write!(f, "Block"),
+            OverflowPolicy::DropOldest => write!(f, "DropOldest"),
+            OverflowPolicy::CoalesceLatest(_) => write!(f, "CoalesceLatest"),
+        }
+    }
+}
+
+/// Capacity and overflow behaviour of an event channel.
+#[derive(Debug, Clone)]
+pub struct ChannelConfig<T> {
+    pub capacity: usize,
+    pub overflow: OverflowPolicy<T>,
+}
+
+impl<T> ChannelConfig<T> {
+    pub fn new(capacity: usize, overflow: OverflowPolicy<T>) -> Self {
+        Self { capacity: capacity.max(1), overflow }
+    }
+}
+
+struct State<T> {
+    buffer: VecDeque<T>,
+    senders: usize,
+    receiver_alive: bool,
+    dropped: u64,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    config: ChannelConfig<T>,
+    event_ready: Notify,
+    space_ready: Notify,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        // A panic while holding the lock leaves the buffer consistent, keep using it
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Creates a bounded channel following `config`.
+///
+/// ## Example
+///
+/// ```rust
+/// use easy_solana::{
+///     event_channel::{event_channel, ChannelConfig, OverflowPolicy},
+///     pumpfun::price_tracker::PriceSample
+/// };
+///
+/// # async fn example() {
+/// // Keeps at most one price per second, consumers only see the latest one
+/// let config = ChannelConfig::new(100, OverflowPolicy::coalesce_by(|sample: &PriceSample| sample.timestamp / 1_000));
+/// let (sender, mut receiver) = event_channel(config);
+/// sender.send(PriceSample { timestamp: 1_000, price: 0.1 }).await.unwrap();
+/// sender.send(PriceSample { timestamp: 1_500, price: 0.2 }).await.unwrap();
+/// assert!(receiver.recv().await.unwrap().price == 0.2);
+/// # }
+/// ```
+pub fn event_channel<T>(config: ChannelConfig<T>) -> (EventSender<T>, EventReceiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            buffer: VecDeque::with_capacity(config.capacity),
+            senders: 1,
+            receiver_alive: true,
+            dropped: 0,
+        }),
+        config,
+        event_ready: Notify::new(),
+        space_ready: Notify::new(),
+    });
+    (EventSender { shared: Arc::clone(&shared) }, EventReceiver { shared })
+}
+
+/// Sending half of an event channel, can be cloned for several producers.
+pub struct EventSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> EventSender<T> {
+    /// Buffers `event` following the overflow policy, waiting for room only with `Block`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `EventChannelError::Closed` if the receiver was dropped.
+    pub async fn send(&self, mut event: T) -> Result<(), EventChannelError> {
+        loop {
+            // Registered before checking the buffer so a receiver dropped meanwhile still wakes it
+            let space_ready = self.shared.space_ready.notified();
+            tokio::pin!(space_ready);
+            space_ready.as_mut().enable();
+            {
+                let mut state = self.shared.lock();
+                if !state.receiver_alive {
+                    return Err(EventChannelError::Closed);
+                }
+                match self.push(&mut state, event) {
+                    Ok(()) => {
+                        self.shared.event_ready.notify_one();
+                        return Ok(());
+                    }
+                    Err(blocked) => event = blocked,
+                }
+            }
+            space_ready.await;
+        }
+    }
+
+    /// Buffers `event` without waiting.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `EventChannelError::Closed` if the receiver was dropped and
+    /// `EventChannelError::Full` if the policy is `Block` and the buffer is full.
+    pub fn try_send(&self, event: T) -> Result<(), EventChannelError> {
+        let mut state = self.shared.lock();
+        if !state.receiver_alive {
+            return Err(EventChannelError::Closed);
+        }
+        self.push(&mut state, event).map_err(|_| EventChannelError::Full)?;
+        self.shared.event_ready.notify_one();
+        Ok(())
+    }
+
+    /// Whether the receiver was dropped.
+    pub fn is_closed(&self) -> bool {
+        !self.shared.lock().receiver_alive
+    }
+
+    /// Returns the event back if the policy is `Block` and the buffer is full.
+    fn push(&self, state: &mut State<T>, event: T) -> Result<(), T> {
+        let capacity = self.shared.config.capacity;
+        match &self.shared.config.overflow {
+            OverflowPolicy::CoalesceLatest(same_key) => {
+                if let Some(buffered) = state.buffer.iter_mut().find(|buffered| same_key(buffered, &event)) {
+                    *buffered = event;
+                    state.dropped += 1;
+                    return Ok(());
+                }
+            }
+            OverflowPolicy::Block if state.buffer.len() >= capacity => return Err(event),
+            _ => {}
+        }
+        if state.buffer.len() >= capacity {
+            state.buffer.pop_front();
+            state.dropped += 1;
+        }
+        state.buffer.push_back(event);
+        Ok(())
+    }
+}
+
+impl<T> Clone for EventSender<T> {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Self { shared: Arc::clone(&self.shared) }
+    }
+}
+
+impl<T> Drop for EventSender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            self.shared.event_ready.notify_one();
+        }
+    }
+}
+
+/// Receiving half of an event channel.
+pub struct EventReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> EventReceiver<T> {
+    /// Next event, `None` once every sender is dropped and the buffer is empty.
+    pub async fn recv(&mut self) -> Option<T> {
+        loop {
+            {
+                let mut state = self.shared.lock();
+                if let Some(event) = state.buffer.pop_front() {
+                    self.shared.space_ready.notify_one();
+                    return Some(event);
+                }
+                if state.senders == 0 {
+                    return None;
+                }
+            }
+            self.shared.event_ready.notified().await;
+        }
+    }
+
+    /// Next buffered event without waiting.
+    pub fn try_recv(&mut self) -> Option<T> {
+        let event = self.shared.lock().buffer.pop_front();
+        if event.is_some() {
+            self.shared.space_ready.notify_one();
+        }
+        event
+    }
+
+    /// Number of buffered events.
+    pub fn len(&self) -> usize {
+        self.shared.lock().buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of events dropped or replaced because the consumer fell behind.
+    pub fn dropped(&self) -> u64 {
+        self.shared.lock().dropped
+    }
+}
+
+impl<T> Drop for EventReceiver<T> {
+    fn drop(&mut self) {
+        self.shared.lock().receiver_alive = false;
+        self.shared.space_ready.notify_waiters();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_drop_oldest() {
+        let (sender, mut receiver) = event_channel(ChannelConfig::new(2, OverflowPolicy::DropOldest));
+        for event in 1..=4 {
+            sender.send(event).await.unwrap();
+        }
+        assert!(receiver.len() == 2 && receiver.dropped() == 2);
+        drop(sender);
+        assert!(receiver.recv().await == Some(3));
+        assert!(receiver.recv().await == Some(4));
+        assert!(receiver.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_latest() {
+        let config = ChannelConfig::new(2, OverflowPolicy::coalesce_by(|(token, _): &(&str, u64)| *token));
+        let (sender, mut receiver) = event_channel(config);
+        sender.send(("BONK", 1)).await.unwrap();
+        sender.send(("WIF", 1)).await.unwrap();
+        sender.send(("BONK", 2)).await.unwrap();
+        assert!(receiver.len() == 2);
+        // New keys still drop the oldest once full
+        sender.send(("POPCAT", 1)).await.unwrap();
+        assert!(receiver.dropped() == 2);
+        assert!(receiver.try_recv() == Some(("WIF", 1)));
+        assert!(receiver.try_recv() == Some(("POPCAT", 1)));
+        assert!(receiver.try_recv().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_block_waits_for_receiver() {
+        let (sender, mut receiver) = event_channel(ChannelConfig::new(1, OverflowPolicy::Block));
+        sender.send(1).await.unwrap();
+        assert!(matches!(sender.try_send(2), Err(EventChannelError::Full)));
+
+        let producer = tokio::spawn(async move {
+            sender.send(2).await.unwrap();
+            sender.send(3).await.unwrap();
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(receiver.len() == 1);
+        let mut received = Vec::new();
+        while let Some(event) = receiver.recv().await {
+            received.push(event);
+        }
+        producer.await.unwrap();
+        assert!(received == vec![1, 2, 3] && receiver.dropped() == 0);
+    }
+
+    #[tokio::test]
+    async fn test_send_after_receiver_dropped() {
+        let (sender, receiver) = event_channel(ChannelConfig::new(1, OverflowPolicy::Block));
+        sender.send(1).await.unwrap();
+        let blocked = tokio::spawn({
+            let sender = sender.clone();
+            async move { sender.send(2).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        drop(receiver);
+        assert!(matches!(blocked.await.unwrap(), Err(EventChannelError::Closed)));
+        assert!(sender.is_closed());
+    }
+}
@@ -0,0 +1,146 @@
+//! # Explorer
+//!
+//! Builds links to transactions and accounts on block explorers, for logs, reports and webhook
+//! payloads, with the cluster set so devnet links do not open on mainnet.
+
+use std::fmt::Display;
+
+/// Cluster a transaction or account belongs to. `Custom` holds the URL of the RPC node, e.g
+/// for a local validator.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Cluster {
+    #[default]
+    Mainnet,
+    Devnet,
+    Testnet,
+    Custom(String),
+}
+
+impl Cluster {
+    /// Infers the cluster from the URL of an RPC node, e.g `client.url()`. Local URLs are
+    /// `Custom`, and any other URL is assumed to be mainnet.
+    pub fn from_rpc_url(rpc_url: &str) -> Self {
+        let lowercase_url = rpc_url.to_lowercase();
+        if lowercase_url.contains("devnet") {
+            Cluster::Devnet
+        } else if lowercase_url.contains("testnet") {
+            Cluster::Testnet
+        } else if ["localhost", "127.0.0.1", "0.0.0.0"].iter().any(|host| lowercase_url.contains(host)) {
+            Cluster::Custom(rpc_url.to_string())
+        } else {
+            Cluster::Mainnet
+        }
+    }
+}
+
+/// Block explorer to link to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Explorer {
+    /// explorer.solana.com
+    #[default]
+    Explorer,
+    Solscan,
+    SolanaFM,
+}
+
+impl Explorer {
+    fn base_url(&self) -> &'static str {
+        match self {
+            Explorer::Explorer => "https://explorer.solana.com",
+            Explorer::Solscan => "https://solscan.io",
+            Explorer::SolanaFM => "https://solana.fm",
+        }
+    }
+
+    fn account_path(&self) -> &'static str {
+        match self {
+            Explorer::Explorer => "address",
+            Explorer::Solscan => "account",
+            Explorer::SolanaFM => "address",
+        }
+    }
+
+    /// Query string selecting the cluster, empty for the default cluster of the explorer.
+    fn cluster_query(&self, cluster: &Cluster) -> String {
+        match (self, cluster) {
+            (Explorer::SolanaFM, Cluster::Mainnet) => "?cluster=mainnet-alpha".to_string(),
+            (Explorer::SolanaFM, Cluster::Devnet) => "?cluster=devnet-alpha".to_string(),
+            (Explorer::SolanaFM, Cluster::Testnet) => "?cluster=testnet-solana".to_string(),
+            (Explorer::SolanaFM, Cluster::Custom(rpc_url)) => format!("?cluster=localnet-solana&customUrl={}", percent_encode(rpc_url)),
+            (_, Cluster::Mainnet) => String::new(),
+            (_, Cluster::Devnet) => "?cluster=devnet".to_string(),
+            (_, Cluster::Testnet) => "?cluster=testnet".to_string(),
+            (_, Cluster::Custom(rpc_url)) => format!("?cluster=custom&customUrl={}", percent_encode(rpc_url)),
+        }
+    }
+}
+
+/// Link to a transaction on `explorer`, accepting a `Signature` or its base58 string.
+///
+/// ## Example
+///
+/// ```rust
+/// use easy_solana::explorer::{explorer_url_for_signature, Cluster, Explorer};
+///
+/// let signature = "5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnbJLgp8uirBgmQpjKhoR4tjF3ZpRzrFmBV6UjKdiSZkQUW";
+/// let url = explorer_url_for_signature(signature, &Cluster::Devnet, Explorer::Solscan);
+/// assert!(url == format!("https://solscan.io/tx/{}?cluster=devnet", signature));
+/// ```
+pub fn explorer_url_for_signature(signature: impl Display, cluster: &Cluster, explorer: Explorer) -> String {
+    format!("{}/tx/{}{}", explorer.base_url(), signature, explorer.cluster_query(cluster))
+}
+
+/// Link to an account on `explorer`, accepting a `Pubkey` or its base58 string.
+pub fn explorer_url_for_account(address: impl Display, cluster: &Cluster, explorer: Explorer) -> String {
+    format!("{}/{}/{}{}", explorer.base_url(), explorer.account_path(), address, explorer.cluster_query(cluster))
+}
+
+/// Percent-encodes everything except unreserved URL characters.
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    const SIGNATURE: &str = "5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnbJLgp8uirBgmQpjKhoR4tjF3ZpRzrFmBV6UjKdiSZkQUW";
+
+    #[test]
+    fn test_explorer_url_for_signature() {
+        assert!(explorer_url_for_signature(SIGNATURE, &Cluster::Mainnet, Explorer::Explorer) == format!("https://explorer.solana.com/tx/{}", SIGNATURE));
+        assert!(explorer_url_for_signature(SIGNATURE, &Cluster::Testnet, Explorer::Explorer) == format!("https://explorer.solana.com/tx/{}?cluster=testnet", SIGNATURE));
+        assert!(explorer_url_for_signature(SIGNATURE, &Cluster::Mainnet, Explorer::Solscan) == format!("https://solscan.io/tx/{}", SIGNATURE));
+        assert!(explorer_url_for_signature(SIGNATURE, &Cluster::Devnet, Explorer::SolanaFM) == format!("https://solana.fm/tx/{}?cluster=devnet-alpha", SIGNATURE));
+    }
+
+    #[test]
+    fn test_explorer_url_for_account() {
+        let address = Pubkey::new_unique();
+        assert!(explorer_url_for_account(address, &Cluster::Devnet, Explorer::Explorer) == format!("https://explorer.solana.com/address/{}?cluster=devnet", address));
+        assert!(explorer_url_for_account(address, &Cluster::Mainnet, Explorer::Solscan) == format!("https://solscan.io/account/{}", address));
+        assert!(explorer_url_for_account(address, &Cluster::Mainnet, Explorer::SolanaFM) == format!("https://solana.fm/address/{}?cluster=mainnet-alpha", address));
+
+        let local_cluster = Cluster::Custom("http://localhost:8899".to_string());
+        assert!(
+            explorer_url_for_account(address, &local_cluster, Explorer::Explorer)
+                == format!("https://explorer.solana.com/address/{}?cluster=custom&customUrl=http%3A%2F%2Flocalhost%3A8899", address)
+        );
+    }
+
+    #[test]
+    fn test_cluster_from_rpc_url() {
+        assert!(Cluster::from_rpc_url("https://api.devnet.solana.com") == Cluster::Devnet);
+        assert!(Cluster::from_rpc_url("https://api.testnet.solana.com") == Cluster::Testnet);
+        assert!(Cluster::from_rpc_url("https://api.mainnet-beta.solana.com") == Cluster::Mainnet);
+        assert!(Cluster::from_rpc_url("http://127.0.0.1:8899") == Cluster::Custom("http://127.0.0.1:8899".to_string()));
+    }
+}
@@ -0,0 +1,512 @@
+//! # Indexer
+//!
+//! Embedded indexer of tracked pump.fun mints and wallets. Declared mints are backfilled with
+//! `HistoryBackfill`, then polling keeps the index up to date with new trades, the prices of
+//! the bonding curves and the SOL and tracked token balances of the wallets. Everything is kept
+//! in an `IndexerStore`, saved as JSON, which can be queried without hitting the RPC.
+//!
+//! Trades are read from pump.fun trade events, so trades after a token migrated are not indexed.
+//!
+//! ```rust,no_run
+//! use std::{sync::Arc, time::Duration};
+//! use easy_solana::{
+//!     create_rpc_client,
+//!     indexer::{Indexer, IndexerConfig},
+//!     supervisor::Supervisor
+//! };
+//!
+//! # async fn example() {
+//! let client = Arc::new(create_rpc_client("RPC_URL"));
+//! let config = IndexerConfig { store_path: Some("index.json".into()), ..Default::default() };
+//! let mut indexer = Indexer::new(config).unwrap();
+//! indexer.track_mint("CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump").unwrap();
+//! indexer.track_wallet("ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5").unwrap();
+//!
+//! let supervisor = Supervisor::new();
+//! let indexer = indexer.spawn(client, Duration::from_secs(10), supervisor.shutdown_signal());
+//! let store = indexer.lock().unwrap().store().clone();
+//! println!("{:?}", store.price("CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump"));
+//! # }
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration
+};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use spl_associated_token_account::get_associated_token_address;
+use crate::{
+    constants::pumpfun_accounts::PUMP_TOKEN_DECIMALS,
+    error::{IndexerError, PersistenceError},
+    pumpfun::{
+        bonding_curve::get_virtual_reserves,
+        curve_math::price_in_sol,
+        trade_event::trade_events_in_transaction
+    },
+    read_transactions::{
+        associated_token_account::get_token_account_amounts,
+        backfill::{fetch_transactions, signatures_since, BackfillCheckpoint, BackfillConfig, HistoryBackfill},
+        batch::MAX_MULTIPLE_ACCOUNTS,
+        trade_stats::{TokenTradeStats, Trade, TradeStatsAggregator},
+        transaction::InspectedTransaction
+    },
+    supervisor::ShutdownSignal
+};
+
+/// Options of an `Indexer`.
+///
+/// ### Fields
+///
+/// - `workers`: Number of transactions fetched in parallel while backfilling and polling.
+/// - `max_trades_per_mint`: Number of the latest trades kept for each mint.
+/// - `store_path`: JSON file the store is loaded from and saved to after every update.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexerConfig {
+    pub workers: usize,
+    pub max_trades_per_mint: usize,
+    pub store_path: Option<PathBuf>,
+}
+
+impl Default for IndexerConfig {
+    fn default() -> Self {
+        Self {
+            workers: 8,
+            max_trades_per_mint: 10_000,
+            store_path: None,
+        }
+    }
+}
+
+/// A pump.fun trade of an indexed mint.
+///
+/// ### Fields
+///
+/// - `signature`: Signature of the transaction of the trade.
+/// - `trader`: Wallet that traded.
+/// - `sol_amount`: SOL swapped in lamports, excluding fees.
+/// - `token_amount`: Tokens swapped without decimals.
+/// - `is_buy`: Whether the trader bought the token.
+/// - `timestamp`: Unix timestamp of the trade in seconds.
+/// - `price_in_sol`: Price of the token after the trade.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IndexedTrade {
+    pub signature: String,
+    pub trader: String,
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub is_buy: bool,
+    pub timestamp: i64,
+    pub price_in_sol: f64,
+}
+
+/// Balances of an indexed wallet.
+///
+/// ### Fields
+///
+/// - `lamports`: SOL balance in lamports.
+/// - `tokens`: Raw balances of the tracked mints by mint address, 0 without a token account.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct WalletBalances {
+    pub lamports: u64,
+    pub tokens: HashMap<String, u64>,
+}
+
+/// Indexed data of a mint.
+///
+/// ### Fields
+///
+/// - `trades`: Latest trades, oldest first.
+/// - `price_in_sol`: Latest price of the token, from its bonding curve or its latest trade.
+/// - `newest_signature`: Newest transaction read, polling continues after it.
+/// - `backfill`: Progress of the history backfill.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct MintIndex {
+    pub trades: Vec<IndexedTrade>,
+    pub price_in_sol: Option<f64>,
+    pub newest_signature: Option<String>,
+    pub backfill: BackfillCheckpoint,
+}
+
+impl MintIndex {
+    /// Adds the trades of `mint` in `transactions`, skipping transactions already indexed, and
+    /// keeps the latest `max_trades`.
+    fn insert_trades(&mut self, mint: &Pubkey, transactions: &[InspectedTransaction], max_trades: usize) {
+        let known_signatures: HashSet<String> = self.trades.iter().map(|trade| trade.signature.clone()).collect();
+        let new_trades = transactions
+            .iter()
+            .filter(|transaction| transaction.error.is_none() && !known_signatures.contains(&transaction.signature))
+            .flat_map(|transaction| {
+                trade_events_in_transaction(transaction)
+                    .into_iter()
+                    .filter(|trade_event| trade_event.mint == *mint)
+                    .map(|trade_event| IndexedTrade {
+                        signature: transaction.signature.clone(),
+                        trader: trade_event.user.to_string(),
+                        sol_amount: trade_event.sol_amount,
+                        token_amount: trade_event.token_amount,
+                        is_buy: trade_event.is_buy,
+                        timestamp: trade_event.timestamp,
+                        price_in_sol: price_in_sol(trade_event.virtual_sol_reserves as u128, trade_event.virtual_token_reserves as u128, PUMP_TOKEN_DECIMALS),
+                    })
+            });
+        self.trades.extend(new_trades);
+        self.trades.sort_by_key(|trade| trade.timestamp);
+        if self.trades.len() > max_trades {
+            self.trades.drain(..self.trades.len() - max_trades);
+        }
+        if self.price_in_sol.is_none() {
+            self.price_in_sol = self.trades.last().map(|trade| trade.price_in_sol);
+        }
+    }
+}
+
+/// Data of the indexed mints and wallets, by address.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct IndexerStore {
+    pub mints: HashMap<String, MintIndex>,
+    pub wallets: HashMap<String, WalletBalances>,
+}
+
+impl IndexerStore {
+    /// SOL balance of a wallet in lamports.
+    pub fn sol_balance(&self, wallet_address: &str) -> Option<u64> {
+        self.wallets.get(wallet_address).map(|balances| balances.lamports)
+    }
+
+    /// Raw balance of a tracked mint held by a wallet.
+    pub fn token_balance(&self, wallet_address: &str, mint_address: &str) -> Option<u64> {
+        self.wallets.get(wallet_address)?.tokens.get(mint_address).copied()
+    }
+
+    /// Latest price of a mint in SOL.
+    pub fn price(&self, mint_address: &str) -> Option<f64> {
+        self.mints.get(mint_address)?.price_in_sol
+    }
+
+    /// Latest trades of a mint, oldest first.
+    pub fn trades(&self, mint_address: &str) -> &[IndexedTrade] {
+        self.mints.get(mint_address).map(|mint_index| mint_index.trades.as_slice()).unwrap_or_default()
+    }
+
+    /// Trade stats of a mint over the hour and the day before `now`, from the indexed trades.
+    pub fn trade_stats(&self, mint_address: &str, now: i64) -> Option<TokenTradeStats> {
+        let mint = Pubkey::from_str(mint_address).ok()?;
+        let mut aggregator = TradeStatsAggregator::new(mint);
+        for trade in self.trades(mint_address) {
+            aggregator.push_trade(&Trade {
+                mint,
+                trader: Pubkey::from_str(&trade.trader).ok()?,
+                sol_amount: trade.sol_amount,
+                is_buy: trade.is_buy,
+                timestamp: trade.timestamp,
+            });
+        }
+        Some(aggregator.stats(now))
+    }
+
+    /// Writes the store to a JSON file. The file is replaced in one step, so a crash while
+    /// saving leaves the previous store rather than a partial file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PersistenceError> {
+        let json = serde_json::to_string(self)?;
+        let temporary_path = path.as_ref().with_extension("tmp");
+        fs::write(&temporary_path, json)?;
+        fs::rename(temporary_path, path)?;
+        Ok(())
+    }
+
+    /// Reads a store saved with `save`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PersistenceError> {
+        let json = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+/// Keeps an `IndexerStore` of tracked mints and wallets up to date.
+#[derive(Debug, Clone)]
+pub struct Indexer {
+    config: IndexerConfig,
+    store: IndexerStore,
+}
+
+impl Indexer {
+    /// Creates an indexer, loading the store of `config` if it exists.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `IndexerError::PersistenceError` if the store can not be read.
+    pub fn new(config: IndexerConfig) -> Result<Self, IndexerError> {
+        let store = match &config.store_path {
+            Some(path) if path.exists() => IndexerStore::load(path)?,
+            _ => IndexerStore::default(),
+        };
+        Ok(Self { config, store })
+    }
+
+    /// Indexed data, readable without RPC requests.
+    pub fn store(&self) -> &IndexerStore {
+        &self.store
+    }
+
+    /// Tracks the trades and price of a pump.fun mint. Mints already tracked are kept as is.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `IndexerError::InvalidAddress` if the address is invalid.
+    pub fn track_mint(&mut self, mint_address: &str) -> Result<(), IndexerError> {
+        let mint = Pubkey::from_str(mint_address)?.to_string();
+        let backfill = BackfillCheckpoint { address: mint.clone(), ..BackfillCheckpoint::default() };
+        self.store.mints.entry(mint).or_insert_with(|| MintIndex { backfill, ..MintIndex::default() });
+        Ok(())
+    }
+
+    /// Tracks the SOL balance of a wallet and its balances of the tracked mints.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `IndexerError::InvalidAddress` if the address is invalid.
+    pub fn track_wallet(&mut self, wallet_address: &str) -> Result<(), IndexerError> {
+        let wallet = Pubkey::from_str(wallet_address)?.to_string();
+        self.store.wallets.entry(wallet).or_default();
+        Ok(())
+    }
+
+    /// Backfills the trades of every tracked mint whose backfill is not complete. The progress
+    /// is saved with the store after every page, so an interrupted backfill resumes where it
+    /// stopped.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `IndexerError::BackfillError` if the history can not be read or the store saved.
+    pub fn backfill(&mut self, client: &RpcClient) -> Result<(), IndexerError> {
+        let backfill_config = BackfillConfig { workers: self.config.workers, ..BackfillConfig::default() };
+        let pending_mints: Vec<String> = self.store.mints
+            .iter()
+            .filter(|(_, mint_index)| !mint_index.backfill.complete)
+            .map(|(mint, _)| mint.clone())
+            .collect();
+
+        for mint_address in pending_mints {
+            let mint = Pubkey::from_str(&mint_address)?;
+            let checkpoint = self.store.mints[&mint_address].backfill.clone();
+            let mut backfill = HistoryBackfill::from_checkpoint(checkpoint, backfill_config.clone())?;
+            let (store, config) = (&mut self.store, &self.config);
+            // The saved checkpoint lags a page behind the trades, the page is deduplicated on resume
+            let result = backfill.run(client, |transactions| {
+                if let Some(mint_index) = store.mints.get_mut(&mint_address) {
+                    mint_index.insert_trades(&mint, &transactions, config.max_trades_per_mint);
+                }
+                if let Some(path) = &config.store_path {
+                    store.save(path)?;
+                }
+                Ok(())
+            });
+            if let Some(mint_index) = self.store.mints.get_mut(&mint_address) {
+                mint_index.newest_signature = mint_index.newest_signature.take().or_else(|| backfill.checkpoint().newest_signature.clone());
+                mint_index.backfill = backfill.checkpoint().clone();
+            }
+            self.persist()?;
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Reads the new trades and the prices of the tracked mints and the balances of the tracked
+    /// wallets, then saves the store.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `IndexerError::ReadError` if data can not be fetched, or
+    /// `IndexerError::PersistenceError` if the store can not be saved.
+    pub fn poll(&mut self, client: &RpcClient) -> Result<(), IndexerError> {
+        self.poll_trades(client)?;
+        self.poll_prices(client)?;
+        self.poll_balances(client)?;
+        self.persist()
+    }
+
+    /// Backfills the tracked mints, then polls every `interval` in the background until
+    /// `shutdown`. The indexer is shared so mints and wallets can be tracked and the store read
+    /// while it runs. Mints tracked later are backfilled on the next poll.
+    pub fn spawn(self, client: Arc<RpcClient>, interval: Duration, mut shutdown: ShutdownSignal) -> Arc<Mutex<Indexer>> {
+        let indexer = Arc::new(Mutex::new(self));
+        let task_indexer = Arc::clone(&indexer);
+
+        tokio::spawn(async move {
+            while !shutdown.is_shutdown() {
+                let poll_indexer = Arc::clone(&task_indexer);
+                let poll_client = Arc::clone(&client);
+                let result = tokio::task::spawn_blocking(move || {
+                    poll_indexer
+                        .lock()
+                        .map(|mut indexer| indexer.backfill(&poll_client).and_then(|_| indexer.poll(&poll_client)))
+                        .ok()
+                }).await;
+                match result {
+                    Ok(Some(Ok(()))) => {}
+                    Ok(Some(Err(err))) => warn!("Unable to update index: {}", err),
+                    _ => warn!("Indexer poll panicked"),
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = shutdown.cancelled() => {}
+                }
+            }
+        });
+
+        indexer
+    }
+
+    fn poll_trades(&mut self, client: &RpcClient) -> Result<(), IndexerError> {
+        for (mint_address, mint_index) in self.store.mints.iter_mut() {
+            let mint = Pubkey::from_str(mint_address)?;
+            let until = mint_index.newest_signature.as_deref();
+            let (newest_signature, signatures) = signatures_since(client, &mint, until)?;
+            let signatures: Vec<&str> = signatures.iter().map(String::as_str).collect();
+            let transactions = fetch_transactions(client, &signatures, self.config.workers)?;
+            mint_index.insert_trades(&mint, &transactions, self.config.max_trades_per_mint);
+            if newest_signature.is_some() {
+                mint_index.newest_signature = newest_signature;
+            }
+        }
+        Ok(())
+    }
+
+    fn poll_prices(&mut self, client: &RpcClient) -> Result<(), IndexerError> {
+        let mint_addresses: Vec<String> = self.store.mints.keys().cloned().collect();
+        for chunk in mint_addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let reserves = get_virtual_reserves(client, chunk.iter().map(String::as_str).collect())?;
+            for (mint_address, reserves) in chunk.iter().zip(reserves) {
+                // Migrated tokens keep the last price of their curve
+                let Some(price) = reserves.and_then(|reserves| reserves.price_in_sol().ok()) else {
+                    continue;
+                };
+                if let Some(mint_index) = self.store.mints.get_mut(mint_address) {
+                    mint_index.price_in_sol = Some(price);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn poll_balances(&mut self, client: &RpcClient) -> Result<(), IndexerError> {
+        let wallets = self.store.wallets.keys().map(|wallet| Pubkey::from_str(wallet)).collect::<Result<Vec<Pubkey>, _>>()?;
+        for chunk in wallets.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let accounts = client.get_multiple_accounts(chunk)?;
+            for (wallet, account) in chunk.iter().zip(accounts) {
+                if let Some(balances) = self.store.wallets.get_mut(&wallet.to_string()) {
+                    balances.lamports = account.map(|account| account.lamports).unwrap_or_default();
+                }
+            }
+        }
+
+        let mints = self.store.mints.keys().map(|mint| Pubkey::from_str(mint)).collect::<Result<Vec<Pubkey>, _>>()?;
+        let holdings: Vec<(Pubkey, Pubkey, String)> = wallets
+            .iter()
+            .flat_map(|wallet| mints.iter().map(move |mint| (*wallet, *mint, get_associated_token_address(wallet, mint).to_string())))
+            .collect();
+        for chunk in holdings.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let amounts = get_token_account_amounts(client, chunk.iter().map(|(_, _, token_account)| token_account.as_str()).collect())?;
+            for ((wallet, mint, _), amount) in chunk.iter().zip(amounts) {
+                if let Some(balances) = self.store.wallets.get_mut(&wallet.to_string()) {
+                    balances.tokens.insert(mint.to_string(), amount.unwrap_or_default());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn persist(&self) -> Result<(), IndexerError> {
+        if let Some(path) = &self.config.store_path {
+            self.store.save(path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{prelude::BASE64_STANDARD, Engine};
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::{hash::Hash, signature::Signature, signer::{keypair::Keypair, Signer}, transaction::Transaction};
+    use solana_system_interface::instruction::transfer;
+    use crate::{
+        constants::pumpfun_accounts::pumpfun_program,
+        constants::pumpfun_accounts::TRADE_EVENT_DISCRIMINATOR,
+        pumpfun::trade_event::TradeEvent,
+        test_utils::{account_with_data, mock_client, multiple_accounts_response, signatures_for_address_response, transaction_response}
+    };
+
+    fn trade_transaction_response(mint: Pubkey, trader: Pubkey, timestamp: i64) -> serde_json::Value {
+        let trade_event = TradeEvent {
+            mint,
+            sol_amount: 1_000_000_000,
+            token_amount: 30_000_000_000_000,
+            is_buy: true,
+            user: trader,
+            timestamp,
+            virtual_sol_reserves: 31_000_000_000,
+            virtual_token_reserves: 1_043_000_000_000_000,
+        };
+        let mut event_data = TRADE_EVENT_DISCRIMINATOR.to_vec();
+        event_data.extend(borsh::to_vec(&trade_event).unwrap());
+        let payer = Keypair::new();
+        let transaction = Transaction::new_signed_with_payer(
+            &[transfer(&payer.pubkey(), &Pubkey::new_unique(), 1_000)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default()
+        );
+        transaction_response(&transaction, 1, Some(timestamp), &[format!("Program data: {}", BASE64_STANDARD.encode(event_data))])
+    }
+
+    #[test]
+    fn test_indexer_backfills_and_polls() {
+        let path = std::env::temp_dir().join(format!("easy_solana_index_{}.json", Pubkey::new_unique()));
+        let (mint, wallet, trader) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let signatures: Vec<String> = (0..3).map(|_| Signature::new_unique().to_string()).collect();
+        let mut curve_reserves = 40_000_000_000_u64.to_le_bytes().to_vec();
+        curve_reserves.extend_from_slice(&40_000_000_000_u64.to_le_bytes());
+
+        let client = mock_client(vec![
+            // backfill of the two existing trades
+            (RpcRequest::GetSignaturesForAddress, signatures_for_address_response(&[(&signatures[1], 20, Some(200), false), (&signatures[2], 10, Some(100), false)])),
+            (RpcRequest::GetTransaction, trade_transaction_response(mint, trader, 200)),
+            (RpcRequest::GetTransaction, trade_transaction_response(mint, trader, 100)),
+            // a new trade, then the prices and balances
+            (RpcRequest::GetSignaturesForAddress, signatures_for_address_response(&[(&signatures[0], 30, Some(300), false)])),
+            (RpcRequest::GetTransaction, trade_transaction_response(mint, trader, 300)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(account_with_data(pumpfun_program(), curve_reserves))], 30)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(account_with_data(solana_sdk::system_program::id(), vec![]))], 30)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(account_with_data(Pubkey::new_unique(), 7_u64.to_le_bytes().to_vec()))], 30)),
+        ]);
+
+        let config = IndexerConfig { workers: 2, store_path: Some(path.clone()), ..Default::default() };
+        let mut indexer = Indexer::new(config.clone()).unwrap();
+        indexer.track_mint(&mint.to_string()).unwrap();
+        indexer.track_wallet(&wallet.to_string()).unwrap();
+        indexer.backfill(&client).unwrap();
+        let mint_index = &indexer.store().mints[&mint.to_string()];
+        assert!(mint_index.backfill.complete && mint_index.newest_signature.as_ref() == Some(&signatures[1]));
+        assert!(mint_index.price_in_sol.is_some());
+
+        indexer.poll(&client).unwrap();
+        // the store is reloaded from its file
+        let store = Indexer::new(config).unwrap().store().clone();
+        let trades = store.trades(&mint.to_string());
+        assert!(trades.iter().map(|trade| trade.timestamp).collect::<Vec<i64>>() == vec![100, 200, 300]);
+        assert!(trades[2].signature == signatures[0] && trades[2].trader == trader.to_string());
+        assert!(store.price(&mint.to_string()) == Some(price_in_sol(40_000_000_000, 40_000_000_000, PUMP_TOKEN_DECIMALS)));
+        assert!(store.sol_balance(&wallet.to_string()) == Some(1_000_000));
+        assert!(store.token_balance(&wallet.to_string(), &mint.to_string()) == Some(7));
+        assert!(store.trade_stats(&mint.to_string(), 300).unwrap().last_hour.trades == 3);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,171 @@
+//! # JSON
+//!
+//! Structured JSON output of simulation results, send results and reports, so pipelines
+//! written in other languages can ingest them directly.
+//!
+//! The schema is stable across releases, new fields may be added but existing fields are not
+//! renamed or removed:
+//!
+//! - Field names are the snake_case names of the Rust fields.
+//! - Public keys and signatures are base58 strings.
+//! - Amounts are integers in lamports or raw token units.
+//! - Durations are integers in milliseconds.
+//! - Instruction data is a base58 string.
+//! - Missing optional values are `null`.
+//! - Transaction errors use the JSON representation of the Solana RPC, e.g
+//!   `{"InstructionError": [0, {"Custom": 1}]}`.
+//! - Enums are objects with a snake_case `type` and their fields under `details`.
+
+use std::{fmt::Display, time::Duration};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use solana_sdk::bs58;
+
+/// Types with a stable JSON representation.
+///
+/// ## Example
+///
+/// ```rust
+/// use solana_sdk::signature::Signature;
+/// use easy_solana::{json::ToJson, ConfirmedTransaction};
+///
+/// let confirmed_transaction = ConfirmedTransaction {
+///     signature: Signature::default(),
+///     slot: 1,
+///     fee: Some(5_000),
+///     compute_units: Some(150),
+///     err: None,
+/// };
+/// let json = confirmed_transaction.to_json();
+/// assert!(json["signature"] == Signature::default().to_string());
+/// assert!(json["fee"] == 5_000);
+/// ```
+pub trait ToJson: Serialize {
+    fn to_json(&self) -> Value {
+        // the types implementing ToJson only contain string map keys, so serializing never fails
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+
+    fn to_json_string(&self) -> String {
+        self.to_json().to_string()
+    }
+}
+
+pub(crate) fn serialize_display<T: Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+pub(crate) fn serialize_display_option<T: Display, S: Serializer>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => serializer.collect_str(value),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub(crate) fn serialize_display_vec<T: Display, S: Serializer>(values: &[T], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(values.iter().map(|value| value.to_string()))
+}
+
+pub(crate) fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
+
+pub(crate) fn serialize_millis_option<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => serialize_millis(duration, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub(crate) fn serialize_base58<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&bs58::encode(data).into_string())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_sdk::{pubkey::Pubkey, signature::Signature, transaction::TransactionError};
+    use crate::{
+        read_transactions::cpi_tree::CpiNode,
+        write_transactions::{
+            preview::{PreviewAction, TransactionPreview},
+            utils::{AggressiveSendResult, ConfirmedTransaction, EndpointSubmissions, ParsedInstruction, SimulationResult}
+        }
+    };
+
+    #[test]
+    fn test_confirmed_transaction_json() {
+        let confirmed_transaction = ConfirmedTransaction {
+            signature: Signature::default(),
+            slot: 10,
+            fee: None,
+            compute_units: Some(150),
+            err: Some(TransactionError::AccountNotFound),
+        };
+        assert!(confirmed_transaction.to_json() == json!({
+            "signature": Signature::default().to_string(),
+            "slot": 10,
+            "fee": null,
+            "compute_units": 150,
+            "err": "AccountNotFound",
+        }));
+    }
+
+    #[test]
+    fn test_simulation_result_json() {
+        let (program_id, account) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let simulation_result = SimulationResult {
+            transaction_logs: vec!["Program log: ok".to_string()],
+            units_consumed: 150,
+            instructions: vec![ParsedInstruction {
+                program: "system".to_string(),
+                program_id: program_id.to_string(),
+                info: json!({ "lamports": 1 }).as_object().unwrap().clone(),
+            }],
+            cpi_tree: vec![CpiNode { program_id, accounts: vec![account], data: vec![1, 2, 3], depth: 1, children: vec![] }],
+            error: None,
+        };
+        let json = simulation_result.to_json();
+        assert!(json["units_consumed"] == 150 && json["error"].is_null());
+        assert!(json["instructions"][0]["info"]["lamports"] == 1);
+        assert!(json["cpi_tree"][0] == json!({
+            "program_id": program_id.to_string(),
+            "accounts": [account.to_string()],
+            "data": bs58::encode([1, 2, 3]).into_string(),
+            "depth": 1,
+            "children": [],
+        }));
+    }
+
+    #[test]
+    fn test_reports_json() {
+        let aggressive_send_result = AggressiveSendResult {
+            signature: Signature::default(),
+            slot: 1,
+            landed_endpoint: None,
+            endpoints: vec![EndpointSubmissions {
+                url: "http://localhost:8899".to_string(),
+                submissions: 2,
+                errors: 0,
+                first_accepted_after: Some(Duration::from_millis(120)),
+            }],
+            elapsed: Duration::from_secs(2),
+        };
+        let json = aggressive_send_result.to_json();
+        assert!(json["elapsed"] == 2_000 && json["endpoints"][0]["first_accepted_after"] == 120);
+
+        let destination = Pubkey::new_unique();
+        let preview = TransactionPreview {
+            fee_payer: None,
+            actions: vec![
+                PreviewAction::TransferSol { from: destination, to: destination, lamports: 5 },
+                PreviewAction::SetComputeLimit(200_000),
+            ],
+        };
+        let json = preview.to_json();
+        assert!(json["actions"][0]["type"] == "transfer_sol" && json["actions"][0]["details"]["to"] == destination.to_string());
+        assert!(json["actions"][1] == json!({ "type": "set_compute_limit", "details": 200_000 }));
+    }
+}
@@ -0,0 +1,206 @@
+//! # Kamino
+//!
+//! Decodes Kamino lending obligations. An obligation holds up to 8 deposits of collateral
+//! tokens and 5 borrows, each in a reserve of one lending market, with the USD value recorded
+//! at the last refresh of the obligation.
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use crate::{
+    constants::kamino_accounts::{
+        kamino_lending_program, OBLIGATION_BORROWS_OFFSET, OBLIGATION_BORROW_SIZE, OBLIGATION_DEPOSIT_SIZE,
+        OBLIGATION_DISCRIMINATOR, OBLIGATION_MAX_BORROWS, OBLIGATION_MAX_DEPOSITS, OBLIGATION_OWNER_OFFSET,
+        RESERVE_DISCRIMINATOR, RESERVE_MINT_DECIMALS_OFFSET, RESERVE_MINT_OFFSET
+    },
+    error::ReadTransactionError
+};
+use super::{
+    utils::{get_owned_program_accounts, read_i128, read_pubkey, read_u64},
+    LendingBalance, LendingPosition, LendingProtocol
+};
+
+/// Scaled fractions of Kamino have 60 fractional bits.
+const SCALED_FRACTION_ONE: f64 = (1_u64 << 60) as f64;
+
+/// Tokens deposited to or borrowed from a reserve by an obligation.
+///
+/// ### Fields
+///
+/// - `reserve`: Reserve of the tokens.
+/// - `amount`: Collateral tokens deposited, or liquidity tokens borrowed, without decimals.
+/// - `market_value_usd`: Value in USD at the last refresh of the obligation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObligationBalance {
+    pub reserve: Pubkey,
+    pub amount: f64,
+    pub market_value_usd: f64,
+}
+
+/// A Kamino obligation with its active deposits and borrows.
+///
+/// ### Fields
+///
+/// - `lending_market`: Lending market of the obligation.
+/// - `owner`: Wallet owning the obligation.
+/// - `deposits`: Collateral deposited.
+/// - `borrows`: Liquidity borrowed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Obligation {
+    pub lending_market: Pubkey,
+    pub owner: Pubkey,
+    pub deposits: Vec<ObligationBalance>,
+    pub borrows: Vec<ObligationBalance>,
+}
+
+impl Obligation {
+    /// Deserializes a Kamino obligation.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::WrongAccountType` if the discriminator does not match,
+    /// or `ReadTransactionError::DeserializeError` if the data is too short.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, ReadTransactionError> {
+        if data.get(..8) != Some(&OBLIGATION_DISCRIMINATOR[..]) {
+            return Err(ReadTransactionError::WrongAccountType("kamino obligation".to_string()));
+        }
+        if data.len() < OBLIGATION_BORROWS_OFFSET + OBLIGATION_BORROW_SIZE * OBLIGATION_MAX_BORROWS {
+            return Err(ReadTransactionError::DeserializeError);
+        }
+        let deposits_offset = OBLIGATION_OWNER_OFFSET + 32;
+        let deposits = data[deposits_offset..deposits_offset + OBLIGATION_DEPOSIT_SIZE * OBLIGATION_MAX_DEPOSITS]
+            .chunks_exact(OBLIGATION_DEPOSIT_SIZE)
+            .filter_map(|deposit| {
+                Some(ObligationBalance {
+                    reserve: read_pubkey(deposit, 0).filter(|reserve| *reserve != Pubkey::default())?,
+                    amount: read_u64(deposit, 32)? as f64,
+                    market_value_usd: read_i128(deposit, 40)? as f64 / SCALED_FRACTION_ONE,
+                })
+            })
+            .collect();
+        let borrows = data[OBLIGATION_BORROWS_OFFSET..OBLIGATION_BORROWS_OFFSET + OBLIGATION_BORROW_SIZE * OBLIGATION_MAX_BORROWS]
+            .chunks_exact(OBLIGATION_BORROW_SIZE)
+            .filter_map(|borrow| {
+                Some(ObligationBalance {
+                    reserve: read_pubkey(borrow, 0).filter(|reserve| *reserve != Pubkey::default())?,
+                    amount: read_i128(borrow, 88)? as f64 / SCALED_FRACTION_ONE,
+                    market_value_usd: read_i128(borrow, 104)? as f64 / SCALED_FRACTION_ONE,
+                })
+            })
+            .collect();
+
+        Ok(Obligation {
+            lending_market: read_pubkey(data, 32).ok_or(ReadTransactionError::DeserializeError)?,
+            owner: read_pubkey(data, OBLIGATION_OWNER_OFFSET).ok_or(ReadTransactionError::DeserializeError)?,
+            deposits,
+            borrows,
+        })
+    }
+}
+
+/// Gets the Kamino positions of a wallet, one per obligation.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::RpcError` if the obligations or their reserves can not be fetched.
+pub fn get_kamino_positions(client: &RpcClient, wallet: &Pubkey) -> Result<Vec<LendingPosition>, ReadTransactionError> {
+    let obligations: Vec<(Pubkey, Obligation)> = get_owned_program_accounts(
+        client,
+        &kamino_lending_program(),
+        &OBLIGATION_DISCRIMINATOR,
+        wallet,
+        OBLIGATION_OWNER_OFFSET,
+    )?
+        .into_iter()
+        .filter_map(|(pubkey, account)| Some((pubkey, Obligation::try_from_account_data(&account.data).ok()?)))
+        .collect();
+
+    let mut reserves: Vec<Pubkey> = Vec::new();
+    for balance in obligations.iter().flat_map(|(_, obligation)| obligation.deposits.iter().chain(&obligation.borrows)) {
+        if !reserves.contains(&balance.reserve) {
+            reserves.push(balance.reserve);
+        }
+    }
+    // Mint and decimals of the liquidity of each reserve, collateral tokens have the same decimals
+    let reserve_mints: Vec<Option<(Pubkey, u8)>> = if reserves.is_empty() {
+        vec![]
+    } else {
+        client.get_multiple_accounts(&reserves)?
+            .into_iter()
+            .map(|account| {
+                let data = account?.data;
+                if data.get(..8) != Some(&RESERVE_DISCRIMINATOR[..]) {
+                    return None;
+                }
+                Some((read_pubkey(&data, RESERVE_MINT_OFFSET)?, read_u64(&data, RESERVE_MINT_DECIMALS_OFFSET)? as u8))
+            })
+            .collect()
+    };
+    let lending_balance = |balance: &ObligationBalance| {
+        let mint = reserves.iter().position(|reserve| *reserve == balance.reserve).and_then(|index| reserve_mints[index]);
+        LendingBalance {
+            reserve: balance.reserve,
+            mint: mint.map(|(mint, _)| mint),
+            amount: balance.amount,
+            ui_amount: mint.map(|(_, decimals)| balance.amount / 10_f64.powi(decimals as i32)),
+            market_value_usd: Some(balance.market_value_usd),
+        }
+    };
+
+    Ok(obligations
+        .iter()
+        .map(|(pubkey, obligation)| LendingPosition {
+            protocol: LendingProtocol::Kamino,
+            account: *pubkey,
+            owner: obligation.owner,
+            market: obligation.lending_market,
+            supplied: obligation.deposits.iter().map(lending_balance).collect(),
+            borrowed: obligation.borrows.iter().map(lending_balance).collect(),
+        })
+        .collect())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use crate::test_utils::{account_with_data, mock_client, multiple_accounts_response, program_accounts_response};
+
+    fn scaled_fraction(value: f64) -> [u8; 16] {
+        ((value * SCALED_FRACTION_ONE) as i128).to_le_bytes()
+    }
+
+    #[test]
+    fn test_get_kamino_positions() {
+        let (wallet, market, reserve, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = vec![0; OBLIGATION_BORROWS_OFFSET + OBLIGATION_BORROW_SIZE * OBLIGATION_MAX_BORROWS];
+        data[..8].copy_from_slice(&OBLIGATION_DISCRIMINATOR);
+        data[32..64].copy_from_slice(market.as_ref());
+        data[64..96].copy_from_slice(wallet.as_ref());
+        data[96..128].copy_from_slice(reserve.as_ref());
+        data[128..136].copy_from_slice(&2_000_000_u64.to_le_bytes());
+        data[136..152].copy_from_slice(&scaled_fraction(310.5));
+        let borrow = OBLIGATION_BORROWS_OFFSET;
+        data[borrow..borrow + 32].copy_from_slice(reserve.as_ref());
+        data[borrow + 88..borrow + 104].copy_from_slice(&scaled_fraction(500_000.0));
+        data[borrow + 104..borrow + 120].copy_from_slice(&scaled_fraction(75.25));
+
+        let mut reserve_data = vec![0; RESERVE_MINT_DECIMALS_OFFSET + 8];
+        reserve_data[..8].copy_from_slice(&RESERVE_DISCRIMINATOR);
+        reserve_data[RESERVE_MINT_OFFSET..RESERVE_MINT_OFFSET + 32].copy_from_slice(mint.as_ref());
+        reserve_data[RESERVE_MINT_DECIMALS_OFFSET] = 6;
+
+        let obligation = Pubkey::new_unique();
+        let client = mock_client(vec![
+            (RpcRequest::GetProgramAccounts, program_accounts_response(&[(obligation, account_with_data(kamino_lending_program(), data))])),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(account_with_data(kamino_lending_program(), reserve_data))], 1)),
+        ]);
+
+        let positions = get_kamino_positions(&client, &wallet).unwrap();
+        assert!(positions.len() == 1 && positions[0].market == market && positions[0].owner == wallet);
+        let (supplied, borrowed) = (&positions[0].supplied, &positions[0].borrowed);
+        assert!(supplied.len() == 1 && supplied[0].ui_amount == Some(2.0) && supplied[0].market_value_usd == Some(310.5));
+        assert!(borrowed.len() == 1 && borrowed[0].mint == Some(mint) && borrowed[0].ui_amount == Some(0.5));
+        assert!(borrowed[0].market_value_usd == Some(75.25));
+    }
+}
@@ -0,0 +1,241 @@
+//! # Marginfi
+//!
+//! Decodes marginfi v2 accounts. A marginfi account holds up to 16 balances, each counting
+//! asset and liability shares of a bank. Shares are converted to tokens with the share values
+//! of the bank, which grow as interest accrues.
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use crate::{
+    constants::marginfi_accounts::{
+        marginfi_program, BANK_DISCRIMINATOR, MARGINFI_ACCOUNT_AUTHORITY_OFFSET, MARGINFI_ACCOUNT_DISCRIMINATOR,
+        MARGINFI_BALANCE_SIZE, MARGINFI_MAX_BALANCES
+    },
+    error::ReadTransactionError
+};
+use super::{
+    utils::{get_owned_program_accounts, read_i128, read_pubkey},
+    LendingBalance, LendingPosition, LendingProtocol
+};
+
+/// Fixed point numbers of marginfi have 48 fractional bits.
+const I80F48_ONE: f64 = (1_u64 << 48) as f64;
+
+/// An active balance of a marginfi account.
+///
+/// ### Fields
+///
+/// - `bank`: Bank of the balance.
+/// - `asset_shares`: Shares of the tokens supplied to the bank.
+/// - `liability_shares`: Shares of the tokens borrowed from the bank.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarginfiBalance {
+    pub bank: Pubkey,
+    pub asset_shares: f64,
+    pub liability_shares: f64,
+}
+
+/// A marginfi account with its active balances.
+///
+/// ### Fields
+///
+/// - `group`: Marginfi group of the account.
+/// - `authority`: Wallet owning the account.
+/// - `balances`: Active balances of the account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarginfiAccount {
+    pub group: Pubkey,
+    pub authority: Pubkey,
+    pub balances: Vec<MarginfiBalance>,
+}
+
+impl MarginfiAccount {
+    /// Deserializes a marginfi account.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::WrongAccountType` if the discriminator does not match,
+    /// or `ReadTransactionError::DeserializeError` if the data is too short.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, ReadTransactionError> {
+        if data.get(..8) != Some(&MARGINFI_ACCOUNT_DISCRIMINATOR[..]) {
+            return Err(ReadTransactionError::WrongAccountType("marginfi account".to_string()));
+        }
+        let balances_offset = MARGINFI_ACCOUNT_AUTHORITY_OFFSET + 32;
+        if data.len() < balances_offset + MARGINFI_BALANCE_SIZE * MARGINFI_MAX_BALANCES {
+            return Err(ReadTransactionError::DeserializeError);
+        }
+        let balances = data[balances_offset..]
+            .chunks_exact(MARGINFI_BALANCE_SIZE)
+            .take(MARGINFI_MAX_BALANCES)
+            .filter(|balance| balance[0] != 0)
+            .map(|balance| {
+                Some(MarginfiBalance {
+                    bank: read_pubkey(balance, 1)?,
+                    asset_shares: read_i128(balance, 40)? as f64 / I80F48_ONE,
+                    liability_shares: read_i128(balance, 56)? as f64 / I80F48_ONE,
+                })
+            })
+            .collect::<Option<Vec<MarginfiBalance>>>()
+            .ok_or(ReadTransactionError::DeserializeError)?;
+
+        Ok(MarginfiAccount {
+            group: read_pubkey(data, 8).ok_or(ReadTransactionError::DeserializeError)?,
+            authority: read_pubkey(data, MARGINFI_ACCOUNT_AUTHORITY_OFFSET).ok_or(ReadTransactionError::DeserializeError)?,
+            balances,
+        })
+    }
+}
+
+/// The fields of a marginfi bank needed to value balances.
+///
+/// ### Fields
+///
+/// - `mint`: Mint of the bank.
+/// - `mint_decimals`: Decimals of the mint.
+/// - `asset_share_value`: Tokens per asset share.
+/// - `liability_share_value`: Tokens per liability share.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarginfiBank {
+    pub mint: Pubkey,
+    pub mint_decimals: u8,
+    pub asset_share_value: f64,
+    pub liability_share_value: f64,
+}
+
+impl MarginfiBank {
+    /// Deserializes the head of a marginfi bank.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::WrongAccountType` if the discriminator does not match,
+    /// or `ReadTransactionError::DeserializeError` if the data is too short.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, ReadTransactionError> {
+        if data.get(..8) != Some(&BANK_DISCRIMINATOR[..]) {
+            return Err(ReadTransactionError::WrongAccountType("marginfi bank".to_string()));
+        }
+        let bank = || Some(MarginfiBank {
+            mint: read_pubkey(data, 8)?,
+            mint_decimals: *data.get(40)?,
+            asset_share_value: read_i128(data, 80)? as f64 / I80F48_ONE,
+            liability_share_value: read_i128(data, 96)? as f64 / I80F48_ONE,
+        });
+        bank().ok_or(ReadTransactionError::DeserializeError)
+    }
+}
+
+/// Gets the marginfi positions of a wallet, one per marginfi account.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::RpcError` if the accounts or their banks can not be fetched.
+pub fn get_marginfi_positions(client: &RpcClient, wallet: &Pubkey) -> Result<Vec<LendingPosition>, ReadTransactionError> {
+    let accounts: Vec<(Pubkey, MarginfiAccount)> = get_owned_program_accounts(
+        client,
+        &marginfi_program(),
+        &MARGINFI_ACCOUNT_DISCRIMINATOR,
+        wallet,
+        MARGINFI_ACCOUNT_AUTHORITY_OFFSET,
+    )?
+        .into_iter()
+        .filter_map(|(pubkey, account)| Some((pubkey, MarginfiAccount::try_from_account_data(&account.data).ok()?)))
+        .collect();
+
+    let mut banks: Vec<Pubkey> = Vec::new();
+    for balance in accounts.iter().flat_map(|(_, account)| &account.balances) {
+        if !banks.contains(&balance.bank) {
+            banks.push(balance.bank);
+        }
+    }
+    let bank_states: Vec<Option<MarginfiBank>> = if banks.is_empty() {
+        vec![]
+    } else {
+        client.get_multiple_accounts(&banks)?
+            .into_iter()
+            .map(|account| MarginfiBank::try_from_account_data(&account?.data).ok())
+            .collect()
+    };
+    let bank_state = |bank: &Pubkey| banks.iter().position(|known| known == bank).and_then(|index| bank_states[index].as_ref());
+
+    Ok(accounts
+        .into_iter()
+        .map(|(pubkey, account)| {
+            let mut supplied = Vec::new();
+            let mut borrowed = Vec::new();
+            for balance in &account.balances {
+                let state = bank_state(&balance.bank);
+                if balance.asset_shares > 0.0 {
+                    supplied.push(lending_balance(balance.bank, balance.asset_shares, state, |state| state.asset_share_value));
+                }
+                if balance.liability_shares > 0.0 {
+                    borrowed.push(lending_balance(balance.bank, balance.liability_shares, state, |state| state.liability_share_value));
+                }
+            }
+            LendingPosition {
+                protocol: LendingProtocol::Marginfi,
+                account: pubkey,
+                owner: account.authority,
+                market: account.group,
+                supplied,
+                borrowed,
+            }
+        })
+        .collect())
+}
+
+/// Converts shares to tokens, leaving the shares as the amount if the bank could not be loaded.
+fn lending_balance(bank: Pubkey, shares: f64, state: Option<&MarginfiBank>, share_value: fn(&MarginfiBank) -> f64) -> LendingBalance {
+    let amount = state.map(|state| shares * share_value(state)).unwrap_or(shares);
+    LendingBalance {
+        reserve: bank,
+        mint: state.map(|state| state.mint),
+        amount,
+        ui_amount: state.map(|state| amount / 10_f64.powi(state.mint_decimals as i32)),
+        market_value_usd: None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use crate::test_utils::{account_with_data, mock_client, multiple_accounts_response, program_accounts_response};
+
+    fn i80f48(value: f64) -> [u8; 16] {
+        ((value * I80F48_ONE) as i128).to_le_bytes()
+    }
+
+    #[test]
+    fn test_get_marginfi_positions() {
+        let (wallet, group, bank, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut account_data = MARGINFI_ACCOUNT_DISCRIMINATOR.to_vec();
+        account_data.extend_from_slice(group.as_ref());
+        account_data.extend_from_slice(wallet.as_ref());
+        let mut balance = vec![0; MARGINFI_BALANCE_SIZE];
+        balance[0] = 1;
+        balance[1..33].copy_from_slice(bank.as_ref());
+        balance[40..56].copy_from_slice(&i80f48(1_000.0));
+        balance[56..72].copy_from_slice(&i80f48(250.0));
+        account_data.extend(balance);
+        account_data.extend(vec![0; MARGINFI_BALANCE_SIZE * (MARGINFI_MAX_BALANCES - 1)]);
+
+        let mut bank_data = BANK_DISCRIMINATOR.to_vec();
+        bank_data.extend_from_slice(mint.as_ref());
+        bank_data.push(3);
+        bank_data.extend(vec![0; 39]);
+        bank_data.extend_from_slice(&i80f48(1.5));
+        bank_data.extend_from_slice(&i80f48(2.0));
+
+        let marginfi_account = Pubkey::new_unique();
+        let client = mock_client(vec![
+            (RpcRequest::GetProgramAccounts, program_accounts_response(&[(marginfi_account, account_with_data(marginfi_program(), account_data))])),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(account_with_data(marginfi_program(), bank_data))], 1)),
+        ]);
+
+        let positions = get_marginfi_positions(&client, &wallet).unwrap();
+        assert!(positions.len() == 1 && positions[0].account == marginfi_account && positions[0].market == group);
+        let (supplied, borrowed) = (&positions[0].supplied[0], &positions[0].borrowed[0]);
+        assert!(supplied.mint == Some(mint) && supplied.amount == 1_500.0 && supplied.ui_amount == Some(1.5));
+        assert!(borrowed.reserve == bank && borrowed.amount == 500.0 && borrowed.ui_amount == Some(0.5));
+    }
+}
@@ -0,0 +1,92 @@
+//! # Lending
+//!
+//! Reads the supplied and borrowed positions of a wallet in lending markets, so portfolio
+//! reports can include more than wallet balances. Each protocol sits behind its own feature,
+//! `marginfi` and `kamino`, and `get_lending_positions` reads every enabled protocol.
+
+#[cfg(feature = "kamino")]
+pub mod kamino;
+#[cfg(feature = "marginfi")]
+pub mod marginfi;
+#[cfg(any(feature = "marginfi", feature = "kamino"))]
+mod utils;
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use crate::error::ReadTransactionError;
+
+/// Lending protocol of a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LendingProtocol {
+    Marginfi,
+    Kamino,
+}
+
+/// Tokens supplied to or borrowed from one bank or reserve of a lending market.
+///
+/// ### Fields
+///
+/// - `reserve`: Marginfi bank or Kamino reserve holding the tokens.
+/// - `mint`: Mint of the tokens, `None` if the reserve could not be loaded.
+/// - `amount`: Tokens without decimals, fractional as interest accrues on shares.
+/// - `ui_amount`: Tokens with decimals applied, `None` if the reserve could not be loaded.
+/// - `market_value_usd`: Value in USD last recorded by the protocol, if the protocol records it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LendingBalance {
+    pub reserve: Pubkey,
+    pub mint: Option<Pubkey>,
+    pub amount: f64,
+    pub ui_amount: Option<f64>,
+    pub market_value_usd: Option<f64>,
+}
+
+/// Position of a wallet in a lending market.
+///
+/// ### Fields
+///
+/// - `protocol`: Lending protocol of the position.
+/// - `account`: Marginfi account or Kamino obligation holding the position.
+/// - `owner`: Wallet owning the position.
+/// - `market`: Marginfi group or Kamino lending market of the position.
+/// - `supplied`: Tokens supplied, Kamino amounts are collateral tokens of the reserve.
+/// - `borrowed`: Tokens borrowed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LendingPosition {
+    pub protocol: LendingProtocol,
+    pub account: Pubkey,
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub supplied: Vec<LendingBalance>,
+    pub borrowed: Vec<LendingBalance>,
+}
+
+/// Gets the positions of a wallet in every lending protocol enabled by features. Without a
+/// lending feature enabled no positions are returned.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InvalidAddress` if the wallet is invalid, or
+/// `ReadTransactionError::RpcError` if the accounts can not be fetched.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_client::rpc_client::RpcClient;
+/// use easy_solana::lending::get_lending_positions;
+///
+/// let client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+/// let positions = get_lending_positions(&client, "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5").unwrap();
+/// for position in positions {
+///     println!("{:?}: {} supplied, {} borrowed", position.protocol, position.supplied.len(), position.borrowed.len());
+/// }
+/// ```
+#[allow(unused_mut, unused_variables)]
+pub fn get_lending_positions(client: &RpcClient, wallet: &str) -> Result<Vec<LendingPosition>, ReadTransactionError> {
+    let wallet: Pubkey = wallet.parse()?;
+    let mut positions = Vec::new();
+    #[cfg(feature = "marginfi")]
+    positions.extend(marginfi::get_marginfi_positions(client, &wallet)?);
+    #[cfg(feature = "kamino")]
+    positions.extend(kamino::get_kamino_positions(client, &wallet)?);
+    Ok(positions)
+}
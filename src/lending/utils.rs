@@ -0,0 +1,39 @@
+//! Account reads shared by the lending protocols.
+
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType}
+};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use crate::error::ReadTransactionError;
+
+/// Gets the accounts of `program_id` starting with `discriminator` and holding `owner` at `owner_offset`.
+pub(crate) fn get_owned_program_accounts(client: &RpcClient, program_id: &Pubkey, discriminator: &[u8], owner: &Pubkey, owner_offset: usize) -> Result<Vec<(Pubkey, Account)>, ReadTransactionError> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, discriminator.to_vec())),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(owner_offset, owner.to_bytes().to_vec())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    Ok(client.get_program_accounts_with_config(program_id, config)?)
+}
+
+pub(crate) fn read_pubkey(data: &[u8], offset: usize) -> Option<Pubkey> {
+    Pubkey::try_from(data.get(offset..offset + 32)?).ok()
+}
+
+#[cfg(feature = "kamino")]
+pub(crate) fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    data.get(offset..offset + 8)?.try_into().ok().map(u64::from_le_bytes)
+}
+
+pub(crate) fn read_i128(data: &[u8], offset: usize) -> Option<i128> {
+    data.get(offset..offset + 16)?.try_into().ok().map(i128::from_le_bytes)
+}
@@ -36,12 +36,18 @@
 
 
 
+// Lets `#[derive(EasyAccount)]` refer to `::easy_solana` inside this crate
+extern crate self as easy_solana;
+
 pub mod utils;
 pub use utils::{
     generate_keypair,
     create_rpc_client
 };
 
+pub mod secret_keypair;
+pub use secret_keypair::SecretKeypair;
+
 pub mod read_transactions;
 pub use read_transactions::{
     metadata::{get_metadata_of_token, get_metadata_of_tokens},
@@ -55,8 +61,78 @@ pub use constants::{
     pumpfun_accounts
 };
 
+pub mod coalescing;
+
+pub mod config;
+pub use config::Config;
+
+pub mod deposits;
+
 pub mod error;
 
+pub mod profiles;
+
+pub mod devnet;
+
+pub mod easy_account;
+
+pub mod event_channel;
+
+pub mod explorer;
+pub use explorer::{explorer_url_for_account, explorer_url_for_signature, Cluster, Explorer};
+
+pub mod indexer;
+
+pub mod json;
+
+pub mod lending;
+
+pub mod rate_limit;
+
+pub mod replay;
+
+pub mod rpc_fixtures;
+
+pub mod rpc_logging;
+
+pub mod rpc_pool;
+
+pub mod supervisor;
+
+pub mod timestamps;
+
+pub mod vesting;
+
+pub mod watchlist;
+
 pub mod pumpfun;
+pub use pumpfun::{
+    bonding_curve::{get_bonding_curve_account, calculate_token_price_in_sol, BondingCurveAccount},
+    bump::construct_bump_pump_token_transaction,
+    quote::{quote_buy, quote_sell, PumpQuote}
+};
+
 pub mod raydium;
+
+pub mod prelude;
+
 pub mod write_transactions;
+pub use write_transactions::{
+    send::{send_sol, send_token},
+    transaction_builder::TransactionBuilder,
+    utils::{
+        simulate_transaction,
+        send_transaction_unchecked,
+        send_transaction_unchecked_with_config,
+        send_and_confirm_transaction,
+        send_and_confirm_transaction_with_config,
+        send_transaction_idempotent,
+        simulate_instructions_as,
+        ConfirmedTransaction,
+        SendConfig,
+        SimulationResult
+    }
+};
+
+#[cfg(test)]
+mod test_utils;
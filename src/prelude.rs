@@ -0,0 +1,45 @@
+//! # Prelude
+//!
+//! Commonly used types, traits and constants, so applications can get started with a single
+//! glob import.
+//!
+//! ```rust
+//! use easy_solana::prelude::*;
+//!
+//! let keypair = Keypair::new();
+//! let lamports = 0.1 * LAMPORTS_PER_SOL as f64;
+//! assert!(keypair.pubkey() != Pubkey::default() && lamports > 0.0);
+//! ```
+
+pub use solana_client::rpc_client::RpcClient;
+pub use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{keypair::Keypair, Signer},
+    transaction::Transaction
+};
+
+pub use crate::{
+    constants::{
+        pumpfun_accounts::{pumpfun_program, PUMP_TOKEN_DECIMALS},
+        solana_programs::{sol_pubkey, system_program, token_2022_program, token_program}
+    },
+    secret_keypair::SecretKeypair,
+    easy_account::EasyAccount,
+    explorer::{explorer_url_for_account, explorer_url_for_signature, Cluster, Explorer},
+    error::{ReadTransactionError, SendError, SimulationError, TransactionBuilderError, WriteTransactionError},
+    utils::{create_rpc_client, generate_keypair},
+    write_transactions::{
+        send::{send_sol, send_token},
+        transaction_builder::TransactionBuilder,
+        utils::{
+            send_and_confirm_transaction,
+            send_transaction_unchecked,
+            simulate_transaction,
+            ConfirmedTransaction,
+            SendConfig
+        }
+    }
+};
@@ -0,0 +1,371 @@
+//! # Profiles
+//!
+//! Named wallets such as "sniper1" or "treasury", each with its own RPC, fees, slippage and
+//! policy, so bots and the CLI reference wallets by name instead of juggling `PRIVATE_KEY_1`,
+//! `PRIVATE_KEY_2` environment variables.
+//!
+//! Keypairs live in a keystore directory as `<name>.json` files in the `solana-keygen` format,
+//! unless a profile points to another file or to an environment variable.
+//!
+//! ```toml
+//! keystore = "keystore"            # relative to profiles.toml [default: keystore]
+//!
+//! [profiles.sniper1]
+//! rpc = { url = "HELIUS_RPC_URL", commitment = "processed" }
+//! fees = { priority = "high" }
+//! policy = { max_lamports_per_transaction = 500_000_000 }
+//!
+//! [profiles.treasury]
+//! keypair_env = "TREASURY_PRIVATE_KEY"
+//! policy = { allowed_destinations = ["ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5"] }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf}
+};
+
+use serde::Deserialize;
+use solana_sdk::signer::Signer;
+use zeroize::Zeroizing;
+
+use crate::{
+    config::{Config, FeesConfig, PolicyConfig, RpcConfig, TradingConfig},
+    error::ProfileError,
+    secret_keypair::SecretKeypair
+};
+
+/// Default file name of the profiles.
+pub const PROFILES_FILE: &str = "profiles.toml";
+/// Default keystore directory, relative to the profiles file.
+pub const KEYSTORE_DIR: &str = "keystore";
+
+/// Directory of keypair files named after their profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keystore {
+    pub dir: PathBuf,
+}
+
+impl Keystore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Keystore { dir: dir.into() }
+    }
+
+    /// Path of the keypair file of `name`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ProfileError::InvalidName` unless the name only contains ASCII letters, digits,
+    /// `-` and `_`.
+    pub fn path(&self, name: &str) -> Result<PathBuf, ProfileError> {
+        validate_name(name)?;
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+
+    /// Reads the keypair of `name`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ProfileError::IoError` if the file can not be read and
+    /// `ProfileError::InvalidKeypairFile` if it is not a `solana-keygen` keypair.
+    pub fn load(&self, name: &str) -> Result<SecretKeypair, ProfileError> {
+        read_keypair_file(&self.path(name)?)
+    }
+
+    /// Writes the keypair of `name`, readable only by the owner on unix. The file is created
+    /// with these permissions, so the keypair is never readable by others, even briefly.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ProfileError::ProfileExists` if `name` already has a keypair, it is never overwritten.
+    pub fn save(&self, name: &str, keypair: &SecretKeypair) -> Result<PathBuf, ProfileError> {
+        let path = self.path(name)?;
+        let bytes = Zeroizing::new(keypair.to_bytes());
+        let json = Zeroizing::new(serde_json::to_string(&bytes[..])?);
+        fs::create_dir_all(&self.dir)?;
+
+        let mut options = OpenOptions::new();
+        // Fails if the file exists, even if it was created since the name was checked
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&path).map_err(|err| match err.kind() {
+            ErrorKind::AlreadyExists => ProfileError::ProfileExists(name.to_string()),
+            _ => ProfileError::IoError(err),
+        })?;
+        file.write_all(json.as_bytes())?;
+        Ok(path)
+    }
+
+    /// Generates and saves a new keypair for `name`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ProfileError::ProfileExists` if `name` already has a keypair.
+    pub fn generate(&self, name: &str) -> Result<SecretKeypair, ProfileError> {
+        let keypair = SecretKeypair::generate();
+        self.save(name, &keypair)?;
+        Ok(keypair)
+    }
+
+    /// Names of the stored keypairs, sorted. Empty if the directory does not exist.
+    pub fn names(&self) -> Result<Vec<String>, ProfileError> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut names = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == "json") {
+                if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+}
+
+/// Settings of one wallet. Sections that are set replace the same section of the base config.
+///
+/// ### Fields
+///
+/// - `keypair_env`: Environment variable holding a base58 private key.
+/// - `keypair_file`: `solana-keygen` keypair file, relative to the profiles file.
+/// - `rpc`, `fees`, `trading`, `policy`: Overrides of the base `Config`.
+///
+/// The keypair is read from the keystore if neither `keypair_env` nor `keypair_file` is set.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub keypair_env: Option<String>,
+    pub keypair_file: Option<PathBuf>,
+    pub rpc: Option<RpcConfig>,
+    pub fees: Option<FeesConfig>,
+    pub trading: Option<TradingConfig>,
+    pub policy: Option<PolicyConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ProfilesFile {
+    keystore: PathBuf,
+    profiles: BTreeMap<String, Profile>,
+}
+
+impl Default for ProfilesFile {
+    fn default() -> Self {
+        ProfilesFile { keystore: PathBuf::from(KEYSTORE_DIR), profiles: BTreeMap::new() }
+    }
+}
+
+/// Named wallet profiles and their keystore.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{profiles::Profiles, Config, TransactionBuilder};
+///
+/// let profiles = Profiles::load("profiles.toml").unwrap();
+/// let config = profiles.config("sniper1", &Config::default()).unwrap();
+/// let keypair = profiles.keypair("sniper1").unwrap();
+/// let client = config.rpc_client().unwrap();
+/// let mut builder = TransactionBuilder::new(&client, &keypair);
+/// builder.with_config(&config).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profiles {
+    pub keystore: Keystore,
+    pub profiles: BTreeMap<String, Profile>,
+    /// Directory that relative paths of the profiles file resolve against.
+    base_dir: PathBuf,
+}
+
+impl Profiles {
+    /// Reads the profiles file at `path`. Relative paths in it resolve against its directory.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ProfileError::IoError` if the file can not be read and `ProfileError::ParseError`
+    /// if it is invalid.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ProfileError> {
+        let path = path.as_ref();
+        let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Self::from_toml_str(&fs::read_to_string(path)?, base_dir)
+    }
+
+    /// Parses profiles, resolving relative paths against `base_dir`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ProfileError::ParseError` for invalid TOML or unknown fields and
+    /// `ProfileError::InvalidName` for invalid profile names.
+    pub fn from_toml_str(toml: &str, base_dir: impl Into<PathBuf>) -> Result<Self, ProfileError> {
+        let file: ProfilesFile = toml::from_str(toml)?;
+        for name in file.profiles.keys() {
+            validate_name(name)?;
+        }
+        let base_dir = base_dir.into();
+        Ok(Profiles { keystore: Keystore::new(base_dir.join(file.keystore)), profiles: file.profiles, base_dir })
+    }
+
+    /// Names of the profiles, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        self.profiles.keys().map(String::as_str).collect()
+    }
+
+    /// Profile of `name`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ProfileError::ProfileNotFound` if there is no such profile.
+    pub fn profile(&self, name: &str) -> Result<&Profile, ProfileError> {
+        self.profiles.get(name).ok_or_else(|| ProfileError::ProfileNotFound(name.to_string()))
+    }
+
+    /// Keypair of `name`, from its environment variable, keypair file or the keystore.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ProfileError::ProfileNotFound` if there is no such profile, or the error of
+    /// reading the keypair.
+    pub fn keypair(&self, name: &str) -> Result<SecretKeypair, ProfileError> {
+        let profile = self.profile(name)?;
+        match (&profile.keypair_env, &profile.keypair_file) {
+            (Some(env_var), _) => Ok(SecretKeypair::from_env(env_var)?),
+            (None, Some(path)) => read_keypair_file(&self.base_dir.join(path)),
+            (None, None) => self.keystore.load(name),
+        }
+    }
+
+    /// `base` with the sections set by the profile of `name` replaced.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ProfileError::ProfileNotFound` if there is no such profile.
+    pub fn config(&self, name: &str, base: &Config) -> Result<Config, ProfileError> {
+        let profile = self.profile(name)?;
+        let mut config = base.clone();
+        if let Some(rpc) = &profile.rpc {
+            config.rpc = rpc.clone();
+        }
+        if let Some(fees) = profile.fees {
+            config.fees = fees;
+        }
+        if let Some(trading) = profile.trading {
+            config.trading = trading;
+        }
+        if let Some(policy) = &profile.policy {
+            config.policy = policy.clone();
+        }
+        Ok(config)
+    }
+
+    /// Public keys of every profile, `None` for profiles whose keypair can not be read.
+    pub fn pubkeys(&self) -> Vec<(&str, Option<String>)> {
+        self.profiles
+            .keys()
+            .map(|name| (name.as_str(), self.keypair(name).ok().map(|keypair| keypair.pubkey().to_string())))
+            .collect()
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), ProfileError> {
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(ProfileError::InvalidName(name.to_string()))
+    }
+}
+
+/// Reads a keypair file in the `solana-keygen` format, a JSON array of 64 bytes.
+fn read_keypair_file(path: &Path) -> Result<SecretKeypair, ProfileError> {
+    let json = Zeroizing::new(fs::read_to_string(path)?);
+    let bytes: Zeroizing<Vec<u8>> = Zeroizing::new(
+        serde_json::from_str(&json).map_err(|_| ProfileError::InvalidKeypairFile(path.to_path_buf()))?
+    );
+    SecretKeypair::from_bytes(&bytes).map_err(|_| ProfileError::InvalidKeypairFile(path.to_path_buf()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::PriorityFeePreset, error::KeypairError};
+
+    const PROFILES: &str = r#"
+        [profiles.sniper1]
+        rpc = { url = "https://api.devnet.solana.com", commitment = "processed" }
+        fees = { priority = "high" }
+
+        [profiles.treasury]
+        keypair_env = "EASY_SOLANA_MISSING_TREASURY_KEY"
+        policy = { max_lamports_per_transaction = 1_000 }
+    "#;
+
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("easy-solana-profiles-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_keystore_round_trip() {
+        let dir = temp_dir("keystore");
+        let keystore = Keystore::new(&dir);
+        assert!(keystore.names().unwrap().is_empty());
+
+        let keypair = keystore.generate("sniper1").unwrap();
+        assert!(keystore.load("sniper1").unwrap().pubkey() == keypair.pubkey());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(keystore.path("sniper1").unwrap()).unwrap().permissions().mode();
+            assert!(mode & 0o777 == 0o600);
+        }
+        assert!(matches!(keystore.save("sniper1", &keypair), Err(ProfileError::ProfileExists(_))));
+        assert!(matches!(keystore.load("../sniper1"), Err(ProfileError::InvalidName(_))));
+        assert!(keystore.names().unwrap() == vec!["sniper1".to_string()]);
+
+        fs::write(dir.join("broken.json"), "[1, 2, 3]").unwrap();
+        assert!(matches!(keystore.load("broken"), Err(ProfileError::InvalidKeypairFile(_))));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_profiles_config_and_keypairs() {
+        let dir = temp_dir("config");
+        let profiles = Profiles::from_toml_str(PROFILES, &dir).unwrap();
+        assert!(profiles.names() == vec!["sniper1", "treasury"]);
+        assert!(profiles.keystore.dir == dir.join(KEYSTORE_DIR));
+
+        let base = Config::from_toml_str("[trading]\nslippage_bps = 300").unwrap();
+        let config = profiles.config("sniper1", &base).unwrap();
+        assert!(config.rpc.url == "https://api.devnet.solana.com");
+        assert!(config.fees.priority == PriorityFeePreset::High);
+        assert!(config.trading.slippage_bps == 300);
+        let config = profiles.config("treasury", &base).unwrap();
+        assert!(config.rpc == base.rpc);
+        assert!(config.policy.max_lamports_per_transaction == Some(1_000));
+        assert!(matches!(profiles.config("unknown", &base), Err(ProfileError::ProfileNotFound(_))));
+
+        let keypair = profiles.keystore.generate("sniper1").unwrap();
+        assert!(profiles.keypair("sniper1").unwrap().pubkey() == keypair.pubkey());
+        assert!(matches!(profiles.keypair("treasury"), Err(ProfileError::KeypairError(KeypairError::EnvVarNotFound(_)))));
+        assert!(profiles.pubkeys() == vec![("sniper1", Some(keypair.pubkey().to_string())), ("treasury", None)]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_profiles() {
+        assert!(matches!(Profiles::from_toml_str("[profiles.\"a/b\"]", ""), Err(ProfileError::InvalidName(_))));
+        assert!(matches!(Profiles::from_toml_str("[profiles.a]\nkey = 1", ""), Err(ProfileError::ParseError(_))));
+    }
+}
@@ -1,24 +1,106 @@
 use crate::{
-    constants::pumpfun_accounts::pumpfun_program, 
+    constants::{
+        pumpfun_accounts::{pumpfun_program, BONDING_CURVE_DISCRIMINATOR},
+        solana_programs::{associated_token_account_program, token_program}
+    }, 
+    read_transactions::account::get_multiple_account_data_slices,
     utils::address_to_pubkey, 
     error::ReadTransactionError
 };
+use solana_account_decoder::UiDataSliceConfig;
 use solana_client::rpc_client::RpcClient;
-use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_sdk::pubkey::Pubkey;
 use borsh::{BorshDeserialize, BorshSerialize};
+use super::curve_math::price_in_sol;
 
-const PUMP_CURVE_TOKEN_DECIMALS: u8 = 6;
+const PUMP_CURVE_TOKEN_DECIMALS: u32 = 6;
 
-// Bonding curve account data
-#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+/// Bonding curve account data of a pump.fun token.
+///
+/// ### Fields
+/// 
+/// - `discriminator`: Anchor account discriminator, always `BONDING_CURVE_DISCRIMINATOR`.
+/// - `virtual_token_reserves`: Token reserves used for pricing, including the virtual liquidity.
+/// - `virtual_sol_reserves`: SOL reserves in lamports used for pricing, including the virtual liquidity.
+/// - `real_token_reserves`: Tokens left to be bought from the curve.
+/// - `real_sol_reserves`: SOL in lamports deposited into the curve.
+/// - `total_token_supply`: Total supply of the token.
+/// - `complete`: Whether the curve is complete and the token has migrated.
+/// - `creator`: Creator of the token, `None` for accounts created before the creator field was added.
+#[derive(Debug, Clone, PartialEq)]
 pub struct BondingCurveAccount {
-    pub unkown_value: u64,
+    pub discriminator: [u8; 8],
     pub virtual_token_reserves: u64,
     pub virtual_sol_reserves: u64,
     pub real_token_reserves: u64,
     pub real_sol_reserves: u64,
     pub total_token_supply: u64,
     pub complete: bool,
+    pub creator: Option<Pubkey>,
+}
+
+// Fields shared by every version of the bonding curve account, after the discriminator
+#[derive(BorshDeserialize, BorshSerialize)]
+struct BondingCurveReserves {
+    virtual_token_reserves: u64,
+    virtual_sol_reserves: u64,
+    real_token_reserves: u64,
+    real_sol_reserves: u64,
+    total_token_supply: u64,
+    complete: bool,
+}
+
+impl BondingCurveAccount {
+    /// Deserializes bonding curve account data. Older accounts end after the `complete` flag,
+    /// newer accounts are followed by the creator and possibly other fields which are ignored.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::WrongAccountType` if the discriminator does not match,
+    /// or `ReadTransactionError::DeserializeError` if the data is too short.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, ReadTransactionError> {
+        if data.len() < 8 || data[..8] != BONDING_CURVE_DISCRIMINATOR {
+            return Err(ReadTransactionError::WrongAccountType("pump.fun bonding curve".to_string()));
+        }
+        let mut remaining_data = &data[8..];
+        let reserves = BondingCurveReserves::deserialize(&mut remaining_data)
+            .map_err(|_| ReadTransactionError::DeserializeError)?;
+        let creator = if remaining_data.len() >= 32 {
+            Some(Pubkey::deserialize(&mut remaining_data).map_err(|_| ReadTransactionError::DeserializeError)?)
+        } else {
+            None
+        };
+
+        Ok(BondingCurveAccount {
+            discriminator: BONDING_CURVE_DISCRIMINATOR,
+            virtual_token_reserves: reserves.virtual_token_reserves,
+            virtual_sol_reserves: reserves.virtual_sol_reserves,
+            real_token_reserves: reserves.real_token_reserves,
+            real_sol_reserves: reserves.real_sol_reserves,
+            total_token_supply: reserves.total_token_supply,
+            complete: reserves.complete,
+            creator,
+        })
+    }
+
+    /// Serializes the account like the pump.fun program, the inverse of `try_from_account_data`.
+    pub fn to_account_data(&self) -> Vec<u8> {
+        let reserves = BondingCurveReserves {
+            virtual_token_reserves: self.virtual_token_reserves,
+            virtual_sol_reserves: self.virtual_sol_reserves,
+            real_token_reserves: self.real_token_reserves,
+            real_sol_reserves: self.real_sol_reserves,
+            total_token_supply: self.total_token_supply,
+            complete: self.complete,
+        };
+        let mut data = self.discriminator.to_vec();
+        // Writing to a vector never fails
+        let _ = reserves.serialize(&mut data);
+        if let Some(creator) = self.creator {
+            data.extend_from_slice(creator.as_ref());
+        }
+        data
+    }
 }
 
 pub fn calculate_token_price_in_sol(curve_state: &BondingCurveAccount) -> Result<f64, ReadTransactionError> {
@@ -26,11 +108,11 @@ pub fn calculate_token_price_in_sol(curve_state: &BondingCurveAccount) -> Result
         return Err(ReadTransactionError::BondingCurveError);
     }
     // Bonding curve prices are calculated by virtual sol / virtual token
-    let virtual_sol_reserves = curve_state.virtual_sol_reserves as f64 / LAMPORTS_PER_SOL as f64;
-    let virtual_token_reserves = curve_state.virtual_token_reserves as f64 / 10_f64.powi(PUMP_CURVE_TOKEN_DECIMALS as i32);
-    let token_price_in_sol = virtual_sol_reserves / virtual_token_reserves;
-
-    Ok(token_price_in_sol)
+    Ok(price_in_sol(
+        curve_state.virtual_sol_reserves as u128,
+        curve_state.virtual_token_reserves as u128,
+        PUMP_CURVE_TOKEN_DECIMALS
+    ))
 }
 
 pub fn get_bonding_curve_account(client: &RpcClient, token_address: &str) -> Option<(Pubkey, BondingCurveAccount)> {
@@ -38,23 +120,203 @@ pub fn get_bonding_curve_account(client: &RpcClient, token_address: &str) -> Opt
     let bonding_curve_account = address_to_pubkey(&bonding_curve_address).ok()?;
 
     if let Ok(account_data) = client.get_account_data(&bonding_curve_account) {
-        if let Ok(bonding_curve_data) = BondingCurveAccount::deserialize(&mut account_data.as_slice()) {
+        if let Ok(bonding_curve_data) = BondingCurveAccount::try_from_account_data(&account_data) {
             return Some((bonding_curve_account, bonding_curve_data))
         }
     }
     None
 }
 
+/// Bytes of a bonding curve account holding its virtual reserves, after the discriminator.
+pub const VIRTUAL_RESERVES_SLICE: UiDataSliceConfig = UiDataSliceConfig { offset: 8, length: 16 };
+
+/// Virtual reserves of a bonding curve, which are all that is needed to price its token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VirtualReserves {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+}
+
+impl VirtualReserves {
+    /// Price of one token in SOL.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::BondingCurveError` if a reserve is empty.
+    pub fn price_in_sol(&self) -> Result<f64, ReadTransactionError> {
+        if self.virtual_token_reserves == 0 || self.virtual_sol_reserves == 0 {
+            return Err(ReadTransactionError::BondingCurveError);
+        }
+        Ok(price_in_sol(self.virtual_sol_reserves as u128, self.virtual_token_reserves as u128, PUMP_CURVE_TOKEN_DECIMALS))
+    }
+}
+
+/// Gets the virtual reserves of the bonding curves of multiple pump.fun tokens, downloading only
+/// 16 bytes per curve, e.g to poll prices frequently. Reserves are `None` for tokens without a
+/// bonding curve. The discriminator is not downloaded, so the curves are not validated.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InvalidAddress` if a token address is invalid.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, pumpfun::bonding_curve::get_virtual_reserves};
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let reserves = get_virtual_reserves(&client, vec!["CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump"]).unwrap();
+/// if let Some(reserves) = reserves[0] {
+///     println!("{} SOL", reserves.price_in_sol().unwrap());
+/// }
+/// ```
+pub fn get_virtual_reserves(client: &RpcClient, token_addresses: Vec<&str>) -> Result<Vec<Option<VirtualReserves>>, ReadTransactionError> {
+    let bonding_curve_addresses = token_addresses
+        .into_iter()
+        .map(get_bonding_curve_address)
+        .collect::<Result<Vec<String>, ReadTransactionError>>()?;
+    let slices = get_multiple_account_data_slices(
+        client,
+        bonding_curve_addresses.iter().map(String::as_str).collect(),
+        VIRTUAL_RESERVES_SLICE
+    )?;
+    Ok(slices
+        .into_iter()
+        .map(|slice| {
+            let data = slice?;
+            Some(VirtualReserves {
+                virtual_token_reserves: u64::from_le_bytes(data.get(..8)?.try_into().ok()?),
+                virtual_sol_reserves: u64::from_le_bytes(data.get(8..16)?.try_into().ok()?),
+            })
+        })
+        .collect())
+}
+
 fn get_bonding_curve_address(token_address: &str) -> Result<String, ReadTransactionError> {
     let token_account = address_to_pubkey(token_address)?;
-    // Get bonding curve data
-    let seed = b"bonding-curve";
+    Ok(derive_bonding_curve(&token_account).to_string())
+}
+
+/// Derives the bonding curve account of a pump.fun token.
+pub fn derive_bonding_curve(mint: &Pubkey) -> Pubkey {
     let (bonding_curve_account, _bump_seed) = Pubkey::find_program_address(
-        &[seed, &token_account.to_bytes()],
+        &[b"bonding-curve", mint.as_ref()],
         &pumpfun_program()
     );
-    Ok(bonding_curve_account.to_string())
-} 
+    bonding_curve_account
+}
+
+/// Derives the associated bonding curve of a pump.fun token, the token account of the
+/// bonding curve holding the tokens that are left to be bought.
+///
+/// ## Example
+///
+/// ```rust
+/// use std::str::FromStr;
+/// use solana_sdk::pubkey::Pubkey;
+/// use easy_solana::pumpfun::bonding_curve::derive_associated_bonding_curve;
+///
+/// let mint = Pubkey::from_str("CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump").unwrap();
+/// let associated_bonding_curve = derive_associated_bonding_curve(&mint);
+/// ```
+pub fn derive_associated_bonding_curve(mint: &Pubkey) -> Pubkey {
+    let bonding_curve_account = derive_bonding_curve(mint);
+    let (associated_bonding_curve_account, _bump_seed) = Pubkey::find_program_address(
+        &[bonding_curve_account.as_ref(), token_program().as_ref(), mint.as_ref()],
+        &associated_token_account_program()
+    );
+    associated_bonding_curve_account
+}
+
+/// Derives the creator vault of a token creator, the account collecting creator fees
+/// of all tokens created by `creator`.
+pub fn derive_creator_vault(creator: &Pubkey) -> Pubkey {
+    let (creator_vault_account, _bump_seed) = Pubkey::find_program_address(
+        &[b"creator-vault", creator.as_ref()],
+        &pumpfun_program()
+    );
+    creator_vault_account
+}
+
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use crate::test_utils::{account_with_data, fresh_bonding_curve, mock_client, multiple_accounts_response};
 
+    fn bonding_curve_data(creator: Option<Pubkey>) -> Vec<u8> {
+        let curve_state = fresh_bonding_curve();
+        let mut data = BONDING_CURVE_DISCRIMINATOR.to_vec();
+        let reserves = BondingCurveReserves {
+            virtual_token_reserves: curve_state.virtual_token_reserves,
+            virtual_sol_reserves: curve_state.virtual_sol_reserves,
+            real_token_reserves: curve_state.real_token_reserves,
+            real_sol_reserves: curve_state.real_sol_reserves,
+            total_token_supply: curve_state.total_token_supply,
+            complete: curve_state.complete,
+        };
+        data.extend(borsh::to_vec(&reserves).unwrap());
+        if let Some(creator) = creator {
+            data.extend_from_slice(creator.as_ref());
+        }
+        data
+    }
 
+    #[test]
+    fn test_get_virtual_reserves() {
+        let sliced_account = account_with_data(pumpfun_program(), bonding_curve_data(None)[8..24].to_vec());
+        let client = mock_client(vec![
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(sliced_account), None], 1)),
+        ]);
+        let tokens = [Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string()];
+        let reserves = get_virtual_reserves(&client, tokens.iter().map(String::as_str).collect()).unwrap();
+        assert!(reserves == vec![Some(VirtualReserves { virtual_token_reserves: 1_073_000_000_000_000, virtual_sol_reserves: 30_000_000_000 }), None]);
+        assert!(reserves[0].unwrap().price_in_sol().unwrap() > 0.0);
+        assert!(matches!(get_virtual_reserves(&client, vec!["invalid"]), Err(ReadTransactionError::InvalidAddress(_))));
+    }
+
+    #[test]
+    fn test_derive_associated_bonding_curve() {
+        let mint = Pubkey::new_unique();
+        let associated_bonding_curve = derive_associated_bonding_curve(&mint);
+        let expected_address = spl_associated_token_account::get_associated_token_address(&derive_bonding_curve(&mint), &mint);
+        assert!(associated_bonding_curve == expected_address);
+    }
+
+    #[test]
+    fn test_derive_creator_vault() {
+        let creator = Pubkey::new_unique();
+        let creator_vault = derive_creator_vault(&creator);
+        assert!(creator_vault == derive_creator_vault(&creator));
+        assert!(!creator_vault.is_on_curve());
+        assert!(creator_vault != derive_creator_vault(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn test_deserialize_bonding_curve_account_with_creator() {
+        let creator = Pubkey::new_unique();
+        let bonding_curve = BondingCurveAccount::try_from_account_data(&bonding_curve_data(Some(creator))).unwrap();
+        assert!(bonding_curve.creator == Some(creator));
+        assert!(bonding_curve.virtual_sol_reserves == 30_000_000_000);
+        assert!(!bonding_curve.complete);
+    }
+
+    #[test]
+    fn test_deserialize_old_bonding_curve_account() {
+        let bonding_curve = BondingCurveAccount::try_from_account_data(&bonding_curve_data(None)).unwrap();
+        assert!(bonding_curve.creator.is_none());
+        assert!(bonding_curve.total_token_supply == 1_000_000_000_000_000);
+    }
+
+    #[test]
+    fn failing_test_deserialize_bonding_curve_account_with_wrong_discriminator() {
+        let mut data = bonding_curve_data(None);
+        data[0] = 0;
+        let result = BondingCurveAccount::try_from_account_data(&data);
+        assert!(matches!(result, Err(ReadTransactionError::WrongAccountType(_))));
+        // truncated data with a valid discriminator
+        let result = BondingCurveAccount::try_from_account_data(&BONDING_CURVE_DISCRIMINATOR);
+        assert!(matches!(result, Err(ReadTransactionError::DeserializeError)));
+    }
+}
@@ -1,25 +1,46 @@
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
-    compute_budget::ComputeBudgetInstruction, native_token::LAMPORTS_PER_SOL, 
+    compute_budget::ComputeBudgetInstruction, 
+    instruction::Instruction,
+    pubkey::Pubkey,
     signer::{
         keypair::Keypair,
         Signer
     }, transaction::Transaction
 };
 
-use solana_program::instruction::{AccountMeta, Instruction};
-
 use crate::{
-    constants::{
-        pumpfun_accounts::{
-            buy_instruction_data, pumpfun_event_authority_account, pumpfun_fee_account, pumpfun_global_account, pumpfun_program, sell_instruction_data, PUMP_TOKEN_DECIMALS
-        },
-        solana_programs::{
-            associated_token_account_program, rent_program, system_program, token_program
-        }
-    }, 
-    error::WriteTransactionError, read_transactions::associated_token_account::derive_associated_token_account_address, utils::address_to_pubkey};
-use super::bonding_curve::{get_bonding_curve_account, calculate_token_price_in_sol};
+    error::{ReadTransactionError, TransactionBuilderError, WriteTransactionError},
+    utils::{address_to_pubkey, sol_to_lamports},
+    write_transactions::transaction_builder::TransactionBuilder
+};
+use super::{
+    bonding_curve::{get_bonding_curve_account, BondingCurveAccount},
+    curve_math::{mul_div_floor, to_u64},
+    instructions::{pump_buy_instruction, pump_sell_instruction}
+};
+
+/// Buy and sell instructions of a bump of `max_sol_cost` lamports for `user`.
+fn bump_instructions(
+    user: &Pubkey,
+    mint: &Pubkey,
+    bonding_state: &BondingCurveAccount,
+    max_sol_cost: u64
+) -> Result<[Instruction; 2], ReadTransactionError> {
+    // Buy 80% of the tokens max_sol_cost gets at the latest spot price
+    let amount_in_decimals = mul_div_floor(
+        max_sol_cost as u128,
+        bonding_state.virtual_token_reserves as u128 * 8,
+        bonding_state.virtual_sol_reserves as u128 * 10,
+        "bump amount"
+    ).and_then(|amount| to_u64(amount, "bump amount"))?;
+
+    let creator = bonding_state.creator.unwrap_or_default();
+    Ok([
+        pump_buy_instruction(user, mint, &creator, amount_in_decimals, max_sol_cost),
+        pump_sell_instruction(user, mint, &creator, amount_in_decimals, 0),
+    ])
+}
 
 /// Bumps token by combining a buy and sell instruction within one transaction
 /// IMPT: check if the associated token account exists first
@@ -35,61 +56,9 @@ pub async fn construct_bump_pump_token_transaction(
     let token_account = address_to_pubkey(token_address)?;
     let user_keypair = Keypair::from_base58_string(base58_keypair);
     let user_account = user_keypair.pubkey();
-    let associated_user_address = derive_associated_token_account_address(
-        &user_account.to_string(), 
-        &token_account.to_string(),
-        token_program()
-    )?;
-    let associated_user_account = address_to_pubkey(&associated_user_address)?;
-    let global_account = pumpfun_global_account();
-    let pumpfun_fee_account = pumpfun_fee_account();
-    let system_program = system_program();
-    let token_program = token_program();
-    let associated_token_program = associated_token_account_program();
-    let rent_program = rent_program();
-    let event_authority_account = pumpfun_event_authority_account();
-    let pumpfun_program = pumpfun_program();
     
-    // Get bonding curve and associated bonding curve accounts
-    let (bonding_curve_account, bonding_state) = get_bonding_curve_account(client, token_address).expect("Unable to get bonding curve addresses. Please try again");
-    let associated_bonding_curve_address = derive_associated_token_account_address(
-        &bonding_curve_account.to_string(), 
-        &token_account.to_string(),
-        token_program
-    )?;
-    let associated_bonding_curve_account = address_to_pubkey(&associated_bonding_curve_address)?;
-    
-    // define buy accounts
-    let buy_accounts = vec![
-        AccountMeta::new_readonly(global_account, false),
-        AccountMeta::new(pumpfun_fee_account, false),
-        AccountMeta::new_readonly(token_account, false),
-        AccountMeta::new(bonding_curve_account, false),
-        AccountMeta::new(associated_bonding_curve_account, false),
-        AccountMeta::new(associated_user_account, false),
-        AccountMeta::new(user_account, true),
-        AccountMeta::new_readonly(system_program, false),
-        AccountMeta::new_readonly(token_program, false),
-        AccountMeta::new_readonly(rent_program, false),
-        AccountMeta::new_readonly(event_authority_account, false),
-        AccountMeta::new_readonly(pumpfun_program, false),
-    ];
-
-    // define sell accounts
-    let sell_accounts = vec![
-        AccountMeta::new_readonly(global_account, false),
-        AccountMeta::new(pumpfun_fee_account, false),
-        AccountMeta::new_readonly(token_account, false),
-        AccountMeta::new(bonding_curve_account, false),
-        AccountMeta::new(associated_bonding_curve_account, false),
-        AccountMeta::new(associated_user_account, false),
-        AccountMeta::new(user_account, true),
-        AccountMeta::new_readonly(system_program, false),
-        AccountMeta::new_readonly(associated_token_program, false),
-        AccountMeta::new_readonly(token_program, false),
-        AccountMeta::new_readonly(event_authority_account, false),
-        AccountMeta::new_readonly(pumpfun_program, false),
-    ];
+    // Get bonding curve account
+    let (_bonding_curve_account, bonding_state) = get_bonding_curve_account(client, token_address).expect("Unable to get bonding curve addresses. Please try again");
 
     // Compute Budget: SetComputeUnitLimit
     let set_compute_unit_limit = ComputeBudgetInstruction::set_compute_unit_limit(compute_limit);
@@ -97,33 +66,8 @@ pub async fn construct_bump_pump_token_transaction(
     // Compute Budget: SetComputeUnitPrice
     let set_compute_unit_price = ComputeBudgetInstruction::set_compute_unit_price(compute_units);
 
-    // get latest bonding curve account data
-    let cost_per_token = calculate_token_price_in_sol(&bonding_state)?;
-    
-    let amount: f64 = (max_sol_cost / cost_per_token) * 0.8;
-    let multiplier = 10_u64.pow(PUMP_TOKEN_DECIMALS);
-    let amount_in_decimals: u64 = (amount * multiplier as f64).round() as u64;
-    let max_sol_cost_in_lamports = (max_sol_cost * LAMPORTS_PER_SOL as f64) as u64;
-
-    let mut buy_instruction_data = buy_instruction_data();
-    buy_instruction_data.extend_from_slice(&amount_in_decimals.to_le_bytes());
-    buy_instruction_data.extend_from_slice(&max_sol_cost_in_lamports.to_le_bytes());
-
-    let mut sell_instruction_data = sell_instruction_data();
-    sell_instruction_data.extend_from_slice(&amount_in_decimals.to_le_bytes());
-    sell_instruction_data.extend_from_slice(&(0_u64).to_le_bytes());
-
-    let buy_instruction = Instruction {
-        program_id: pumpfun_program,
-        accounts: buy_accounts.clone(),
-        data: buy_instruction_data,
-    };
-
-    let sell_instruction = Instruction {
-        program_id: pumpfun_program,
-        accounts: sell_accounts.clone(),
-        data: sell_instruction_data,
-    };
+    let max_sol_cost_in_lamports = sol_to_lamports(max_sol_cost);
+    let [buy_instruction, sell_instruction] = bump_instructions(&user_account, &token_account, &bonding_state, max_sol_cost_in_lamports)?;
 
     let mut transaction = Transaction::new_with_payer(
         &[
@@ -141,6 +85,46 @@ pub async fn construct_bump_pump_token_transaction(
     Ok(transaction)
 }
 
+impl TransactionBuilder<'_> {
+    /// Adds a bump of a pump.fun token costing at most `max_sol_cost` SOL, see `bump_pumpfun_token_lamports`.
+    pub fn bump_pumpfun_token(&mut self, token_address: &str, max_sol_cost: f64) -> Result<&mut Self, TransactionBuilderError> {
+        self.bump_pumpfun_token_lamports(token_address, sol_to_lamports(max_sol_cost))
+    }
+
+    /// Adds a bump of a pump.fun token for the payer keypair, buying and selling back the tokens
+    /// `max_sol_cost` lamports get, followed by the fee transfers of the fee config on `max_sol_cost`.
+    /// The associated token account of the payer for the token has to exist.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::InvalidAddress` if the token address is invalid and
+    /// `TransactionBuilderError::InstructionError` if the token has no bonding curve.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use easy_solana::{create_rpc_client, SecretKeypair, TransactionBuilder};
+    ///
+    /// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+    /// let keypair = SecretKeypair::from_env("PRIVATE_KEY_1").unwrap();
+    /// let mut builder = TransactionBuilder::new(&client, keypair.keypair());
+    /// builder.set_compute_limit(200_000);
+    /// builder.bump_pumpfun_token_lamports("CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump", 20_000_000).unwrap();
+    /// let transaction = builder.build().unwrap();
+    /// ```
+    pub fn bump_pumpfun_token_lamports(&mut self, token_address: &str, max_sol_cost: u64) -> Result<&mut Self, TransactionBuilderError> {
+        let mint = address_to_pubkey(token_address)?;
+        let (_, bonding_state) = get_bonding_curve_account(self.client, token_address)
+            .ok_or_else(|| TransactionBuilderError::InstructionError(format!("{} has no bonding curve", token_address)))?;
+        let instructions = bump_instructions(&self.payer_keypair.pubkey(), &mint, &bonding_state, max_sol_cost)
+            .map_err(|err| TransactionBuilderError::InstructionError(err.to_string()))?;
+
+        self.instructions.extend(instructions);
+        self.apply_fee(max_sol_cost);
+        Ok(self)
+    }
+}
+
 
 
 #[cfg(test)]
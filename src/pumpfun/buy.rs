@@ -0,0 +1,159 @@
+//! # Buy
+//!
+//! Standalone pump.fun buys for the `TransactionBuilder`, quoted from the bonding curve with
+//! slippage control.
+
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use crate::{
+    constants::{solana_programs::token_program, units::BASIS_POINTS_DIVISOR},
+    error::TransactionBuilderError,
+    utils::{address_to_pubkey, sol_to_lamports},
+    write_transactions::transaction_builder::TransactionBuilder
+};
+use super::{
+    bonding_curve::{get_bonding_curve_account, BondingCurveAccount},
+    curve_math::{mul_div_floor, to_u64},
+    global::{get_global_account, GlobalAccount},
+    instructions::pump_buy_instruction,
+    quote::{quote_buy, PumpQuote}
+};
+
+impl TransactionBuilder<'_> {
+    /// Adds a pump.fun buy of `sol_amount` SOL, fees included, see `buy_pumpfun_token_lamports`.
+    pub fn buy_pumpfun_token(&mut self, token_address: &str, sol_amount: f64, slippage_bps: u16) -> Result<PumpQuote, TransactionBuilderError> {
+        self.buy_pumpfun_token_lamports(token_address, sol_to_lamports(sol_amount), slippage_bps)
+    }
+
+    /// Adds a pump.fun buy of `lamports`, fees included, for the payer keypair, creating its
+    /// associated token account if it does not exist. The tokens bought are the quote from the
+    /// current bonding curve lowered by `slippage_bps`, and at most `lamports` are spent, so the
+    /// buy fails instead of overpaying if the price rises by more than the slippage.
+    /// The fee config of the builder, if any, is charged on the quoted SOL cost.
+    ///
+    /// Returns the quote the buy was sized from, i.e the expected fill before slippage.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::InvalidAddress` if the token address is invalid,
+    /// `TransactionBuilderError::InstructionError` if the token has no active bonding curve or
+    /// the slippage exceeds 10000 basis points, and `TransactionBuilderError::QueryError` if the
+    /// global account can not be fetched.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use easy_solana::{create_rpc_client, SecretKeypair, TransactionBuilder};
+    ///
+    /// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+    /// let keypair = SecretKeypair::from_env("PRIVATE_KEY_1").unwrap();
+    /// let mut builder = TransactionBuilder::new(&client, keypair.keypair());
+    /// builder.set_compute_limit(100_000);
+    /// let expected_fill = builder.buy_pumpfun_token_lamports("CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump", 10_000_000, 100).unwrap();
+    /// println!("Buying about {} tokens", expected_fill.token_amount);
+    /// let transaction = builder.build().unwrap();
+    /// ```
+    pub fn buy_pumpfun_token_lamports(&mut self, token_address: &str, lamports: u64, slippage_bps: u16) -> Result<PumpQuote, TransactionBuilderError> {
+        if slippage_bps as u128 > BASIS_POINTS_DIVISOR {
+            return Err(TransactionBuilderError::InstructionError(format!("Slippage of {} basis points exceeds 100%", slippage_bps)));
+        }
+        let mint = address_to_pubkey(token_address)?;
+        let (_, curve_state) = get_bonding_curve_account(self.client, token_address)
+            .ok_or_else(|| TransactionBuilderError::InstructionError(format!("{} has no bonding curve", token_address)))?;
+        let global_state = get_global_account(self.client)?;
+
+        self.push_pumpfun_buy(&mint, &curve_state, &global_state, lamports, slippage_bps)
+    }
+
+    /// Adds the associated token account and buy instructions for a buy quoted on `curve_state`,
+    /// followed by the fee transfers of the fee config on the SOL spent.
+    pub(super) fn push_pumpfun_buy(
+        &mut self,
+        mint: &Pubkey,
+        curve_state: &BondingCurveAccount,
+        global_state: &GlobalAccount,
+        max_sol_cost: u64,
+        slippage_bps: u16
+    ) -> Result<PumpQuote, TransactionBuilderError> {
+        let quote = quote_buy(curve_state, global_state, max_sol_cost)
+            .map_err(|err| TransactionBuilderError::InstructionError(err.to_string()))?;
+        let token_amount = mul_div_floor(quote.token_amount as u128, BASIS_POINTS_DIVISOR - slippage_bps as u128, BASIS_POINTS_DIVISOR, "minimum tokens")
+            .and_then(|token_amount| to_u64(token_amount, "minimum tokens"))
+            .map_err(|err| TransactionBuilderError::InstructionError(err.to_string()))?;
+
+        let payer = self.payer_keypair.pubkey();
+        self.instructions.push(create_associated_token_account_idempotent(
+            &self.fee_payer_pubkey(),
+            &payer,
+            mint,
+            &token_program(),
+        ));
+        self.instructions.push(pump_buy_instruction(&payer, mint, &curve_state.creator.unwrap_or_default(), token_amount, max_sol_cost));
+        self.apply_fee(quote.total_sol);
+        Ok(quote)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::signer::keypair::Keypair;
+    use solana_system_interface::instruction::transfer;
+    use crate::{
+        constants::pumpfun_accounts::pumpfun_program,
+        pumpfun::bonding_curve::derive_creator_vault,
+        write_transactions::fee::{FeeAmount, FeeConfig, Referral},
+        test_utils::{account_info_response, account_with_data, fresh_bonding_curve, global_account_data, mock_client, CURVE_CREATOR}
+    };
+
+    #[test]
+    fn test_buy_pumpfun_token_applies_slippage() {
+        let curve_state = fresh_bonding_curve();
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&account_with_data(pumpfun_program(), curve_state.to_account_data())), 1)),
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&account_with_data(pumpfun_program(), global_account_data())), 1)),
+        ]);
+        let payer_keypair = Keypair::new();
+        let mint = Pubkey::new_unique();
+
+        let mut builder = TransactionBuilder::new(&client, &payer_keypair);
+        let quote = builder.buy_pumpfun_token(&mint.to_string(), 0.5, 250).unwrap();
+        assert!(quote.total_sol <= 500_000_000 && quote.token_amount > 0);
+        assert!(builder.instructions.len() == 2);
+        let data = &builder.instructions[1].data;
+        assert!(data[8..16] == (quote.token_amount * 9_750 / 10_000).to_le_bytes());
+        assert!(data[16..24] == 500_000_000_u64.to_le_bytes());
+        assert!(builder.instructions[1].accounts[9].pubkey == derive_creator_vault(&CURVE_CREATOR));
+    }
+
+    #[test]
+    fn test_buy_pumpfun_token_charges_basis_points_fee() {
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&account_with_data(pumpfun_program(), fresh_bonding_curve().to_account_data())), 1)),
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&account_with_data(pumpfun_program(), global_account_data())), 1)),
+        ]);
+        let payer_keypair = Keypair::new();
+        let referral = Referral { wallet: Pubkey::new_unique(), share_bps: 2_000 };
+        let fee_config = FeeConfig { fee_wallet: Pubkey::new_unique(), amount: FeeAmount::BasisPoints(100), referral: Some(referral) };
+
+        let mut builder = TransactionBuilder::new(&client, &payer_keypair);
+        builder.with_fee_config(fee_config);
+        let quote = builder.buy_pumpfun_token(&Pubkey::new_unique().to_string(), 0.5, 250).unwrap();
+        let (fee, referral_fee) = fee_config.fee_split(quote.total_sol);
+        assert!(fee > 0 && referral_fee > 0);
+        assert!(builder.instructions.len() == 4);
+        assert!(builder.instructions[2] == transfer(&payer_keypair.pubkey(), &fee_config.fee_wallet, fee));
+        assert!(builder.instructions[3] == transfer(&payer_keypair.pubkey(), &referral.wallet, referral_fee));
+    }
+
+    #[test]
+    fn failing_test_buy_pumpfun_token_with_excessive_slippage() {
+        let client = mock_client(vec![]);
+        let payer_keypair = Keypair::new();
+        let mut builder = TransactionBuilder::new(&client, &payer_keypair);
+        let result = builder.buy_pumpfun_token(&Pubkey::new_unique().to_string(), 0.5, 10_001);
+        assert!(matches!(result, Err(TransactionBuilderError::InstructionError(_))));
+        assert!(builder.instructions.is_empty());
+    }
+}
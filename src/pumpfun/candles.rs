@@ -0,0 +1,252 @@
+use std::{collections::VecDeque, fs, path::Path, time::Duration};
+use serde::{Deserialize, Serialize};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use crate::{constants::pumpfun_accounts::PUMP_TOKEN_DECIMALS, error::PersistenceError};
+use super::trade_event::TradeEvent;
+
+/// OHLCV candle of a token, prices are in SOL per token.
+///
+/// ### Fields
+///
+/// - `start_time`: Unix timestamp in seconds the candle starts at.
+/// - `open`, `high`, `low`, `close`: Prices of the first, highest, lowest and last trade.
+/// - `volume_sol`: SOL traded, excluding fees.
+/// - `volume_tokens`: Tokens traded in ui format.
+/// - `trades`: Number of trades.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Candle {
+    pub start_time: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume_sol: f64,
+    pub volume_tokens: f64,
+    pub trades: u64,
+}
+
+/// Aggregates trade events into candles of a fixed interval, keeping the latest `capacity` candles.
+/// Intervals with no trades get a candle without volume at the close of the candle before them.
+///
+/// ## Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use easy_solana::pumpfun::candles::CandleAggregator;
+///
+/// let mut one_minute_candles = CandleAggregator::new(Duration::from_secs(60), 1_440);
+/// // for trade_event in trade_events { one_minute_candles.push_trade(&trade_event); }
+/// let latest_candle = one_minute_candles.latest();
+/// ```
+#[derive(Debug, Clone)]
+pub struct CandleAggregator {
+    interval_secs: i64,
+    capacity: usize,
+    candles: VecDeque<Candle>,
+}
+
+impl CandleAggregator {
+    /// Creates an aggregator with candles of `interval`, rounded down to whole seconds with a minimum of 1 second.
+    pub fn new(interval: Duration, capacity: usize) -> Self {
+        CandleAggregator {
+            interval_secs: interval.as_secs().max(1) as i64,
+            capacity: capacity.max(1),
+            candles: VecDeque::new(),
+        }
+    }
+
+    /// Adds a trade to the candle of its interval, opening it if needed. Trades too old to fit in the
+    /// latest `capacity` intervals are ignored.
+    pub fn push_trade(&mut self, trade_event: &TradeEvent) {
+        if trade_event.token_amount == 0 {
+            return;
+        }
+        let sol = trade_event.sol_amount as f64 / LAMPORTS_PER_SOL as f64;
+        let tokens = trade_event.token_amount as f64 / 10_f64.powi(PUMP_TOKEN_DECIMALS as i32);
+        let price = sol / tokens;
+        let start_time = trade_event.timestamp - trade_event.timestamp.rem_euclid(self.interval_secs);
+
+        self.open_candle(start_time, price);
+
+        // Late trades update the candle of their interval, if it is still kept
+        let is_latest_candle = self.candles.back().is_some_and(|candle| candle.start_time == start_time);
+        if let Some(candle) = self.candles.iter_mut().rev().find(|candle| candle.start_time == start_time) {
+            if candle.trades == 0 {
+                // The first trade of an opened or filled interval sets all its prices
+                *candle = empty_candle(start_time, price);
+            } else {
+                candle.high = candle.high.max(price);
+                candle.low = candle.low.min(price);
+                if is_latest_candle {
+                    candle.close = price;
+                }
+            }
+            candle.volume_sol += sol;
+            candle.volume_tokens += tokens;
+            candle.trades += 1;
+        }
+    }
+
+    /// Opens the candle of the interval starting at `start_time` at `price` if there is none, filling
+    /// the intervals between it and the kept candles with the close of the candle before them.
+    fn open_candle(&mut self, start_time: i64, price: f64) {
+        let (Some(oldest), Some(latest)) = (self.candles.front(), self.candles.back()) else {
+            self.candles.push_back(empty_candle(start_time, price));
+            return;
+        };
+        let kept_span = self.interval_secs.saturating_mul(i64::try_from(self.capacity - 1).unwrap_or(i64::MAX));
+        if start_time > latest.start_time {
+            // Intervals that would be dropped right away are not filled
+            let first_start_time = (latest.start_time + self.interval_secs).max(start_time.saturating_sub(kept_span));
+            let close = latest.close;
+            for filled_start_time in (first_start_time..start_time).step_by(self.interval_secs as usize) {
+                self.candles.push_back(empty_candle(filled_start_time, close));
+            }
+            self.candles.push_back(empty_candle(start_time, price));
+            while self.candles.len() > self.capacity {
+                self.candles.pop_front();
+            }
+        } else if start_time < oldest.start_time && latest.start_time - start_time <= kept_span {
+            let mut filled_start_time = oldest.start_time - self.interval_secs;
+            while filled_start_time > start_time {
+                self.candles.push_front(empty_candle(filled_start_time, price));
+                filled_start_time -= self.interval_secs;
+            }
+            self.candles.push_front(empty_candle(start_time, price));
+        } else if start_time >= oldest.start_time {
+            // Candles loaded with `load` can have gaps between them
+            let index = self.candles.partition_point(|candle| candle.start_time < start_time);
+            if self.candles[index].start_time != start_time {
+                self.candles.insert(index, empty_candle(start_time, price));
+            }
+        }
+    }
+
+    /// Candles from oldest to latest.
+    pub fn candles(&self) -> &VecDeque<Candle> {
+        &self.candles
+    }
+
+    /// The candle of the latest interval.
+    pub fn latest(&self) -> Option<&Candle> {
+        self.candles.back()
+    }
+
+    /// Writes the candles to a JSON file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PersistenceError> {
+        let json = serde_json::to_string(&self.candles)?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Creates an aggregator from candles previously saved with `save`.
+    pub fn load(path: impl AsRef<Path>, interval: Duration, capacity: usize) -> Result<Self, PersistenceError> {
+        let json = fs::read_to_string(path)?;
+        let candles: Vec<Candle> = serde_json::from_str(&json)?;
+        let mut aggregator = CandleAggregator::new(interval, capacity);
+        let skipped_candles = candles.len().saturating_sub(aggregator.capacity);
+        aggregator.candles = candles.into_iter().skip(skipped_candles).collect();
+        Ok(aggregator)
+    }
+}
+
+/// Candle without trades, all prices at `price`.
+fn empty_candle(start_time: i64, price: f64) -> Candle {
+    Candle {
+        start_time,
+        open: price,
+        high: price,
+        low: price,
+        close: price,
+        volume_sol: 0.0,
+        volume_tokens: 0.0,
+        trades: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn trade(timestamp: i64, sol_amount: u64, token_amount: u64) -> TradeEvent {
+        TradeEvent {
+            mint: Pubkey::default(),
+            sol_amount,
+            token_amount,
+            is_buy: true,
+            user: Pubkey::default(),
+            timestamp,
+            virtual_sol_reserves: 0,
+            virtual_token_reserves: 0,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_candles() {
+        let mut aggregator = CandleAggregator::new(Duration::from_secs(15), 2);
+        aggregator.push_trade(&trade(90, 1_000_000_000, 1_000_000));
+        aggregator.push_trade(&trade(100, 3_000_000_000, 1_000_000));
+        aggregator.push_trade(&trade(95, 4_000_000_000, 2_000_000));
+        aggregator.push_trade(&trade(106, 2_000_000_000, 1_000_000));
+        // late trade only updates the range of its candle
+        aggregator.push_trade(&trade(92, 5_000_000_000, 1_000_000));
+
+        let candles = aggregator.candles();
+        assert!(candles.len() == 2);
+        assert!(candles[0].start_time == 90);
+        assert!(candles[0].open == 1.0 && candles[0].high == 5.0 && candles[0].low == 1.0 && candles[0].close == 2.0);
+        assert!(candles[0].volume_sol == 13.0 && candles[0].trades == 4);
+        assert!(aggregator.latest().unwrap().start_time == 105);
+
+        // oldest candle is dropped when over capacity
+        aggregator.push_trade(&trade(121, 1_000_000_000, 1_000_000));
+        assert!(aggregator.candles().len() == 2);
+        assert!(aggregator.candles()[0].start_time == 105);
+    }
+
+    #[test]
+    fn test_trades_after_gap_fill_empty_intervals() {
+        let mut aggregator = CandleAggregator::new(Duration::from_secs(10), 6);
+        aggregator.push_trade(&trade(20, 1_000_000_000, 1_000_000));
+        aggregator.push_trade(&trade(55, 3_000_000_000, 1_000_000));
+
+        let candles = aggregator.candles();
+        assert!(candles.iter().map(|candle| candle.start_time).eq([20, 30, 40, 50]));
+        assert!(candles[1] == empty_candle(30, 1.0) && candles[2] == empty_candle(40, 1.0));
+        assert!(candles[3].open == 3.0 && candles[3].trades == 1);
+
+        // late trade in a filled interval sets its prices
+        aggregator.push_trade(&trade(42, 2_000_000_000, 1_000_000));
+        let candle = &aggregator.candles()[2];
+        assert!(candle.open == 2.0 && candle.close == 2.0 && candle.trades == 1 && candle.volume_sol == 2.0);
+
+        // late trade before the oldest candle opens its interval while there is capacity
+        aggregator.push_trade(&trade(5, 4_000_000_000, 1_000_000));
+        let candles = aggregator.candles();
+        assert!(candles.iter().map(|candle| candle.start_time).eq([0, 10, 20, 30, 40, 50]));
+        assert!(candles[0].open == 4.0 && candles[1] == empty_candle(10, 4.0));
+        aggregator.push_trade(&trade(-5, 4_000_000_000, 1_000_000));
+        assert!(aggregator.candles().len() == 6 && aggregator.candles()[0].start_time == 0);
+
+        // a gap longer than the capacity only keeps the latest intervals
+        aggregator.push_trade(&trade(1_000, 1_000_000_000, 1_000_000));
+        let candles = aggregator.candles();
+        assert!(candles.iter().map(|candle| candle.start_time).eq([950, 960, 970, 980, 990, 1_000]));
+        assert!(candles[0] == empty_candle(950, 3.0));
+    }
+
+    #[test]
+    fn test_save_and_load_candles() {
+        let mut aggregator = CandleAggregator::new(Duration::from_secs(1), 10);
+        aggregator.push_trade(&trade(1, 1_000_000_000, 1_000_000));
+        aggregator.push_trade(&trade(2, 2_000_000_000, 1_000_000));
+
+        let path = std::env::temp_dir().join(format!("candles_{}.json", Pubkey::new_unique()));
+        aggregator.save(&path).unwrap();
+        let loaded_aggregator = CandleAggregator::load(&path, Duration::from_secs(1), 1).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(loaded_aggregator.candles().len() == 1);
+        assert!(loaded_aggregator.latest() == aggregator.latest());
+    }
+}
@@ -0,0 +1,223 @@
+//! # Create
+//!
+//! Pump.fun token creation for the `TransactionBuilder`. The create instruction initializes the
+//! mint, its metadata and its bonding curve, and can be followed by a dev buy in the same
+//! transaction, which is then the first trade on the curve.
+
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signer::{keypair::Keypair, Signer}
+};
+use crate::{
+    constants::{
+        pumpfun_accounts::{
+            pumpfun_event_authority_account, pumpfun_global_account, pumpfun_program, pumpfun_token_mint_authority_program,
+            BONDING_CURVE_DISCRIMINATOR, CREATE_INSTRUCTION_DISCRIMINATOR
+        },
+        solana_programs::{associated_token_account_program, metadata_program, rent_program, system_program, token_program}
+    },
+    error::TransactionBuilderError,
+    utils::sol_to_lamports,
+    write_transactions::transaction_builder::TransactionBuilder
+};
+use super::{
+    bonding_curve::{derive_associated_bonding_curve, derive_bonding_curve, BondingCurveAccount},
+    global::{get_global_account, GlobalAccount},
+    quote::PumpQuote
+};
+
+// Length limits of the token metadata program
+const MAX_NAME_LENGTH: usize = 32;
+const MAX_SYMBOL_LENGTH: usize = 10;
+const MAX_URI_LENGTH: usize = 200;
+
+/// Derives the metadata account of a token.
+pub fn derive_metadata_account(mint: &Pubkey) -> Pubkey {
+    let metadata_program = metadata_program();
+    let (metadata_account, _bump_seed) = Pubkey::find_program_address(
+        &[b"metadata", metadata_program.as_ref(), mint.as_ref()],
+        &metadata_program
+    );
+    metadata_account
+}
+
+/// Creates a pump.fun create instruction for a new token, with `user` as its creator.
+///
+/// ### Arguments
+///
+/// * `user` - wallet creating the token and paying for its accounts.
+/// * `mint` - address of the new token, which has to sign the transaction.
+/// * `name` - name of the token.
+/// * `symbol` - symbol of the token.
+/// * `uri` - uri of the token metadata json, e.g an IPFS link.
+pub fn pump_create_instruction(user: &Pubkey, mint: &Pubkey, name: &str, symbol: &str, uri: &str) -> Instruction {
+    let mut data = CREATE_INSTRUCTION_DISCRIMINATOR.to_vec();
+    for text in [name, symbol, uri] {
+        data.extend_from_slice(&(text.len() as u32).to_le_bytes());
+        data.extend_from_slice(text.as_bytes());
+    }
+    data.extend_from_slice(user.as_ref());
+
+    Instruction {
+        program_id: pumpfun_program(),
+        accounts: vec![
+            AccountMeta::new(*mint, true),
+            AccountMeta::new_readonly(pumpfun_token_mint_authority_program(), false),
+            AccountMeta::new(derive_bonding_curve(mint), false),
+            AccountMeta::new(derive_associated_bonding_curve(mint), false),
+            AccountMeta::new_readonly(pumpfun_global_account(), false),
+            AccountMeta::new_readonly(metadata_program(), false),
+            AccountMeta::new(derive_metadata_account(mint), false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(system_program(), false),
+            AccountMeta::new_readonly(token_program(), false),
+            AccountMeta::new_readonly(associated_token_account_program(), false),
+            AccountMeta::new_readonly(rent_program(), false),
+            AccountMeta::new_readonly(pumpfun_event_authority_account(), false),
+            AccountMeta::new_readonly(pumpfun_program(), false),
+        ],
+        data,
+    }
+}
+
+/// Bonding curve of a token right after its creation.
+fn initial_bonding_curve(global_state: &GlobalAccount, creator: &Pubkey) -> BondingCurveAccount {
+    BondingCurveAccount {
+        discriminator: BONDING_CURVE_DISCRIMINATOR,
+        virtual_token_reserves: global_state.initial_virtual_token_reserves,
+        virtual_sol_reserves: global_state.initial_virtual_sol_reserves,
+        real_token_reserves: global_state.initial_real_token_reserves,
+        real_sol_reserves: 0,
+        total_token_supply: global_state.token_total_supply,
+        complete: false,
+        creator: Some(*creator),
+    }
+}
+
+impl<'a> TransactionBuilder<'a> {
+    /// Adds the creation of a pump.fun token with an optional dev buy of `dev_buy_sol` SOL, see
+    /// `create_pumpfun_token_lamports`.
+    pub fn create_pumpfun_token(
+        &mut self,
+        mint_keypair: &'a Keypair,
+        name: &str,
+        symbol: &str,
+        uri: &str,
+        dev_buy_sol: Option<f64>
+    ) -> Result<Option<PumpQuote>, TransactionBuilderError> {
+        self.create_pumpfun_token_lamports(mint_keypair, name, symbol, uri, dev_buy_sol.map(sol_to_lamports))
+    }
+
+    /// Adds the creation of a pump.fun token by the payer keypair, signed by `mint_keypair`, the
+    /// keypair of the new token. With `dev_buy_lamports`, the payer also buys the token for that
+    /// many lamports, fees included, right after creating it. The curve can not move between the
+    /// create and the buy, so the dev buy is quoted without slippage. The fee config of the
+    /// builder, if any, is charged on the SOL spent by the dev buy.
+    ///
+    /// Returns the quote of the dev buy, if any.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::InstructionError` if the name, symbol or uri exceed the
+    /// metadata length limits or the dev buy can not be quoted, and
+    /// `TransactionBuilderError::QueryError` if the global account can not be fetched for the dev buy.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use solana_sdk::signer::keypair::Keypair;
+    /// use easy_solana::{create_rpc_client, SecretKeypair, TransactionBuilder};
+    ///
+    /// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+    /// let keypair = SecretKeypair::from_env("PRIVATE_KEY_1").unwrap();
+    /// let mint_keypair = Keypair::new();
+    /// let mut builder = TransactionBuilder::new(&client, keypair.keypair());
+    /// builder.set_compute_limit(250_000);
+    /// builder.create_pumpfun_token_lamports(&mint_keypair, "Easy Solana", "EASY", "https://ipfs.io/ipfs/QmExample", Some(100_000_000)).unwrap();
+    /// let transaction = builder.build().unwrap();
+    /// ```
+    pub fn create_pumpfun_token_lamports(
+        &mut self,
+        mint_keypair: &'a Keypair,
+        name: &str,
+        symbol: &str,
+        uri: &str,
+        dev_buy_lamports: Option<u64>
+    ) -> Result<Option<PumpQuote>, TransactionBuilderError> {
+        for (field, text, max_length) in [("Name", name, MAX_NAME_LENGTH), ("Symbol", symbol, MAX_SYMBOL_LENGTH), ("Uri", uri, MAX_URI_LENGTH)] {
+            if text.len() > max_length {
+                return Err(TransactionBuilderError::InstructionError(format!("{} exceeds {} bytes", field, max_length)));
+            }
+        }
+        // Fetch the global account before adding instructions, so nothing is added on errors
+        let global_state = match dev_buy_lamports {
+            Some(_) => Some(get_global_account(self.client)?),
+            None => None,
+        };
+
+        let payer = self.payer_keypair.pubkey();
+        let mint = mint_keypair.pubkey();
+        self.instructions.push(pump_create_instruction(&payer, &mint, name, symbol, uri));
+        self.signing_keypairs.push(mint_keypair);
+
+        match (dev_buy_lamports, global_state) {
+            (Some(lamports), Some(global_state)) => {
+                let curve_state = initial_bonding_curve(&global_state, &payer);
+                self.push_pumpfun_buy(&mint, &curve_state, &global_state, lamports, 0).map(Some)
+            }
+            _ => {
+                self.apply_fee(0);
+                Ok(None)
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::hash::Hash;
+    use crate::{
+        pumpfun::bonding_curve::derive_creator_vault,
+        test_utils::{account_info_response, account_with_data, global_account_data, mock_client}
+    };
+
+    #[test]
+    fn test_create_pumpfun_token_with_dev_buy() {
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&account_with_data(pumpfun_program(), global_account_data())), 1)),
+        ]);
+        let (payer_keypair, mint_keypair) = (Keypair::new(), Keypair::new());
+
+        let mut builder = TransactionBuilder::new(&client, &payer_keypair);
+        let quote = builder.create_pumpfun_token(&mint_keypair, "Easy", "EASY", "https://example.com/easy.json", Some(1.0)).unwrap().unwrap();
+        assert!(quote.total_sol <= 1_000_000_000 && quote.token_amount > 0);
+        assert!(builder.instructions.len() == 3);
+
+        let create_instruction = &builder.instructions[0];
+        assert!(create_instruction.data[..8] == CREATE_INSTRUCTION_DISCRIMINATOR);
+        assert!(create_instruction.data[8..12] == 4_u32.to_le_bytes() && &create_instruction.data[12..16] == b"Easy");
+        assert!(create_instruction.data[create_instruction.data.len() - 32..] == payer_keypair.pubkey().to_bytes());
+        assert!(create_instruction.accounts[6].pubkey == derive_metadata_account(&mint_keypair.pubkey()));
+        // the dev buy is not lowered by slippage and pays creator fees to the payer
+        assert!(builder.instructions[2].data[8..16] == quote.token_amount.to_le_bytes());
+        assert!(builder.instructions[2].accounts[9].pubkey == derive_creator_vault(&payer_keypair.pubkey()));
+
+        let transaction = builder.build_with_blockhash(Hash::default()).unwrap();
+        assert!(transaction.signatures.len() == 2);
+        assert!(transaction.verify().is_ok());
+    }
+
+    #[test]
+    fn failing_test_create_pumpfun_token_with_long_symbol() {
+        let client = mock_client(vec![]);
+        let (payer_keypair, mint_keypair) = (Keypair::new(), Keypair::new());
+        let mut builder = TransactionBuilder::new(&client, &payer_keypair);
+        let result = builder.create_pumpfun_token(&mint_keypair, "Easy", "EASYSOLANAS", "", None);
+        assert!(matches!(result, Err(TransactionBuilderError::InstructionError(_))));
+        assert!(builder.instructions.is_empty() && builder.signing_keypairs.is_empty());
+    }
+}
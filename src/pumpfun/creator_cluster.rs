@@ -0,0 +1,428 @@
+//! # Creator Cluster
+//!
+//! Clusters the wallets around a pump.fun creator and reports how their launches went.
+//! Starting from the creator, the history of every wallet is read to find the tokens it
+//! launched and the wallets it exchanged SOL with, which are then scanned in turn up to
+//! `max_depth` funding hops. Wallets buying a launch in its creation slot are reported as
+//! related but not scanned.
+//!
+//! Every scanned wallet and launch costs one request per transaction read, keep the limits
+//! of `CreatorClusterConfig` small for interactive use.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    str::FromStr
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use crate::{
+    constants::pumpfun_accounts::{pumpfun_program, CREATE_INSTRUCTION_DISCRIMINATOR, PUMP_TOKEN_DECIMALS, PUMP_TOKEN_TOTAL_SUPPLY},
+    error::ReadTransactionError,
+    read_transactions::{
+        history::{get_transaction_history, sol_transfers_in_transaction, HistoryConfig},
+        transaction::{InspectedInstruction, InspectedTransaction}
+    }
+};
+use super::{
+    bonding_curve::{derive_bonding_curve, get_bonding_curve_account, BondingCurveAccount},
+    curve_math::price_in_sol,
+    trade_event::{trade_events_in_transaction, TradeEvent}
+};
+
+/// Index of the creator in the accounts of the pump.fun create instruction.
+const CREATE_USER_ACCOUNT_INDEX: usize = 7;
+
+/// Limits of a creator analysis.
+///
+/// ### Fields
+///
+/// - `wallet_history`: Transactions read for each scanned wallet.
+/// - `trade_history`: Transactions read from the bonding curve of each launch.
+/// - `max_depth`: Funding hops followed from the creator, 0 only scans the creator.
+/// - `max_wallets`: Maximum number of wallets scanned, including the creator.
+/// - `min_funding_lamports`: Minimum SOL exchanged with a wallet for it to be related.
+/// - `rug_drawdown`: Drop from the peak market cap, between 0 and 1, after which a launch that did not graduate is a rug.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreatorClusterConfig {
+    pub wallet_history: HistoryConfig,
+    pub trade_history: HistoryConfig,
+    pub max_depth: usize,
+    pub max_wallets: usize,
+    pub min_funding_lamports: u64,
+    pub rug_drawdown: f64,
+}
+
+impl Default for CreatorClusterConfig {
+    fn default() -> Self {
+        Self {
+            wallet_history: HistoryConfig { limit: Some(200), ..Default::default() },
+            trade_history: HistoryConfig { limit: Some(1_000), ..Default::default() },
+            max_depth: 1,
+            max_wallets: 10,
+            min_funding_lamports: LAMPORTS_PER_SOL / 10,
+            rug_drawdown: 0.9,
+        }
+    }
+}
+
+/// How a wallet is related to a wallet of the cluster.
+///
+/// - FundedBy: The related wallet sent `lamports` to the cluster wallet.
+/// - Funded: The cluster wallet sent `lamports` to the related wallet.
+/// - LaunchBuyer: The related wallet bought `mint` in the slot it was created in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalletRelation {
+    FundedBy { lamports: u64 },
+    Funded { lamports: u64 },
+    LaunchBuyer { mint: Pubkey },
+}
+
+/// A wallet related to the cluster.
+///
+/// ### Fields
+///
+/// - `wallet`: The related wallet.
+/// - `related_to`: The cluster wallet it is related to.
+/// - `relation`: How the wallets are related.
+/// - `depth`: Hops from the creator, 1 for wallets related to the creator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelatedWallet {
+    pub wallet: Pubkey,
+    pub related_to: Pubkey,
+    pub relation: WalletRelation,
+    pub depth: usize,
+}
+
+/// A token created on pump.fun.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Launch {
+    pub mint: Pubkey,
+    pub creator: Pubkey,
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+}
+
+/// Outcome of a launch, market caps are in SOL and read from the trades within `trade_history`.
+///
+/// ### Fields
+///
+/// - `launch`: The launch.
+/// - `trades`: Number of trades read.
+/// - `peak_market_cap_sol`: Highest market cap after a trade.
+/// - `last_market_cap_sol`: Market cap after the latest trade.
+/// - `graduated`: Whether the bonding curve completed and the token migrated.
+/// - `is_rug`: Whether the token did not graduate and its market cap dropped by `rug_drawdown` from its peak.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaunchOutcome {
+    pub launch: Launch,
+    pub trades: usize,
+    pub peak_market_cap_sol: f64,
+    pub last_market_cap_sol: f64,
+    pub graduated: bool,
+    pub is_rug: bool,
+}
+
+/// Wallets clustered around a creator and the outcomes of their launches.
+///
+/// ### Fields
+///
+/// - `creator`: The analysed creator.
+/// - `scanned_wallets`: Wallets whose history was read, starting with the creator.
+/// - `related_wallets`: Relations found, a wallet appears once per relation.
+/// - `launches`: Launches of the scanned wallets, oldest first.
+/// - `rug_rate`: Share of launches that are rugs, 0 without launches.
+/// - `average_peak_market_cap_sol`: Average peak market cap of the launches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreatorCluster {
+    pub creator: Pubkey,
+    pub scanned_wallets: Vec<Pubkey>,
+    pub related_wallets: Vec<RelatedWallet>,
+    pub launches: Vec<LaunchOutcome>,
+    pub rug_rate: f64,
+    pub average_peak_market_cap_sol: f64,
+}
+
+/// Finds the wallets related to a pump.fun creator through funding and prior launches, and
+/// reports the outcomes of the tokens launched by the creator and the related wallets.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InvalidAddress` if the creator is invalid, or an error if
+/// a history can not be read.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_client::rpc_client::RpcClient;
+/// use easy_solana::pumpfun::creator_cluster::{analyze_creator, CreatorClusterConfig};
+///
+/// let client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+/// let cluster = analyze_creator(&client, "9PvGbwGBQVJ5gVn5DjS6ubpkMaHcA5n1AmphFFaytaQR", &CreatorClusterConfig::default()).unwrap();
+/// println!("{} launches, {:.0}% rugged", cluster.launches.len(), cluster.rug_rate * 100.0);
+/// ```
+pub fn analyze_creator(client: &RpcClient, creator: &str, config: &CreatorClusterConfig) -> Result<CreatorCluster, ReadTransactionError> {
+    let creator = Pubkey::from_str(creator)?;
+    let mut queue = VecDeque::from([(creator, 0)]);
+    let mut depths = HashMap::new();
+    let mut scanned_wallets = Vec::new();
+    let mut related_wallets = Vec::new();
+    let mut launches: Vec<Launch> = Vec::new();
+
+    while let Some((wallet, depth)) = queue.pop_front() {
+        if scanned_wallets.len() >= config.max_wallets {
+            break;
+        }
+        if depths.contains_key(&wallet) {
+            continue;
+        }
+        depths.insert(wallet, depth);
+        scanned_wallets.push(wallet);
+
+        let history = get_transaction_history(client, &wallet.to_string(), &config.wallet_history)?;
+        for transaction in &history {
+            let new_launches = launches_in_transaction(transaction)
+                .into_iter()
+                .filter(|launch| launch.creator == wallet && !launches.iter().any(|known_launch| known_launch.mint == launch.mint))
+                .collect::<Vec<Launch>>();
+            launches.extend(new_launches);
+        }
+        if depth >= config.max_depth {
+            continue;
+        }
+        for (counterparty, relation) in funding_relations(&wallet, &history, config.min_funding_lamports) {
+            related_wallets.push(RelatedWallet { wallet: counterparty, related_to: wallet, relation, depth: depth + 1 });
+            queue.push_back((counterparty, depth + 1));
+        }
+    }
+
+    let mut outcomes = Vec::new();
+    for launch in launches {
+        let bonding_curve = derive_bonding_curve(&launch.mint);
+        let history = get_transaction_history(client, &bonding_curve.to_string(), &config.trade_history)?;
+        let mut trade_events = Vec::new();
+        for transaction in &history {
+            for trade_event in trade_events_in_transaction(transaction).into_iter().filter(|trade_event| trade_event.mint == launch.mint) {
+                let is_launch_buyer = trade_event.is_buy && transaction.slot == launch.slot && trade_event.user != launch.creator;
+                let is_known = related_wallets.iter().any(|related_wallet| {
+                    related_wallet.wallet == trade_event.user && related_wallet.relation == WalletRelation::LaunchBuyer { mint: launch.mint }
+                });
+                if is_launch_buyer && !is_known {
+                    related_wallets.push(RelatedWallet {
+                        wallet: trade_event.user,
+                        related_to: launch.creator,
+                        relation: WalletRelation::LaunchBuyer { mint: launch.mint },
+                        depth: depths.get(&launch.creator).copied().unwrap_or_default() + 1,
+                    });
+                }
+                trade_events.push(trade_event);
+            }
+        }
+        let curve_state = get_bonding_curve_account(client, &launch.mint.to_string()).map(|(_, curve_state)| curve_state);
+        outcomes.push(launch_outcome(launch, &trade_events, curve_state.as_ref(), config.rug_drawdown));
+    }
+    outcomes.sort_by_key(|outcome| outcome.launch.slot);
+
+    let (rug_rate, average_peak_market_cap_sol) = if outcomes.is_empty() {
+        (0.0, 0.0)
+    } else {
+        let launch_count = outcomes.len() as f64;
+        (
+            outcomes.iter().filter(|outcome| outcome.is_rug).count() as f64 / launch_count,
+            outcomes.iter().map(|outcome| outcome.peak_market_cap_sol).sum::<f64>() / launch_count,
+        )
+    };
+
+    Ok(CreatorCluster {
+        creator,
+        scanned_wallets,
+        related_wallets,
+        launches: outcomes,
+        rug_rate,
+        average_peak_market_cap_sol,
+    })
+}
+
+/// Extracts the pump.fun tokens created by a transaction, from top level and inner instructions.
+/// Failed transactions have no launches.
+pub fn launches_in_transaction(transaction: &InspectedTransaction) -> Vec<Launch> {
+    if transaction.error.is_some() {
+        return vec![];
+    }
+    let pumpfun_program = pumpfun_program();
+    let is_create_instruction = |instruction: &&InspectedInstruction| {
+        instruction.program_id == pumpfun_program && instruction.data.starts_with(&CREATE_INSTRUCTION_DISCRIMINATOR)
+    };
+    transaction.instructions
+        .iter()
+        .chain(transaction.inner_instructions.iter().flat_map(|inner_instructions| &inner_instructions.instructions))
+        .filter(is_create_instruction)
+        .filter_map(|instruction| Some(Launch {
+            mint: *instruction.accounts.first()?,
+            creator: *instruction.accounts.get(CREATE_USER_ACCOUNT_INDEX)?,
+            signature: transaction.signature.clone(),
+            slot: transaction.slot,
+            block_time: transaction.block_time,
+        }))
+        .collect()
+}
+
+/// Computes the outcome of a launch from its trades, oldest first, and its current bonding curve.
+/// A launch without a bonding curve account is assumed not to have graduated.
+pub fn launch_outcome(launch: Launch, trade_events: &[TradeEvent], curve_state: Option<&BondingCurveAccount>, rug_drawdown: f64) -> LaunchOutcome {
+    let total_supply = curve_state
+        .map(|curve_state| curve_state.total_token_supply)
+        .filter(|total_supply| *total_supply > 0)
+        .unwrap_or(PUMP_TOKEN_TOTAL_SUPPLY);
+    let ui_total_supply = total_supply as f64 / 10_f64.powi(PUMP_TOKEN_DECIMALS as i32);
+    let market_caps: Vec<f64> = trade_events
+        .iter()
+        .map(|trade_event| {
+            price_in_sol(trade_event.virtual_sol_reserves as u128, trade_event.virtual_token_reserves as u128, PUMP_TOKEN_DECIMALS) * ui_total_supply
+        })
+        .collect();
+    let peak_market_cap_sol = market_caps.iter().copied().fold(0.0, f64::max);
+    let last_market_cap_sol = market_caps.last().copied().unwrap_or_default();
+    let graduated = curve_state.is_some_and(|curve_state| curve_state.complete);
+    let is_rug = !graduated && !market_caps.is_empty() && last_market_cap_sol <= peak_market_cap_sol * (1.0 - rug_drawdown);
+
+    LaunchOutcome {
+        launch,
+        trades: trade_events.len(),
+        peak_market_cap_sol,
+        last_market_cap_sol,
+        graduated,
+        is_rug,
+    }
+}
+
+/// Wallets that exchanged at least `min_lamports` with `wallet`, in the order first seen.
+fn funding_relations(wallet: &Pubkey, history: &[InspectedTransaction], min_lamports: u64) -> Vec<(Pubkey, WalletRelation)> {
+    let mut counterparties: Vec<(Pubkey, bool)> = Vec::new();
+    let mut totals: HashMap<(Pubkey, bool), u64> = HashMap::new();
+    let mut seen = HashSet::new();
+    for transfer in history.iter().flat_map(sol_transfers_in_transaction) {
+        let counterparty = match (transfer.from == *wallet, transfer.to == *wallet) {
+            (false, true) => (transfer.from, true),
+            (true, false) => (transfer.to, false),
+            _ => continue,
+        };
+        if seen.insert(counterparty) {
+            counterparties.push(counterparty);
+        }
+        let total = totals.entry(counterparty).or_default();
+        *total = total.saturating_add(transfer.lamports);
+    }
+
+    counterparties
+        .into_iter()
+        .filter(|counterparty| totals[counterparty] >= min_lamports)
+        .map(|(counterparty, is_incoming)| {
+            let lamports = totals[&(counterparty, is_incoming)];
+            let relation = if is_incoming { WalletRelation::FundedBy { lamports } } else { WalletRelation::Funded { lamports } };
+            (counterparty, relation)
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_transactions::transaction::InspectedInnerInstructions;
+
+    fn transaction(slot: u64, instructions: Vec<InspectedInstruction>, inner_instructions: Vec<InspectedInnerInstructions>) -> InspectedTransaction {
+        InspectedTransaction {
+            signature: format!("signature-{}", slot),
+            slot,
+            block_time: Some(slot as i64),
+            is_versioned: false,
+            account_keys: vec![],
+            instructions,
+            inner_instructions,
+            logs: vec![],
+            fee: 5_000,
+            error: None,
+        }
+    }
+
+    fn create_instruction(mint: Pubkey, creator: Pubkey) -> InspectedInstruction {
+        let mut accounts = vec![mint];
+        accounts.extend((1..CREATE_USER_ACCOUNT_INDEX).map(|_| Pubkey::new_unique()));
+        accounts.push(creator);
+        InspectedInstruction { program_id: pumpfun_program(), accounts, data: CREATE_INSTRUCTION_DISCRIMINATOR.to_vec(), stack_height: None }
+    }
+
+    fn trade(mint: Pubkey, virtual_sol_reserves: u64) -> TradeEvent {
+        TradeEvent {
+            mint,
+            sol_amount: 0,
+            token_amount: 0,
+            is_buy: true,
+            user: Pubkey::new_unique(),
+            timestamp: 0,
+            virtual_sol_reserves,
+            virtual_token_reserves: PUMP_TOKEN_TOTAL_SUPPLY,
+        }
+    }
+
+    #[test]
+    fn test_launches_in_transaction() {
+        let (mint, bundled_mint, creator) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let inner_instructions = vec![InspectedInnerInstructions { index: 0, instructions: vec![create_instruction(bundled_mint, creator)] }];
+        let mut transaction = transaction(7, vec![create_instruction(mint, creator)], inner_instructions);
+
+        let launches = launches_in_transaction(&transaction);
+        assert!(launches.len() == 2 && launches[0].mint == mint && launches[1].mint == bundled_mint);
+        assert!(launches.iter().all(|launch| launch.creator == creator && launch.slot == 7));
+
+        transaction.error = Some(solana_sdk::transaction::TransactionError::AccountNotFound);
+        assert!(launches_in_transaction(&transaction).is_empty());
+    }
+
+    #[test]
+    fn test_launch_outcome() {
+        let mint = Pubkey::new_unique();
+        let launch = Launch { mint, creator: Pubkey::new_unique(), signature: String::new(), slot: 1, block_time: None };
+        // 1 SOL of virtual reserves against the whole supply is a market cap of 1 SOL
+        let trades = vec![trade(mint, 30 * LAMPORTS_PER_SOL), trade(mint, 80 * LAMPORTS_PER_SOL), trade(mint, 5 * LAMPORTS_PER_SOL)];
+
+        let outcome = launch_outcome(launch.clone(), &trades, None, 0.9);
+        assert!((outcome.peak_market_cap_sol - 80.0).abs() < 1e-9 && (outcome.last_market_cap_sol - 5.0).abs() < 1e-9);
+        assert!(outcome.trades == 3 && !outcome.graduated && outcome.is_rug);
+
+        let curve_state = BondingCurveAccount {
+            discriminator: [0; 8],
+            virtual_token_reserves: 0,
+            virtual_sol_reserves: 0,
+            real_token_reserves: 0,
+            real_sol_reserves: 0,
+            total_token_supply: PUMP_TOKEN_TOTAL_SUPPLY,
+            complete: true,
+            creator: None,
+        };
+        let outcome = launch_outcome(launch, &trades, Some(&curve_state), 0.9);
+        assert!(outcome.graduated && !outcome.is_rug);
+    }
+
+    #[test]
+    fn test_funding_relations() {
+        let (wallet, funder, recipient) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = |from: &Pubkey, to: &Pubkey, lamports: u64| {
+            let instruction = solana_system_interface::instruction::transfer(from, to, lamports);
+            InspectedInstruction {
+                program_id: instruction.program_id,
+                accounts: instruction.accounts.iter().map(|account| account.pubkey).collect(),
+                data: instruction.data,
+                stack_height: None,
+            }
+        };
+        let history = vec![
+            transaction(1, vec![instruction(&funder, &wallet, LAMPORTS_PER_SOL)], vec![]),
+            transaction(2, vec![instruction(&wallet, &recipient, 1_000), instruction(&funder, &wallet, LAMPORTS_PER_SOL)], vec![]),
+        ];
+
+        let relations = funding_relations(&wallet, &history, LAMPORTS_PER_SOL / 10);
+        assert!(relations == vec![(funder, WalletRelation::FundedBy { lamports: 2 * LAMPORTS_PER_SOL })]);
+    }
+}
@@ -0,0 +1,375 @@
+//! # Curve Math
+//!
+//! Constant product math of pump.fun bonding curves on raw reserves, and invariant checks
+//! guarding it against rounding in favour of the trader. Every division rounds against the
+//! trader, so a buy followed by a sell of the same tokens never returns more SOL than was paid.
+//!
+//! All amounts are `u128` lamports or raw token units computed with checked arithmetic, overflows
+//! surface as `MathError` instead of wrapping or losing precision in floating point.
+
+use crate::error::{CurveInvariantError, MathError};
+use super::{
+    bonding_curve::BondingCurveAccount,
+    global::GlobalAccount,
+    quote::{quote_buy, quote_sell, PumpQuote}
+};
+
+/// `value * numerator / denominator` rounded down.
+///
+/// ## Errors
+///
+/// Returns `MathError` naming `operation` if the product overflows or `denominator` is zero.
+pub fn mul_div_floor(value: u128, numerator: u128, denominator: u128, operation: &'static str) -> Result<u128, MathError> {
+    let product = value.checked_mul(numerator).ok_or(MathError::Overflow(operation))?;
+    product.checked_div(denominator).ok_or(MathError::DivisionByZero(operation))
+}
+
+/// `value * numerator / denominator` rounded up.
+///
+/// ## Errors
+///
+/// Returns `MathError` naming `operation` if the product overflows or `denominator` is zero.
+pub fn mul_div_ceil(value: u128, numerator: u128, denominator: u128, operation: &'static str) -> Result<u128, MathError> {
+    if denominator == 0 {
+        return Err(MathError::DivisionByZero(operation));
+    }
+    let product = value.checked_mul(numerator).ok_or(MathError::Overflow(operation))?;
+    Ok(product.div_ceil(denominator))
+}
+
+/// Converts an amount back to the `u64` used on chain.
+///
+/// ## Errors
+///
+/// Returns `MathError::Overflow` naming `operation` if `value` does not fit.
+pub fn to_u64(value: u128, operation: &'static str) -> Result<u64, MathError> {
+    u64::try_from(value).map_err(|_| MathError::Overflow(operation))
+}
+
+/// Tokens bought with `sol_in` lamports, excluding fees, rounded down.
+///
+/// ## Errors
+///
+/// Returns `MathError` if the reserves overflow or are zero.
+pub fn tokens_out(virtual_sol_reserves: u128, virtual_token_reserves: u128, sol_in: u128) -> Result<u128, MathError> {
+    let sol_reserves_after = virtual_sol_reserves.checked_add(sol_in).ok_or(MathError::Overflow("tokens out"))?;
+    mul_div_floor(sol_in, virtual_token_reserves, sol_reserves_after, "tokens out")
+}
+
+/// Lamports needed to buy `token_out` tokens, excluding fees, rounded up.
+///
+/// ## Errors
+///
+/// Returns `MathError::DivisionByZero` if the curve does not hold more than `token_out` virtual
+/// tokens and `MathError::Overflow` if the cost overflows.
+pub fn sol_in_for_tokens(virtual_sol_reserves: u128, virtual_token_reserves: u128, token_out: u128) -> Result<u128, MathError> {
+    let remaining_tokens = virtual_token_reserves.checked_sub(token_out).ok_or(MathError::DivisionByZero("sol in"))?;
+    mul_div_ceil(token_out, virtual_sol_reserves, remaining_tokens, "sol in")
+}
+
+/// Lamports received for selling `token_in` tokens, excluding fees, rounded down.
+///
+/// ## Errors
+///
+/// Returns `MathError` if the reserves overflow or are zero.
+pub fn sol_out(virtual_sol_reserves: u128, virtual_token_reserves: u128, token_in: u128) -> Result<u128, MathError> {
+    let token_reserves_after = virtual_token_reserves.checked_add(token_in).ok_or(MathError::Overflow("sol out"))?;
+    mul_div_floor(token_in, virtual_sol_reserves, token_reserves_after, "sol out")
+}
+
+/// Price in SOL per token of a lamport amount and a raw token amount with `decimals`. The
+/// amounts are kept exact and only the ratio is converted to floating point for display.
+pub fn price_in_sol(lamports: u128, token_amount: u128, decimals: u32) -> f64 {
+    if token_amount == 0 {
+        return 0.0;
+    }
+    let scale = 10_f64.powi(decimals as i32) / solana_sdk::native_token::LAMPORTS_PER_SOL as f64;
+    ratio(lamports, token_amount) * scale
+}
+
+/// `numerator / denominator` as a float, dividing first so large amounts keep their precision.
+pub fn ratio(numerator: u128, denominator: u128) -> f64 {
+    let whole = numerator / denominator;
+    let remainder = numerator % denominator;
+    whole as f64 + remainder as f64 / denominator as f64
+}
+
+/// Product of the virtual reserves, which trades never decrease.
+pub fn constant_product(curve_state: &BondingCurveAccount) -> u128 {
+    curve_state.virtual_sol_reserves as u128 * curve_state.virtual_token_reserves as u128
+}
+
+/// The curve after the buy of `quote`, fees are not added to the reserves.
+pub fn apply_buy(curve_state: &BondingCurveAccount, quote: &PumpQuote) -> BondingCurveAccount {
+    BondingCurveAccount {
+        virtual_sol_reserves: curve_state.virtual_sol_reserves.saturating_add(quote.sol_amount),
+        virtual_token_reserves: curve_state.virtual_token_reserves.saturating_sub(quote.token_amount),
+        real_sol_reserves: curve_state.real_sol_reserves.saturating_add(quote.sol_amount),
+        real_token_reserves: curve_state.real_token_reserves.saturating_sub(quote.token_amount),
+        ..curve_state.clone()
+    }
+}
+
+/// The curve after the sell of `quote`, fees are not removed from the reserves.
+pub fn apply_sell(curve_state: &BondingCurveAccount, quote: &PumpQuote) -> BondingCurveAccount {
+    BondingCurveAccount {
+        virtual_sol_reserves: curve_state.virtual_sol_reserves.saturating_sub(quote.sol_amount),
+        virtual_token_reserves: curve_state.virtual_token_reserves.saturating_add(quote.token_amount),
+        real_sol_reserves: curve_state.real_sol_reserves.saturating_sub(quote.sol_amount),
+        real_token_reserves: curve_state.real_token_reserves.saturating_add(quote.token_amount),
+        ..curve_state.clone()
+    }
+}
+
+/// Quotes of a buy and the sell of the bought tokens checked by `verify_curve_roundtrip`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurveRoundtrip {
+    pub buy: PumpQuote,
+    pub sell: PumpQuote,
+}
+
+impl CurveRoundtrip {
+    /// Lamports lost to fees and rounding.
+    pub fn loss(&self) -> u64 {
+        self.buy.total_sol - self.sell.total_sol
+    }
+}
+
+/// Buys with `sol_amount` lamports, sells the bought tokens right after and checks that
+///
+/// - the buy spends at most `sol_amount` lamports, fees included,
+/// - neither trade decreases the product of the virtual reserves,
+/// - the sell returns at most what the buy cost, net of fees.
+///
+/// ## Errors
+///
+/// Returns `CurveInvariantError::CurveError` if the curve can not be traded and another
+/// `CurveInvariantError` naming the broken invariant otherwise.
+///
+/// ## Example
+///
+/// ```rust
+/// use easy_solana::pumpfun::{
+///     bonding_curve::BondingCurveAccount,
+///     curve_math::verify_curve_roundtrip,
+///     global::GlobalAccount
+/// };
+/// use easy_solana::constants::pumpfun_accounts::BONDING_CURVE_DISCRIMINATOR;
+/// use solana_sdk::pubkey::Pubkey;
+///
+/// let curve = BondingCurveAccount {
+///     discriminator: BONDING_CURVE_DISCRIMINATOR,
+///     virtual_token_reserves: 1_073_000_000_000_000,
+///     virtual_sol_reserves: 30_000_000_000,
+///     real_token_reserves: 793_100_000_000_000,
+///     real_sol_reserves: 0,
+///     total_token_supply: 1_000_000_000_000_000,
+///     complete: false,
+///     creator: None,
+/// };
+/// let global = GlobalAccount {
+///     initialized: true,
+///     authority: Pubkey::default(),
+///     fee_recipient: Pubkey::default(),
+///     initial_virtual_token_reserves: 1_073_000_000_000_000,
+///     initial_virtual_sol_reserves: 30_000_000_000,
+///     initial_real_token_reserves: 793_100_000_000_000,
+///     token_total_supply: 1_000_000_000_000_000,
+///     fee_basis_points: 95,
+///     creator_fee_basis_points: 5,
+/// };
+/// let roundtrip = verify_curve_roundtrip(&curve, &global, 1_000_000_000).unwrap();
+/// assert!(roundtrip.loss() > 0);
+/// ```
+pub fn verify_curve_roundtrip(
+    curve_state: &BondingCurveAccount,
+    global_state: &GlobalAccount,
+    sol_amount: u64
+) -> Result<CurveRoundtrip, CurveInvariantError> {
+    let buy = quote_buy(curve_state, global_state, sol_amount)?;
+    if buy.total_sol > sol_amount {
+        return Err(CurveInvariantError::Overspent { requested: sol_amount, paid: buy.total_sol });
+    }
+    let after_buy = apply_buy(curve_state, &buy);
+    check_product(curve_state, &after_buy)?;
+
+    let sell = quote_sell(&after_buy, global_state, buy.token_amount)?;
+    check_product(&after_buy, &apply_sell(&after_buy, &sell))?;
+    if sell.total_sol > buy.total_sol {
+        return Err(CurveInvariantError::RoundtripProfit { paid: buy.total_sol, received: sell.total_sol });
+    }
+    Ok(CurveRoundtrip { buy, sell })
+}
+
+/// Entry point for fuzzers such as `cargo fuzz`, running `verify_curve_roundtrip` on a curve,
+/// fees and amount read from `data`. Inputs that do not describe a tradeable curve pass.
+///
+/// `data` holds the virtual SOL, virtual token and real token reserves and the SOL amount as
+/// little endian `u64`s limited to 63 bits, followed by the protocol and creator fees as little
+/// endian `u16`s limited to 1_000 basis points. Shorter inputs pass.
+///
+/// ## Errors
+///
+/// Returns the `CurveInvariantError` of a broken invariant.
+pub fn fuzz_curve_roundtrip(data: &[u8]) -> Result<(), CurveInvariantError> {
+    if data.len() < 36 {
+        return Ok(());
+    }
+    let word = |index: usize| {
+        let bytes: [u8; 8] = data[index * 8..index * 8 + 8].try_into().unwrap_or_default();
+        u64::from_le_bytes(bytes) >> 1
+    };
+    let basis_points = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]) as u64 % 1_001;
+
+    let virtual_token_reserves = word(1);
+    let curve_state = BondingCurveAccount {
+        discriminator: crate::constants::pumpfun_accounts::BONDING_CURVE_DISCRIMINATOR,
+        virtual_sol_reserves: word(0),
+        virtual_token_reserves,
+        real_token_reserves: word(2).min(virtual_token_reserves.saturating_sub(1)),
+        real_sol_reserves: 0,
+        total_token_supply: virtual_token_reserves,
+        complete: false,
+        creator: Some(solana_sdk::pubkey::Pubkey::new_from_array([1; 32])),
+    };
+    let global_state = GlobalAccount {
+        initialized: true,
+        authority: Default::default(),
+        fee_recipient: Default::default(),
+        initial_virtual_token_reserves: curve_state.virtual_token_reserves,
+        initial_virtual_sol_reserves: curve_state.virtual_sol_reserves,
+        initial_real_token_reserves: curve_state.real_token_reserves,
+        token_total_supply: curve_state.total_token_supply,
+        fee_basis_points: basis_points(32),
+        creator_fee_basis_points: basis_points(34),
+    };
+    match verify_curve_roundtrip(&curve_state, &global_state, word(3)) {
+        Ok(_) | Err(CurveInvariantError::CurveError(_)) => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+fn check_product(before: &BondingCurveAccount, after: &BondingCurveAccount) -> Result<(), CurveInvariantError> {
+    let (before, after) = (constant_product(before), constant_product(after));
+    if after < before {
+        return Err(CurveInvariantError::ProductDecreased { before, after });
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic xorshift generator, so failing cases can be reproduced from the seed.
+    struct Generator(u64);
+
+    impl Generator {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn range(&mut self, min: u64, max: u64) -> u64 {
+            min + self.next() % (max - min + 1)
+        }
+    }
+
+    #[test]
+    fn test_primitives_round_against_trader() {
+        assert!(tokens_out(30, 1_000, 3) == Ok(90));
+        // 90 tokens need 2.96 lamports
+        assert!(sol_in_for_tokens(30, 1_000, 90) == Ok(3));
+        assert!(sol_in_for_tokens(30, 1_000, 1_000) == Err(MathError::DivisionByZero("sol in")));
+        assert!(sol_out(33, 910, 90) == Ok(2));
+    }
+
+    #[test]
+    fn test_checked_math_surfaces_overflow() {
+        assert!(mul_div_floor(u128::MAX, 2, 3, "test") == Err(MathError::Overflow("test")));
+        assert!(mul_div_ceil(7, 1, 2, "test") == Ok(4));
+        assert!(to_u64(u64::MAX as u128 + 1, "test") == Err(MathError::Overflow("test")));
+        assert!(tokens_out(u128::MAX, 1, 1) == Err(MathError::Overflow("tokens out")));
+    }
+
+    #[test]
+    fn test_price_keeps_precision_on_large_reserves() {
+        // Above 2^53 lamports a float can not represent every amount
+        let lamports = (1_u128 << 60) + 1;
+        assert!(ratio(lamports * 3, 3) == lamports as f64);
+        assert!(price_in_sol(30_000_000_000, 1_073_000_000_000_000, 6) == 30.0 / 1_073_000_000.0);
+    }
+
+    #[test]
+    fn test_roundtrip_invariants_hold_for_random_curves() {
+        let mut generator = Generator(0x5eed_c0de);
+        for _ in 0..2_000 {
+            let virtual_token_reserves = generator.range(1_000, 1_100_000_000_000_000);
+            let curve_state = BondingCurveAccount {
+                discriminator: crate::constants::pumpfun_accounts::BONDING_CURVE_DISCRIMINATOR,
+                virtual_token_reserves,
+                virtual_sol_reserves: generator.range(1, 500_000_000_000),
+                real_token_reserves: generator.range(0, virtual_token_reserves - 1),
+                real_sol_reserves: 0,
+                total_token_supply: virtual_token_reserves,
+                complete: false,
+                creator: generator.next().is_multiple_of(2).then(solana_sdk::pubkey::Pubkey::new_unique),
+            };
+            let global_state = GlobalAccount {
+                initialized: true,
+                authority: Default::default(),
+                fee_recipient: Default::default(),
+                initial_virtual_token_reserves: 0,
+                initial_virtual_sol_reserves: 0,
+                initial_real_token_reserves: 0,
+                token_total_supply: 0,
+                fee_basis_points: generator.range(0, 500),
+                creator_fee_basis_points: generator.range(0, 100),
+            };
+            let sol_amount = generator.range(0, 1_000_000_000_000);
+            let result = verify_curve_roundtrip(&curve_state, &global_state, sol_amount);
+            assert!(result.is_ok(), "{:?} {:?} {} {:?}", curve_state, global_state, sol_amount, result);
+        }
+    }
+
+    #[test]
+    fn test_fuzz_entry_point() {
+        let mut generator = Generator(42);
+        for _ in 0..2_000 {
+            let data: Vec<u8> = (0..36).map(|_| generator.next() as u8).collect();
+            assert!(fuzz_curve_roundtrip(&data).is_ok(), "{:?}", data);
+        }
+        assert!(fuzz_curve_roundtrip(&[0xff; 36]).is_ok());
+        assert!(fuzz_curve_roundtrip(&[1; 3]).is_ok());
+    }
+
+    #[test]
+    fn failing_test_roundtrip_on_completed_curve() {
+        let curve_state = BondingCurveAccount {
+            discriminator: crate::constants::pumpfun_accounts::BONDING_CURVE_DISCRIMINATOR,
+            virtual_token_reserves: 1_000,
+            virtual_sol_reserves: 1_000,
+            real_token_reserves: 500,
+            real_sol_reserves: 0,
+            total_token_supply: 1_000,
+            complete: true,
+            creator: None,
+        };
+        let global_state = GlobalAccount {
+            initialized: true,
+            authority: Default::default(),
+            fee_recipient: Default::default(),
+            initial_virtual_token_reserves: 0,
+            initial_virtual_sol_reserves: 0,
+            initial_real_token_reserves: 0,
+            token_total_supply: 0,
+            fee_basis_points: 100,
+            creator_fee_basis_points: 0,
+        };
+        let result = verify_curve_roundtrip(&curve_state, &global_state, 100);
+        assert!(matches!(result, Err(CurveInvariantError::CurveError(_))));
+    }
+}
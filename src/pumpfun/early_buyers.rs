@@ -0,0 +1,218 @@
+//! # Early Buyers
+//!
+//! Finds the first wallets buying a pump.fun token by walking its history from the launch,
+//! and checks whether they still hold what they bought. Wallets buying within
+//! `SNIPER_SLOT_WINDOW` slots of the launch are flagged as snipers.
+
+use std::str::FromStr;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::{extension::StateWithExtensions, state::Account as Token2022Account};
+use crate::{
+    error::ReadTransactionError,
+    read_transactions::{
+        batch::MAX_MULTIPLE_ACCOUNTS,
+        history::{get_signature_history, HistoryConfig},
+        transaction::{get_transaction, InspectedTransaction}
+    }
+};
+use super::{creator_cluster::launches_in_transaction, trade_event::trade_events_in_transaction};
+
+/// Slots after the launch within which a buyer is considered a sniper.
+pub const SNIPER_SLOT_WINDOW: u64 = 2;
+
+/// One of the first buyers of a token.
+///
+/// ### Fields
+///
+/// - `wallet`: The buyer.
+/// - `rank`: Order of the first buy, starting at 1.
+/// - `first_buy_signature`: Transaction of the first buy.
+/// - `slots_after_launch`: Slots between the launch and the first buy.
+/// - `block_time`: Estimated unix timestamp of the first buy, if available.
+/// - `sol_spent`: Lamports spent on the buys seen while searching for the first buyers, excluding fees.
+/// - `tokens_bought`: Tokens bought without decimals over the same buys.
+/// - `current_balance`: Tokens currently held in the associated token account.
+/// - `has_sold`: Whether the buyer holds less than it bought, by selling or moving the tokens.
+/// - `is_creator`: Whether the buyer created the token.
+/// - `is_sniper`: Whether the first buy landed within `SNIPER_SLOT_WINDOW` slots of the launch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EarlyBuyer {
+    pub wallet: Pubkey,
+    pub rank: usize,
+    pub first_buy_signature: String,
+    pub slots_after_launch: u64,
+    pub block_time: Option<i64>,
+    pub sol_spent: u64,
+    pub tokens_bought: u64,
+    pub current_balance: u64,
+    pub has_sold: bool,
+    pub is_creator: bool,
+    pub is_sniper: bool,
+}
+
+/// Gets the first `n` wallets that bought a pump.fun token, their buy sizes, and whether they
+/// have sold since. The whole signature history of the mint is paged to reach the launch, then
+/// transactions are fetched oldest first until `n` buyers are found.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InvalidAddress` if the mint is invalid, or an error if the
+/// history or the token accounts of the buyers can not be fetched.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_client::rpc_client::RpcClient;
+/// use easy_solana::pumpfun::early_buyers::get_early_buyers;
+///
+/// let client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+/// let early_buyers = get_early_buyers(&client, "2qEHjDLDLbuBgRYvsxhc5D6uDWAivNFZGan56P1tpump", 20).unwrap();
+/// let snipers_holding = early_buyers.iter().filter(|buyer| buyer.is_sniper && !buyer.has_sold).count();
+/// ```
+pub fn get_early_buyers(client: &RpcClient, mint: &str, n: usize) -> Result<Vec<EarlyBuyer>, ReadTransactionError> {
+    let mint_pubkey = Pubkey::from_str(mint)?;
+    if n == 0 {
+        return Ok(vec![]);
+    }
+    let signatures = get_signature_history(client, mint, &HistoryConfig { limit: None, ..Default::default() })?;
+
+    let mut scan = EarlyBuyerScan::new(mint_pubkey, n);
+    for signature_info in signatures.iter().rev() {
+        scan.push_transaction(&get_transaction(client, &signature_info.signature)?);
+        if scan.early_buyers.len() == n {
+            break;
+        }
+    }
+    let mut early_buyers = scan.early_buyers;
+
+    let token_program = client.get_account(&mint_pubkey)?.owner;
+    let token_accounts: Vec<Pubkey> = early_buyers
+        .iter()
+        .map(|early_buyer| get_associated_token_address_with_program_id(&early_buyer.wallet, &mint_pubkey, &token_program))
+        .collect();
+    let mut balances = Vec::with_capacity(token_accounts.len());
+    for token_accounts_chunk in token_accounts.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        balances.extend(client.get_multiple_accounts(token_accounts_chunk)?.into_iter().map(|account| {
+            account
+                .and_then(|account| StateWithExtensions::<Token2022Account>::unpack(&account.data).ok().map(|token_account| token_account.base.amount))
+                .unwrap_or_default()
+        }));
+    }
+    for (early_buyer, balance) in early_buyers.iter_mut().zip(balances) {
+        early_buyer.current_balance = balance;
+        early_buyer.has_sold = balance < early_buyer.tokens_bought;
+    }
+
+    Ok(early_buyers)
+}
+
+/// First buyers found in transactions pushed oldest first, the first transaction being the launch.
+struct EarlyBuyerScan {
+    mint: Pubkey,
+    n: usize,
+    launch: Option<(u64, Option<Pubkey>)>,
+    early_buyers: Vec<EarlyBuyer>,
+}
+
+impl EarlyBuyerScan {
+    fn new(mint: Pubkey, n: usize) -> Self {
+        Self { mint, n, launch: None, early_buyers: Vec::new() }
+    }
+
+    fn push_transaction(&mut self, transaction: &InspectedTransaction) {
+        let mint = self.mint;
+        let (launch_slot, creator) = *self.launch.get_or_insert_with(|| {
+            let creator = launches_in_transaction(transaction)
+                .into_iter()
+                .find(|launch| launch.mint == mint)
+                .map(|launch| launch.creator);
+            (transaction.slot, creator)
+        });
+
+        let buys = trade_events_in_transaction(transaction)
+            .into_iter()
+            .filter(|trade_event| trade_event.mint == mint && trade_event.is_buy);
+        for buy in buys {
+            if let Some(early_buyer) = self.early_buyers.iter_mut().find(|early_buyer| early_buyer.wallet == buy.user) {
+                early_buyer.sol_spent = early_buyer.sol_spent.saturating_add(buy.sol_amount);
+                early_buyer.tokens_bought = early_buyer.tokens_bought.saturating_add(buy.token_amount);
+                continue;
+            }
+            if self.early_buyers.len() == self.n {
+                continue;
+            }
+            let slots_after_launch = transaction.slot.saturating_sub(launch_slot);
+            self.early_buyers.push(EarlyBuyer {
+                wallet: buy.user,
+                rank: self.early_buyers.len() + 1,
+                first_buy_signature: transaction.signature.clone(),
+                slots_after_launch,
+                block_time: transaction.block_time,
+                sol_spent: buy.sol_amount,
+                tokens_bought: buy.token_amount,
+                current_balance: 0,
+                has_sold: false,
+                is_creator: creator == Some(buy.user),
+                is_sniper: slots_after_launch <= SNIPER_SLOT_WINDOW,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use crate::{constants::pumpfun_accounts::TRADE_EVENT_DISCRIMINATOR, pumpfun::trade_event::TradeEvent};
+
+    fn transaction(slot: u64, trades: &[(Pubkey, Pubkey, u64)]) -> InspectedTransaction {
+        let logs = trades
+            .iter()
+            .map(|(mint, user, sol_amount)| {
+                let trade_event = TradeEvent {
+                    mint: *mint,
+                    sol_amount: *sol_amount,
+                    token_amount: sol_amount * 1_000,
+                    is_buy: true,
+                    user: *user,
+                    timestamp: slot as i64,
+                    virtual_sol_reserves: 0,
+                    virtual_token_reserves: 0,
+                };
+                let mut data = TRADE_EVENT_DISCRIMINATOR.to_vec();
+                data.extend(borsh::to_vec(&trade_event).unwrap());
+                format!("Program data: {}", STANDARD.encode(data))
+            })
+            .collect();
+        InspectedTransaction {
+            signature: format!("signature-{}", slot),
+            slot,
+            block_time: None,
+            is_versioned: false,
+            account_keys: vec![],
+            instructions: vec![],
+            inner_instructions: vec![],
+            logs,
+            fee: 5_000,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_early_buyer_scan() {
+        let (mint, other_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let wallets: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let mut scan = EarlyBuyerScan::new(mint, 2);
+        scan.push_transaction(&transaction(100, &[(mint, wallets[0], 1_000), (other_mint, wallets[1], 1_000)]));
+        scan.push_transaction(&transaction(110, &[(mint, wallets[1], 500), (mint, wallets[0], 200)]));
+        scan.push_transaction(&transaction(120, &[(mint, wallets[2], 300)]));
+
+        let early_buyers = &scan.early_buyers;
+        assert!(early_buyers.len() == 2);
+        assert!(early_buyers[0].wallet == wallets[0] && early_buyers[0].sol_spent == 1_200 && early_buyers[0].is_sniper);
+        assert!(early_buyers[1].wallet == wallets[1] && early_buyers[1].rank == 2 && early_buyers[1].slots_after_launch == 10);
+        assert!(!early_buyers[1].is_sniper && !early_buyers[1].is_creator);
+    }
+}
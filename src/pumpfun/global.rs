@@ -0,0 +1,127 @@
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use borsh::BorshDeserialize;
+use crate::{
+    constants::pumpfun_accounts::{pumpfun_global_account, GLOBAL_ACCOUNT_DISCRIMINATOR},
+    error::ReadTransactionError
+};
+
+/// Global configuration account of the pump.fun program.
+///
+/// ### Fields
+///
+/// - `initialized`: Whether the global account has been initialized.
+/// - `authority`: Admin of the program.
+/// - `fee_recipient`: Account receiving the protocol fees.
+/// - `initial_virtual_token_reserves`: Virtual token reserves of newly created bonding curves.
+/// - `initial_virtual_sol_reserves`: Virtual SOL reserves in lamports of newly created bonding curves.
+/// - `initial_real_token_reserves`: Real token reserves of newly created bonding curves.
+/// - `token_total_supply`: Total supply of newly created tokens.
+/// - `fee_basis_points`: Protocol fee charged on every trade, in basis points.
+/// - `creator_fee_basis_points`: Creator fee charged on every trade, in basis points. `0` for
+///   accounts created before creator fees were introduced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlobalAccount {
+    pub initialized: bool,
+    pub authority: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub initial_virtual_token_reserves: u64,
+    pub initial_virtual_sol_reserves: u64,
+    pub initial_real_token_reserves: u64,
+    pub token_total_supply: u64,
+    pub fee_basis_points: u64,
+    pub creator_fee_basis_points: u64,
+}
+
+#[derive(BorshDeserialize)]
+struct GlobalConfig {
+    initialized: bool,
+    authority: Pubkey,
+    fee_recipient: Pubkey,
+    initial_virtual_token_reserves: u64,
+    initial_virtual_sol_reserves: u64,
+    initial_real_token_reserves: u64,
+    token_total_supply: u64,
+    fee_basis_points: u64,
+}
+
+// Fields appended to the global account in later program versions
+#[derive(BorshDeserialize)]
+struct GlobalConfigExtension {
+    _withdraw_authority: Pubkey,
+    _enable_migrate: bool,
+    _pool_migration_fee: u64,
+    creator_fee_basis_points: u64,
+}
+
+impl GlobalAccount {
+    /// Deserializes global account data. Creator fees are read if the account contains them.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::WrongAccountType` if the discriminator does not match,
+    /// or `ReadTransactionError::DeserializeError` if the data is too short.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, ReadTransactionError> {
+        if data.len() < 8 || data[..8] != GLOBAL_ACCOUNT_DISCRIMINATOR {
+            return Err(ReadTransactionError::WrongAccountType("pump.fun global".to_string()));
+        }
+        let mut remaining_data = &data[8..];
+        let config = GlobalConfig::deserialize(&mut remaining_data)
+            .map_err(|_| ReadTransactionError::DeserializeError)?;
+        let creator_fee_basis_points = GlobalConfigExtension::deserialize(&mut remaining_data)
+            .map(|extension| extension.creator_fee_basis_points)
+            .unwrap_or(0);
+
+        Ok(GlobalAccount {
+            initialized: config.initialized,
+            authority: config.authority,
+            fee_recipient: config.fee_recipient,
+            initial_virtual_token_reserves: config.initial_virtual_token_reserves,
+            initial_virtual_sol_reserves: config.initial_virtual_sol_reserves,
+            initial_real_token_reserves: config.initial_real_token_reserves,
+            token_total_supply: config.token_total_supply,
+            fee_basis_points: config.fee_basis_points,
+            creator_fee_basis_points,
+        })
+    }
+}
+
+/// Fetches and deserializes the pump.fun global account.
+pub fn get_global_account(client: &RpcClient) -> Result<GlobalAccount, ReadTransactionError> {
+    let account_data = client.get_account_data(&pumpfun_global_account())?;
+    GlobalAccount::try_from_account_data(&account_data)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn global_account_data(creator_fee_basis_points: Option<u64>) -> Vec<u8> {
+        let mut data = GLOBAL_ACCOUNT_DISCRIMINATOR.to_vec();
+        data.push(1);
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        for value in [1_073_000_000_000_000_u64, 30_000_000_000, 793_100_000_000_000, 1_000_000_000_000_000, 95] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        if let Some(creator_fee_basis_points) = creator_fee_basis_points {
+            data.extend_from_slice(Pubkey::new_unique().as_ref());
+            data.push(1);
+            data.extend_from_slice(&15_000_000_u64.to_le_bytes());
+            data.extend_from_slice(&creator_fee_basis_points.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_deserialize_global_account() {
+        let global_account = GlobalAccount::try_from_account_data(&global_account_data(Some(5))).unwrap();
+        assert!(global_account.fee_basis_points == 95);
+        assert!(global_account.creator_fee_basis_points == 5);
+
+        let old_global_account = GlobalAccount::try_from_account_data(&global_account_data(None)).unwrap();
+        assert!(old_global_account.fee_basis_points == 95);
+        assert!(old_global_account.creator_fee_basis_points == 0);
+    }
+}
@@ -0,0 +1,129 @@
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey
+};
+use spl_associated_token_account::get_associated_token_address;
+use crate::constants::{
+    pumpfun_accounts::{
+        buy_instruction_data, pumpfun_event_authority_account, pumpfun_fee_account, pumpfun_global_account, pumpfun_program, sell_instruction_data
+    },
+    solana_programs::{system_program, token_program}
+};
+use super::bonding_curve::{derive_associated_bonding_curve, derive_bonding_curve, derive_creator_vault};
+
+/// Creates a pump.fun buy instruction for `user`, which only needs to be a public key so the
+/// instruction can be simulated for a watch-only wallet. The associated token account of the
+/// user for the token has to exist.
+///
+/// ### Arguments
+///
+/// * `user` - wallet buying the tokens.
+/// * `mint` - pump.fun token to buy.
+/// * `creator` - creator of the token, whose creator vault receives the creator fee.
+/// * `token_amount` - raw amount of tokens to buy.
+/// * `max_sol_cost` - maximum lamports to spend including fees.
+pub fn pump_buy_instruction(user: &Pubkey, mint: &Pubkey, creator: &Pubkey, token_amount: u64, max_sol_cost: u64) -> Instruction {
+    let mut data = buy_instruction_data();
+    data.extend_from_slice(&token_amount.to_le_bytes());
+    data.extend_from_slice(&max_sol_cost.to_le_bytes());
+
+    Instruction {
+        program_id: pumpfun_program(),
+        accounts: vec![
+            AccountMeta::new_readonly(pumpfun_global_account(), false),
+            AccountMeta::new(pumpfun_fee_account(), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(derive_bonding_curve(mint), false),
+            AccountMeta::new(derive_associated_bonding_curve(mint), false),
+            AccountMeta::new(get_associated_token_address(user, mint), false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(system_program(), false),
+            AccountMeta::new_readonly(token_program(), false),
+            AccountMeta::new(derive_creator_vault(creator), false),
+            AccountMeta::new_readonly(pumpfun_event_authority_account(), false),
+            AccountMeta::new_readonly(pumpfun_program(), false),
+        ],
+        data,
+    }
+}
+
+/// Creates a pump.fun sell instruction for `user`, which only needs to be a public key so the
+/// instruction can be simulated for a watch-only wallet.
+///
+/// ### Arguments
+///
+/// * `user` - wallet selling the tokens.
+/// * `mint` - pump.fun token to sell.
+/// * `creator` - creator of the token, whose creator vault receives the creator fee.
+/// * `token_amount` - raw amount of tokens to sell.
+/// * `min_sol_output` - minimum lamports to receive after fees.
+pub fn pump_sell_instruction(user: &Pubkey, mint: &Pubkey, creator: &Pubkey, token_amount: u64, min_sol_output: u64) -> Instruction {
+    let mut data = sell_instruction_data();
+    data.extend_from_slice(&token_amount.to_le_bytes());
+    data.extend_from_slice(&min_sol_output.to_le_bytes());
+
+    Instruction {
+        program_id: pumpfun_program(),
+        accounts: vec![
+            AccountMeta::new_readonly(pumpfun_global_account(), false),
+            AccountMeta::new(pumpfun_fee_account(), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(derive_bonding_curve(mint), false),
+            AccountMeta::new(derive_associated_bonding_curve(mint), false),
+            AccountMeta::new(get_associated_token_address(user, mint), false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(system_program(), false),
+            AccountMeta::new(derive_creator_vault(creator), false),
+            AccountMeta::new_readonly(token_program(), false),
+            AccountMeta::new_readonly(pumpfun_event_authority_account(), false),
+            AccountMeta::new_readonly(pumpfun_program(), false),
+        ],
+        data,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pump_buy_and_sell_instructions() {
+        let (user, mint, creator) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let buy_instruction = pump_buy_instruction(&user, &mint, &creator, 1_000_000, 20_000_000);
+        assert!(buy_instruction.data[..8] == buy_instruction_data()[..]);
+        assert!(buy_instruction.data[8..16] == 1_000_000_u64.to_le_bytes());
+        assert!(buy_instruction.data[16..24] == 20_000_000_u64.to_le_bytes());
+        assert!(buy_instruction.accounts == vec![
+            AccountMeta::new_readonly(pumpfun_global_account(), false),
+            AccountMeta::new(pumpfun_fee_account(), false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(derive_bonding_curve(&mint), false),
+            AccountMeta::new(derive_associated_bonding_curve(&mint), false),
+            AccountMeta::new(get_associated_token_address(&user, &mint), false),
+            AccountMeta::new(user, true),
+            AccountMeta::new_readonly(system_program(), false),
+            AccountMeta::new_readonly(token_program(), false),
+            AccountMeta::new(derive_creator_vault(&creator), false),
+            AccountMeta::new_readonly(pumpfun_event_authority_account(), false),
+            AccountMeta::new_readonly(pumpfun_program(), false),
+        ]);
+
+        let sell_instruction = pump_sell_instruction(&user, &mint, &creator, 1_000_000, 0);
+        assert!(sell_instruction.data[..8] == sell_instruction_data()[..]);
+        assert!(sell_instruction.accounts == vec![
+            AccountMeta::new_readonly(pumpfun_global_account(), false),
+            AccountMeta::new(pumpfun_fee_account(), false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(derive_bonding_curve(&mint), false),
+            AccountMeta::new(derive_associated_bonding_curve(&mint), false),
+            AccountMeta::new(get_associated_token_address(&user, &mint), false),
+            AccountMeta::new(user, true),
+            AccountMeta::new_readonly(system_program(), false),
+            AccountMeta::new(derive_creator_vault(&creator), false),
+            AccountMeta::new_readonly(token_program(), false),
+            AccountMeta::new_readonly(pumpfun_event_authority_account(), false),
+            AccountMeta::new_readonly(pumpfun_program(), false),
+        ]);
+    }
+}
@@ -0,0 +1,608 @@
+//! # Limit Orders
+//!
+//! Bonding curves have no order book, so limit orders are emulated: orders registered on a
+//! `TriggerEngine` are checked against every bonding curve update and traded through the
+//! `TransactionBuilder` once their price is reached. Fills are sized so that the price after the
+//! trade stays within the limit of the order, the rest of the order waits for the next update.
+//! Orders past their expiry are closed with whatever was filled until then.
+//!
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use solana_sdk::pubkey::Pubkey;
+//! use easy_solana::{
+//!     create_rpc_client,
+//!     pumpfun::limit_orders::{CurveUpdate, LimitOrder, TriggerEngine},
+//!     SecretKeypair
+//! };
+//!
+//! # async fn example(mint: Pubkey) {
+//! let client = Arc::new(create_rpc_client("RPC_URL"));
+//! let payer = Arc::new(SecretKeypair::from_env("PRIVATE_KEY_1").unwrap().keypair().insecure_clone());
+//! let mut engine = TriggerEngine::new();
+//! engine.place(LimitOrder::buy(mint, 0.000_000_03, 100_000_000).expires_at(1_760_000_000_000)).unwrap();
+//! engine.place(LimitOrder::sell(mint, 1_000_000_000).take_profit(0.000_000_06).stop_loss(0.000_000_02)).unwrap();
+//! engine.on_fill(|fill| println!("Order {} filled {} in {}", fill.order_id, fill.amount, fill.signature));
+//!
+//! let (sender, receiver) = tokio::sync::mpsc::channel::<CurveUpdate>(100);
+//! let engine = engine.spawn(client, payer, receiver);
+//! // send a `CurveUpdate` whenever the bonding curve of the mint changes
+//! # }
+//! ```
+
+use std::sync::{Arc, Mutex};
+use log::warn;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer
+};
+use tokio::sync::mpsc::Receiver;
+use crate::{
+    constants::{pumpfun_accounts::PUMP_TOKEN_DECIMALS, units::BASIS_POINTS_DIVISOR},
+    error::{LimitOrderError, TransactionBuilderError},
+    write_transactions::{transaction_builder::TransactionBuilder, utils::send_and_confirm_transaction}
+};
+use super::{
+    bonding_curve::{calculate_token_price_in_sol, BondingCurveAccount},
+    curve_math::{mul_div_floor, to_u64},
+    global::{get_global_account, GlobalAccount},
+    instructions::pump_sell_instruction,
+    quote::{fee_basis_points, quote_sell, PumpQuote}
+};
+
+const DEFAULT_SLIPPAGE_BPS: u16 = 100;
+
+/// Direction and trigger prices of a limit order, prices are in SOL per token.
+///
+/// - Buy: Spends `sol_amount` lamports, fees included, while the price is at most `limit_price`.
+/// - Sell: Sells `token_amount` raw tokens while the price is at least `take_profit`, or all
+///   remaining tokens at once when the price falls to `stop_loss`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrderSide {
+    Buy { limit_price: f64, sol_amount: u64 },
+    Sell { take_profit: Option<f64>, stop_loss: Option<f64>, token_amount: u64 },
+}
+
+/// A limit order on the bonding curve of a pump.fun token.
+///
+/// ### Fields
+///
+/// - `mint`: Token traded by the order.
+/// - `side`: Direction, amount and trigger prices of the order.
+/// - `slippage_bps`: Slippage allowed on each fill, defaults to 100 basis points.
+/// - `expires_at`: Unix timestamp in milliseconds after which the order is no longer filled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LimitOrder {
+    pub mint: Pubkey,
+    pub side: OrderSide,
+    pub slippage_bps: u16,
+    pub expires_at: Option<u64>,
+}
+
+impl LimitOrder {
+    /// Buy order spending `sol_amount` lamports while the price is at most `limit_price`.
+    pub fn buy(mint: Pubkey, limit_price: f64, sol_amount: u64) -> Self {
+        LimitOrder {
+            mint,
+            side: OrderSide::Buy { limit_price, sol_amount },
+            slippage_bps: DEFAULT_SLIPPAGE_BPS,
+            expires_at: None,
+        }
+    }
+
+    /// Sell order of `token_amount` raw tokens, triggered by `take_profit` or `stop_loss`.
+    pub fn sell(mint: Pubkey, token_amount: u64) -> Self {
+        LimitOrder {
+            mint,
+            side: OrderSide::Sell { take_profit: None, stop_loss: None, token_amount },
+            slippage_bps: DEFAULT_SLIPPAGE_BPS,
+            expires_at: None,
+        }
+    }
+
+    /// Sells once the price is at least `price`, ignored for buy orders.
+    pub fn take_profit(mut self, price: f64) -> Self {
+        if let OrderSide::Sell { take_profit, .. } = &mut self.side {
+            *take_profit = Some(price);
+        }
+        self
+    }
+
+    /// Sells everything once the price is at most `price`, ignored for buy orders.
+    pub fn stop_loss(mut self, price: f64) -> Self {
+        if let OrderSide::Sell { stop_loss, .. } = &mut self.side {
+            *stop_loss = Some(price);
+        }
+        self
+    }
+
+    pub fn slippage_bps(mut self, slippage_bps: u16) -> Self {
+        self.slippage_bps = slippage_bps;
+        self
+    }
+
+    /// Stops filling the order after `timestamp`, in unix milliseconds.
+    pub fn expires_at(mut self, timestamp: u64) -> Self {
+        self.expires_at = Some(timestamp);
+        self
+    }
+
+    /// Lamports to spend for buy orders, raw tokens to sell for sell orders.
+    pub fn amount(&self) -> u64 {
+        match self.side {
+            OrderSide::Buy { sol_amount, .. } => sol_amount,
+            OrderSide::Sell { token_amount, .. } => token_amount,
+        }
+    }
+
+    fn validate(&self) -> Result<(), LimitOrderError> {
+        let is_valid_price = |price: f64| price.is_finite() && price > 0.0;
+        if self.amount() == 0 {
+            return Err(LimitOrderError::InvalidOrder("Amount is zero".to_string()));
+        }
+        if self.slippage_bps as u128 > BASIS_POINTS_DIVISOR {
+            return Err(LimitOrderError::InvalidOrder(format!("Slippage of {} basis points exceeds 100%", self.slippage_bps)));
+        }
+        match self.side {
+            OrderSide::Buy { limit_price, .. } if !is_valid_price(limit_price) => {
+                Err(LimitOrderError::InvalidOrder(format!("Invalid limit price {}", limit_price)))
+            }
+            OrderSide::Sell { take_profit: None, stop_loss: None, .. } => {
+                Err(LimitOrderError::InvalidOrder("Sell order has no take profit or stop loss".to_string()))
+            }
+            OrderSide::Sell { take_profit, stop_loss, .. } => {
+                if let Some(price) = take_profit.into_iter().chain(stop_loss).find(|price| !is_valid_price(*price)) {
+                    return Err(LimitOrderError::InvalidOrder(format!("Invalid trigger price {}", price)));
+                }
+                match (take_profit, stop_loss) {
+                    (Some(take_profit), Some(stop_loss)) if stop_loss >= take_profit => Err(LimitOrderError::InvalidOrder(
+                        format!("Stop loss {} is not below take profit {}", stop_loss, take_profit)
+                    )),
+                    _ => Ok(()),
+                }
+            }
+            OrderSide::Buy { .. } => Ok(()),
+        }
+    }
+}
+
+/// Lifecycle of an order.
+///
+/// - Open: Nothing filled yet.
+/// - PartiallyFilled: Part of the amount filled, the rest waits for the price.
+/// - Filled: The whole amount filled.
+/// - Expired: Expired before being filled, keeps what was filled.
+/// - Cancelled: Cancelled before being filled, keeps what was filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderStatus {
+    Open,
+    PartiallyFilled,
+    Filled,
+    Expired,
+    Cancelled,
+}
+
+/// An order registered on a `TriggerEngine`.
+///
+/// ### Fields
+///
+/// - `id`: Identifier returned when the order was placed.
+/// - `order`: The order as placed.
+/// - `filled`: Lamports spent by buy orders, raw tokens sold by sell orders.
+/// - `status`: Lifecycle of the order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderState {
+    pub id: u64,
+    pub order: LimitOrder,
+    pub filled: u64,
+    pub status: OrderStatus,
+}
+
+impl OrderState {
+    /// Amount left to fill.
+    pub fn remaining(&self) -> u64 {
+        self.order.amount().saturating_sub(self.filled)
+    }
+
+    /// Whether the order can still be filled.
+    pub fn is_active(&self) -> bool {
+        matches!(self.status, OrderStatus::Open | OrderStatus::PartiallyFilled)
+    }
+}
+
+/// A bonding curve state observed at `timestamp`, in unix milliseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurveUpdate {
+    pub mint: Pubkey,
+    pub curve_state: BondingCurveAccount,
+    pub timestamp: u64,
+}
+
+/// Amount of an order to fill on the current curve, lamports for buys and raw tokens for sells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Trigger {
+    pub order_id: u64,
+    pub amount: u64,
+}
+
+/// A fill executed by the engine.
+///
+/// ### Fields
+///
+/// - `order_id`: Order that was filled.
+/// - `mint`: Token traded.
+/// - `signature`: Signature of the confirmed transaction.
+/// - `amount`: Lamports committed by buys, raw tokens sold by sells.
+/// - `quote`: Quote the fill was sized from, i.e the expected fill before slippage.
+/// - `status`: Status of the order after the fill.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderFill {
+    pub order_id: u64,
+    pub mint: Pubkey,
+    pub signature: Signature,
+    pub amount: u64,
+    pub quote: PumpQuote,
+    pub status: OrderStatus,
+}
+
+type FillCallback = Box<dyn FnMut(&OrderFill) + Send>;
+
+/// Registers limit orders and fills them as bonding curve updates arrive.
+pub struct TriggerEngine {
+    orders: Vec<OrderState>,
+    next_id: u64,
+    fill_callbacks: Vec<FillCallback>,
+}
+
+impl Default for TriggerEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TriggerEngine {
+    pub fn new() -> Self {
+        TriggerEngine {
+            orders: Vec::new(),
+            next_id: 0,
+            fill_callbacks: Vec::new(),
+        }
+    }
+
+    /// Registers an order and returns its id.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `LimitOrderError::InvalidOrder` if the amount is zero, a price is not positive,
+    /// the slippage exceeds 10000 basis points, a sell order has no trigger price or its stop
+    /// loss is not below its take profit.
+    pub fn place(&mut self, order: LimitOrder) -> Result<u64, LimitOrderError> {
+        order.validate()?;
+        let id = self.next_id;
+        self.next_id += 1;
+        self.orders.push(OrderState { id, order, filled: 0, status: OrderStatus::Open });
+        Ok(id)
+    }
+
+    /// Cancels an active order, returns false if the order is unknown or no longer active.
+    pub fn cancel(&mut self, order_id: u64) -> bool {
+        match self.orders.iter_mut().find(|state| state.id == order_id && state.is_active()) {
+            Some(state) => {
+                state.status = OrderStatus::Cancelled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn order(&self, order_id: u64) -> Option<&OrderState> {
+        self.orders.iter().find(|state| state.id == order_id)
+    }
+
+    /// All orders placed, including closed ones.
+    pub fn orders(&self) -> &[OrderState] {
+        &self.orders
+    }
+
+    /// Calls `callback` with every fill executed by the engine.
+    pub fn on_fill(&mut self, callback: impl FnMut(&OrderFill) + Send + 'static) -> &mut Self {
+        self.fill_callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// Expires active orders whose expiry is before `timestamp` and returns their ids.
+    pub fn expire(&mut self, timestamp: u64) -> Vec<u64> {
+        self.orders
+            .iter_mut()
+            .filter(|state| state.is_active() && state.order.expires_at.is_some_and(|expires_at| expires_at < timestamp))
+            .map(|state| {
+                state.status = OrderStatus::Expired;
+                state.id
+            })
+            .collect()
+    }
+
+    /// Orders of the updated token whose price is reached, with the amount to fill so that the
+    /// price after the trade stays within their limit. Stop losses fill the whole remaining
+    /// amount. Orders are not triggered on complete curves.
+    pub fn triggers(&self, update: &CurveUpdate, global_state: &GlobalAccount) -> Vec<Trigger> {
+        let curve_state = &update.curve_state;
+        let Ok(price) = calculate_token_price_in_sol(curve_state) else {
+            return Vec::new();
+        };
+        if curve_state.complete {
+            return Vec::new();
+        }
+
+        self.orders
+            .iter()
+            .filter(|state| state.is_active() && state.order.mint == update.mint)
+            .filter(|state| state.order.expires_at.is_none_or(|expires_at| expires_at >= update.timestamp))
+            .filter_map(|state| {
+                let amount = match state.order.side {
+                    OrderSide::Buy { limit_price, .. } => buy_fill(curve_state, global_state, limit_price),
+                    OrderSide::Sell { take_profit: Some(take_profit), .. } if price >= take_profit => take_profit_fill(curve_state, take_profit),
+                    OrderSide::Sell { stop_loss: Some(stop_loss), .. } if price <= stop_loss => u64::MAX,
+                    OrderSide::Sell { .. } => 0,
+                }.min(state.remaining());
+                (amount > 0).then_some(Trigger { order_id: state.id, amount })
+            })
+            .collect()
+    }
+
+    /// Adds `amount` to the filled amount of an order and returns its new status, `None` if the
+    /// order is unknown.
+    pub fn record_fill(&mut self, order_id: u64, amount: u64) -> Option<OrderStatus> {
+        let state = self.orders.iter_mut().find(|state| state.id == order_id)?;
+        state.filled = state.filled.saturating_add(amount).min(state.order.amount());
+        if state.is_active() {
+            state.status = match state.remaining() {
+                0 => OrderStatus::Filled,
+                _ => OrderStatus::PartiallyFilled,
+            };
+        }
+        Some(state.status)
+    }
+
+    /// Expires orders, then fills the triggered orders of the updated token with one
+    /// transaction each, paid and signed by `payer`. Triggered orders whose transaction fails
+    /// stay active and are retried on the next update.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `LimitOrderError::ReadError` if the pump.fun global account can not be fetched.
+    pub fn execute(&mut self, client: &RpcClient, payer: &Keypair, update: &CurveUpdate) -> Result<Vec<OrderFill>, LimitOrderError> {
+        self.expire(update.timestamp);
+        if !self.orders.iter().any(|state| state.is_active() && state.order.mint == update.mint) {
+            return Ok(Vec::new());
+        }
+        let global_state = get_global_account(client)?;
+
+        let mut fills = Vec::new();
+        for trigger in self.triggers(update, &global_state) {
+            let Some(order) = self.order(trigger.order_id).map(|state| state.order) else {
+                continue;
+            };
+            match send_fill(client, payer, &order, &update.curve_state, &global_state, trigger.amount) {
+                Ok((signature, quote)) => {
+                    let Some(status) = self.record_fill(trigger.order_id, trigger.amount) else {
+                        continue;
+                    };
+                    let fill = OrderFill { order_id: trigger.order_id, mint: order.mint, signature, amount: trigger.amount, quote, status };
+                    for callback in self.fill_callbacks.iter_mut() {
+                        callback(&fill);
+                    }
+                    fills.push(fill);
+                }
+                Err(err) => warn!("Unable to fill order {}: {}", trigger.order_id, err),
+            }
+        }
+        Ok(fills)
+    }
+
+    /// Executes orders on every update received until the sender is dropped. The engine is
+    /// shared so orders can be placed and cancelled while it runs.
+    pub fn spawn(self, client: Arc<RpcClient>, payer: Arc<Keypair>, mut receiver: Receiver<CurveUpdate>) -> Arc<Mutex<TriggerEngine>> {
+        let engine = Arc::new(Mutex::new(self));
+        let task_engine = Arc::clone(&engine);
+
+        tokio::spawn(async move {
+            while let Some(update) = receiver.recv().await {
+                let execute_engine = Arc::clone(&task_engine);
+                let execute_client = Arc::clone(&client);
+                let execute_payer = Arc::clone(&payer);
+                let result = tokio::task::spawn_blocking(move || {
+                    execute_engine
+                        .lock()
+                        .map(|mut engine| engine.execute(&execute_client, &execute_payer, &update).map(|_| ()))
+                        .ok()
+                }).await;
+                match result {
+                    Ok(Some(Err(err))) => warn!("Unable to execute limit orders: {}", err),
+                    Ok(Some(Ok(()))) => {}
+                    _ => warn!("Limit order execution panicked"),
+                }
+            }
+        });
+
+        engine
+    }
+}
+
+/// Lamports per raw token at a price in SOL per token.
+fn raw_price(price: f64) -> f64 {
+    price * LAMPORTS_PER_SOL as f64 / 10_f64.powi(PUMP_TOKEN_DECIMALS as i32)
+}
+
+/// Lamports, fees included, that can be spent before the price rises above `limit_price`.
+fn buy_fill(curve_state: &BondingCurveAccount, global_state: &GlobalAccount, limit_price: f64) -> u64 {
+    let virtual_sol_reserves = curve_state.virtual_sol_reserves as f64;
+    let invariant = virtual_sol_reserves * curve_state.virtual_token_reserves as f64;
+    // The price after the trade is sol_reserves^2 / invariant
+    let swap_sol = (raw_price(limit_price) * invariant).sqrt() - virtual_sol_reserves;
+    if swap_sol <= 0.0 {
+        return 0;
+    }
+    let (fee_basis_points, creator_fee_basis_points) = fee_basis_points(curve_state, global_state);
+    let total_basis_points = BASIS_POINTS_DIVISOR + fee_basis_points + creator_fee_basis_points;
+    (swap_sol * total_basis_points as f64 / BASIS_POINTS_DIVISOR as f64) as u64
+}
+
+/// Raw tokens that can be sold before the price falls below `take_profit`.
+fn take_profit_fill(curve_state: &BondingCurveAccount, take_profit: f64) -> u64 {
+    let virtual_token_reserves = curve_state.virtual_token_reserves as f64;
+    let invariant = curve_state.virtual_sol_reserves as f64 * virtual_token_reserves;
+    // The price after the trade is invariant / token_reserves^2
+    let token_amount = (invariant / raw_price(take_profit)).sqrt() - virtual_token_reserves;
+    token_amount.max(0.0) as u64
+}
+
+/// Sends the transaction of a fill and waits for its confirmation.
+fn send_fill(
+    client: &RpcClient,
+    payer: &Keypair,
+    order: &LimitOrder,
+    curve_state: &BondingCurveAccount,
+    global_state: &GlobalAccount,
+    amount: u64
+) -> Result<(Signature, PumpQuote), LimitOrderError> {
+    let mut builder = TransactionBuilder::new(client, payer);
+    let quote = match order.side {
+        OrderSide::Buy { .. } => builder.push_pumpfun_buy(&order.mint, curve_state, global_state, amount, order.slippage_bps)?,
+        OrderSide::Sell { .. } => builder.push_pumpfun_sell(&order.mint, curve_state, global_state, amount, order.slippage_bps)?,
+    };
+    let confirmed_transaction = send_and_confirm_transaction(client, builder.build()?)?
+        .into_result(LimitOrderError::TransactionFailed)?;
+    Ok((confirmed_transaction.signature, quote))
+}
+
+impl TransactionBuilder<'_> {
+    /// Adds a sell of `token_amount` raw tokens quoted on `curve_state`, receiving at least the
+    /// quote lowered by `slippage_bps`, followed by the fee transfers on the SOL received.
+    fn push_pumpfun_sell(
+        &mut self,
+        mint: &Pubkey,
+        curve_state: &BondingCurveAccount,
+        global_state: &GlobalAccount,
+        token_amount: u64,
+        slippage_bps: u16
+    ) -> Result<PumpQuote, TransactionBuilderError> {
+        let quote = quote_sell(curve_state, global_state, token_amount)
+            .map_err(|err| TransactionBuilderError::InstructionError(err.to_string()))?;
+        let min_sol_output = mul_div_floor(quote.total_sol as u128, BASIS_POINTS_DIVISOR - slippage_bps as u128, BASIS_POINTS_DIVISOR, "minimum sol")
+            .and_then(|min_sol_output| to_u64(min_sol_output, "minimum sol"))
+            .map_err(|err| TransactionBuilderError::InstructionError(err.to_string()))?;
+
+        let payer = self.payer_keypair.pubkey();
+        self.instructions.push(pump_sell_instruction(&payer, mint, &curve_state.creator.unwrap_or_default(), token_amount, min_sol_output));
+        self.apply_fee(quote.total_sol);
+        Ok(quote)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        pumpfun::quote::quote_buy,
+        test_utils::{fresh_bonding_curve, global_account}
+    };
+
+    fn curve(virtual_sol_reserves: u64) -> BondingCurveAccount {
+        BondingCurveAccount { virtual_sol_reserves, ..fresh_bonding_curve() }
+    }
+
+    fn update(mint: Pubkey, virtual_sol_reserves: u64, timestamp: u64) -> CurveUpdate {
+        CurveUpdate { mint, curve_state: curve(virtual_sol_reserves), timestamp }
+    }
+
+    #[test]
+    fn test_buy_fill_keeps_price_below_limit() {
+        let mint = Pubkey::new_unique();
+        let mut engine = TriggerEngine::new();
+        let spot_price = calculate_token_price_in_sol(&curve(30_000_000_000)).unwrap();
+        let order_id = engine.place(LimitOrder::buy(mint, spot_price * 1.01, 1_000_000_000)).unwrap();
+
+        // above the limit nothing is bought
+        assert!(engine.triggers(&update(mint, 31_000_000_000, 0), &global_account()).is_empty());
+        // other tokens do not trigger the order
+        assert!(engine.triggers(&update(Pubkey::new_unique(), 30_000_000_000, 0), &global_account()).is_empty());
+
+        // a 1% higher price leaves room for about 0.5% more SOL in the curve, plus fees
+        let triggers = engine.triggers(&update(mint, 30_000_000_000, 0), &global_account());
+        assert!(triggers.len() == 1 && triggers[0].order_id == order_id);
+        assert!(triggers[0].amount > 150_000_000 && triggers[0].amount < 152_000_000);
+        let quote = quote_buy(&curve(30_000_000_000), &global_account(), triggers[0].amount).unwrap();
+        assert!(quote.post_trade_price <= spot_price * 1.01);
+
+        assert!(engine.record_fill(order_id, triggers[0].amount) == Some(OrderStatus::PartiallyFilled));
+        // a lower price fills at most the remaining amount
+        let triggers = engine.triggers(&update(mint, 1_000_000_000, 0), &global_account());
+        let filled = engine.order(order_id).unwrap().filled;
+        assert!(triggers[0].amount == 1_000_000_000 - filled);
+        assert!(engine.record_fill(order_id, triggers[0].amount) == Some(OrderStatus::Filled));
+        assert!(engine.triggers(&update(mint, 1_000_000_000, 0), &global_account()).is_empty());
+    }
+
+    #[test]
+    fn test_sell_take_profit_and_stop_loss() {
+        let mint = Pubkey::new_unique();
+        let mut engine = TriggerEngine::new();
+        let spot_price = calculate_token_price_in_sol(&curve(30_000_000_000)).unwrap();
+        let order_id = engine.place(
+            LimitOrder::sell(mint, 100_000_000_000_000).take_profit(spot_price * 1.5).stop_loss(spot_price * 0.8)
+        ).unwrap();
+        assert!(engine.triggers(&update(mint, 30_000_000_000, 0), &global_account()).is_empty());
+
+        // the take profit only sells down to its price
+        let curve_state = curve(50_000_000_000);
+        let triggers = engine.triggers(&update(mint, 50_000_000_000, 0), &global_account());
+        assert!(triggers.len() == 1 && triggers[0].amount < 100_000_000_000_000);
+        let quote = quote_sell(&curve_state, &global_account(), triggers[0].amount).unwrap();
+        assert!(quote.post_trade_price >= spot_price * 1.5 * 0.999_999);
+        engine.record_fill(order_id, triggers[0].amount);
+
+        // the stop loss sells everything left
+        let triggers = engine.triggers(&update(mint, 20_000_000_000, 0), &global_account());
+        assert!(triggers[0].amount == engine.order(order_id).unwrap().remaining());
+    }
+
+    #[test]
+    fn test_orders_expire_and_cancel() {
+        let mint = Pubkey::new_unique();
+        let mut engine = TriggerEngine::new();
+        let expiring_id = engine.place(LimitOrder::buy(mint, 1.0, 1_000_000).expires_at(1_000)).unwrap();
+        let cancelled_id = engine.place(LimitOrder::buy(mint, 1.0, 1_000_000)).unwrap();
+        engine.record_fill(expiring_id, 400_000);
+
+        assert!(engine.triggers(&update(mint, 30_000_000_000, 1_001), &global_account()).len() == 1);
+        assert!(engine.expire(1_000).is_empty());
+        assert!(engine.expire(1_001) == vec![expiring_id]);
+        let expired_order = engine.order(expiring_id).unwrap();
+        assert!(expired_order.status == OrderStatus::Expired && expired_order.filled == 400_000);
+        assert!(engine.record_fill(expiring_id, 1) == Some(OrderStatus::Expired));
+
+        assert!(engine.cancel(cancelled_id) && !engine.cancel(cancelled_id));
+        assert!(engine.triggers(&update(mint, 30_000_000_000, 0), &global_account()).is_empty());
+    }
+
+    #[test]
+    fn failing_test_place_invalid_orders() {
+        let mint = Pubkey::new_unique();
+        let mut engine = TriggerEngine::new();
+        for order in [
+            LimitOrder::buy(mint, 1.0, 0),
+            LimitOrder::buy(mint, 0.0, 1),
+            LimitOrder::buy(mint, f64::NAN, 1),
+            LimitOrder::buy(mint, 1.0, 1).slippage_bps(10_001),
+            LimitOrder::sell(mint, 1),
+            LimitOrder::sell(mint, 1).take_profit(1.0).stop_loss(1.0),
+            LimitOrder::sell(mint, 1).stop_loss(-1.0),
+        ] {
+            assert!(matches!(engine.place(order), Err(LimitOrderError::InvalidOrder(_))));
+        }
+        assert!(engine.orders().is_empty());
+    }
+}
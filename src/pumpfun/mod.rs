@@ -1,2 +1,15 @@
 pub mod bonding_curve;
-pub mod bump;
\ No newline at end of file
+pub mod bump;
+pub mod buy;
+pub mod candles;
+pub mod create;
+pub mod creator_cluster;
+pub mod curve_math;
+pub mod early_buyers;
+pub mod global;
+pub mod instructions;
+pub mod limit_orders;
+pub mod price_tracker;
+pub mod quote;
+pub mod simulate;
+pub mod trade_event;
@@ -0,0 +1,198 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, RwLock},
+    time::Duration
+};
+use tokio::sync::mpsc::Receiver;
+use crate::error::ReadTransactionError;
+use super::bonding_curve::{calculate_token_price_in_sol, BondingCurveAccount};
+
+/// A token price observed at `timestamp`, in unix milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceSample {
+    pub timestamp: u64,
+    pub price: f64,
+}
+
+impl PriceSample {
+    /// Creates a sample from the current state of a bonding curve.
+    pub fn from_bonding_curve(curve_state: &BondingCurveAccount, timestamp: u64) -> Result<Self, ReadTransactionError> {
+        Ok(PriceSample {
+            timestamp,
+            price: calculate_token_price_in_sol(curve_state)?,
+        })
+    }
+}
+
+/// Maintains a rolling time weighted average price (TWAP), exponential moving average (EMA)
+/// and volatility over a stream of prices of a single token.
+///
+/// ## Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use easy_solana::pumpfun::price_tracker::{PriceSample, PriceTracker};
+///
+/// let mut tracker = PriceTracker::new(Duration::from_secs(60), 10);
+/// tracker.update(PriceSample { timestamp: 0, price: 1.0 });
+/// tracker.update(PriceSample { timestamp: 30_000, price: 2.0 });
+/// tracker.update(PriceSample { timestamp: 60_000, price: 2.0 });
+/// assert!(tracker.twap() == Some(1.5));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PriceTracker {
+    window: Duration,
+    ema_alpha: f64,
+    samples: VecDeque<PriceSample>,
+    ema: Option<f64>,
+}
+
+impl PriceTracker {
+    /// Creates a tracker keeping samples from the last `window` for the TWAP and volatility,
+    /// and an EMA smoothed over `ema_period` samples.
+    pub fn new(window: Duration, ema_period: u32) -> Self {
+        PriceTracker {
+            window,
+            ema_alpha: 2.0 / (ema_period as f64 + 1.0),
+            samples: VecDeque::new(),
+            ema: None,
+        }
+    }
+
+    /// Adds a price sample, samples older than the latest sample are ignored.
+    pub fn update(&mut self, sample: PriceSample) {
+        if self.samples.back().is_some_and(|latest| sample.timestamp < latest.timestamp) {
+            return;
+        }
+        self.ema = Some(match self.ema {
+            Some(ema) => self.ema_alpha * sample.price + (1.0 - self.ema_alpha) * ema,
+            None => sample.price,
+        });
+        self.samples.push_back(sample);
+
+        // Keep the last sample before the window, it sets the price at the start of the window
+        let window_start = sample.timestamp.saturating_sub(self.window.as_millis() as u64);
+        while self.samples.get(1).is_some_and(|next| next.timestamp <= window_start) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Latest price.
+    pub fn latest(&self) -> Option<f64> {
+        self.samples.back().map(|sample| sample.price)
+    }
+
+    /// Time weighted average price over the window, each price is weighted by how long it lasted.
+    pub fn twap(&self) -> Option<f64> {
+        let latest = self.samples.back()?;
+        let window_start = latest.timestamp.saturating_sub(self.window.as_millis() as u64);
+
+        let mut weighted_sum = 0.0;
+        let mut total_time = 0;
+        for (sample, next) in self.samples.iter().zip(self.samples.iter().skip(1)) {
+            let duration = next.timestamp - sample.timestamp.max(window_start);
+            weighted_sum += sample.price * duration as f64;
+            total_time += duration;
+        }
+
+        if total_time == 0 {
+            return Some(latest.price);
+        }
+        Some(weighted_sum / total_time as f64)
+    }
+
+    /// Exponential moving average of all samples.
+    pub fn ema(&self) -> Option<f64> {
+        self.ema
+    }
+
+    /// Standard deviation of the log returns between samples in the window.
+    pub fn volatility(&self) -> Option<f64> {
+        let returns: Vec<f64> = self.samples
+            .iter()
+            .zip(self.samples.iter().skip(1))
+            .filter(|(sample, next)| sample.price > 0.0 && next.price > 0.0)
+            .map(|(sample, next)| (next.price / sample.price).ln())
+            .collect();
+        if returns.len() < 2 {
+            return None;
+        }
+
+        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+        let variance = returns.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+        Some(variance.sqrt())
+    }
+
+    /// Consumes a stream of price samples in the background. The returned tracker is updated
+    /// as samples arrive and can be read at any time, the task ends when the sender is dropped.
+    pub fn spawn(self, mut receiver: Receiver<PriceSample>) -> Arc<RwLock<PriceTracker>> {
+        let tracker = Arc::new(RwLock::new(self));
+        let task_tracker = Arc::clone(&tracker);
+        tokio::spawn(async move {
+            while let Some(sample) = receiver.recv().await {
+                if let Ok(mut tracker) = task_tracker.write() {
+                    tracker.update(sample);
+                }
+            }
+        });
+        tracker
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: u64, price: f64) -> PriceSample {
+        PriceSample { timestamp, price }
+    }
+
+    #[test]
+    fn test_twap_over_window() {
+        let mut tracker = PriceTracker::new(Duration::from_secs(10), 3);
+        tracker.update(sample(0, 100.0));
+        tracker.update(sample(5_000, 1.0));
+        tracker.update(sample(15_000, 2.0));
+        tracker.update(sample(20_000, 2.0));
+        // window is 10s..20s, price 1.0 for 5s then 2.0 for 5s
+        assert!(tracker.twap() == Some(1.5));
+        assert!(tracker.latest() == Some(2.0));
+    }
+
+    #[test]
+    fn test_ema_and_volatility() {
+        let mut tracker = PriceTracker::new(Duration::from_secs(60), 3);
+        assert!(tracker.ema().is_none());
+        tracker.update(sample(0, 1.0));
+        tracker.update(sample(1_000, 2.0));
+        // alpha = 0.5
+        assert!(tracker.ema() == Some(1.5));
+        assert!(tracker.volatility().is_none());
+
+        tracker.update(sample(2_000, 2.0));
+        tracker.update(sample(3_000, 2.0));
+        assert!(tracker.volatility().unwrap() > 0.0);
+
+        // out of order samples are ignored
+        tracker.update(sample(500, 10.0));
+        assert!(tracker.latest() == Some(2.0));
+    }
+
+    #[tokio::test]
+    async fn test_spawn_price_tracker() {
+        let (sender, receiver) = tokio::sync::mpsc::channel(10);
+        let tracker = PriceTracker::new(Duration::from_secs(60), 3).spawn(receiver);
+        sender.send(sample(0, 1.0)).await.unwrap();
+        sender.send(sample(1_000, 3.0)).await.unwrap();
+        drop(sender);
+
+        for _ in 0..100 {
+            if tracker.read().unwrap().latest() == Some(3.0) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(tracker.read().unwrap().twap() == Some(1.0));
+    }
+}
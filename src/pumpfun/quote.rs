@@ -0,0 +1,215 @@
+use solana_sdk::pubkey::Pubkey;
+use crate::{
+    constants::{pumpfun_accounts::PUMP_TOKEN_DECIMALS, units::BASIS_POINTS_DIVISOR},
+    error::{MathError, ReadTransactionError}
+};
+use super::{bonding_curve::BondingCurveAccount, curve_math, global::GlobalAccount};
+
+/// Quote of a pump.fun trade, with all amounts in lamports or raw token units.
+///
+/// ### Fields
+///
+/// - `token_amount`: Tokens bought or sold.
+/// - `sol_amount`: SOL swapped on the bonding curve, excluding fees.
+/// - `protocol_fee`: Fee paid to the pump.fun fee recipient.
+/// - `creator_fee`: Fee paid to the creator vault of the token.
+/// - `total_sol`: SOL paid including fees for buys, SOL received after fees for sells.
+/// - `price_impact`: How much worse the average price of the trade is than the spot price
+///   before the trade, in percent.
+/// - `post_trade_price`: Spot price in SOL per token after the trade.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PumpQuote {
+    pub token_amount: u64,
+    pub sol_amount: u64,
+    pub protocol_fee: u64,
+    pub creator_fee: u64,
+    pub total_sol: u64,
+    pub price_impact: f64,
+    pub post_trade_price: f64,
+}
+
+/// Quotes the tokens received when spending `sol_amount` lamports, fees included, on a bonding curve.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::BondingCurveError` if the curve is complete or has no reserves
+/// and `ReadTransactionError::MathError` if an amount overflows.
+pub fn quote_buy(
+    curve_state: &BondingCurveAccount,
+    global_state: &GlobalAccount,
+    sol_amount: u64
+) -> Result<PumpQuote, ReadTransactionError> {
+    check_curve(curve_state)?;
+    let (fee_basis_points, creator_fee_basis_points) = fee_basis_points(curve_state, global_state);
+    // Fees are charged on top of the SOL swapped on the curve
+    let total_basis_points = BASIS_POINTS_DIVISOR + fee_basis_points + creator_fee_basis_points;
+    let mut swap_sol = curve_math::mul_div_floor(sol_amount as u128, BASIS_POINTS_DIVISOR, total_basis_points, "swap amount")?;
+
+    let virtual_sol_reserves = curve_state.virtual_sol_reserves as u128;
+    let virtual_token_reserves = curve_state.virtual_token_reserves as u128;
+    let (token_amount, sol_cost, protocol_fee, creator_fee, total_sol) = loop {
+        let token_amount = curve_math::tokens_out(virtual_sol_reserves, virtual_token_reserves, swap_sol)?
+            .min(curve_state.real_token_reserves as u128);
+        // Recompute the SOL needed for the capped token amount
+        let sol_cost = curve_math::sol_in_for_tokens(virtual_sol_reserves, virtual_token_reserves, token_amount)?;
+        let protocol_fee = curve_math::mul_div_ceil(sol_cost, fee_basis_points, BASIS_POINTS_DIVISOR, "protocol fee")?;
+        let creator_fee = curve_math::mul_div_ceil(sol_cost, creator_fee_basis_points, BASIS_POINTS_DIVISOR, "creator fee")?;
+        let total_sol = sol_cost
+            .checked_add(protocol_fee)
+            .and_then(|total| total.checked_add(creator_fee))
+            .ok_or(MathError::Overflow("total cost"))?;
+        // Rounding the cost and fees up can exceed `sol_amount` by a few lamports
+        let overspent = total_sol.saturating_sub(sol_amount as u128);
+        if overspent == 0 || swap_sol == 0 {
+            break (token_amount, sol_cost, protocol_fee, creator_fee, total_sol);
+        }
+        swap_sol = swap_sol.saturating_sub(overspent);
+    };
+
+    // The average price exceeds the spot price by (sol_cost / token_amount) / (sol / tokens) - 1
+    let average_cost = checked_mul(sol_cost, virtual_token_reserves, "price impact")?;
+    let spot_cost = checked_mul(token_amount, virtual_sol_reserves, "price impact")?;
+    let price_impact = match token_amount {
+        0 => 0.0,
+        _ => curve_math::ratio(average_cost.saturating_sub(spot_cost), spot_cost) * 100.0,
+    };
+    Ok(PumpQuote {
+        token_amount: curve_math::to_u64(token_amount, "token amount")?,
+        sol_amount: curve_math::to_u64(sol_cost, "sol amount")?,
+        protocol_fee: curve_math::to_u64(protocol_fee, "protocol fee")?,
+        creator_fee: curve_math::to_u64(creator_fee, "creator fee")?,
+        total_sol: curve_math::to_u64(total_sol, "total cost")?,
+        price_impact,
+        post_trade_price: price_in_sol(virtual_sol_reserves + sol_cost, virtual_token_reserves - token_amount),
+    })
+}
+
+/// Quotes the SOL received after fees when selling `token_amount` raw tokens on a bonding curve.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::BondingCurveError` if the curve is complete or has no reserves
+/// and `ReadTransactionError::MathError` if an amount overflows.
+pub fn quote_sell(
+    curve_state: &BondingCurveAccount,
+    global_state: &GlobalAccount,
+    token_amount: u64
+) -> Result<PumpQuote, ReadTransactionError> {
+    check_curve(curve_state)?;
+    let (fee_basis_points, creator_fee_basis_points) = fee_basis_points(curve_state, global_state);
+
+    let virtual_sol_reserves = curve_state.virtual_sol_reserves as u128;
+    let virtual_token_reserves = curve_state.virtual_token_reserves as u128;
+    let sol_out = curve_math::sol_out(virtual_sol_reserves, virtual_token_reserves, token_amount as u128)?;
+    let protocol_fee = curve_math::mul_div_ceil(sol_out, fee_basis_points, BASIS_POINTS_DIVISOR, "protocol fee")?;
+    let creator_fee = curve_math::mul_div_ceil(sol_out, creator_fee_basis_points, BASIS_POINTS_DIVISOR, "creator fee")?;
+
+    // The average price falls short of the spot price by 1 - (sol_out / token_amount) / (sol / tokens)
+    let average_proceeds = checked_mul(sol_out, virtual_token_reserves, "price impact")?;
+    let spot_proceeds = checked_mul(token_amount as u128, virtual_sol_reserves, "price impact")?;
+    let price_impact = match token_amount {
+        0 => 0.0,
+        _ => curve_math::ratio(spot_proceeds.saturating_sub(average_proceeds), spot_proceeds) * 100.0,
+    };
+    Ok(PumpQuote {
+        token_amount,
+        sol_amount: curve_math::to_u64(sol_out, "sol amount")?,
+        protocol_fee: curve_math::to_u64(protocol_fee, "protocol fee")?,
+        creator_fee: curve_math::to_u64(creator_fee, "creator fee")?,
+        total_sol: curve_math::to_u64(sol_out.saturating_sub(protocol_fee + creator_fee), "sol received")?,
+        price_impact,
+        post_trade_price: price_in_sol(virtual_sol_reserves - sol_out, virtual_token_reserves + token_amount as u128),
+    })
+}
+
+fn check_curve(curve_state: &BondingCurveAccount) -> Result<(), ReadTransactionError> {
+    if curve_state.complete || curve_state.virtual_token_reserves == 0 || curve_state.virtual_sol_reserves == 0 {
+        return Err(ReadTransactionError::BondingCurveError);
+    }
+    Ok(())
+}
+
+fn checked_mul(value: u128, other: u128, operation: &'static str) -> Result<u128, MathError> {
+    value.checked_mul(other).ok_or(MathError::Overflow(operation))
+}
+
+/// Price in SOL per token of a lamport amount and a raw token amount.
+fn price_in_sol(lamports: u128, token_amount: u128) -> f64 {
+    curve_math::price_in_sol(lamports, token_amount, PUMP_TOKEN_DECIMALS)
+}
+
+// Creator fees are only charged on curves with a creator
+pub(super) fn fee_basis_points(curve_state: &BondingCurveAccount, global_state: &GlobalAccount) -> (u128, u128) {
+    let has_creator = curve_state.creator.is_some_and(|creator| creator != Pubkey::default());
+    let creator_fee_basis_points = if has_creator { global_state.creator_fee_basis_points } else { 0 };
+    (global_state.fee_basis_points as u128, creator_fee_basis_points as u128)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{fresh_bonding_curve, global_account};
+
+    fn curve(creator: Option<Pubkey>) -> BondingCurveAccount {
+        BondingCurveAccount { creator, ..fresh_bonding_curve() }
+    }
+
+    #[test]
+    fn test_quote_buy_with_creator_fee() {
+        let quote = quote_buy(&curve(Some(Pubkey::new_unique())), &global_account(), 1_000_000_000).unwrap();
+        assert!(quote.total_sol <= 1_000_000_000);
+        assert!(quote.total_sol == quote.sol_amount + quote.protocol_fee + quote.creator_fee);
+        assert!(quote.creator_fee > 0);
+        // 1% total fee
+        assert!(quote.sol_amount > 989_000_000 && quote.sol_amount < 991_000_000);
+
+        let quote_without_creator = quote_buy(&curve(None), &global_account(), 1_000_000_000).unwrap();
+        assert!(quote_without_creator.creator_fee == 0);
+        assert!(quote_without_creator.token_amount > quote.token_amount);
+    }
+
+    #[test]
+    fn test_quote_price_impact() {
+        let curve_state = curve(None);
+        let spot_price = 30.0 / 1_073_000_000.0;
+        // 3 SOL is about 10% of the virtual SOL reserves
+        let buy_quote = quote_buy(&curve_state, &global_account(), 3_000_000_000).unwrap();
+        assert!(buy_quote.price_impact > 9.0 && buy_quote.price_impact < 11.0);
+        assert!(buy_quote.post_trade_price > spot_price * 1.15);
+
+        let sell_quote = quote_sell(&curve_state, &global_account(), 100_000_000_000_000).unwrap();
+        assert!(sell_quote.price_impact > 8.0 && sell_quote.price_impact < 9.0);
+        assert!(sell_quote.post_trade_price < spot_price);
+
+        let small_quote = quote_buy(&curve_state, &global_account(), 1_000_000).unwrap();
+        assert!(small_quote.price_impact < 0.01);
+    }
+
+    #[test]
+    fn test_quote_sell_with_creator_fee() {
+        let quote = quote_sell(&curve(Some(Pubkey::new_unique())), &global_account(), 1_000_000_000_000).unwrap();
+        assert!(quote.total_sol == quote.sol_amount - quote.protocol_fee - quote.creator_fee);
+        assert!(quote.protocol_fee == (quote.sol_amount as u128 * 95).div_ceil(10_000) as u64);
+    }
+
+    #[test]
+    fn test_quote_buy_never_exceeds_sol_amount_after_rounding() {
+        let curve_state = BondingCurveAccount {
+            virtual_token_reserves: 398_491_083_024_633,
+            virtual_sol_reserves: 219_654_094_495,
+            real_token_reserves: 252_064_398_038_833,
+            ..curve(Some(Pubkey::new_unique()))
+        };
+        let global_state = GlobalAccount { fee_basis_points: 56, creator_fee_basis_points: 30, ..global_account() };
+        let quote = quote_buy(&curve_state, &global_state, 237_973_488_006).unwrap();
+        assert!(quote.total_sol <= 237_973_488_006);
+    }
+
+    #[test]
+    fn failing_test_quote_completed_curve() {
+        let mut completed_curve = curve(None);
+        completed_curve.complete = true;
+        let result = quote_buy(&completed_curve, &global_account(), 1_000_000_000);
+        assert!(matches!(result, Err(ReadTransactionError::BondingCurveError)));
+    }
+}
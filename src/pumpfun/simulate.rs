@@ -0,0 +1,103 @@
+//! # Simulate
+//!
+//! Simulates pump.fun trades as any wallet with only its address, so portfolio and analytics
+//! apps can estimate the outcome of buys and sells for watch-only wallets.
+
+use solana_client::rpc_client::RpcClient;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use crate::{
+    constants::{solana_programs::token_program, units::BASIS_POINTS_DIVISOR},
+    error::{ReadTransactionError, SimulationError},
+    utils::address_to_pubkey,
+    write_transactions::utils::{simulate_instructions_as, SimulationResult}
+};
+use super::{
+    bonding_curve::get_bonding_curve_account,
+    global::get_global_account,
+    instructions::{pump_buy_instruction, pump_sell_instruction},
+    quote::{quote_buy, quote_sell}
+};
+
+/// Simulates buying a pump.fun token with `sol_amount` lamports, fees included, as
+/// `wallet_address` without its keypair. The associated token account of the wallet is created
+/// in the simulation if it does not exist.
+///
+/// ## Errors
+///
+/// Returns `SimulationError::QueryError` if the bonding curve or global account can not be read,
+/// e.g when the token has migrated.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, pumpfun::simulate::simulate_pump_buy_as};
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let simulation_result = simulate_pump_buy_as(
+///     &client,
+///     "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5",
+///     "CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump",
+///     10_000_000,
+///     100
+/// ).unwrap();
+/// ```
+pub fn simulate_pump_buy_as(
+    client: &RpcClient,
+    wallet_address: &str,
+    token_address: &str,
+    sol_amount: u64,
+    slippage_basis_points: u64
+) -> Result<SimulationResult, SimulationError> {
+    let wallet_pubkey = address_to_pubkey(wallet_address)?;
+    let mint_pubkey = address_to_pubkey(token_address)?;
+    let (_, curve_state) = get_bonding_curve_account(client, token_address).ok_or(ReadTransactionError::BondingCurveError)?;
+    let global_state = get_global_account(client)?;
+    let quote = quote_buy(&curve_state, &global_state, sol_amount)?;
+    let max_sol_cost = quote.total_sol.saturating_mul(BASIS_POINTS_DIVISOR as u64 + slippage_basis_points) / BASIS_POINTS_DIVISOR as u64;
+
+    let instructions = [
+        create_associated_token_account_idempotent(&wallet_pubkey, &wallet_pubkey, &mint_pubkey, &token_program()),
+        pump_buy_instruction(&wallet_pubkey, &mint_pubkey, &curve_state.creator.unwrap_or_default(), quote.token_amount, max_sol_cost),
+    ];
+    simulate_instructions_as(client, &instructions, wallet_address)
+}
+
+/// Simulates selling `token_amount` raw tokens of a pump.fun token as `wallet_address` without
+/// its keypair.
+///
+/// ## Errors
+///
+/// Returns `SimulationError::QueryError` if the bonding curve or global account can not be read,
+/// e.g when the token has migrated.
+pub fn simulate_pump_sell_as(
+    client: &RpcClient,
+    wallet_address: &str,
+    token_address: &str,
+    token_amount: u64,
+    slippage_basis_points: u64
+) -> Result<SimulationResult, SimulationError> {
+    let wallet_pubkey = address_to_pubkey(wallet_address)?;
+    let mint_pubkey = address_to_pubkey(token_address)?;
+    let (_, curve_state) = get_bonding_curve_account(client, token_address).ok_or(ReadTransactionError::BondingCurveError)?;
+    let global_state = get_global_account(client)?;
+    let quote = quote_sell(&curve_state, &global_state, token_amount)?;
+    let min_sol_output = quote.total_sol.saturating_mul((BASIS_POINTS_DIVISOR as u64).saturating_sub(slippage_basis_points)) / BASIS_POINTS_DIVISOR as u64;
+
+    let instructions = [pump_sell_instruction(&wallet_pubkey, &mint_pubkey, &curve_state.creator.unwrap_or_default(), token_amount, min_sol_output)];
+    simulate_instructions_as(client, &instructions, wallet_address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+    use crate::test_utils::mock_client;
+
+    #[test]
+    fn test_simulate_pump_buy_of_migrated_token() {
+        // bonding curve account does not exist
+        let client = mock_client(vec![]);
+        let result = simulate_pump_buy_as(&client, &Pubkey::new_unique().to_string(), &Pubkey::new_unique().to_string(), 10_000_000, 100);
+        assert!(matches!(result, Err(SimulationError::QueryError(ReadTransactionError::BondingCurveError))));
+    }
+}
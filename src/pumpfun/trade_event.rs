@@ -0,0 +1,138 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_sdk::pubkey::Pubkey;
+use crate::{
+    constants::pumpfun_accounts::{pumpfun_program, EVENT_INSTRUCTION_TAG, TRADE_EVENT_DISCRIMINATOR},
+    error::ReadTransactionError,
+    read_transactions::transaction::InspectedTransaction
+};
+
+/// A buy or sell on a pump.fun bonding curve, as emitted by the program.
+///
+/// ### Fields
+///
+/// - `mint`: Token traded.
+/// - `sol_amount`: SOL swapped in lamports, excluding fees.
+/// - `token_amount`: Tokens swapped without decimals.
+/// - `is_buy`: Whether the user bought tokens.
+/// - `user`: Wallet that traded.
+/// - `timestamp`: Unix timestamp of the trade in seconds.
+/// - `virtual_sol_reserves`: Virtual SOL reserves of the curve after the trade.
+/// - `virtual_token_reserves`: Virtual token reserves of the curve after the trade.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq)]
+pub struct TradeEvent {
+    pub mint: Pubkey,
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub is_buy: bool,
+    pub user: Pubkey,
+    pub timestamp: i64,
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+}
+
+impl TradeEvent {
+    /// Deserializes a trade event from event data starting with the event discriminator.
+    /// Fields appended to the event by newer program versions are ignored.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::WrongAccountType` if the data is not a trade event,
+    /// or `ReadTransactionError::DeserializeError` if the data is too short.
+    pub fn try_from_event_data(data: &[u8]) -> Result<Self, ReadTransactionError> {
+        if data.len() < 8 || data[..8] != TRADE_EVENT_DISCRIMINATOR {
+            return Err(ReadTransactionError::WrongAccountType("pump.fun trade event".to_string()));
+        }
+        TradeEvent::deserialize(&mut &data[8..]).map_err(|_| ReadTransactionError::DeserializeError)
+    }
+}
+
+/// Extracts the pump.fun trade events of a transaction, in the order they were emitted.
+///
+/// Events are read from the self invocations of the pump.fun program, and from the
+/// `Program data:` logs of transactions made before events were emitted through CPIs.
+pub fn trade_events_in_transaction(transaction: &InspectedTransaction) -> Vec<TradeEvent> {
+    let pumpfun_program = pumpfun_program();
+    let events: Vec<TradeEvent> = transaction.inner_instructions
+        .iter()
+        .flat_map(|inner_instructions| &inner_instructions.instructions)
+        .filter(|instruction| instruction.program_id == pumpfun_program && instruction.data.starts_with(&EVENT_INSTRUCTION_TAG))
+        .filter_map(|instruction| TradeEvent::try_from_event_data(&instruction.data[8..]).ok())
+        .collect();
+    if !events.is_empty() {
+        return events;
+    }
+
+    transaction.logs
+        .iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .filter_map(|encoded_data| STANDARD.decode(encoded_data).ok())
+        .filter_map(|data| TradeEvent::try_from_event_data(&data).ok())
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_transactions::transaction::{InspectedInnerInstructions, InspectedInstruction};
+
+    fn trade_event_data() -> (TradeEvent, Vec<u8>) {
+        let trade_event = TradeEvent {
+            mint: Pubkey::new_unique(),
+            sol_amount: 1_000_000_000,
+            token_amount: 30_000_000_000_000,
+            is_buy: true,
+            user: Pubkey::new_unique(),
+            timestamp: 1_730_000_000,
+            virtual_sol_reserves: 31_000_000_000,
+            virtual_token_reserves: 1_043_000_000_000_000,
+        };
+        let mut data = TRADE_EVENT_DISCRIMINATOR.to_vec();
+        data.extend(borsh::to_vec(&trade_event).unwrap());
+        // newer events have additional fields
+        data.extend_from_slice(&[0; 40]);
+        (trade_event, data)
+    }
+
+    fn transaction(inner_instructions: Vec<InspectedInnerInstructions>, logs: Vec<String>) -> InspectedTransaction {
+        InspectedTransaction {
+            signature: String::new(),
+            slot: 0,
+            block_time: None,
+            is_versioned: false,
+            account_keys: vec![],
+            instructions: vec![],
+            inner_instructions,
+            logs,
+            fee: 5_000,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_trade_events_from_self_invocation() {
+        let (trade_event, event_data) = trade_event_data();
+        let mut data = EVENT_INSTRUCTION_TAG.to_vec();
+        data.extend(event_data);
+        let inner_instructions = vec![InspectedInnerInstructions {
+            index: 0,
+            instructions: vec![InspectedInstruction { program_id: pumpfun_program(), accounts: vec![], data, stack_height: Some(2) }],
+        }];
+
+        let events = trade_events_in_transaction(&transaction(inner_instructions, vec![]));
+        assert!(events == vec![trade_event]);
+    }
+
+    #[test]
+    fn test_trade_events_from_logs() {
+        let (trade_event, event_data) = trade_event_data();
+        let logs = vec![
+            "Program log: Instruction: Buy".to_string(),
+            format!("Program data: {}", STANDARD.encode(event_data)),
+        ];
+
+        let events = trade_events_in_transaction(&transaction(vec![], logs));
+        assert!(events == vec![trade_event]);
+    }
+}
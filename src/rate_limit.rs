@@ -0,0 +1,431 @@
+//! # Rate Limits
+//!
+//! Recognises the rate limit responses of common RPC providers, waits as long as the provider
+//! asks before retrying and surfaces a typed `RateLimitError` once retries run out, so callers
+//! and `RpcPool` can reroute instead of matching opaque error strings.
+
+use std::{fmt, sync::LazyLock, time::Duration};
+use async_trait::async_trait;
+use http::Extensions;
+use log::debug;
+use regex::Regex;
+use reqwest::{header::RETRY_AFTER, Request, Response, StatusCode};
+use reqwest_middleware::{ClientBuilder, Middleware, Next};
+use serde_json::Value;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_client::{RpcClient, RpcClientConfig},
+    rpc_request::{RpcError, RpcRequest},
+    rpc_sender::{RpcSender, RpcTransportStats}
+};
+use solana_rpc_client::http_sender::HttpSender;
+use solana_sdk::commitment_config::CommitmentConfig;
+use crate::{error::RateLimitError, utils::resolve_rpc_url};
+
+/// Hosted RPC provider of an endpoint, detected from its URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RpcProvider {
+    Helius,
+    QuickNode,
+    Triton,
+    Alchemy,
+    /// Public endpoints of Solana Labs such as `api.mainnet-beta.solana.com`.
+    Solana,
+    Other,
+}
+
+impl RpcProvider {
+    pub fn from_url(url: &str) -> Self {
+        let host = url
+            .split("://")
+            .last()
+            .and_then(|rest| rest.split(['/', '?', ':']).next())
+            .unwrap_or_default()
+            .to_lowercase();
+        if host.contains("helius") {
+            RpcProvider::Helius
+        } else if host.ends_with("quiknode.pro") || host.contains("quicknode") {
+            RpcProvider::QuickNode
+        } else if host.ends_with("rpcpool.com") || host.contains("triton") {
+            RpcProvider::Triton
+        } else if host.ends_with("alchemy.com") {
+            RpcProvider::Alchemy
+        } else if host.ends_with("solana.com") {
+            RpcProvider::Solana
+        } else {
+            RpcProvider::Other
+        }
+    }
+
+    /// Whether a JSON-RPC error of this provider reports a rate limit.
+    pub fn is_rate_limit_error(&self, code: i64, message: &str) -> bool {
+        let message = message.to_lowercase();
+        code == 429
+            || code == -32429
+            // Solana nodes use -32007 for skipped slots, QuickNode for its request limit
+            || (*self == RpcProvider::QuickNode && code == -32007)
+            || ["rate limit", "too many requests", "request limit"].iter().any(|pattern| message.contains(pattern))
+    }
+}
+
+impl fmt::Display for RpcProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RpcProvider::Helius => "Helius",
+            RpcProvider::QuickNode => "QuickNode",
+            RpcProvider::Triton => "Triton",
+            RpcProvider::Alchemy => "Alchemy",
+            RpcProvider::Solana => "Solana",
+            RpcProvider::Other => "RPC provider",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Retries of rate limited requests.
+///
+/// ### Fields
+///
+/// - `max_retries`: Retries before the `RateLimitError` is returned.
+/// - `backoff`: Delay before the first retry when the provider does not ask for one, doubled on
+///   every retry.
+/// - `max_wait`: Longest delay waited for, a provider asking for more fails the request right
+///   away so it can be rerouted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    pub max_retries: u32,
+    pub backoff: Duration,
+    pub max_wait: Duration,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self { max_retries: 3, backoff: Duration::from_millis(500), max_wait: Duration::from_secs(30) }
+    }
+}
+
+impl RateLimitConfig {
+    /// Delay before retry `retry`, starting at 0, without a delay from the provider.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.backoff.saturating_mul(1 << retry.min(16)).min(self.max_wait)
+    }
+}
+
+/// Longest delay taken from a provider, larger values are capped.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(600);
+
+static RETRY_HINT_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:retry after|try again in)\s*(\d+(?:\.\d+)?)\s*(ms|milliseconds?)?").unwrap()
+});
+
+/// Delay of `seconds` capped at `MAX_RETRY_DELAY`, `None` if it is negative or not a number.
+fn retry_delay(seconds: f64) -> Option<Duration> {
+    if seconds.is_nan() || seconds < 0.0 {
+        return None;
+    }
+    Some(Duration::try_from_secs_f64(seconds).ok().map_or(MAX_RETRY_DELAY, |delay| delay.min(MAX_RETRY_DELAY)))
+}
+
+/// Parses a `Retry-After` header in seconds, HTTP dates are not supported. Delays are capped at
+/// 10 minutes.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    retry_delay(value.trim().parse::<f64>().ok()?)
+}
+
+/// Delay asked for in an error message such as "retry after 2s" or "try again in 500ms", capped
+/// at 10 minutes.
+pub fn parse_retry_hint(message: &str) -> Option<Duration> {
+    let captures = RETRY_HINT_REGEX.captures(message)?;
+    let amount = captures[1].parse::<f64>().ok()?;
+    let seconds = if captures.get(2).is_some() { amount / 1_000.0 } else { amount };
+    retry_delay(seconds)
+}
+
+/// Rate limit error from the `Retry-After` header and body of an HTTP 429 response. Bodies are
+/// JSON-RPC errors for Helius, QuickNode, Triton and Alchemy and plain text for public nodes.
+pub fn parse_rate_limit_response(provider: RpcProvider, retry_after: Option<&str>, body: &str) -> RateLimitError {
+    let json = serde_json::from_str::<Value>(body).unwrap_or_default();
+    let message = json["error"]["message"]
+        .as_str()
+        .or(json["error"].as_str())
+        .or(json["message"].as_str())
+        .unwrap_or(body.trim());
+    let message = match message.is_empty() {
+        true => "Too many requests".to_string(),
+        false => message.to_string(),
+    };
+    let retry_after = retry_after.and_then(parse_retry_after).or_else(|| parse_retry_hint(&message));
+    RateLimitError { provider, retry_after, message }
+}
+
+/// The rate limit behind `err`, `None` if the request failed for another reason.
+///
+/// Recognises errors of `RateLimitSender`, HTTP 429 errors of a plain `HttpSender` and JSON-RPC
+/// rate limit errors of `provider`.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, rate_limit::{rate_limit_error, RpcProvider}};
+///
+/// let client = create_rpc_client("HELIUS_RPC_URL");
+/// if let Err(err) = client.get_slot() {
+///     if let Some(limit) = rate_limit_error(&err, RpcProvider::from_url(&client.url())) {
+///         println!("{} asks to wait {:?}", limit.provider, limit.retry_after);
+///     }
+/// }
+/// ```
+pub fn rate_limit_error(err: &ClientError, provider: RpcProvider) -> Option<RateLimitError> {
+    match err.kind() {
+        ClientErrorKind::Middleware(err) => err.downcast_ref::<RateLimitError>().cloned(),
+        ClientErrorKind::Reqwest(err) if err.status() == Some(StatusCode::TOO_MANY_REQUESTS) => {
+            Some(RateLimitError { provider, retry_after: None, message: err.to_string() })
+        }
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, message, .. })
+            if provider.is_rate_limit_error(*code, message) =>
+        {
+            Some(RateLimitError { provider, retry_after: parse_retry_hint(message), message: message.clone() })
+        }
+        _ => None,
+    }
+}
+
+/// HTTP middleware turning 429 responses into a `RateLimitError` with the delay and message of
+/// the provider, instead of the status error of `HttpSender`.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitMiddleware {
+    pub provider: RpcProvider,
+}
+
+#[async_trait]
+impl Middleware for RateLimitMiddleware {
+    async fn handle(
+        &self,
+        request: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>
+    ) -> reqwest_middleware::Result<Response> {
+        let response = next.run(request, extensions).await?;
+        if response.status() != StatusCode::TOO_MANY_REQUESTS {
+            return Ok(response);
+        }
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = response.text().await.unwrap_or_default();
+        let limit = parse_rate_limit_response(self.provider, retry_after.as_deref(), &body);
+        Err(reqwest_middleware::Error::middleware(limit))
+    }
+}
+
+/// `RpcSender` wrapper that retries rate limited requests after the delay asked by the provider,
+/// returning a `ClientErrorKind::Middleware` holding the `RateLimitError` once retries run out.
+pub struct RateLimitSender<S: RpcSender> {
+    inner: S,
+    provider: RpcProvider,
+    config: RateLimitConfig,
+}
+
+impl<S: RpcSender> RateLimitSender<S> {
+    /// Wraps `inner`, detecting the provider from its URL.
+    pub fn new(inner: S, config: RateLimitConfig) -> Self {
+        let provider = RpcProvider::from_url(&inner.url());
+        Self { inner, provider, config }
+    }
+}
+
+#[async_trait]
+impl<S: RpcSender + Send + Sync> RpcSender for RateLimitSender<S> {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let mut retries = 0;
+        loop {
+            let err = match self.inner.send(request, params.clone()).await {
+                Ok(result) => return Ok(result),
+                Err(err) => err,
+            };
+            let Some(limit) = rate_limit_error(&err, self.provider) else {
+                return Err(err);
+            };
+            let delay = limit.retry_after.unwrap_or_else(|| self.config.backoff(retries));
+            if retries >= self.config.max_retries || delay > self.config.max_wait {
+                return Err(ClientError::new_with_request(ClientErrorKind::Middleware(limit.into()), request));
+            }
+            debug!("{} rate limited {}, retrying in {:?}", self.provider, request, delay);
+            tokio::time::sleep(delay).await;
+            retries += 1;
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+/// HTTP sender to `url` whose rate limit responses are parsed by `RateLimitMiddleware` and
+/// retried following `config`.
+pub fn rate_limited_http_sender(url: &str, config: RateLimitConfig) -> RateLimitSender<HttpSender> {
+    let timeout = Duration::from_secs(30);
+    let client = reqwest::Client::builder()
+        .default_headers(HttpSender::default_headers())
+        .timeout(timeout)
+        .pool_idle_timeout(timeout)
+        .build()
+        .unwrap_or_default();
+    let client = ClientBuilder::new(client).with(RateLimitMiddleware { provider: RpcProvider::from_url(url) }).build();
+    RateLimitSender::new(HttpSender::new_with_client_with_middleware(url, client), config)
+}
+
+/// Creates an RPC client like `create_rpc_client` that waits out rate limits following
+/// `config` and reports the ones it gives up on as `RateLimitError`.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::rate_limit::{create_rate_limited_rpc_client, RateLimitConfig};
+///
+/// let client = create_rate_limited_rpc_client("HELIUS_RPC_URL", RateLimitConfig::default());
+/// let slot = client.get_slot();
+/// ```
+pub fn create_rate_limited_rpc_client(rpc_input: &str, config: RateLimitConfig) -> RpcClient {
+    RpcClient::new_sender(
+        rate_limited_http_sender(&resolve_rpc_url(rpc_input), config),
+        RpcClientConfig::with_commitment(CommitmentConfig::confirmed()),
+    )
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener
+    };
+    use crate::test_utils::RateLimitedSender;
+
+    fn no_wait() -> RateLimitConfig {
+        RateLimitConfig { backoff: Duration::ZERO, ..RateLimitConfig::default() }
+    }
+
+    /// Serves one HTTP response per connection, returning the URL of the server.
+    async fn serve(responses: Vec<String>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 4096];
+                // Reads the headers and the body announced by Content-Length
+                while let Ok(read) = stream.read(&mut buffer).await {
+                    request.extend_from_slice(&buffer[..read]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text[..end]
+                            .lines()
+                            .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|len| len.trim().parse::<usize>().unwrap_or(0)))
+                            .unwrap_or(0);
+                        if request.len() >= end + 4 + length {
+                            break;
+                        }
+                    }
+                    if read == 0 {
+                        break;
+                    }
+                }
+                stream.write_all(response.as_bytes()).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+        url
+    }
+
+    fn http_response(status: &str, headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\n{}Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status, headers, body.len(), body
+        )
+    }
+
+    #[test]
+    fn test_provider_from_url() {
+        assert!(RpcProvider::from_url("https://mainnet.helius-rpc.com/?api-key=key") == RpcProvider::Helius);
+        assert!(RpcProvider::from_url("https://example.solana-mainnet.quiknode.pro/token/") == RpcProvider::QuickNode);
+        assert!(RpcProvider::from_url("https://example.mainnet.rpcpool.com") == RpcProvider::Triton);
+        assert!(RpcProvider::from_url("https://solana-mainnet.g.alchemy.com/v2/key") == RpcProvider::Alchemy);
+        assert!(RpcProvider::from_url("https://api.mainnet-beta.solana.com") == RpcProvider::Solana);
+        assert!(RpcProvider::from_url("http://127.0.0.1:8899") == RpcProvider::Other);
+        // Only QuickNode reports rate limits with the code Solana nodes use for skipped slots
+        assert!(RpcProvider::QuickNode.is_rate_limit_error(-32007, "Slot was skipped"));
+        assert!(!RpcProvider::Other.is_rate_limit_error(-32007, "Slot was skipped"));
+    }
+
+    #[test]
+    fn test_parse_huge_and_invalid_retry_delays() {
+        assert!(parse_retry_after("1e300") == Some(MAX_RETRY_DELAY));
+        assert!(parse_retry_after("inf") == Some(MAX_RETRY_DELAY));
+        assert!(parse_retry_after("NaN").is_none());
+        assert!(parse_retry_after("-1").is_none());
+        let huge_hint = format!("retry after {}s", "9".repeat(400));
+        assert!(parse_retry_hint(&huge_hint) == Some(MAX_RETRY_DELAY));
+        assert!(parse_retry_hint(&format!("try again in {}ms", "9".repeat(30))) == Some(MAX_RETRY_DELAY));
+    }
+
+    #[test]
+    fn test_parse_rate_limit_response() {
+        assert!(parse_retry_after("2") == Some(Duration::from_secs(2)));
+        assert!(parse_retry_after(" 0.5 ") == Some(Duration::from_millis(500)));
+        assert!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT").is_none());
+        assert!(parse_retry_hint("Too many requests, try again in 250ms") == Some(Duration::from_millis(250)));
+
+        let helius = r#"{"jsonrpc":"2.0","error":{"code":-32429,"message":"rate limited, retry after 3s"},"id":1}"#;
+        let limit = parse_rate_limit_response(RpcProvider::Helius, None, helius);
+        assert!(limit.message == "rate limited, retry after 3s" && limit.retry_after == Some(Duration::from_secs(3)));
+        // The header wins over hints in the body
+        let limit = parse_rate_limit_response(RpcProvider::Solana, Some("10"), "Too many requests for a specific RPC call\n");
+        assert!(limit.message == "Too many requests for a specific RPC call" && limit.retry_after == Some(Duration::from_secs(10)));
+        assert!(parse_rate_limit_response(RpcProvider::Other, None, "").message == "Too many requests");
+    }
+
+    #[tokio::test]
+    async fn test_sender_retries_rate_limited_requests() {
+        let sender = RateLimitSender::new(RateLimitedSender::new("https://mainnet.helius-rpc.com", (-32429, "Too many requests"), 2, json!(7)), no_wait());
+        assert!(sender.send(RpcRequest::GetSlot, Value::Null).await.unwrap() == json!(7));
+
+        let config = RateLimitConfig { max_retries: 1, ..no_wait() };
+        let sender = RateLimitSender::new(RateLimitedSender::new("https://mainnet.helius-rpc.com", (-32429, "Too many requests"), 2, json!(7)), config);
+        let err = sender.send(RpcRequest::GetSlot, Value::Null).await.unwrap_err();
+        let limit = rate_limit_error(&err, RpcProvider::Other).unwrap();
+        assert!(limit.provider == RpcProvider::Helius && limit.retry_after.is_none());
+        assert!(err.request() == Some(&RpcRequest::GetSlot));
+
+        // Other errors are returned untouched
+        let sender = RateLimitSender::new(RateLimitedSender::new("http://127.0.0.1", (-32002, "Transaction simulation failed"), 1, json!(7)), no_wait());
+        let err = sender.send(RpcRequest::GetSlot, Value::Null).await.unwrap_err();
+        assert!(rate_limit_error(&err, RpcProvider::Other).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_http_rate_limit_responses() {
+        let limited = http_response("429 Too Many Requests", "Retry-After: 0\r\n", r#"{"error":{"code":429,"message":"Too many requests"}}"#);
+        let ok = http_response("200 OK", "", r#"{"jsonrpc":"2.0","result":42,"id":0}"#);
+        let url = serve(vec![limited, ok]).await;
+        let sender = rate_limited_http_sender(&url, no_wait());
+        assert!(sender.send(RpcRequest::GetSlot, Value::Null).await.unwrap() == json!(42));
+
+        // A provider asking for longer than `max_wait` fails right away
+        let limited = http_response("429 Too Many Requests", "Retry-After: 120\r\n", r#"{"error":{"code":-32429,"message":"Rate limit exceeded"}}"#);
+        let url = serve(vec![limited]).await;
+        let sender = rate_limited_http_sender(&url, RateLimitConfig { max_wait: Duration::from_secs(1), ..no_wait() });
+        let err = sender.send(RpcRequest::GetSlot, Value::Null).await.unwrap_err();
+        let limit = rate_limit_error(&err, RpcProvider::Other).unwrap();
+        assert!(limit.message == "Rate limit exceeded" && limit.retry_after == Some(Duration::from_secs(120)));
+    }
+}
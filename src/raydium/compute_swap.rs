@@ -44,6 +44,70 @@ struct SwapData {
     slippage_bps: i32,
     #[serde(rename = "priceImpactPct")]
     price_impact_pct: f64,
+    #[serde(rename = "routePlan", default)]
+    route_plan: Vec<RoutePlan>,
+}
+
+/// A single pool hop of the swap route
+#[derive(Deserialize, Debug)]
+#[allow(unused)]
+struct RoutePlan {
+    #[serde(rename = "poolId")]
+    pool_id: String,
+    #[serde(rename = "feeMint")]
+    fee_mint: String,
+    #[serde(rename = "feeAmount")]
+    fee_amount: String,
+}
+
+/// Quote of a Raydium swap with amounts in raw token units.
+///
+/// ### Fields
+///
+/// - `input_amount`: Amount of the input token swapped.
+/// - `output_amount`: Expected amount of the output token received.
+/// - `price_impact_pct`: Price impact of the swap in percent.
+/// - `fees`: Pool fees of each hop of the route as `(fee_mint, fee_amount)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaydiumSwapQuote {
+    pub input_amount: u64,
+    pub output_amount: u64,
+    pub price_impact_pct: f64,
+    pub fees: Vec<(String, u64)>,
+}
+
+/// Gets a quote for swapping `input_amount` raw units of `input_mint` to `output_mint` through Raydium.
+pub async fn get_raydium_swap_quote(
+    input_mint: &str,
+    input_amount: u64,
+    output_mint: &str,
+    slippage_bps: u32,
+) -> Result<RaydiumSwapQuote, RaydiumSwapError> {
+    let url = format!(
+        "https://transaction-v1.raydium.io/compute/swap-base-in?inputMint={}&outputMint={}&amount={}&slippageBps={}&txVersion=V0",
+        input_mint, output_mint, input_amount, slippage_bps
+    );
+    let response: RaydiumPriceResponse = reqwest::get(&url).await?.json().await?;
+    let data = match (response.data, response.msg) {
+        (Some(data), _) => data,
+        (None, Some(msg)) => return Err(RaydiumSwapError::InvalidResponse(msg)),
+        (None, None) => return Err(RaydiumSwapError::InvalidResponse("Unknown error".to_string())),
+    };
+
+    let parse_amount = |amount: &str| amount
+        .parse::<u64>()
+        .map_err(|_| RaydiumSwapError::InvalidResponse(format!("Failed to parse amount {}", amount)));
+    let fees = data.route_plan
+        .iter()
+        .map(|route| Ok((route.fee_mint.clone(), parse_amount(&route.fee_amount)?)))
+        .collect::<Result<Vec<(String, u64)>, RaydiumSwapError>>()?;
+
+    Ok(RaydiumSwapQuote {
+        input_amount: parse_amount(&data.input_amount)?,
+        output_amount: parse_amount(&data.output_amount)?,
+        price_impact_pct: data.price_impact_pct,
+        fees,
+    })
 }
 
 /// Gets the output amount of tokens from a Raydium swap.
@@ -0,0 +1,419 @@
+//! # Raydium CPMM
+//!
+//! Instructions of the Raydium constant product AMM (CPMM), which creates pools for any pair of
+//! mints without an OpenBook market. The program id is part of the pool keys, so the same
+//! instructions work with the mainnet program from `raydium_cpmm_program` and the devnet deployment.
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signer::Signer
+};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent
+};
+use spl_token_2022::{extension::StateWithExtensions, state::Account as Token2022Account};
+use crate::{
+    constants::{
+        raydium_accounts::{CPMM_INITIALIZE_DISCRIMINATOR, CPMM_SWAP_BASE_INPUT_DISCRIMINATOR},
+        solana_programs::{associated_token_account_program, rent_program, system_program, token_program}
+    },
+    error::{ReadTransactionError, TransactionBuilderError},
+    pumpfun::curve_math::{mul_div_ceil, to_u64, tokens_out}
+};
+use crate::write_transactions::transaction_builder::TransactionBuilder;
+
+/// `(mint, token program, vault)` of one side of a pool.
+type PoolSide = (Pubkey, Pubkey, Pubkey);
+
+/// Trade fee rates are in millionths.
+const FEE_RATE_DENOMINATOR: u128 = 1_000_000;
+
+// Pool state: discriminator, ten public keys, five single byte fields and the LP supply precede
+// the protocol and fund fees of both tokens
+const POOL_STATE_FEES_OFFSET: usize = 8 + 10 * 32 + 5 + 8;
+
+// Amm config: discriminator, bump, disable_create_pool and index precede the trade fee rate
+const AMM_CONFIG_TRADE_FEE_RATE_OFFSET: usize = 8 + 1 + 1 + 2;
+
+/// Addresses of a CPMM pool, all derived from the program id, the amm config and the two mints.
+/// Mints are ordered by their bytes as required by the program, `token_0_mint < token_1_mint`.
+///
+/// ### Fields
+///
+/// - `program_id`: CPMM program owning the pool.
+/// - `amm_config`: Fee tier config of the pool.
+/// - `authority`: Authority of the vaults and the LP mint.
+/// - `pool_state`: Pool account.
+/// - `token_0_mint`, `token_1_mint`: Mints of the pair.
+/// - `token_0_program`, `token_1_program`: Token programs of the mints.
+/// - `token_0_vault`, `token_1_vault`: Token accounts holding the liquidity.
+/// - `lp_mint`: Mint of the LP token.
+/// - `observation_state`: Price oracle account of the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpmmPoolKeys {
+    pub program_id: Pubkey,
+    pub amm_config: Pubkey,
+    pub authority: Pubkey,
+    pub pool_state: Pubkey,
+    pub token_0_mint: Pubkey,
+    pub token_1_mint: Pubkey,
+    pub token_0_program: Pubkey,
+    pub token_1_program: Pubkey,
+    pub token_0_vault: Pubkey,
+    pub token_1_vault: Pubkey,
+    pub lp_mint: Pubkey,
+    pub observation_state: Pubkey,
+}
+
+impl CpmmPoolKeys {
+    /// Derives the pool addresses for a pair of `(mint, token_program)`, in any order, using the
+    /// amm config at `amm_config_index`. Index 0 is the default 0.25% fee tier.
+    pub fn derive(program_id: &Pubkey, amm_config_index: u16, token_a: (Pubkey, Pubkey), token_b: (Pubkey, Pubkey)) -> Self {
+        let ((token_0_mint, token_0_program), (token_1_mint, token_1_program)) = if token_a.0 < token_b.0 {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        let amm_config = derive_amm_config(program_id, amm_config_index);
+        let (authority, _) = Pubkey::find_program_address(&[b"vault_and_lp_mint_auth_seed"], program_id);
+        let (pool_state, _) = Pubkey::find_program_address(
+            &[b"pool", amm_config.as_ref(), token_0_mint.as_ref(), token_1_mint.as_ref()],
+            program_id,
+        );
+        let (token_0_vault, _) = Pubkey::find_program_address(&[b"pool_vault", pool_state.as_ref(), token_0_mint.as_ref()], program_id);
+        let (token_1_vault, _) = Pubkey::find_program_address(&[b"pool_vault", pool_state.as_ref(), token_1_mint.as_ref()], program_id);
+        let (lp_mint, _) = Pubkey::find_program_address(&[b"pool_lp_mint", pool_state.as_ref()], program_id);
+        let (observation_state, _) = Pubkey::find_program_address(&[b"observation", pool_state.as_ref()], program_id);
+
+        Self {
+            program_id: *program_id,
+            amm_config,
+            authority,
+            pool_state,
+            token_0_mint,
+            token_1_mint,
+            token_0_program,
+            token_1_program,
+            token_0_vault,
+            token_1_vault,
+            lp_mint,
+            observation_state,
+        }
+    }
+
+    /// Input and output sides of a swap from `input_mint`,
+    /// `None` if `input_mint` is not part of the pool.
+    fn sides(&self, input_mint: &Pubkey) -> Option<(PoolSide, PoolSide)> {
+        let side_0 = (self.token_0_mint, self.token_0_program, self.token_0_vault);
+        let side_1 = (self.token_1_mint, self.token_1_program, self.token_1_vault);
+        if *input_mint == self.token_0_mint {
+            Some((side_0, side_1))
+        } else if *input_mint == self.token_1_mint {
+            Some((side_1, side_0))
+        } else {
+            None
+        }
+    }
+}
+
+/// Liquidity of a CPMM pool available to swaps.
+///
+/// ### Fields
+///
+/// - `token_0_reserve`, `token_1_reserve`: Vault balances without the protocol and fund fees
+///   collected in the vaults, which are not part of the liquidity.
+/// - `trade_fee_rate`: Fee taken from the input of a swap, in millionths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpmmReserves {
+    pub token_0_reserve: u64,
+    pub token_1_reserve: u64,
+    pub trade_fee_rate: u64,
+}
+
+impl CpmmReserves {
+    /// Quotes the output of an exact input swap of `amount_in` of `input_mint` on `pool`. Token-2022
+    /// transfer fees of the mints are not included.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::QuoteError` if `input_mint` is not part of the pool and
+    /// `ReadTransactionError::MathError` if the pool has no liquidity.
+    pub fn quote_swap_base_input(&self, pool: &CpmmPoolKeys, input_mint: &Pubkey, amount_in: u64) -> Result<u64, ReadTransactionError> {
+        let (input_reserve, output_reserve) = if *input_mint == pool.token_0_mint {
+            (self.token_0_reserve, self.token_1_reserve)
+        } else if *input_mint == pool.token_1_mint {
+            (self.token_1_reserve, self.token_0_reserve)
+        } else {
+            return Err(ReadTransactionError::QuoteError(format!("{} is not a mint of the pool", input_mint)));
+        };
+        let trade_fee = mul_div_ceil(amount_in as u128, self.trade_fee_rate as u128, FEE_RATE_DENOMINATOR, "trade fee")?;
+        // Same constant product as the bonding curve, rounded down in favour of the pool
+        let amount_out = tokens_out(input_reserve as u128, output_reserve as u128, (amount_in as u128).saturating_sub(trade_fee))?;
+        Ok(to_u64(amount_out, "amount out")?)
+    }
+}
+
+/// Fetches the reserves and trade fee of a CPMM pool in a single request.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::AccountNotFound` if the pool, its amm config or a vault does
+/// not exist, and `ReadTransactionError::DeserializeError` if an account can not be read.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_sdk::pubkey::Pubkey;
+/// use easy_solana::{
+///     constants::{raydium_accounts::raydium_cpmm_program, solana_programs::{sol_pubkey, token_program}},
+///     create_rpc_client,
+///     raydium::cpmm::{get_cpmm_reserves, CpmmPoolKeys}
+/// };
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let mint = Pubkey::from_str_const("CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump");
+/// let pool = CpmmPoolKeys::derive(&raydium_cpmm_program(), 0, (mint, token_program()), (sol_pubkey(), token_program()));
+/// let reserves = get_cpmm_reserves(&client, &pool).unwrap();
+/// let lamports_out = reserves.quote_swap_base_input(&pool, &mint, 1_000_000).unwrap();
+/// ```
+pub fn get_cpmm_reserves(client: &RpcClient, pool: &CpmmPoolKeys) -> Result<CpmmReserves, ReadTransactionError> {
+    let accounts = client.get_multiple_accounts(&[pool.pool_state, pool.amm_config, pool.token_0_vault, pool.token_1_vault])?;
+    let [pool_state, amm_config, vault_0, vault_1]: [Account; 4] = accounts
+        .into_iter()
+        .collect::<Option<Vec<Account>>>()
+        .and_then(|accounts| accounts.try_into().ok())
+        .ok_or(ReadTransactionError::AccountNotFound)?;
+
+    let read_u64 = |data: &[u8], offset: usize| data
+        .get(offset..offset + 8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(ReadTransactionError::DeserializeError);
+    let vault_amount = |data: &[u8]| StateWithExtensions::<Token2022Account>::unpack(data)
+        .map(|vault| vault.base.amount)
+        .map_err(|_| ReadTransactionError::DeserializeError);
+    // Fees are stored as protocol fees of both tokens followed by fund fees of both tokens
+    let fees_0 = read_u64(&pool_state.data, POOL_STATE_FEES_OFFSET)? as u128 + read_u64(&pool_state.data, POOL_STATE_FEES_OFFSET + 16)? as u128;
+    let fees_1 = read_u64(&pool_state.data, POOL_STATE_FEES_OFFSET + 8)? as u128 + read_u64(&pool_state.data, POOL_STATE_FEES_OFFSET + 24)? as u128;
+
+    Ok(CpmmReserves {
+        token_0_reserve: (vault_amount(&vault_0.data)? as u128).saturating_sub(fees_0) as u64,
+        token_1_reserve: (vault_amount(&vault_1.data)? as u128).saturating_sub(fees_1) as u64,
+        trade_fee_rate: read_u64(&amm_config.data, AMM_CONFIG_TRADE_FEE_RATE_OFFSET)?,
+    })
+}
+
+/// Derives the address of the amm config at `index`.
+pub fn derive_amm_config(program_id: &Pubkey, index: u16) -> Pubkey {
+    Pubkey::find_program_address(&[b"amm_config", &index.to_be_bytes()], program_id).0
+}
+
+/// Creates the instruction opening the pool `pool` with initial liquidity from the associated
+/// token accounts of `creator`, who receives the LP tokens. Pass `open_time` 0 to allow swaps
+/// immediately.
+///
+/// ### Arguments
+///
+/// * `pool` - keys of the pool to open.
+/// * `creator` - wallet providing the liquidity.
+/// * `create_pool_fee_account` - account of the program receiving the pool creation fee.
+/// * `init_amount_0`, `init_amount_1` - raw amounts of `token_0_mint` and `token_1_mint` deposited.
+/// * `open_time` - unix timestamp from which swaps are allowed.
+pub fn cpmm_initialize_instruction(
+    pool: &CpmmPoolKeys,
+    creator: &Pubkey,
+    create_pool_fee_account: &Pubkey,
+    init_amount_0: u64,
+    init_amount_1: u64,
+    open_time: u64,
+) -> Instruction {
+    let mut data = CPMM_INITIALIZE_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&init_amount_0.to_le_bytes());
+    data.extend_from_slice(&init_amount_1.to_le_bytes());
+    data.extend_from_slice(&open_time.to_le_bytes());
+
+    Instruction {
+        program_id: pool.program_id,
+        accounts: vec![
+            AccountMeta::new(*creator, true),
+            AccountMeta::new_readonly(pool.amm_config, false),
+            AccountMeta::new_readonly(pool.authority, false),
+            AccountMeta::new(pool.pool_state, false),
+            AccountMeta::new_readonly(pool.token_0_mint, false),
+            AccountMeta::new_readonly(pool.token_1_mint, false),
+            AccountMeta::new(pool.lp_mint, false),
+            AccountMeta::new(get_associated_token_address_with_program_id(creator, &pool.token_0_mint, &pool.token_0_program), false),
+            AccountMeta::new(get_associated_token_address_with_program_id(creator, &pool.token_1_mint, &pool.token_1_program), false),
+            AccountMeta::new(get_associated_token_address_with_program_id(creator, &pool.lp_mint, &token_program()), false),
+            AccountMeta::new(pool.token_0_vault, false),
+            AccountMeta::new(pool.token_1_vault, false),
+            AccountMeta::new(*create_pool_fee_account, false),
+            AccountMeta::new(pool.observation_state, false),
+            AccountMeta::new_readonly(token_program(), false),
+            AccountMeta::new_readonly(pool.token_0_program, false),
+            AccountMeta::new_readonly(pool.token_1_program, false),
+            AccountMeta::new_readonly(associated_token_account_program(), false),
+            AccountMeta::new_readonly(system_program(), false),
+            AccountMeta::new_readonly(rent_program(), false),
+        ],
+        data,
+    }
+}
+
+/// Creates an exact input swap of `amount_in` of `input_mint` between the associated token
+/// accounts of `payer`, failing if less than `minimum_amount_out` would be received.
+///
+/// ## Errors
+///
+/// Returns `TransactionBuilderError::InstructionError` if `input_mint` is not part of the pool.
+pub fn cpmm_swap_base_input_instruction(
+    pool: &CpmmPoolKeys,
+    payer: &Pubkey,
+    input_mint: &Pubkey,
+    amount_in: u64,
+    minimum_amount_out: u64,
+) -> Result<Instruction, TransactionBuilderError> {
+    let ((input_mint, input_program, input_vault), (output_mint, output_program, output_vault)) = pool
+        .sides(input_mint)
+        .ok_or_else(|| TransactionBuilderError::InstructionError(format!("{} is not a mint of the pool", input_mint)))?;
+    let mut data = CPMM_SWAP_BASE_INPUT_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+
+    Ok(Instruction {
+        program_id: pool.program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*payer, true),
+            AccountMeta::new_readonly(pool.authority, false),
+            AccountMeta::new_readonly(pool.amm_config, false),
+            AccountMeta::new(pool.pool_state, false),
+            AccountMeta::new(get_associated_token_address_with_program_id(payer, &input_mint, &input_program), false),
+            AccountMeta::new(get_associated_token_address_with_program_id(payer, &output_mint, &output_program), false),
+            AccountMeta::new(input_vault, false),
+            AccountMeta::new(output_vault, false),
+            AccountMeta::new_readonly(input_program, false),
+            AccountMeta::new_readonly(output_program, false),
+            AccountMeta::new_readonly(input_mint, false),
+            AccountMeta::new_readonly(output_mint, false),
+            AccountMeta::new(pool.observation_state, false),
+        ],
+        data,
+    })
+}
+
+impl TransactionBuilder<'_> {
+    /// Adds an exact input swap on a Raydium CPMM pool for the payer keypair, creating the
+    /// associated token account of the output mint if it does not exist. Wrap SOL first with
+    /// `wrap_sol` when swapping from WSOL.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::InstructionError` if `input_mint` is not part of the pool.
+    pub fn raydium_cpmm_swap(&mut self, pool: &CpmmPoolKeys, input_mint: &Pubkey, amount_in: u64, minimum_amount_out: u64) -> Result<&mut Self, TransactionBuilderError> {
+        let payer = self.payer_keypair.pubkey();
+        let swap_instruction = cpmm_swap_base_input_instruction(pool, &payer, input_mint, amount_in, minimum_amount_out)?;
+        let (output_mint, output_program) = if *input_mint == pool.token_0_mint {
+            (pool.token_1_mint, pool.token_1_program)
+        } else {
+            (pool.token_0_mint, pool.token_0_program)
+        };
+        self.instructions.push(create_associated_token_account_idempotent(
+            &self.fee_payer_pubkey(),
+            &payer,
+            &output_mint,
+            &output_program,
+        ));
+        self.instructions.push(swap_instruction);
+        Ok(self)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::program_pack::Pack;
+    use crate::{
+        constants::{raydium_accounts::raydium_cpmm_program, solana_programs::sol_pubkey},
+        test_utils::{account_with_data, mock_client, multiple_accounts_response}
+    };
+
+    #[test]
+    fn test_pool_keys_are_independent_of_mint_order() {
+        let program_id = raydium_cpmm_program();
+        let mint = Pubkey::new_unique();
+        let pool = CpmmPoolKeys::derive(&program_id, 0, (mint, token_program()), (sol_pubkey(), token_program()));
+        let reversed = CpmmPoolKeys::derive(&program_id, 0, (sol_pubkey(), token_program()), (mint, token_program()));
+        assert!(pool == reversed);
+        assert!(pool.token_0_mint < pool.token_1_mint);
+        assert!(pool.amm_config == derive_amm_config(&program_id, 0));
+        assert!(pool.pool_state != CpmmPoolKeys::derive(&program_id, 1, (mint, token_program()), (sol_pubkey(), token_program())).pool_state);
+    }
+
+    #[test]
+    fn test_initialize_instruction() {
+        let pool = CpmmPoolKeys::derive(&raydium_cpmm_program(), 0, (Pubkey::new_unique(), token_program()), (sol_pubkey(), token_program()));
+        let (creator, fee_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = cpmm_initialize_instruction(&pool, &creator, &fee_account, 1_000, 2_000, 0);
+        assert!(instruction.data[..8] == CPMM_INITIALIZE_DISCRIMINATOR);
+        assert!(instruction.data[8..16] == 1_000_u64.to_le_bytes());
+        assert!(instruction.data[16..24] == 2_000_u64.to_le_bytes());
+        assert!(instruction.data.len() == 32);
+        assert!(instruction.accounts.len() == 20);
+        assert!(instruction.accounts[0].pubkey == creator && instruction.accounts[0].is_signer);
+        assert!(instruction.accounts[12].pubkey == fee_account);
+    }
+
+    #[test]
+    fn test_swap_instruction_orders_sides_by_input() {
+        let mint = Pubkey::new_unique();
+        let pool = CpmmPoolKeys::derive(&raydium_cpmm_program(), 0, (mint, token_program()), (sol_pubkey(), token_program()));
+        let payer = Pubkey::new_unique();
+
+        let buy = cpmm_swap_base_input_instruction(&pool, &payer, &sol_pubkey(), 1_000, 10).unwrap();
+        assert!(buy.data[..8] == CPMM_SWAP_BASE_INPUT_DISCRIMINATOR);
+        assert!(buy.accounts[4].pubkey == get_associated_token_address_with_program_id(&payer, &sol_pubkey(), &token_program()));
+        assert!(buy.accounts[11].pubkey == mint);
+
+        let sell = cpmm_swap_base_input_instruction(&pool, &payer, &mint, 10, 0).unwrap();
+        assert!(sell.accounts[6].pubkey == buy.accounts[7].pubkey);
+        assert!(sell.accounts[10].pubkey == mint);
+
+        assert!(cpmm_swap_base_input_instruction(&pool, &payer, &Pubkey::new_unique(), 1, 0).is_err());
+    }
+
+    #[test]
+    fn test_cpmm_reserves_exclude_collected_fees() {
+        let pool = CpmmPoolKeys::derive(&raydium_cpmm_program(), 0, (Pubkey::new_unique(), token_program()), (sol_pubkey(), token_program()));
+        let mut pool_state = vec![0; 637];
+        // protocol fees of token 0 and fund fees of token 1
+        pool_state[POOL_STATE_FEES_OFFSET..POOL_STATE_FEES_OFFSET + 8].copy_from_slice(&1_000_u64.to_le_bytes());
+        pool_state[POOL_STATE_FEES_OFFSET + 24..POOL_STATE_FEES_OFFSET + 32].copy_from_slice(&2_000_u64.to_le_bytes());
+        let mut amm_config = vec![0; 236];
+        amm_config[AMM_CONFIG_TRADE_FEE_RATE_OFFSET..AMM_CONFIG_TRADE_FEE_RATE_OFFSET + 8].copy_from_slice(&2_500_u64.to_le_bytes());
+        let vault = |mint: Pubkey, amount: u64| {
+            let mut data = vec![0; spl_token::state::Account::LEN];
+            spl_token::state::Account { mint, owner: pool.authority, amount, state: spl_token::state::AccountState::Initialized, ..Default::default() }
+                .pack_into_slice(&mut data);
+            Some(account_with_data(token_program(), data))
+        };
+        let client = mock_client(vec![(RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[
+            Some(account_with_data(raydium_cpmm_program(), pool_state)),
+            Some(account_with_data(raydium_cpmm_program(), amm_config)),
+            vault(pool.token_0_mint, 1_000_001_000),
+            vault(pool.token_1_mint, 2_000_002_000),
+        ], 1))]);
+
+        let reserves = get_cpmm_reserves(&client, &pool).unwrap();
+        assert!(reserves == CpmmReserves { token_0_reserve: 1_000_000_000, token_1_reserve: 2_000_000_000, trade_fee_rate: 2_500 });
+        // 0.25% of the input is taken as fee before the constant product
+        let amount_out = reserves.quote_swap_base_input(&pool, &pool.token_0_mint, 1_000_000).unwrap();
+        assert!(amount_out == (2_000_000_000_u128 * 997_500 / (1_000_000_000 + 997_500)) as u64);
+        assert!(reserves.quote_swap_base_input(&pool, &Pubkey::new_unique(), 1_000_000).is_err());
+    }
+}
@@ -0,0 +1,289 @@
+//! # Liquidity Lock
+//!
+//! Checks whether the liquidity of a Raydium pool can be pulled, by inspecting who holds its
+//! LP tokens. LP tokens that were burned, sent to the incinerator or deposited into a locker
+//! can not be withdrawn, while LP tokens left with the deployer can be redeemed at any time.
+
+use std::str::FromStr;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey};
+use spl_token::instruction::TokenInstruction;
+use crate::{
+    constants::{
+        raydium_accounts::{raydium_lp_locker_program, streamflow_program},
+        solana_programs::incinerator
+    },
+    error::ReadTransactionError,
+    read_transactions::{
+        history::{get_transaction_history, HistoryConfig},
+        instruction_decoder::DecodedInstruction,
+        mint_account::get_mint_account,
+        transaction::InspectedTransaction
+    }
+};
+
+/// Accounts and history inspected by `verify_liquidity_lock`.
+///
+/// ### Fields
+///
+/// - `lockers`: Programs holding locked LP tokens, by default the Raydium LP locker and Streamflow.
+/// - `burn_history`: Transactions of the LP mint searched for burns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiquidityLockConfig {
+    pub lockers: Vec<Pubkey>,
+    pub burn_history: HistoryConfig,
+}
+
+impl Default for LiquidityLockConfig {
+    fn default() -> Self {
+        Self {
+            lockers: vec![raydium_lp_locker_program(), streamflow_program()],
+            burn_history: HistoryConfig { limit: None, ..Default::default() },
+        }
+    }
+}
+
+/// Who holds LP tokens.
+///
+/// - Deployer: The wallet that deployed the pool, which can withdraw the liquidity.
+/// - Locker: An account of a locker program.
+/// - Incinerator: The incinerator address, the tokens are lost.
+/// - Other: Any other wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LpHolderKind {
+    Deployer,
+    Locker(Pubkey),
+    Incinerator,
+    Other,
+}
+
+/// A token account holding LP tokens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LpHolder {
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub kind: LpHolderKind,
+}
+
+/// Distribution of the LP tokens of a pool. Shares are of every LP token minted, between 0 and 1.
+///
+/// ### Fields
+///
+/// - `lp_mint`: The LP mint.
+/// - `supply`: LP tokens in circulation.
+/// - `burned`: LP tokens burned within the searched history.
+/// - `burned_share`: Share burned or held by the incinerator.
+/// - `locked_share`: Share held by lockers.
+/// - `deployer_share`: Share held by the deployer.
+/// - `holders`: Largest holders of the LP mint, at most 20.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiquidityLockReport {
+    pub lp_mint: Pubkey,
+    pub supply: u64,
+    pub burned: u64,
+    pub burned_share: f64,
+    pub locked_share: f64,
+    pub deployer_share: f64,
+    pub holders: Vec<LpHolder>,
+}
+
+impl LiquidityLockReport {
+    /// Builds the report of `holders` of an LP mint with `supply` tokens in circulation after `burned` were burned.
+    pub fn new(lp_mint: Pubkey, supply: u64, burned: u64, holders: Vec<LpHolder>) -> Self {
+        let minted = supply as u128 + burned as u128;
+        let share_of = |kind_matches: fn(&LpHolderKind) -> bool| {
+            let amount: u128 = holders.iter().filter(|holder| kind_matches(&holder.kind)).map(|holder| holder.amount as u128).sum();
+            if minted == 0 { 0.0 } else { amount as f64 / minted as f64 }
+        };
+        let incinerated_share = share_of(|kind| *kind == LpHolderKind::Incinerator);
+        let burned_share = if minted == 0 { 0.0 } else { burned as f64 / minted as f64 } + incinerated_share;
+
+        Self {
+            lp_mint,
+            supply,
+            burned,
+            burned_share,
+            locked_share: share_of(|kind| matches!(kind, LpHolderKind::Locker(_))),
+            deployer_share: share_of(|kind| *kind == LpHolderKind::Deployer),
+            holders,
+        }
+    }
+
+    /// Share of the liquidity that can not be withdrawn, burned or locked.
+    pub fn secured_share(&self) -> f64 {
+        (self.burned_share + self.locked_share).min(1.0)
+    }
+}
+
+/// Inspects the LP mint of a Raydium pool: how many LP tokens were burned or sent to a locker,
+/// and what share remains with the deployer. Holders are read from the 20 largest LP token
+/// accounts, an account is held by a locker if its owner is a locker program or an account of one.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InvalidAddress` if an address is invalid,
+/// `ReadTransactionError::DeserializeError` if `lp_mint` is not an SPL token mint,
+/// or an error if the accounts or the burn history can not be fetched.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_client::rpc_client::RpcClient;
+/// use easy_solana::raydium::liquidity_lock::{verify_liquidity_lock, LiquidityLockConfig};
+///
+/// let client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+/// let lp_mint = "8HoQnePLqPj4M7PUDzfw8e3Ymdwgc7NLGnaTUapubyvu";
+/// let deployer = "9PvGbwGBQVJ5gVn5DjS6ubpkMaHcA5n1AmphFFaytaQR";
+/// let report = verify_liquidity_lock(&client, lp_mint, deployer, &LiquidityLockConfig::default()).unwrap();
+/// println!("{:.1}% of the liquidity is burned or locked", report.secured_share() * 100.0);
+/// ```
+pub fn verify_liquidity_lock(
+    client: &RpcClient,
+    lp_mint: &str,
+    deployer: &str,
+    config: &LiquidityLockConfig
+) -> Result<LiquidityLockReport, ReadTransactionError> {
+    let lp_mint_pubkey = Pubkey::from_str(lp_mint)?;
+    let deployer = Pubkey::from_str(deployer)?;
+    let mint = get_mint_account(client, lp_mint)?;
+
+    let largest_accounts = client.get_token_largest_accounts(&lp_mint_pubkey)?;
+    let token_account_pubkeys = largest_accounts
+        .iter()
+        .map(|largest_account| Pubkey::from_str(&largest_account.address))
+        .collect::<Result<Vec<Pubkey>, _>>()?;
+    let token_accounts = client.get_multiple_accounts(&token_account_pubkeys)?;
+    let token_accounts: Vec<(Pubkey, spl_token::state::Account)> = token_account_pubkeys
+        .into_iter()
+        .zip(token_accounts)
+        .filter_map(|(pubkey, account)| Some((pubkey, spl_token::state::Account::unpack(&account?.data).ok()?)))
+        .filter(|(_, token_account)| token_account.amount > 0)
+        .collect();
+
+    let owners: Vec<Pubkey> = token_accounts.iter().map(|(_, token_account)| token_account.owner).collect();
+    let owner_programs: Vec<Option<Pubkey>> = client.get_multiple_accounts(&owners)?
+        .into_iter()
+        .map(|account| account.map(|account| account.owner))
+        .collect();
+    let holders = token_accounts
+        .into_iter()
+        .zip(owner_programs)
+        .map(|((token_account_pubkey, token_account), owner_program)| LpHolder {
+            token_account: token_account_pubkey,
+            owner: token_account.owner,
+            amount: token_account.amount,
+            kind: holder_kind(&token_account.owner, owner_program.as_ref(), &deployer, &config.lockers),
+        })
+        .collect();
+
+    let history = get_transaction_history(client, lp_mint, &config.burn_history)?;
+    let burned = history
+        .iter()
+        .map(|transaction| burned_in_transaction(transaction, &lp_mint_pubkey))
+        .fold(0_u64, u64::saturating_add);
+
+    Ok(LiquidityLockReport::new(lp_mint_pubkey, mint.supply, burned, holders))
+}
+
+fn holder_kind(owner: &Pubkey, owner_program: Option<&Pubkey>, deployer: &Pubkey, lockers: &[Pubkey]) -> LpHolderKind {
+    if owner == deployer {
+        LpHolderKind::Deployer
+    } else if *owner == incinerator() {
+        LpHolderKind::Incinerator
+    } else if let Some(locker) = lockers.iter().find(|locker| *locker == owner || Some(*locker) == owner_program) {
+        LpHolderKind::Locker(*locker)
+    } else {
+        LpHolderKind::Other
+    }
+}
+
+/// LP tokens of `lp_mint` burned by a transaction, from top level and inner instructions.
+fn burned_in_transaction(transaction: &InspectedTransaction, lp_mint: &Pubkey) -> u64 {
+    if transaction.error.is_some() {
+        return 0;
+    }
+    transaction.instructions
+        .iter()
+        .chain(transaction.inner_instructions.iter().flat_map(|inner_instructions| &inner_instructions.instructions))
+        .filter(|instruction| instruction.accounts.get(1) == Some(lp_mint))
+        .filter_map(|instruction| match instruction.decode() {
+            DecodedInstruction::Token(TokenInstruction::Burn { amount } | TokenInstruction::BurnChecked { amount, .. }) => Some(amount),
+            _ => None,
+        })
+        .fold(0, u64::saturating_add)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::{account::Account, program_option::COption};
+    use crate::{
+        constants::solana_programs::{system_program, token_program},
+        test_utils::{account_info_response, account_with_data, mock_client, multiple_accounts_response, signatures_for_address_response}
+    };
+
+    fn token_account(lp_mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+        let token_account = spl_token::state::Account {
+            mint: *lp_mint,
+            owner: *owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        token_account.pack_into_slice(&mut data);
+        account_with_data(token_program(), data)
+    }
+
+    #[test]
+    fn test_verify_liquidity_lock() {
+        let (lp_mint, deployer, locker_escrow) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let token_accounts = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let mint = spl_token::state::Mint { mint_authority: COption::None, supply: 1_000, decimals: 9, is_initialized: true, freeze_authority: COption::None };
+        let mut mint_data = vec![0; spl_token::state::Mint::LEN];
+        mint.pack_into_slice(&mut mint_data);
+
+        let largest_accounts: Vec<Value> = [(token_accounts[0], 600), (token_accounts[1], 300), (token_accounts[2], 100)]
+            .iter()
+            .map(|(address, amount)| json!({
+                "address": address.to_string(), "amount": amount.to_string(), "decimals": 9, "uiAmount": null, "uiAmountString": ""
+            }))
+            .collect();
+        let largest_accounts = json!({ "context": { "slot": 1 }, "value": largest_accounts });
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&account_with_data(token_program(), mint_data)), 1)),
+            (RpcRequest::GetTokenLargestAccounts, largest_accounts),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[
+                Some(token_account(&lp_mint, &locker_escrow, 600)),
+                Some(token_account(&lp_mint, &incinerator(), 300)),
+                Some(token_account(&lp_mint, &deployer, 100)),
+            ], 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[
+                Some(account_with_data(raydium_lp_locker_program(), vec![])),
+                None,
+                Some(account_with_data(system_program(), vec![])),
+            ], 1)),
+            (RpcRequest::GetSignaturesForAddress, signatures_for_address_response(&[])),
+        ]);
+
+        let report = verify_liquidity_lock(&client, &lp_mint.to_string(), &deployer.to_string(), &LiquidityLockConfig::default()).unwrap();
+        assert!(report.holders[0].kind == LpHolderKind::Locker(raydium_lp_locker_program()));
+        assert!(report.holders[1].kind == LpHolderKind::Incinerator && report.holders[2].kind == LpHolderKind::Deployer);
+        assert!((report.locked_share - 0.6).abs() < 1e-9 && (report.burned_share - 0.3).abs() < 1e-9);
+        assert!((report.deployer_share - 0.1).abs() < 1e-9 && (report.secured_share() - 0.9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_burned_lp_tokens_count_towards_minted_supply() {
+        let lp_mint = Pubkey::new_unique();
+        let deployer = Pubkey::new_unique();
+        let holders = vec![LpHolder { token_account: Pubkey::new_unique(), owner: deployer, amount: 250, kind: LpHolderKind::Deployer }];
+        let report = LiquidityLockReport::new(lp_mint, 250, 750, holders);
+        assert!((report.burned_share - 0.75).abs() < 1e-9 && (report.deployer_share - 0.25).abs() < 1e-9);
+        assert!(report.locked_share == 0.0);
+    }
+}
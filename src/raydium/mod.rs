@@ -1 +1,3 @@
-pub mod compute_swap;
\ No newline at end of file
+pub mod compute_swap;
+pub mod cpmm;
+pub mod liquidity_lock;
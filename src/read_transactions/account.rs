@@ -1,6 +1,9 @@
-use borsh::BorshDeserialize;
-use solana_sdk::{native_token::LAMPORTS_PER_SOL, program_pack::Pack};
-use solana_client::rpc_client::RpcClient;
+use std::any::Any;
+use solana_sdk::{account::Account as SolanaAccount, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcAccountInfoConfig};
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_sdk_ids::bpf_loader_upgradeable;
 use spl_token::state::{
     Account as SplAssociatedTokenAccount,
     Mint as SplMintAccount,
@@ -8,10 +11,13 @@ use spl_token::state::{
 use crate::{
     constants::solana_programs::system_program, 
     error::ReadTransactionError, 
-    utils::{address_to_pubkey, addresses_to_pubkeys},
+    utils::address_to_pubkey,
 };
 
-use super::metadata::MetadataAccount;
+use super::{
+    metadata::MetadataAccount,
+    token_extensions::{unpack_mint, unpack_token_account, TokenExtensions},
+};
 
 /// A generic struct for any account on Solana, mainly used when the account type is unknown.
 ///
@@ -21,11 +27,13 @@ use super::metadata::MetadataAccount;
 /// - `sol_balance`: The sol balance in the account in ui format e.g 0.1 SOL
 /// - `account_type`: The type of account with the relevant data deserialized.
 /// - `data`: The data held within the account, custom programs can be borsh deserialized given that the user knows the struct of the data.
+/// - `token_extensions`: Extensions of a Token-2022 mint, `None` for any other account.
 pub struct Account {
     pub pubkey: String,
     pub sol_balance: f64,
     pub account_type: AccountType,
-    pub data: Vec<u8>
+    pub data: Vec<u8>,
+    pub token_extensions: Option<TokenExtensions>,
 }
 
 /// Types of Solana accounts
@@ -33,20 +41,139 @@ pub struct Account {
 /// 
 /// - AssociatedToken: contains the token data belonging to a wallet account, such as token balance, token metadata and more. The wallet account owner has write permissions to transfer tokens and close the account. 
 /// 
-/// - Mint: Commonly known as the token address, it contains the overall token data such as token supply, decimals and the authority account of the token.
+/// - Mint: Commonly known as the token address, it contains the overall token data such as token supply, decimals and the authority account of the token. Mints and token accounts of Token-2022 are read without their extensions, the extensions of mints are in `Account::token_extensions`.
 /// 
 /// - Metadata: holds the metadata of a token, such as token names, token tickers, and their URIs. 
 /// 
 /// - Program: Accounts which are executable, meaning that wallet accounts can interact with these program accounts. 
+///
+/// - Custom: Accounts decoded by a decoder registered with an `AccountParser`.
 pub enum AccountType {
     Wallet,
     AssociatedToken(SplAssociatedTokenAccount),
     Mint(SplMintAccount),
     Metadata(MetadataAccount),
-    Program,
+    Program(ProgramAccount),
+    Custom(CustomAccount),
     Others
 }
 
+/// Account of a third-party program decoded into a user-defined type.
+///
+/// ### Fields
+///
+/// - `program_id`: Program owning the account, which the decoder was registered for.
+/// - `name`: Name the decoder was registered with.
+/// - `value`: The decoded account, read with `downcast_ref`.
+pub struct CustomAccount {
+    pub program_id: Pubkey,
+    pub name: String,
+    pub value: Box<dyn Any + Send + Sync>,
+}
+
+impl CustomAccount {
+    /// Returns the decoded account if it is a `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.value.downcast_ref()
+    }
+}
+
+type AccountDecoder = Box<dyn Fn(&[u8]) -> Option<Box<dyn Any + Send + Sync>> + Send + Sync>;
+
+/// Classifies fetched accounts, trying the decoders registered for the owner of an account
+/// before the built-in account types. Without decoders it classifies accounts like `get_account`.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use borsh::BorshDeserialize;
+/// use easy_solana::{
+///     create_rpc_client,
+///     read_transactions::account::{get_account_with_parser, AccountParser, AccountType}
+/// };
+/// use solana_sdk::pubkey::Pubkey;
+///
+/// #[derive(BorshDeserialize)]
+/// struct Counter {
+///     count: u64,
+/// }
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let mut parser = AccountParser::new();
+/// parser.register(Pubkey::new_unique(), "counter", |data| Counter::try_from_slice(data).ok());
+/// let account = get_account_with_parser(&client, "COUNTER_ACCOUNT_ADDRESS", &parser).unwrap();
+/// if let AccountType::Custom(custom_account) = &account.account_type {
+///     let counter = custom_account.downcast_ref::<Counter>().unwrap();
+///     println!("{} counted {}", custom_account.name, counter.count);
+/// }
+/// ```
+#[derive(Default)]
+pub struct AccountParser {
+    decoders: Vec<(Pubkey, String, AccountDecoder)>,
+}
+
+impl AccountParser {
+    /// Creates a parser without custom decoders.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a decoder for the accounts owned by `program_id`. Decoders of the same program
+    /// are tried in the order they were registered, returning `None` passes the account on to
+    /// the next decoder, then to the built-in account types.
+    pub fn register<T, F>(&mut self, program_id: Pubkey, name: &str, decode: F) -> &mut Self
+    where
+        T: Any + Send + Sync,
+        F: Fn(&[u8]) -> Option<T> + Send + Sync + 'static,
+    {
+        let decoder: AccountDecoder = Box::new(move |data| decode(data).map(|value| Box::new(value) as Box<dyn Any + Send + Sync>));
+        self.decoders.push((program_id, name.to_string(), decoder));
+        self
+    }
+
+    /// Determines the account type of a fetched account, trying the registered decoders first.
+    pub fn parse(&self, pubkey: &Pubkey, account: SolanaAccount) -> Account {
+        let custom_account = self.decoders
+            .iter()
+            .filter(|(program_id, _, _)| *program_id == account.owner)
+            .find_map(|(program_id, name, decode)| {
+                decode(&account.data).map(|value| CustomAccount { program_id: *program_id, name: name.clone(), value })
+            });
+        match custom_account {
+            Some(custom_account) => Account {
+                pubkey: pubkey.to_string(),
+                sol_balance: account.lamports as f64 / LAMPORTS_PER_SOL as f64,
+                account_type: AccountType::Custom(custom_account),
+                data: account.data,
+                token_extensions: None,
+            },
+            None => parse_account(pubkey, account),
+        }
+    }
+}
+
+/// Details of an executable program account. Programs of the upgradeable loader store their
+/// code in a separate ProgramData account, which also holds the upgrade authority.
+///
+/// ### Fields
+///
+/// - `program_data_address`: Address of the ProgramData account, `None` if the program is not upgradeable.
+/// - `upgrade_authority`: Account allowed to upgrade the program, `None` if the program is immutable.
+/// - `deployment_slot`: Slot the program was last deployed at.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProgramAccount {
+    pub program_data_address: Option<Pubkey>,
+    pub upgrade_authority: Option<Pubkey>,
+    pub deployment_slot: Option<u64>,
+}
+
+impl ProgramAccount {
+    /// Whether the code of the program can still be replaced by its upgrade authority.
+    pub fn is_upgradeable(&self) -> bool {
+        self.upgrade_authority.is_some()
+    }
+}
+
 /// Gets the account of any solana address.
 /// 
 /// # Arguments
@@ -60,86 +187,210 @@ pub enum AccountType {
 /// struct on success, or an error if invalid address or non existent account
 /// 
 pub fn get_account(client: &RpcClient, address: &str) -> Result<Account, ReadTransactionError> {
+    get_account_with_parser(client, address, &AccountParser::default())
+}
+
+/// Gets the account of any solana address like `get_account`, decoding accounts of third-party
+/// programs with the decoders registered in `parser`.
+pub fn get_account_with_parser(client: &RpcClient, address: &str, parser: &AccountParser) -> Result<Account, ReadTransactionError> {
     // Parse the public address into a Pubkey
     let pubkey = address_to_pubkey(address)?;
 
-    // Fetch the account balance in lamports
+    // Fetch the account and resolve the program data of programs
     let account = client.get_account(&pubkey)?;
-    let account_type: AccountType;
-    if account.executable {
-        account_type = AccountType::Program
+    let mut account = parser.parse(&pubkey, account);
+    resolve_program_accounts(client, [&mut account])?;
+
+    Ok(account)
+}
+
+/// Gets the accounts of multiple solana addresses in a single request.
+///
+/// # Arguments
+///
+/// * `client` - An instance of the RPC client used to communicate with the blockchain.
+/// * `addresses` - addresses of any solana accounts
+///
+/// # Returns
+///
+/// `Result<Vec<Option<Account>>, ReadTransactionError>` - Returns an `Option<Account>` for each
+/// address in the same order, `None` if the account does not exist, or
+/// `ReadTransactionError::InvalidAddress` if any of the addresses is invalid.
+pub fn get_multiple_accounts(client: &RpcClient, addresses: Vec<&str>) -> Result<Vec<Option<Account>>, ReadTransactionError> {
+    get_multiple_accounts_with_parser(client, addresses, &AccountParser::default())
+}
+
+/// Gets the accounts of multiple solana addresses like `get_multiple_accounts`, decoding accounts
+/// of third-party programs with the decoders registered in `parser`.
+pub fn get_multiple_accounts_with_parser(client: &RpcClient, addresses: Vec<&str>, parser: &AccountParser) -> Result<Vec<Option<Account>>, ReadTransactionError> {
+    let pubkeys = addresses
+        .into_iter()
+        .map(address_to_pubkey)
+        .collect::<Result<Vec<Pubkey>, _>>()?;
+    let accounts = client.get_multiple_accounts(&pubkeys)?;
+
+    let mut result: Vec<Option<Account>> = accounts
+        .into_iter()
+        .zip(pubkeys)
+        .map(|(account_option, pubkey)| account_option.map(|account| parser.parse(&pubkey, account)))
+        .collect();
+    resolve_program_accounts(client, result.iter_mut().flatten())?;
+
+    Ok(result)
+}
+
+/// Gets the accounts of multiple solana addresses, failing if any of the accounts does not exist.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::AccountNotFound` if any of the addresses has no account.
+pub fn get_multiple_accounts_strict(client: &RpcClient, addresses: Vec<&str>) -> Result<Vec<Account>, ReadTransactionError> {
+    get_multiple_accounts(client, addresses)?
+        .into_iter()
+        .map(|account_option| account_option.ok_or(ReadTransactionError::AccountNotFound))
+        .collect()
+}
+
+/// Gets only the bytes of `data_slice` of an account's data, e.g a single field, to cut the
+/// bandwidth of polling loops. The account is not decoded since its data is partial.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::AccountNotFound` if the account does not exist.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_account_decoder::UiDataSliceConfig;
+/// use easy_solana::{create_rpc_client, read_transactions::account::get_account_data_slice};
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// // Amount of a token account
+/// let data = get_account_data_slice(&client, "7geCZYWHtghvWj11sb7exvu4uMANfhvGvEvVRRZ8GmSd", UiDataSliceConfig { offset: 64, length: 8 }).unwrap();
+/// ```
+pub fn get_account_data_slice(client: &RpcClient, address: &str, data_slice: UiDataSliceConfig) -> Result<Vec<u8>, ReadTransactionError> {
+    let pubkey = address_to_pubkey(address)?;
+    let account = client
+        .get_account_with_config(&pubkey, data_slice_config(client, data_slice))?
+        .value
+        .ok_or(ReadTransactionError::AccountNotFound)?;
+    Ok(account.data)
+}
+
+/// Gets the bytes of `data_slice` of multiple accounts in a single request, `None` for accounts
+/// that do not exist. Fails with `ReadTransactionError::InvalidAddress` on invalid addresses like
+/// `get_multiple_accounts`.
+pub fn get_multiple_account_data_slices(client: &RpcClient, addresses: Vec<&str>, data_slice: UiDataSliceConfig) -> Result<Vec<Option<Vec<u8>>>, ReadTransactionError> {
+    let pubkeys = addresses
+        .into_iter()
+        .map(address_to_pubkey)
+        .collect::<Result<Vec<Pubkey>, _>>()?;
+    let accounts = client.get_multiple_accounts_with_config(&pubkeys, data_slice_config(client, data_slice))?.value;
+    Ok(accounts.into_iter().map(|account| account.map(|account| account.data)).collect())
+}
+
+fn data_slice_config(client: &RpcClient, data_slice: UiDataSliceConfig) -> RpcAccountInfoConfig {
+    RpcAccountInfoConfig {
+        // Slices are only supported by binary encodings
+        encoding: Some(UiAccountEncoding::Base64),
+        data_slice: Some(data_slice),
+        commitment: Some(client.commitment()),
+        ..RpcAccountInfoConfig::default()
+    }
+}
+
+/// Determines the account type of a fetched account based on its owner and data.
+pub(crate) fn parse_account(pubkey: &Pubkey, account: SolanaAccount) -> Account {
+    let mut token_extensions = None;
+    let account_type = if account.executable {
+        AccountType::Program(ProgramAccount {
+            program_data_address: parse_program_data_address(&account),
+            ..ProgramAccount::default()
+        })
     } else if account.owner == system_program() {
-        account_type = AccountType::Wallet
-    } else if SplMintAccount::unpack(&account.data).is_ok() {
-        let mint_data = SplMintAccount::unpack(&account.data)
-            .map_err(|_| ReadTransactionError::DeserializeError)?;
-        account_type = AccountType::Mint(mint_data)
-    } else if SplAssociatedTokenAccount::unpack(&account.data).is_ok() {
-        let associated_token_data = SplAssociatedTokenAccount::unpack(&account.data)
-            .map_err(|_| ReadTransactionError::DeserializeError)?;
-        account_type = AccountType::AssociatedToken(associated_token_data)
-    } else if MetadataAccount::deserialize(&mut account.data.as_ref()).is_ok() {
-        let metadata = MetadataAccount::deserialize(&mut account.data.as_ref())
-            .map_err(|_| ReadTransactionError::DeserializeError)?;
-        account_type = AccountType::Metadata(metadata)
+        AccountType::Wallet
+    } else if let Ok((mint_data, extensions)) = unpack_mint(&account.owner, &account.data) {
+        token_extensions = extensions;
+        AccountType::Mint(mint_data)
+    } else if let Ok(associated_token_data) = unpack_token_account(&account.owner, &account.data) {
+        AccountType::AssociatedToken(associated_token_data)
+    } else if let Ok(metadata) = MetadataAccount::try_from_account_data(&account.data) {
+        AccountType::Metadata(metadata)
     } else {
-        account_type = AccountType::Others
-    }
+        AccountType::Others
+    };
 
-    Ok(Account { 
-        pubkey: address.to_string(),
+    Account {
+        pubkey: pubkey.to_string(),
         sol_balance: account.lamports as f64 / LAMPORTS_PER_SOL as f64,
         account_type,
-        data: account.data
-     })
+        data: account.data,
+        token_extensions,
+    }
 }
 
-pub fn get_multiple_accounts(client: &RpcClient, addresses: Vec<&str>) -> Result<Vec<Account>, ReadTransactionError> {
-    let pubkeys = addresses_to_pubkeys(addresses);
-    let accounts = client.get_multiple_accounts(&pubkeys)?;
+/// Reads the ProgramData address of a program owned by the upgradeable loader.
+fn parse_program_data_address(account: &SolanaAccount) -> Option<Pubkey> {
+    if account.owner != bpf_loader_upgradeable::ID {
+        return None;
+    }
+    match bincode::deserialize(&account.data) {
+        Ok(UpgradeableLoaderState::Program { programdata_address }) => Some(programdata_address),
+        _ => None,
+    }
+}
 
-    let mut result: Vec<Account> = vec![];
-    
-    // Iterate over accounts and corresponding pubkeys
-    for (account_option, pubkey) in accounts.iter().zip(pubkeys) {
-        match account_option {
-            Some(account) => {
-                // Determine the account type based on its data
-                let account_type = if account.executable {
-                    AccountType::Program
-                } else if account.owner == system_program() {
-                    AccountType::Wallet
-                } else if let Ok(mint_data) = SplMintAccount::unpack(&account.data) {
-                    AccountType::Mint(mint_data)
-                } else if let Ok(associated_token_data) = SplAssociatedTokenAccount::unpack(&account.data) {
-                    AccountType::AssociatedToken(associated_token_data)
-                } else if let Ok(metadata) = MetadataAccount::deserialize(&mut account.data.as_ref()) {
-                    AccountType::Metadata(metadata)
-                } else {
-                    AccountType::Others
-                };
-
-                // Add the successfully processed account to the result vector
-                result.push(Account {
-                    pubkey: pubkey.to_string(),
-                    sol_balance: account.lamports as f64 / LAMPORTS_PER_SOL as f64,
-                    account_type,
-                    data: account.data.clone(),
-                });
-            }
-            None => {
-                // Handle the case where an account is `None` (nonexistent or invalid account)
-                return Err(ReadTransactionError::AccountNotFound);
-            }
-        }
+/// Reads the deployment slot and upgrade authority stored in a ProgramData account.
+pub fn parse_program_data(data: &[u8]) -> Result<(u64, Option<Pubkey>), ReadTransactionError> {
+    let metadata = data
+        .get(..UpgradeableLoaderState::size_of_programdata_metadata())
+        .ok_or(ReadTransactionError::DeserializeError)?;
+    match bincode::deserialize(metadata) {
+        Ok(UpgradeableLoaderState::ProgramData { slot, upgrade_authority_address }) => Ok((slot, upgrade_authority_address)),
+        _ => Err(ReadTransactionError::WrongAccountType("ProgramData".to_string())),
+    }
+}
+
+/// Fetches the ProgramData accounts of upgradeable programs in a single request and fills in
+/// their upgrade authority and deployment slot.
+pub(crate) fn resolve_program_accounts<'a>(
+    client: &RpcClient,
+    accounts: impl IntoIterator<Item = &'a mut Account>
+) -> Result<(), ReadTransactionError> {
+    let mut program_accounts: Vec<&mut ProgramAccount> = accounts
+        .into_iter()
+        .filter_map(|account| match &mut account.account_type {
+            AccountType::Program(program_account) if program_account.program_data_address.is_some() => Some(program_account),
+            _ => None,
+        })
+        .collect();
+    if program_accounts.is_empty() {
+        return Ok(());
     }
 
-    Ok(result)
+    let program_data_pubkeys: Vec<Pubkey> = program_accounts
+        .iter()
+        .filter_map(|program_account| program_account.program_data_address)
+        .collect();
+    let program_data_accounts = client.get_multiple_accounts(&program_data_pubkeys)?;
+    for (program_account, program_data_account) in program_accounts.iter_mut().zip(program_data_accounts) {
+        let program_data_account = program_data_account.ok_or(ReadTransactionError::AccountNotFound)?;
+        let (slot, upgrade_authority) = parse_program_data(&program_data_account.data)?;
+        program_account.deployment_slot = Some(slot);
+        program_account.upgrade_authority = upgrade_authority;
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::utils::create_rpc_client;
+    use solana_client::rpc_request::RpcRequest;
+    use crate::test_utils::account_info_response;
+    use crate::{
+        test_utils::{account_with_data, mock_client, multiple_accounts_response},
+        utils::create_rpc_client
+    };
 
     use super::*;
 
@@ -167,7 +418,7 @@ mod tests {
     fn test_get_multiple_accounts() {
         let client = create_rpc_client("RPC_URL");
         let addresses = vec![WALLET_ADDRESS_1, ASSOCIATED_ACT_ACCOUNT_ADDRESS, ACT_MINT_ADDRESS, PNUT_METADATA_ADDRESS];
-        let accounts = get_multiple_accounts(&client, addresses)
+        let accounts = get_multiple_accounts_strict(&client, addresses)
             .expect("Unable to get accounts");
         let does_not_contain_unknown_account_type = accounts.iter().all(|account| {
             match account.account_type {
@@ -182,4 +433,144 @@ mod tests {
         assert!(does_not_contain_unknown_account_type)
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_get_multiple_accounts_with_missing_account() {
+        let wallet_account = account_with_data(system_program(), vec![]);
+        let response = multiple_accounts_response(&[Some(wallet_account), None], 1);
+        let client = mock_client(vec![
+            (RpcRequest::GetMultipleAccounts, response.clone()),
+            (RpcRequest::GetMultipleAccounts, response),
+        ]);
+        let missing_address = Pubkey::new_unique().to_string();
+        let addresses = vec![WALLET_ADDRESS_1, missing_address.as_str()];
+
+        let accounts = get_multiple_accounts(&client, addresses.clone()).unwrap();
+        assert!(accounts.len() == 2);
+        assert!(matches!(accounts[0].as_ref().map(|account| &account.account_type), Some(AccountType::Wallet)));
+        assert!(accounts[1].is_none());
+
+        let strict_result = get_multiple_accounts_strict(&client, addresses);
+        assert!(matches!(strict_result, Err(ReadTransactionError::AccountNotFound)));
+    }
+
+    #[test]
+    fn failing_test_get_multiple_accounts_with_invalid_address() {
+        let client = mock_client(vec![]);
+        let result = get_multiple_accounts(&client, vec![WALLET_ADDRESS_1, "invalid", ACT_MINT_ADDRESS]);
+        assert!(matches!(result, Err(ReadTransactionError::InvalidAddress(_))));
+    }
+
+    #[test]
+    fn test_get_account_data_slice() {
+        let sliced_account = account_with_data(Pubkey::new_unique(), 42_u64.to_le_bytes().to_vec());
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&sliced_account), 1)),
+            (RpcRequest::GetAccountInfo, account_info_response(None, 1)),
+        ]);
+        let data_slice = UiDataSliceConfig { offset: 64, length: 8 };
+        let data = get_account_data_slice(&client, ASSOCIATED_ACT_ACCOUNT_ADDRESS, data_slice).unwrap();
+        assert!(data == 42_u64.to_le_bytes());
+
+        let result = get_account_data_slice(&client, ASSOCIATED_ACT_ACCOUNT_ADDRESS, data_slice);
+        assert!(matches!(result, Err(ReadTransactionError::AccountNotFound)));
+    }
+
+    #[test]
+    fn test_get_account_resolves_upgrade_authority() {
+        let program_address = Pubkey::new_unique();
+        let program_data_address = Pubkey::new_unique();
+        let upgrade_authority = Pubkey::new_unique();
+
+        let mut program_account = account_with_data(
+            bpf_loader_upgradeable::ID,
+            bincode::serialize(&UpgradeableLoaderState::Program { programdata_address: program_data_address }).unwrap()
+        );
+        program_account.executable = true;
+        let mut program_data = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+            slot: 42,
+            upgrade_authority_address: Some(upgrade_authority),
+        }).unwrap();
+        program_data.extend_from_slice(&[0; 16]);
+        let program_data_account = account_with_data(bpf_loader_upgradeable::ID, program_data);
+
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&program_account), 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(program_data_account)], 1)),
+        ]);
+        let account = get_account(&client, &program_address.to_string()).unwrap();
+        match account.account_type {
+            AccountType::Program(program_account) => {
+                assert!(program_account.program_data_address == Some(program_data_address));
+                assert!(program_account.upgrade_authority == Some(upgrade_authority));
+                assert!(program_account.deployment_slot == Some(42));
+                assert!(program_account.is_upgradeable());
+            }
+            _ => panic!("Expected program account"),
+        }
+    }
+
+    #[test]
+    fn test_get_token_2022_mint_account_with_extensions() {
+        use spl_token_2022::{
+            extension::{permanent_delegate::PermanentDelegate, BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut},
+            state::Mint as Token2022Mint
+        };
+        use crate::constants::solana_programs::token_2022_program;
+
+        let permanent_delegate = Pubkey::new_unique();
+        let length = ExtensionType::try_calculate_account_len::<Token2022Mint>(&[ExtensionType::PermanentDelegate]).unwrap();
+        let mut data = vec![0; length];
+        let mut mint_state = StateWithExtensionsMut::<Token2022Mint>::unpack_uninitialized(&mut data).unwrap();
+        mint_state.init_extension::<PermanentDelegate>(true).unwrap().delegate = Some(permanent_delegate).try_into().unwrap();
+        mint_state.base = Token2022Mint { supply: 500, decimals: 9, is_initialized: true, ..Default::default() };
+        mint_state.pack_base();
+        mint_state.init_account_type().unwrap();
+
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&account_with_data(token_2022_program(), data)), 1)),
+        ]);
+        let account = get_account(&client, &Pubkey::new_unique().to_string()).unwrap();
+        assert!(matches!(account.account_type, AccountType::Mint(mint) if mint.supply == 500 && mint.decimals == 9));
+        let extensions = account.token_extensions.unwrap();
+        assert!(extensions.permanent_delegate == Some(permanent_delegate));
+        assert!(extensions.transfer_fee.is_none() && extensions.interest_bearing.is_none());
+    }
+
+    #[test]
+    fn test_get_account_with_parser_decodes_custom_accounts() {
+        #[derive(Debug, PartialEq)]
+        struct Counter {
+            count: u64,
+        }
+        let program_id = Pubkey::new_unique();
+        let counter_account = account_with_data(program_id, 7_u64.to_le_bytes().to_vec());
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&counter_account), 1)),
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&counter_account), 1)),
+        ]);
+
+        let mut parser = AccountParser::new();
+        parser
+            .register(program_id, "empty", |data: &[u8]| data.is_empty().then_some(()))
+            .register(program_id, "counter", |data: &[u8]| Some(Counter { count: u64::from_le_bytes(data.try_into().ok()?) }));
+        let address = Pubkey::new_unique().to_string();
+        let account = get_account_with_parser(&client, &address, &parser).unwrap();
+        match &account.account_type {
+            AccountType::Custom(custom_account) => {
+                assert!(custom_account.program_id == program_id && custom_account.name == "counter");
+                assert!(custom_account.downcast_ref::<Counter>() == Some(&Counter { count: 7 }));
+                assert!(custom_account.downcast_ref::<u64>().is_none());
+            }
+            _ => panic!("Expected custom account"),
+        }
+
+        let account = get_account(&client, &address).unwrap();
+        assert!(matches!(account.account_type, AccountType::Others));
+    }
+
+    #[test]
+    fn failing_test_parse_program_data_of_wrong_account() {
+        let data = bincode::serialize(&UpgradeableLoaderState::Program { programdata_address: Pubkey::new_unique() }).unwrap();
+        assert!(parse_program_data(&data).is_err());
+    }
+}
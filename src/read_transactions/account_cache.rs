@@ -0,0 +1,328 @@
+//! # Account Cache
+//!
+//! This module pre-fetches the accounts needed to trade a set of tokens, so that the first
+//! trade after startup can be built without waiting on several RPC round trips. The cache can
+//! be saved to disk and loaded on the next startup, so only new tokens are fetched.
+
+use std::{collections::HashMap, fs, path::Path, time::{Duration, Instant}};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{de::Error as _, Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{hash::Hash, program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::Mint as SplMintAccount;
+use crate::{
+    constants::solana_programs::associated_token_account_program,
+    error::{PersistenceError, ReadTransactionError},
+    pumpfun::bonding_curve::{derive_bonding_curve, BondingCurveAccount},
+    utils::addresses_to_pubkeys
+};
+use super::batch::MAX_MULTIPLE_ACCOUNTS;
+
+/// Blockhashes are valid for about 60 seconds, cached blockhashes are only used for part of that.
+const BLOCKHASH_MAX_AGE: Duration = Duration::from_secs(30);
+
+/// Cached state of a token.
+///
+/// ### Fields
+///
+/// - `mint`: The mint account, `None` if it does not exist.
+/// - `token_program`: Program owning the mint.
+/// - `bonding_curve`: The pump.fun bonding curve, `None` if the token is not from pump.fun.
+/// - `token_account_exists`: Whether the owner's associated token account exists.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedToken {
+    pub mint: Option<SplMintAccount>,
+    pub token_program: Option<Pubkey>,
+    pub bonding_curve: Option<BondingCurveAccount>,
+    pub token_account_exists: bool,
+}
+
+/// Token saved by `AccountCache::save`, accounts are base64 encoded account data.
+#[derive(Serialize, Deserialize)]
+struct TokenSnapshot {
+    mint_address: String,
+    mint: Option<String>,
+    token_program: Option<String>,
+    bonding_curve: Option<String>,
+    token_account_exists: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheSnapshot {
+    owner: String,
+    tokens: Vec<TokenSnapshot>,
+}
+
+/// Cache of token accounts and the latest blockhash for the wallet `owner`.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_sdk::{signer::Signer, signer::keypair::Keypair};
+/// use easy_solana::{create_rpc_client, read_transactions::account_cache::AccountCache};
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let keypair = Keypair::new();
+/// let mut cache = AccountCache::load("account_cache.json").unwrap_or_else(|_| AccountCache::new(keypair.pubkey()));
+/// let tokens = vec!["CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump"];
+/// cache.prewarm(&client, cache.uncached(tokens)).unwrap();
+/// let blockhash = cache.blockhash();
+/// cache.save("account_cache.json").unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct AccountCache {
+    owner: Pubkey,
+    tokens: HashMap<Pubkey, CachedToken>,
+    blockhash: Option<(Hash, Instant)>,
+}
+
+impl AccountCache {
+    pub fn new(owner: Pubkey) -> Self {
+        AccountCache {
+            owner,
+            tokens: HashMap::new(),
+            blockhash: None,
+        }
+    }
+
+    /// Fetches and caches the mint, bonding curve and associated token account existence of each token,
+    /// along with the latest blockhash. Accounts are fetched in batches, invalid addresses are ignored.
+    ///
+    /// ## Errors
+    ///
+    /// Returns a `ReadTransactionError` if any RPC request fails.
+    pub fn prewarm(&mut self, client: &RpcClient, mints: Vec<&str>) -> Result<(), ReadTransactionError> {
+        let mint_pubkeys = addresses_to_pubkeys(mints);
+
+        // Mints and bonding curves first, the associated token account depends on the mint's program
+        let mut pubkeys = mint_pubkeys.clone();
+        pubkeys.extend(mint_pubkeys.iter().map(derive_bonding_curve));
+        let accounts = get_multiple_accounts_batched(client, &pubkeys)?;
+        let (mint_accounts, bonding_curve_accounts) = accounts.split_at(mint_pubkeys.len());
+
+        let mut tokens: Vec<(Pubkey, CachedToken)> = mint_pubkeys
+            .iter()
+            .zip(mint_accounts.iter().zip(bonding_curve_accounts))
+            .map(|(mint_pubkey, (mint_account, bonding_curve_account))| {
+                let mint = mint_account.as_ref().and_then(|account| {
+                    account.data.get(..SplMintAccount::LEN).and_then(|data| SplMintAccount::unpack(data).ok())
+                });
+                let cached_token = CachedToken {
+                    mint,
+                    token_program: mint_account.as_ref().map(|account| account.owner),
+                    bonding_curve: bonding_curve_account
+                        .as_ref()
+                        .and_then(|account| BondingCurveAccount::try_from_account_data(&account.data).ok()),
+                    token_account_exists: false,
+                };
+                (*mint_pubkey, cached_token)
+            })
+            .collect();
+
+        let token_account_pubkeys: Vec<Pubkey> = tokens
+            .iter()
+            .filter_map(|(mint_pubkey, cached_token)| {
+                cached_token.token_program.map(|token_program| derive_token_account(&self.owner, mint_pubkey, &token_program))
+            })
+            .collect();
+        let token_accounts = get_multiple_accounts_batched(client, &token_account_pubkeys)?;
+        let mut token_accounts = token_accounts.into_iter();
+        for (_, cached_token) in tokens.iter_mut().filter(|(_, cached_token)| cached_token.token_program.is_some()) {
+            cached_token.token_account_exists = token_accounts.next().flatten().is_some();
+        }
+
+        let blockhash = client.get_latest_blockhash()?;
+        self.blockhash = Some((blockhash, Instant::now()));
+        self.tokens.extend(tokens);
+        Ok(())
+    }
+
+    /// Cached state of a token, `None` if the token has not been pre-warmed.
+    pub fn token(&self, mint: &Pubkey) -> Option<&CachedToken> {
+        self.tokens.get(mint)
+    }
+
+    /// Cached blockhash, `None` if it is too old to be used safely.
+    pub fn blockhash(&self) -> Option<Hash> {
+        self.blockhash
+            .filter(|(_, fetched_at)| fetched_at.elapsed() < BLOCKHASH_MAX_AGE)
+            .map(|(blockhash, _)| blockhash)
+    }
+
+    /// Addresses of `mints` that are not cached, e.g the tokens to `prewarm` after `load`.
+    pub fn uncached<'a>(&self, mints: Vec<&'a str>) -> Vec<&'a str> {
+        mints
+            .into_iter()
+            .filter(|mint| !mint.parse::<Pubkey>().is_ok_and(|mint| self.tokens.contains_key(&mint)))
+            .collect()
+    }
+
+    /// Writes the cached tokens to a JSON file. The blockhash is not saved, it expires long
+    /// before the next startup.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PersistenceError> {
+        let encode = |data: &[u8]| STANDARD.encode(data);
+        let tokens = self.tokens
+            .iter()
+            .map(|(mint_address, cached_token)| TokenSnapshot {
+                mint_address: mint_address.to_string(),
+                mint: cached_token.mint.map(|mint| {
+                    let mut data = vec![0; SplMintAccount::LEN];
+                    mint.pack_into_slice(&mut data);
+                    encode(&data)
+                }),
+                token_program: cached_token.token_program.map(|token_program| token_program.to_string()),
+                bonding_curve: cached_token.bonding_curve.as_ref().map(|bonding_curve| encode(&bonding_curve.to_account_data())),
+                token_account_exists: cached_token.token_account_exists,
+            })
+            .collect();
+        let json = serde_json::to_string(&CacheSnapshot { owner: self.owner.to_string(), tokens })?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Creates a cache from tokens previously saved with `save`. Bonding curves are as of the
+    /// save, `prewarm` the tokens whose reserves have to be current.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `PersistenceError::IoError` if the file can not be read and
+    /// `PersistenceError::SerializeError` if it is not a saved cache.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PersistenceError> {
+        let json = fs::read_to_string(path)?;
+        let snapshot: CacheSnapshot = serde_json::from_str(&json)?;
+        let invalid = |what: &str| serde_json::Error::custom(format!("invalid {} in account cache", what));
+        let parse_pubkey = |address: &str| address.parse::<Pubkey>().map_err(|_| invalid("address"));
+        let decode = |data: &str| STANDARD.decode(data).map_err(|_| invalid("account data"));
+
+        let mut cache = AccountCache::new(parse_pubkey(&snapshot.owner)?);
+        for token in snapshot.tokens {
+            let mint = match token.mint {
+                Some(data) => Some(SplMintAccount::unpack(&decode(&data)?).map_err(|_| invalid("mint"))?),
+                None => None,
+            };
+            let bonding_curve = match token.bonding_curve {
+                Some(data) => Some(BondingCurveAccount::try_from_account_data(&decode(&data)?).map_err(|_| invalid("bonding curve"))?),
+                None => None,
+            };
+            let cached_token = CachedToken {
+                mint,
+                token_program: token.token_program.as_deref().map(parse_pubkey).transpose()?,
+                bonding_curve,
+                token_account_exists: token.token_account_exists,
+            };
+            cache.tokens.insert(parse_pubkey(&token.mint_address)?, cached_token);
+        }
+        Ok(cache)
+    }
+
+    /// Marks the associated token account of a token as created, e.g after sending a transaction creating it.
+    pub fn set_token_account_exists(&mut self, mint: &Pubkey, exists: bool) {
+        if let Some(cached_token) = self.tokens.get_mut(mint) {
+            cached_token.token_account_exists = exists;
+        }
+    }
+}
+
+fn get_multiple_accounts_batched(
+    client: &RpcClient,
+    pubkeys: &[Pubkey]
+) -> Result<Vec<Option<solana_sdk::account::Account>>, ReadTransactionError> {
+    let mut accounts = Vec::with_capacity(pubkeys.len());
+    for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        accounts.extend(client.get_multiple_accounts(chunk)?);
+    }
+    Ok(accounts)
+}
+
+fn derive_token_account(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    let (token_account, _nonce) = Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &associated_token_account_program()
+    );
+    token_account
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::program_option::COption;
+    use crate::{
+        constants::solana_programs::token_program,
+        test_utils::{account_with_data, fresh_bonding_curve, mock_client, multiple_accounts_response}
+    };
+
+    #[test]
+    fn test_prewarm_account_cache() {
+        let owner = Pubkey::new_unique();
+        let (pump_mint, other_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mint = SplMintAccount {
+            mint_authority: COption::None,
+            supply: 1_000_000,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        let mut mint_data = vec![0; SplMintAccount::LEN];
+        mint.pack_into_slice(&mut mint_data);
+        let mint_account = account_with_data(token_program(), mint_data);
+
+        let client = mock_client(vec![
+            // mints then bonding curves
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(mint_account.clone()), Some(mint_account), None, None], 1)),
+            // associated token accounts
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(account_with_data(token_program(), vec![])), None], 1)),
+        ]);
+
+        let mut cache = AccountCache::new(owner);
+        let (pump_address, other_address) = (pump_mint.to_string(), other_mint.to_string());
+        cache.prewarm(&client, vec![&pump_address, &other_address]).unwrap();
+
+        let cached_token = cache.token(&pump_mint).unwrap();
+        assert!(cached_token.mint == Some(mint));
+        assert!(cached_token.token_program == Some(token_program()));
+        assert!(cached_token.bonding_curve.is_none());
+        assert!(cached_token.token_account_exists);
+        assert!(!cache.token(&other_mint).unwrap().token_account_exists);
+        assert!(cache.blockhash().is_some());
+    }
+
+    #[test]
+    fn test_save_and_load_account_cache() {
+        let (mint_address, curve_address) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mint = SplMintAccount {
+            mint_authority: COption::Some(Pubkey::new_unique()),
+            supply: 1_000_000_000,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        let bonding_curve = fresh_bonding_curve();
+        let mut cache = AccountCache::new(Pubkey::new_unique());
+        cache.tokens.insert(mint_address, CachedToken {
+            mint: Some(mint),
+            token_program: Some(token_program()),
+            bonding_curve: None,
+            token_account_exists: true,
+        });
+        cache.tokens.insert(curve_address, CachedToken {
+            mint: None,
+            token_program: None,
+            bonding_curve: Some(bonding_curve),
+            token_account_exists: false,
+        });
+        cache.blockhash = Some((Hash::new_unique(), Instant::now()));
+
+        let path = std::env::temp_dir().join(format!("account_cache_{}.json", Pubkey::new_unique()));
+        cache.save(&path).unwrap();
+        let loaded = AccountCache::load(&path).unwrap();
+        std::fs::write(&path, r#"{"owner":"not an address","tokens":[]}"#).unwrap();
+        assert!(matches!(AccountCache::load(&path), Err(PersistenceError::SerializeError(_))));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(loaded.owner == cache.owner && loaded.tokens == cache.tokens);
+        assert!(loaded.blockhash().is_none());
+        let (cached, new) = (mint_address.to_string(), Pubkey::new_unique().to_string());
+        assert!(loaded.uncached(vec![&cached, &new, "invalid"]) == vec![new.as_str(), "invalid"]);
+    }
+}
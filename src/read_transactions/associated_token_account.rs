@@ -3,18 +3,20 @@
 //! This module contains functions and structures for querying and 
 //! deriving associated token accounts.
 
-use solana_sdk::{program_pack::Pack, pubkey::{ParsePubkeyError, Pubkey}};
+use solana_sdk::pubkey::{ParsePubkeyError, Pubkey};
 use solana_client::{rpc_client::RpcClient, rpc_request::TokenAccountsFilter};
-use spl_token::state::{
-    Account as SplTokenAccount,
-    Mint as SplMintAccount,
-};
-use solana_account_decoder::UiAccountData;
+use spl_token::state::Mint as SplMintAccount;
+use solana_account_decoder::{UiAccountData, UiDataSliceConfig};
 use serde_json::Value;
 use std::{collections::HashMap, str::FromStr};
 use crate::{
     constants::solana_programs::{associated_token_account_program, token_program}, error::ReadTransactionError, utils::{address_to_pubkey, addresses_to_pubkeys}
 };
+use super::{
+    account::get_multiple_account_data_slices,
+    token_extensions::{unpack_mint, unpack_token_account, TokenExtensions},
+    token_filter::TokenFilter,
+};
 
 
 /// Represents an associated token account, which holds a specific token 
@@ -36,6 +38,7 @@ use crate::{
 /// - `token_ui_amount`: The token amount in a user-friendly format (e.g., with decimals converted to f64).
 /// - `mint_authority`: The authority responsible for minting the token (if any).
 /// - `token_program`: The program that owns the token, typically "Token2022" or "Token" for SPL tokens.
+/// - `extensions`: Extensions of the mint for Token-2022 tokens, e.g transfer fees or a permanent delegate.
 #[derive(Debug)]
 pub struct AssociatedTokenAccount {
     pub pubkey: String,
@@ -46,7 +49,8 @@ pub struct AssociatedTokenAccount {
     pub token_amount: u64, 
     pub token_ui_amount: f64, 
     pub mint_authority: Option<Pubkey>, 
-    pub token_program: String 
+    pub token_program: String,
+    pub extensions: Option<TokenExtensions>,
 }
 
 /// Derives the associated token account address from the wallet address and mint address. 
@@ -185,12 +189,10 @@ pub fn derive_multiple_associated_token_account_addresses(
 pub fn get_associated_token_account(client: &RpcClient, associated_token_account_address: &str) -> Result<AssociatedTokenAccount, ReadTransactionError> {
     let associated_token_account_pubkey = address_to_pubkey(associated_token_account_address)?;
 
-    let token_account_data = client.get_account_data(&associated_token_account_pubkey)?;
-    let token_account: SplTokenAccount = SplTokenAccount::unpack(&token_account_data)
-        .map_err(|_| ReadTransactionError::DeserializeError)?;
+    let associated_token_account = client.get_account(&associated_token_account_pubkey)?;
+    let token_account = unpack_token_account(&associated_token_account.owner, &associated_token_account.data)?;
     let mint_account = client.get_account(&token_account.mint)?;
-    let mint_account_data: SplMintAccount = SplMintAccount::unpack(&mint_account.data)
-        .map_err(|_| ReadTransactionError::DeserializeError)?;
+    let (mint_account_data, extensions) = unpack_mint(&mint_account.owner, &mint_account.data)?;
 
     Ok(AssociatedTokenAccount {
         pubkey: associated_token_account_pubkey.to_string(),
@@ -201,7 +203,8 @@ pub fn get_associated_token_account(client: &RpcClient, associated_token_account
         token_amount: token_account.amount,
         token_ui_amount: token_account.amount as f64 / u64::pow(10, mint_account_data.decimals as u32) as f64,
         mint_authority: mint_account_data.mint_authority.into(),
-        token_program: mint_account.owner.to_string()
+        token_program: mint_account.owner.to_string(),
+        extensions,
     })
 }
 
@@ -236,7 +239,7 @@ pub fn get_multiple_associated_token_accounts(
 
     for (pubkey, account_option) in associated_token_pubkeys.iter().zip(associated_token_accounts.into_iter()) {
         if let Some(account) = account_option {
-            if let Ok(token_account) = SplTokenAccount::unpack(&account.data) {
+            if let Ok(token_account) = unpack_token_account(&account.owner, &account.data) {
                 token_accounts.push((pubkey, token_account));
                 mint_pubkeys.push(token_account.mint);
             } else {
@@ -251,13 +254,13 @@ pub fn get_multiple_associated_token_accounts(
     let mint_accounts = client.get_multiple_accounts(&mint_pubkeys)?;
 
     // Deserialise mint accounts and get mint account owner
-    let mint_accounts_data: Vec<(SplMintAccount, Pubkey)> = mint_accounts
+    let mint_accounts_data: Vec<(SplMintAccount, Option<TokenExtensions>, Pubkey)> = mint_accounts
         .into_iter()
         .filter_map(|account_option| {
             account_option.and_then(|account| {
-                SplMintAccount::unpack(&account.data)
+                unpack_mint(&account.owner, &account.data)
                     .ok()
-                    .map(|mint_account| (mint_account, account.owner))
+                    .map(|(mint_account, extensions)| (mint_account, extensions, account.owner))
             })
         })
         .collect();
@@ -265,7 +268,7 @@ pub fn get_multiple_associated_token_accounts(
     // Build associated token account details by matching token and mint accounts
     let mut associated_token_accounts = Vec::new();
 
-    for ((pubkey, token_account), (mint_account, token_program)) in token_accounts.into_iter().zip(mint_accounts_data.into_iter()) {
+    for ((pubkey, token_account), (mint_account, extensions, token_program)) in token_accounts.into_iter().zip(mint_accounts_data.into_iter()) {
         associated_token_accounts.push(AssociatedTokenAccount {
             pubkey: pubkey.to_string(),
             owner_pubkey: token_account.owner.to_string(),
@@ -276,7 +279,8 @@ pub fn get_multiple_associated_token_accounts(
             token_ui_amount: token_account.amount as f64
                 / u64::pow(10, mint_account.decimals as u32) as f64,
             mint_authority: mint_account.mint_authority.into(),
-            token_program: token_program.to_string()
+            token_program: token_program.to_string(),
+            extensions,
         });
     }
 
@@ -284,6 +288,34 @@ pub fn get_multiple_associated_token_accounts(
 }
 
 
+/// Bytes of a token account holding its raw token amount, for `get_token_account_amounts`.
+pub const TOKEN_AMOUNT_SLICE: UiDataSliceConfig = UiDataSliceConfig { offset: 64, length: 8 };
+
+/// Gets the raw token amounts of multiple token accounts, downloading only the amount field of
+/// each account, e.g to poll balances frequently. Amounts are `None` for accounts that do not
+/// exist.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InvalidAddress` if any of the addresses is invalid.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, read_transactions::associated_token_account::get_token_account_amounts};
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let amounts = get_token_account_amounts(&client, vec!["7geCZYWHtghvWj11sb7exvu4uMANfhvGvEvVRRZ8GmSd"]).unwrap();
+/// ```
+pub fn get_token_account_amounts(client: &RpcClient, token_account_addresses: Vec<&str>) -> Result<Vec<Option<u64>>, ReadTransactionError> {
+    let slices = get_multiple_account_data_slices(client, token_account_addresses, TOKEN_AMOUNT_SLICE)?;
+    Ok(slices
+        .into_iter()
+        .map(|slice| slice.and_then(|data| Some(u64::from_le_bytes(data.try_into().ok()?))))
+        .collect())
+}
+
+
 #[derive(Debug)]
 struct WalletTokenAccount {
     pub pubkey: String,
@@ -308,6 +340,29 @@ struct WalletTokenAccount {
 pub fn get_all_token_accounts(
     client: &RpcClient,
     wallet_address: &str,
+) -> Result<Vec<AssociatedTokenAccount>, ReadTransactionError> {
+    get_all_token_accounts_with_filter(client, wallet_address, &TokenFilter::default())
+}
+
+/// Gets the associated token accounts of a wallet address kept by `filter`. Filtered accounts
+/// are dropped before their mints are fetched.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{
+///     create_rpc_client,
+///     read_transactions::{associated_token_account::get_all_token_accounts_with_filter, token_filter::TokenFilter}
+/// };
+///
+/// let client = create_rpc_client("RPC_URL");
+/// let filter = TokenFilter { hide_zero_balances: true, hide_frozen: true, ..Default::default() };
+/// let token_accounts = get_all_token_accounts_with_filter(&client, "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5", &filter).unwrap();
+/// ```
+pub fn get_all_token_accounts_with_filter(
+    client: &RpcClient,
+    wallet_address: &str,
+    filter: &TokenFilter,
 ) -> Result<Vec<AssociatedTokenAccount>, ReadTransactionError> {
     // Convert wallet address to Pubkey
     let wallet_pubkey = address_to_pubkey(wallet_address)?;
@@ -369,6 +424,11 @@ pub fn get_all_token_accounts(
                 .and_then(Value::as_f64)
                 .unwrap_or(0.0);
 
+            let is_frozen = info.get("state").and_then(Value::as_str) == Some("frozen");
+            if !filter.allows(&mint_pubkey, token_balance, is_frozen) {
+                continue;
+            }
+
             // Add to the list
             wallet_tokens.push(WalletTokenAccount {
                 pubkey: pubkey.to_string(),
@@ -396,18 +456,18 @@ pub fn get_all_token_accounts(
     let mint_accounts = client.get_multiple_accounts(&mint_pubkeys)?;
 
     // Deserialise mint accounts and get mint pubkey
-    let mint_accounts_data: Vec<SplMintAccount> = mint_accounts
+    let mint_accounts_data: Vec<(SplMintAccount, Option<TokenExtensions>)> = mint_accounts
         .into_iter()
         .filter_map(|account_option| {
             account_option.and_then(|account| {
-                SplMintAccount::unpack(&account.data)
+                unpack_mint(&account.owner, &account.data)
                     .ok()
             })
         })
         .collect();
     
     let mut associated_token_accounts: Vec<AssociatedTokenAccount> = Vec::new();
-    for (wallet_token_account, mint_account) in wallet_tokens.into_iter().zip(mint_accounts_data.into_iter()) {
+    for (wallet_token_account, (mint_account, extensions)) in wallet_tokens.into_iter().zip(mint_accounts_data.into_iter()) {
         associated_token_accounts.push(AssociatedTokenAccount {
             pubkey: wallet_token_account.pubkey,
             owner_pubkey: wallet_token_account.owner_pubkey,
@@ -417,7 +477,8 @@ pub fn get_all_token_accounts(
             token_amount: wallet_token_account.token_amount,
             token_ui_amount: wallet_token_account.ui_amount,
             mint_authority: mint_account.mint_authority.into(),
-            token_program: wallet_token_account.token_program
+            token_program: wallet_token_account.token_program,
+            extensions,
         })
     }
 
@@ -429,7 +490,16 @@ pub fn get_all_token_accounts(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{solana_programs::token_2022_program, utils::create_rpc_client};
+    use solana_client::rpc_request::RpcRequest;
+    use spl_token_2022::{
+        extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut},
+        state::{Account as Token2022Account, AccountState, Mint as Token2022Mint}
+    };
+    use crate::{
+        solana_programs::token_2022_program,
+        test_utils::{account_info_response, account_with_data, mock_client},
+        utils::create_rpc_client
+    };
 
     const WALLET_ADDRESS_1: &str = "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5";
     const ASSOCIATED_ACT_ACCOUNT_ADDRESS: &str = "7geCZYWHtghvWj11sb7exvu4uMANfhvGvEvVRRZ8GmSd";
@@ -453,6 +523,39 @@ mod tests {
         assert!(associated_token_account.mint_authority.is_none());
     }
 
+    #[test]
+    fn test_get_token_2022_associated_token_account() {
+        let (mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let length = ExtensionType::try_calculate_account_len::<Token2022Mint>(&[ExtensionType::TransferFeeConfig]).unwrap();
+        let mut mint_data = vec![0; length];
+        let mut mint_state = StateWithExtensionsMut::<Token2022Mint>::unpack_uninitialized(&mut mint_data).unwrap();
+        let transfer_fee_config = mint_state.init_extension::<TransferFeeConfig>(true).unwrap();
+        transfer_fee_config.newer_transfer_fee.transfer_fee_basis_points = 100.into();
+        transfer_fee_config.newer_transfer_fee.maximum_fee = 1_000.into();
+        mint_state.base = Token2022Mint { supply: 1_000_000, decimals: 2, is_initialized: true, ..Default::default() };
+        mint_state.pack_base();
+        mint_state.init_account_type().unwrap();
+
+        let length = ExtensionType::try_calculate_account_len::<Token2022Account>(&[ExtensionType::TransferFeeAmount]).unwrap();
+        let mut token_account_data = vec![0; length];
+        let mut token_account_state = StateWithExtensionsMut::<Token2022Account>::unpack_uninitialized(&mut token_account_data).unwrap();
+        token_account_state.init_extension::<spl_token_2022::extension::transfer_fee::TransferFeeAmount>(true).unwrap();
+        token_account_state.base = Token2022Account { mint, owner, amount: 250, state: AccountState::Initialized, ..Default::default() };
+        token_account_state.pack_base();
+        token_account_state.init_account_type().unwrap();
+
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&account_with_data(token_2022_program(), token_account_data)), 1)),
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&account_with_data(token_2022_program(), mint_data)), 1)),
+        ]);
+        let associated_token_account = get_associated_token_account(&client, &Pubkey::new_unique().to_string()).unwrap();
+        assert!(associated_token_account.mint_pubkey == mint.to_string() && associated_token_account.owner_pubkey == owner.to_string());
+        assert!(associated_token_account.token_amount == 250 && associated_token_account.token_ui_amount == 2.5);
+        assert!(associated_token_account.token_program == token_2022_program().to_string());
+        let transfer_fee = associated_token_account.extensions.and_then(|extensions| extensions.transfer_fee).unwrap();
+        assert!(transfer_fee.rate_at(0).fee(250) == 3);
+    }
+
     #[test]
     fn faling_test_get_invalid_associated_token_account() {
         let client = create_rpc_client("RPC_URL");
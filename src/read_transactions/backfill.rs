@@ -0,0 +1,343 @@
+//! # Backfill
+//!
+//! Full-history indexing of active addresses. The signatures of the address are paged newest
+//! first, and the transactions of each page are fetched by parallel workers, each over a
+//! contiguous range of signatures. A checkpoint is saved once a page has been handled, so an
+//! interrupted backfill resumes after the last handled page instead of starting over.
+
+use std::{fs, path::{Path, PathBuf}, str::FromStr, thread};
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use crate::error::{BackfillError, PersistenceError, ReadTransactionError};
+use super::{
+    history::{SignatureInfo, SIGNATURES_PAGE_SIZE},
+    transaction::{get_transaction, InspectedTransaction}
+};
+
+/// Options of a history backfill.
+///
+/// ### Fields
+///
+/// - `workers`: Number of transactions fetched in parallel, keep it within the rate limit of the RPC.
+/// - `include_failed`: Whether failed transactions are fetched.
+/// - `checkpoint_path`: JSON file the progress is saved to after every page, and resumed from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackfillConfig {
+    pub workers: usize,
+    pub include_failed: bool,
+    pub checkpoint_path: Option<PathBuf>,
+}
+
+impl Default for BackfillConfig {
+    fn default() -> Self {
+        Self {
+            workers: 8,
+            include_failed: false,
+            checkpoint_path: None,
+        }
+    }
+}
+
+/// Progress of a history backfill.
+///
+/// ### Fields
+///
+/// - `address`: Address whose history is read.
+/// - `before`: Oldest signature handled, the backfill continues with older signatures.
+/// - `newest_signature`: Newest signature when the backfill started, transactions after it are not backfilled.
+/// - `transactions`: Number of transactions handled.
+/// - `complete`: Whether the first transaction of the address was reached.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackfillCheckpoint {
+    pub address: String,
+    pub before: Option<String>,
+    pub newest_signature: Option<String>,
+    pub transactions: u64,
+    pub complete: bool,
+}
+
+impl BackfillCheckpoint {
+    /// Writes the checkpoint to a JSON file. The file is replaced in one step, so a crash while
+    /// saving leaves the previous checkpoint rather than a partial file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PersistenceError> {
+        let json = serde_json::to_string(self)?;
+        let temporary_path = path.as_ref().with_extension("tmp");
+        fs::write(&temporary_path, json)?;
+        fs::rename(temporary_path, path)?;
+        Ok(())
+    }
+
+    /// Reads a checkpoint saved with `save`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PersistenceError> {
+        let json = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+/// Backfill of the transaction history of an address, from newest to oldest.
+pub struct HistoryBackfill {
+    address: Pubkey,
+    config: BackfillConfig,
+    checkpoint: BackfillCheckpoint,
+}
+
+impl HistoryBackfill {
+    /// Creates a backfill of `address`, resuming from the checkpoint file of `config` if it exists.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `BackfillError::InvalidAddress` if the address is invalid,
+    /// `BackfillError::PersistenceError` if the checkpoint can not be read, and
+    /// `BackfillError::CheckpointMismatch` if the checkpoint belongs to another address.
+    pub fn new(address: &str, config: BackfillConfig) -> Result<Self, BackfillError> {
+        let address = Pubkey::from_str(address)?;
+        let checkpoint = match &config.checkpoint_path {
+            Some(path) if path.exists() => BackfillCheckpoint::load(path)?,
+            _ => BackfillCheckpoint { address: address.to_string(), ..BackfillCheckpoint::default() },
+        };
+        if checkpoint.address != address.to_string() {
+            return Err(BackfillError::CheckpointMismatch(checkpoint.address));
+        }
+        Ok(Self { address, config, checkpoint })
+    }
+
+    /// Creates a backfill resuming from `checkpoint`, e.g a checkpoint kept with other data
+    /// instead of in its own file.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `BackfillError::InvalidAddress` if the address of the checkpoint is invalid.
+    pub fn from_checkpoint(checkpoint: BackfillCheckpoint, config: BackfillConfig) -> Result<Self, BackfillError> {
+        let address = Pubkey::from_str(&checkpoint.address)?;
+        Ok(Self { address, config, checkpoint })
+    }
+
+    /// Progress of the backfill.
+    pub fn checkpoint(&self) -> &BackfillCheckpoint {
+        &self.checkpoint
+    }
+
+    /// Backfills the history until the first transaction of the address, passing the
+    /// transactions of every page of up to 1,000 signatures to `handle_page`, newest first. The
+    /// checkpoint only moves past a page once `handle_page` succeeds, so after an error the
+    /// backfill can be run again, in this process or from the saved checkpoint, and the failed
+    /// page is fetched again.
+    ///
+    /// Returns the number of transactions handled by this run.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `BackfillError::ReadError` if signatures or transactions can not be fetched, the
+    /// error of `handle_page`, or `BackfillError::PersistenceError` if the checkpoint can not be saved.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use easy_solana::{
+    ///     create_rpc_client,
+    ///     read_transactions::backfill::{BackfillConfig, HistoryBackfill}
+    /// };
+    ///
+    /// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+    /// let config = BackfillConfig { checkpoint_path: Some("backfill.json".into()), ..Default::default() };
+    /// let mut backfill = HistoryBackfill::new("ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5", config).unwrap();
+    /// backfill.run(&client, |transactions| {
+    ///     println!("{} transactions", transactions.len());
+    ///     Ok(())
+    /// }).unwrap();
+    /// ```
+    pub fn run<F>(&mut self, client: &RpcClient, mut handle_page: F) -> Result<u64, BackfillError>
+    where
+        F: FnMut(Vec<InspectedTransaction>) -> Result<(), BackfillError>,
+    {
+        let mut handled = 0;
+        while !self.checkpoint.complete {
+            let before = self.checkpoint.before
+                .as_deref()
+                .map(Signature::from_str)
+                .transpose()
+                .map_err(|_| ReadTransactionError::InvalidSignature)?;
+            let page: Vec<SignatureInfo> = client
+                .get_signatures_for_address_with_config(
+                    &self.address,
+                    GetConfirmedSignaturesForAddress2Config {
+                        before,
+                        until: None,
+                        limit: Some(SIGNATURES_PAGE_SIZE),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                    }
+                )?
+                .into_iter()
+                .map(SignatureInfo::from)
+                .collect();
+
+            let signatures: Vec<&str> = page
+                .iter()
+                .filter(|signature_info| self.config.include_failed || !signature_info.is_failed)
+                .map(|signature_info| signature_info.signature.as_str())
+                .collect();
+            let transactions = fetch_transactions(client, &signatures, self.config.workers)?;
+            let transaction_count = transactions.len() as u64;
+            handle_page(transactions)?;
+
+            if self.checkpoint.newest_signature.is_none() {
+                self.checkpoint.newest_signature = page.first().map(|signature_info| signature_info.signature.clone());
+            }
+            if let Some(oldest) = page.last() {
+                self.checkpoint.before = Some(oldest.signature.clone());
+            }
+            self.checkpoint.transactions += transaction_count;
+            self.checkpoint.complete = page.len() < SIGNATURES_PAGE_SIZE;
+            handled += transaction_count;
+            if let Some(path) = &self.config.checkpoint_path {
+                self.checkpoint.save(path)?;
+            }
+        }
+        Ok(handled)
+    }
+}
+
+/// Fetches transactions in the order of `signatures`, splitting them into one contiguous range
+/// per worker.
+pub(crate) fn fetch_transactions(client: &RpcClient, signatures: &[&str], workers: usize) -> Result<Vec<InspectedTransaction>, ReadTransactionError> {
+    if signatures.is_empty() {
+        return Ok(vec![]);
+    }
+    let range_size = signatures.len().div_ceil(workers.max(1));
+    let ranges: Vec<Result<Vec<InspectedTransaction>, ReadTransactionError>> = thread::scope(|scope| {
+        let handles: Vec<_> = signatures
+            .chunks(range_size)
+            .map(|range| scope.spawn(move || {
+                range
+                    .iter()
+                    .map(|signature| get_transaction(client, signature))
+                    .collect::<Result<Vec<InspectedTransaction>, ReadTransactionError>>()
+            }))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|_| Err(ReadTransactionError::RpcError("Backfill worker panicked".to_string()))))
+            .collect()
+    });
+    let mut transactions = Vec::with_capacity(signatures.len());
+    for range in ranges {
+        transactions.extend(range?);
+    }
+    Ok(transactions)
+}
+
+
+/// Newest signature of `address` and the signatures of its successful transactions after
+/// `until`, newest first. Without `until`, only the latest page is read.
+pub(crate) fn signatures_since(client: &RpcClient, address: &Pubkey, until: Option<&str>) -> Result<(Option<String>, Vec<String>), ReadTransactionError> {
+    let until = until
+        .map(Signature::from_str)
+        .transpose()
+        .map_err(|_| ReadTransactionError::InvalidSignature)?;
+    let mut newest_signature = None;
+    let mut signatures = Vec::new();
+    let mut before = None;
+    loop {
+        let page = client.get_signatures_for_address_with_config(
+            address,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until,
+                limit: Some(SIGNATURES_PAGE_SIZE),
+                commitment: Some(CommitmentConfig::confirmed()),
+            }
+        )?;
+        if newest_signature.is_none() {
+            newest_signature = page.first().map(|status| status.signature.clone());
+        }
+        before = match page.last() {
+            Some(status) => Some(Signature::from_str(&status.signature).map_err(|_| ReadTransactionError::InvalidSignature)?),
+            None => break,
+        };
+        let is_last_page = page.len() < SIGNATURES_PAGE_SIZE;
+        signatures.extend(page.into_iter().filter(|status| status.err.is_none()).map(|status| status.signature));
+        if is_last_page || until.is_none() {
+            break;
+        }
+    }
+    Ok((newest_signature, signatures))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::{hash::Hash, signer::{keypair::Keypair, Signer}};
+    use solana_system_interface::instruction::transfer;
+    use crate::test_utils::{mock_client, signatures_for_address_response, transaction_response};
+
+    fn signature_page(length: usize) -> Vec<String> {
+        (0..length).map(|_| Signature::new_unique().to_string()).collect()
+    }
+
+    /// Response of `getSignaturesForAddress` where only the first `successful` transactions succeeded.
+    fn page_response(signatures: &[String], successful: usize) -> serde_json::Value {
+        let page: Vec<(&str, u64, Option<i64>, bool)> = signatures
+            .iter()
+            .enumerate()
+            .map(|(index, signature)| (signature.as_str(), 1_000 - index as u64, Some(1_000 - index as i64), index >= successful))
+            .collect();
+        signatures_for_address_response(&page)
+    }
+
+    fn transaction_responses(count: usize) -> Vec<(RpcRequest, serde_json::Value)> {
+        let payer = Keypair::new();
+        let transaction = solana_sdk::transaction::Transaction::new_signed_with_payer(
+            &[transfer(&payer.pubkey(), &Pubkey::new_unique(), 1_000)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default()
+        );
+        (0..count).map(|_| (RpcRequest::GetTransaction, transaction_response(&transaction, 1, Some(1), &[]))).collect()
+    }
+
+    #[test]
+    fn test_backfill_resumes_from_checkpoint() {
+        let path = std::env::temp_dir().join(format!("easy_solana_backfill_{}.json", Pubkey::new_unique()));
+        let address = Pubkey::new_unique().to_string();
+        let config = BackfillConfig { workers: 2, checkpoint_path: Some(path.clone()), ..Default::default() };
+        let (first_page, second_page) = (signature_page(SIGNATURES_PAGE_SIZE), signature_page(4));
+
+        // The handler fails on the second page
+        let mut responses = vec![
+            (RpcRequest::GetSignaturesForAddress, page_response(&first_page, 3)),
+            (RpcRequest::GetSignaturesForAddress, page_response(&second_page, 2)),
+        ];
+        responses.extend(transaction_responses(5));
+        let client = mock_client(responses);
+        let mut backfill = HistoryBackfill::new(&address, config.clone()).unwrap();
+        let mut pages = Vec::new();
+        let result = backfill.run(&client, |transactions| {
+            if !pages.is_empty() {
+                return Err(BackfillError::HandlerError("storage is full".to_string()));
+            }
+            pages.push(transactions.iter().map(|transaction| transaction.signature.clone()).collect::<Vec<String>>());
+            Ok(())
+        });
+        assert!(matches!(result, Err(BackfillError::HandlerError(_))));
+        assert!(pages == vec![first_page[..3].to_vec()]);
+
+        // A new backfill resumes after the first page
+        let checkpoint = BackfillCheckpoint::load(&path).unwrap();
+        assert!(checkpoint.before.as_ref() == first_page.last() && checkpoint.newest_signature.as_ref() == first_page.first());
+        assert!(checkpoint.transactions == 3 && !checkpoint.complete);
+        let mut responses = vec![(RpcRequest::GetSignaturesForAddress, page_response(&second_page, 2))];
+        responses.extend(transaction_responses(2));
+        let client = mock_client(responses);
+        let mut backfill = HistoryBackfill::new(&address, config.clone()).unwrap();
+        let handled = backfill.run(&client, |_| Ok(())).unwrap();
+        assert!(handled == 2);
+        assert!(backfill.checkpoint().transactions == 5 && backfill.checkpoint().complete);
+
+        let result = HistoryBackfill::new(&Pubkey::new_unique().to_string(), config);
+        assert!(matches!(result, Err(BackfillError::CheckpointMismatch(_))));
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,5 +1,11 @@
+use serde_json::json;
 use solana_sdk::native_token::LAMPORTS_PER_SOL;
-use solana_client::rpc_client::RpcClient;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::RpcContextConfig,
+    rpc_request::RpcRequest,
+    rpc_response::Response
+};
 
 use crate::{error::ReadTransactionError, get_associated_token_account, utils::address_to_pubkey};
 
@@ -18,6 +24,21 @@ pub fn get_sol_balance(client: &RpcClient, address: &str) -> Result<f64, ReadTra
     Ok(ui_balance)
 }
 
+/// Queries an account's solana balance in UI format from an RPC node that has processed
+/// at least `min_context_slot`, e.g. the slot of a transaction that was just sent.
+pub fn get_sol_balance_with_min_context_slot(client: &RpcClient, address: &str, min_context_slot: u64) -> Result<f64, ReadTransactionError> {
+    let pubkey = address_to_pubkey(address)?;
+    let config = RpcContextConfig {
+        commitment: Some(client.commitment()),
+        min_context_slot: Some(min_context_slot),
+    };
+
+    let balance: Response<u64> = client.send(RpcRequest::GetBalance, json!([pubkey.to_string(), config]))?;
+    let ui_balance = balance.value as f64 / LAMPORTS_PER_SOL as f64;
+
+    Ok(ui_balance)
+}
+
 pub struct SplTokenBalance {
     pub balance: u64, // balance without decimals
     pub token_decimals: u8, // token decimals
@@ -38,7 +59,7 @@ pub fn get_token_balance(client: &RpcClient, associated_token_account_address: &
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::create_rpc_client;
+    use crate::{test_utils::mock_client, utils::create_rpc_client};
 
     const EMPTY_WALLET_ADDRESS: &str = "7o2B9chozpRvHsLgm1Qp3UV9NrS7bx7NH3BZKSePtHEh";
     const ASSOCIATED_HAPPY_CAT_WALLET_ADDRESS: &str = "4ZVBVjcaLUqUxVi3EHaVKp1pZ96AZoznyGWgWxKYZhsD";
@@ -69,4 +90,10 @@ mod tests {
         }
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_get_sol_balance_with_min_context_slot() {
+        let client = mock_client(vec![(RpcRequest::GetBalance, json!({ "context": { "slot": 10 }, "value": 20_000_000 }))]);
+        let sol_balance = get_sol_balance_with_min_context_slot(&client, EMPTY_WALLET_ADDRESS, 10).unwrap();
+        assert!(sol_balance == 0.02);
+    }
+}
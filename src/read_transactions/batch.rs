@@ -0,0 +1,257 @@
+//! # Batch
+//!
+//! This module sends several JSON-RPC requests in a single HTTP request, for providers that
+//! support JSON-RPC batching, and uses it to load token details in one round trip.
+
+use serde_json::{json, Value};
+use solana_account_decoder::UiAccount;
+use reqwest::StatusCode;
+use solana_client::{rpc_client::RpcClient, rpc_request::RpcRequest};
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::Mint as SplMintAccount;
+use crate::{
+    constants::solana_programs::metadata_program,
+    error::ReadTransactionError,
+    pumpfun::bonding_curve::{derive_bonding_curve, BondingCurveAccount},
+    utils::addresses_to_pubkeys
+};
+use super::metadata::MetadataAccount;
+
+/// Maximum number of accounts per `getMultipleAccounts` request.
+pub(crate) const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Mint, metadata and bonding curve of a token, each `None` if the account does not exist or is invalid.
+#[derive(Debug)]
+pub struct TokenDetails {
+    pub mint_pubkey: Pubkey,
+    pub mint: Option<SplMintAccount>,
+    pub metadata: Option<MetadataAccount>,
+    pub bonding_curve: Option<BondingCurveAccount>,
+}
+
+/// Sends JSON-RPC requests as a single batch and returns the result of each request in order.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::BatchNotSupported` if the provider rejects the batch or
+/// `rpc_url` is not an HTTP endpoint, and `ReadTransactionError::RpcError` if the HTTP request
+/// fails otherwise, e.g on network errors or rate limits. Errors of individual requests are
+/// returned in their result.
+pub async fn send_batch_request(rpc_url: &str, requests: Vec<(RpcRequest, Value)>) -> Result<Vec<Result<Value, String>>, ReadTransactionError> {
+    let request_count = requests.len();
+    let batch: Vec<Value> = requests
+        .into_iter()
+        .enumerate()
+        .map(|(id, (request, params))| request.build_request_json(id as u64, params))
+        .collect();
+
+    let response = reqwest::Client::new()
+        .post(rpc_url)
+        .json(&batch)
+        .send()
+        .await
+        .map_err(|err| match err.is_builder() {
+            true => ReadTransactionError::BatchNotSupported(err.to_string()),
+            false => ReadTransactionError::RpcError(err.to_string()),
+        })?;
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+        return Err(ReadTransactionError::RpcError(format!("Batch request failed with status {}", status)));
+    }
+    let response: Value = match response.json().await {
+        Ok(response) => response,
+        // Providers rejecting batches may answer with a client error that is not JSON
+        Err(err) if status.is_client_error() => return Err(ReadTransactionError::BatchNotSupported(err.to_string())),
+        Err(err) => return Err(ReadTransactionError::RpcError(err.to_string())),
+    };
+
+    parse_batch_response(response, request_count)
+}
+
+/// Matches batch responses to their requests by id, as providers may answer in any order.
+fn parse_batch_response(response: Value, request_count: usize) -> Result<Vec<Result<Value, String>>, ReadTransactionError> {
+    let Value::Array(responses) = response else {
+        return Err(ReadTransactionError::BatchNotSupported(response.to_string()));
+    };
+
+    let mut results = vec![Err("Missing response".to_string()); request_count];
+    for response in responses {
+        let Some(id) = response.get("id").and_then(Value::as_u64).filter(|id| (*id as usize) < request_count) else {
+            continue;
+        };
+        results[id as usize] = match (response.get("result"), response.get("error")) {
+            (_, Some(error)) => Err(error.to_string()),
+            (Some(result), None) => Ok(result.clone()),
+            (None, None) => Err("Missing result".to_string()),
+        };
+    }
+    Ok(results)
+}
+
+/// Fetches several groups of accounts in a single HTTP request, with `getMultipleAccounts` calls
+/// of at most 100 accounts each. Falls back to one request per call if the provider does not
+/// support batching.
+pub async fn get_multiple_accounts_batch(
+    client: &RpcClient,
+    account_groups: &[Vec<Pubkey>]
+) -> Result<Vec<Vec<Option<Account>>>, ReadTransactionError> {
+    let chunks: Vec<(usize, &[Pubkey])> = account_groups
+        .iter()
+        .enumerate()
+        .flat_map(|(group_index, pubkeys)| pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS).map(move |chunk| (group_index, chunk)))
+        .collect();
+    let requests = chunks
+        .iter()
+        .map(|(_, pubkeys)| {
+            let addresses: Vec<String> = pubkeys.iter().map(Pubkey::to_string).collect();
+            (RpcRequest::GetMultipleAccounts, json!([addresses, { "encoding": "base64", "commitment": client.commitment().commitment }]))
+        })
+        .collect();
+
+    let chunk_accounts = match send_batch_request(&client.url(), requests).await {
+        Ok(results) => results
+            .into_iter()
+            .map(|result| {
+                let result = result.map_err(ReadTransactionError::RpcError)?;
+                parse_multiple_accounts_result(&result)
+            })
+            .collect::<Result<Vec<_>, ReadTransactionError>>()?,
+        Err(ReadTransactionError::BatchNotSupported(_)) => chunks
+            .iter()
+            .map(|(_, pubkeys)| Ok(client.get_multiple_accounts(pubkeys)?))
+            .collect::<Result<Vec<_>, ReadTransactionError>>()?,
+        Err(err) => return Err(err),
+    };
+
+    let mut accounts = vec![Vec::new(); account_groups.len()];
+    for ((group_index, _), chunk_accounts) in chunks.iter().zip(chunk_accounts) {
+        accounts[*group_index].extend(chunk_accounts);
+    }
+    Ok(accounts)
+}
+
+fn parse_multiple_accounts_result(result: &Value) -> Result<Vec<Option<Account>>, ReadTransactionError> {
+    let accounts = result
+        .get("value")
+        .and_then(Value::as_array)
+        .ok_or(ReadTransactionError::DeserializeError)?;
+    accounts
+        .iter()
+        .map(|account| {
+            if account.is_null() {
+                return Ok(None);
+            }
+            let ui_account: UiAccount = serde_json::from_value(account.clone()).map_err(|_| ReadTransactionError::DeserializeError)?;
+            Ok(ui_account.decode())
+        })
+        .collect()
+}
+
+/// Gets the mint, metadata and bonding curve of each token in a single round trip.
+/// Invalid addresses are filtered out.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, read_transactions::batch::get_token_details};
+///
+/// # async fn example() {
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let token_details = get_token_details(&client, vec!["CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump"]).await.unwrap();
+/// # }
+/// ```
+pub async fn get_token_details(client: &RpcClient, token_addresses: Vec<&str>) -> Result<Vec<TokenDetails>, ReadTransactionError> {
+    let mint_pubkeys = addresses_to_pubkeys(token_addresses);
+    let metadata_program = metadata_program();
+    let metadata_pubkeys: Vec<Pubkey> = mint_pubkeys
+        .iter()
+        .map(|mint_pubkey| {
+            let seeds = &[b"metadata", metadata_program.as_ref(), mint_pubkey.as_ref()];
+            Pubkey::find_program_address(seeds, &metadata_program).0
+        })
+        .collect();
+    let bonding_curve_pubkeys: Vec<Pubkey> = mint_pubkeys.iter().map(derive_bonding_curve).collect();
+
+    let account_groups = get_multiple_accounts_batch(client, &[mint_pubkeys.clone(), metadata_pubkeys, bonding_curve_pubkeys]).await?;
+    let [mint_accounts, metadata_accounts, bonding_curve_accounts]: [Vec<Option<Account>>; 3] = account_groups
+        .try_into()
+        .map_err(|_| ReadTransactionError::DeserializeError)?;
+
+    let token_details = mint_pubkeys
+        .into_iter()
+        .zip(mint_accounts)
+        .zip(metadata_accounts.into_iter().zip(bonding_curve_accounts))
+        .map(|((mint_pubkey, mint_account), (metadata_account, bonding_curve_account))| TokenDetails {
+            mint_pubkey,
+            mint: mint_account.and_then(|account| {
+                account.data.get(..SplMintAccount::LEN).and_then(|data| SplMintAccount::unpack(data).ok())
+            }),
+            metadata: metadata_account.and_then(|account| MetadataAccount::try_from_account_data(&account.data).ok()),
+            bonding_curve: bonding_curve_account.and_then(|account| BondingCurveAccount::try_from_account_data(&account.data).ok()),
+        })
+        .collect();
+
+    Ok(token_details)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constants::pumpfun_accounts::pumpfun_program,
+        test_utils::{account_with_data, mock_client, multiple_accounts_response}
+    };
+
+    #[test]
+    fn test_parse_batch_response_out_of_order() {
+        let response = json!([
+            { "jsonrpc": "2.0", "id": 1, "error": { "code": -32602, "message": "Invalid params" } },
+            { "jsonrpc": "2.0", "id": 0, "result": 5 },
+        ]);
+        let results = parse_batch_response(response, 3).unwrap();
+        assert!(results[0] == Ok(json!(5)));
+        assert!(results[1].is_err());
+        assert!(results[2].is_err());
+
+        // providers without batch support answer with a single error object
+        let response = json!({ "jsonrpc": "2.0", "id": null, "error": { "code": -32600, "message": "Batch not supported" } });
+        assert!(matches!(parse_batch_response(response, 1), Err(ReadTransactionError::BatchNotSupported(_))));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_token_details_without_batch_support() {
+        let mint = Pubkey::new_unique();
+        let bonding_curve_account = account_with_data(pumpfun_program(), vec![0; 8]);
+        // mock client has no HTTP endpoint, so requests fall back to the client one group at a time
+        let client = mock_client(vec![
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[None], 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[None], 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(bonding_curve_account)], 1)),
+        ]);
+
+        let mint_address = mint.to_string();
+        let token_details = get_token_details(&client, vec![&mint_address]).await.unwrap();
+        assert!(token_details.len() == 1);
+        assert!(token_details[0].mint_pubkey == mint);
+        assert!(token_details[0].mint.is_none() && token_details[0].metadata.is_none());
+        // wrong discriminator
+        assert!(token_details[0].bonding_curve.is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_multiple_accounts_batch_chunks_large_groups() {
+        let pubkeys: Vec<Pubkey> = (0..150).map(|_| Pubkey::new_unique()).collect();
+        let account = account_with_data(pumpfun_program(), vec![1]);
+        let client = mock_client(vec![
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&vec![None; 100], 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&vec![Some(account.clone()); 50], 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[None], 1)),
+        ]);
+
+        let account_groups = get_multiple_accounts_batch(&client, &[pubkeys, vec![Pubkey::new_unique()]]).await.unwrap();
+        assert!(account_groups.len() == 2);
+        assert!(account_groups[0].len() == 150 && account_groups[1].len() == 1);
+        assert!(account_groups[0][99].is_none() && account_groups[0][100] == Some(account));
+    }
+}
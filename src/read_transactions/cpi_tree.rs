@@ -0,0 +1,168 @@
+//! # CPI Tree
+//!
+//! This module rebuilds the tree of cross program invocations (CPIs) of a transaction
+//! from its flat list of inner instructions, so that swap routes and internal transfers
+//! can be analysed by which program invoked which.
+
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::json::{serialize_base58, serialize_display, serialize_display_vec};
+use super::transaction::{InspectedInnerInstructions, InspectedInstruction};
+
+/// An instruction and the instructions it invoked.
+///
+/// ### Fields
+///
+/// - `program_id`: The program that was invoked.
+/// - `accounts`: Accounts passed to the program, empty if unavailable (e.g parsed simulation instructions).
+/// - `data`: Instruction data, empty if unavailable.
+/// - `depth`: Invocation depth, top level instructions have a depth of 1.
+/// - `children`: Instructions invoked by this instruction, in order.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CpiNode {
+    #[serde(serialize_with = "serialize_display")]
+    pub program_id: Pubkey,
+    #[serde(serialize_with = "serialize_display_vec")]
+    pub accounts: Vec<Pubkey>,
+    #[serde(serialize_with = "serialize_base58")]
+    pub data: Vec<u8>,
+    pub depth: u32,
+    pub children: Vec<CpiNode>,
+}
+
+impl CpiNode {
+    /// Returns this node and all of its descendants in invocation order.
+    pub fn flatten(&self) -> Vec<&CpiNode> {
+        let mut nodes = vec![self];
+        for child in &self.children {
+            nodes.extend(child.flatten());
+        }
+        nodes
+    }
+
+    /// Whether this instruction invoked the given program, directly or indirectly.
+    pub fn invokes(&self, program_id: &Pubkey) -> bool {
+        self.children.iter().any(|child| &child.program_id == program_id || child.invokes(program_id))
+    }
+}
+
+/// Builds one CPI tree per top level instruction. Inner instructions are nested by their
+/// stack height, inner instructions without a stack height are treated as direct CPIs
+/// of their top level instruction.
+pub fn build_cpi_tree(instructions: &[InspectedInstruction], inner_instructions: &[InspectedInnerInstructions]) -> Vec<CpiNode> {
+    instructions
+        .iter()
+        .enumerate()
+        .map(|(index, instruction)| {
+            let mut flat_nodes = vec![to_cpi_node(instruction, 1)];
+            if let Some(invoked) = inner_instructions.iter().find(|inner| inner.index as usize == index) {
+                flat_nodes.extend(
+                    invoked.instructions
+                        .iter()
+                        .map(|inner_instruction| to_cpi_node(inner_instruction, inner_instruction.stack_height.unwrap_or(2).max(2)))
+                );
+            }
+            // A top level instruction is always the single root of its nodes
+            nest_by_depth(flat_nodes).remove(0)
+        })
+        .collect()
+}
+
+fn to_cpi_node(instruction: &InspectedInstruction, depth: u32) -> CpiNode {
+    CpiNode {
+        program_id: instruction.program_id,
+        accounts: instruction.accounts.clone(),
+        data: instruction.data.clone(),
+        depth,
+        children: Vec::new(),
+    }
+}
+
+/// Nests nodes listed in invocation order under the closest previous node with a lower depth.
+fn nest_by_depth(flat_nodes: Vec<CpiNode>) -> Vec<CpiNode> {
+    let mut roots: Vec<CpiNode> = Vec::new();
+    let mut stack: Vec<CpiNode> = Vec::new();
+
+    for node in flat_nodes {
+        while stack.last().is_some_and(|top| top.depth >= node.depth) {
+            attach_top(&mut stack, &mut roots);
+        }
+        stack.push(node);
+    }
+    while !stack.is_empty() {
+        attach_top(&mut stack, &mut roots);
+    }
+
+    roots
+}
+
+fn attach_top(stack: &mut Vec<CpiNode>, roots: &mut Vec<CpiNode>) {
+    if let Some(node) = stack.pop() {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => roots.push(node),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instruction(program_id: Pubkey, stack_height: Option<u32>) -> InspectedInstruction {
+        InspectedInstruction {
+            program_id,
+            accounts: vec![],
+            data: vec![],
+            stack_height,
+        }
+    }
+
+    #[test]
+    fn test_build_cpi_tree() {
+        let swap_program = Pubkey::new_unique();
+        let amm_program = Pubkey::new_unique();
+        let token_program = Pubkey::new_unique();
+        let compute_budget_program = Pubkey::new_unique();
+
+        // compute budget, then swap -> amm -> (token, token) and swap -> token
+        let instructions = vec![instruction(compute_budget_program, Some(1)), instruction(swap_program, Some(1))];
+        let inner_instructions = vec![InspectedInnerInstructions {
+            index: 1,
+            instructions: vec![
+                instruction(amm_program, Some(2)),
+                instruction(token_program, Some(3)),
+                instruction(token_program, Some(3)),
+                instruction(token_program, Some(2)),
+            ],
+        }];
+
+        let cpi_tree = build_cpi_tree(&instructions, &inner_instructions);
+        assert!(cpi_tree.len() == 2);
+        assert!(cpi_tree[0].children.is_empty());
+
+        let swap_node = &cpi_tree[1];
+        assert!(swap_node.children.len() == 2);
+        assert!(swap_node.children[0].program_id == amm_program);
+        assert!(swap_node.children[0].children.len() == 2);
+        assert!(swap_node.children[1].program_id == token_program);
+        assert!(swap_node.invokes(&token_program));
+        assert!(swap_node.flatten().len() == 5);
+    }
+
+    #[test]
+    fn test_build_cpi_tree_without_stack_heights() {
+        let program = Pubkey::new_unique();
+        let instructions = vec![instruction(program, None)];
+        let inner_instructions = vec![InspectedInnerInstructions {
+            index: 0,
+            instructions: vec![instruction(program, None), instruction(program, None)],
+        }];
+
+        let cpi_tree = build_cpi_tree(&instructions, &inner_instructions);
+        assert!(cpi_tree[0].children.len() == 2);
+        assert!(cpi_tree[0].children.iter().all(|child| child.depth == 2));
+    }
+}
@@ -0,0 +1,426 @@
+//! # Governance
+//!
+//! Reads the SPL Governance deposits and votes of a wallet, so DAO tooling can show the tokens
+//! locked in realms alongside the liquid portfolio. DAOs often run their own deployment of the
+//! program, the program id is passed to every reader, `spl_governance_program` being the
+//! shared deployment.
+
+use std::str::FromStr;
+use borsh::BorshDeserialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType}
+};
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::Mint;
+use crate::{
+    constants::governance_accounts::{
+        REALM_V1, REALM_V2, TOKEN_OWNER_RECORD_V1, TOKEN_OWNER_RECORD_V2, VOTE_RECORD_V1, VOTE_RECORD_V2
+    },
+    error::ReadTransactionError
+};
+
+/// Offset of the governing token owner in token owner records.
+const TOKEN_OWNER_RECORD_OWNER_OFFSET: usize = 65;
+/// Offset of the governing token owner in vote records.
+const VOTE_RECORD_OWNER_OFFSET: usize = 33;
+/// Offset of the governance delegate in token owner records, the same in both versions.
+const TOKEN_OWNER_RECORD_DELEGATE_OFFSET: usize = 121;
+
+/// Deposit of governing tokens into a realm, from which the owner votes.
+///
+/// ### Fields
+///
+/// - `realm`: Realm the tokens are deposited in.
+/// - `governing_token_mint`: Community or council mint of the realm.
+/// - `governing_token_owner`: Wallet owning the deposit.
+/// - `deposit_amount`: Tokens deposited without decimals.
+/// - `unrelinquished_votes`: Votes cast on proposals that are not relinquished yet, tokens can not be withdrawn while non zero.
+/// - `outstanding_proposals`: Proposals created by the owner that are not finalized yet.
+/// - `governance_delegate`: Wallet allowed to vote on behalf of the owner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenOwnerRecord {
+    pub realm: Pubkey,
+    pub governing_token_mint: Pubkey,
+    pub governing_token_owner: Pubkey,
+    pub deposit_amount: u64,
+    pub unrelinquished_votes: u64,
+    pub outstanding_proposals: u8,
+    pub governance_delegate: Option<Pubkey>,
+}
+
+impl TokenOwnerRecord {
+    /// Deserializes a V1 or V2 token owner record.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::WrongAccountType` if the account is not a token owner record,
+    /// or `ReadTransactionError::DeserializeError` if the data is too short.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, ReadTransactionError> {
+        let account_type = *data.first().ok_or(ReadTransactionError::DeserializeError)?;
+        if account_type != TOKEN_OWNER_RECORD_V1 && account_type != TOKEN_OWNER_RECORD_V2 {
+            return Err(ReadTransactionError::WrongAccountType("governance token owner record".to_string()));
+        }
+        if data.len() < TOKEN_OWNER_RECORD_DELEGATE_OFFSET + 1 {
+            return Err(ReadTransactionError::DeserializeError);
+        }
+        let unrelinquished_votes = if account_type == TOKEN_OWNER_RECORD_V2 {
+            read_u64(data, 105)?
+        } else {
+            u32::from_le_bytes(data[105..109].try_into().map_err(|_| ReadTransactionError::DeserializeError)?) as u64
+        };
+        let governance_delegate = match data[TOKEN_OWNER_RECORD_DELEGATE_OFFSET] {
+            0 => None,
+            _ => Some(read_pubkey(data, TOKEN_OWNER_RECORD_DELEGATE_OFFSET + 1)?),
+        };
+
+        Ok(TokenOwnerRecord {
+            realm: read_pubkey(data, 1)?,
+            governing_token_mint: read_pubkey(data, 33)?,
+            governing_token_owner: read_pubkey(data, 65)?,
+            deposit_amount: read_u64(data, 97)?,
+            unrelinquished_votes,
+            outstanding_proposals: data[113],
+            governance_delegate,
+        })
+    }
+}
+
+/// How a wallet voted on a proposal. V1 vote records only have yes and no votes, read as
+/// `Approve` and `Deny`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GovernanceVoteKind {
+    Approve,
+    Deny,
+    Abstain,
+    Veto,
+}
+
+/// Vote of a wallet on a proposal.
+///
+/// ### Fields
+///
+/// - `proposal`: Proposal voted on.
+/// - `governing_token_owner`: Wallet that voted.
+/// - `is_relinquished`: Whether the vote was relinquished, which is required to withdraw the deposit.
+/// - `voter_weight`: Weight of the vote, usually the deposited tokens without decimals.
+/// - `vote`: How the wallet voted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoteRecord {
+    pub proposal: Pubkey,
+    pub governing_token_owner: Pubkey,
+    pub is_relinquished: bool,
+    pub voter_weight: u64,
+    pub vote: GovernanceVoteKind,
+}
+
+impl VoteRecord {
+    /// Deserializes a V1 or V2 vote record.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::WrongAccountType` if the account is not a vote record,
+    /// or `ReadTransactionError::DeserializeError` if the data is too short or the vote is unknown.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, ReadTransactionError> {
+        let account_type = *data.first().ok_or(ReadTransactionError::DeserializeError)?;
+        let (voter_weight, vote) = match account_type {
+            VOTE_RECORD_V2 => {
+                let vote = match data.get(74) {
+                    Some(0) => GovernanceVoteKind::Approve,
+                    Some(1) => GovernanceVoteKind::Deny,
+                    Some(2) => GovernanceVoteKind::Abstain,
+                    Some(3) => GovernanceVoteKind::Veto,
+                    _ => return Err(ReadTransactionError::DeserializeError),
+                };
+                (read_u64(data, 66)?, vote)
+            }
+            VOTE_RECORD_V1 => {
+                let vote = match data.get(66) {
+                    Some(0) => GovernanceVoteKind::Approve,
+                    Some(1) => GovernanceVoteKind::Deny,
+                    _ => return Err(ReadTransactionError::DeserializeError),
+                };
+                (read_u64(data, 67)?, vote)
+            }
+            _ => return Err(ReadTransactionError::WrongAccountType("governance vote record".to_string())),
+        };
+
+        Ok(VoteRecord {
+            proposal: read_pubkey(data, 1)?,
+            governing_token_owner: read_pubkey(data, 33)?,
+            is_relinquished: data.get(65).is_some_and(|is_relinquished| *is_relinquished != 0),
+            voter_weight,
+            vote,
+        })
+    }
+}
+
+/// A token owner record of a wallet with the name of its realm and the deposit in ui format.
+///
+/// ### Fields
+///
+/// - `token_owner_record`: Address of the record.
+/// - `record`: The record.
+/// - `realm_name`: Name of the realm, `None` if the realm could not be read.
+/// - `ui_amount`: Deposit in ui format, `None` if the mint could not be read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GovernanceDeposit {
+    pub token_owner_record: Pubkey,
+    pub record: TokenOwnerRecord,
+    pub realm_name: Option<String>,
+    pub ui_amount: Option<f64>,
+}
+
+/// A vote record of a wallet with the realm of the proposal.
+///
+/// ### Fields
+///
+/// - `vote_record`: Address of the record.
+/// - `record`: The record.
+/// - `governance`: Governance of the proposal, `None` if the proposal was closed.
+/// - `realm`: Realm of the governance, `None` if the proposal or the governance was closed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GovernanceVote {
+    pub vote_record: Pubkey,
+    pub record: VoteRecord,
+    pub governance: Option<Pubkey>,
+    pub realm: Option<Pubkey>,
+}
+
+/// Derives the token owner record of `governing_token_owner` for `governing_token_mint` in `realm`.
+pub fn derive_token_owner_record(program_id: &Pubkey, realm: &Pubkey, governing_token_mint: &Pubkey, governing_token_owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"governance", realm.as_ref(), governing_token_mint.as_ref(), governing_token_owner.as_ref()],
+        program_id,
+    ).0
+}
+
+/// Gets the governing tokens a wallet deposited in the realms of a governance program.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InvalidAddress` if the wallet is invalid, or
+/// `ReadTransactionError::RpcError` if the accounts can not be fetched.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_client::rpc_client::RpcClient;
+/// use easy_solana::{
+///     constants::governance_accounts::spl_governance_program,
+///     read_transactions::governance::get_governance_deposits
+/// };
+///
+/// let client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+/// let deposits = get_governance_deposits(&client, "9PvGbwGBQVJ5gVn5DjS6ubpkMaHcA5n1AmphFFaytaQR", &spl_governance_program()).unwrap();
+/// for deposit in deposits {
+///     println!("{:?}: {:?} locked", deposit.realm_name, deposit.ui_amount);
+/// }
+/// ```
+pub fn get_governance_deposits(client: &RpcClient, wallet: &str, program_id: &Pubkey) -> Result<Vec<GovernanceDeposit>, ReadTransactionError> {
+    let wallet = Pubkey::from_str(wallet)?;
+    let records: Vec<(Pubkey, TokenOwnerRecord)> = get_owner_accounts(client, program_id, &wallet, TOKEN_OWNER_RECORD_OWNER_OFFSET)?
+        .into_iter()
+        .filter_map(|(pubkey, account)| Some((pubkey, TokenOwnerRecord::try_from_account_data(&account.data).ok()?)))
+        .filter(|(_, record)| record.governing_token_owner == wallet)
+        .collect();
+
+    let realms: Vec<Pubkey> = records.iter().map(|(_, record)| record.realm).collect();
+    let mints: Vec<Pubkey> = records.iter().map(|(_, record)| record.governing_token_mint).collect();
+    let realm_accounts = client.get_multiple_accounts(&realms)?;
+    let mint_accounts = client.get_multiple_accounts(&mints)?;
+
+    let deposits = records
+        .into_iter()
+        .zip(realm_accounts.into_iter().zip(mint_accounts))
+        .map(|((token_owner_record, record), (realm_account, mint_account))| {
+            let decimals = mint_account.and_then(|account| Mint::unpack(&account.data).ok()).map(|mint| mint.decimals);
+            GovernanceDeposit {
+                token_owner_record,
+                realm_name: realm_account.and_then(|account| realm_name(&account.data)),
+                ui_amount: decimals.map(|decimals| record.deposit_amount as f64 / 10_f64.powi(decimals as i32)),
+                record,
+            }
+        })
+        .collect();
+    Ok(deposits)
+}
+
+/// Gets the votes of a wallet on the proposals of a governance program, with the realm of
+/// each proposal.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InvalidAddress` if the wallet is invalid, or
+/// `ReadTransactionError::RpcError` if the accounts can not be fetched.
+pub fn get_governance_votes(client: &RpcClient, wallet: &str, program_id: &Pubkey) -> Result<Vec<GovernanceVote>, ReadTransactionError> {
+    let wallet = Pubkey::from_str(wallet)?;
+    let records: Vec<(Pubkey, VoteRecord)> = get_owner_accounts(client, program_id, &wallet, VOTE_RECORD_OWNER_OFFSET)?
+        .into_iter()
+        .filter_map(|(pubkey, account)| Some((pubkey, VoteRecord::try_from_account_data(&account.data).ok()?)))
+        .filter(|(_, record)| record.governing_token_owner == wallet)
+        .collect();
+
+    // Proposals and governances both start with the account they belong to
+    let proposals: Vec<Pubkey> = records.iter().map(|(_, record)| record.proposal).collect();
+    let governances: Vec<Option<Pubkey>> = parent_accounts(client, &proposals)?;
+    let known_governances: Vec<Pubkey> = governances.iter().flatten().copied().collect();
+    let mut realms = parent_accounts(client, &known_governances)?.into_iter();
+
+    let votes = records
+        .into_iter()
+        .zip(governances)
+        .map(|((vote_record, record), governance)| GovernanceVote {
+            vote_record,
+            record,
+            governance,
+            realm: governance.and_then(|_| realms.next().flatten()),
+        })
+        .collect();
+    Ok(votes)
+}
+
+fn get_owner_accounts(client: &RpcClient, program_id: &Pubkey, owner: &Pubkey, owner_offset: usize) -> Result<Vec<(Pubkey, Account)>, ReadTransactionError> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(owner_offset, owner.to_bytes().to_vec()))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    Ok(client.get_program_accounts_with_config(program_id, config)?)
+}
+
+/// The account stored after the account type of each account, `None` for closed accounts.
+fn parent_accounts(client: &RpcClient, pubkeys: &[Pubkey]) -> Result<Vec<Option<Pubkey>>, ReadTransactionError> {
+    if pubkeys.is_empty() {
+        return Ok(vec![]);
+    }
+    let accounts = client.get_multiple_accounts(pubkeys)?;
+    Ok(accounts.into_iter().map(|account| read_pubkey(&account?.data, 1).ok()).collect())
+}
+
+/// Reads the name of a V1 or V2 realm.
+fn realm_name(data: &[u8]) -> Option<String> {
+    if data.first() != Some(&REALM_V1) && data.first() != Some(&REALM_V2) {
+        return None;
+    }
+    // account type, community mint, legacy fields, reserved and min weight to create a governance
+    let mut remaining_data = data.get(1 + 32 + 2 + 6 + 8..)?;
+    // max voter weight source, an enum with a u64
+    remaining_data = remaining_data.get(1 + 8..)?;
+    Option::<Pubkey>::deserialize(&mut remaining_data).ok()?;
+    remaining_data = remaining_data.get(6 + 2..)?;
+    Option::<Pubkey>::deserialize(&mut remaining_data).ok()?;
+    String::deserialize(&mut remaining_data).ok()
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey, ReadTransactionError> {
+    data.get(offset..offset + 32)
+        .and_then(|bytes| Pubkey::try_from(bytes).ok())
+        .ok_or(ReadTransactionError::DeserializeError)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64, ReadTransactionError> {
+    data.get(offset..offset + 8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(ReadTransactionError::DeserializeError)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::program_option::COption;
+    use crate::{
+        constants::{governance_accounts::spl_governance_program, solana_programs::token_program},
+        test_utils::{account_with_data, mock_client, multiple_accounts_response, program_accounts_response}
+    };
+
+    fn token_owner_record_data(realm: &Pubkey, mint: &Pubkey, owner: &Pubkey, deposit_amount: u64, delegate: Option<&Pubkey>) -> Vec<u8> {
+        let mut data = vec![TOKEN_OWNER_RECORD_V2];
+        data.extend_from_slice(realm.as_ref());
+        data.extend_from_slice(mint.as_ref());
+        data.extend_from_slice(owner.as_ref());
+        data.extend_from_slice(&deposit_amount.to_le_bytes());
+        data.extend_from_slice(&2_u64.to_le_bytes());
+        data.extend_from_slice(&[1, 1, 0, 0, 0, 0, 0, 0]);
+        match delegate {
+            Some(delegate) => {
+                data.push(1);
+                data.extend_from_slice(delegate.as_ref());
+            }
+            None => data.push(0),
+        }
+        data.extend_from_slice(&[0; 124]);
+        data
+    }
+
+    fn realm_data(name: &str) -> Vec<u8> {
+        let mut data = vec![REALM_V2];
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(&[0; 2 + 6 + 8]);
+        data.push(0);
+        data.extend_from_slice(&10_000_000_000_u64.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(&[0; 6 + 2]);
+        data.push(0);
+        data.extend(borsh::to_vec(name).unwrap());
+        data.extend_from_slice(&[0; 128]);
+        data
+    }
+
+    #[test]
+    fn test_get_governance_deposits() {
+        let (realm, mint, wallet, delegate) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let program_id = spl_governance_program();
+        let record_address = derive_token_owner_record(&program_id, &realm, &mint, &wallet);
+        let record = account_with_data(program_id, token_owner_record_data(&realm, &mint, &wallet, 2_500_000, Some(&delegate)));
+        let mint_state = Mint { mint_authority: COption::None, supply: 1, decimals: 6, is_initialized: true, freeze_authority: COption::None };
+        let mut mint_data = vec![0; Mint::LEN];
+        mint_state.pack_into_slice(&mut mint_data);
+
+        let client = mock_client(vec![
+            (RpcRequest::GetProgramAccounts, program_accounts_response(&[(record_address, record)])),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(account_with_data(program_id, realm_data("Mango DAO")))], 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(account_with_data(token_program(), mint_data))], 1)),
+        ]);
+
+        let deposits = get_governance_deposits(&client, &wallet.to_string(), &program_id).unwrap();
+        assert!(deposits.len() == 1 && deposits[0].token_owner_record == record_address);
+        assert!(deposits[0].realm_name.as_deref() == Some("Mango DAO") && deposits[0].ui_amount == Some(2.5));
+        let record = &deposits[0].record;
+        assert!(record.realm == realm && record.deposit_amount == 2_500_000 && record.unrelinquished_votes == 2);
+        assert!(record.outstanding_proposals == 1 && record.governance_delegate == Some(delegate));
+    }
+
+    #[test]
+    fn test_vote_record_versions() {
+        let (proposal, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = vec![VOTE_RECORD_V2];
+        data.extend_from_slice(proposal.as_ref());
+        data.extend_from_slice(owner.as_ref());
+        data.push(1);
+        data.extend_from_slice(&1_000_u64.to_le_bytes());
+        data.extend_from_slice(&[3, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let vote_record = VoteRecord::try_from_account_data(&data).unwrap();
+        assert!(vote_record.proposal == proposal && vote_record.governing_token_owner == owner);
+        assert!(vote_record.is_relinquished && vote_record.voter_weight == 1_000 && vote_record.vote == GovernanceVoteKind::Veto);
+
+        let mut data = vec![VOTE_RECORD_V1];
+        data.extend_from_slice(proposal.as_ref());
+        data.extend_from_slice(owner.as_ref());
+        data.extend_from_slice(&[0, 1]);
+        data.extend_from_slice(&500_u64.to_le_bytes());
+        let vote_record = VoteRecord::try_from_account_data(&data).unwrap();
+        assert!(!vote_record.is_relinquished && vote_record.voter_weight == 500 && vote_record.vote == GovernanceVoteKind::Deny);
+
+        assert!(VoteRecord::try_from_account_data(&[TOKEN_OWNER_RECORD_V2; 200]).is_err());
+    }
+}
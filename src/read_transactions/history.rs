@@ -0,0 +1,430 @@
+//! # History
+//!
+//! This module pages through the signatures of an address and fetches its transactions,
+//! the building block of analyses over on-chain history. Transactions can be fetched as
+//! `TransactionRecord`s, with their balance changes and decoded instructions, to audit what
+//! happened to a wallet. It also extracts the SOL transfers of inspected transactions.
+
+use std::str::FromStr;
+use solana_client::{
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+    rpc_response::RpcConfirmedTransactionStatusWithSignature
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature, transaction::TransactionError};
+use solana_system_interface::instruction::SystemInstruction;
+use solana_transaction_status_client_types::option_serializer::OptionSerializer;
+use crate::error::ReadTransactionError;
+use super::{
+    instruction_decoder::DecodedInstruction,
+    token_transfers::{token_balance_changes, TokenBalanceChange},
+    transaction::{fetch_transaction, get_transaction, inspect_transaction, InspectedInnerInstructions, InspectedInstruction, InspectedTransaction}
+};
+
+/// Maximum number of signatures returned by a single `getSignaturesForAddress` request.
+pub(crate) const SIGNATURES_PAGE_SIZE: usize = 1_000;
+
+/// Range of the history of an address to read. The default reads the latest 1,000
+/// successful transactions.
+///
+/// ### Fields
+///
+/// - `limit`: Maximum number of signatures returned, `None` to read the whole history.
+/// - `start_time`: Unix timestamp of the oldest transaction returned, paging stops once older transactions are reached.
+/// - `end_time`: Unix timestamp of the newest transaction returned, newer transactions are skipped.
+/// - `include_failed`: Whether failed transactions are returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryConfig {
+    pub limit: Option<usize>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub include_failed: bool,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            limit: Some(1_000),
+            start_time: None,
+            end_time: None,
+            include_failed: false,
+        }
+    }
+}
+
+/// Signature of a transaction involving an address.
+///
+/// ### Fields
+///
+/// - `signature`: Signature of the transaction.
+/// - `slot`: The slot the transaction was confirmed in.
+/// - `block_time`: Estimated unix timestamp of the block, if available.
+/// - `is_failed`: Whether the transaction failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureInfo {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub is_failed: bool,
+}
+
+impl From<RpcConfirmedTransactionStatusWithSignature> for SignatureInfo {
+    fn from(status: RpcConfirmedTransactionStatusWithSignature) -> Self {
+        Self {
+            signature: status.signature,
+            slot: status.slot,
+            block_time: status.block_time,
+            is_failed: status.err.is_some(),
+        }
+    }
+}
+
+/// A SOL transfer between two accounts, including the lamports used to fund new accounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolTransfer {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub lamports: u64,
+}
+
+/// SOL balance of an account before and after a transaction, in lamports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolBalance {
+    pub account: Pubkey,
+    pub pre_balance: u64,
+    pub post_balance: u64,
+}
+
+impl SolBalance {
+    /// Lamports gained by the account, negative if it lost lamports.
+    pub fn change(&self) -> i128 {
+        self.post_balance as i128 - self.pre_balance as i128
+    }
+}
+
+/// A confirmed transaction with its balance changes and instructions.
+///
+/// ### Fields
+///
+/// - `signature`: The first signature of the transaction.
+/// - `slot`: The slot the transaction was confirmed in.
+/// - `block_time`: Estimated unix timestamp of the block, if available.
+/// - `fee`: Fee paid in lamports.
+/// - `error`: The transaction error if the transaction failed.
+/// - `sol_balances`: SOL balances of every account of the transaction, in the order of its account keys.
+/// - `token_balance_changes`: Net token balance change of each wallet and token, unchanged balances are left out.
+/// - `instructions`: Top level instructions of the transaction.
+/// - `inner_instructions`: Instructions invoked by the top level instructions (CPIs).
+#[derive(Debug, Clone)]
+pub struct TransactionRecord {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub fee: u64,
+    pub error: Option<TransactionError>,
+    pub sol_balances: Vec<SolBalance>,
+    pub token_balance_changes: Vec<TokenBalanceChange>,
+    pub instructions: Vec<InspectedInstruction>,
+    pub inner_instructions: Vec<InspectedInnerInstructions>,
+}
+
+impl TransactionRecord {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Lamports gained by `account`, fees included, `None` if the account is not part of the transaction.
+    pub fn sol_change(&self, account: &Pubkey) -> Option<i128> {
+        self.sol_balances
+            .iter()
+            .find(|balance| balance.account == *account)
+            .map(SolBalance::change)
+    }
+
+    /// Top level instructions decoded according to their program, see `decode_instruction`.
+    pub fn decoded_instructions(&self) -> Vec<DecodedInstruction<'_>> {
+        self.instructions.iter().map(InspectedInstruction::decode).collect()
+    }
+}
+
+/// Gets the signatures of the transactions involving `address` within the range of `config`,
+/// newest first. Signatures are requested in pages of 1,000 until the limit, the start time
+/// or the first transaction of the address is reached.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InvalidAddress` if the address is invalid, or
+/// `ReadTransactionError::RpcError` if a page can not be fetched.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_client::rpc_client::RpcClient;
+/// use easy_solana::read_transactions::history::{get_signature_history, HistoryConfig};
+///
+/// let client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+/// let config = HistoryConfig { limit: Some(100), ..Default::default() };
+/// let signatures = get_signature_history(&client, "So11111111111111111111111111111111111111112", &config).unwrap();
+/// ```
+pub fn get_signature_history(client: &RpcClient, address: &str, config: &HistoryConfig) -> Result<Vec<SignatureInfo>, ReadTransactionError> {
+    let address = Pubkey::from_str(address)?;
+    let limit = config.limit.unwrap_or(usize::MAX);
+    let mut signatures = Vec::new();
+    let mut before = None;
+
+    while signatures.len() < limit {
+        let page = client.get_signatures_for_address_with_config(
+            &address,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some(SIGNATURES_PAGE_SIZE),
+                commitment: Some(CommitmentConfig::confirmed()),
+            }
+        )?;
+        let is_last_page = page.len() < SIGNATURES_PAGE_SIZE;
+        before = match page.last() {
+            Some(status) => Some(Signature::from_str(&status.signature).map_err(|_| ReadTransactionError::InvalidSignature)?),
+            None => break,
+        };
+
+        for signature_info in page.into_iter().map(SignatureInfo::from) {
+            let block_time = signature_info.block_time;
+            if config.start_time.is_some_and(|start_time| block_time.is_some_and(|block_time| block_time < start_time)) {
+                return Ok(signatures);
+            }
+            let is_too_new = config.end_time.is_some_and(|end_time| block_time.is_some_and(|block_time| block_time > end_time));
+            if is_too_new || (signature_info.is_failed && !config.include_failed) {
+                continue;
+            }
+            signatures.push(signature_info);
+            if signatures.len() == limit {
+                break;
+            }
+        }
+        if is_last_page {
+            break;
+        }
+    }
+
+    Ok(signatures)
+}
+
+/// Fetches the transactions involving `address` within the range of `config`, oldest first.
+/// Every transaction is fetched with its own request, so large ranges are slow.
+///
+/// ## Errors
+///
+/// Returns an error if the signatures or a transaction can not be fetched.
+pub fn get_transaction_history(client: &RpcClient, address: &str, config: &HistoryConfig) -> Result<Vec<InspectedTransaction>, ReadTransactionError> {
+    let signatures = get_signature_history(client, address, config)?;
+    signatures
+        .iter()
+        .rev()
+        .map(|signature_info| get_transaction(client, &signature_info.signature))
+        .collect()
+}
+
+/// Fetches a confirmed transaction as a `TransactionRecord`, with the SOL balances of its
+/// accounts, the token balance changes of each wallet and its instructions. Instructions of the
+/// System, SPL Token and pump.fun programs can be decoded with `decoded_instructions`.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InvalidSignature` if the signature is invalid,
+/// `ReadTransactionError::RpcError` if the transaction can not be fetched, or
+/// `ReadTransactionError::DeserializeError` if it can not be decoded.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, read_transactions::history::get_parsed_transaction};
+///
+/// let client = create_rpc_client("RPC_URL");
+/// let signature = "5UfDuX7WXY8jpTgsKAxVyx7Ta8ZbxEcnYYrpD2E3cRsAs9N7RsEbCa4qQNvM7JPdkuMSbMR1ybBQvT6x8mE6Luix";
+/// let record = get_parsed_transaction(&client, signature).unwrap();
+/// for instruction in record.decoded_instructions() {
+///     println!("{:?}", instruction);
+/// }
+/// ```
+pub fn get_parsed_transaction(client: &RpcClient, signature: &str) -> Result<TransactionRecord, ReadTransactionError> {
+    let signature = Signature::from_str(signature).map_err(|_| ReadTransactionError::InvalidSignature)?;
+    let confirmed_transaction = fetch_transaction(client, &signature)?;
+    let meta = confirmed_transaction.transaction.meta
+        .as_ref()
+        .ok_or(ReadTransactionError::DeserializeError)?;
+    let (pre_balances, post_balances) = (meta.pre_balances.clone(), meta.post_balances.clone());
+    let token_balance_changes = match (&meta.pre_token_balances, &meta.post_token_balances) {
+        (OptionSerializer::Some(pre_token_balances), OptionSerializer::Some(post_token_balances)) => {
+            token_balance_changes(pre_token_balances, post_token_balances)?
+        }
+        _ => vec![],
+    };
+
+    let transaction = inspect_transaction(client, &signature, confirmed_transaction)?;
+    let sol_balances = transaction.account_keys
+        .iter()
+        .zip(pre_balances.into_iter().zip(post_balances))
+        .map(|(account, (pre_balance, post_balance))| SolBalance { account: *account, pre_balance, post_balance })
+        .collect();
+
+    Ok(TransactionRecord {
+        signature: transaction.signature,
+        slot: transaction.slot,
+        block_time: transaction.block_time,
+        fee: transaction.fee,
+        error: transaction.error,
+        sol_balances,
+        token_balance_changes,
+        instructions: transaction.instructions,
+        inner_instructions: transaction.inner_instructions,
+    })
+}
+
+/// Fetches the transactions involving `address` within the range of `config` as
+/// `TransactionRecord`s, oldest first. Every transaction is fetched with its own request.
+///
+/// ## Errors
+///
+/// Returns an error if the signatures or a transaction can not be fetched.
+pub fn get_transaction_records(client: &RpcClient, address: &str, config: &HistoryConfig) -> Result<Vec<TransactionRecord>, ReadTransactionError> {
+    let signatures = get_signature_history(client, address, config)?;
+    signatures
+        .iter()
+        .rev()
+        .map(|signature_info| get_parsed_transaction(client, &signature_info.signature))
+        .collect()
+}
+
+/// Extracts the SOL transfers of a transaction, from top level and inner System Program
+/// instructions, in the order they were executed. Failed transactions have no transfers.
+pub fn sol_transfers_in_transaction(transaction: &InspectedTransaction) -> Vec<SolTransfer> {
+    if transaction.error.is_some() {
+        return vec![];
+    }
+    let mut transfers = Vec::new();
+    for (index, instruction) in transaction.instructions.iter().enumerate() {
+        transfers.extend(sol_transfer(instruction));
+        let inner_instructions = transaction.inner_instructions
+            .iter()
+            .filter(|inner_instructions| inner_instructions.index as usize == index)
+            .flat_map(|inner_instructions| &inner_instructions.instructions);
+        transfers.extend(inner_instructions.filter_map(sol_transfer));
+    }
+    transfers
+}
+
+fn sol_transfer(instruction: &InspectedInstruction) -> Option<SolTransfer> {
+    let (from_index, to_index, lamports) = match instruction.decode() {
+        DecodedInstruction::System(SystemInstruction::Transfer { lamports }) => (0, 1, lamports),
+        DecodedInstruction::System(SystemInstruction::CreateAccount { lamports, .. }) => (0, 1, lamports),
+        DecodedInstruction::System(SystemInstruction::CreateAccountWithSeed { lamports, .. }) => (0, 1, lamports),
+        DecodedInstruction::System(SystemInstruction::TransferWithSeed { lamports, .. }) => (0, 2, lamports),
+        _ => return None,
+    };
+    Some(SolTransfer {
+        from: *instruction.accounts.get(from_index)?,
+        to: *instruction.accounts.get(to_index)?,
+        lamports,
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use serde_json::json;
+    use solana_sdk::transaction::Transaction;
+    use crate::{
+        constants::solana_programs::token_program,
+        pumpfun::instructions::pump_buy_instruction,
+        read_transactions::instruction_decoder::PumpFunInstruction,
+        test_utils::{mock_client, signatures_for_address_response, transaction_response}
+    };
+
+    #[test]
+    fn test_get_signature_history_filters_range() {
+        let signatures: Vec<String> = (0..4).map(|_| Signature::new_unique().to_string()).collect();
+        let response = signatures_for_address_response(&[
+            (&signatures[0], 40, Some(400), false),
+            (&signatures[1], 30, Some(300), true),
+            (&signatures[2], 20, Some(200), false),
+            (&signatures[3], 10, Some(100), false),
+        ]);
+        let client = mock_client(vec![(RpcRequest::GetSignaturesForAddress, response)]);
+
+        let config = HistoryConfig { start_time: Some(150), end_time: Some(350), ..Default::default() };
+        let history = get_signature_history(&client, &Pubkey::new_unique().to_string(), &config).unwrap();
+        assert!(history.len() == 1 && history[0].signature == signatures[2]);
+    }
+
+    #[test]
+    fn test_get_parsed_transaction() {
+        let (payer, mint, recipient) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let transaction = Transaction::new_with_payer(
+            &[
+                solana_system_interface::instruction::transfer(&payer, &recipient, 1_000),
+                pump_buy_instruction(&payer, &mint, &Pubkey::new_unique(), 500, 2_000),
+            ],
+            Some(&payer)
+        );
+        let account_keys = transaction.message.account_keys.clone();
+        let token_balance = |amount: u64| json!({
+            "accountIndex": 3,
+            "mint": mint.to_string(),
+            "owner": payer.to_string(),
+            "programId": token_program().to_string(),
+            "uiTokenAmount": { "amount": amount.to_string(), "decimals": 6, "uiAmount": null, "uiAmountString": "0" }
+        });
+        let mut response = transaction_response(&transaction, 7, Some(700), &[]);
+        response["meta"]["preBalances"] = json!(account_keys.iter().map(|_| 10_000).collect::<Vec<u64>>());
+        response["meta"]["postBalances"] = json!(account_keys.iter().map(|key| if *key == payer { 6_000 } else { 10_000 }).collect::<Vec<u64>>());
+        response["meta"]["preTokenBalances"] = json!([]);
+        response["meta"]["postTokenBalances"] = json!([token_balance(500)]);
+        let client = mock_client(vec![(RpcRequest::GetTransaction, response)]);
+
+        let record = get_parsed_transaction(&client, &Signature::new_unique().to_string()).unwrap();
+        assert!(record.is_success() && record.slot == 7 && record.block_time == Some(700) && record.fee == 5_000);
+        assert!(record.sol_balances.len() == account_keys.len());
+        assert!(record.sol_change(&payer) == Some(-4_000) && record.sol_change(&recipient) == Some(0));
+        assert!(record.sol_change(&Pubkey::new_unique()).is_none());
+        assert!(record.token_balance_changes.len() == 1 && record.token_balance_changes[0].change == 500);
+
+        let decoded_instructions = record.decoded_instructions();
+        assert!(matches!(decoded_instructions[0], DecodedInstruction::System(SystemInstruction::Transfer { lamports: 1_000 })));
+        assert!(decoded_instructions[1] == DecodedInstruction::PumpFun(PumpFunInstruction::Buy { token_amount: 500, max_sol_cost: 2_000 }));
+    }
+
+    #[test]
+    fn test_sol_transfers_in_transaction() {
+        let (wallet, funder, new_account) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = |instruction: solana_sdk::instruction::Instruction| InspectedInstruction {
+            program_id: instruction.program_id,
+            accounts: instruction.accounts.iter().map(|account| account.pubkey).collect(),
+            data: instruction.data,
+            stack_height: None,
+        };
+        let create_account = solana_system_interface::instruction::create_account(&wallet, &new_account, 2_000, 0, &Pubkey::new_unique());
+        let mut transaction = InspectedTransaction {
+            signature: String::new(),
+            slot: 1,
+            block_time: None,
+            is_versioned: false,
+            account_keys: vec![],
+            instructions: vec![instruction(solana_system_interface::instruction::transfer(&funder, &wallet, 5_000))],
+            inner_instructions: vec![InspectedInnerInstructions { index: 0, instructions: vec![instruction(create_account)] }],
+            logs: vec![],
+            fee: 5_000,
+            error: None,
+        };
+
+        let transfers = sol_transfers_in_transaction(&transaction);
+        assert!(transfers == vec![
+            SolTransfer { from: funder, to: wallet, lamports: 5_000 },
+            SolTransfer { from: wallet, to: new_account, lamports: 2_000 },
+        ]);
+
+        transaction.error = Some(solana_sdk::transaction::TransactionError::AccountNotFound);
+        assert!(sol_transfers_in_transaction(&transaction).is_empty());
+    }
+}
@@ -0,0 +1,196 @@
+//! # Instruction Decoder
+//!
+//! This module decodes raw instruction data of the common Solana programs and pump.fun into
+//! typed instructions. It is used by both the transaction inspector and the simulator.
+
+use borsh::BorshDeserialize;
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_system_interface::instruction::SystemInstruction;
+use spl_associated_token_account::instruction::AssociatedTokenAccountInstruction;
+use spl_token::instruction::TokenInstruction;
+use spl_token_2022::instruction::TokenInstruction as Token2022Instruction;
+
+use crate::constants::{
+    pumpfun_accounts::{buy_instruction_data, pumpfun_program, sell_instruction_data, CREATE_INSTRUCTION_DISCRIMINATOR},
+    solana_programs::{associated_token_account_program, compute_budget_program, system_program, token_2022_program, token_program}
+};
+use super::{cpi_tree::CpiNode, transaction::InspectedInstruction};
+
+/// Instruction decoded according to the program it calls.
+///
+/// - System: SOL transfers, account creation and allocation.
+/// - Token: SPL token instructions such as transfers, mints, burns and closing accounts.
+/// - Token2022: Token 2022 instructions, including extension instructions.
+/// - AssociatedTokenAccount: creating associated token accounts.
+/// - ComputeBudget: compute limit and priority fee instructions.
+/// - PumpFun: pump.fun buys, sells and token creations.
+/// - Unknown: instructions of other programs, or data that could not be decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodedInstruction<'a> {
+    System(SystemInstruction),
+    Token(TokenInstruction<'a>),
+    Token2022(Token2022Instruction<'a>),
+    AssociatedTokenAccount(AssociatedTokenAccountInstruction),
+    ComputeBudget(ComputeBudgetInstruction),
+    PumpFun(PumpFunInstruction),
+    Unknown,
+}
+
+/// Instructions of the pump.fun program, amounts are in lamports or raw token units.
+///
+/// - Buy: buys `token_amount` tokens for at most `max_sol_cost`, fees included.
+/// - Sell: sells `token_amount` tokens for at least `min_sol_output`, after fees.
+/// - Create: creates a token and its bonding curve.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PumpFunInstruction {
+    Buy { token_amount: u64, max_sol_cost: u64 },
+    Sell { token_amount: u64, min_sol_output: u64 },
+    Create { name: String, symbol: String, uri: String },
+}
+
+/// Decodes instruction data of the System, SPL Token, Token 2022, Associated Token Account,
+/// Compute Budget and pump.fun programs. Instructions of any other program, or invalid instruction
+/// data, are returned as `DecodedInstruction::Unknown`.
+///
+/// ## Example
+///
+/// ```rust
+/// use solana_compute_budget_interface::ComputeBudgetInstruction;
+/// use easy_solana::read_transactions::instruction_decoder::{decode_instruction, DecodedInstruction};
+///
+/// let instruction = ComputeBudgetInstruction::set_compute_unit_limit(1_000_000);
+/// let decoded_instruction = decode_instruction(&instruction.program_id, &instruction.data);
+/// assert!(matches!(decoded_instruction, DecodedInstruction::ComputeBudget(_)));
+/// ```
+pub fn decode_instruction<'a>(program_id: &Pubkey, data: &'a [u8]) -> DecodedInstruction<'a> {
+    let decoded_instruction = if *program_id == system_program() {
+        bincode::deserialize(data).ok().map(DecodedInstruction::System)
+    } else if *program_id == token_program() {
+        TokenInstruction::unpack(data).ok().map(DecodedInstruction::Token)
+    } else if *program_id == token_2022_program() {
+        Token2022Instruction::unpack(data).ok().map(DecodedInstruction::Token2022)
+    } else if *program_id == associated_token_account_program() {
+        // Empty instruction data is the original create instruction
+        if data.is_empty() {
+            Some(DecodedInstruction::AssociatedTokenAccount(AssociatedTokenAccountInstruction::Create))
+        } else {
+            AssociatedTokenAccountInstruction::try_from_slice(data).ok().map(DecodedInstruction::AssociatedTokenAccount)
+        }
+    } else if *program_id == compute_budget_program() {
+        ComputeBudgetInstruction::try_from_slice(data).ok().map(DecodedInstruction::ComputeBudget)
+    } else if *program_id == pumpfun_program() {
+        decode_pumpfun_instruction(data).map(DecodedInstruction::PumpFun)
+    } else {
+        None
+    };
+
+    decoded_instruction.unwrap_or(DecodedInstruction::Unknown)
+}
+
+fn decode_pumpfun_instruction(data: &[u8]) -> Option<PumpFunInstruction> {
+    let (discriminator, mut args) = data.split_at_checked(8)?;
+    let amount = |offset: usize| args.get(offset..offset + 8).and_then(|bytes| bytes.try_into().ok()).map(u64::from_le_bytes);
+
+    if discriminator == buy_instruction_data() {
+        Some(PumpFunInstruction::Buy { token_amount: amount(0)?, max_sol_cost: amount(8)? })
+    } else if discriminator == sell_instruction_data() {
+        Some(PumpFunInstruction::Sell { token_amount: amount(0)?, min_sol_output: amount(8)? })
+    } else if discriminator == CREATE_INSTRUCTION_DISCRIMINATOR {
+        // The strings are followed by the creator, which is also an account of the instruction
+        Some(PumpFunInstruction::Create {
+            name: String::deserialize(&mut args).ok()?,
+            symbol: String::deserialize(&mut args).ok()?,
+            uri: String::deserialize(&mut args).ok()?,
+        })
+    } else {
+        None
+    }
+}
+
+impl InspectedInstruction {
+    /// Decodes the instruction data according to its program.
+    pub fn decode(&self) -> DecodedInstruction<'_> {
+        decode_instruction(&self.program_id, &self.data)
+    }
+}
+
+impl CpiNode {
+    /// Decodes the instruction data according to its program.
+    pub fn decode(&self) -> DecodedInstruction<'_> {
+        decode_instruction(&self.program_id, &self.data)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signer::{keypair::Keypair, Signer};
+    use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+    #[test]
+    fn test_decode_system_transfer() {
+        let from_pubkey = Pubkey::new_unique();
+        let to_pubkey = Pubkey::new_unique();
+        let instruction = solana_system_interface::instruction::transfer(&from_pubkey, &to_pubkey, 1_000);
+        match decode_instruction(&instruction.program_id, &instruction.data) {
+            DecodedInstruction::System(SystemInstruction::Transfer { lamports }) => assert!(lamports == 1_000),
+            decoded_instruction => panic!("Expected system transfer, but got {:?}", decoded_instruction),
+        }
+    }
+
+    #[test]
+    fn test_decode_token_instructions() {
+        let owner = Keypair::new().pubkey();
+        let mint = Pubkey::new_unique();
+        let token_account = Pubkey::new_unique();
+
+        let burn_instruction = spl_token::instruction::burn(&token_program(), &token_account, &mint, &owner, &[], 500).unwrap();
+        assert!(burn_instruction.data.len() == 9);
+        assert!(matches!(
+            decode_instruction(&burn_instruction.program_id, &burn_instruction.data),
+            DecodedInstruction::Token(TokenInstruction::Burn { amount: 500 })
+        ));
+
+        let close_instruction = spl_token_2022::instruction::close_account(&token_2022_program(), &token_account, &owner, &owner, &[]).unwrap();
+        assert!(matches!(
+            decode_instruction(&close_instruction.program_id, &close_instruction.data),
+            DecodedInstruction::Token2022(Token2022Instruction::CloseAccount)
+        ));
+
+        let create_instruction = create_associated_token_account_idempotent(&owner, &owner, &mint, &token_program());
+        assert!(matches!(
+            decode_instruction(&create_instruction.program_id, &create_instruction.data),
+            DecodedInstruction::AssociatedTokenAccount(AssociatedTokenAccountInstruction::CreateIdempotent)
+        ));
+    }
+
+    #[test]
+    fn test_decode_pumpfun_instructions() {
+        let (user, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let buy_instruction = crate::pumpfun::instructions::pump_buy_instruction(&user, &mint, &Pubkey::new_unique(), 1_000, 2_000);
+        assert!(decode_instruction(&buy_instruction.program_id, &buy_instruction.data)
+            == DecodedInstruction::PumpFun(PumpFunInstruction::Buy { token_amount: 1_000, max_sol_cost: 2_000 }));
+
+        let sell_instruction = crate::pumpfun::instructions::pump_sell_instruction(&user, &mint, &Pubkey::new_unique(), 1_000, 500);
+        assert!(decode_instruction(&sell_instruction.program_id, &sell_instruction.data)
+            == DecodedInstruction::PumpFun(PumpFunInstruction::Sell { token_amount: 1_000, min_sol_output: 500 }));
+
+        let create_instruction = crate::pumpfun::create::pump_create_instruction(&user, &mint, "Token", "TKN", "https://example.com");
+        let create = PumpFunInstruction::Create { name: "Token".to_string(), symbol: "TKN".to_string(), uri: "https://example.com".to_string() };
+        assert!(decode_instruction(&create_instruction.program_id, &create_instruction.data) == DecodedInstruction::PumpFun(create));
+
+        // truncated amounts
+        assert!(decode_instruction(&pumpfun_program(), &buy_instruction.data[..20]) == DecodedInstruction::Unknown);
+    }
+
+    #[test]
+    fn test_decode_unknown_instruction() {
+        let decoded_instruction = decode_instruction(&Pubkey::new_unique(), &[1, 2, 3]);
+        assert!(decoded_instruction == DecodedInstruction::Unknown);
+        // invalid data of a known program
+        let decoded_instruction = decode_instruction(&token_program(), &[255]);
+        assert!(decoded_instruction == DecodedInstruction::Unknown);
+    }
+}
@@ -28,6 +28,24 @@ use crate::{
      pub uri: String,
  }
 
+/// First byte of metadata accounts, the `MetadataV1` variant of the metaplex `Key` enum.
+const METADATA_ACCOUNT_KEY: u8 = 4;
+
+impl MetadataAccount {
+    /// Deserializes metadata account data after checking its key byte.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::WrongAccountType` if the account is not a metadata account,
+    /// or `ReadTransactionError::DeserializeError` if the data does not match the schema.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, ReadTransactionError> {
+        if data.first() != Some(&METADATA_ACCOUNT_KEY) {
+            return Err(ReadTransactionError::WrongAccountType("metadata".to_string()));
+        }
+        MetadataAccount::deserialize(&mut &data[..]).map_err(|_| ReadTransactionError::DeserializeError)
+    }
+}
+
 
 /// Fetches the metadata account given a token address, deserializing their data and returning `MetadataAccount`. 
 /// Paddings in token name, symbol and uri are trimmed.
@@ -43,9 +61,7 @@ pub fn get_metadata_of_token(client: &RpcClient, token_address: &str) -> Result<
     let metadata_account = client.get_account(&metadata_pubkey)?;
 
     // Deserialize account data
-    let mut deserialized_metadata_account = 
-        MetadataAccount::deserialize(&mut metadata_account.data.as_ref())
-        .map_err(|_| ReadTransactionError::DeserializeError)?;
+    let mut deserialized_metadata_account = MetadataAccount::try_from_account_data(&metadata_account.data)?;
 
     // Trim paddings
     deserialized_metadata_account.data.name = deserialized_metadata_account.data.name.trim_end_matches('\0').to_string();
@@ -81,7 +97,7 @@ pub fn get_metadata_of_tokens(client: &RpcClient, token_addresses: Vec<&str>) ->
         .into_iter()
         .flatten()
         .filter_map(|account| {
-            let mut metadata_account = MetadataAccount::deserialize(&mut account.data.as_ref()).ok()?;
+            let mut metadata_account = MetadataAccount::try_from_account_data(&account.data).ok()?;
             metadata_account.data.name = metadata_account.data.name.trim_end_matches('\0').to_string();
             metadata_account.data.symbol = metadata_account.data.symbol.trim_end_matches('\0').to_string();
             metadata_account.data.uri = metadata_account.data.uri.trim_end_matches('\0').to_string();
@@ -104,6 +120,27 @@ mod tests {
     const MIRACOLI_MINT_ADDRESS: &str = "FafEz1HqZwzoNJ626HY8ZNBi2NwUYJE1tVn173rjpump";
     const WALLET_ADDRESS: &str = "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5";
     
+    #[test]
+    fn test_metadata_account_key_validation() {
+        let metadata_account = MetadataAccount {
+            key: METADATA_ACCOUNT_KEY,
+            update_authority: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            data: Metadata { name: "Token".to_string(), symbol: "TKN".to_string(), uri: "".to_string() },
+            primary_sale_happened: false,
+            is_mutable: true,
+        };
+        let mut data = borsh::to_vec(&metadata_account).unwrap();
+        // metadata accounts are padded with trailing bytes
+        data.extend_from_slice(&[0; 64]);
+        let deserialized_metadata_account = MetadataAccount::try_from_account_data(&data).unwrap();
+        assert!(deserialized_metadata_account.mint == metadata_account.mint);
+
+        data[0] = 1;
+        let result = MetadataAccount::try_from_account_data(&data);
+        assert!(matches!(result, Err(ReadTransactionError::WrongAccountType(_))));
+    }
+
     #[test]
     fn test_get_metadata_of_token() {
         let client = create_rpc_client("RPC_URL");
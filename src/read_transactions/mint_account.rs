@@ -1,4 +1,3 @@
-use solana_sdk::program_pack::Pack;
 use solana_client::rpc_client::RpcClient;
 use spl_token::state::Mint as SplMintAccount;
 
@@ -6,13 +5,13 @@ use crate::{
     utils::{address_to_pubkey, addresses_to_pubkeys},
     error::ReadTransactionError
 };
+use super::token_extensions::unpack_mint;
 
 
 pub fn get_mint_account(client: &RpcClient, token_address: &str) -> Result<SplMintAccount, ReadTransactionError> {
     let token_pubkey = address_to_pubkey(token_address)?;
     let token_account = client.get_account(&token_pubkey)?;
-    let mint_data = SplMintAccount::unpack(&token_account.data)
-        .map_err(|_| ReadTransactionError::DeserializeError)?; 
+    let (mint_data, _) = unpack_mint(&token_account.owner, &token_account.data)?;
     
     Ok(mint_data)
 }
@@ -24,7 +23,8 @@ pub fn get_multiple_mint_accounts(client: &RpcClient, token_addresses: Vec<&str>
         .iter_mut()
         .filter_map(|account_option| {
             // Check if the account is Some and try to unpack it
-            account_option.as_mut().and_then(|account| SplMintAccount::unpack(&account.data).ok())
+            account_option.as_mut().and_then(|account| unpack_mint(&account.owner, &account.data).ok())
+                .map(|(mint_data, _)| mint_data)
         })
         .collect();
     
@@ -0,0 +1,246 @@
+//! # Mint Watcher
+//!
+//! This module subscribes to a token mint and raises events when its supply or authorities
+//! change, or when the mint is closed. Supports both SPL Token and Token 2022 mints.
+
+use futures_util::StreamExt;
+use log::warn;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_client::RpcClient,
+    rpc_config::RpcAccountInfoConfig
+};
+use solana_sdk::{account::Account as SolanaAccount, commitment_config::CommitmentConfig, pubkey::Pubkey};
+use spl_token_2022::{
+    extension::{mint_close_authority::MintCloseAuthority, BaseStateWithExtensions, StateWithExtensions},
+    state::Mint as Token2022Mint
+};
+use tokio::sync::mpsc::{self, Receiver};
+use crate::{
+    error::ReadTransactionError,
+    event_channel::{event_channel, ChannelConfig, EventReceiver, OverflowPolicy},
+    timestamps::{EventTimestamp, Timestamped},
+    utils::address_to_pubkey
+};
+
+/// Number of events buffered before the watcher waits for the receiver.
+const MINT_EVENT_BUFFER: usize = 32;
+
+/// State of a mint that is watched for changes.
+///
+/// ### Fields
+///
+/// - `supply`: Total supply of the token without decimals.
+/// - `mint_authority`: Account allowed to mint new tokens, `None` if minting is disabled.
+/// - `freeze_authority`: Account allowed to freeze token accounts.
+/// - `close_authority`: Account allowed to close the mint, only available for Token 2022 mints.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MintState {
+    pub supply: u64,
+    pub mint_authority: Option<Pubkey>,
+    pub freeze_authority: Option<Pubkey>,
+    pub close_authority: Option<Pubkey>,
+}
+
+impl MintState {
+    /// Reads the mint state from SPL Token or Token 2022 mint account data.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, ReadTransactionError> {
+        let mint = StateWithExtensions::<Token2022Mint>::unpack(data)
+            .map_err(|_| ReadTransactionError::WrongAccountType("Mint".to_string()))?;
+        let close_authority = mint
+            .get_extension::<MintCloseAuthority>()
+            .ok()
+            .and_then(|extension| Option::<Pubkey>::from(extension.close_authority));
+
+        Ok(Self {
+            supply: mint.base.supply,
+            mint_authority: mint.base.mint_authority.into(),
+            freeze_authority: mint.base.freeze_authority.into(),
+            close_authority,
+        })
+    }
+}
+
+/// Change of a watched mint.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MintEvent {
+    SupplyChanged { previous: u64, current: u64 },
+    MintAuthorityChanged { previous: Option<Pubkey>, current: Option<Pubkey> },
+    FreezeAuthorityChanged { previous: Option<Pubkey>, current: Option<Pubkey> },
+    CloseAuthorityChanged { previous: Option<Pubkey>, current: Option<Pubkey> },
+    Closed,
+}
+
+/// Compares two states of a mint, `current` is `None` if the mint was closed.
+pub fn mint_events(previous: &MintState, current: Option<&MintState>) -> Vec<MintEvent> {
+    let Some(current) = current else {
+        return vec![MintEvent::Closed];
+    };
+
+    let mut events = Vec::new();
+    if previous.supply != current.supply {
+        events.push(MintEvent::SupplyChanged { previous: previous.supply, current: current.supply });
+    }
+    if previous.mint_authority != current.mint_authority {
+        events.push(MintEvent::MintAuthorityChanged { previous: previous.mint_authority, current: current.mint_authority });
+    }
+    if previous.freeze_authority != current.freeze_authority {
+        events.push(MintEvent::FreezeAuthorityChanged { previous: previous.freeze_authority, current: current.freeze_authority });
+    }
+    if previous.close_authority != current.close_authority {
+        events.push(MintEvent::CloseAuthorityChanged { previous: previous.close_authority, current: current.close_authority });
+    }
+    events
+}
+
+/// Subscribes to a mint over the websocket endpoint `ws_url` and sends an event for every
+/// change of the mint. The initial state is fetched with `client`. The subscription ends after
+/// the mint is closed or the receiver is dropped.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::WrongAccountType` if the address is not a mint and
+/// `ReadTransactionError::RpcError` if the websocket connection fails.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, read_transactions::mint_watcher::watch_mint};
+///
+/// # async fn example() {
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let mut events = watch_mint(&client, "wss://api.mainnet-beta.solana.com", "CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump")
+///     .await
+///     .unwrap();
+/// while let Some(event) = events.recv().await {
+///     println!("{:?}", event);
+/// }
+/// # }
+/// ```
+pub async fn watch_mint(client: &RpcClient, ws_url: &str, token_address: &str) -> Result<Receiver<MintEvent>, ReadTransactionError> {
+    let mut events = watch_mint_with_channel(
+        client,
+        ws_url,
+        token_address,
+        ChannelConfig::new(MINT_EVENT_BUFFER, OverflowPolicy::Block)
+    ).await?;
+    let (sender, receiver) = mpsc::channel(MINT_EVENT_BUFFER);
+    tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            if sender.send(event.event).await.is_err() {
+                return;
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+/// Like `watch_mint`, buffering events in a channel following `channel`, e.g to keep only the
+/// latest supply change while the consumer is busy. Events carry the slot of the notification
+/// and the time it was received, see `SkewEstimator` to estimate the feed delay.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::WrongAccountType` if the address is not a mint and
+/// `ReadTransactionError::RpcError` if the websocket connection fails.
+pub async fn watch_mint_with_channel(
+    client: &RpcClient,
+    ws_url: &str,
+    token_address: &str,
+    channel: ChannelConfig<Timestamped<MintEvent>>
+) -> Result<EventReceiver<Timestamped<MintEvent>>, ReadTransactionError> {
+    let mint_pubkey = address_to_pubkey(token_address)?;
+    let mint_account = client.get_account(&mint_pubkey)?;
+    let mut state = MintState::try_from_account_data(&mint_account.data)?;
+
+    let pubsub_client = PubsubClient::new(ws_url)
+        .await
+        .map_err(|err| ReadTransactionError::RpcError(err.to_string()))?;
+    let (sender, receiver) = event_channel(channel);
+
+    tokio::spawn(async move {
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            ..RpcAccountInfoConfig::default()
+        };
+        let (mut notifications, unsubscribe) = match pubsub_client.account_subscribe(&mint_pubkey, Some(config)).await {
+            Ok(subscription) => subscription,
+            Err(err) => {
+                warn!("Unable to subscribe to mint {}: {}", mint_pubkey, err);
+                return;
+            }
+        };
+
+        while let Some(notification) = notifications.next().await {
+            let timestamp = EventTimestamp::received_now(notification.context.slot, None);
+            // Closed accounts are returned to the system program with no data
+            let current = notification.value
+                .decode::<SolanaAccount>()
+                .and_then(|account| MintState::try_from_account_data(&account.data).ok());
+            for event in mint_events(&state, current.as_ref()) {
+                if sender.send(Timestamped { event, timestamp }).await.is_err() {
+                    unsubscribe().await;
+                    return;
+                }
+            }
+            match current {
+                Some(current) => state = current,
+                None => break,
+            }
+        }
+        unsubscribe().await;
+    });
+
+    Ok(receiver)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::program_option::COption;
+    use solana_sdk::program_pack::Pack;
+
+    fn mint_state(supply: u64, mint_authority: Option<Pubkey>) -> MintState {
+        MintState {
+            supply,
+            mint_authority,
+            freeze_authority: None,
+            close_authority: None,
+        }
+    }
+
+    #[test]
+    fn test_mint_state_from_spl_token_mint() {
+        let mint_authority = Pubkey::new_unique();
+        let mint = spl_token::state::Mint {
+            mint_authority: COption::Some(mint_authority),
+            supply: 1_000,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint::pack(mint, &mut data).unwrap();
+
+        let state = MintState::try_from_account_data(&data).unwrap();
+        assert!(state == mint_state(1_000, Some(mint_authority)));
+        assert!(MintState::try_from_account_data(&[0; 10]).is_err());
+    }
+
+    #[test]
+    fn test_mint_events() {
+        let mint_authority = Pubkey::new_unique();
+        let previous = mint_state(1_000, Some(mint_authority));
+
+        assert!(mint_events(&previous, Some(&previous)).is_empty());
+        let events = mint_events(&previous, Some(&mint_state(2_000, None)));
+        assert!(events == vec![
+            MintEvent::SupplyChanged { previous: 1_000, current: 2_000 },
+            MintEvent::MintAuthorityChanged { previous: Some(mint_authority), current: None },
+        ]);
+        assert!(mint_events(&previous, None) == vec![MintEvent::Closed]);
+    }
+}
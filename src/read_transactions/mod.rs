@@ -1,5 +1,28 @@
+pub mod backfill;
 pub mod balances;
+pub mod batch;
 pub mod associated_token_account;
 pub mod mint_account;
+pub mod mint_watcher;
 pub mod metadata;
-pub mod account;
\ No newline at end of file
+pub mod portfolio;
+pub mod program_accounts;
+pub mod account;
+pub mod account_cache;
+pub mod cpi_tree;
+pub mod governance;
+pub mod history;
+pub mod instruction_decoder;
+pub mod query;
+pub mod snapshot;
+pub mod spam;
+pub mod stake_pool;
+pub mod swap_estimate;
+pub mod sysvar;
+pub mod token_extensions;
+pub mod token_filter;
+pub mod trade_stats;
+pub mod token_transfers;
+pub mod transaction;
+pub mod wallet_graph;
+pub mod zero_copy;
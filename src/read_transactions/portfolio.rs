@@ -0,0 +1,649 @@
+//! # Portfolio
+//!
+//! Values the SOL and token holdings of many wallets at once. Lookups are shared across wallets,
+//! each mint is loaded once with its metadata and bonding curve, and requests are chunked into
+//! JSON-RPC batches, so tracking dozens of wallets takes a handful of HTTP requests.
+//!
+//! Tokens are valued in SOL from their pump.fun bonding curve, and liquid staking tokens like
+//! mSOL and JitoSOL from the exchange rate of their stake pool. Other tokens are listed without
+//! a value and left out of the totals. Every holding is classified with the
+//! default `SpamClassifier`, so UIs can hide likely spam airdrops.
+//!
+//! `get_portfolio` values a single wallet further: tokens without a bonding curve are quoted
+//! through Raydium, and every value is converted to USD at the Raydium price of SOL in USDC.
+
+use std::collections::HashMap;
+use serde_json::{json, Value};
+use solana_account_decoder::UiAccount;
+use solana_client::{rpc_client::RpcClient, rpc_request::RpcRequest};
+use solana_sdk::{account::Account, native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::Account as SplTokenAccount;
+use crate::{
+    constants::solana_programs::{sol_pubkey, token_2022_program, token_program},
+    error::ReadTransactionError,
+    pumpfun::bonding_curve::calculate_token_price_in_sol,
+    raydium::compute_swap::get_raydium_swap_quote,
+    utils::address_to_pubkey
+};
+use super::{
+    batch::{get_multiple_accounts_batch, get_token_details, send_batch_request, TokenDetails, MAX_MULTIPLE_ACCOUNTS},
+    spam::{SpamClassifier, SpamVerdict},
+    stake_pool::{get_exchange_rates, known_liquid_staking_tokens, LiquidStakingToken},
+    token_filter::TokenFilter
+};
+
+/// Maximum number of requests sent in one JSON-RPC batch.
+const MAX_REQUESTS_PER_BATCH: usize = 50;
+/// Slippage used for Raydium quotes, it does not affect the expected output.
+const QUOTE_SLIPPAGE_BPS: u32 = 100;
+const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+const USDC_DECIMALS: i32 = 6;
+
+/// Token account of a wallet with the details of its mint.
+///
+/// ### Fields
+///
+/// - `mint`: Mint of the token.
+/// - `token_account`: Token account holding the balance.
+/// - `token_program`: Token program owning the token account.
+/// - `amount`: Raw balance, without decimals.
+/// - `decimals`: Decimals of the mint, 0 if the mint could not be loaded.
+/// - `ui_amount`: Balance with decimals applied.
+/// - `name`: Name from the token metadata, if any.
+/// - `symbol`: Symbol from the token metadata, if any.
+/// - `uri`: URI of the off-chain metadata, if any.
+/// - `update_authority`: Update authority of the token metadata, usually its creator.
+/// - `price_in_sol`: Price of one token in SOL, `None` if the token has no bonding curve and is not a liquid staking token.
+/// - `value_in_sol`: Value of the balance in SOL, `None` if the token has no price.
+/// - `value_in_usd`: Value of the balance in USD, only set by `get_portfolio`.
+/// - `spam`: Whether the holding is likely a spam airdrop, and why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortfolioHolding {
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub token_program: Pubkey,
+    pub amount: u64,
+    pub decimals: u8,
+    pub ui_amount: f64,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub uri: Option<String>,
+    pub update_authority: Option<Pubkey>,
+    pub price_in_sol: Option<f64>,
+    pub value_in_sol: Option<f64>,
+    pub value_in_usd: Option<f64>,
+    pub spam: SpamVerdict,
+}
+
+/// Holdings of a single wallet.
+///
+/// ### Fields
+///
+/// - `wallet`: Address of the wallet.
+/// - `lamports`: SOL balance of the wallet.
+/// - `holdings`: Token accounts of the wallet, of both token programs.
+/// - `value_in_sol`: SOL balance plus the value of every priced holding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalletPortfolio {
+    pub wallet: Pubkey,
+    pub lamports: u64,
+    pub holdings: Vec<PortfolioHolding>,
+    pub value_in_sol: f64,
+}
+
+/// Holdings of one mint summed across every wallet.
+///
+/// ### Fields
+///
+/// - `mint`: Mint of the token.
+/// - `amount`: Raw balance summed across wallets.
+/// - `ui_amount`: Balance with decimals applied.
+/// - `symbol`: Symbol from the token metadata, if any.
+/// - `value_in_sol`: Value of the balance in SOL, `None` if the token has no price.
+/// - `wallet_count`: Number of token accounts holding the mint.
+/// - `is_spam`: Whether the token is likely a spam airdrop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregateHolding {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub ui_amount: f64,
+    pub symbol: Option<String>,
+    pub value_in_sol: Option<f64>,
+    pub wallet_count: usize,
+    pub is_spam: bool,
+}
+
+/// Per-wallet and aggregate valuation of several wallets.
+///
+/// ### Fields
+///
+/// - `wallets`: Portfolio of each wallet, in the order they were requested.
+/// - `holdings`: Holdings of each mint across every wallet, in order of first appearance.
+/// - `lamports`: SOL balance of every wallet.
+/// - `value_in_sol`: Total value of every wallet.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Portfolios {
+    pub wallets: Vec<WalletPortfolio>,
+    pub holdings: Vec<AggregateHolding>,
+    pub lamports: u64,
+    pub value_in_sol: f64,
+}
+
+/// Valuation of a single wallet in SOL and USD.
+///
+/// ### Fields
+///
+/// - `wallet`: Address of the wallet.
+/// - `lamports`: SOL balance of the wallet.
+/// - `sol_price_in_usd`: Price of SOL in USD, `None` if it could not be quoted.
+/// - `holdings`: Token accounts of the wallet, valued in SOL and USD when a price is known.
+/// - `value_in_sol`: SOL balance plus the value of every priced holding.
+/// - `value_in_usd`: `value_in_sol` in USD, `None` without a SOL price.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Portfolio {
+    pub wallet: Pubkey,
+    pub lamports: u64,
+    pub sol_price_in_usd: Option<f64>,
+    pub holdings: Vec<PortfolioHolding>,
+    pub value_in_sol: f64,
+    pub value_in_usd: Option<f64>,
+}
+
+/// Token account returned by `getTokenAccountsByOwner`.
+struct OwnedTokenAccount {
+    pubkey: Pubkey,
+    token_program: Pubkey,
+    account: SplTokenAccount,
+}
+
+/// Gets the SOL balance and token holdings of every wallet, valued in SOL, along with the
+/// holdings summed across wallets.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InvalidAddress` if a wallet address is invalid and
+/// `ReadTransactionError::RpcError` if a request fails.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, read_transactions::portfolio::get_portfolios};
+///
+/// # async fn example() {
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let portfolios = get_portfolios(&client, &["ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5"]).await.unwrap();
+/// for wallet in &portfolios.wallets {
+///     println!("{} holds {:.4} SOL in value", wallet.wallet, wallet.value_in_sol);
+/// }
+/// println!("Total {:.4} SOL", portfolios.value_in_sol);
+/// # }
+/// ```
+pub async fn get_portfolios(client: &RpcClient, wallets: &[&str]) -> Result<Portfolios, ReadTransactionError> {
+    get_portfolios_with_filter(client, wallets, &TokenFilter::default()).await
+}
+
+/// Gets the portfolios of every wallet like `get_portfolios`, keeping only the token accounts
+/// allowed by `filter`. Filtered tokens are not looked up and left out of the totals.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{
+///     create_rpc_client,
+///     read_transactions::{portfolio::get_portfolios_with_filter, token_filter::TokenFilter}
+/// };
+///
+/// # async fn example() {
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let filter = TokenFilter { hide_zero_balances: true, ..Default::default() };
+/// let portfolios = get_portfolios_with_filter(&client, &["ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5"], &filter).await.unwrap();
+/// # }
+/// ```
+pub async fn get_portfolios_with_filter(client: &RpcClient, wallets: &[&str], filter: &TokenFilter) -> Result<Portfolios, ReadTransactionError> {
+    let wallet_pubkeys = wallets
+        .iter()
+        .map(|wallet| address_to_pubkey(wallet))
+        .collect::<Result<Vec<Pubkey>, _>>()?;
+    if wallet_pubkeys.is_empty() {
+        return Ok(Portfolios::default());
+    }
+
+    let wallet_groups: Vec<Vec<Pubkey>> = wallet_pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS).map(<[Pubkey]>::to_vec).collect();
+    let balances: Vec<u64> = get_multiple_accounts_batch(client, &wallet_groups)
+        .await?
+        .into_iter()
+        .flatten()
+        .map(|account| account.map(|account| account.lamports).unwrap_or(0))
+        .collect();
+    let token_accounts: Vec<Vec<OwnedTokenAccount>> = get_token_accounts_of_wallets(client, &wallet_pubkeys)
+        .await?
+        .into_iter()
+        .map(|token_accounts| {
+            token_accounts
+                .into_iter()
+                .filter(|token_account| {
+                    let account = &token_account.account;
+                    filter.allows(&account.mint, account.amount, account.is_frozen())
+                })
+                .collect()
+        })
+        .collect();
+
+    // Each mint is loaded once, however many wallets hold it
+    let mut mints: Vec<Pubkey> = Vec::new();
+    for token_account in token_accounts.iter().flatten() {
+        if !mints.contains(&token_account.account.mint) {
+            mints.push(token_account.account.mint);
+        }
+    }
+    let spam_classifier = SpamClassifier::default();
+    let mut token_details: HashMap<Pubkey, TokenDetails> = HashMap::new();
+    for mint_chunk in mints.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let mint_addresses: Vec<String> = mint_chunk.iter().map(Pubkey::to_string).collect();
+        let details = get_token_details(client, mint_addresses.iter().map(String::as_str).collect()).await?;
+        token_details.extend(details.into_iter().map(|details| (details.mint_pubkey, details)));
+    }
+    let liquid_staking_tokens: Vec<LiquidStakingToken> = known_liquid_staking_tokens()
+        .into_iter()
+        .filter(|token| mints.contains(&token.mint))
+        .collect();
+    let exchange_rates = get_exchange_rates(client, &liquid_staking_tokens)?;
+
+    let wallets: Vec<WalletPortfolio> = wallet_pubkeys
+        .into_iter()
+        .zip(balances)
+        .zip(token_accounts)
+        .map(|((wallet, lamports), token_accounts)| {
+            let holdings: Vec<PortfolioHolding> = token_accounts
+                .into_iter()
+                .map(|token_account| {
+                    let details = token_details.get(&token_account.account.mint);
+                    let exchange_rate = exchange_rates.get(&token_account.account.mint).copied();
+                    portfolio_holding(token_account, details, exchange_rate, &spam_classifier)
+                })
+                .collect();
+            let value_in_sol = lamports as f64 / LAMPORTS_PER_SOL as f64
+                + holdings.iter().filter_map(|holding| holding.value_in_sol).sum::<f64>();
+            WalletPortfolio { wallet, lamports, holdings, value_in_sol }
+        })
+        .collect();
+    Ok(aggregate(wallets, &mints))
+}
+
+/// Gets the SOL balance and token holdings of a wallet valued in SOL and USD. Holdings are
+/// priced like `get_portfolios`, and the remaining tokens are priced from a Raydium quote of
+/// selling the whole balance for SOL. Holdings flagged as spam are not quoted, and tokens
+/// without a Raydium route are left without a value.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InvalidAddress` if the wallet address is invalid and
+/// `ReadTransactionError::RpcError` if a request fails. Failed Raydium quotes only leave
+/// values unset.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, read_transactions::portfolio::get_portfolio};
+///
+/// # async fn example() {
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let portfolio = get_portfolio(&client, "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5").await.unwrap();
+/// for holding in &portfolio.holdings {
+///     println!("{} {:?}: {:?} USD", holding.ui_amount, holding.symbol, holding.value_in_usd);
+/// }
+/// println!("Total {:.4} SOL, {:?} USD", portfolio.value_in_sol, portfolio.value_in_usd);
+/// # }
+/// ```
+pub async fn get_portfolio(client: &RpcClient, wallet: &str) -> Result<Portfolio, ReadTransactionError> {
+    let wallet_portfolio = get_portfolios(client, &[wallet])
+        .await?
+        .wallets
+        .pop()
+        .ok_or(ReadTransactionError::AccountNotFound)?;
+
+    let sol_mint = sol_pubkey().to_string();
+    let mut raydium_values: HashMap<Pubkey, f64> = HashMap::new();
+    for holding in &wallet_portfolio.holdings {
+        if holding.value_in_sol.is_some() || holding.amount == 0 || holding.spam.is_spam || holding.mint == sol_pubkey() {
+            continue;
+        }
+        if let Ok(quote) = get_raydium_swap_quote(&holding.mint.to_string(), holding.amount, &sol_mint, QUOTE_SLIPPAGE_BPS).await {
+            raydium_values.insert(holding.token_account, quote.output_amount as f64 / LAMPORTS_PER_SOL as f64);
+        }
+    }
+    let sol_price_in_usd = get_raydium_swap_quote(&sol_mint, LAMPORTS_PER_SOL, USDC_MINT, QUOTE_SLIPPAGE_BPS)
+        .await
+        .ok()
+        .map(|quote| quote.output_amount as f64 / 10_f64.powi(USDC_DECIMALS));
+    Ok(value_portfolio(wallet_portfolio, &raydium_values, sol_price_in_usd))
+}
+
+/// Values the holdings of `wallet_portfolio` without a price from `raydium_values`, the SOL
+/// received for the balance of each token account, and converts every value to USD.
+fn value_portfolio(wallet_portfolio: WalletPortfolio, raydium_values: &HashMap<Pubkey, f64>, sol_price_in_usd: Option<f64>) -> Portfolio {
+    let spam_classifier = SpamClassifier::default();
+    let holdings: Vec<PortfolioHolding> = wallet_portfolio.holdings
+        .into_iter()
+        .map(|mut holding| {
+            if holding.mint == sol_pubkey() {
+                // Wrapped SOL is worth its amount in SOL
+                holding.price_in_sol = Some(1.0);
+                holding.value_in_sol = Some(holding.ui_amount);
+            } else if let (None, Some(value)) = (holding.value_in_sol, raydium_values.get(&holding.token_account)) {
+                holding.price_in_sol = (holding.ui_amount > 0.0).then(|| value / holding.ui_amount);
+                holding.value_in_sol = Some(*value);
+            }
+            // Prices found here are liquidity, which the spam rules look for
+            holding.spam = spam_classifier.classify(&holding);
+            holding.value_in_usd = holding.value_in_sol.zip(sol_price_in_usd).map(|(value, price)| value * price);
+            holding
+        })
+        .collect();
+    let value_in_sol = wallet_portfolio.lamports as f64 / LAMPORTS_PER_SOL as f64
+        + holdings.iter().filter_map(|holding| holding.value_in_sol).sum::<f64>();
+
+    Portfolio {
+        wallet: wallet_portfolio.wallet,
+        lamports: wallet_portfolio.lamports,
+        sol_price_in_usd,
+        holdings,
+        value_in_sol,
+        value_in_usd: sol_price_in_usd.map(|price| value_in_sol * price),
+    }
+}
+
+fn portfolio_holding(token_account: OwnedTokenAccount, details: Option<&TokenDetails>, exchange_rate: Option<f64>, spam_classifier: &SpamClassifier) -> PortfolioHolding {
+    let decimals = details.and_then(|details| details.mint).map(|mint| mint.decimals).unwrap_or(0);
+    let ui_amount = token_account.account.amount as f64 / 10_f64.powi(decimals as i32);
+    let metadata = details.and_then(|details| details.metadata.as_ref());
+    // Metadata strings are padded with null bytes
+    let trimmed = |text: &str| text.trim_end_matches('\0').to_string();
+    let price_in_sol = details
+        .and_then(|details| details.bonding_curve.as_ref())
+        .and_then(|bonding_curve| calculate_token_price_in_sol(bonding_curve).ok())
+        .or(exchange_rate);
+
+    let mut holding = PortfolioHolding {
+        mint: token_account.account.mint,
+        token_account: token_account.pubkey,
+        token_program: token_account.token_program,
+        amount: token_account.account.amount,
+        decimals,
+        ui_amount,
+        name: metadata.map(|metadata| trimmed(&metadata.data.name)),
+        symbol: metadata.map(|metadata| trimmed(&metadata.data.symbol)),
+        uri: metadata.map(|metadata| trimmed(&metadata.data.uri)),
+        update_authority: metadata.map(|metadata| metadata.update_authority),
+        price_in_sol,
+        value_in_sol: price_in_sol.map(|price| price * ui_amount),
+        value_in_usd: None,
+        spam: SpamVerdict::default(),
+    };
+    holding.spam = spam_classifier.classify(&holding);
+    holding
+}
+
+/// Sums the holdings of each mint across wallets.
+fn aggregate(wallets: Vec<WalletPortfolio>, mints: &[Pubkey]) -> Portfolios {
+    let mut holdings: Vec<AggregateHolding> = mints
+        .iter()
+        .map(|mint| AggregateHolding { mint: *mint, amount: 0, ui_amount: 0.0, symbol: None, value_in_sol: None, wallet_count: 0, is_spam: false })
+        .collect();
+    for holding in wallets.iter().flat_map(|wallet| &wallet.holdings) {
+        let Some(aggregate_holding) = holdings.iter_mut().find(|aggregate_holding| aggregate_holding.mint == holding.mint) else {
+            continue;
+        };
+        aggregate_holding.amount = aggregate_holding.amount.saturating_add(holding.amount);
+        aggregate_holding.ui_amount += holding.ui_amount;
+        aggregate_holding.symbol = holding.symbol.clone();
+        aggregate_holding.value_in_sol = holding.value_in_sol.map(|value| value + aggregate_holding.value_in_sol.unwrap_or(0.0));
+        aggregate_holding.wallet_count += 1;
+        aggregate_holding.is_spam = holding.spam.is_spam;
+    }
+
+    Portfolios {
+        lamports: wallets.iter().map(|wallet| wallet.lamports).fold(0, u64::saturating_add),
+        value_in_sol: wallets.iter().map(|wallet| wallet.value_in_sol).sum(),
+        wallets,
+        holdings,
+    }
+}
+
+/// Gets the token accounts of both token programs for each wallet, in JSON-RPC batches.
+/// Falls back to one request at a time if the provider does not support batching.
+async fn get_token_accounts_of_wallets(client: &RpcClient, wallets: &[Pubkey]) -> Result<Vec<Vec<OwnedTokenAccount>>, ReadTransactionError> {
+    let token_programs = [token_program(), token_2022_program()];
+    let requests: Vec<(RpcRequest, Value)> = wallets
+        .iter()
+        .flat_map(|wallet| {
+            token_programs.iter().map(move |program| {
+                let config = json!({ "encoding": "base64", "commitment": client.commitment().commitment });
+                (RpcRequest::GetTokenAccountsByOwner, json!([wallet.to_string(), { "programId": program.to_string() }, config]))
+            })
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(requests.len());
+    for batch in requests.chunks(MAX_REQUESTS_PER_BATCH) {
+        match send_batch_request(&client.url(), batch.to_vec()).await {
+            Ok(batch_results) => {
+                for result in batch_results {
+                    results.push(parse_token_accounts_result(&result.map_err(ReadTransactionError::RpcError)?)?);
+                }
+            }
+            Err(ReadTransactionError::BatchNotSupported(_)) => {
+                for (request, params) in batch {
+                    let result: Value = client.send(*request, params.clone())?;
+                    results.push(parse_token_accounts_result(&result)?);
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    let mut results = results.into_iter();
+    Ok(wallets
+        .iter()
+        .map(|_| results.by_ref().take(token_programs.len()).flatten().collect())
+        .collect())
+}
+
+fn parse_token_accounts_result(result: &Value) -> Result<Vec<OwnedTokenAccount>, ReadTransactionError> {
+    let keyed_accounts = result
+        .get("value")
+        .and_then(Value::as_array)
+        .ok_or(ReadTransactionError::DeserializeError)?;
+    keyed_accounts
+        .iter()
+        .map(|keyed_account| {
+            let pubkey = keyed_account
+                .get("pubkey")
+                .and_then(Value::as_str)
+                .ok_or(ReadTransactionError::DeserializeError)?
+                .parse::<Pubkey>()?;
+            let ui_account: UiAccount = keyed_account
+                .get("account")
+                .and_then(|account| serde_json::from_value(account.clone()).ok())
+                .ok_or(ReadTransactionError::DeserializeError)?;
+            let account: Account = ui_account.decode().ok_or(ReadTransactionError::DeserializeError)?;
+            // Token 2022 accounts hold extensions after the base account
+            let token_account = account.data
+                .get(..SplTokenAccount::LEN)
+                .and_then(|data| SplTokenAccount::unpack(data).ok())
+                .ok_or(ReadTransactionError::DeserializeError)?;
+            Ok(OwnedTokenAccount { pubkey, token_program: account.owner, account: token_account })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spl_token::state::{AccountState, Mint as SplMintAccount};
+    use crate::{
+        constants::{
+            pumpfun_accounts::{pumpfun_program, BONDING_CURVE_DISCRIMINATOR},
+            stake_pool_accounts::{jitosol_mint, STAKE_POOL_TOTAL_LAMPORTS_OFFSET}
+        },
+        pumpfun::bonding_curve::BondingCurveAccount,
+        test_utils::{account_with_data, mock_client, multiple_accounts_response, program_accounts_response, token_accounts_by_owner_response}
+    };
+
+    fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> (Pubkey, Account) {
+        let mut data = vec![0; SplTokenAccount::LEN];
+        let token_account = SplTokenAccount { mint: *mint, owner: *owner, amount, state: AccountState::Initialized, ..Default::default() };
+        SplTokenAccount::pack(token_account, &mut data).unwrap();
+        (Pubkey::new_unique(), account_with_data(token_program(), data))
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_portfolios_shares_mint_lookups() {
+        let (wallet_1, wallet_2) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (priced_mint, unpriced_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let mut mint_data = vec![0; SplMintAccount::LEN];
+        SplMintAccount::pack(SplMintAccount { decimals: 6, is_initialized: true, ..Default::default() }, &mut mint_data).unwrap();
+        let mint_account = account_with_data(token_program(), mint_data);
+        let bonding_curve = BondingCurveAccount {
+            discriminator: BONDING_CURVE_DISCRIMINATOR,
+            virtual_token_reserves: 1_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 0,
+            real_sol_reserves: 0,
+            total_token_supply: 1_000_000_000_000,
+            complete: false,
+            creator: None,
+        };
+        let bonding_curve_account = account_with_data(pumpfun_program(), bonding_curve.to_account_data());
+
+        // mock client has no HTTP endpoint, so requests fall back to the client one at a time
+        let client = mock_client(vec![
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(account_with_data(Pubkey::default(), vec![])), None], 1)),
+            (RpcRequest::GetTokenAccountsByOwner, token_accounts_by_owner_response(&[
+                token_account(&priced_mint, &wallet_1, 2_000_000),
+                token_account(&unpriced_mint, &wallet_1, 5),
+            ], 1)),
+            (RpcRequest::GetTokenAccountsByOwner, token_accounts_by_owner_response(&[], 1)),
+            (RpcRequest::GetTokenAccountsByOwner, token_accounts_by_owner_response(&[token_account(&priced_mint, &wallet_2, 1_000_000)], 1)),
+            (RpcRequest::GetTokenAccountsByOwner, token_accounts_by_owner_response(&[], 1)),
+            // a single lookup of both mints
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(mint_account.clone()), Some(mint_account)], 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[None, None], 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(bonding_curve_account), None], 1)),
+        ]);
+
+        let wallet_addresses = [wallet_1.to_string(), wallet_2.to_string()];
+        let wallets: Vec<&str> = wallet_addresses.iter().map(String::as_str).collect();
+        let portfolios = get_portfolios(&client, &wallets).await.unwrap();
+
+        assert!(portfolios.wallets.len() == 2);
+        assert!(portfolios.lamports == 1_000_000);
+        let holdings = &portfolios.wallets[0].holdings;
+        assert!(holdings[0].ui_amount == 2.0 && (holdings[0].value_in_sol.unwrap() - 0.06).abs() < 1e-9);
+        assert!(holdings[1].decimals == 6 && holdings[1].value_in_sol.is_none());
+        assert!(!holdings[1].spam.is_spam && holdings[1].spam.flags[0].rule == "no_liquidity");
+        assert!((portfolios.wallets[0].value_in_sol - 0.061).abs() < 1e-9);
+
+        assert!(portfolios.holdings.len() == 2);
+        assert!(portfolios.holdings[0].amount == 3_000_000 && portfolios.holdings[0].wallet_count == 2);
+        assert!((portfolios.holdings[0].value_in_sol.unwrap() - 0.09).abs() < 1e-9);
+        assert!((portfolios.value_in_sol - 0.091).abs() < 1e-9);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_portfolios_with_filter_skips_filtered_mints() {
+        let wallet = Pubkey::new_unique();
+        let (spam_mint, empty_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let client = mock_client(vec![
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[None], 1)),
+            (RpcRequest::GetTokenAccountsByOwner, token_accounts_by_owner_response(&[
+                token_account(&spam_mint, &wallet, 1_000),
+                token_account(&empty_mint, &wallet, 0),
+            ], 1)),
+            (RpcRequest::GetTokenAccountsByOwner, token_accounts_by_owner_response(&[], 1)),
+        ]);
+
+        let filter = TokenFilter { ignore_mints: vec![spam_mint], hide_zero_balances: true, ..Default::default() };
+        let portfolios = get_portfolios_with_filter(&client, &[&wallet.to_string()], &filter).await.unwrap();
+        assert!(portfolios.wallets[0].holdings.is_empty());
+        assert!(portfolios.holdings.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_portfolios_values_liquid_staking_tokens() {
+        let wallet = Pubkey::new_unique();
+        let mut mint_data = vec![0; SplMintAccount::LEN];
+        SplMintAccount::pack(SplMintAccount { decimals: 9, is_initialized: true, ..Default::default() }, &mut mint_data).unwrap();
+        let mut stake_pool_data = vec![0; STAKE_POOL_TOTAL_LAMPORTS_OFFSET];
+        stake_pool_data.extend_from_slice(&1_200_u64.to_le_bytes());
+        stake_pool_data.extend_from_slice(&1_000_u64.to_le_bytes());
+        let client = mock_client(vec![
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[None], 1)),
+            (RpcRequest::GetTokenAccountsByOwner, token_accounts_by_owner_response(&[token_account(&jitosol_mint(), &wallet, 2_000_000_000)], 1)),
+            (RpcRequest::GetTokenAccountsByOwner, token_accounts_by_owner_response(&[], 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(account_with_data(token_program(), mint_data))], 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[None], 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[None], 1)),
+            (RpcRequest::GetProgramAccounts, program_accounts_response(&[(Pubkey::new_unique(), account_with_data(Pubkey::new_unique(), stake_pool_data))])),
+        ]);
+
+        let portfolios = get_portfolios(&client, &[&wallet.to_string()]).await.unwrap();
+        let holding = &portfolios.wallets[0].holdings[0];
+        assert!((holding.price_in_sol.unwrap() - 1.2).abs() < 1e-9);
+        assert!((holding.value_in_sol.unwrap() - 2.4).abs() < 1e-9);
+        assert!((portfolios.value_in_sol - 2.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_value_portfolio_with_raydium_quotes_and_usd() {
+        let holding = |mint: Pubkey, ui_amount: f64, price_in_sol: Option<f64>| PortfolioHolding {
+            mint,
+            token_account: Pubkey::new_unique(),
+            token_program: token_program(),
+            amount: (ui_amount * 1_000_000.0) as u64,
+            decimals: 6,
+            ui_amount,
+            name: None,
+            symbol: Some("TKN".to_string()),
+            uri: None,
+            update_authority: None,
+            price_in_sol,
+            value_in_sol: price_in_sol.map(|price| price * ui_amount),
+            value_in_usd: None,
+            spam: SpamVerdict::default(),
+        };
+        let curve_holding = holding(Pubkey::new_unique(), 2.0, Some(0.03));
+        let raydium_holding = holding(Pubkey::new_unique(), 4.0, None);
+        let wsol_holding = holding(sol_pubkey(), 0.5, None);
+        let unquoted_holding = holding(Pubkey::new_unique(), 1.0, None);
+        let raydium_values = HashMap::from([(raydium_holding.token_account, 0.2)]);
+        let wallet_portfolio = WalletPortfolio {
+            wallet: Pubkey::new_unique(),
+            lamports: LAMPORTS_PER_SOL,
+            holdings: vec![curve_holding, raydium_holding, wsol_holding, unquoted_holding],
+            value_in_sol: 1.06,
+        };
+
+        let portfolio = value_portfolio(wallet_portfolio, &raydium_values, Some(150.0));
+        let holdings = &portfolio.holdings;
+        assert!((holdings[0].value_in_usd.unwrap() - 9.0).abs() < 1e-9);
+        assert!((holdings[1].price_in_sol.unwrap() - 0.05).abs() < 1e-9 && (holdings[1].value_in_usd.unwrap() - 30.0).abs() < 1e-9);
+        assert!(holdings[1].spam.flags.iter().all(|flag| flag.rule != "no_liquidity"));
+        assert!(holdings[2].value_in_sol == Some(0.5));
+        assert!(holdings[3].value_in_sol.is_none() && holdings[3].value_in_usd.is_none());
+        assert!((portfolio.value_in_sol - 1.76).abs() < 1e-9);
+        assert!((portfolio.value_in_usd.unwrap() - 264.0).abs() < 1e-9);
+
+        // without a SOL price, values stay in SOL
+        let portfolio = value_portfolio(WalletPortfolio { wallet: Pubkey::new_unique(), lamports: 0, holdings: vec![], value_in_sol: 0.0 }, &HashMap::new(), None);
+        assert!(portfolio.value_in_usd.is_none());
+    }
+
+    #[tokio::test]
+    async fn failing_test_get_portfolios_of_invalid_wallet() {
+        let client = mock_client(vec![]);
+        let result = get_portfolios(&client, &["invalid"]).await;
+        assert!(matches!(result, Err(ReadTransactionError::InvalidAddress(_))));
+    }
+}
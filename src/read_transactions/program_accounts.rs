@@ -0,0 +1,319 @@
+//! # Program Accounts
+//!
+//! Streams the results of `getProgramAccounts` for scans returning tens of thousands of accounts.
+//! `RpcClient::get_program_accounts` parses the whole response into a vector, which for large
+//! programs takes hundreds of MB. Here the response is read chunk by chunk and each account is
+//! decoded as soon as it has arrived, so only one account is held in memory at a time.
+//!
+//! Pair the stream with a `dataSlice` to only download the bytes that are needed, e.g the owner
+//! of token accounts, and with filters to let the RPC skip unrelated accounts.
+
+use serde_json::{json, Value};
+use solana_account_decoder::UiDataSliceConfig;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_filter::RpcFilterType,
+    rpc_request::RpcRequest,
+    rpc_response::RpcKeyedAccount
+};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use crate::error::ReadTransactionError;
+
+/// Options of a program account scan.
+///
+/// ### Fields
+///
+/// - `filters`: Filters every account has to match, e.g `Memcmp` and `DataSize`.
+/// - `data_slice`: Range of the data returned for each account, the whole data if `None`.
+#[derive(Debug, Clone, Default)]
+pub struct ProgramAccountsScan {
+    pub filters: Vec<RpcFilterType>,
+    pub data_slice: Option<UiDataSliceConfig>,
+}
+
+/// Accounts of a program, decoded one at a time while the response downloads.
+pub struct ProgramAccountStream {
+    response: reqwest::Response,
+    scanner: KeyedAccountScanner,
+    finished: bool,
+}
+
+/// Starts a `getProgramAccounts` scan of `program_id`, returning the accounts as they arrive.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::RpcError` if the request can not be sent. Errors returned by
+/// the RPC, e.g scans disabled by the provider, are returned by `ProgramAccountStream::next`.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_account_decoder::UiDataSliceConfig;
+/// use solana_client::rpc_filter::RpcFilterType;
+/// use easy_solana::{
+///     create_rpc_client,
+///     constants::pumpfun_accounts::pumpfun_program,
+///     read_transactions::program_accounts::{stream_program_accounts, ProgramAccountsScan}
+/// };
+///
+/// # async fn example() {
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// // Bonding curves, reading only their virtual reserves
+/// let scan = ProgramAccountsScan {
+///     filters: vec![RpcFilterType::DataSize(150)],
+///     data_slice: Some(UiDataSliceConfig { offset: 8, length: 16 }),
+/// };
+/// let mut accounts = stream_program_accounts(&client, &pumpfun_program(), &scan).await.unwrap();
+/// while let Some(account) = accounts.next().await {
+///     let (pubkey, account) = account.unwrap();
+///     println!("{}: {:?}", pubkey, account.data);
+/// }
+/// # }
+/// ```
+pub async fn stream_program_accounts(client: &RpcClient, program_id: &Pubkey, scan: &ProgramAccountsScan) -> Result<ProgramAccountStream, ReadTransactionError> {
+    let mut config = json!({
+        "encoding": "base64",
+        "withContext": true,
+        "commitment": client.commitment().commitment,
+        "filters": scan.filters,
+    });
+    if let Some(data_slice) = &scan.data_slice {
+        config["dataSlice"] = json!(data_slice);
+    }
+    let request = RpcRequest::GetProgramAccounts.build_request_json(1, json!([program_id.to_string(), config]));
+
+    let response = reqwest::Client::new()
+        .post(client.url())
+        .json(&request)
+        .send()
+        .await
+        .map_err(|err| ReadTransactionError::RpcError(err.to_string()))?;
+    Ok(ProgramAccountStream { response, scanner: KeyedAccountScanner::default(), finished: false })
+}
+
+impl ProgramAccountStream {
+    /// Returns the next account, or `None` once every account has been returned. The stream ends
+    /// after returning an error.
+    pub async fn next(&mut self) -> Option<Result<(Pubkey, Account), ReadTransactionError>> {
+        loop {
+            if self.finished {
+                return None;
+            }
+            match self.scanner.next_account() {
+                Ok(Some(account)) => return Some(Ok(account)),
+                Ok(None) => {}
+                Err(err) => {
+                    self.finished = true;
+                    return Some(Err(err));
+                }
+            }
+            match self.response.chunk().await {
+                Ok(Some(chunk)) => self.scanner.push(&chunk),
+                Ok(None) => {
+                    self.finished = true;
+                    return self.scanner.finish().err().map(Err);
+                }
+                Err(err) => {
+                    self.finished = true;
+                    return Some(Err(ReadTransactionError::RpcError(err.to_string())));
+                }
+            }
+        }
+    }
+
+    /// Slot the accounts were read at, known once the first accounts have arrived.
+    pub fn slot(&self) -> Option<u64> {
+        self.scanner.slot
+    }
+}
+
+/// Where the scanner is in the JSON-RPC response.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum ScanState {
+    /// Before the array of accounts, reading the context.
+    #[default]
+    Header,
+    /// Inside the array of accounts.
+    Accounts,
+    /// After the array of accounts.
+    Done,
+}
+
+/// Incremental parser of a `getProgramAccounts` response with context. Bytes are pushed as they
+/// arrive and each account is parsed once its closing brace is found, then dropped from the buffer.
+#[derive(Debug, Default)]
+struct KeyedAccountScanner {
+    buffer: Vec<u8>,
+    state: ScanState,
+    slot: Option<u64>,
+    // Progress of the search for the end of the current account, kept between chunks
+    scanned: usize,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+}
+
+impl KeyedAccountScanner {
+    fn push(&mut self, chunk: &[u8]) {
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// Parses the next complete account in the buffer, `None` if more bytes are needed.
+    fn next_account(&mut self) -> Result<Option<(Pubkey, Account)>, ReadTransactionError> {
+        if self.state == ScanState::Header && !self.read_header() {
+            return Ok(None);
+        }
+        if self.state == ScanState::Done {
+            return Ok(None);
+        }
+
+        if self.scanned == 0 {
+            // Skip the separators before the next account
+            let Some(start) = self.buffer.iter().position(|byte| !byte.is_ascii_whitespace() && *byte != b',') else {
+                self.buffer.clear();
+                return Ok(None);
+            };
+            self.buffer.drain(..start);
+            match self.buffer[0] {
+                b']' => {
+                    self.state = ScanState::Done;
+                    return Ok(None);
+                }
+                b'{' => {}
+                _ => return Err(ReadTransactionError::DeserializeError),
+            }
+        }
+
+        let Some(end) = self.find_account_end() else {
+            return Ok(None);
+        };
+        let keyed_account: RpcKeyedAccount = serde_json::from_slice(&self.buffer[..end])
+            .map_err(|_| ReadTransactionError::DeserializeError)?;
+        self.buffer.drain(..end);
+        let pubkey = keyed_account.pubkey.parse::<Pubkey>()?;
+        let account = keyed_account.account.decode().ok_or(ReadTransactionError::DeserializeError)?;
+        Ok(Some((pubkey, account)))
+    }
+
+    /// Reads the context and moves to the accounts, `false` if the array has not started yet.
+    fn read_header(&mut self) -> bool {
+        let Some(value_key) = find(&self.buffer, b"\"value\"") else {
+            return false;
+        };
+        let Some(array_start) = self.buffer[value_key..].iter().position(|byte| *byte == b'[') else {
+            return false;
+        };
+        self.slot = find(&self.buffer[..value_key], b"\"slot\"").and_then(|slot_key| {
+            let digits: String = self.buffer[slot_key + 6..value_key]
+                .iter()
+                .skip_while(|byte| !byte.is_ascii_digit())
+                .take_while(|byte| byte.is_ascii_digit())
+                .map(|byte| *byte as char)
+                .collect();
+            digits.parse().ok()
+        });
+        self.buffer.drain(..value_key + array_start + 1);
+        self.state = ScanState::Accounts;
+        true
+    }
+
+    /// Index after the closing brace of the account at the start of the buffer, if it has arrived.
+    fn find_account_end(&mut self) -> Option<usize> {
+        for index in self.scanned..self.buffer.len() {
+            let byte = self.buffer[index];
+            if self.in_string {
+                match byte {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match byte {
+                b'"' => self.in_string = true,
+                b'{' => self.depth += 1,
+                b'}' => {
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        self.scanned = 0;
+                        return Some(index + 1);
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.scanned = self.buffer.len();
+        None
+    }
+
+    /// Checks the response was complete once it has been fully read.
+    fn finish(&self) -> Result<(), ReadTransactionError> {
+        match self.state {
+            ScanState::Done => Ok(()),
+            // Error responses have no array of accounts
+            ScanState::Header => {
+                let response: Value = serde_json::from_slice(&self.buffer).map_err(|_| ReadTransactionError::DeserializeError)?;
+                let error = response.get("error").map(Value::to_string).unwrap_or_else(|| response.to_string());
+                Err(ReadTransactionError::RpcError(error))
+            }
+            ScanState::Accounts => Err(ReadTransactionError::RpcError("Response ended before the last account".to_string())),
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{account_with_data, program_accounts_response};
+
+    fn scan_in_chunks(response: &[u8], chunk_size: usize) -> (KeyedAccountScanner, Vec<(Pubkey, Account)>) {
+        let mut scanner = KeyedAccountScanner::default();
+        let mut accounts = Vec::new();
+        for chunk in response.chunks(chunk_size) {
+            scanner.push(chunk);
+            while let Some(account) = scanner.next_account().unwrap() {
+                accounts.push(account);
+            }
+        }
+        (scanner, accounts)
+    }
+
+    #[test]
+    fn test_keyed_account_scanner_across_chunks() {
+        let expected: Vec<(Pubkey, Account)> = (0..3_u8)
+            .map(|index| (Pubkey::new_unique(), account_with_data(Pubkey::new_unique(), vec![index; 40 + index as usize])))
+            .collect();
+        let response = json!({
+            "jsonrpc": "2.0",
+            "result": { "context": { "apiVersion": "2.1.0", "slot": 341_197_053 }, "value": program_accounts_response(&expected) },
+            "id": 1
+        });
+        let response = serde_json::to_vec_pretty(&response).unwrap();
+
+        for chunk_size in [1, 7, 64, response.len()] {
+            let (scanner, accounts) = scan_in_chunks(&response, chunk_size);
+            assert!(accounts == expected);
+            assert!(scanner.slot == Some(341_197_053));
+            assert!(scanner.finish().is_ok());
+            // consumed accounts are dropped from the buffer
+            assert!(scanner.buffer.len() < 64);
+        }
+    }
+
+    #[test]
+    fn failing_test_keyed_account_scanner_with_error_response() {
+        let response = br#"{"jsonrpc":"2.0","error":{"code":-32010,"message":"excluded from account secondary indexes"},"id":1}"#;
+        let (scanner, accounts) = scan_in_chunks(response, 16);
+        assert!(accounts.is_empty());
+        assert!(matches!(scanner.finish(), Err(ReadTransactionError::RpcError(message)) if message.contains("-32010")));
+
+        let (scanner, _) = scan_in_chunks(br#"{"result":{"context":{"slot":1},"value":[{"pubkey":"#, 8);
+        assert!(scanner.finish().is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! # Query
+//!
+//! This module contains a builder for account reads, allowing individual reads to override
+//! the commitment and retry policy of the client without constructing a new `RpcClient`.
+//! Reads can also require a minimum context slot, so that state read right after sending a
+//! transaction is not older than the transaction.
+
+use std::{str::FromStr, thread, time::Duration};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcAccountInfoConfig};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use crate::{
+    error::ReadTransactionError,
+    utils::address_to_pubkey
+};
+use super::account::{parse_account, resolve_program_accounts, Account};
+
+/// Default time waited between retries of a failed read.
+const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Builder for reading a single account.
+///
+/// ### Fields
+///
+/// - `commitment`: Commitment of the read, the client's commitment if `None`.
+/// - `retries`: Number of times a read failing with an RPC error is retried.
+/// - `retry_interval`: Time waited between retries.
+/// - `min_context_slot`: Minimum slot the RPC node must have processed to serve the read.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_sdk::commitment_config::CommitmentConfig;
+/// use easy_solana::{create_rpc_client, read_transactions::query::Query};
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let account = client
+///     .account("CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump")
+///     .commitment(CommitmentConfig::finalized())
+///     .retries(3)
+///     .fetch()
+///     .unwrap();
+/// ```
+pub struct AccountQuery<'a> {
+    client: &'a RpcClient,
+    address: String,
+    pub commitment: Option<CommitmentConfig>,
+    pub retries: usize,
+    pub retry_interval: Duration,
+    pub min_context_slot: Option<u64>,
+}
+
+impl<'a> AccountQuery<'a> {
+    pub fn new(client: &'a RpcClient, address: &str) -> Self {
+        Self {
+            client,
+            address: address.to_string(),
+            commitment: None,
+            retries: 0,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+            min_context_slot: None,
+        }
+    }
+
+    /// Sets the commitment of the read.
+    pub fn commitment(mut self, commitment: CommitmentConfig) -> Self {
+        self.commitment = Some(commitment);
+        self
+    }
+
+    /// Sets the number of retries when the read fails with an RPC error.
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Sets the time waited between retries.
+    pub fn retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// Sets the minimum slot the RPC node must have processed to serve the read. Nodes that are
+    /// behind return an error, which is retried according to `retries`.
+    pub fn min_context_slot(mut self, min_context_slot: u64) -> Self {
+        self.min_context_slot = Some(min_context_slot);
+        self
+    }
+
+    /// Requires the read to reflect the state after the transaction `signature` landed.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::TransactionNotFound` if the transaction has not landed.
+    pub fn read_after(self, signature: &str) -> Result<Self, ReadTransactionError> {
+        let slot = get_signature_slot(self.client, signature)?;
+        Ok(self.min_context_slot(slot))
+    }
+
+    /// Fetches the account.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::AccountNotFound` if the account does not exist, or the
+    /// last RPC error if every attempt failed.
+    pub fn fetch(&self) -> Result<Account, ReadTransactionError> {
+        let pubkey = address_to_pubkey(&self.address)?;
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(self.commitment.unwrap_or(self.client.commitment())),
+            min_context_slot: self.min_context_slot,
+            ..RpcAccountInfoConfig::default()
+        };
+
+        let mut attempts = 0;
+        loop {
+            let result = self.client
+                .get_account_with_config(&pubkey, config.clone())
+                .map_err(ReadTransactionError::from);
+            match result {
+                Ok(response) => {
+                    let account = response.value.ok_or(ReadTransactionError::AccountNotFound)?;
+                    let mut account = parse_account(&pubkey, account);
+                    resolve_program_accounts(self.client, [&mut account])?;
+                    return Ok(account);
+                }
+                Err(err) if attempts >= self.retries => return Err(err),
+                Err(_) => {
+                    attempts += 1;
+                    thread::sleep(self.retry_interval);
+                }
+            }
+        }
+    }
+}
+
+/// Gets the slot a transaction landed in.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InvalidSignature` if the signature cannot be parsed and
+/// `ReadTransactionError::TransactionNotFound` if the transaction has not landed.
+pub fn get_signature_slot(client: &RpcClient, signature: &str) -> Result<u64, ReadTransactionError> {
+    let signature = Signature::from_str(signature).map_err(|_| ReadTransactionError::InvalidSignature)?;
+    let status = client
+        .get_signature_statuses(&[signature])?
+        .value
+        .remove(0)
+        .ok_or(ReadTransactionError::TransactionNotFound)?;
+    Ok(status.slot)
+}
+
+/// Starts read queries from a client.
+pub trait Query {
+    /// Creates a query for the account at `address`.
+    fn account(&self, address: &str) -> AccountQuery<'_>;
+}
+
+impl Query for RpcClient {
+    fn account(&self, address: &str) -> AccountQuery<'_> {
+        AccountQuery::new(self, address)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use crate::{
+        constants::solana_programs::system_program,
+        read_transactions::account::AccountType,
+        test_utils::{account_info_response, account_with_data, mock_client}
+    };
+
+    const WALLET_ADDRESS_1: &str = "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5";
+
+    #[test]
+    fn test_account_query() {
+        let wallet_account = account_with_data(system_program(), vec![]);
+        let client = mock_client(vec![(RpcRequest::GetAccountInfo, account_info_response(Some(&wallet_account), 1))]);
+        let account = client
+            .account(WALLET_ADDRESS_1)
+            .commitment(CommitmentConfig::finalized())
+            .retries(3)
+            .fetch()
+            .unwrap();
+        assert!(account.pubkey == WALLET_ADDRESS_1);
+        assert!(matches!(account.account_type, AccountType::Wallet));
+    }
+
+    #[test]
+    fn failing_test_account_query_missing_account() {
+        let client = mock_client(vec![(RpcRequest::GetAccountInfo, account_info_response(None, 1))]);
+        let result = client.account(WALLET_ADDRESS_1).retries(3).fetch();
+        assert!(matches!(result, Err(ReadTransactionError::AccountNotFound)));
+    }
+
+    #[test]
+    fn test_account_query_read_after_signature() {
+        let wallet_account = account_with_data(system_program(), vec![]);
+        let client = mock_client(vec![(RpcRequest::GetAccountInfo, account_info_response(Some(&wallet_account), 1))]);
+        let signature = Signature::default().to_string();
+        // default mock status lands the transaction in slot 1
+        let query = client.account(WALLET_ADDRESS_1).read_after(&signature).unwrap();
+        assert!(query.min_context_slot == Some(1));
+        assert!(query.fetch().is_ok());
+    }
+
+    #[test]
+    fn failing_test_read_after_unknown_signature() {
+        let client = mock_client(vec![(RpcRequest::GetSignatureStatuses, json!({ "context": { "slot": 1 }, "value": [null] }))]);
+        let result = client.account(WALLET_ADDRESS_1).read_after(&Signature::default().to_string());
+        assert!(matches!(result, Err(ReadTransactionError::TransactionNotFound)));
+    }
+}
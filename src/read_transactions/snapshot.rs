@@ -0,0 +1,151 @@
+//! # Snapshot
+//!
+//! This module reads multiple accounts at a single slot. Accounts beyond the limit of one
+//! `getMultipleAccounts` request are fetched in several requests, which a load balanced
+//! provider may serve from nodes at different slots, so the snapshot is retried until every
+//! request is answered at the same slot.
+
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcAccountInfoConfig};
+use solana_sdk::pubkey::Pubkey;
+use crate::{error::ReadTransactionError, utils::addresses_to_pubkeys};
+use super::batch::MAX_MULTIPLE_ACCOUNTS;
+use super::account::{parse_account, Account};
+
+/// Number of times a snapshot is read before giving up on a consistent slot.
+const MAX_SNAPSHOT_ATTEMPTS: usize = 5;
+
+/// Accounts read at the same slot.
+///
+/// ### Fields
+///
+/// - `slot`: The slot all accounts were read at.
+/// - `accounts`: The account of each address in the requested order, `None` if it does not exist.
+pub struct AccountSnapshot {
+    pub slot: u64,
+    pub accounts: Vec<Option<Account>>,
+}
+
+/// Reads the accounts of the addresses at a single slot. Requests after the first require the
+/// slot of the first as their minimum context slot, and the snapshot is read again from the
+/// newest slot seen if a request is answered at a different slot.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InconsistentSnapshot` if the accounts could not be read at
+/// the same slot within the maximum number of attempts.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, read_transactions::snapshot::get_consistent_snapshot};
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let snapshot = get_consistent_snapshot(&client, vec![
+///     "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5",
+///     "CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump"
+/// ]).unwrap();
+/// println!("Accounts read at slot {}", snapshot.slot);
+/// ```
+pub fn get_consistent_snapshot(client: &RpcClient, addresses: Vec<&str>) -> Result<AccountSnapshot, ReadTransactionError> {
+    let pubkeys = addresses_to_pubkeys(addresses);
+    let mut min_context_slot = None;
+
+    for _ in 0..MAX_SNAPSHOT_ATTEMPTS {
+        match read_snapshot(client, &pubkeys, min_context_slot)? {
+            Ok(snapshot) => return Ok(snapshot),
+            Err(newest_slot) => min_context_slot = Some(newest_slot),
+        }
+    }
+
+    Err(ReadTransactionError::InconsistentSnapshot(MAX_SNAPSHOT_ATTEMPTS))
+}
+
+/// Reads the accounts in chunks, returning the newest slot seen if the chunks were served
+/// at different slots.
+fn read_snapshot(
+    client: &RpcClient,
+    pubkeys: &[Pubkey],
+    min_context_slot: Option<u64>
+) -> Result<Result<AccountSnapshot, u64>, ReadTransactionError> {
+    let mut snapshot_slot = min_context_slot;
+    let mut accounts = Vec::with_capacity(pubkeys.len());
+
+    for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(client.commitment()),
+            min_context_slot: snapshot_slot,
+            ..RpcAccountInfoConfig::default()
+        };
+        let response = client.get_multiple_accounts_with_config(chunk, config)?;
+        match snapshot_slot {
+            Some(slot) if response.context.slot != slot && !accounts.is_empty() => {
+                return Ok(Err(response.context.slot.max(slot)));
+            }
+            _ => snapshot_slot = Some(response.context.slot),
+        }
+        accounts.extend(
+            chunk
+                .iter()
+                .zip(response.value)
+                .map(|(pubkey, account_option)| account_option.map(|account| parse_account(pubkey, account)))
+        );
+    }
+
+    Ok(Ok(AccountSnapshot {
+        slot: snapshot_slot.unwrap_or_default(),
+        accounts,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::account::Account as SolanaAccount;
+    use crate::{
+        constants::solana_programs::system_program,
+        test_utils::{account_with_data, mock_client, multiple_accounts_response}
+    };
+
+    fn chunk_response(accounts: &[Option<SolanaAccount>], slot: u64) -> (RpcRequest, serde_json::Value) {
+        (RpcRequest::GetMultipleAccounts, multiple_accounts_response(accounts, slot))
+    }
+
+    #[test]
+    fn test_consistent_snapshot_retries_mixed_slots() {
+        let pubkeys: Vec<String> = (0..150).map(|_| Pubkey::new_unique().to_string()).collect();
+        let addresses: Vec<&str> = pubkeys.iter().map(String::as_str).collect();
+        let first_chunk = vec![Some(account_with_data(system_program(), vec![])); 100];
+        let second_chunk = vec![None; 50];
+
+        let client = mock_client(vec![
+            // second chunk is served by a node at a newer slot
+            chunk_response(&first_chunk, 10),
+            chunk_response(&second_chunk, 11),
+            chunk_response(&first_chunk, 11),
+            chunk_response(&second_chunk, 11),
+        ]);
+        let snapshot = get_consistent_snapshot(&client, addresses).unwrap();
+        assert!(snapshot.slot == 11);
+        assert!(snapshot.accounts.len() == 150);
+        assert!(snapshot.accounts[0].is_some());
+        assert!(snapshot.accounts[149].is_none());
+    }
+
+    #[test]
+    fn failing_test_inconsistent_snapshot() {
+        let pubkeys: Vec<String> = (0..101).map(|_| Pubkey::new_unique().to_string()).collect();
+        let addresses: Vec<&str> = pubkeys.iter().map(String::as_str).collect();
+        let first_chunk = vec![None; 100];
+        let second_chunk = vec![None; 1];
+
+        let responses = (0..MAX_SNAPSHOT_ATTEMPTS as u64)
+            .flat_map(|attempt| [chunk_response(&first_chunk, attempt * 2), chunk_response(&second_chunk, attempt * 2 + 1)])
+            .collect();
+        let client = mock_client(responses);
+        let result = get_consistent_snapshot(&client, addresses);
+        assert!(matches!(result, Err(ReadTransactionError::InconsistentSnapshot(_))));
+    }
+}
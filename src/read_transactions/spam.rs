@@ -0,0 +1,246 @@
+//! # Spam
+//!
+//! Flags likely spam airdrops in wallet listings. A `SpamClassifier` runs a set of `SpamRule`s
+//! over each holding and adds up the scores of the rules that match. Holdings reaching the
+//! threshold are spam, so one weak signal, e.g a token without a bonding curve, is not enough.
+//!
+//! Portfolios are classified with the default classifier. Custom rules implement `SpamRule`
+//! and are added with `SpamClassifier::add_rule`, then applied with `classify_portfolios`.
+
+use solana_sdk::pubkey::Pubkey;
+use super::portfolio::{PortfolioHolding, Portfolios};
+
+/// Score from which a holding is spam with the default classifier.
+pub const DEFAULT_SPAM_THRESHOLD: u32 = 2;
+
+/// Rule of a `SpamClassifier` matching a holding.
+///
+/// ### Fields
+///
+/// - `rule`: Name of the rule.
+/// - `reason`: Why the holding matched.
+/// - `score`: Score added to the holding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpamFlag {
+    pub rule: String,
+    pub reason: String,
+    pub score: u32,
+}
+
+/// Result of classifying a holding, not spam and without flags by default.
+///
+/// ### Fields
+///
+/// - `flags`: Rules the holding matched.
+/// - `score`: Sum of the scores of the flags.
+/// - `is_spam`: Whether the score reached the threshold of the classifier.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpamVerdict {
+    pub flags: Vec<SpamFlag>,
+    pub score: u32,
+    pub is_spam: bool,
+}
+
+/// Signal that a holding may be spam.
+pub trait SpamRule: Send + Sync {
+    /// Returns a flag if the holding matches the rule.
+    fn check(&self, holding: &PortfolioHolding) -> Option<SpamFlag>;
+}
+
+/// Matches tokens that can not be priced, i.e without a bonding curve to sell into.
+#[derive(Debug, Clone)]
+pub struct NoLiquidity {
+    pub score: u32,
+}
+
+impl SpamRule for NoLiquidity {
+    fn check(&self, holding: &PortfolioHolding) -> Option<SpamFlag> {
+        holding.price_in_sol.is_none().then(|| SpamFlag {
+            rule: "no_liquidity".to_string(),
+            reason: "Token has no liquidity to price it".to_string(),
+            score: self.score,
+        })
+    }
+}
+
+/// Matches priced holdings worth less than `min_value_in_sol`.
+#[derive(Debug, Clone)]
+pub struct ZeroValue {
+    pub min_value_in_sol: f64,
+    pub score: u32,
+}
+
+impl SpamRule for ZeroValue {
+    fn check(&self, holding: &PortfolioHolding) -> Option<SpamFlag> {
+        let value_in_sol = holding.value_in_sol?;
+        (value_in_sol < self.min_value_in_sol).then(|| SpamFlag {
+            rule: "zero_value".to_string(),
+            reason: format!("Holding is worth {} SOL", value_in_sol),
+            score: self.score,
+        })
+    }
+}
+
+/// Matches metadata typical of scam airdrops: a name or symbol advertising a website or a
+/// claim, or a URI that is not served over https, IPFS or Arweave.
+#[derive(Debug, Clone)]
+pub struct SuspiciousMetadata {
+    pub keywords: Vec<String>,
+    pub score: u32,
+}
+
+impl Default for SuspiciousMetadata {
+    fn default() -> Self {
+        let keywords = ["claim", "airdrop", "reward", "visit", "voucher", "http", "www.", ".com", ".io", ".xyz", ".net"];
+        Self { keywords: keywords.iter().map(|keyword| keyword.to_string()).collect(), score: 2 }
+    }
+}
+
+impl SpamRule for SuspiciousMetadata {
+    fn check(&self, holding: &PortfolioHolding) -> Option<SpamFlag> {
+        let flag = |reason: String| Some(SpamFlag { rule: "suspicious_metadata".to_string(), reason, score: self.score });
+        for text in [&holding.name, &holding.symbol].into_iter().flatten() {
+            let lowercase_text = text.to_lowercase();
+            if let Some(keyword) = self.keywords.iter().find(|keyword| lowercase_text.contains(keyword.as_str())) {
+                return flag(format!("Metadata {:?} contains {:?}", text, keyword));
+            }
+        }
+        let uri = holding.uri.as_deref().filter(|uri| !uri.is_empty())?;
+        let is_trusted_scheme = ["https://", "ipfs://", "ar://"].iter().any(|scheme| uri.starts_with(scheme));
+        if !is_trusted_scheme {
+            return flag(format!("Metadata URI {:?} is not https, IPFS or Arweave", uri));
+        }
+        None
+    }
+}
+
+/// Matches tokens whose metadata update authority is a known scam creator.
+#[derive(Debug, Clone)]
+pub struct KnownCreators {
+    pub creators: Vec<Pubkey>,
+    pub score: u32,
+}
+
+impl SpamRule for KnownCreators {
+    fn check(&self, holding: &PortfolioHolding) -> Option<SpamFlag> {
+        let creator = holding.update_authority.filter(|creator| self.creators.contains(creator))?;
+        Some(SpamFlag {
+            rule: "known_creator".to_string(),
+            reason: format!("Token was created by {}", creator),
+            score: self.score,
+        })
+    }
+}
+
+/// Classifies holdings with a set of rules. The default classifier uses `NoLiquidity`,
+/// `ZeroValue` and `SuspiciousMetadata` with a threshold of `DEFAULT_SPAM_THRESHOLD`.
+///
+/// ## Example
+///
+/// ```rust
+/// use easy_solana::read_transactions::spam::{KnownCreators, SpamClassifier};
+/// use solana_sdk::pubkey::Pubkey;
+///
+/// let mut classifier = SpamClassifier::default();
+/// classifier.add_rule(KnownCreators { creators: vec![Pubkey::new_unique()], score: 2 });
+/// ```
+pub struct SpamClassifier {
+    rules: Vec<Box<dyn SpamRule>>,
+    pub threshold: u32,
+}
+
+impl Default for SpamClassifier {
+    fn default() -> Self {
+        let mut classifier = Self::new(DEFAULT_SPAM_THRESHOLD);
+        classifier
+            .add_rule(NoLiquidity { score: 1 })
+            .add_rule(ZeroValue { min_value_in_sol: 0.000_001, score: 1 })
+            .add_rule(SuspiciousMetadata::default());
+        classifier
+    }
+}
+
+impl SpamClassifier {
+    /// Creates a classifier without rules.
+    pub fn new(threshold: u32) -> Self {
+        Self { rules: Vec::new(), threshold }
+    }
+
+    pub fn add_rule(&mut self, rule: impl SpamRule + 'static) -> &mut Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    pub fn classify(&self, holding: &PortfolioHolding) -> SpamVerdict {
+        let flags: Vec<SpamFlag> = self.rules.iter().filter_map(|rule| rule.check(holding)).collect();
+        let score = flags.iter().map(|flag| flag.score).fold(0, u32::saturating_add);
+        SpamVerdict { flags, score, is_spam: score >= self.threshold }
+    }
+
+    /// Classifies every holding of `portfolios` again, e.g with custom rules after `get_portfolios`.
+    pub fn classify_portfolios(&self, portfolios: &mut Portfolios) {
+        for holding in portfolios.wallets.iter_mut().flat_map(|wallet| &mut wallet.holdings) {
+            holding.spam = self.classify(holding);
+            if let Some(aggregate_holding) = portfolios.holdings.iter_mut().find(|aggregate_holding| aggregate_holding.mint == holding.mint) {
+                aggregate_holding.is_spam = holding.spam.is_spam;
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holding() -> PortfolioHolding {
+        PortfolioHolding {
+            mint: Pubkey::new_unique(),
+            token_account: Pubkey::new_unique(),
+            token_program: Pubkey::new_unique(),
+            amount: 1_000_000,
+            decimals: 6,
+            ui_amount: 1.0,
+            name: Some("Token".to_string()),
+            symbol: Some("TKN".to_string()),
+            uri: Some("https://ipfs.io/ipfs/metadata.json".to_string()),
+            update_authority: Some(Pubkey::new_unique()),
+            price_in_sol: None,
+            value_in_sol: None,
+            value_in_usd: None,
+            spam: SpamVerdict::default(),
+        }
+    }
+
+    #[test]
+    fn test_single_weak_signal_is_not_spam() {
+        // e.g a stablecoin without a bonding curve
+        let verdict = SpamClassifier::default().classify(&holding());
+        assert!(verdict.flags.len() == 1 && verdict.flags[0].rule == "no_liquidity");
+        assert!(!verdict.is_spam);
+    }
+
+    #[test]
+    fn test_spam_airdrops_are_flagged() {
+        let classifier = SpamClassifier::default();
+        let mut airdrop = holding();
+        airdrop.name = Some("Visit claim-rewards.com".to_string());
+        assert!(classifier.classify(&airdrop).is_spam);
+
+        let mut airdrop = holding();
+        airdrop.uri = Some("http://bit.ly/claim".to_string());
+        let verdict = classifier.classify(&airdrop);
+        assert!(verdict.is_spam && verdict.score == 3);
+
+        let mut dust = holding();
+        (dust.price_in_sol, dust.value_in_sol) = (Some(0.0), Some(0.0));
+        assert!(!classifier.classify(&dust).is_spam);
+
+        let mut classifier = SpamClassifier::default();
+        let creator = holding().update_authority.unwrap();
+        let mut scam = holding();
+        scam.update_authority = Some(creator);
+        classifier.add_rule(KnownCreators { creators: vec![creator], score: 2 });
+        assert!(classifier.classify(&scam).is_spam);
+    }
+}
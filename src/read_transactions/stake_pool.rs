@@ -0,0 +1,178 @@
+//! # Stake Pools
+//!
+//! Values liquid staking tokens in SOL from the accounts of the pools backing them. A pool token
+//! is worth the lamports staked by the pool divided by the pool tokens minted, which grows every
+//! epoch as staking rewards are added.
+//!
+//! SPL stake pools are found from their pool mint, so any token of the SPL stake pool program
+//! can be valued without knowing the address of its pool.
+
+use std::collections::HashMap;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType}
+};
+use solana_sdk::pubkey::Pubkey;
+use crate::{
+    constants::stake_pool_accounts::{
+        bsol_mint, jitosol_mint, marinade_state, msol_mint, spl_stake_pool_program, MARINADE_MSOL_PRICE_OFFSET,
+        MARINADE_PRICE_DENOMINATOR, STAKE_POOL_ACCOUNT_TYPE, STAKE_POOL_MINT_OFFSET, STAKE_POOL_TOTAL_LAMPORTS_OFFSET
+    },
+    error::ReadTransactionError
+};
+
+/// Account holding the exchange rate of a liquid staking token.
+///
+/// - SplStakePool: Stake pool of the SPL stake pool program minting the token, used by JitoSOL and bSOL.
+/// - Marinade: State account of the Marinade program, used by mSOL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StakePoolKind {
+    SplStakePool,
+    Marinade(Pubkey),
+}
+
+impl StakePoolKind {
+    /// Reads the SOL value of one pool token from the pool account data, `None` if the data is
+    /// too short or the pool has no tokens minted.
+    pub fn exchange_rate(&self, data: &[u8]) -> Option<f64> {
+        match self {
+            StakePoolKind::SplStakePool => {
+                let total_lamports = read_u64(data, STAKE_POOL_TOTAL_LAMPORTS_OFFSET)?;
+                let pool_token_supply = read_u64(data, STAKE_POOL_TOTAL_LAMPORTS_OFFSET + 8)?;
+                if pool_token_supply == 0 {
+                    return None;
+                }
+                Some(total_lamports as f64 / pool_token_supply as f64)
+            }
+            StakePoolKind::Marinade(_) => {
+                let msol_price = read_u64(data, MARINADE_MSOL_PRICE_OFFSET)?;
+                Some(msol_price as f64 / MARINADE_PRICE_DENOMINATOR as f64)
+            }
+        }
+    }
+}
+
+/// A liquid staking token and the pool backing it.
+///
+/// ### Fields
+///
+/// - `mint`: Mint of the pool token.
+/// - `symbol`: Symbol of the pool token.
+/// - `pool`: Account holding the exchange rate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiquidStakingToken {
+    pub mint: Pubkey,
+    pub symbol: String,
+    pub pool: StakePoolKind,
+}
+
+/// The major liquid staking tokens recognized by the portfolio valuation.
+pub fn known_liquid_staking_tokens() -> Vec<LiquidStakingToken> {
+    vec![
+        LiquidStakingToken { mint: msol_mint(), symbol: "mSOL".to_string(), pool: StakePoolKind::Marinade(marinade_state()) },
+        LiquidStakingToken { mint: jitosol_mint(), symbol: "JitoSOL".to_string(), pool: StakePoolKind::SplStakePool },
+        LiquidStakingToken { mint: bsol_mint(), symbol: "bSOL".to_string(), pool: StakePoolKind::SplStakePool },
+    ]
+}
+
+/// Gets the SOL value of one token of each liquid staking token, keyed by mint. Tokens whose
+/// pool account can not be read are left out.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::RpcError` if the pool accounts can not be fetched. SPL stake
+/// pools are searched with `getProgramAccounts`, which some providers disable.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_client::rpc_client::RpcClient;
+/// use easy_solana::read_transactions::stake_pool::{get_exchange_rates, known_liquid_staking_tokens};
+///
+/// let client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+/// let exchange_rates = get_exchange_rates(&client, &known_liquid_staking_tokens()).unwrap();
+/// for (mint, sol_per_token) in exchange_rates {
+///     println!("1 {} = {:.6} SOL", mint, sol_per_token);
+/// }
+/// ```
+pub fn get_exchange_rates(client: &RpcClient, tokens: &[LiquidStakingToken]) -> Result<HashMap<Pubkey, f64>, ReadTransactionError> {
+    let mut exchange_rates = HashMap::new();
+    let marinade_tokens: Vec<(&LiquidStakingToken, Pubkey)> = tokens
+        .iter()
+        .filter_map(|token| match token.pool {
+            StakePoolKind::Marinade(state) => Some((token, state)),
+            StakePoolKind::SplStakePool => None,
+        })
+        .collect();
+    if !marinade_tokens.is_empty() {
+        let states: Vec<Pubkey> = marinade_tokens.iter().map(|(_, state)| *state).collect();
+        for ((token, _), account) in marinade_tokens.iter().zip(client.get_multiple_accounts(&states)?) {
+            if let Some(exchange_rate) = account.and_then(|account| token.pool.exchange_rate(&account.data)) {
+                exchange_rates.insert(token.mint, exchange_rate);
+            }
+        }
+    }
+
+    for token in tokens.iter().filter(|token| token.pool == StakePoolKind::SplStakePool) {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, vec![STAKE_POOL_ACCOUNT_TYPE])),
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(STAKE_POOL_MINT_OFFSET, token.mint.to_bytes().to_vec())),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let pools = client.get_program_accounts_with_config(&spl_stake_pool_program(), config)?;
+        if let Some(exchange_rate) = pools.first().and_then(|(_, pool)| token.pool.exchange_rate(&pool.data)) {
+            exchange_rates.insert(token.mint, exchange_rate);
+        }
+    }
+    Ok(exchange_rates)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    data.get(offset..offset + 8)?.try_into().ok().map(u64::from_le_bytes)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use crate::test_utils::{account_with_data, mock_client, multiple_accounts_response, program_accounts_response};
+
+    fn stake_pool_data(total_lamports: u64, pool_token_supply: u64) -> Vec<u8> {
+        let mut data = vec![0; STAKE_POOL_TOTAL_LAMPORTS_OFFSET];
+        data[0] = STAKE_POOL_ACCOUNT_TYPE;
+        data.extend_from_slice(&total_lamports.to_le_bytes());
+        data.extend_from_slice(&pool_token_supply.to_le_bytes());
+        data.extend_from_slice(&[0; 64]);
+        data
+    }
+
+    #[test]
+    fn test_get_exchange_rates() {
+        let mut marinade_data = vec![0; MARINADE_MSOL_PRICE_OFFSET + 64];
+        marinade_data[MARINADE_MSOL_PRICE_OFFSET..MARINADE_MSOL_PRICE_OFFSET + 8]
+            .copy_from_slice(&(MARINADE_PRICE_DENOMINATOR * 5 / 4).to_le_bytes());
+        let client = mock_client(vec![
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(account_with_data(Pubkey::new_unique(), marinade_data))], 1)),
+            (RpcRequest::GetProgramAccounts, program_accounts_response(&[
+                (Pubkey::new_unique(), account_with_data(spl_stake_pool_program(), stake_pool_data(1_100, 1_000))),
+            ])),
+            (RpcRequest::GetProgramAccounts, program_accounts_response(&[
+                (Pubkey::new_unique(), account_with_data(spl_stake_pool_program(), stake_pool_data(0, 0))),
+            ])),
+        ]);
+
+        let exchange_rates = get_exchange_rates(&client, &known_liquid_staking_tokens()).unwrap();
+        assert!(exchange_rates.len() == 2);
+        assert!(exchange_rates[&msol_mint()] == 1.25);
+        assert!((exchange_rates[&jitosol_mint()] - 1.1).abs() < 1e-9);
+    }
+}
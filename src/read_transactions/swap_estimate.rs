@@ -0,0 +1,134 @@
+//! # Swap Estimate
+//!
+//! This module estimates the tokens received for a SOL budget without sending a transaction,
+//! quoting the pump.fun bonding curve while a token is still on it and Raydium otherwise.
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use crate::{
+    constants::{pumpfun_accounts::PUMP_TOKEN_DECIMALS, solana_programs::sol_pubkey},
+    error::ReadTransactionError,
+    pumpfun::{
+        bonding_curve::{get_bonding_curve_account, BondingCurveAccount},
+        global::{get_global_account, GlobalAccount},
+        quote::quote_buy
+    },
+    raydium::compute_swap::get_raydium_swap_quote,
+    utils::sol_to_lamports
+};
+use super::mint_account::get_mint_account;
+
+/// Slippage used for Raydium quotes, it does not affect the expected output.
+const QUOTE_SLIPPAGE_BPS: u32 = 100;
+
+/// Venue a swap is quoted on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SwapVenue {
+    BondingCurve,
+    Raydium,
+}
+
+/// Estimated result of buying a token with SOL.
+///
+/// ### Fields
+///
+/// - `venue`: Venue the swap was quoted on.
+/// - `sol_in`: SOL spent, including fees.
+/// - `tokens_out`: Expected tokens received in ui format.
+/// - `price_impact`: Price impact of the swap in percent.
+/// - `fees`: Fees paid in SOL.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputEstimate {
+    pub venue: SwapVenue,
+    pub sol_in: f64,
+    pub tokens_out: f64,
+    pub price_impact: f64,
+    pub fees: f64,
+}
+
+/// Estimates the tokens received when buying `mint` with `sol_in` SOL. Tokens that are still on
+/// their pump.fun bonding curve are quoted from the curve, all other tokens are quoted through Raydium.
+///
+/// ## Errors
+///
+/// Returns a `ReadTransactionError` if the accounts cannot be fetched or no quote is available.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, read_transactions::swap_estimate::estimate_output};
+///
+/// # async fn example() {
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let estimate = estimate_output(&client, "CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump", 0.5).await.unwrap();
+/// println!("You will receive ~{} tokens", estimate.tokens_out);
+/// # }
+/// ```
+pub async fn estimate_output(client: &RpcClient, mint: &str, sol_in: f64) -> Result<OutputEstimate, ReadTransactionError> {
+    let lamports_in = sol_to_lamports(sol_in);
+
+    if let Some((_, curve_state)) = get_bonding_curve_account(client, mint) {
+        if !curve_state.complete {
+            let global_state = get_global_account(client)?;
+            return estimate_bonding_curve_output(&curve_state, &global_state, lamports_in);
+        }
+    }
+
+    let decimals = get_mint_account(client, mint)?.decimals;
+    let sol_mint = sol_pubkey().to_string();
+    let quote = get_raydium_swap_quote(&sol_mint, lamports_in, mint, QUOTE_SLIPPAGE_BPS)
+        .await
+        .map_err(|err| ReadTransactionError::QuoteError(err.to_string()))?;
+    // Pool fees of a SOL input route are charged in SOL
+    let fee_lamports: u64 = quote.fees
+        .iter()
+        .filter(|(fee_mint, _)| *fee_mint == sol_mint)
+        .map(|(_, fee_amount)| fee_amount)
+        .sum();
+
+    Ok(OutputEstimate {
+        venue: SwapVenue::Raydium,
+        sol_in: quote.input_amount as f64 / LAMPORTS_PER_SOL as f64,
+        tokens_out: quote.output_amount as f64 / 10_f64.powi(decimals as i32),
+        price_impact: quote.price_impact_pct,
+        fees: fee_lamports as f64 / LAMPORTS_PER_SOL as f64,
+    })
+}
+
+/// Estimates the tokens received when spending `lamports_in` lamports on a bonding curve.
+pub fn estimate_bonding_curve_output(
+    curve_state: &BondingCurveAccount,
+    global_state: &GlobalAccount,
+    lamports_in: u64
+) -> Result<OutputEstimate, ReadTransactionError> {
+    let quote = quote_buy(curve_state, global_state, lamports_in)?;
+
+    Ok(OutputEstimate {
+        venue: SwapVenue::BondingCurve,
+        sol_in: quote.total_sol as f64 / LAMPORTS_PER_SOL as f64,
+        tokens_out: quote.token_amount as f64 / 10_f64.powi(PUMP_TOKEN_DECIMALS as i32),
+        price_impact: quote.price_impact,
+        fees: (quote.protocol_fee + quote.creator_fee) as f64 / LAMPORTS_PER_SOL as f64,
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{fresh_bonding_curve, global_account};
+
+    #[test]
+    fn test_estimate_bonding_curve_output() {
+        let curve_state = fresh_bonding_curve();
+        let global_state = global_account();
+
+        let small_estimate = estimate_bonding_curve_output(&curve_state, &global_state, 10_000_000).unwrap();
+        let large_estimate = estimate_bonding_curve_output(&curve_state, &global_state, 3_000_000_000).unwrap();
+        assert!(small_estimate.venue == SwapVenue::BondingCurve);
+        assert!(small_estimate.price_impact > 0.0 && small_estimate.price_impact < 0.1);
+        // 3 SOL is about 10% of the virtual SOL reserves
+        assert!(large_estimate.price_impact > 9.0 && large_estimate.price_impact < 11.0);
+        assert!((large_estimate.fees - 0.0297).abs() < 0.001);
+    }
+}
@@ -0,0 +1,76 @@
+//! # Sysvar
+//!
+//! This module reads the Clock, Rent and EpochSchedule sysvar accounts, which hold
+//! cluster state needed for rent calculations and time based logic.
+
+use serde::de::DeserializeOwned;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    sysvar::{clock::{self, Clock}, epoch_schedule::{self, EpochSchedule}, rent::{self, Rent}}
+};
+use crate::error::ReadTransactionError;
+
+/// Gets the Clock sysvar, holding the current slot, epoch and unix timestamp of the cluster.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, read_transactions::sysvar::get_clock};
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let clock = get_clock(&client).unwrap();
+/// println!("Slot {} at {}", clock.slot, clock.unix_timestamp);
+/// ```
+pub fn get_clock(client: &RpcClient) -> Result<Clock, ReadTransactionError> {
+    get_sysvar(client, &clock::ID)
+}
+
+/// Gets the Rent sysvar, used to calculate the minimum balance of rent exempt accounts.
+pub fn get_rent(client: &RpcClient) -> Result<Rent, ReadTransactionError> {
+    get_sysvar(client, &rent::ID)
+}
+
+/// Gets the EpochSchedule sysvar, used to convert between slots and epochs.
+pub fn get_epoch_schedule(client: &RpcClient) -> Result<EpochSchedule, ReadTransactionError> {
+    get_sysvar(client, &epoch_schedule::ID)
+}
+
+/// Fetches a sysvar account and deserializes its bincode encoded data.
+fn get_sysvar<T: DeserializeOwned>(client: &RpcClient, sysvar_id: &Pubkey) -> Result<T, ReadTransactionError> {
+    let account = client.get_account(sysvar_id)?;
+    bincode::deserialize(&account.data).map_err(|_| ReadTransactionError::DeserializeError)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::sysvar;
+    use crate::test_utils::{account_info_response, account_with_data, mock_client};
+
+    #[test]
+    fn test_get_clock() {
+        let clock = Clock { slot: 100, epoch: 2, unix_timestamp: 1_700_000_000, ..Clock::default() };
+        let account = account_with_data(sysvar::ID, bincode::serialize(&clock).unwrap());
+        let client = mock_client(vec![(RpcRequest::GetAccountInfo, account_info_response(Some(&account), 100))]);
+        assert!(get_clock(&client).unwrap() == clock);
+    }
+
+    #[test]
+    fn test_get_rent() {
+        let rent = Rent::default();
+        let account = account_with_data(sysvar::ID, bincode::serialize(&rent).unwrap());
+        let client = mock_client(vec![(RpcRequest::GetAccountInfo, account_info_response(Some(&account), 1))]);
+        let fetched_rent = get_rent(&client).unwrap();
+        assert!(fetched_rent.minimum_balance(165) == rent.minimum_balance(165));
+    }
+
+    #[test]
+    fn failing_test_get_epoch_schedule_invalid_data() {
+        let account = account_with_data(sysvar::ID, vec![1, 2, 3]);
+        let client = mock_client(vec![(RpcRequest::GetAccountInfo, account_info_response(Some(&account), 1))]);
+        assert!(matches!(get_epoch_schedule(&client), Err(ReadTransactionError::DeserializeError)));
+    }
+}
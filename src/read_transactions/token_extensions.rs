@@ -0,0 +1,270 @@
+//! # Token Extensions
+//!
+//! Parses mints and token accounts of both token programs. Token-2022 accounts store extensions
+//! after the base state, which `spl_token` can not unpack, so they are read with
+//! `StateWithExtensions` and their base state converted to the `spl_token` types. The transfer
+//! fee, permanent delegate and interest-bearing extensions of Token-2022 mints are surfaced in
+//! `TokenExtensions`.
+
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::{Account as SplTokenAccount, AccountState as SplAccountState, Mint as SplMintAccount};
+use spl_token_2022::{
+    extension::{
+        interest_bearing_mint::InterestBearingConfig,
+        permanent_delegate::PermanentDelegate,
+        transfer_fee::{TransferFee, TransferFeeConfig},
+        BaseStateWithExtensions,
+        StateWithExtensions
+    },
+    state::{Account as Token2022Account, AccountState, Mint as Token2022Mint}
+};
+use crate::{
+    constants::{solana_programs::token_2022_program, units::BASIS_POINTS_DIVISOR},
+    error::ReadTransactionError
+};
+
+/// Transfer fee of a Token-2022 mint from an epoch on.
+///
+/// ### Fields
+///
+/// - `epoch`: First epoch the fee applies to.
+/// - `maximum_fee`: Highest fee of a transfer, in raw token units.
+/// - `fee_basis_points`: Fee of a transfer as a share of its amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferFeeRate {
+    pub epoch: u64,
+    pub maximum_fee: u64,
+    pub fee_basis_points: u16,
+}
+
+impl TransferFeeRate {
+    /// Fee withheld from a transfer of `amount`, rounded up like the token program.
+    pub fn fee(&self, amount: u64) -> u64 {
+        let fee = (amount as u128 * self.fee_basis_points as u128).div_ceil(BASIS_POINTS_DIVISOR);
+        fee.min(self.maximum_fee as u128) as u64
+    }
+}
+
+impl From<&TransferFee> for TransferFeeRate {
+    fn from(transfer_fee: &TransferFee) -> Self {
+        Self {
+            epoch: u64::from(transfer_fee.epoch),
+            maximum_fee: u64::from(transfer_fee.maximum_fee),
+            fee_basis_points: u16::from(transfer_fee.transfer_fee_basis_points),
+        }
+    }
+}
+
+/// Transfer fee extension of a Token-2022 mint. Fees are withheld in the recipient token
+/// account and can be withdrawn by the withdraw authority.
+///
+/// ### Fields
+///
+/// - `authority`: Account allowed to change the fee, `None` if the fee is fixed.
+/// - `withdraw_withheld_authority`: Account allowed to withdraw withheld fees.
+/// - `withheld_amount`: Fees harvested to the mint and not withdrawn yet.
+/// - `older`: Fee before the epoch of `newer`.
+/// - `newer`: Latest fee, which applies from its epoch on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferFeeExtension {
+    pub authority: Option<Pubkey>,
+    pub withdraw_withheld_authority: Option<Pubkey>,
+    pub withheld_amount: u64,
+    pub older: TransferFeeRate,
+    pub newer: TransferFeeRate,
+}
+
+impl TransferFeeExtension {
+    /// Fee applying to transfers in `epoch`.
+    pub fn rate_at(&self, epoch: u64) -> &TransferFeeRate {
+        match epoch >= self.newer.epoch {
+            true => &self.newer,
+            false => &self.older,
+        }
+    }
+}
+
+/// Interest-bearing extension of a Token-2022 mint. Balances do not change, the interest is
+/// only applied when displaying amounts.
+///
+/// ### Fields
+///
+/// - `rate_authority`: Account allowed to change the rate, `None` if the rate is fixed.
+/// - `initialization_timestamp`: Unix timestamp the interest accrues from.
+/// - `pre_update_average_rate`: Average rate in basis points before the last rate update.
+/// - `last_update_timestamp`: Unix timestamp of the last rate update.
+/// - `current_rate`: Yearly rate in basis points since the last update, negative rates shrink
+///   displayed amounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterestBearingExtension {
+    pub rate_authority: Option<Pubkey>,
+    pub initialization_timestamp: i64,
+    pub pre_update_average_rate: i16,
+    pub last_update_timestamp: i64,
+    pub current_rate: i16,
+}
+
+/// Extensions of a Token-2022 mint, `None` for extensions the mint does not have.
+///
+/// ### Fields
+///
+/// - `transfer_fee`: Fee withheld from every transfer.
+/// - `permanent_delegate`: Account allowed to transfer or burn tokens of any holder.
+/// - `interest_bearing`: Interest applied to displayed amounts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenExtensions {
+    pub transfer_fee: Option<TransferFeeExtension>,
+    pub permanent_delegate: Option<Pubkey>,
+    pub interest_bearing: Option<InterestBearingExtension>,
+}
+
+impl TokenExtensions {
+    fn from_mint_state(mint_state: &StateWithExtensions<Token2022Mint>) -> Self {
+        Self {
+            transfer_fee: mint_state.get_extension::<TransferFeeConfig>().ok().map(|config| TransferFeeExtension {
+                authority: config.transfer_fee_config_authority.into(),
+                withdraw_withheld_authority: config.withdraw_withheld_authority.into(),
+                withheld_amount: u64::from(config.withheld_amount),
+                older: TransferFeeRate::from(&config.older_transfer_fee),
+                newer: TransferFeeRate::from(&config.newer_transfer_fee),
+            }),
+            permanent_delegate: mint_state
+                .get_extension::<PermanentDelegate>()
+                .ok()
+                .and_then(|extension| Option::<Pubkey>::from(extension.delegate)),
+            interest_bearing: mint_state.get_extension::<InterestBearingConfig>().ok().map(|config| InterestBearingExtension {
+                rate_authority: config.rate_authority.into(),
+                initialization_timestamp: i64::from(config.initialization_timestamp),
+                pre_update_average_rate: i16::from(config.pre_update_average_rate),
+                last_update_timestamp: i64::from(config.last_update_timestamp),
+                current_rate: i16::from(config.current_rate),
+            }),
+        }
+    }
+}
+
+/// Unpacks the mint data of an account owned by `owner`, with the extensions of Token-2022 mints.
+/// Mints of other owners are unpacked as `spl_token` mints and have no extensions.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::DeserializeError` if the data is not a mint.
+pub fn unpack_mint(owner: &Pubkey, data: &[u8]) -> Result<(SplMintAccount, Option<TokenExtensions>), ReadTransactionError> {
+    if *owner != token_2022_program() {
+        let mint = SplMintAccount::unpack(data).map_err(|_| ReadTransactionError::DeserializeError)?;
+        return Ok((mint, None));
+    }
+    let mint_state = StateWithExtensions::<Token2022Mint>::unpack(data).map_err(|_| ReadTransactionError::DeserializeError)?;
+    let base = mint_state.base;
+    let mint = SplMintAccount {
+        mint_authority: base.mint_authority,
+        supply: base.supply,
+        decimals: base.decimals,
+        is_initialized: base.is_initialized,
+        freeze_authority: base.freeze_authority,
+    };
+    Ok((mint, Some(TokenExtensions::from_mint_state(&mint_state))))
+}
+
+/// Unpacks the token account data of an account owned by `owner`, skipping the extensions of
+/// Token-2022 accounts. Accounts of other owners are unpacked as `spl_token` accounts.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::DeserializeError` if the data is not a token account.
+pub fn unpack_token_account(owner: &Pubkey, data: &[u8]) -> Result<SplTokenAccount, ReadTransactionError> {
+    if *owner != token_2022_program() {
+        return SplTokenAccount::unpack(data).map_err(|_| ReadTransactionError::DeserializeError);
+    }
+    let base = StateWithExtensions::<Token2022Account>::unpack(data).map_err(|_| ReadTransactionError::DeserializeError)?.base;
+    Ok(SplTokenAccount {
+        mint: base.mint,
+        owner: base.owner,
+        amount: base.amount,
+        delegate: base.delegate,
+        state: match base.state {
+            AccountState::Uninitialized => SplAccountState::Uninitialized,
+            AccountState::Initialized => SplAccountState::Initialized,
+            AccountState::Frozen => SplAccountState::Frozen,
+        },
+        is_native: base.is_native,
+        delegated_amount: base.delegated_amount,
+        close_authority: base.close_authority,
+    })
+}
+
+/// Whether a mint has a permanent delegate or a non-zero transfer fee, i.e holders can lose
+/// tokens or receive less than was sent.
+pub fn has_holder_risk(extensions: &TokenExtensions) -> bool {
+    let charges_fee = extensions.transfer_fee.is_some_and(|transfer_fee| {
+        transfer_fee.older.fee_basis_points > 0 || transfer_fee.newer.fee_basis_points > 0
+    });
+    extensions.permanent_delegate.is_some() || charges_fee
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spl_token_2022::extension::{BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut};
+    use crate::constants::solana_programs::token_program;
+
+    fn token_2022_mint_data(permanent_delegate: Pubkey) -> Vec<u8> {
+        let extension_types = [ExtensionType::TransferFeeConfig, ExtensionType::PermanentDelegate, ExtensionType::InterestBearingConfig];
+        let length = ExtensionType::try_calculate_account_len::<Token2022Mint>(&extension_types).unwrap();
+        let mut data = vec![0; length];
+        let mut mint_state = StateWithExtensionsMut::<Token2022Mint>::unpack_uninitialized(&mut data).unwrap();
+        let transfer_fee = mint_state.init_extension::<TransferFeeConfig>(true).unwrap();
+        transfer_fee.withdraw_withheld_authority = Some(permanent_delegate).try_into().unwrap();
+        transfer_fee.older_transfer_fee = TransferFee { epoch: 0.into(), maximum_fee: 5_000.into(), transfer_fee_basis_points: 50.into() };
+        transfer_fee.newer_transfer_fee = TransferFee { epoch: 700.into(), maximum_fee: 5_000.into(), transfer_fee_basis_points: 100.into() };
+        mint_state.init_extension::<PermanentDelegate>(true).unwrap().delegate = Some(permanent_delegate).try_into().unwrap();
+        let interest_bearing = mint_state.init_extension::<InterestBearingConfig>(true).unwrap();
+        interest_bearing.current_rate = 250.into();
+        interest_bearing.initialization_timestamp = 1_700_000_000.into();
+        mint_state.base = Token2022Mint { supply: 1_000_000, decimals: 6, is_initialized: true, ..Default::default() };
+        mint_state.pack_base();
+        mint_state.init_account_type().unwrap();
+        data
+    }
+
+    #[test]
+    fn test_unpack_token_2022_mint_with_extensions() {
+        let permanent_delegate = Pubkey::new_unique();
+        let (mint, extensions) = unpack_mint(&token_2022_program(), &token_2022_mint_data(permanent_delegate)).unwrap();
+        assert!(mint.supply == 1_000_000 && mint.decimals == 6);
+
+        let extensions = extensions.unwrap();
+        assert!(extensions.permanent_delegate == Some(permanent_delegate));
+        let transfer_fee = extensions.transfer_fee.unwrap();
+        assert!(transfer_fee.authority.is_none() && transfer_fee.withdraw_withheld_authority == Some(permanent_delegate));
+        assert!(transfer_fee.rate_at(699).fee(10_000) == 50);
+        assert!(transfer_fee.rate_at(700).fee(10_000) == 100);
+        // rounded up and capped at the maximum fee
+        assert!(transfer_fee.rate_at(700).fee(150) == 2);
+        assert!(transfer_fee.rate_at(700).fee(10_000_000) == 5_000);
+        let interest_bearing = extensions.interest_bearing.unwrap();
+        assert!(interest_bearing.current_rate == 250 && interest_bearing.initialization_timestamp == 1_700_000_000);
+        assert!(has_holder_risk(&extensions));
+
+        // spl token mints can not hold extensions
+        assert!(unpack_mint(&token_program(), &token_2022_mint_data(permanent_delegate)).is_err());
+    }
+
+    #[test]
+    fn test_unpack_token_2022_account() {
+        let (mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let length = ExtensionType::try_calculate_account_len::<Token2022Account>(&[ExtensionType::ImmutableOwner]).unwrap();
+        let mut data = vec![0; length];
+        let mut account_state = StateWithExtensionsMut::<Token2022Account>::unpack_uninitialized(&mut data).unwrap();
+        account_state.init_extension::<spl_token_2022::extension::immutable_owner::ImmutableOwner>(true).unwrap();
+        account_state.base = Token2022Account { mint, owner, amount: 42, state: AccountState::Frozen, ..Default::default() };
+        account_state.pack_base();
+        account_state.init_account_type().unwrap();
+
+        let token_account = unpack_token_account(&token_2022_program(), &data).unwrap();
+        assert!(token_account.mint == mint && token_account.owner == owner && token_account.amount == 42);
+        assert!(token_account.is_frozen());
+        assert!(unpack_token_account(&token_program(), &data).is_err());
+        assert!(unpack_mint(&token_2022_program(), &data).is_err());
+    }
+}
@@ -0,0 +1,71 @@
+//! # Token Filter
+//!
+//! Filters applied to token account listings, e.g to hide spam airdrops or empty accounts,
+//! before mints are looked up for the remaining accounts.
+
+use solana_sdk::pubkey::Pubkey;
+
+/// Token accounts kept by wallet listings. The default filter keeps every token account.
+///
+/// ### Fields
+///
+/// - `ignore_mints`: Mints that are always hidden.
+/// - `only_mints`: If set, only these mints are listed.
+/// - `hide_zero_balances`: Hides token accounts with a balance of 0.
+/// - `hide_frozen`: Hides token accounts frozen by the freeze authority of their mint.
+///
+/// ## Example
+///
+/// ```rust
+/// use easy_solana::read_transactions::token_filter::TokenFilter;
+/// use solana_sdk::pubkey::Pubkey;
+///
+/// let spam_mint = Pubkey::new_unique();
+/// let filter = TokenFilter { ignore_mints: vec![spam_mint], hide_zero_balances: true, ..Default::default() };
+/// assert!(!filter.allows(&spam_mint, 1_000, false));
+/// assert!(!filter.allows(&Pubkey::new_unique(), 0, false));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TokenFilter {
+    pub ignore_mints: Vec<Pubkey>,
+    pub only_mints: Option<Vec<Pubkey>>,
+    pub hide_zero_balances: bool,
+    pub hide_frozen: bool,
+}
+
+impl TokenFilter {
+    /// Whether a token account of `mint` holding the raw `amount` is kept.
+    pub fn allows(&self, mint: &Pubkey, amount: u64, is_frozen: bool) -> bool {
+        if self.ignore_mints.contains(mint) {
+            return false;
+        }
+        if let Some(only_mints) = &self.only_mints {
+            if !only_mints.contains(mint) {
+                return false;
+            }
+        }
+        let is_hidden = (self.hide_zero_balances && amount == 0) || (self.hide_frozen && is_frozen);
+        !is_hidden
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_filter() {
+        let (mint, other_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(TokenFilter::default().allows(&mint, 0, true));
+
+        let filter = TokenFilter { only_mints: Some(vec![mint]), hide_frozen: true, ..Default::default() };
+        assert!(filter.allows(&mint, 0, false));
+        assert!(!filter.allows(&mint, 1, true));
+        assert!(!filter.allows(&other_mint, 1, false));
+
+        // ignored mints win over allowed mints
+        let filter = TokenFilter { ignore_mints: vec![mint], only_mints: Some(vec![mint]), ..Default::default() };
+        assert!(!filter.allows(&mint, 1, false));
+    }
+}
@@ -0,0 +1,231 @@
+//! # Token Transfers
+//!
+//! This module extracts token transfers from confirmed transactions by diffing
+//! the token balances before and after the transaction.
+
+use std::str::FromStr;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::signature::Signature;
+use solana_transaction_status_client_types::{
+    option_serializer::OptionSerializer,
+    UiTransactionTokenBalance
+};
+use crate::error::ReadTransactionError;
+use super::transaction::fetch_transaction;
+
+/// A token transfer between two wallets within a transaction.
+///
+/// ### Fields
+///
+/// - `mint`: Address of the transferred token.
+/// - `from_owner`: Wallet that sent the tokens, `None` if the tokens were minted.
+/// - `to_owner`: Wallet that received the tokens, `None` if the tokens were burned.
+/// - `amount`: Amount transferred without decimals.
+/// - `decimals`: Decimals of the token.
+/// - `ui_amount`: Amount transferred in ui format e.g 0.1 USDC
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenTransfer {
+    pub mint: String,
+    pub from_owner: Option<String>,
+    pub to_owner: Option<String>,
+    pub amount: u64,
+    pub decimals: u8,
+    pub ui_amount: f64,
+}
+
+/// Net token balance change of a wallet for a single token within a transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenBalanceChange {
+    pub mint: String,
+    pub owner: Option<String>,
+    pub decimals: u8,
+    pub change: i128,
+}
+
+/// Gets the token transfers of a confirmed transaction.
+///
+/// Transfers are derived from the difference between the pre and post token balances
+/// of every wallet. For each token, the wallets whose balance decreased are matched
+/// against the wallets whose balance increased, in the order they appear in the transaction.
+///
+/// # Arguments
+///
+/// * `client` - An instance of the RPC client used to communicate with the blockchain.
+/// * `signature` - Signature of the transaction.
+///
+/// # Returns
+///
+/// `Result<Vec<TokenTransfer>, ReadTransactionError>` - Returns the token transfers on success,
+/// or an error if the signature is invalid or the transaction does not exist.
+pub fn token_transfers_in_transaction(client: &RpcClient, signature: &str) -> Result<Vec<TokenTransfer>, ReadTransactionError> {
+    let signature = Signature::from_str(signature).map_err(|_| ReadTransactionError::InvalidSignature)?;
+    let confirmed_transaction = fetch_transaction(client, &signature)?;
+    let meta = confirmed_transaction.transaction.meta
+        .ok_or(ReadTransactionError::DeserializeError)?;
+
+    let pre_token_balances = match meta.pre_token_balances {
+        OptionSerializer::Some(balances) => balances,
+        _ => vec![],
+    };
+    let post_token_balances = match meta.post_token_balances {
+        OptionSerializer::Some(balances) => balances,
+        _ => vec![],
+    };
+
+    let balance_changes = token_balance_changes(&pre_token_balances, &post_token_balances)?;
+    Ok(token_transfers_from_balance_changes(&balance_changes))
+}
+
+/// Sums the token balance changes of each wallet and token, wallets without any change are left out.
+pub fn token_balance_changes(
+    pre_token_balances: &[UiTransactionTokenBalance],
+    post_token_balances: &[UiTransactionTokenBalance]
+) -> Result<Vec<TokenBalanceChange>, ReadTransactionError> {
+    let mut balance_changes: Vec<TokenBalanceChange> = Vec::new();
+
+    let signed_balances = pre_token_balances
+        .iter()
+        .map(|balance| (balance, -1_i128))
+        .chain(post_token_balances.iter().map(|balance| (balance, 1_i128)));
+
+    for (balance, sign) in signed_balances {
+        let amount = balance.ui_token_amount.amount
+            .parse::<u64>()
+            .map_err(|_| ReadTransactionError::DeserializeError)?;
+        let owner = match &balance.owner {
+            OptionSerializer::Some(owner) => Some(owner.clone()),
+            _ => None,
+        };
+        let existing_change = balance_changes
+            .iter_mut()
+            .find(|change| change.mint == balance.mint && change.owner == owner);
+        match existing_change {
+            Some(change) => change.change += sign * amount as i128,
+            None => balance_changes.push(TokenBalanceChange {
+                mint: balance.mint.clone(),
+                owner,
+                decimals: balance.ui_token_amount.decimals,
+                change: sign * amount as i128,
+            }),
+        }
+    }
+
+    balance_changes.retain(|change| change.change != 0);
+    Ok(balance_changes)
+}
+
+/// Matches wallets whose balance decreased with wallets whose balance increased for each token.
+/// Unmatched increases are treated as mints and unmatched decreases as burns.
+pub fn token_transfers_from_balance_changes(balance_changes: &[TokenBalanceChange]) -> Vec<TokenTransfer> {
+    let mut mints: Vec<&str> = Vec::new();
+    for change in balance_changes {
+        if !mints.contains(&change.mint.as_str()) {
+            mints.push(&change.mint);
+        }
+    }
+
+    let mut transfers = Vec::new();
+    for mint in mints {
+        let mint_changes = balance_changes.iter().filter(|change| change.mint == mint);
+        let mut senders: Vec<(&TokenBalanceChange, u128)> = mint_changes.clone()
+            .filter(|change| change.change < 0)
+            .map(|change| (change, change.change.unsigned_abs()))
+            .collect();
+        let mut receivers: Vec<(&TokenBalanceChange, u128)> = mint_changes
+            .filter(|change| change.change > 0)
+            .map(|change| (change, change.change.unsigned_abs()))
+            .collect();
+
+        let (mut sender_index, mut receiver_index) = (0, 0);
+        while sender_index < senders.len() || receiver_index < receivers.len() {
+            let sender = senders.get_mut(sender_index);
+            let receiver = receivers.get_mut(receiver_index);
+            let (from_owner, to_owner, amount) = match (sender, receiver) {
+                (Some((sender, sent)), Some((receiver, received))) => {
+                    let amount = (*sent).min(*received);
+                    *sent -= amount;
+                    *received -= amount;
+                    if *sent == 0 { sender_index += 1; }
+                    if *received == 0 { receiver_index += 1; }
+                    (sender.owner.clone(), receiver.owner.clone(), amount)
+                }
+                (Some((sender, sent)), None) => {
+                    sender_index += 1;
+                    (sender.owner.clone(), None, *sent)
+                }
+                (None, Some((receiver, received))) => {
+                    receiver_index += 1;
+                    (None, receiver.owner.clone(), *received)
+                }
+                (None, None) => break,
+            };
+
+            let decimals = balance_changes
+                .iter()
+                .find(|change| change.mint == mint)
+                .map(|change| change.decimals)
+                .unwrap_or(0);
+            let amount = amount as u64;
+            transfers.push(TokenTransfer {
+                mint: mint.to_string(),
+                from_owner,
+                to_owner,
+                amount,
+                decimals,
+                ui_amount: amount as f64 / 10_f64.powi(decimals as i32),
+            });
+        }
+    }
+
+    transfers
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALLET_ADDRESS_1: &str = "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5";
+    const WALLET_ADDRESS_2: &str = "joNASGVYc6ugNiUCsamrJ8i2PBoxFW9YvqNisNfFNXg";
+    const USDC_TOKEN_ADDRESS: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    fn balance(account_index: u8, owner: &str, amount: u64) -> UiTransactionTokenBalance {
+        serde_json::from_value(serde_json::json!({
+            "accountIndex": account_index,
+            "mint": USDC_TOKEN_ADDRESS,
+            "owner": owner,
+            "uiTokenAmount": {
+                "amount": amount.to_string(),
+                "decimals": 6,
+                "uiAmount": amount as f64 / 1_000_000.0,
+                "uiAmountString": (amount as f64 / 1_000_000.0).to_string()
+            }
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_token_transfers_from_balances() {
+        let pre_token_balances = vec![balance(1, WALLET_ADDRESS_1, 5_000_000), balance(2, WALLET_ADDRESS_2, 1_000_000)];
+        let post_token_balances = vec![balance(1, WALLET_ADDRESS_1, 3_000_000), balance(2, WALLET_ADDRESS_2, 3_000_000)];
+
+        let balance_changes = token_balance_changes(&pre_token_balances, &post_token_balances).unwrap();
+        let transfers = token_transfers_from_balance_changes(&balance_changes);
+        assert!(transfers.len() == 1);
+        assert!(transfers[0].from_owner.as_deref() == Some(WALLET_ADDRESS_1));
+        assert!(transfers[0].to_owner.as_deref() == Some(WALLET_ADDRESS_2));
+        assert!(transfers[0].amount == 2_000_000);
+        assert!(transfers[0].ui_amount == 2.0);
+    }
+
+    #[test]
+    fn test_token_transfers_from_minted_balance() {
+        // newly created token account has no pre balance
+        let post_token_balances = vec![balance(1, WALLET_ADDRESS_1, 1_000_000)];
+
+        let balance_changes = token_balance_changes(&[], &post_token_balances).unwrap();
+        let transfers = token_transfers_from_balance_changes(&balance_changes);
+        assert!(transfers.len() == 1);
+        assert!(transfers[0].from_owner.is_none());
+        assert!(transfers[0].to_owner.as_deref() == Some(WALLET_ADDRESS_1));
+    }
+}
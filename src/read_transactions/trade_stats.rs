@@ -0,0 +1,314 @@
+//! # Trade Stats
+//!
+//! Volume, trade count and unique traders of a token over the last hour and day, computed
+//! from parsed transactions only. pump.fun trades are read from the trade events of the
+//! program, trades on Raydium and other AMMs from the balance changes of the fee payer.
+//!
+//! `get_trade_stats` backfills the last day from the history of the token, the returned
+//! aggregator is then kept up to date with `push_trade` or `spawn` over a stream of trades.
+
+use std::{
+    collections::{HashSet, VecDeque},
+    str::FromStr,
+    sync::{Arc, RwLock}
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status_client_types::option_serializer::OptionSerializer;
+use crate::{
+    constants::solana_programs::sol_pubkey,
+    error::ReadTransactionError,
+    event_channel::EventReceiver,
+    pumpfun::trade_event::{trade_events_in_transaction, TradeEvent}
+};
+use super::{
+    history::{get_signature_history, HistoryConfig},
+    token_transfers::token_balance_changes,
+    transaction::{fetch_transaction, inspect_transaction}
+};
+
+const HOUR_SECS: i64 = 60 * 60;
+const DAY_SECS: i64 = 24 * HOUR_SECS;
+
+/// A buy or sell of a token against SOL.
+///
+/// ### Fields
+///
+/// - `mint`: Token traded.
+/// - `trader`: Wallet that traded.
+/// - `sol_amount`: SOL swapped in lamports.
+/// - `is_buy`: Whether the trader bought the token.
+/// - `timestamp`: Unix timestamp of the trade in seconds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trade {
+    pub mint: Pubkey,
+    pub trader: Pubkey,
+    pub sol_amount: u64,
+    pub is_buy: bool,
+    pub timestamp: i64,
+}
+
+impl From<&TradeEvent> for Trade {
+    fn from(trade_event: &TradeEvent) -> Self {
+        Self {
+            mint: trade_event.mint,
+            trader: trade_event.user,
+            sol_amount: trade_event.sol_amount,
+            is_buy: trade_event.is_buy,
+            timestamp: trade_event.timestamp,
+        }
+    }
+}
+
+/// Trading activity over a time window, volumes are in SOL.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TradeStats {
+    pub volume_sol: f64,
+    pub buy_volume_sol: f64,
+    pub sell_volume_sol: f64,
+    pub trades: u64,
+    pub unique_traders: usize,
+}
+
+/// Trading activity of a token over the last hour and the last day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenTradeStats {
+    pub mint: Pubkey,
+    pub last_hour: TradeStats,
+    pub last_day: TradeStats,
+}
+
+/// Keeps the trades of a token from the last day, relative to its latest trade, to compute
+/// `TokenTradeStats`. Trades of other tokens are ignored.
+///
+/// ## Example
+///
+/// ```rust
+/// use easy_solana::read_transactions::trade_stats::{Trade, TradeStatsAggregator};
+/// use solana_sdk::pubkey::Pubkey;
+///
+/// let mint = Pubkey::new_unique();
+/// let mut aggregator = TradeStatsAggregator::new(mint);
+/// aggregator.push_trade(&Trade { mint, trader: Pubkey::new_unique(), sol_amount: 1_000_000_000, is_buy: true, timestamp: 1_000 });
+/// let stats = aggregator.stats(1_000);
+/// assert!(stats.last_hour.trades == 1 && stats.last_hour.volume_sol == 1.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TradeStatsAggregator {
+    mint: Pubkey,
+    trades: VecDeque<Trade>,
+}
+
+impl TradeStatsAggregator {
+    pub fn new(mint: Pubkey) -> Self {
+        Self { mint, trades: VecDeque::new() }
+    }
+
+    /// Adds a trade, keeping trades ordered by timestamp. Trades older than a day before the
+    /// latest trade are dropped.
+    pub fn push_trade(&mut self, trade: &Trade) {
+        if trade.mint != self.mint {
+            return;
+        }
+        let index = self.trades.iter().rposition(|known_trade| known_trade.timestamp <= trade.timestamp).map_or(0, |index| index + 1);
+        self.trades.insert(index, trade.clone());
+
+        let latest_timestamp = self.trades.back().map(|trade| trade.timestamp).unwrap_or_default();
+        while self.trades.front().is_some_and(|trade| trade.timestamp <= latest_timestamp - DAY_SECS) {
+            self.trades.pop_front();
+        }
+    }
+
+    pub fn push_trade_event(&mut self, trade_event: &TradeEvent) {
+        self.push_trade(&Trade::from(trade_event));
+    }
+
+    /// Stats of the hour and the day before `now`, a unix timestamp in seconds.
+    pub fn stats(&self, now: i64) -> TokenTradeStats {
+        TokenTradeStats {
+            mint: self.mint,
+            last_hour: self.stats_since(now - HOUR_SECS, now),
+            last_day: self.stats_since(now - DAY_SECS, now),
+        }
+    }
+
+    /// Consumes a stream of trades in the background. The returned aggregator is updated as
+    /// trades arrive and can be read at any time, the task ends when the sender is dropped.
+    pub fn spawn(self, mut receiver: EventReceiver<Trade>) -> Arc<RwLock<TradeStatsAggregator>> {
+        let aggregator = Arc::new(RwLock::new(self));
+        let task_aggregator = Arc::clone(&aggregator);
+        tokio::spawn(async move {
+            while let Some(trade) = receiver.recv().await {
+                if let Ok(mut aggregator) = task_aggregator.write() {
+                    aggregator.push_trade(&trade);
+                }
+            }
+        });
+        aggregator
+    }
+
+    fn stats_since(&self, start_time: i64, now: i64) -> TradeStats {
+        let mut stats = TradeStats::default();
+        let mut traders = HashSet::new();
+        for trade in self.trades.iter().filter(|trade| trade.timestamp > start_time && trade.timestamp <= now) {
+            let sol_amount = trade.sol_amount as f64 / LAMPORTS_PER_SOL as f64;
+            if trade.is_buy {
+                stats.buy_volume_sol += sol_amount;
+            } else {
+                stats.sell_volume_sol += sol_amount;
+            }
+            stats.trades += 1;
+            traders.insert(trade.trader);
+        }
+        stats.volume_sol = stats.buy_volume_sol + stats.sell_volume_sol;
+        stats.unique_traders = traders.len();
+        stats
+    }
+}
+
+/// Backfills the trades of the day before `now` and returns the aggregator, to be kept up to
+/// date with new trades. The history of `pool` is read if set, which is required for Raydium
+/// pools whose swaps do not reference the mint, otherwise the history of the mint.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InvalidAddress` if an address is invalid, or an error if
+/// the history can not be fetched.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_client::rpc_client::RpcClient;
+/// use easy_solana::read_transactions::trade_stats::get_trade_stats;
+///
+/// let client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+/// let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64;
+/// let aggregator = get_trade_stats(&client, "2qEHjDLDLbuBgRYvsxhc5D6uDWAivNFZGan56P1tpump", None, now).unwrap();
+/// let stats = aggregator.stats(now);
+/// println!("24h volume: {:.2} SOL from {} traders", stats.last_day.volume_sol, stats.last_day.unique_traders);
+/// ```
+pub fn get_trade_stats(client: &RpcClient, mint: &str, pool: Option<&str>, now: i64) -> Result<TradeStatsAggregator, ReadTransactionError> {
+    let mint = Pubkey::from_str(mint)?;
+    let mint_address = mint.to_string();
+    let config = HistoryConfig { limit: None, start_time: Some(now - DAY_SECS), end_time: Some(now), include_failed: false };
+    let signatures = get_signature_history(client, pool.unwrap_or(&mint_address), &config)?;
+
+    let mut aggregator = TradeStatsAggregator::new(mint);
+    for signature_info in signatures.iter().rev() {
+        for trade in trades_in_transaction(client, &signature_info.signature, &mint)? {
+            aggregator.push_trade(&trade);
+        }
+    }
+    Ok(aggregator)
+}
+
+/// Gets the trades of `mint` in a transaction. pump.fun trades are read from the trade events,
+/// otherwise a swap by the fee payer is read from its balance changes, including the SOL it
+/// wrapped. Rent paid for new accounts is counted in the SOL amount of such swaps.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InvalidSignature` if the signature is invalid, or an error
+/// if the transaction can not be fetched or decoded.
+pub fn trades_in_transaction(client: &RpcClient, signature: &str, mint: &Pubkey) -> Result<Vec<Trade>, ReadTransactionError> {
+    let signature = Signature::from_str(signature).map_err(|_| ReadTransactionError::InvalidSignature)?;
+    let confirmed_transaction = fetch_transaction(client, &signature)?;
+    let meta = confirmed_transaction.transaction.meta.clone().ok_or(ReadTransactionError::DeserializeError)?;
+    let transaction = inspect_transaction(client, &signature, confirmed_transaction)?;
+    if transaction.error.is_some() {
+        return Ok(vec![]);
+    }
+
+    let trades: Vec<Trade> = trade_events_in_transaction(&transaction)
+        .iter()
+        .filter(|trade_event| trade_event.mint == *mint)
+        .map(Trade::from)
+        .collect();
+    if !trades.is_empty() {
+        return Ok(trades);
+    }
+
+    let Some(trader) = transaction.account_keys.first().copied() else {
+        return Ok(vec![]);
+    };
+    let (pre_token_balances, post_token_balances) = match (meta.pre_token_balances, meta.post_token_balances) {
+        (OptionSerializer::Some(pre_token_balances), OptionSerializer::Some(post_token_balances)) => (pre_token_balances, post_token_balances),
+        _ => return Ok(vec![]),
+    };
+    let balance_changes = token_balance_changes(&pre_token_balances, &post_token_balances)?;
+    let trader_change = |change_mint: &Pubkey| {
+        balance_changes
+            .iter()
+            .filter(|change| change.mint == change_mint.to_string() && change.owner == Some(trader.to_string()))
+            .map(|change| change.change)
+            .sum::<i128>()
+    };
+    let lamport_change = match (meta.pre_balances.first(), meta.post_balances.first()) {
+        (Some(pre_balance), Some(post_balance)) => *post_balance as i128 - *pre_balance as i128 + meta.fee as i128,
+        _ => 0,
+    };
+    let sol_change = lamport_change + trader_change(&sol_pubkey());
+    let timestamp = transaction.block_time.unwrap_or_default();
+    Ok(swap_trade(*mint, trader, trader_change(mint), sol_change, timestamp).into_iter().collect())
+}
+
+/// A swap of `mint` against SOL from the balance changes of the trader, `None` if the changes
+/// are not a buy or a sell.
+fn swap_trade(mint: Pubkey, trader: Pubkey, token_change: i128, sol_change: i128, timestamp: i64) -> Option<Trade> {
+    let is_buy = match (token_change.signum(), sol_change.signum()) {
+        (1, -1) => true,
+        (-1, 1) => false,
+        _ => return None,
+    };
+    Some(Trade {
+        mint,
+        trader,
+        sol_amount: u64::try_from(sol_change.unsigned_abs()).ok()?,
+        is_buy,
+        timestamp,
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(mint: Pubkey, trader: Pubkey, sol_amount: u64, is_buy: bool, timestamp: i64) -> Trade {
+        Trade { mint, trader, sol_amount, is_buy, timestamp }
+    }
+
+    #[test]
+    fn test_trade_stats_windows() {
+        let mint = Pubkey::new_unique();
+        let (trader, other_trader) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let now = 100_000;
+        let mut aggregator = TradeStatsAggregator::new(mint);
+        aggregator.push_trade(&trade(mint, trader, LAMPORTS_PER_SOL, true, now - 2 * HOUR_SECS));
+        aggregator.push_trade(&trade(mint, trader, 2 * LAMPORTS_PER_SOL, false, now - 60));
+        // trades arriving out of order and of other tokens
+        aggregator.push_trade(&trade(mint, other_trader, LAMPORTS_PER_SOL / 2, true, now - 120));
+        aggregator.push_trade(&trade(Pubkey::new_unique(), other_trader, LAMPORTS_PER_SOL, true, now));
+
+        let stats = aggregator.stats(now);
+        assert!(stats.last_hour == TradeStats { volume_sol: 2.5, buy_volume_sol: 0.5, sell_volume_sol: 2.0, trades: 2, unique_traders: 2 });
+        assert!(stats.last_day.trades == 3 && stats.last_day.volume_sol == 3.5 && stats.last_day.unique_traders == 2);
+
+        // a trade a day later drops the older trades
+        aggregator.push_trade(&trade(mint, trader, LAMPORTS_PER_SOL, true, now + DAY_SECS - 90));
+        assert!(aggregator.trades.len() == 2);
+        let stats = aggregator.stats(now + DAY_SECS - 90);
+        assert!(stats.last_day.trades == 2 && stats.last_hour.trades == 1);
+    }
+
+    #[test]
+    fn test_swap_trade_from_balance_changes() {
+        let (mint, trader) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let buy = swap_trade(mint, trader, 1_000, -(LAMPORTS_PER_SOL as i128), 10).unwrap();
+        assert!(buy.is_buy && buy.sol_amount == LAMPORTS_PER_SOL);
+        let sell = swap_trade(mint, trader, -1_000, 5_000, 10).unwrap();
+        assert!(!sell.is_buy && sell.sol_amount == 5_000);
+        // a transfer of tokens without SOL is not a trade
+        assert!(swap_trade(mint, trader, 1_000, 0, 10).is_none());
+    }
+}
@@ -0,0 +1,298 @@
+//! # Transaction
+//!
+//! This module contains functions and structures for fetching and inspecting
+//! confirmed transactions, including versioned (v0) transactions that load
+//! accounts from address lookup tables.
+
+use std::str::FromStr;
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::{
+    bs58,
+    commitment_config::CommitmentConfig,
+    message::{v0::MessageAddressTableLookup, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::TransactionError
+};
+use solana_transaction_status_client_types::{
+    option_serializer::OptionSerializer,
+    EncodedConfirmedTransactionWithStatusMeta,
+    UiInstruction,
+    UiTransactionEncoding
+};
+use crate::{
+    error::ReadTransactionError,
+    utils::addresses_to_pubkeys
+};
+use super::{
+    cpi_tree::{build_cpi_tree, CpiNode},
+    zero_copy::AddressLookupTableView
+};
+
+/// A confirmed transaction with all account keys resolved, including accounts loaded
+/// from address lookup tables.
+///
+/// ### Fields
+///
+/// - `signature`: The first signature of the transaction.
+/// - `slot`: The slot the transaction was confirmed in.
+/// - `block_time`: Estimated unix timestamp of the block, if available.
+/// - `is_versioned`: Whether the transaction is a versioned (v0) transaction.
+/// - `account_keys`: Full account list, static keys followed by writable and then readonly lookup table addresses.
+/// - `instructions`: Top level instructions of the transaction.
+/// - `inner_instructions`: Instructions invoked by the top level instructions (CPIs).
+/// - `logs`: Log messages of the transaction.
+/// - `fee`: Fee paid in lamports.
+/// - `error`: The transaction error if the transaction failed.
+#[derive(Debug, Clone)]
+pub struct InspectedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub is_versioned: bool,
+    pub account_keys: Vec<Pubkey>,
+    pub instructions: Vec<InspectedInstruction>,
+    pub inner_instructions: Vec<InspectedInnerInstructions>,
+    pub logs: Vec<String>,
+    pub fee: u64,
+    pub error: Option<TransactionError>,
+}
+
+impl InspectedTransaction {
+    /// Builds the tree of cross program invocations of each top level instruction.
+    pub fn cpi_tree(&self) -> Vec<CpiNode> {
+        build_cpi_tree(&self.instructions, &self.inner_instructions)
+    }
+}
+
+/// An instruction with its program and accounts resolved to pubkeys.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InspectedInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub data: Vec<u8>,
+    pub stack_height: Option<u32>,
+}
+
+/// Inner instructions invoked by the top level instruction at `index`.
+#[derive(Debug, Clone)]
+pub struct InspectedInnerInstructions {
+    pub index: u8,
+    pub instructions: Vec<InspectedInstruction>,
+}
+
+/// Fetches a confirmed transaction and resolves its instructions, supporting both legacy and
+/// versioned (v0) transactions. Accounts loaded from address lookup tables are taken from the
+/// transaction metadata, or fetched from the lookup tables if the RPC does not return them.
+///
+/// # Arguments
+///
+/// * `client` - An instance of the RPC client used to communicate with the blockchain.
+/// * `signature` - Signature of the transaction.
+///
+/// # Returns
+///
+/// `Result<InspectedTransaction, ReadTransactionError>` - Returns the `InspectedTransaction` on success,
+/// or an error if the signature is invalid, the transaction does not exist or cannot be decoded.
+pub fn get_transaction(client: &RpcClient, signature: &str) -> Result<InspectedTransaction, ReadTransactionError> {
+    let signature = Signature::from_str(signature).map_err(|_| ReadTransactionError::InvalidSignature)?;
+    let confirmed_transaction = fetch_transaction(client, &signature)?;
+    inspect_transaction(client, &signature, confirmed_transaction)
+}
+
+/// Resolves the instructions of a transaction fetched with `fetch_transaction`, only fetching
+/// lookup tables the RPC did not resolve.
+pub(crate) fn inspect_transaction(
+    client: &RpcClient,
+    signature: &Signature,
+    confirmed_transaction: EncodedConfirmedTransactionWithStatusMeta
+) -> Result<InspectedTransaction, ReadTransactionError> {
+    let transaction = confirmed_transaction.transaction.transaction
+        .decode()
+        .ok_or(ReadTransactionError::DeserializeError)?;
+    let meta = confirmed_transaction.transaction.meta
+        .ok_or(ReadTransactionError::DeserializeError)?;
+    let message = &transaction.message;
+
+    // Accounts loaded from lookup tables, as returned by the RPC
+    let loaded_addresses = match &meta.loaded_addresses {
+        OptionSerializer::Some(loaded_addresses) => Some((
+            addresses_to_pubkeys(loaded_addresses.writable.iter().map(String::as_str).collect()),
+            addresses_to_pubkeys(loaded_addresses.readonly.iter().map(String::as_str).collect()),
+        )),
+        _ => None,
+    };
+    let (writable_addresses, readonly_addresses) = match (loaded_addresses, message.address_table_lookups()) {
+        (Some(loaded_addresses), _) => loaded_addresses,
+        (None, Some(lookups)) if !lookups.is_empty() => resolve_address_lookup_tables(client, lookups)?,
+        _ => (vec![], vec![]),
+    };
+    let account_keys = reconstruct_account_keys(message.static_account_keys(), &writable_addresses, &readonly_addresses);
+
+    let instructions = message
+        .instructions()
+        .iter()
+        .map(|instruction| resolve_instruction(&account_keys, instruction.program_id_index, &instruction.accounts, instruction.data.clone(), Some(1)))
+        .collect::<Result<Vec<InspectedInstruction>, ReadTransactionError>>()?;
+
+    let mut inner_instructions = Vec::new();
+    if let OptionSerializer::Some(ui_inner_instructions) = &meta.inner_instructions {
+        for ui_inner_instruction in ui_inner_instructions {
+            let mut instructions = Vec::new();
+            for ui_instruction in &ui_inner_instruction.instructions {
+                if let UiInstruction::Compiled(compiled_instruction) = ui_instruction {
+                    let data = bs58::decode(&compiled_instruction.data)
+                        .into_vec()
+                        .map_err(|_| ReadTransactionError::DeserializeError)?;
+                    instructions.push(resolve_instruction(
+                        &account_keys,
+                        compiled_instruction.program_id_index,
+                        &compiled_instruction.accounts,
+                        data,
+                        compiled_instruction.stack_height
+                    )?);
+                }
+            }
+            inner_instructions.push(InspectedInnerInstructions {
+                index: ui_inner_instruction.index,
+                instructions,
+            });
+        }
+    }
+
+    let logs = match meta.log_messages {
+        OptionSerializer::Some(logs) => logs,
+        _ => vec![],
+    };
+
+    Ok(InspectedTransaction {
+        signature: signature.to_string(),
+        slot: confirmed_transaction.slot,
+        block_time: confirmed_transaction.block_time,
+        is_versioned: matches!(message, VersionedMessage::V0(_)),
+        account_keys,
+        instructions,
+        inner_instructions,
+        logs,
+        fee: meta.fee,
+        error: meta.err,
+    })
+}
+
+/// Fetches a confirmed legacy or versioned transaction in binary encoding.
+pub(crate) fn fetch_transaction(client: &RpcClient, signature: &Signature) -> Result<EncodedConfirmedTransactionWithStatusMeta, ReadTransactionError> {
+    let confirmed_transaction = client.get_transaction_with_config(
+        signature,
+        RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        }
+    )?;
+    Ok(confirmed_transaction)
+}
+
+/// Fetches the address lookup tables used by a versioned transaction and returns the
+/// writable and readonly addresses they load, in the order used by the transaction.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::AccountNotFound` if a lookup table does not exist and
+/// `ReadTransactionError::DeserializeError` if an index is out of range of its lookup table.
+pub fn resolve_address_lookup_tables(
+    client: &RpcClient,
+    lookups: &[MessageAddressTableLookup]
+) -> Result<(Vec<Pubkey>, Vec<Pubkey>), ReadTransactionError> {
+    let lookup_table_pubkeys: Vec<Pubkey> = lookups.iter().map(|lookup| lookup.account_key).collect();
+    let lookup_table_accounts = client.get_multiple_accounts(&lookup_table_pubkeys)?;
+
+    let mut writable_addresses = Vec::new();
+    let mut readonly_addresses = Vec::new();
+    for (lookup, account_option) in lookups.iter().zip(lookup_table_accounts) {
+        let account = account_option.ok_or(ReadTransactionError::AccountNotFound)?;
+        let lookup_table = AddressLookupTableView::try_from_account_data(&account.data)?;
+        for index in &lookup.writable_indexes {
+            writable_addresses.push(*lookup_table.get(*index).ok_or(ReadTransactionError::DeserializeError)?);
+        }
+        for index in &lookup.readonly_indexes {
+            readonly_addresses.push(*lookup_table.get(*index).ok_or(ReadTransactionError::DeserializeError)?);
+        }
+    }
+
+    Ok((writable_addresses, readonly_addresses))
+}
+
+/// Reads the addresses stored in an address lookup table account. Use `AddressLookupTableView`
+/// to read them without copying.
+pub fn parse_address_lookup_table(data: &[u8]) -> Result<Vec<Pubkey>, ReadTransactionError> {
+    Ok(AddressLookupTableView::try_from_account_data(data)?.addresses().to_vec())
+}
+
+/// Builds the full account list of a transaction, which is the static account keys
+/// followed by the writable and then the readonly addresses loaded from lookup tables.
+pub fn reconstruct_account_keys(static_account_keys: &[Pubkey], writable_addresses: &[Pubkey], readonly_addresses: &[Pubkey]) -> Vec<Pubkey> {
+    static_account_keys
+        .iter()
+        .chain(writable_addresses)
+        .chain(readonly_addresses)
+        .copied()
+        .collect()
+}
+
+fn resolve_instruction(
+    account_keys: &[Pubkey],
+    program_id_index: u8,
+    account_indexes: &[u8],
+    data: Vec<u8>,
+    stack_height: Option<u32>
+) -> Result<InspectedInstruction, ReadTransactionError> {
+    let program_id = *account_keys.get(program_id_index as usize).ok_or(ReadTransactionError::DeserializeError)?;
+    let accounts = account_indexes
+        .iter()
+        .map(|index| account_keys.get(*index as usize).copied().ok_or(ReadTransactionError::DeserializeError))
+        .collect::<Result<Vec<Pubkey>, ReadTransactionError>>()?;
+
+    Ok(InspectedInstruction {
+        program_id,
+        accounts,
+        data,
+        stack_height,
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_transactions::zero_copy::LOOKUP_TABLE_META_SIZE;
+
+    #[test]
+    fn test_parse_address_lookup_table() {
+        let addresses = vec![Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut data = vec![0_u8; LOOKUP_TABLE_META_SIZE];
+        for address in &addresses {
+            data.extend_from_slice(address.as_ref());
+        }
+        let parsed_addresses = parse_address_lookup_table(&data).unwrap();
+        assert!(parsed_addresses == addresses);
+    }
+
+    #[test]
+    fn failing_test_parse_invalid_address_lookup_table() {
+        let data = vec![0_u8; LOOKUP_TABLE_META_SIZE + 10];
+        assert!(parse_address_lookup_table(&data).is_err());
+    }
+
+    #[test]
+    fn test_resolve_instruction_with_lookup_table_accounts() {
+        let static_account_keys = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let writable_addresses = vec![Pubkey::new_unique()];
+        let readonly_addresses = vec![Pubkey::new_unique()];
+        let account_keys = reconstruct_account_keys(&static_account_keys, &writable_addresses, &readonly_addresses);
+
+        let instruction = resolve_instruction(&account_keys, 3, &[0, 2], vec![1, 2, 3], Some(1)).unwrap();
+        assert!(instruction.program_id == readonly_addresses[0]);
+        assert!(instruction.accounts == vec![static_account_keys[0], writable_addresses[0]]);
+        assert!(resolve_instruction(&account_keys, 4, &[0], vec![], None).is_err());
+    }
+}
@@ -0,0 +1,308 @@
+//! # Wallet Graph
+//!
+//! Builds a directed graph of the SOL and token flows between a seed set of wallets, e.g to
+//! investigate a cluster of wallets or detect airdrop farming, and exports it as JSON or as
+//! a Graphviz DOT file for visualization.
+
+use std::{collections::HashSet, fmt::Write, str::FromStr};
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_transaction_status_client_types::option_serializer::OptionSerializer;
+use crate::{
+    error::ReadTransactionError,
+    json::{serialize_display, ToJson}
+};
+use super::{
+    history::{get_signature_history, sol_transfers_in_transaction, HistoryConfig},
+    token_transfers::{token_balance_changes, token_transfers_from_balance_changes},
+    transaction::{fetch_transaction, inspect_transaction}
+};
+
+/// Decimals of SOL amounts in lamports.
+const SOL_DECIMALS: u8 = 9;
+
+/// Transactions read to build a wallet graph.
+///
+/// ### Fields
+///
+/// - `history`: Range of the history read for each seed wallet, e.g `start_time` and `end_time`.
+/// - `include_counterparties`: Whether flows between seeds and other wallets are included, by default only flows between seeds are.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WalletGraphConfig {
+    pub history: HistoryConfig,
+    pub include_counterparties: bool,
+}
+
+/// Asset moved along an edge.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(tag = "type", content = "details", rename_all = "snake_case")]
+pub enum FlowAsset {
+    Sol,
+    Token {
+        #[serde(serialize_with = "serialize_display")]
+        mint: Pubkey,
+    },
+}
+
+/// A wallet of the graph.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct GraphNode {
+    #[serde(serialize_with = "serialize_display")]
+    pub wallet: Pubkey,
+    pub is_seed: bool,
+}
+
+/// Total flow of an asset from one wallet to another.
+///
+/// ### Fields
+///
+/// - `from`, `to`: Sending and receiving wallets.
+/// - `asset`: SOL or the mint of the token.
+/// - `amount`: Total amount in lamports or raw token units.
+/// - `decimals`: Decimals of the asset.
+/// - `transfers`: Number of transfers.
+/// - `first_time`, `last_time`: Unix timestamps of the first and last transfer, if available.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FlowEdge {
+    #[serde(serialize_with = "serialize_display")]
+    pub from: Pubkey,
+    #[serde(serialize_with = "serialize_display")]
+    pub to: Pubkey,
+    pub asset: FlowAsset,
+    pub amount: u64,
+    pub decimals: u8,
+    pub transfers: u64,
+    pub first_time: Option<i64>,
+    pub last_time: Option<i64>,
+}
+
+/// Directed graph of flows between wallets, with one edge per sender, receiver and asset.
+///
+/// ## Example
+///
+/// ```rust
+/// use easy_solana::read_transactions::wallet_graph::{FlowAsset, WalletGraph};
+/// use solana_sdk::pubkey::Pubkey;
+///
+/// let (funder, farmer) = (Pubkey::new_unique(), Pubkey::new_unique());
+/// let mut graph = WalletGraph::new(&[funder, farmer]);
+/// graph.add_flow(funder, farmer, FlowAsset::Sol, 9, 50_000_000, Some(1_700_000_000));
+/// assert!(graph.to_dot().contains("0.05 SOL"));
+/// ```
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct WalletGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<FlowEdge>,
+}
+
+impl ToJson for WalletGraph {}
+
+impl WalletGraph {
+    /// Creates a graph with the seed wallets and no edges.
+    pub fn new(seeds: &[Pubkey]) -> Self {
+        let mut graph = Self::default();
+        for seed in seeds {
+            if !graph.contains(seed) {
+                graph.nodes.push(GraphNode { wallet: *seed, is_seed: true });
+            }
+        }
+        graph
+    }
+
+    pub fn contains(&self, wallet: &Pubkey) -> bool {
+        self.nodes.iter().any(|node| node.wallet == *wallet)
+    }
+
+    pub fn is_seed(&self, wallet: &Pubkey) -> bool {
+        self.nodes.iter().any(|node| node.wallet == *wallet && node.is_seed)
+    }
+
+    /// Adds a transfer to the edge of `from`, `to` and `asset`, adding wallets that are not
+    /// in the graph yet as non seed nodes.
+    pub fn add_flow(&mut self, from: Pubkey, to: Pubkey, asset: FlowAsset, decimals: u8, amount: u64, block_time: Option<i64>) {
+        for wallet in [from, to] {
+            if !self.contains(&wallet) {
+                self.nodes.push(GraphNode { wallet, is_seed: false });
+            }
+        }
+        let edge = self.edges.iter_mut().find(|edge| edge.from == from && edge.to == to && edge.asset == asset);
+        match edge {
+            Some(edge) => {
+                edge.amount = edge.amount.saturating_add(amount);
+                edge.transfers += 1;
+                edge.first_time = min_time(edge.first_time, block_time);
+                edge.last_time = edge.last_time.max(block_time);
+            }
+            None => self.edges.push(FlowEdge {
+                from,
+                to,
+                asset,
+                amount,
+                decimals,
+                transfers: 1,
+                first_time: block_time,
+                last_time: block_time,
+            }),
+        }
+    }
+
+    /// Renders the graph in the Graphviz DOT language, seeds are drawn as boxes and edges are
+    /// labelled with the amount moved and the number of transfers.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph wallets {\n");
+        for node in &self.nodes {
+            let shape = if node.is_seed { "box" } else { "ellipse" };
+            let _ = writeln!(dot, "    \"{}\" [label=\"{}\", shape={}];", node.wallet, short_address(&node.wallet), shape);
+        }
+        for edge in &self.edges {
+            let asset = match edge.asset {
+                FlowAsset::Sol => "SOL".to_string(),
+                FlowAsset::Token { mint } => short_address(&mint),
+            };
+            let ui_amount = edge.amount as f64 / 10_f64.powi(edge.decimals as i32);
+            let _ = writeln!(dot, "    \"{}\" -> \"{}\" [label=\"{} {} ({})\"];", edge.from, edge.to, ui_amount, asset, edge.transfers);
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Builds the graph of SOL and token flows between `seeds` from their histories. A transaction
+/// involving several seeds is only counted once. Token flows are between the owners of the
+/// token accounts, mints and burns are left out.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::InvalidAddress` if a seed is invalid, or an error if a
+/// history or a transaction can not be fetched.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_client::rpc_client::RpcClient;
+/// use easy_solana::{
+///     json::ToJson,
+///     read_transactions::{history::HistoryConfig, wallet_graph::{build_wallet_graph, WalletGraphConfig}}
+/// };
+///
+/// let client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+/// let config = WalletGraphConfig {
+///     history: HistoryConfig { limit: None, start_time: Some(1_735_689_600), end_time: Some(1_738_368_000), include_failed: false },
+///     include_counterparties: false,
+/// };
+/// let seeds = ["9PvGbwGBQVJ5gVn5DjS6ubpkMaHcA5n1AmphFFaytaQR", "5tzFkiKscXHK5ZXCGbXZxdw7gTjjD1mBwuoFbhUvuAi9"];
+/// let graph = build_wallet_graph(&client, &seeds, &config).unwrap();
+/// std::fs::write("wallets.dot", graph.to_dot()).unwrap();
+/// std::fs::write("wallets.json", graph.to_json_string()).unwrap();
+/// ```
+pub fn build_wallet_graph(client: &RpcClient, seeds: &[&str], config: &WalletGraphConfig) -> Result<WalletGraph, ReadTransactionError> {
+    let seed_pubkeys = seeds
+        .iter()
+        .map(|seed| Pubkey::from_str(seed))
+        .collect::<Result<Vec<Pubkey>, _>>()?;
+    let mut graph = WalletGraph::new(&seed_pubkeys);
+    let mut seen_signatures = HashSet::new();
+
+    for seed in seeds {
+        let signatures = get_signature_history(client, seed, &config.history)?;
+        for signature_info in signatures.iter().rev() {
+            if !seen_signatures.insert(signature_info.signature.clone()) {
+                continue;
+            }
+            add_transaction_flows(client, &mut graph, &signature_info.signature, config.include_counterparties)?;
+        }
+    }
+    Ok(graph)
+}
+
+fn add_transaction_flows(client: &RpcClient, graph: &mut WalletGraph, signature: &str, include_counterparties: bool) -> Result<(), ReadTransactionError> {
+    let signature = Signature::from_str(signature).map_err(|_| ReadTransactionError::InvalidSignature)?;
+    let confirmed_transaction = fetch_transaction(client, &signature)?;
+    let meta = confirmed_transaction.transaction.meta.clone().ok_or(ReadTransactionError::DeserializeError)?;
+    let transaction = inspect_transaction(client, &signature, confirmed_transaction)?;
+    if transaction.error.is_some() {
+        return Ok(());
+    }
+
+    let mut flows: Vec<(Pubkey, Pubkey, FlowAsset, u8, u64)> = sol_transfers_in_transaction(&transaction)
+        .into_iter()
+        .map(|transfer| (transfer.from, transfer.to, FlowAsset::Sol, SOL_DECIMALS, transfer.lamports))
+        .collect();
+    if let (OptionSerializer::Some(pre_token_balances), OptionSerializer::Some(post_token_balances)) = (meta.pre_token_balances, meta.post_token_balances) {
+        let balance_changes = token_balance_changes(&pre_token_balances, &post_token_balances)?;
+        for transfer in token_transfers_from_balance_changes(&balance_changes) {
+            let (Some(from), Some(to)) = (transfer.from_owner, transfer.to_owner) else {
+                continue;
+            };
+            let (from, to, mint) = (Pubkey::from_str(&from)?, Pubkey::from_str(&to)?, Pubkey::from_str(&transfer.mint)?);
+            flows.push((from, to, FlowAsset::Token { mint }, transfer.decimals, transfer.amount));
+        }
+    }
+
+    for (from, to, asset, decimals, amount) in flows {
+        if is_kept_flow(graph, &from, &to, include_counterparties) {
+            graph.add_flow(from, to, asset, decimals, amount, transaction.block_time);
+        }
+    }
+    Ok(())
+}
+
+/// Whether a flow is part of the graph: between two seeds, or between a seed and another wallet
+/// if counterparties are included.
+fn is_kept_flow(graph: &WalletGraph, from: &Pubkey, to: &Pubkey, include_counterparties: bool) -> bool {
+    if from == to {
+        return false;
+    }
+    let (is_from_seed, is_to_seed) = (graph.is_seed(from), graph.is_seed(to));
+    (is_from_seed && is_to_seed) || (include_counterparties && (is_from_seed || is_to_seed))
+}
+
+fn min_time(time: Option<i64>, other_time: Option<i64>) -> Option<i64> {
+    match (time, other_time) {
+        (Some(time), Some(other_time)) => Some(time.min(other_time)),
+        _ => time.or(other_time),
+    }
+}
+
+/// First and last 4 characters of an address, e.g `9Pvb…taQR`.
+fn short_address(address: &Pubkey) -> String {
+    let address = address.to_string();
+    format!("{}…{}", &address[..4], &address[address.len() - 4..])
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_sdk::native_token::LAMPORTS_PER_SOL;
+
+    #[test]
+    fn test_wallet_graph_edges() {
+        let (funder, farmer, exchange) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mint = Pubkey::new_unique();
+        let mut graph = WalletGraph::new(&[funder, farmer, funder]);
+        assert!(graph.nodes.len() == 2);
+
+        graph.add_flow(funder, farmer, FlowAsset::Sol, SOL_DECIMALS, LAMPORTS_PER_SOL, Some(200));
+        graph.add_flow(funder, farmer, FlowAsset::Sol, SOL_DECIMALS, LAMPORTS_PER_SOL / 2, Some(100));
+        graph.add_flow(farmer, exchange, FlowAsset::Token { mint }, 6, 5_000_000, None);
+        assert!(graph.edges.len() == 2 && graph.nodes.len() == 3 && !graph.is_seed(&exchange));
+        let edge = &graph.edges[0];
+        assert!(edge.amount == 3 * LAMPORTS_PER_SOL / 2 && edge.transfers == 2);
+        assert!(edge.first_time == Some(100) && edge.last_time == Some(200));
+
+        assert!(is_kept_flow(&graph, &funder, &farmer, false));
+        assert!(!is_kept_flow(&graph, &farmer, &exchange, false) && is_kept_flow(&graph, &farmer, &exchange, true));
+        assert!(!is_kept_flow(&graph, &funder, &funder, true));
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph wallets {") && dot.contains(&format!("\"{}\" -> \"{}\" [label=\"1.5 SOL (2)\"];", funder, farmer)));
+        assert!(dot.contains(&format!("\"{}\" -> \"{}\" [label=\"5 {} (1)\"];", farmer, exchange, short_address(&mint))));
+
+        let graph_json = graph.to_json();
+        assert!(graph_json["nodes"][0] == json!({ "wallet": funder.to_string(), "is_seed": true }));
+        assert!(graph_json["edges"][1]["asset"] == json!({ "type": "token", "details": { "mint": mint.to_string() } }));
+    }
+}
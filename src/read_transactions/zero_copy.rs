@@ -0,0 +1,155 @@
+//! # Zero Copy
+//!
+//! Views reading large accounts in place, e.g lookup tables, instead of copying them into owned
+//! structs with Borsh. Views borrow the account data, so reading a single field of a large
+//! account allocates nothing.
+//!
+//! Fixed layouts are read with `view` into `#[repr(C)]` structs deriving `bytemuck::Pod`, and
+//! arrays of entries, like the ticks of a tick array, with `view_slice`.
+
+use std::any::type_name;
+use bytemuck::Pod;
+use solana_sdk::pubkey::Pubkey;
+use crate::error::ReadTransactionError;
+
+/// Size of the metadata stored before the addresses in an address lookup table account.
+pub const LOOKUP_TABLE_META_SIZE: usize = 56;
+
+/// Views the start of `data` after `discriminator` as a `T`, ignoring data after it.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::WrongAccountType` if the discriminator does not match, or
+/// `ReadTransactionError::DeserializeError` if the data is too short or not aligned for `T`.
+///
+/// ## Example
+///
+/// ```rust
+/// use bytemuck::{Pod, Zeroable};
+/// use easy_solana::read_transactions::zero_copy::view;
+///
+/// #[derive(Clone, Copy, Pod, Zeroable)]
+/// #[repr(C)]
+/// struct Reserves {
+///     base: [u8; 8],
+///     quote: [u8; 8],
+/// }
+///
+/// let data = [[7; 8], [1; 8], [2; 8]].concat();
+/// let reserves: &Reserves = view(&data, &[7; 8]).unwrap();
+/// assert!(u64::from_le_bytes(reserves.quote) == u64::from_le_bytes([2; 8]));
+/// ```
+pub fn view<'a, T: Pod>(data: &'a [u8], discriminator: &[u8]) -> Result<&'a T, ReadTransactionError> {
+    let data = data
+        .strip_prefix(discriminator)
+        .ok_or_else(|| ReadTransactionError::WrongAccountType(type_name::<T>().to_string()))?;
+    let bytes = data.get(..size_of::<T>()).ok_or(ReadTransactionError::DeserializeError)?;
+    bytemuck::try_from_bytes(bytes).map_err(|_| ReadTransactionError::DeserializeError)
+}
+
+/// Views `data` as a slice of `T`.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::DeserializeError` if the length of the data is not a multiple
+/// of the size of `T`, or the data is not aligned for `T`.
+pub fn view_slice<T: Pod>(data: &[u8]) -> Result<&[T], ReadTransactionError> {
+    bytemuck::try_cast_slice(data).map_err(|_| ReadTransactionError::DeserializeError)
+}
+
+/// View of an address lookup table account.
+#[derive(Debug, Clone, Copy)]
+pub struct AddressLookupTableView<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> AddressLookupTableView<'a> {
+    /// Views the data of an address lookup table account.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::DeserializeError` if the data is shorter than the metadata
+    /// or the addresses are not a multiple of 32 bytes.
+    pub fn try_from_account_data(data: &'a [u8]) -> Result<Self, ReadTransactionError> {
+        if data.len() < LOOKUP_TABLE_META_SIZE || !(data.len() - LOOKUP_TABLE_META_SIZE).is_multiple_of(32) {
+            return Err(ReadTransactionError::DeserializeError);
+        }
+        Ok(Self { data })
+    }
+
+    /// Addresses stored in the table.
+    pub fn addresses(&self) -> &'a [Pubkey] {
+        // Pubkeys have an alignment of 1, so any length checked slice can be cast
+        bytemuck::cast_slice(&self.data[LOOKUP_TABLE_META_SIZE..])
+    }
+
+    /// Address at `index`, as referenced by the transactions using the table.
+    pub fn get(&self, index: u8) -> Option<&'a Pubkey> {
+        self.addresses().get(index as usize)
+    }
+
+    /// Slot the table was deactivated at, `u64::MAX` while the table is active.
+    pub fn deactivation_slot(&self) -> u64 {
+        u64::from_le_bytes(self.data[4..12].try_into().unwrap())
+    }
+
+    /// Authority allowed to extend the table, `None` once the table is frozen.
+    pub fn authority(&self) -> Option<Pubkey> {
+        match self.data[21] {
+            0 => None,
+            _ => Pubkey::try_from(&self.data[22..54]).ok(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+    #[repr(C)]
+    struct Tick {
+        index: [u8; 4],
+        liquidity: [u8; 16],
+    }
+
+    #[test]
+    fn test_view_and_view_slice() {
+        let discriminator = [3; 8];
+        let mut data = discriminator.to_vec();
+        for index in 0..3_i32 {
+            data.extend_from_slice(&index.to_le_bytes());
+            data.extend_from_slice(&(index as u128 * 100).to_le_bytes());
+        }
+        let first_tick: &Tick = view(&data, &discriminator).unwrap();
+        assert!(i32::from_le_bytes(first_tick.index) == 0);
+        let ticks: &[Tick] = view_slice(&data[8..]).unwrap();
+        assert!(ticks.len() == 3 && u128::from_le_bytes(ticks[2].liquidity) == 200);
+
+        assert!(matches!(view::<Tick>(&data, &[4; 8]), Err(ReadTransactionError::WrongAccountType(_))));
+        assert!(matches!(view::<Tick>(&data[..10], &discriminator), Err(ReadTransactionError::DeserializeError)));
+        assert!(view_slice::<Tick>(&data[8..30]).is_err());
+    }
+
+    #[test]
+    fn test_address_lookup_table_view() {
+        let (authority, addresses) = (Pubkey::new_unique(), [Pubkey::new_unique(), Pubkey::new_unique()]);
+        let mut data = 1_u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&u64::MAX.to_le_bytes());
+        data.extend_from_slice(&[0; 9]);
+        data.push(1);
+        data.extend_from_slice(authority.as_ref());
+        data.extend_from_slice(&[0; 2]);
+        for address in &addresses {
+            data.extend_from_slice(address.as_ref());
+        }
+
+        let lookup_table = AddressLookupTableView::try_from_account_data(&data).unwrap();
+        assert!(lookup_table.addresses() == addresses && lookup_table.get(1) == Some(&addresses[1]));
+        assert!(lookup_table.get(2).is_none());
+        assert!(lookup_table.deactivation_slot() == u64::MAX && lookup_table.authority() == Some(authority));
+        assert!(AddressLookupTableView::try_from_account_data(&data[..data.len() - 1]).is_err());
+    }
+}
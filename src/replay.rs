@@ -0,0 +1,255 @@
+//! # Replay
+//!
+//! Numbers listener events and keeps the most recent ones, so consumers reconnecting after a
+//! hiccup can ask for the events they missed instead of re-syncing the full history.
+
+use std::{collections::VecDeque, sync::Arc};
+use tokio::{sync::Mutex, task::JoinHandle};
+use crate::{
+    error::ReplayError,
+    event_channel::{event_channel, ChannelConfig, EventReceiver, EventSender}
+};
+
+/// Event with its sequence number, starting at 1 and increasing by 1 for every event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sequenced<T> {
+    pub sequence: u64,
+    pub event: T,
+}
+
+/// The `capacity` most recent events.
+#[derive(Debug, Clone)]
+pub struct ReplayBuffer<T> {
+    events: VecDeque<Sequenced<T>>,
+    capacity: usize,
+    next_sequence: u64,
+}
+
+impl<T: Clone> ReplayBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self { events: VecDeque::with_capacity(capacity), capacity, next_sequence: 1 }
+    }
+
+    /// Numbers and stores `event`, dropping the oldest event once full.
+    pub fn push(&mut self, event: T) -> Sequenced<T> {
+        let sequenced = Sequenced { sequence: self.next_sequence, event };
+        self.next_sequence += 1;
+        if self.events.len() >= self.capacity {
+            self.events.pop_front();
+        }
+        if self.capacity > 0 {
+            self.events.push_back(sequenced.clone());
+        }
+        sequenced
+    }
+
+    /// Sequence number of the last event, 0 before the first one.
+    pub fn latest_sequence(&self) -> u64 {
+        self.next_sequence - 1
+    }
+
+    /// Events after `sequence`, the last sequence number a consumer received.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReplayError::Gap` if events after `sequence` were already dropped, the consumer
+    /// has to re-sync, and `ReplayError::FutureSequence` if `sequence` was never published.
+    pub fn since(&self, sequence: u64) -> Result<Vec<Sequenced<T>>, ReplayError> {
+        let latest = self.latest_sequence();
+        if sequence > latest {
+            return Err(ReplayError::FutureSequence { requested: sequence, latest });
+        }
+        let oldest = self.events.front().map_or(self.next_sequence, |event| event.sequence);
+        if sequence + 1 < oldest {
+            return Err(ReplayError::Gap { requested: sequence, oldest });
+        }
+        Ok(self.events.iter().filter(|event| event.sequence > sequence).cloned().collect())
+    }
+}
+
+struct PublisherState<T> {
+    buffer: ReplayBuffer<T>,
+    subscribers: Vec<EventSender<Sequenced<T>>>,
+}
+
+/// Fans events of a listener out to subscribers, replaying missed events to reconnecting ones.
+///
+/// ## Example
+///
+/// ```rust
+/// use easy_solana::{
+///     event_channel::{ChannelConfig, OverflowPolicy},
+///     replay::ReplayPublisher
+/// };
+///
+/// # async fn example() {
+/// let publisher = ReplayPublisher::new(1_000);
+/// let mut events = publisher.subscribe(ChannelConfig::new(100, OverflowPolicy::Block), None).await.unwrap();
+/// publisher.publish("trade").await;
+/// let last_seen = events.recv().await.unwrap().sequence;
+/// drop(events);
+///
+/// // Reconnect, receiving everything published after `last_seen` first
+/// publisher.publish("missed trade").await;
+/// let mut events = publisher.subscribe(ChannelConfig::new(100, OverflowPolicy::Block), Some(last_seen)).await.unwrap();
+/// assert!(events.recv().await.unwrap().event == "missed trade");
+/// # }
+/// ```
+pub struct ReplayPublisher<T> {
+    state: Arc<Mutex<PublisherState<T>>>,
+}
+
+impl<T> Clone for ReplayPublisher<T> {
+    fn clone(&self) -> Self {
+        Self { state: Arc::clone(&self.state) }
+    }
+}
+
+impl<T: Clone + Send> ReplayPublisher<T> {
+    /// Publisher keeping the `replay_capacity` most recent events.
+    pub fn new(replay_capacity: usize) -> Self {
+        let state = PublisherState { buffer: ReplayBuffer::new(replay_capacity), subscribers: Vec::new() };
+        Self { state: Arc::new(Mutex::new(state)) }
+    }
+
+    /// Numbers `event`, stores it for replay and sends it to every subscriber. Subscribers whose
+    /// receiver was dropped are removed.
+    pub async fn publish(&self, event: T) -> u64 {
+        let mut state = self.state.lock().await;
+        let sequenced = state.buffer.push(event);
+        let subscribers = std::mem::take(&mut state.subscribers);
+        let mut open = Vec::with_capacity(subscribers.len());
+        for subscriber in subscribers {
+            if subscriber.send(sequenced.clone()).await.is_ok() {
+                open.push(subscriber);
+            }
+        }
+        state.subscribers = open;
+        sequenced.sequence
+    }
+
+    /// Subscribes to events after `after`, the last sequence number received before a
+    /// reconnect, or only to new events if `None`. Missed events are delivered first, the
+    /// channel grows to hold them on top of `config.capacity`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReplayError::Gap` if the missed events are no longer buffered and
+    /// `ReplayError::FutureSequence` if `after` was never published.
+    pub async fn subscribe(
+        &self,
+        config: ChannelConfig<Sequenced<T>>,
+        after: Option<u64>
+    ) -> Result<EventReceiver<Sequenced<T>>, ReplayError> {
+        let mut state = self.state.lock().await;
+        let missed = match after {
+            Some(sequence) => state.buffer.since(sequence)?,
+            None => Vec::new(),
+        };
+        let (sender, receiver) = event_channel(ChannelConfig::new(config.capacity + missed.len(), config.overflow));
+        for event in missed {
+            // The channel has room for every missed event
+            let _ = sender.try_send(event);
+        }
+        state.subscribers.push(sender);
+        Ok(receiver)
+    }
+
+    /// Publishes every event of a listener such as `watch_mint_with_channel` in the background,
+    /// until the listener ends.
+    pub fn forward(&self, mut events: EventReceiver<T>) -> JoinHandle<()>
+    where
+        T: 'static,
+    {
+        let publisher = self.clone();
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                publisher.publish(event).await;
+            }
+        })
+    }
+
+    /// Sequence number of the last published event, 0 before the first one.
+    pub async fn latest_sequence(&self) -> u64 {
+        self.state.lock().await.buffer.latest_sequence()
+    }
+
+    /// Number of subscribers that were connected at the last publish or subscribed since.
+    pub async fn subscriber_count(&self) -> usize {
+        self.state.lock().await.subscribers.len()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_channel::OverflowPolicy;
+
+    fn config<T>() -> ChannelConfig<T> {
+        ChannelConfig::new(2, OverflowPolicy::Block)
+    }
+
+    #[test]
+    fn test_replay_buffer_since() {
+        let mut buffer = ReplayBuffer::new(3);
+        assert!(buffer.since(0).unwrap().is_empty());
+        for price in 1..=5 {
+            buffer.push(price * 10);
+        }
+        assert!(buffer.latest_sequence() == 5);
+        let events = buffer.since(3).unwrap();
+        assert!(events == vec![Sequenced { sequence: 4, event: 40 }, Sequenced { sequence: 5, event: 50 }]);
+        assert!(buffer.since(2).unwrap().len() == 3);
+        assert!(buffer.since(5).unwrap().is_empty());
+        assert!(matches!(buffer.since(1), Err(ReplayError::Gap { requested: 1, oldest: 3 })));
+        assert!(matches!(buffer.since(6), Err(ReplayError::FutureSequence { requested: 6, latest: 5 })));
+    }
+
+    #[tokio::test]
+    async fn test_reconnecting_subscriber_receives_missed_events() {
+        let publisher = ReplayPublisher::new(10);
+        let mut live = publisher.subscribe(config(), None).await.unwrap();
+        publisher.publish("buy").await;
+        let last_seen = live.recv().await.unwrap().sequence;
+        drop(live);
+
+        for event in ["sell", "buy", "sell"] {
+            publisher.publish(event).await;
+        }
+        // The dropped subscriber is removed on the next publish
+        assert!(publisher.subscriber_count().await == 0);
+
+        let mut reconnected = publisher.subscribe(config(), Some(last_seen)).await.unwrap();
+        publisher.publish("buy").await;
+        let mut sequences = Vec::new();
+        for _ in 0..4 {
+            sequences.push(reconnected.recv().await.unwrap().sequence);
+        }
+        assert!(sequences == vec![2, 3, 4, 5]);
+        assert!(publisher.latest_sequence().await == 5);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_after_evicted_events() {
+        let publisher = ReplayPublisher::new(2);
+        for event in 0..5 {
+            publisher.publish(event).await;
+        }
+        assert!(matches!(publisher.subscribe(config(), Some(1)).await, Err(ReplayError::Gap { oldest: 4, .. })));
+        assert!(publisher.subscribe(config(), Some(3)).await.unwrap().len() == 2);
+    }
+
+    #[tokio::test]
+    async fn test_forward_listener_events() {
+        let publisher = ReplayPublisher::new(10);
+        let (listener, events) = event_channel(config());
+        let forwarder = publisher.forward(events);
+        listener.send("supply changed").await.unwrap();
+        drop(listener);
+        forwarder.await.unwrap();
+
+        let mut replayed = publisher.subscribe(config(), Some(0)).await.unwrap();
+        assert!(replayed.recv().await == Some(Sequenced { sequence: 1, event: "supply changed" }));
+    }
+}
@@ -0,0 +1,317 @@
+//! # RPC Fixtures
+//!
+//! Records live RPC responses into fixture files and replays them, so pricing and transaction
+//! building logic can be tested deterministically without a cluster.
+//!
+//! Fixtures are JSON lines, one request per line with its method, parameters and either the
+//! result or the error of the response.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::Mutex
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_client::{RpcClient, RpcClientConfig},
+    rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+    rpc_response::RpcSimulateTransactionResult,
+    rpc_sender::{RpcSender, RpcTransportStats}
+};
+use solana_rpc_client::http_sender::HttpSender;
+use solana_sdk::commitment_config::CommitmentConfig;
+use crate::{error::PersistenceError, utils::resolve_rpc_url};
+
+/// Error of a recorded response.
+///
+/// ### Fields
+///
+/// - `code`: JSON-RPC error code, `None` for transport errors.
+/// - `message`: The error message.
+/// - `preflight_failure`: Simulation result of a `sendTransaction` failing preflight checks.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FixtureError {
+    pub code: Option<i64>,
+    pub message: String,
+    pub preflight_failure: Option<RpcSimulateTransactionResult>,
+}
+
+impl FixtureError {
+    fn from_client_error(err: &ClientError) -> Self {
+        match err.kind() {
+            ClientErrorKind::RpcError(RpcError::RpcResponseError { code, message, data }) => FixtureError {
+                code: Some(*code),
+                message: message.clone(),
+                preflight_failure: match data {
+                    RpcResponseErrorData::SendTransactionPreflightFailure(result) => Some(result.clone()),
+                    _ => None,
+                },
+            },
+            kind => FixtureError { code: None, message: kind.to_string(), preflight_failure: None },
+        }
+    }
+
+    fn to_client_error(&self) -> ClientError {
+        match self.code {
+            Some(code) => RpcError::RpcResponseError {
+                code,
+                message: self.message.clone(),
+                data: match &self.preflight_failure {
+                    Some(result) => RpcResponseErrorData::SendTransactionPreflightFailure(result.clone()),
+                    None => RpcResponseErrorData::Empty,
+                },
+            }.into(),
+            None => ClientErrorKind::Custom(self.message.clone()).into(),
+        }
+    }
+}
+
+/// A recorded request and its response.
+///
+/// ### Fields
+///
+/// - `url`: Endpoint the request was sent to.
+/// - `method`: JSON-RPC method, e.g `getAccountInfo`.
+/// - `params`: Parameters of the request.
+/// - `result`: Result of a successful response.
+/// - `error`: Error of a failed response.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FixtureEntry {
+    pub url: String,
+    pub method: String,
+    pub params: Value,
+    pub result: Option<Value>,
+    pub error: Option<FixtureError>,
+}
+
+/// `RpcSender` wrapper appending every request and its response to a fixture file.
+pub struct RecordingSender<S: RpcSender> {
+    inner: S,
+    file: Mutex<File>,
+}
+
+impl<S: RpcSender> RecordingSender<S> {
+    /// Wraps `inner`, appending to the fixture file at `path`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `PersistenceError::IoError` if the fixture file cannot be opened.
+    pub fn new(inner: S, path: impl AsRef<Path>) -> Result<Self, PersistenceError> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { inner, file: Mutex::new(file) })
+    }
+}
+
+#[async_trait]
+impl<S: RpcSender + Send + Sync> RpcSender for RecordingSender<S> {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let result = self.inner.send(request, params.clone()).await;
+        let entry = FixtureEntry {
+            url: self.inner.url(),
+            method: request.to_string(),
+            params,
+            result: result.as_ref().ok().cloned(),
+            error: result.as_ref().err().map(FixtureError::from_client_error),
+        };
+        if let (Ok(mut file), Ok(line)) = (self.file.lock(), serde_json::to_string(&entry)) {
+            // Recording must never fail the request itself
+            let _ = writeln!(file, "{}", line);
+        }
+        result
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+/// `RpcSender` answering requests with the responses of a fixture file.
+///
+/// Requests are matched by method and parameters. Identical requests receive the recorded
+/// responses in order, and the last one once they run out, so polling loops terminate.
+/// Requests that were not recorded fail with `ClientErrorKind::Custom`.
+pub struct ReplaySender {
+    url: String,
+    responses: Mutex<HashMap<(String, String), Vec<FixtureEntry>>>,
+}
+
+impl ReplaySender {
+    /// Loads the fixture file at `path`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `PersistenceError::IoError` if the file cannot be read and
+    /// `PersistenceError::SerializeError` if a line is not a recorded request.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PersistenceError> {
+        let entries = fs::read_to_string(path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<FixtureEntry>, _>>()?;
+        Ok(Self::from_entries(entries))
+    }
+
+    pub fn from_entries(entries: Vec<FixtureEntry>) -> Self {
+        let url = entries.first().map(|entry| entry.url.clone()).unwrap_or_default();
+        let mut responses: HashMap<(String, String), Vec<FixtureEntry>> = HashMap::new();
+        for entry in entries {
+            responses.entry((entry.method.clone(), entry.params.to_string())).or_default().push(entry);
+        }
+        Self { url, responses: Mutex::new(responses) }
+    }
+}
+
+#[async_trait]
+impl RpcSender for ReplaySender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let key = (request.to_string(), params.to_string());
+        let entry = {
+            let mut responses = self.responses.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            match responses.get_mut(&key) {
+                Some(entries) if entries.len() > 1 => Some(entries.remove(0)),
+                Some(entries) => entries.first().cloned(),
+                None => None,
+            }
+        };
+        let Some(entry) = entry else {
+            return Err(ClientErrorKind::Custom(format!("No recorded response for {} {}", key.0, key.1)).into());
+        };
+        match (entry.result, entry.error) {
+            (_, Some(error)) => Err(error.to_client_error()),
+            (result, None) => Ok(result.unwrap_or(Value::Null)),
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        RpcTransportStats::default()
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+}
+
+/// Whether a fixture client records or replays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixtureMode {
+    /// Sends requests to the endpoint and appends the responses to the fixture file.
+    Record,
+    /// Answers from the fixture file without network access.
+    Replay,
+    /// Replays if the fixture file exists and records it otherwise.
+    Auto,
+}
+
+/// Creates an RPC client like `create_rpc_client` that records its responses to `path`, or
+/// replays them, following `mode`.
+///
+/// ## Errors
+///
+/// Returns `PersistenceError::IoError` if the fixture file cannot be opened and
+/// `PersistenceError::SerializeError` if a replayed fixture file is invalid.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{
+///     pumpfun::bonding_curve::{calculate_token_price_in_sol, get_bonding_curve_account},
+///     rpc_fixtures::{create_fixture_rpc_client, FixtureMode}
+/// };
+///
+/// // The first run records from RPC_URL, later runs replay without network access
+/// let client = create_fixture_rpc_client("RPC_URL", "tests/fixtures/bonding_curve.jsonl", FixtureMode::Auto).unwrap();
+/// let (_, bonding_curve) = get_bonding_curve_account(&client, "CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump").unwrap();
+/// let price = calculate_token_price_in_sol(&bonding_curve).unwrap();
+/// ```
+pub fn create_fixture_rpc_client(
+    rpc_input: &str,
+    path: impl AsRef<Path>,
+    mode: FixtureMode
+) -> Result<RpcClient, PersistenceError> {
+    let config = RpcClientConfig::with_commitment(CommitmentConfig::confirmed());
+    let replay = match mode {
+        FixtureMode::Record => false,
+        FixtureMode::Replay => true,
+        FixtureMode::Auto => path.as_ref().exists(),
+    };
+    if replay {
+        return Ok(RpcClient::new_sender(ReplaySender::load(path)?, config));
+    }
+
+    Ok(RpcClient::new_sender(RecordingSender::new(HttpSender::new(resolve_rpc_url(rpc_input)), path)?, config))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_rpc_client::mock_sender::MockSender;
+    use solana_sdk::pubkey::Pubkey;
+    use crate::test_utils::RateLimitedSender;
+
+    fn client(sender: impl RpcSender + Send + Sync + 'static) -> RpcClient {
+        RpcClient::new_sender(sender, RpcClientConfig::with_commitment(CommitmentConfig::confirmed()))
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = std::env::temp_dir().join(format!("rpc_fixture_{}.jsonl", Pubkey::new_unique()));
+        let address = Pubkey::new_unique();
+        let recording = client(RecordingSender::new(MockSender::new("succeeds"), &path).unwrap());
+        let balance = recording.get_balance(&address).unwrap();
+        let blockhash = recording.get_latest_blockhash().unwrap();
+
+        let replay = client(ReplaySender::load(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert!(replay.get_balance(&address).unwrap() == balance);
+        // The last response is reused once the recorded ones run out
+        assert!(replay.get_balance(&address).unwrap() == balance);
+        assert!(replay.get_latest_blockhash().unwrap() == blockhash);
+        assert!(replay.url() == recording.url());
+
+        let err = replay.get_balance(&Pubkey::new_unique()).unwrap_err();
+        assert!(err.to_string().contains("No recorded response for getBalance"));
+    }
+
+    #[test]
+    fn test_replay_errors_and_response_order() {
+        let path = std::env::temp_dir().join(format!("rpc_fixture_{}.jsonl", Pubkey::new_unique()));
+        let failing = RateLimitedSender::new("https://mainnet.helius-rpc.com", (-32429, "Rate limited"), 1, serde_json::json!(7));
+        let recording = client(RecordingSender::new(failing, &path).unwrap());
+        assert!(recording.get_slot().is_err());
+        assert!(recording.get_slot().unwrap() == 7);
+
+        let replay = client(ReplaySender::load(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+        let err = replay.get_slot().unwrap_err();
+        assert!(matches!(err.kind(), ClientErrorKind::RpcError(RpcError::RpcResponseError { code: -32429, .. })));
+        assert!(replay.get_slot().unwrap() == 7);
+        assert!(replay.url() == "https://mainnet.helius-rpc.com");
+    }
+
+    #[test]
+    fn test_auto_mode_replays_existing_fixture() {
+        let path = std::env::temp_dir().join(format!("rpc_fixture_{}.jsonl", Pubkey::new_unique()));
+        let entry = FixtureEntry {
+            url: "https://api.mainnet-beta.solana.com".to_string(),
+            method: "getSlot".to_string(),
+            params: serde_json::json!([{ "commitment": "confirmed" }]),
+            result: Some(serde_json::json!(42)),
+            error: None,
+        };
+        std::fs::write(&path, format!("{}\n", serde_json::to_string(&entry).unwrap())).unwrap();
+        let client = create_fixture_rpc_client("http://127.0.0.1:1", &path, FixtureMode::Auto).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(client.get_slot().unwrap() == 42);
+        assert!(matches!(ReplaySender::load(&path), Err(PersistenceError::IoError(_))));
+    }
+}
@@ -0,0 +1,556 @@
+//! # RPC Pool
+//!
+//! Benchmarks RPC endpoints and routes requests to the fastest endpoint that is in sync with
+//! the cluster, keeping the others as fallbacks. Endpoints falling behind the cluster are
+//! quarantined so reads do not silently serve stale state, and rate limited endpoints are
+//! skipped until the provider accepts requests again.
+
+use std::{
+    sync::{Arc, Mutex, RwLock},
+    thread,
+    time::{Duration, Instant}
+};
+use log::{info, warn};
+use serde::Serialize;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_client::RpcClient
+};
+use solana_sdk::{pubkey::Pubkey, sysvar};
+use tokio::task::JoinHandle;
+use crate::{
+    json::{serialize_millis_option, ToJson},
+    rate_limit::{rate_limit_error, RpcProvider},
+    supervisor::ShutdownSignal,
+    utils::create_rpc_client
+};
+
+/// Requests of `benchmark_endpoints`.
+///
+/// ### Fields
+///
+/// - `samples`: Number of times each request is measured, the median latency is reported.
+/// - `account`: Account fetched to measure `getAccountInfo`, the clock sysvar by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BenchmarkConfig {
+    pub samples: u32,
+    pub account: Pubkey,
+}
+
+impl Default for BenchmarkConfig {
+    fn default() -> Self {
+        Self { samples: 3, account: sysvar::clock::id() }
+    }
+}
+
+/// Measurements of one endpoint.
+///
+/// ### Fields
+///
+/// - `url`: The endpoint.
+/// - `blockhash_latency`: Median latency of `getLatestBlockhash`, `None` if every request failed.
+/// - `account_latency`: Median latency of `getAccountInfo`, `None` if every request failed.
+/// - `slot`: Slot reported by the endpoint.
+/// - `slot_lag`: Slots behind the highest slot reported by any benchmarked endpoint.
+/// - `errors`: Number of failed requests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EndpointBenchmark {
+    pub url: String,
+    #[serde(serialize_with = "serialize_millis_option")]
+    pub blockhash_latency: Option<Duration>,
+    #[serde(serialize_with = "serialize_millis_option")]
+    pub account_latency: Option<Duration>,
+    pub slot: Option<u64>,
+    pub slot_lag: Option<u64>,
+    pub errors: u32,
+}
+
+impl EndpointBenchmark {
+    /// Whether every kind of request succeeded at least once.
+    pub fn is_reachable(&self) -> bool {
+        self.blockhash_latency.is_some() && self.account_latency.is_some() && self.slot.is_some()
+    }
+
+    /// Sum of the median latencies, `None` if the endpoint is unreachable.
+    pub fn latency(&self) -> Option<Duration> {
+        if !self.is_reachable() {
+            return None;
+        }
+        Some(self.blockhash_latency? + self.account_latency?)
+    }
+}
+
+/// Benchmarks ranked from best to worst: reachable endpoints first, then by slot lag, then by
+/// latency. Endpoints with equal slot lag and latency keep their input order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BenchmarkReport {
+    pub endpoints: Vec<EndpointBenchmark>,
+}
+
+impl ToJson for BenchmarkReport {}
+
+impl BenchmarkReport {
+    /// The best endpoint, `None` if no endpoint is reachable.
+    pub fn fastest(&self) -> Option<&EndpointBenchmark> {
+        self.endpoints.first().filter(|endpoint| endpoint.is_reachable())
+    }
+
+    /// URLs of the reachable endpoints in rank order.
+    pub fn ranked_urls(&self) -> Vec<&str> {
+        self.endpoints
+            .iter()
+            .filter(|endpoint| endpoint.is_reachable())
+            .map(|endpoint| endpoint.url.as_str())
+            .collect()
+    }
+}
+
+/// Measures `getLatestBlockhash` and `getAccountInfo` latency and the slot lag of every endpoint
+/// in parallel. Endpoints are URLs or environment variables holding them, like `create_rpc_client`.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::rpc_pool::{benchmark_endpoints, RpcPool};
+///
+/// let report = benchmark_endpoints(&["https://api.mainnet-beta.solana.com", "HELIUS_RPC_URL"]);
+/// for endpoint in &report.endpoints {
+///     println!("{} {:?} lag {:?}", endpoint.url, endpoint.latency(), endpoint.slot_lag);
+/// }
+/// let pool = RpcPool::from_report(&report);
+/// let client = pool.primary().unwrap();
+/// ```
+pub fn benchmark_endpoints(endpoints: &[&str]) -> BenchmarkReport {
+    benchmark_endpoints_with_config(endpoints, &BenchmarkConfig::default())
+}
+
+/// `benchmark_endpoints` with the number of samples and the fetched account of `config`.
+pub fn benchmark_endpoints_with_config(endpoints: &[&str], config: &BenchmarkConfig) -> BenchmarkReport {
+    let clients: Vec<RpcClient> = endpoints.iter().map(|endpoint| create_rpc_client(endpoint)).collect();
+    benchmark_clients(&clients, config)
+}
+
+/// `benchmark_endpoints` for existing clients.
+pub fn benchmark_clients(clients: &[RpcClient], config: &BenchmarkConfig) -> BenchmarkReport {
+    let mut endpoints: Vec<EndpointBenchmark> = thread::scope(|scope| {
+        let handles: Vec<_> = clients
+            .iter()
+            .map(|client| scope.spawn(move || benchmark_client(client, config)))
+            .collect();
+        handles
+            .into_iter()
+            .zip(clients)
+            .map(|(handle, client)| handle.join().unwrap_or_else(|_| unreachable_endpoint(client, config)))
+            .collect()
+    });
+
+    if let Some(max_slot) = endpoints.iter().filter_map(|endpoint| endpoint.slot).max() {
+        for endpoint in &mut endpoints {
+            endpoint.slot_lag = endpoint.slot.map(|slot| max_slot.saturating_sub(slot));
+        }
+    }
+    endpoints.sort_by_key(|endpoint| (!endpoint.is_reachable(), endpoint.slot_lag, endpoint.latency()));
+    BenchmarkReport { endpoints }
+}
+
+fn benchmark_client(client: &RpcClient, config: &BenchmarkConfig) -> EndpointBenchmark {
+    let mut errors = 0;
+    let mut measure = |request: &dyn Fn() -> bool| {
+        let mut latencies = Vec::new();
+        for _ in 0..config.samples.max(1) {
+            let start = Instant::now();
+            if request() {
+                latencies.push(start.elapsed());
+            } else {
+                errors += 1;
+            }
+        }
+        median(latencies)
+    };
+    let blockhash_latency = measure(&|| client.get_latest_blockhash().is_ok());
+    let account_latency = measure(&|| client.get_account_with_commitment(&config.account, client.commitment()).is_ok());
+    let slot = client.get_slot().inspect_err(|_| errors += 1).ok();
+
+    EndpointBenchmark { url: client.url(), blockhash_latency, account_latency, slot, slot_lag: None, errors }
+}
+
+fn unreachable_endpoint(client: &RpcClient, config: &BenchmarkConfig) -> EndpointBenchmark {
+    EndpointBenchmark {
+        url: client.url(),
+        blockhash_latency: None,
+        account_latency: None,
+        slot: None,
+        slot_lag: None,
+        errors: config.samples.max(1) * 2 + 1,
+    }
+}
+
+fn median(mut latencies: Vec<Duration>) -> Option<Duration> {
+    latencies.sort();
+    latencies.get(latencies.len() / 2).copied()
+}
+
+/// Limits of `RpcPool::check_slot_lag` and the slot lag monitor.
+///
+/// ### Fields
+///
+/// - `max_slot_lag`: Endpoints more slots behind the highest observed slot are quarantined.
+/// - `interval`: Time between checks of the monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotLagConfig {
+    pub max_slot_lag: u64,
+    pub interval: Duration,
+}
+
+impl Default for SlotLagConfig {
+    fn default() -> Self {
+        Self { max_slot_lag: 25, interval: Duration::from_secs(2) }
+    }
+}
+
+/// Slot of an endpoint at the last check.
+///
+/// ### Fields
+///
+/// - `slot`: Slot reported by the endpoint, `None` if it could not be fetched.
+/// - `slot_lag`: Slots behind the highest slot of the check.
+/// - `quarantined`: Whether the endpoint is skipped until it catches up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SlotLagStatus {
+    pub url: String,
+    pub slot: Option<u64>,
+    pub slot_lag: Option<u64>,
+    pub quarantined: bool,
+}
+
+/// How long a rate limited endpoint is skipped when the provider does not ask for a delay.
+pub const RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(1);
+
+struct PoolEndpoint {
+    client: RpcClient,
+    slot: Option<u64>,
+    quarantined: bool,
+    rate_limited_until: Mutex<Option<Instant>>,
+}
+
+impl PoolEndpoint {
+    fn is_available(&self) -> bool {
+        let rate_limited_until = self.rate_limited_until.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        !self.quarantined && rate_limited_until.is_none_or(|until| Instant::now() >= until)
+    }
+
+    fn rate_limit(&self, cooldown: Duration) {
+        *self.rate_limited_until.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Instant::now() + cooldown);
+    }
+}
+
+impl From<RpcClient> for PoolEndpoint {
+    fn from(client: RpcClient) -> Self {
+        Self { client, slot: None, quarantined: false, rate_limited_until: Mutex::new(None) }
+    }
+}
+
+/// Clients ordered by preference, the first one that is neither quarantined nor rate limited is
+/// used for requests and the others are fallbacks.
+pub struct RpcPool {
+    endpoints: Vec<PoolEndpoint>,
+}
+
+impl RpcPool {
+    /// Pool of `endpoints` in the given order, URLs or environment variables holding them.
+    pub fn new(endpoints: &[&str]) -> Self {
+        Self::from_clients(endpoints.iter().map(|endpoint| create_rpc_client(endpoint)).collect())
+    }
+
+    /// Pool of existing clients in the given order.
+    pub fn from_clients(clients: Vec<RpcClient>) -> Self {
+        Self { endpoints: clients.into_iter().map(PoolEndpoint::from).collect() }
+    }
+
+    /// Pool of the reachable endpoints of `report` in rank order.
+    pub fn from_report(report: &BenchmarkReport) -> Self {
+        Self::new(&report.ranked_urls())
+    }
+
+    /// Reorders the clients by rank in `report`. Unreachable clients and clients missing from
+    /// the report move to the end, keeping their order.
+    pub fn rank(&mut self, report: &BenchmarkReport) {
+        let ranked = report.ranked_urls();
+        self.endpoints.sort_by_key(|endpoint| {
+            let url = endpoint.client.url();
+            ranked.iter().position(|ranked_url| *ranked_url == url).unwrap_or(usize::MAX)
+        });
+    }
+
+    /// The preferred available client, `None` if every client is quarantined or rate limited or
+    /// the pool is empty.
+    pub fn primary(&self) -> Option<&RpcClient> {
+        self.healthy_clients().into_iter().next()
+    }
+
+    /// Clients that are neither quarantined nor rate limited in preference order.
+    pub fn healthy_clients(&self) -> Vec<&RpcClient> {
+        self.endpoints
+            .iter()
+            .filter(|endpoint| endpoint.is_available())
+            .map(|endpoint| &endpoint.client)
+            .collect()
+    }
+
+    /// Runs `request` on the available clients in preference order until one is not rate
+    /// limited. Rate limited clients are skipped for the delay asked by their provider, or
+    /// `RATE_LIMIT_COOLDOWN`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the first error that is not a rate limit, the last rate limit error if every
+    /// client is rate limited and `ClientErrorKind::Custom` if no client is available. The error
+    /// is boxed, as `ClientError` is large.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use easy_solana::rpc_pool::RpcPool;
+    ///
+    /// let pool = RpcPool::new(&["HELIUS_RPC_URL", "https://api.mainnet-beta.solana.com"]);
+    /// let slot = pool.with_fallback(|client| client.get_slot()).unwrap();
+    /// ```
+    pub fn with_fallback<T>(&self, request: impl Fn(&RpcClient) -> ClientResult<T>) -> Result<T, Box<ClientError>> {
+        let mut last_error = None;
+        for endpoint in self.endpoints.iter().filter(|endpoint| endpoint.is_available()) {
+            let err = match request(&endpoint.client) {
+                Ok(result) => return Ok(result),
+                Err(err) => err,
+            };
+            let url = endpoint.client.url();
+            let Some(limit) = rate_limit_error(&err, RpcProvider::from_url(&url)) else {
+                return Err(Box::new(err));
+            };
+            let cooldown = limit.retry_after.unwrap_or(RATE_LIMIT_COOLDOWN);
+            warn!("{} is rate limited, rerouting for {:?}", url, cooldown);
+            endpoint.rate_limit(cooldown);
+            last_error = Some(err);
+        }
+        Err(Box::new(last_error.unwrap_or_else(|| ClientErrorKind::Custom("No healthy RPC endpoint".to_string()).into())))
+    }
+
+    /// Every client in preference order, including quarantined and rate limited ones.
+    pub fn clients(&self) -> Vec<&RpcClient> {
+        self.endpoints.iter().map(|endpoint| &endpoint.client).collect()
+    }
+
+    /// URLs of the clients in preference order.
+    pub fn urls(&self) -> Vec<String> {
+        self.endpoints.iter().map(|endpoint| endpoint.client.url()).collect()
+    }
+
+    /// Fetches the slot of every client in parallel, `None` for clients that failed.
+    pub fn fetch_slots(&self) -> Vec<Option<u64>> {
+        thread::scope(|scope| {
+            let handles: Vec<_> = self.endpoints
+                .iter()
+                .map(|endpoint| scope.spawn(|| endpoint.client.get_slot().ok()))
+                .collect();
+            handles.into_iter().map(|handle| handle.join().ok().flatten()).collect()
+        })
+    }
+
+    /// Fetches the slot of every client and quarantines those lagging more than
+    /// `config.max_slot_lag` behind the highest slot or failing to respond. Quarantined clients
+    /// are released once they catch up.
+    pub fn check_slot_lag(&mut self, config: &SlotLagConfig) -> Vec<SlotLagStatus> {
+        let slots = self.fetch_slots();
+        self.record_slots(&slots, config)
+    }
+
+    /// Updates the quarantine of every client from `slots`, in the order of `clients`.
+    pub fn record_slots(&mut self, slots: &[Option<u64>], config: &SlotLagConfig) -> Vec<SlotLagStatus> {
+        let max_slot = slots.iter().flatten().max().copied();
+        self.endpoints
+            .iter_mut()
+            .zip(slots)
+            .map(|(endpoint, slot)| {
+                let slot_lag = slot.zip(max_slot).map(|(slot, max_slot)| max_slot.saturating_sub(slot));
+                let quarantined = slot_lag.is_none_or(|slot_lag| slot_lag > config.max_slot_lag);
+                if quarantined != endpoint.quarantined {
+                    let url = endpoint.client.url();
+                    match quarantined {
+                        true => warn!("Quarantining {}, slot lag {:?}", url, slot_lag),
+                        false => info!("Releasing {} from quarantine", url),
+                    }
+                }
+                endpoint.slot = *slot;
+                endpoint.quarantined = quarantined;
+                SlotLagStatus { url: endpoint.client.url(), slot: *slot, slot_lag, quarantined }
+            })
+            .collect()
+    }
+}
+
+/// Checks the slot lag of `pool` every `config.interval` until `shutdown`, so reads through
+/// `primary` skip stale nodes. Commonly spawned next to listeners of a `Supervisor`.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{
+///     rpc_pool::{spawn_slot_lag_monitor, RpcPool, SlotLagConfig},
+///     supervisor::Supervisor
+/// };
+/// use std::sync::{Arc, RwLock};
+///
+/// # async fn example() {
+/// let supervisor = Supervisor::new();
+/// let pool = Arc::new(RwLock::new(RpcPool::new(&["https://api.mainnet-beta.solana.com", "HELIUS_RPC_URL"])));
+/// spawn_slot_lag_monitor(Arc::clone(&pool), SlotLagConfig::default(), supervisor.shutdown_signal());
+///
+/// let slot = pool.read().unwrap().primary().unwrap().get_slot();
+/// # }
+/// ```
+pub fn spawn_slot_lag_monitor(
+    pool: Arc<RwLock<RpcPool>>,
+    config: SlotLagConfig,
+    mut shutdown: ShutdownSignal
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        while !shutdown.is_shutdown() {
+            let check_pool = Arc::clone(&pool);
+            // Slots are fetched under the read lock so requests keep flowing during the check
+            let result = tokio::task::spawn_blocking(move || {
+                let slots = check_pool.read().map(|pool| pool.fetch_slots()).ok()?;
+                check_pool.write().ok().map(|mut pool| pool.record_slots(&slots, &config))
+            }).await;
+            if result.is_err() {
+                warn!("Slot lag check panicked");
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(config.interval) => {}
+                _ = shutdown.cancelled() => {}
+            }
+        }
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_client::rpc_client::RpcClientConfig;
+    use solana_rpc_client::mock_sender::MocksMap;
+    use solana_sdk::commitment_config::CommitmentConfig;
+    use crate::test_utils::RateLimitedSender;
+
+    fn client_at_slot(url: &str, slot: u64) -> RpcClient {
+        let mocks: MocksMap = vec![(RpcRequest::GetSlot, json!(slot))].into_iter().collect();
+        RpcClient::new_mock_with_mocks_map(url.to_string(), mocks)
+    }
+
+    fn benchmark(url: &str, latency_ms: Option<u64>, slot_lag: Option<u64>) -> EndpointBenchmark {
+        let latency = latency_ms.map(Duration::from_millis);
+        EndpointBenchmark {
+            url: url.to_string(),
+            blockhash_latency: latency,
+            account_latency: latency,
+            slot: slot_lag.map(|lag| 100 - lag),
+            slot_lag,
+            errors: 0,
+        }
+    }
+
+    #[test]
+    fn test_benchmark_clients() {
+        let clients = vec![RpcClient::new_mock("fails"), client_at_slot("succeeds", 90), client_at_slot("succeeds", 100)];
+        let report = benchmark_clients(&clients, &BenchmarkConfig { samples: 2, ..BenchmarkConfig::default() });
+
+        assert!(report.endpoints.len() == 3);
+        // In sync endpoints rank first, unreachable ones last
+        assert!(report.endpoints[0].slot == Some(100) && report.endpoints[0].slot_lag == Some(0));
+        assert!(report.endpoints[1].slot_lag == Some(10));
+        let failed = &report.endpoints[2];
+        assert!(!failed.is_reachable() && failed.errors == 5 && failed.latency().is_none());
+        assert!(report.fastest().unwrap().errors == 0);
+        assert!(report.ranked_urls().len() == 2);
+        assert!(report.to_json()["endpoints"][2]["blockhash_latency"].is_null());
+    }
+
+    #[test]
+    fn test_pool_follows_report_ranking() {
+        let report = BenchmarkReport {
+            endpoints: vec![
+                benchmark("https://fast.rpc", Some(10), Some(0)),
+                benchmark("https://slow.rpc", Some(90), Some(0)),
+                benchmark("https://down.rpc", None, None),
+            ],
+        };
+        let pool = RpcPool::from_report(&report);
+        assert!(pool.urls() == vec!["https://fast.rpc", "https://slow.rpc"]);
+        assert!(pool.primary().unwrap().url() == "https://fast.rpc");
+
+        let mut pool = RpcPool::new(&["https://down.rpc", "https://other.rpc", "https://slow.rpc", "https://fast.rpc"]);
+        pool.rank(&report);
+        assert!(pool.urls() == vec!["https://fast.rpc", "https://slow.rpc", "https://down.rpc", "https://other.rpc"]);
+        assert!(RpcPool::new(&[]).primary().is_none());
+    }
+
+    #[test]
+    fn test_quarantine_lagging_endpoints() {
+        let config = SlotLagConfig { max_slot_lag: 10, ..SlotLagConfig::default() };
+        let mut pool = RpcPool::new(&["https://primary.rpc", "https://backup.rpc", "https://down.rpc"]);
+
+        let statuses = pool.record_slots(&[Some(100), Some(95), None], &config);
+        assert!(statuses[1].slot_lag == Some(5) && !statuses[1].quarantined);
+        assert!(statuses[2].quarantined && pool.healthy_clients().len() == 2);
+
+        // The primary falls behind, requests move to the backup until it catches up
+        pool.record_slots(&[Some(100), Some(120), Some(118)], &config);
+        assert!(pool.primary().unwrap().url() == "https://backup.rpc");
+        assert!(pool.clients().len() == 3);
+        pool.record_slots(&[Some(125), Some(125), Some(125)], &config);
+        assert!(pool.primary().unwrap().url() == "https://primary.rpc");
+        assert!(pool.healthy_clients().len() == 3);
+    }
+
+    #[test]
+    fn test_check_slot_lag() {
+        let mut pool = RpcPool::from_clients(vec![client_at_slot("succeeds", 50), client_at_slot("succeeds", 100), RpcClient::new_mock("fails")]);
+        let statuses = pool.check_slot_lag(&SlotLagConfig::default());
+        assert!(statuses.iter().map(|status| status.quarantined).collect::<Vec<_>>() == vec![true, false, true]);
+        assert!(pool.primary().unwrap().get_slot().unwrap() == 0);
+    }
+
+    #[test]
+    // The requests return the `ClientResult` of `RpcClient`
+    #[allow(clippy::result_large_err)]
+    fn test_reroute_rate_limited_endpoints() {
+        let limited = RateLimitedSender::new("https://mainnet.helius-rpc.com", (-32429, "Rate limited"), 1, json!(7));
+        let limited = RpcClient::new_sender(limited, RpcClientConfig::with_commitment(CommitmentConfig::confirmed()));
+        let pool = RpcPool::from_clients(vec![limited, client_at_slot("succeeds", 100)]);
+
+        assert!(pool.with_fallback(|client| client.get_slot()).unwrap() == 100);
+        // Without a delay from Helius the endpoint cools down for `RATE_LIMIT_COOLDOWN`
+        assert!(pool.healthy_clients().len() == 1 && pool.primary().unwrap().url() != "https://mainnet.helius-rpc.com");
+        // Other errors are not rerouted
+        let err = pool.with_fallback(|_| Err::<u64, _>(ClientErrorKind::Custom("failed".to_string()).into()));
+        assert!(matches!(err.unwrap_err().kind(), ClientErrorKind::Custom(message) if message == "failed"));
+        assert!(RpcPool::new(&[]).with_fallback(|client| client.get_slot()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_slot_lag_monitor_stops_on_shutdown() {
+        let supervisor = crate::supervisor::Supervisor::new();
+        let pool = Arc::new(RwLock::new(RpcPool::from_clients(vec![RpcClient::new_mock("fails")])));
+        let config = SlotLagConfig { interval: Duration::from_millis(5), ..SlotLagConfig::default() };
+        let monitor = spawn_slot_lag_monitor(Arc::clone(&pool), config, supervisor.shutdown_signal());
+        for _ in 0..100 {
+            if pool.read().unwrap().primary().is_none() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert!(pool.read().unwrap().primary().is_none());
+        supervisor.shutdown(Duration::from_secs(1)).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), monitor).await.unwrap().unwrap();
+    }
+}
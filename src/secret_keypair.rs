@@ -0,0 +1,143 @@
+//! # Secret Keypair
+//!
+//! `SecretKeypair` wraps a solana-sdk `Keypair` so private keys loaded from base58 strings or
+//! environment variables do not linger in memory or leak through logs.
+
+use std::{env, fmt, ops::Deref};
+use solana_sdk::{bs58, signature::Keypair, signer::Signer};
+use zeroize::Zeroizing;
+use crate::error::KeypairError;
+
+/// A keypair whose secret key is zeroized on drop and is never printed by `Debug`.
+///
+/// Intermediate base58 strings and bytes read while loading the keypair are zeroized as well.
+/// It dereferences to `Keypair`, so it can be passed anywhere a `&Keypair` is accepted, such as
+/// `TransactionBuilder::new`, and implements `Signer` through `Deref` for signing transactions directly.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, SecretKeypair, TransactionBuilder};
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let keypair = SecretKeypair::from_env("PRIVATE_KEY").unwrap();
+/// let transaction = TransactionBuilder::new(&client, &keypair)
+///     .transfer_sol(0.001, &keypair, "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5")
+///     .unwrap()
+///     .build()
+///     .unwrap();
+/// ```
+pub struct SecretKeypair(Keypair);
+
+impl SecretKeypair {
+    /// Generates a new random keypair.
+    pub fn generate() -> Self {
+        SecretKeypair(Keypair::new())
+    }
+
+    /// Reads a keypair from a base58 encoded private key.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `KeypairError::Base58DecodeError` or `KeypairError::InvalidKeypairBytes` if the
+    /// string is not a valid keypair.
+    pub fn from_base58(keypair_string: &str) -> Result<Self, KeypairError> {
+        let keypair_bytes = Zeroizing::new(
+            bs58::decode(keypair_string)
+                .into_vec()
+                .map_err(|_| KeypairError::Base58DecodeError)?
+        );
+        Self::from_bytes(&keypair_bytes)
+    }
+
+    /// Reads a keypair from 64 bytes of secret and public key.
+    pub fn from_bytes(keypair_bytes: &[u8]) -> Result<Self, KeypairError> {
+        Keypair::try_from(keypair_bytes)
+            .map(SecretKeypair)
+            .map_err(|_| KeypairError::InvalidKeypairBytes)
+    }
+
+    /// Reads a keypair from a base58 encoded private key stored in an environment variable,
+    /// loading a `.env` file if present. The environment variable itself is left untouched.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `KeypairError::EnvVarNotFound` if the environment variable is not set.
+    pub fn from_env(env_var: &str) -> Result<Self, KeypairError> {
+        dotenv::dotenv().ok();
+        let keypair_string = Zeroizing::new(
+            env::var(env_var).map_err(|_| KeypairError::EnvVarNotFound(env_var.to_string()))?
+        );
+        Self::from_base58(&keypair_string)
+    }
+
+    /// The wrapped keypair.
+    pub fn keypair(&self) -> &Keypair {
+        &self.0
+    }
+
+    /// Base58 encoded private key, zeroized when dropped.
+    pub fn to_base58_string(&self) -> Zeroizing<String> {
+        Zeroizing::new(self.0.to_base58_string())
+    }
+}
+
+impl From<Keypair> for SecretKeypair {
+    fn from(keypair: Keypair) -> Self {
+        SecretKeypair(keypair)
+    }
+}
+
+impl Deref for SecretKeypair {
+    type Target = Keypair;
+
+    fn deref(&self) -> &Keypair {
+        &self.0
+    }
+}
+
+impl AsRef<Keypair> for SecretKeypair {
+    fn as_ref(&self) -> &Keypair {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretKeypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretKeypair")
+            .field("pubkey", &self.0.pubkey())
+            .finish_non_exhaustive()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_keypair_from_base58() {
+        let keypair = Keypair::new();
+        let secret_keypair = SecretKeypair::from_base58(&keypair.to_base58_string()).unwrap();
+        assert!(secret_keypair.pubkey() == keypair.pubkey());
+        assert!(*secret_keypair.to_base58_string() == keypair.to_base58_string());
+        assert!(matches!(SecretKeypair::from_base58("0OIl"), Err(KeypairError::Base58DecodeError)));
+        assert!(matches!(SecretKeypair::from_base58("abc"), Err(KeypairError::InvalidKeypairBytes)));
+    }
+
+    #[test]
+    fn test_secret_keypair_debug_hides_secret() {
+        let secret_keypair = SecretKeypair::generate();
+        let debug = format!("{:?}", secret_keypair);
+        assert!(debug.contains(&secret_keypair.pubkey().to_string()));
+        assert!(!debug.contains(&format!("{:?}", secret_keypair.secret_bytes())));
+        assert!(!debug.contains(secret_keypair.to_base58_string().as_str()));
+    }
+
+    #[test]
+    fn test_secret_keypair_from_missing_env_var() {
+        let result = SecretKeypair::from_env("EASY_SOLANA_MISSING_PRIVATE_KEY");
+        assert!(matches!(result, Err(KeypairError::EnvVarNotFound(_))));
+    }
+}
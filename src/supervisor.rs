@@ -0,0 +1,359 @@
+//! # Supervisor
+//!
+//! Owns long running listener tasks such as websocket or geyser subscriptions, restarts them
+//! with exponential backoff when they fail or panic, reports their health and stops them
+//! together on shutdown.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    future::Future,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant}
+};
+use log::{info, warn};
+use tokio::{sync::watch, task::JoinHandle};
+use crate::error::SupervisorError;
+
+/// Backoff of a restarted task.
+///
+/// ### Fields
+///
+/// - `initial_backoff`: Delay before the first restart.
+/// - `max_backoff`: Upper bound of the delay, it doubles after every failed attempt.
+/// - `max_restarts`: Restarts before the task is marked as failed, unlimited if `None`.
+/// - `healthy_after`: Attempts running longer than this reset the backoff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub max_restarts: Option<u32>,
+    pub healthy_after: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_restarts: None,
+            healthy_after: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Delay before restart number `restart`, starting at 1.
+    pub fn backoff(&self, restart: u32) -> Duration {
+        let factor = 2u32.saturating_pow(restart.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Health of a supervised task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskHealth {
+    Running,
+    /// The task failed with `last_error` and is restarted after a backoff.
+    Restarting { restarts: u32, last_error: String },
+    /// The task returned successfully and is not restarted.
+    Completed,
+    /// The task exceeded `max_restarts`.
+    Failed { restarts: u32, last_error: String },
+    /// The task was stopped by a shutdown.
+    Stopped,
+}
+
+impl TaskHealth {
+    /// Whether the task is running or ended without an error.
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, TaskHealth::Running | TaskHealth::Completed | TaskHealth::Stopped)
+    }
+}
+
+/// Shutdown notification handed to every attempt of a task, so it can unsubscribe and return.
+#[derive(Debug, Clone)]
+pub struct ShutdownSignal(watch::Receiver<bool>);
+
+impl ShutdownSignal {
+    /// Whether a shutdown was requested.
+    pub fn is_shutdown(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Completes once a shutdown is requested, e.g in `tokio::select!` next to a subscription.
+    pub async fn cancelled(&mut self) {
+        // An error means the supervisor was dropped, which also ends its tasks
+        let _ = self.0.wait_for(|shutdown| *shutdown).await;
+    }
+}
+
+type HealthMap = Arc<RwLock<HashMap<String, TaskHealth>>>;
+
+/// Supervises named tasks.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{
+///     create_rpc_client,
+///     read_transactions::mint_watcher::watch_mint,
+///     supervisor::{RestartPolicy, Supervisor}
+/// };
+/// use std::time::Duration;
+///
+/// # async fn example() {
+/// let mut supervisor = Supervisor::new();
+/// supervisor.spawn("mint-watcher", RestartPolicy::default(), |mut shutdown| async move {
+///     let client = create_rpc_client("RPC_URL");
+///     let mut events = watch_mint(&client, "wss://api.mainnet-beta.solana.com", "CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump")
+///         .await
+///         .map_err(|err| err.to_string())?;
+///     loop {
+///         tokio::select! {
+///             event = events.recv() => match event {
+///                 Some(event) => println!("{:?}", event),
+///                 None => return Err("Subscription ended".to_string()),
+///             },
+///             _ = shutdown.cancelled() => return Ok(()),
+///         }
+///     }
+/// }).unwrap();
+///
+/// println!("{:?}", supervisor.health());
+/// supervisor.shutdown(Duration::from_secs(5)).await.unwrap();
+/// # }
+/// ```
+pub struct Supervisor {
+    shutdown: watch::Sender<bool>,
+    health: HealthMap,
+    tasks: Vec<(String, JoinHandle<()>)>,
+}
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        let (shutdown, _) = watch::channel(false);
+        Self { shutdown, health: Arc::new(RwLock::new(HashMap::new())), tasks: Vec::new() }
+    }
+
+    /// Runs the future created by `task` until it returns `Ok`, restarting it after an error
+    /// or panic following `policy`. Every attempt receives a `ShutdownSignal` and should return
+    /// once it is cancelled. Must be called within a tokio runtime.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `SupervisorError::DuplicateTask` if a task with the same name is supervised and
+    /// `SupervisorError::ShuttingDown` after `shutdown` was requested.
+    pub fn spawn<F, Fut, E>(&mut self, name: &str, policy: RestartPolicy, mut task: F) -> Result<(), SupervisorError>
+    where
+        F: FnMut(ShutdownSignal) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Display + Send + 'static,
+    {
+        if *self.shutdown.borrow() {
+            return Err(SupervisorError::ShuttingDown);
+        }
+        if self.tasks.iter().any(|(task_name, _)| task_name == name) {
+            return Err(SupervisorError::DuplicateTask(name.to_string()));
+        }
+
+        let name = name.to_string();
+        let health = Arc::clone(&self.health);
+        let mut shutdown = ShutdownSignal(self.shutdown.subscribe());
+        set_health(&health, &name, TaskHealth::Running);
+
+        let task_name = name.clone();
+        let handle = tokio::spawn(async move {
+            let mut restarts = 0;
+            loop {
+                let started = Instant::now();
+                let result = tokio::spawn(task(shutdown.clone())).await;
+                if shutdown.is_shutdown() {
+                    set_health(&health, &task_name, TaskHealth::Stopped);
+                    return;
+                }
+                let last_error = match result {
+                    Ok(Ok(())) => {
+                        set_health(&health, &task_name, TaskHealth::Completed);
+                        return;
+                    }
+                    Ok(Err(err)) => err.to_string(),
+                    Err(err) => format!("Task panicked: {}", err),
+                };
+
+                if started.elapsed() >= policy.healthy_after {
+                    restarts = 0;
+                }
+                if policy.max_restarts.is_some_and(|max_restarts| restarts >= max_restarts) {
+                    warn!("Task {} failed after {} restarts: {}", task_name, restarts, last_error);
+                    set_health(&health, &task_name, TaskHealth::Failed { restarts, last_error });
+                    return;
+                }
+                restarts += 1;
+                let backoff = policy.backoff(restarts);
+                warn!("Task {} failed, restarting in {:?}: {}", task_name, backoff, last_error);
+                set_health(&health, &task_name, TaskHealth::Restarting { restarts, last_error });
+
+                tokio::select! {
+                    _ = tokio::time::sleep(backoff) => {
+                        info!("Restarting task {}", task_name);
+                        set_health(&health, &task_name, TaskHealth::Running);
+                    }
+                    _ = shutdown.cancelled() => {
+                        set_health(&health, &task_name, TaskHealth::Stopped);
+                        return;
+                    }
+                }
+            }
+        });
+        self.tasks.push((name, handle));
+        Ok(())
+    }
+
+    /// Signal handed to tasks, e.g to stop work that is not supervised together with them.
+    pub fn shutdown_signal(&self) -> ShutdownSignal {
+        ShutdownSignal(self.shutdown.subscribe())
+    }
+
+    /// Health of every supervised task by name.
+    pub fn health(&self) -> HashMap<String, TaskHealth> {
+        self.health.read().map(|health| health.clone()).unwrap_or_default()
+    }
+
+    /// Whether every task is running or ended without an error.
+    pub fn is_healthy(&self) -> bool {
+        self.health().values().all(TaskHealth::is_healthy)
+    }
+
+    /// Requests a shutdown and waits up to `timeout` for the tasks to return. Tasks still running
+    /// afterwards are aborted.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `SupervisorError::ShutdownTimeout` with the names of the aborted tasks.
+    pub async fn shutdown(self, timeout: Duration) -> Result<(), SupervisorError> {
+        self.shutdown.send_replace(true);
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut aborted = Vec::new();
+        for (name, mut handle) in self.tasks {
+            if tokio::time::timeout_at(deadline, &mut handle).await.is_err() {
+                handle.abort();
+                set_health(&self.health, &name, TaskHealth::Stopped);
+                aborted.push(name);
+            }
+        }
+        if aborted.is_empty() {
+            Ok(())
+        } else {
+            Err(SupervisorError::ShutdownTimeout(aborted))
+        }
+    }
+
+    /// Waits for Ctrl-C, then shuts down like `shutdown`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `SupervisorError::ShutdownTimeout` with the names of the aborted tasks.
+    pub async fn run_until_ctrl_c(self, timeout: Duration) -> Result<(), SupervisorError> {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            warn!("Unable to listen for Ctrl-C, shutting down: {}", err);
+        }
+        self.shutdown(timeout).await
+    }
+}
+
+fn set_health(health: &HealthMap, name: &str, task_health: TaskHealth) {
+    if let Ok(mut health) = health.write() {
+        health.insert(name.to_string(), task_health);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_policy(max_restarts: Option<u32>) -> RestartPolicy {
+        RestartPolicy {
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+            max_restarts,
+            healthy_after: Duration::from_secs(60),
+        }
+    }
+
+    async fn wait_for_health(supervisor: &Supervisor, name: &str, expected: impl Fn(&TaskHealth) -> bool) {
+        for _ in 0..200 {
+            if supervisor.health().get(name).is_some_and(&expected) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("Unexpected health of {}: {:?}", name, supervisor.health().get(name));
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let policy = RestartPolicy::default();
+        assert!(policy.backoff(1) == Duration::from_millis(500));
+        assert!(policy.backoff(3) == Duration::from_secs(2));
+        assert!(policy.backoff(20) == Duration::from_secs(30));
+        assert!(policy.backoff(u32::MAX) == Duration::from_secs(30));
+    }
+
+    #[tokio::test]
+    async fn test_restarts_failed_and_panicked_tasks() {
+        let mut supervisor = Supervisor::new();
+        let attempts = Arc::new(AtomicU32::new(0));
+        let task_attempts = Arc::clone(&attempts);
+        supervisor.spawn("flaky", fast_policy(None), move |_| {
+            let attempt = task_attempts.fetch_add(1, Ordering::SeqCst);
+            async move {
+                match attempt {
+                    0 => Err("Connection reset"),
+                    1 => panic!("Subscription crashed"),
+                    _ => Ok(()),
+                }
+            }
+        }).unwrap();
+        wait_for_health(&supervisor, "flaky", |health| *health == TaskHealth::Completed).await;
+        assert!(attempts.load(Ordering::SeqCst) == 3);
+
+        supervisor.spawn("broken", fast_policy(Some(2)), |_| async { Err("Invalid endpoint") }).unwrap();
+        wait_for_health(&supervisor, "broken", |health| matches!(health, TaskHealth::Failed { restarts: 2, .. })).await;
+        assert!(!supervisor.is_healthy());
+        assert!(matches!(
+            supervisor.spawn("broken", fast_policy(None), |_| async { Ok::<(), String>(()) }),
+            Err(SupervisorError::DuplicateTask(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_tasks() {
+        let mut supervisor = Supervisor::new();
+        supervisor.spawn("listener", fast_policy(None), |mut shutdown| async move {
+            shutdown.cancelled().await;
+            Ok::<(), String>(())
+        }).unwrap();
+        supervisor.spawn("stuck", fast_policy(None), |_| async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok::<(), String>(())
+        }).unwrap();
+        wait_for_health(&supervisor, "listener", |health| *health == TaskHealth::Running).await;
+        assert!(supervisor.is_healthy());
+
+        let health = Arc::clone(&supervisor.health);
+        let result = supervisor.shutdown(Duration::from_millis(50)).await;
+        assert!(matches!(result, Err(SupervisorError::ShutdownTimeout(names)) if names == vec!["stuck".to_string()]));
+        let health = health.read().unwrap();
+        assert!(health.get("listener") == Some(&TaskHealth::Stopped));
+        assert!(health.get("stuck") == Some(&TaskHealth::Stopped));
+    }
+}
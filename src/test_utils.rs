@@ -0,0 +1,216 @@
+//! Helpers for tests that mock RPC responses instead of calling a live cluster.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use solana_account_decoder::{encode_ui_account, UiAccountEncoding};
+use solana_client::{
+    client_error::Result as ClientResult,
+    rpc_client::RpcClient,
+    rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+    rpc_sender::{RpcSender, RpcTransportStats}
+};
+use solana_rpc_client::mock_sender::MocksMap;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use solana_sdk::{account::Account, pubkey::Pubkey, transaction::Transaction};
+use crate::{
+    constants::pumpfun_accounts::{pumpfun_fee_account, BONDING_CURVE_DISCRIMINATOR, GLOBAL_ACCOUNT_DISCRIMINATOR},
+    pumpfun::{bonding_curve::BondingCurveAccount, global::GlobalAccount}
+};
+
+/// Creator of `fresh_bonding_curve`.
+pub(crate) const CURVE_CREATOR: Pubkey = Pubkey::new_from_array([1; 32]);
+
+/// Creates a client that answers each request with the queued responses, falling back
+/// to the default mock responses once a queue is empty.
+pub(crate) fn mock_client(responses: Vec<(RpcRequest, Value)>) -> RpcClient {
+    RpcClient::new_mock_with_mocks_map("succeeds", responses.into_iter().collect::<MocksMap>())
+}
+
+/// Response of `getMultipleAccounts`, `None` for accounts that do not exist.
+pub(crate) fn multiple_accounts_response(accounts: &[Option<Account>], slot: u64) -> Value {
+    let accounts: Vec<Value> = accounts
+        .iter()
+        .map(|account| match account {
+            Some(account) => encode_account(account),
+            None => Value::Null,
+        })
+        .collect();
+    json!({ "context": { "slot": slot }, "value": accounts })
+}
+
+/// Response of `getAccountInfo`, `None` if the account does not exist.
+pub(crate) fn account_info_response(account: Option<&Account>, slot: u64) -> Value {
+    let account = account.map(encode_account).unwrap_or(Value::Null);
+    json!({ "context": { "slot": slot }, "value": account })
+}
+
+/// Response of `getTokenAccountsByOwner` with base64 encoded accounts.
+pub(crate) fn token_accounts_by_owner_response(accounts: &[(Pubkey, Account)], slot: u64) -> Value {
+    let accounts: Vec<Value> = accounts
+        .iter()
+        .map(|(pubkey, account)| json!({ "pubkey": pubkey.to_string(), "account": encode_account(account) }))
+        .collect();
+    json!({ "context": { "slot": slot }, "value": accounts })
+}
+
+/// Response of `getProgramAccounts` with base64 encoded accounts.
+pub(crate) fn program_accounts_response(accounts: &[(Pubkey, Account)]) -> Value {
+    let accounts: Vec<Value> = accounts
+        .iter()
+        .map(|(pubkey, account)| json!({ "pubkey": pubkey.to_string(), "account": encode_account(account) }))
+        .collect();
+    Value::Array(accounts)
+}
+
+/// Response of `getSignaturesForAddress` from `(signature, slot, block_time, is_failed)`, newest first.
+pub(crate) fn signatures_for_address_response(signatures: &[(&str, u64, Option<i64>, bool)]) -> Value {
+    let signatures: Vec<Value> = signatures
+        .iter()
+        .map(|(signature, slot, block_time, is_failed)| json!({
+            "signature": signature,
+            "slot": slot,
+            "err": if *is_failed { json!({ "InstructionError": [0, "InvalidArgument"] }) } else { Value::Null },
+            "memo": null,
+            "blockTime": block_time,
+            "confirmationStatus": "confirmed"
+        }))
+        .collect();
+    Value::Array(signatures)
+}
+
+/// Response of `getTransaction` for a successful legacy transaction, base64 encoded.
+pub(crate) fn transaction_response(transaction: &Transaction, slot: u64, block_time: Option<i64>, logs: &[String]) -> Value {
+    let encoded_transaction = BASE64_STANDARD.encode(bincode::serialize(transaction).unwrap());
+    json!({
+        "slot": slot,
+        "blockTime": block_time,
+        "transaction": [encoded_transaction, "base64"],
+        "meta": {
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 5_000,
+            "preBalances": [],
+            "postBalances": [],
+            "innerInstructions": [],
+            "logMessages": logs,
+            "loadedAddresses": { "writable": [], "readonly": [] }
+        }
+    })
+}
+
+/// Response of a successful `simulateTransaction` with no inner instructions.
+pub(crate) fn simulation_response(logs: &[&str], units_consumed: u64) -> Value {
+    json!({
+        "context": { "slot": 1 },
+        "value": { "err": null, "logs": logs, "unitsConsumed": units_consumed, "innerInstructions": [] }
+    })
+}
+
+/// An account owned by `owner` holding `data`.
+pub(crate) fn account_with_data(owner: Pubkey, data: Vec<u8>) -> Account {
+    Account {
+        lamports: 1_000_000,
+        data,
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Bonding curve of a token created by `CURVE_CREATOR` that has not been traded yet, with the
+/// initial reserves of `global_account`.
+pub(crate) fn fresh_bonding_curve() -> BondingCurveAccount {
+    BondingCurveAccount {
+        discriminator: BONDING_CURVE_DISCRIMINATOR,
+        virtual_token_reserves: 1_073_000_000_000_000,
+        virtual_sol_reserves: 30_000_000_000,
+        real_token_reserves: 793_100_000_000_000,
+        real_sol_reserves: 0,
+        total_token_supply: 1_000_000_000_000_000,
+        complete: false,
+        creator: Some(CURVE_CREATOR),
+    }
+}
+
+/// Pump.fun global account with a 0.95% protocol fee and a 0.05% creator fee.
+pub(crate) fn global_account() -> GlobalAccount {
+    GlobalAccount {
+        initialized: true,
+        authority: Pubkey::default(),
+        fee_recipient: pumpfun_fee_account(),
+        initial_virtual_token_reserves: 1_073_000_000_000_000,
+        initial_virtual_sol_reserves: 30_000_000_000,
+        initial_real_token_reserves: 793_100_000_000_000,
+        token_total_supply: 1_000_000_000_000_000,
+        fee_basis_points: 95,
+        creator_fee_basis_points: 5,
+    }
+}
+
+/// Account data of `global_account`, as stored by the pump.fun program.
+pub(crate) fn global_account_data() -> Vec<u8> {
+    let global = global_account();
+    let mut data = GLOBAL_ACCOUNT_DISCRIMINATOR.to_vec();
+    data.push(global.initialized as u8);
+    data.extend_from_slice(global.authority.as_ref());
+    data.extend_from_slice(global.fee_recipient.as_ref());
+    for value in [
+        global.initial_virtual_token_reserves,
+        global.initial_virtual_sol_reserves,
+        global.initial_real_token_reserves,
+        global.token_total_supply,
+        global.fee_basis_points,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    // withdraw authority, migration flag and pool migration fee
+    data.extend_from_slice(&[0; 41]);
+    data.extend_from_slice(&global.creator_fee_basis_points.to_le_bytes());
+    data
+}
+
+fn encode_account(account: &Account) -> Value {
+    let ui_account = encode_ui_account(&Pubkey::default(), account, UiAccountEncoding::Base64, None, None);
+    serde_json::to_value(ui_account).unwrap()
+}
+
+/// Sender answering with the JSON-RPC error `code` `rate_limited` times, then with `result`.
+pub(crate) struct RateLimitedSender {
+    url: String,
+    code: i64,
+    message: String,
+    rate_limited: AtomicU32,
+    result: Value,
+}
+
+impl RateLimitedSender {
+    pub(crate) fn new(url: &str, (code, message): (i64, &str), rate_limited: u32, result: Value) -> Self {
+        Self { url: url.to_string(), code, message: message.to_string(), rate_limited: rate_limited.into(), result }
+    }
+}
+
+#[async_trait]
+impl RpcSender for RateLimitedSender {
+    async fn send(&self, _request: RpcRequest, _params: Value) -> ClientResult<Value> {
+        let limited = self.rate_limited
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |remaining| remaining.checked_sub(1))
+            .is_ok();
+        if limited {
+            return Err(RpcError::RpcResponseError {
+                code: self.code,
+                message: self.message.clone(),
+                data: RpcResponseErrorData::Empty,
+            }.into());
+        }
+        Ok(self.result.clone())
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        RpcTransportStats::default()
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+}
@@ -0,0 +1,205 @@
+//! # Timestamps
+//!
+//! Chain and local receive times of streamed events, with an estimator of the feed delay so
+//! latency sensitive strategies can measure and compensate for it. The delay includes the skew
+//! between the local clock and the cluster, which is why it is estimated from samples instead
+//! of trusting either clock.
+
+use std::{
+    collections::VecDeque,
+    time::{SystemTime, UNIX_EPOCH}
+};
+use solana_client::rpc_client::RpcClient;
+use crate::error::ReadTransactionError;
+
+/// Target duration of a slot, used to estimate the time of slots without a block time.
+pub const SLOT_DURATION_MS: i64 = 400;
+
+/// When an event happened on chain and when it was received.
+///
+/// ### Fields
+///
+/// - `slot`: Slot of the event.
+/// - `block_time`: Unix timestamp in seconds of the block, `None` if the feed does not include it.
+/// - `received_at_ms`: Local unix time in milliseconds when the event was received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventTimestamp {
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub received_at_ms: i64,
+}
+
+impl EventTimestamp {
+    /// Timestamp of an event received now.
+    pub fn received_now(slot: u64, block_time: Option<i64>) -> Self {
+        Self { slot, block_time, received_at_ms: unix_time_ms() }
+    }
+
+    /// Receive time minus block time, `None` without a block time. Block times are truncated
+    /// to seconds, so a single delay is up to one second too high.
+    pub fn feed_delay_ms(&self) -> Option<i64> {
+        self.block_time.map(|block_time| self.received_at_ms - block_time * 1_000)
+    }
+}
+
+/// Streamed event with its timestamp.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timestamped<T> {
+    pub event: T,
+    pub timestamp: EventTimestamp,
+}
+
+/// Estimates the delay of a feed from the events it delivers.
+///
+/// The skew is the smallest delay of the last `window` events with a block time, which filters
+/// out both the truncation of block times and transient network delays. Events without a block
+/// time are dated from the latest slot anchor.
+///
+/// ## Example
+///
+/// ```rust
+/// use easy_solana::timestamps::{EventTimestamp, SkewEstimator};
+///
+/// let mut estimator = SkewEstimator::new(100);
+/// estimator.observe(&EventTimestamp { slot: 1_000, block_time: Some(1_700_000_000), received_at_ms: 1_700_000_000_650 });
+/// estimator.observe(&EventTimestamp { slot: 1_005, block_time: Some(1_700_000_002), received_at_ms: 1_700_000_002_250 });
+/// assert!(estimator.skew_ms() == Some(250));
+///
+/// // Websocket notifications only carry a slot
+/// let notification = EventTimestamp { slot: 1_010, block_time: None, received_at_ms: 1_700_000_004_500 };
+/// assert!(estimator.latency_ms(&notification) == Some(500));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SkewEstimator {
+    window: usize,
+    delays: VecDeque<i64>,
+    /// Latest slot with a known block time, in milliseconds.
+    anchor: Option<(u64, i64)>,
+}
+
+impl SkewEstimator {
+    pub fn new(window: usize) -> Self {
+        Self { window: window.max(1), delays: VecDeque::with_capacity(window), anchor: None }
+    }
+
+    /// Adds the delay of an event with a block time and moves the slot anchor forward.
+    pub fn observe(&mut self, timestamp: &EventTimestamp) {
+        let (Some(delay), Some(block_time)) = (timestamp.feed_delay_ms(), timestamp.block_time) else {
+            return;
+        };
+        if self.delays.len() >= self.window {
+            self.delays.pop_front();
+        }
+        self.delays.push_back(delay);
+        self.set_anchor(timestamp.slot, block_time);
+    }
+
+    /// Records the block time of `slot`, unless a later slot is already known.
+    pub fn set_anchor(&mut self, slot: u64, block_time: i64) {
+        if self.anchor.is_none_or(|(anchor_slot, _)| slot >= anchor_slot) {
+            self.anchor = Some((slot, block_time * 1_000));
+        }
+    }
+
+    /// Fetches the current slot and its block time as the slot anchor.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::RpcError` if the slot or block time can not be fetched.
+    pub fn anchor_from_client(&mut self, client: &RpcClient) -> Result<(), ReadTransactionError> {
+        let slot = client.get_slot()?;
+        let block_time = client.get_block_time(slot)?;
+        self.set_anchor(slot, block_time);
+        Ok(())
+    }
+
+    /// Smallest delay between block time and receive time over the window.
+    pub fn skew_ms(&self) -> Option<i64> {
+        self.delays.iter().min().copied()
+    }
+
+    /// Average delay over the window, including the truncation of block times.
+    pub fn average_delay_ms(&self) -> Option<i64> {
+        if self.delays.is_empty() {
+            return None;
+        }
+        Some(self.delays.iter().sum::<i64>() / self.delays.len() as i64)
+    }
+
+    /// Unix time in milliseconds of the event on chain, from its block time or estimated from
+    /// the slot anchor. `None` if neither is known.
+    pub fn chain_time_ms(&self, timestamp: &EventTimestamp) -> Option<i64> {
+        if let Some(block_time) = timestamp.block_time {
+            return Some(block_time * 1_000);
+        }
+        self.anchor.map(|(anchor_slot, anchor_time)| {
+            anchor_time + (timestamp.slot as i64 - anchor_slot as i64) * SLOT_DURATION_MS
+        })
+    }
+
+    /// Receive time minus the chain time of the event.
+    pub fn latency_ms(&self, timestamp: &EventTimestamp) -> Option<i64> {
+        self.chain_time_ms(timestamp).map(|chain_time| timestamp.received_at_ms - chain_time)
+    }
+
+    /// Local receive time shifted by the skew, comparable with chain times.
+    pub fn corrected_received_ms(&self, timestamp: &EventTimestamp) -> i64 {
+        timestamp.received_at_ms - self.skew_ms().unwrap_or(0)
+    }
+}
+
+/// Local unix time in milliseconds.
+pub fn unix_time_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::mock_client;
+
+    fn timestamp(slot: u64, block_time: Option<i64>, received_at_ms: i64) -> EventTimestamp {
+        EventTimestamp { slot, block_time, received_at_ms }
+    }
+
+    #[test]
+    fn test_skew_uses_smallest_delay_in_window() {
+        let mut estimator = SkewEstimator::new(2);
+        assert!(estimator.skew_ms().is_none());
+        estimator.observe(&timestamp(10, Some(100), 100_100));
+        estimator.observe(&timestamp(12, Some(101), 101_900));
+        estimator.observe(&timestamp(13, None, 105_000));
+        assert!(estimator.skew_ms() == Some(100) && estimator.average_delay_ms() == Some(500));
+
+        // The first sample leaves the window
+        estimator.observe(&timestamp(15, Some(102), 102_300));
+        assert!(estimator.skew_ms() == Some(300));
+        assert!(estimator.corrected_received_ms(&timestamp(16, None, 103_000)) == 102_700);
+    }
+
+    #[test]
+    fn test_chain_time_from_slot_anchor() {
+        let mut estimator = SkewEstimator::new(10);
+        assert!(estimator.latency_ms(&timestamp(5, None, 1_000)).is_none());
+        estimator.set_anchor(100, 50);
+        // Older anchors are ignored
+        estimator.set_anchor(90, 10);
+        assert!(estimator.chain_time_ms(&timestamp(105, None, 0)) == Some(52_000));
+        assert!(estimator.chain_time_ms(&timestamp(95, None, 0)) == Some(48_000));
+        assert!(estimator.latency_ms(&timestamp(110, None, 54_750)) == Some(750));
+        assert!(estimator.latency_ms(&timestamp(110, Some(55), 55_100)) == Some(100));
+    }
+
+    #[test]
+    fn test_anchor_from_client() {
+        let client = mock_client(Vec::new());
+        let mut estimator = SkewEstimator::new(10);
+        estimator.anchor_from_client(&client).unwrap();
+        assert!(estimator.chain_time_ms(&timestamp(1, None, 0)) == Some(SLOT_DURATION_MS));
+        assert!(EventTimestamp::received_now(1, None).received_at_ms > 0);
+    }
+}
@@ -1,5 +1,6 @@
 use solana_sdk::{
     commitment_config::CommitmentConfig, 
+    native_token::LAMPORTS_PER_SOL,
     pubkey::{ParsePubkeyError, Pubkey}, 
     signature::Keypair, 
     signer::Signer,
@@ -13,6 +14,7 @@ use dotenv::dotenv;
 use std::env;
 use regex::Regex;
 use log::info;
+use zeroize::Zeroizing;
 
 use crate::error::KeypairError;
 
@@ -62,15 +64,18 @@ pub fn generate_keypair(starts_with: Option<&str>, ends_with: Option<&str>) -> R
     }
 }
 
-/// Creates an Rpc Client, accepts an enviroment variable name or direct URL
-pub fn create_rpc_client(rpc_input: &str) -> RpcClient {
+/// Resolves an RPC URL, accepts an enviroment variable name or direct URL
+pub fn resolve_rpc_url(rpc_input: &str) -> String {
     // Load environment variables from .env file if present
     dotenv().ok();
 
     // Check if rpc_input is an environment variable name or a direct URL
-    let rpc_url = env::var(rpc_input).unwrap_or_else(|_| rpc_input.to_string());
+    env::var(rpc_input).unwrap_or_else(|_| rpc_input.to_string())
+}
 
-    RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed())
+/// Creates an Rpc Client, accepts an enviroment variable name or direct URL
+pub fn create_rpc_client(rpc_input: &str) -> RpcClient {
+    RpcClient::new_with_commitment(resolve_rpc_url(rpc_input), CommitmentConfig::confirmed())
 }
 
 /// Reads a `Vec<String>` of addresses to `Vec<Pubkey>`, invalid addresses are removed.
@@ -85,12 +90,24 @@ pub fn address_to_pubkey(address: &str) -> Result<Pubkey, ParsePubkeyError> {
     address.parse::<Pubkey>()
 }
 
+/// Converts an amount of SOL to lamports, rounded to the nearest lamport. Multiplying by
+/// `LAMPORTS_PER_SOL` and casting truncates, e.g 1.005 SOL would become 1,004,999,999 lamports.
+/// Negative and non finite amounts convert to 0.
+pub fn sol_to_lamports(sol: f64) -> u64 {
+    if !sol.is_finite() {
+        return 0;
+    }
+    (sol * LAMPORTS_PER_SOL as f64).round() as u64
+}
+
 pub fn base58_to_keypair(keypair_string: &str) -> Result<Keypair, KeypairError> {
-    let keypair_bytes = bs58::decode(keypair_string)
-    .into_vec()
-    .map_err(|_| KeypairError::Base58DecodeError)?;
+    let keypair_bytes = Zeroizing::new(
+        bs58::decode(keypair_string)
+        .into_vec()
+        .map_err(|_| KeypairError::Base58DecodeError)?
+    );
 
-    Keypair::from_bytes(&keypair_bytes).map_err(|_| KeypairError::InvalidKeypairBytes)
+    Keypair::try_from(keypair_bytes.as_slice()).map_err(|_| KeypairError::InvalidKeypairBytes)
 }
 
 #[cfg(test)]
@@ -98,6 +115,16 @@ mod tests {
     use solana_sdk::signer::Signer;
     use super::*;
 
+    #[test]
+    fn test_sol_to_lamports_rounds() {
+        // 1.005 * 1e9 is 1004999999.9999999 in floating point
+        assert!(sol_to_lamports(1.005) == 1_005_000_000);
+        assert!(sol_to_lamports(0.000_000_001) == 1);
+        assert!(sol_to_lamports(-1.0) == 0);
+        assert!(sol_to_lamports(f64::NAN) == 0);
+        assert!(sol_to_lamports(f64::INFINITY) == 0);
+    }
+
     #[test]
     fn test_generate_invalid_keypair() {
         let invalid_base58_keypair = "asd";
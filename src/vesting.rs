@@ -0,0 +1,611 @@
+//! # Vesting
+//!
+//! Time-locked token transfers through the Jupiter Lock program. Tokens are moved into a token
+//! account owned by a vesting escrow, released linearly to a recipient: an amount unlocks at the
+//! cliff time, then a fixed amount at every period. The recipient claims unlocked tokens with
+//! `claim_vesting`.
+//!
+//! A vesting is created with a `VestingAuthority` allowed to cancel it. Cancelling sends the
+//! unlocked tokens to the recipient, returns the locked tokens to the creator and closes the
+//! escrow token account. A vesting created with `VestingAuthority::Neither` is irrevocable.
+//! Only mints of the original token program are supported.
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signer::{keypair::Keypair, Signer}
+};
+use spl_associated_token_account::{
+    get_associated_token_address,
+    instruction::create_associated_token_account_idempotent
+};
+use crate::{
+    constants::{
+        solana_programs::{system_program, token_program},
+        vesting_accounts::{
+            memo_program,
+            CANCEL_VESTING_ESCROW_DISCRIMINATOR,
+            CLAIM_DISCRIMINATOR,
+            CREATE_VESTING_ESCROW_DISCRIMINATOR,
+            VESTING_ESCROW_DISCRIMINATOR,
+            VESTING_ESCROW_SIZE
+        }
+    },
+    error::{ReadTransactionError, TransactionBuilderError},
+    write_transactions::transaction_builder::TransactionBuilder
+};
+
+/// Who may cancel a vesting or change its recipient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VestingAuthority {
+    Neither = 0,
+    Creator = 1,
+    Recipient = 2,
+    Either = 3,
+}
+
+impl VestingAuthority {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Neither),
+            1 => Some(Self::Creator),
+            2 => Some(Self::Recipient),
+            3 => Some(Self::Either),
+            _ => None,
+        }
+    }
+
+    fn allows_creator(self) -> bool {
+        matches!(self, Self::Creator | Self::Either)
+    }
+
+    fn allows_recipient(self) -> bool {
+        matches!(self, Self::Recipient | Self::Either)
+    }
+}
+
+/// Release schedule of a vesting, in raw token amounts and unix timestamps.
+///
+/// ### Fields
+///
+/// - `start_time`: Start of the vesting, at or before `cliff_time`.
+/// - `cliff_time`: Time `cliff_unlock_amount` unlocks, periods are counted from it.
+/// - `frequency`: Length of a period in seconds.
+/// - `cliff_unlock_amount`: Amount unlocked at the cliff.
+/// - `amount_per_period`: Amount unlocked at the end of every period.
+/// - `number_of_period`: Number of periods after the cliff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VestingSchedule {
+    pub start_time: u64,
+    pub cliff_time: u64,
+    pub frequency: u64,
+    pub cliff_unlock_amount: u64,
+    pub amount_per_period: u64,
+    pub number_of_period: u64,
+}
+
+impl VestingSchedule {
+    /// Raw amount locked by the schedule, `None` on overflow.
+    pub fn total_amount(&self) -> Option<u64> {
+        self.amount_per_period
+            .checked_mul(self.number_of_period)?
+            .checked_add(self.cliff_unlock_amount)
+    }
+
+    /// Raw amount unlocked at the unix timestamp `now`, claimed or not.
+    pub fn unlocked_amount(&self, now: i64) -> u64 {
+        let now = now.max(0) as u64;
+        if now < self.cliff_time {
+            return 0;
+        }
+        let periods = (now - self.cliff_time)
+            .checked_div(self.frequency)
+            .unwrap_or(self.number_of_period)
+            .min(self.number_of_period);
+        self.cliff_unlock_amount.saturating_add(periods.saturating_mul(self.amount_per_period))
+    }
+}
+
+/// Addresses of a vesting, derived from the program id, a base key and the mint.
+///
+/// ### Fields
+///
+/// - `program_id`: Vesting program owning the escrow.
+/// - `base`: Key signing the creation, unique per vesting.
+/// - `escrow`: Account holding the recipient and the schedule.
+/// - `escrow_token_account`: Associated token account of the escrow holding the locked tokens.
+/// - `mint`: Mint of the vested token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VestingKeys {
+    pub program_id: Pubkey,
+    pub base: Pubkey,
+    pub escrow: Pubkey,
+    pub escrow_token_account: Pubkey,
+    pub mint: Pubkey,
+}
+
+impl VestingKeys {
+    /// Derives the vesting addresses for `base`, the public key of a new keypair signing the
+    /// creation of the vesting.
+    pub fn derive(program_id: &Pubkey, base: &Pubkey, mint: &Pubkey) -> Self {
+        let (escrow, _) = Pubkey::find_program_address(&[b"escrow", base.as_ref()], program_id);
+        Self {
+            program_id: *program_id,
+            base: *base,
+            escrow,
+            escrow_token_account: get_associated_token_address(&escrow, mint),
+            mint: *mint,
+        }
+    }
+
+    fn event_authority(&self) -> Pubkey {
+        Pubkey::find_program_address(&[b"__event_authority"], &self.program_id).0
+    }
+}
+
+/// State of a vesting escrow.
+///
+/// ### Fields
+///
+/// - `recipient`: Wallet receiving the released tokens.
+/// - `mint`: Mint of the vested token.
+/// - `creator`: Wallet that funded the vesting, receiving the locked tokens on cancel.
+/// - `cancel_authority`: Who may cancel the vesting.
+/// - `schedule`: Release schedule of the vesting.
+/// - `total_claimed_amount`: Raw amount already claimed by the recipient.
+/// - `cancelled_at`: Unix timestamp of the cancellation, 0 if the vesting is active.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VestingAccount {
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    pub creator: Pubkey,
+    pub cancel_authority: VestingAuthority,
+    pub schedule: VestingSchedule,
+    pub total_claimed_amount: u64,
+    pub cancelled_at: u64,
+}
+
+impl VestingAccount {
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, ReadTransactionError> {
+        let data = data.get(..VESTING_ESCROW_SIZE).ok_or(ReadTransactionError::DeserializeError)?;
+        if data[..8] != VESTING_ESCROW_DISCRIMINATOR {
+            return Err(ReadTransactionError::WrongAccountType("vesting".to_string()));
+        }
+        let read_pubkey = |offset: usize| Pubkey::try_from(&data[offset..offset + 32]).unwrap();
+        let read_u64 = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+
+        Ok(Self {
+            recipient: read_pubkey(8),
+            mint: read_pubkey(40),
+            creator: read_pubkey(72),
+            cancel_authority: VestingAuthority::from_u8(data[138]).ok_or(ReadTransactionError::DeserializeError)?,
+            schedule: VestingSchedule {
+                start_time: read_u64(192),
+                cliff_time: read_u64(144),
+                frequency: read_u64(152),
+                cliff_unlock_amount: read_u64(160),
+                amount_per_period: read_u64(168),
+                number_of_period: read_u64(176),
+            },
+            total_claimed_amount: read_u64(184),
+            cancelled_at: read_u64(200),
+        })
+    }
+
+    /// Raw amount the recipient can claim at the unix timestamp `now`.
+    pub fn claimable_amount(&self, now: i64) -> u64 {
+        if self.cancelled_at != 0 {
+            return 0;
+        }
+        self.schedule.unlocked_amount(now).saturating_sub(self.total_claimed_amount)
+    }
+
+    /// Raw amount not unlocked yet at the unix timestamp `now`.
+    pub fn locked_amount(&self, now: i64) -> u64 {
+        if self.cancelled_at != 0 {
+            return 0;
+        }
+        self.schedule.total_amount().unwrap_or(u64::MAX).saturating_sub(self.schedule.unlocked_amount(now))
+    }
+}
+
+/// Gets the state of a vesting.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::WrongAccountType` if the account is not a vesting escrow and
+/// `ReadTransactionError::DeserializeError` if its data can not be decoded.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, constants::vesting_accounts::token_vesting_program, vesting::{get_vesting_account, VestingKeys}};
+/// use solana_sdk::pubkey::Pubkey;
+///
+/// let client = create_rpc_client("RPC_URL");
+/// let keys = VestingKeys::derive(&token_vesting_program(), &Pubkey::new_unique(), &Pubkey::new_unique());
+/// let vesting = get_vesting_account(&client, &keys).unwrap();
+/// println!("{} tokens claimed", vesting.total_claimed_amount);
+/// ```
+pub fn get_vesting_account(client: &RpcClient, keys: &VestingKeys) -> Result<VestingAccount, ReadTransactionError> {
+    let account = client.get_account(&keys.escrow)?;
+    if account.owner != keys.program_id {
+        return Err(ReadTransactionError::WrongAccountType("vesting".to_string()));
+    }
+    VestingAccount::try_from_account_data(&account.data)
+}
+
+/// Creates the instruction moving the total of `schedule` from `sender_token_account`, owned by
+/// `sender`, into the escrow token account, which has to exist. Both `sender` and the base key sign.
+pub fn vesting_create_instruction(
+    keys: &VestingKeys,
+    sender: &Pubkey,
+    sender_token_account: &Pubkey,
+    recipient: &Pubkey,
+    schedule: &VestingSchedule,
+    cancel_authority: VestingAuthority,
+) -> Instruction {
+    let mut data = CREATE_VESTING_ESCROW_DISCRIMINATOR.to_vec();
+    for value in [
+        schedule.start_time,
+        schedule.cliff_time,
+        schedule.frequency,
+        schedule.cliff_unlock_amount,
+        schedule.amount_per_period,
+        schedule.number_of_period,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    // Only the recipient can change the recipient
+    data.push(VestingAuthority::Recipient as u8);
+    data.push(cancel_authority as u8);
+
+    Instruction {
+        program_id: keys.program_id,
+        accounts: vec![
+            AccountMeta::new(keys.base, true),
+            AccountMeta::new(keys.escrow, false),
+            AccountMeta::new(keys.escrow_token_account, false),
+            AccountMeta::new(*sender, true),
+            AccountMeta::new(*sender_token_account, false),
+            AccountMeta::new_readonly(*recipient, false),
+            AccountMeta::new_readonly(token_program(), false),
+            AccountMeta::new_readonly(system_program(), false),
+            AccountMeta::new_readonly(keys.event_authority(), false),
+            AccountMeta::new_readonly(keys.program_id, false),
+        ],
+        data,
+    }
+}
+
+/// Creates the instruction sending up to `max_amount` unlocked tokens to `recipient_token_account`,
+/// signed by `recipient`.
+pub fn vesting_claim_instruction(keys: &VestingKeys, recipient: &Pubkey, recipient_token_account: &Pubkey, max_amount: u64) -> Instruction {
+    let mut data = CLAIM_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&max_amount.to_le_bytes());
+
+    Instruction {
+        program_id: keys.program_id,
+        accounts: vec![
+            AccountMeta::new(keys.escrow, false),
+            AccountMeta::new(keys.escrow_token_account, false),
+            AccountMeta::new(*recipient, true),
+            AccountMeta::new(*recipient_token_account, false),
+            AccountMeta::new_readonly(token_program(), false),
+            AccountMeta::new_readonly(keys.event_authority(), false),
+            AccountMeta::new_readonly(keys.program_id, false),
+        ],
+        data,
+    }
+}
+
+/// Creates the instruction cancelling a vesting, signed by `signer`, which the cancel authority of
+/// the vesting has to allow. Unlocked tokens go to `recipient_token_account`, locked tokens to
+/// `creator_token_account` and the rent of the escrow token account to `rent_receiver`.
+pub fn vesting_cancel_instruction(
+    keys: &VestingKeys,
+    signer: &Pubkey,
+    creator_token_account: &Pubkey,
+    recipient_token_account: &Pubkey,
+    rent_receiver: &Pubkey,
+) -> Instruction {
+    let mut data = CANCEL_VESTING_ESCROW_DISCRIMINATOR.to_vec();
+    // No transfer hook accounts
+    data.push(0);
+
+    Instruction {
+        program_id: keys.program_id,
+        accounts: vec![
+            AccountMeta::new(keys.escrow, false),
+            AccountMeta::new(keys.mint, false),
+            AccountMeta::new(keys.escrow_token_account, false),
+            AccountMeta::new(*creator_token_account, false),
+            AccountMeta::new(*recipient_token_account, false),
+            AccountMeta::new(*rent_receiver, false),
+            AccountMeta::new(*signer, true),
+            AccountMeta::new_readonly(memo_program(), false),
+            AccountMeta::new_readonly(token_program(), false),
+            AccountMeta::new_readonly(keys.event_authority(), false),
+            AccountMeta::new_readonly(keys.program_id, false),
+        ],
+        data,
+    }
+}
+
+impl<'a> TransactionBuilder<'a> {
+    /// Adds the instructions locking the total of `schedule` from the associated token account of
+    /// the payer keypair, released over time to `recipient`. `base_keypair` is a new keypair
+    /// identifying the vesting, derive the `VestingKeys` from its public key. The escrow token
+    /// account and the token account of the recipient are created if they do not exist.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::InstructionError` if the keys were derived from another
+    /// base key, the schedule locks no tokens, its amount overflows, it starts after the cliff or
+    /// has periods of length 0.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use easy_solana::{create_rpc_client, constants::vesting_accounts::token_vesting_program, vesting::{VestingAuthority, VestingKeys, VestingSchedule}, SecretKeypair, TransactionBuilder};
+    /// use solana_sdk::{pubkey::Pubkey, signer::{keypair::Keypair, Signer}};
+    ///
+    /// let client = create_rpc_client("RPC_URL");
+    /// let keypair = SecretKeypair::from_env("PRIVATE_KEY_1").unwrap();
+    /// let base_keypair = Keypair::new();
+    /// let keys = VestingKeys::derive(&token_vesting_program(), &base_keypair.pubkey(), &Pubkey::new_unique());
+    /// // 250_000 tokens at the cliff, then 62_500 every 30 days for a year
+    /// let schedule = VestingSchedule {
+    ///     start_time: 1_767_225_600,
+    ///     cliff_time: 1_767_225_600,
+    ///     frequency: 2_592_000,
+    ///     cliff_unlock_amount: 250_000,
+    ///     amount_per_period: 62_500,
+    ///     number_of_period: 12,
+    /// };
+    /// let transaction = TransactionBuilder::new(&client, &keypair)
+    ///     .create_vesting(&keys, &base_keypair, &Pubkey::new_unique(), &schedule, VestingAuthority::Creator)
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn create_vesting(
+        &mut self,
+        keys: &VestingKeys,
+        base_keypair: &'a Keypair,
+        recipient: &Pubkey,
+        schedule: &VestingSchedule,
+        cancel_authority: VestingAuthority,
+    ) -> Result<&mut Self, TransactionBuilderError> {
+        if base_keypair.pubkey() != keys.base {
+            return Err(TransactionBuilderError::InstructionError("Vesting keys were derived from another base key".to_string()));
+        }
+        match schedule.total_amount() {
+            None => return Err(TransactionBuilderError::InstructionError("Vesting amount overflows".to_string())),
+            Some(0) => return Err(TransactionBuilderError::InstructionError("A vesting needs a non zero amount".to_string())),
+            Some(_) => {}
+        }
+        if schedule.start_time > schedule.cliff_time {
+            return Err(TransactionBuilderError::InstructionError("Vesting starts after its cliff".to_string()));
+        }
+        if schedule.frequency == 0 {
+            return Err(TransactionBuilderError::InstructionError("Vesting periods can not be 0 seconds".to_string()));
+        }
+        let sender = self.payer_keypair.pubkey();
+        let fee_payer = self.fee_payer_pubkey();
+
+        self.instructions.push(create_associated_token_account_idempotent(&fee_payer, &keys.escrow, &keys.mint, &token_program()));
+        self.instructions.push(create_associated_token_account_idempotent(&fee_payer, recipient, &keys.mint, &token_program()));
+        self.instructions.push(vesting_create_instruction(
+            keys,
+            &sender,
+            &get_associated_token_address(&sender, &keys.mint),
+            recipient,
+            schedule,
+            cancel_authority,
+        ));
+        self.signing_keypairs.push(base_keypair);
+        Ok(self)
+    }
+
+    /// Adds the instruction sending the claimable tokens of a vesting to the associated token
+    /// account of the payer keypair, which has to be the recipient.
+    pub fn claim_vesting(&mut self, keys: &VestingKeys) -> &mut Self {
+        let recipient = self.payer_keypair.pubkey();
+        let recipient_token_account = get_associated_token_address(&recipient, &keys.mint);
+        self.instructions.push(vesting_claim_instruction(keys, &recipient, &recipient_token_account, u64::MAX));
+        self
+    }
+
+    /// Adds the instruction cancelling `vesting`, signed by the payer keypair. Unlocked tokens go
+    /// to the recipient, locked tokens back to the creator, both to their associated token accounts.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::InstructionError` if the vesting is already cancelled or
+    /// its cancel authority does not allow the payer keypair.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use easy_solana::{create_rpc_client, constants::vesting_accounts::token_vesting_program, vesting::{get_vesting_account, VestingKeys}, SecretKeypair, TransactionBuilder};
+    /// use solana_sdk::pubkey::Pubkey;
+    ///
+    /// let client = create_rpc_client("RPC_URL");
+    /// let keypair = SecretKeypair::from_env("PRIVATE_KEY_1").unwrap();
+    /// let keys = VestingKeys::derive(&token_vesting_program(), &Pubkey::new_unique(), &Pubkey::new_unique());
+    /// let vesting = get_vesting_account(&client, &keys).unwrap();
+    /// let transaction = TransactionBuilder::new(&client, &keypair)
+    ///     .cancel_vesting(&keys, &vesting)
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn cancel_vesting(&mut self, keys: &VestingKeys, vesting: &VestingAccount) -> Result<&mut Self, TransactionBuilderError> {
+        if vesting.cancelled_at != 0 {
+            return Err(TransactionBuilderError::InstructionError("Vesting is already cancelled".to_string()));
+        }
+        let signer = self.payer_keypair.pubkey();
+        let allowed = (signer == vesting.creator && vesting.cancel_authority.allows_creator())
+            || (signer == vesting.recipient && vesting.cancel_authority.allows_recipient());
+        if !allowed {
+            return Err(TransactionBuilderError::InstructionError("Vesting can not be cancelled by this keypair".to_string()));
+        }
+
+        self.instructions.push(create_associated_token_account_idempotent(&self.fee_payer_pubkey(), &vesting.creator, &keys.mint, &token_program()));
+        self.instructions.push(vesting_cancel_instruction(
+            keys,
+            &signer,
+            &get_associated_token_address(&vesting.creator, &keys.mint),
+            &get_associated_token_address(&vesting.recipient, &keys.mint),
+            &vesting.creator,
+        ));
+        Ok(self)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{constants::vesting_accounts::token_vesting_program, test_utils::mock_client};
+
+    fn schedule() -> VestingSchedule {
+        VestingSchedule {
+            start_time: 100,
+            cliff_time: 100,
+            frequency: 10,
+            cliff_unlock_amount: 1_000,
+            amount_per_period: 500,
+            number_of_period: 4,
+        }
+    }
+
+    fn vesting_data(recipient: &Pubkey, creator: &Pubkey, mint: &Pubkey, cancel_authority: VestingAuthority) -> Vec<u8> {
+        let schedule = schedule();
+        let mut data = VESTING_ESCROW_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(recipient.as_ref());
+        data.extend_from_slice(mint.as_ref());
+        data.extend_from_slice(creator.as_ref());
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(&[255, VestingAuthority::Recipient as u8, cancel_authority as u8, 0, 0, 0, 0, 0]);
+        for value in [
+            schedule.cliff_time,
+            schedule.frequency,
+            schedule.cliff_unlock_amount,
+            schedule.amount_per_period,
+            schedule.number_of_period,
+            600,
+            schedule.start_time,
+            0,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.resize(VESTING_ESCROW_SIZE, 0);
+        data
+    }
+
+    #[test]
+    fn test_vesting_keys_derive_escrow_from_base() {
+        let mint = Pubkey::new_unique();
+        let base = Pubkey::new_unique();
+        let keys = VestingKeys::derive(&token_vesting_program(), &base, &mint);
+        let (escrow, _) = Pubkey::find_program_address(&[b"escrow", base.as_ref()], &token_vesting_program());
+        assert!(keys.escrow == escrow);
+        assert!(keys.escrow_token_account == get_associated_token_address(&escrow, &mint));
+        assert!(VestingKeys::derive(&token_vesting_program(), &Pubkey::new_unique(), &mint).escrow != escrow);
+    }
+
+    #[test]
+    fn test_schedule_unlocks_at_cliff_and_every_period() {
+        let schedule = schedule();
+        assert!(schedule.total_amount() == Some(3_000));
+        assert!(schedule.unlocked_amount(99) == 0);
+        assert!(schedule.unlocked_amount(100) == 1_000);
+        assert!(schedule.unlocked_amount(125) == 2_000);
+        assert!(schedule.unlocked_amount(1_000) == 3_000);
+    }
+
+    #[test]
+    fn test_vesting_account_from_data() {
+        let (recipient, creator, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let data = vesting_data(&recipient, &creator, &mint, VestingAuthority::Creator);
+        let vesting = VestingAccount::try_from_account_data(&data).unwrap();
+        assert!(vesting.recipient == recipient && vesting.creator == creator && vesting.mint == mint);
+        assert!(vesting.cancel_authority == VestingAuthority::Creator);
+        assert!(vesting.schedule == schedule());
+        assert!(vesting.claimable_amount(125) == 1_400);
+        assert!(vesting.locked_amount(125) == 1_000);
+        assert!(VestingAccount::try_from_account_data(&data[..200]).is_err());
+
+        let mut wrong_type = data.clone();
+        wrong_type[0] ^= 1;
+        assert!(matches!(VestingAccount::try_from_account_data(&wrong_type), Err(ReadTransactionError::WrongAccountType(_))));
+    }
+
+    #[test]
+    fn test_create_vesting_instructions() {
+        let client = mock_client(vec![]);
+        let keypair = Keypair::new();
+        let base_keypair = Keypair::new();
+        let keys = VestingKeys::derive(&token_vesting_program(), &base_keypair.pubkey(), &Pubkey::new_unique());
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        let recipient = Pubkey::new_unique();
+        builder.create_vesting(&keys, &base_keypair, &recipient, &schedule(), VestingAuthority::Creator).unwrap();
+        assert!(builder.instructions.len() == 3);
+        assert!(builder.signing_keypairs.len() == 1);
+
+        let create = &builder.instructions[2];
+        assert!(create.data[..8] == CREATE_VESTING_ESCROW_DISCRIMINATOR);
+        assert!(create.data[8..16] == 100_u64.to_le_bytes());
+        assert!(create.data[56..] == [VestingAuthority::Recipient as u8, VestingAuthority::Creator as u8]);
+        assert!(create.accounts[0].pubkey == keys.base && create.accounts[0].is_signer);
+        assert!(create.accounts[2].pubkey == keys.escrow_token_account);
+        assert!(create.accounts[5].pubkey == recipient);
+    }
+
+    #[test]
+    fn failing_test_create_vesting_with_invalid_schedule() {
+        let client = mock_client(vec![]);
+        let keypair = Keypair::new();
+        let base_keypair = Keypair::new();
+        let keys = VestingKeys::derive(&token_vesting_program(), &base_keypair.pubkey(), &Pubkey::new_unique());
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        let recipient = Pubkey::new_unique();
+        let invalid_schedules = [
+            VestingSchedule { cliff_unlock_amount: 0, amount_per_period: 0, ..schedule() },
+            VestingSchedule { amount_per_period: u64::MAX, ..schedule() },
+            VestingSchedule { start_time: 200, ..schedule() },
+            VestingSchedule { frequency: 0, ..schedule() },
+        ];
+        for invalid_schedule in invalid_schedules {
+            assert!(builder.create_vesting(&keys, &base_keypair, &recipient, &invalid_schedule, VestingAuthority::Creator).is_err());
+        }
+        let other_base = Keypair::new();
+        assert!(builder.create_vesting(&keys, &other_base, &recipient, &schedule(), VestingAuthority::Creator).is_err());
+        assert!(builder.instructions.is_empty());
+    }
+
+    #[test]
+    fn test_cancel_vesting_checks_cancel_authority() {
+        let client = mock_client(vec![]);
+        let creator = Keypair::new();
+        let recipient = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let keys = VestingKeys::derive(&token_vesting_program(), &Pubkey::new_unique(), &mint);
+        let vesting = VestingAccount::try_from_account_data(&vesting_data(&recipient.pubkey(), &creator.pubkey(), &mint, VestingAuthority::Creator)).unwrap();
+
+        assert!(TransactionBuilder::new(&client, &recipient).cancel_vesting(&keys, &vesting).is_err());
+        let cancelled = VestingAccount { cancelled_at: 150, ..vesting.clone() };
+        assert!(TransactionBuilder::new(&client, &creator).cancel_vesting(&keys, &cancelled).is_err());
+
+        let mut builder = TransactionBuilder::new(&client, &creator);
+        builder.cancel_vesting(&keys, &vesting).unwrap();
+        let cancel = &builder.instructions[1];
+        assert!(cancel.data == [CANCEL_VESTING_ESCROW_DISCRIMINATOR.as_slice(), &[0]].concat());
+        assert!(cancel.accounts[3].pubkey == get_associated_token_address(&creator.pubkey(), &mint));
+        assert!(cancel.accounts[4].pubkey == get_associated_token_address(&recipient.pubkey(), &mint));
+        assert!(cancel.accounts[6].pubkey == creator.pubkey() && cancel.accounts[6].is_signer);
+    }
+}
@@ -0,0 +1,303 @@
+//! # Watchlist
+//!
+//! Accounts registered on a `Watchlist` are refreshed on an adaptive schedule and decoded, and
+//! the change callbacks are only called when a decoded value changes. Accounts that keep their
+//! value are refreshed less and less often, down to the maximum interval, and go back to the
+//! minimum interval as soon as they change.
+//!
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use easy_solana::{
+//!     create_rpc_client,
+//!     supervisor::Supervisor,
+//!     watchlist::{Watchlist, WatchlistConfig}
+//! };
+//!
+//! # async fn example() {
+//! let client = Arc::new(create_rpc_client("RPC_URL"));
+//! let mut watchlist = Watchlist::new(WatchlistConfig::default());
+//! watchlist.watch("ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5", |account| Some(account.lamports)).unwrap();
+//! watchlist.on_change(|update| println!("{} changed from {:?} to {:?}", update.address, update.previous, update.current));
+//!
+//! let supervisor = Supervisor::new();
+//! let watchlist = watchlist.spawn(client, supervisor.shutdown_signal());
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant}
+};
+use log::warn;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcAccountInfoConfig};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use crate::{
+    error::ReadTransactionError,
+    read_transactions::batch::MAX_MULTIPLE_ACCOUNTS,
+    supervisor::ShutdownSignal,
+    utils::address_to_pubkey
+};
+
+/// Decodes the watched value of an account, `None` if the account can not be decoded.
+pub type AccountDecoder<T> = Arc<dyn Fn(&Account) -> Option<T> + Send + Sync>;
+
+/// Called with every change of a watched value.
+pub type ChangeCallback<T> = Box<dyn FnMut(&WatchUpdate<T>) + Send>;
+
+/// Refresh schedule of a `Watchlist`.
+///
+/// ### Fields
+///
+/// - `min_interval`: Interval of new accounts and accounts that just changed.
+/// - `max_interval`: Longest interval of accounts that keep their value. The interval doubles
+///   after every refresh without change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchlistConfig {
+    pub min_interval: Duration,
+    pub max_interval: Duration,
+}
+
+impl Default for WatchlistConfig {
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Change of a watched value.
+///
+/// ### Fields
+///
+/// - `address`: The watched account.
+/// - `previous`: Value before the change, `None` on the first refresh or if the account did not
+///   exist or could not be decoded.
+/// - `current`: Value after the change, `None` if the account does not exist or can not be decoded.
+/// - `slot`: Slot the account was read at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchUpdate<T> {
+    pub address: Pubkey,
+    pub previous: Option<T>,
+    pub current: Option<T>,
+    pub slot: u64,
+}
+
+struct WatchedAccount<T> {
+    decoder: AccountDecoder<T>,
+    value: Option<T>,
+    refreshed: bool,
+    interval: Duration,
+    next_refresh: Instant,
+}
+
+/// Accounts refreshed on an adaptive schedule, see the module documentation.
+pub struct Watchlist<T> {
+    config: WatchlistConfig,
+    accounts: HashMap<Pubkey, WatchedAccount<T>>,
+    callbacks: Vec<ChangeCallback<T>>,
+}
+
+impl<T: Clone + PartialEq> Watchlist<T> {
+    pub fn new(config: WatchlistConfig) -> Self {
+        Self {
+            config,
+            accounts: HashMap::new(),
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// Watches an account, decoded with `decoder`. The account is refreshed on the next poll.
+    /// Watching an account again replaces its decoder.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::InvalidAddress` if the address is invalid.
+    pub fn watch(&mut self, address: &str, decoder: impl Fn(&Account) -> Option<T> + Send + Sync + 'static) -> Result<(), ReadTransactionError> {
+        let pubkey = address_to_pubkey(address)?;
+        self.accounts.insert(pubkey, WatchedAccount {
+            decoder: Arc::new(decoder),
+            value: None,
+            refreshed: false,
+            interval: self.config.min_interval,
+            next_refresh: Instant::now(),
+        });
+        Ok(())
+    }
+
+    /// Stops watching an account, returns `false` if it was not watched.
+    pub fn unwatch(&mut self, address: &Pubkey) -> bool {
+        self.accounts.remove(address).is_some()
+    }
+
+    /// Adds a callback called with every change of a watched value.
+    pub fn on_change(&mut self, callback: impl FnMut(&WatchUpdate<T>) + Send + 'static) -> &mut Self {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// Latest value of a watched account, `None` if it is not watched, not refreshed yet, does
+    /// not exist or can not be decoded.
+    pub fn value(&self, address: &Pubkey) -> Option<&T> {
+        self.accounts.get(address)?.value.as_ref()
+    }
+
+    /// Time of the next refresh that is due, `None` without watched accounts.
+    pub fn next_refresh(&self) -> Option<Instant> {
+        self.accounts.values().map(|account| account.next_refresh).min()
+    }
+
+    /// Refreshes the accounts due at `now`, calls the callbacks for every changed value and
+    /// returns the changes.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::RpcError` if the accounts can not be fetched. Accounts
+    /// fetched before the error keep their new values.
+    pub fn poll(&mut self, client: &RpcClient, now: Instant) -> Result<Vec<WatchUpdate<T>>, ReadTransactionError> {
+        let due_accounts: Vec<Pubkey> = self.accounts
+            .iter()
+            .filter(|(_, account)| account.next_refresh <= now)
+            .map(|(pubkey, _)| *pubkey)
+            .collect();
+        let mut updates = Vec::new();
+
+        for chunk in due_accounts.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let config = RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(client.commitment()),
+                ..RpcAccountInfoConfig::default()
+            };
+            let response = client.get_multiple_accounts_with_config(chunk, config)?;
+            for (pubkey, account) in chunk.iter().zip(response.value) {
+                updates.extend(self.refresh(pubkey, account.as_ref(), response.context.slot, now));
+            }
+        }
+
+        for update in &updates {
+            for callback in self.callbacks.iter_mut() {
+                callback(update);
+            }
+        }
+        Ok(updates)
+    }
+
+    /// Records the new value of a watched account and schedules its next refresh, returning an
+    /// update if the value changed.
+    fn refresh(&mut self, pubkey: &Pubkey, account: Option<&Account>, slot: u64, now: Instant) -> Option<WatchUpdate<T>> {
+        let (min_interval, max_interval) = (self.config.min_interval, self.config.max_interval);
+        let watched = self.accounts.get_mut(pubkey)?;
+        let current = account.and_then(|account| (watched.decoder)(account));
+        let changed = !watched.refreshed || current != watched.value;
+
+        watched.interval = match changed {
+            true => min_interval,
+            false => (watched.interval * 2).clamp(min_interval, max_interval),
+        };
+        watched.next_refresh = now + watched.interval;
+        watched.refreshed = true;
+        if !changed {
+            return None;
+        }
+        let previous = std::mem::replace(&mut watched.value, current.clone());
+        Some(WatchUpdate { address: *pubkey, previous, current, slot })
+    }
+}
+
+impl<T: Clone + PartialEq + Send + 'static> Watchlist<T> {
+    /// Polls in the background until `shutdown`, waking up for the next refresh that is due but
+    /// at most every minimum interval. The watchlist is shared so accounts and callbacks can be
+    /// added while it runs.
+    pub fn spawn(self, client: Arc<RpcClient>, mut shutdown: ShutdownSignal) -> Arc<Mutex<Watchlist<T>>> {
+        let min_interval = self.config.min_interval;
+        let watchlist = Arc::new(Mutex::new(self));
+        let task_watchlist = Arc::clone(&watchlist);
+
+        tokio::spawn(async move {
+            while !shutdown.is_shutdown() {
+                let poll_watchlist = Arc::clone(&task_watchlist);
+                let poll_client = Arc::clone(&client);
+                let result = tokio::task::spawn_blocking(move || {
+                    poll_watchlist
+                        .lock()
+                        .map(|mut watchlist| (watchlist.poll(&poll_client, Instant::now()).map(|_| ()), watchlist.next_refresh()))
+                        .ok()
+                }).await;
+                let next_refresh = match result {
+                    Ok(Some((poll_result, next_refresh))) => {
+                        if let Err(err) = poll_result {
+                            warn!("Unable to refresh watchlist: {}", err);
+                        }
+                        next_refresh
+                    }
+                    _ => {
+                        warn!("Watchlist poll panicked");
+                        None
+                    }
+                };
+                let wait = next_refresh
+                    .map(|next_refresh| next_refresh.saturating_duration_since(Instant::now()))
+                    .unwrap_or(min_interval)
+                    .max(min_interval);
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = shutdown.cancelled() => {}
+                }
+            }
+        });
+
+        watchlist
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use crate::test_utils::{account_with_data, mock_client, multiple_accounts_response};
+
+    fn amount_account(amount: u64) -> Account {
+        account_with_data(Pubkey::new_unique(), amount.to_le_bytes().to_vec())
+    }
+
+    fn decode_amount(account: &Account) -> Option<u64> {
+        Some(u64::from_le_bytes(account.data.get(..8)?.try_into().ok()?))
+    }
+
+    #[test]
+    fn test_watchlist_reports_changes_only() {
+        let client = mock_client(vec![
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(amount_account(5))], 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(amount_account(5))], 2)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(amount_account(8))], 3)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[None], 4)),
+        ]);
+        let address = Pubkey::new_unique();
+        let second = Duration::from_secs(1);
+        let mut watchlist = Watchlist::new(WatchlistConfig { min_interval: second, max_interval: second * 4 });
+        watchlist.watch(&address.to_string(), decode_amount).unwrap();
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let callback_changes = Arc::clone(&changes);
+        watchlist.on_change(move |update| callback_changes.lock().unwrap().push(update.current));
+
+        let start = Instant::now();
+        let updates = watchlist.poll(&client, start).unwrap();
+        assert!(updates == vec![WatchUpdate { address, previous: None, current: Some(5), slot: 1 }]);
+        // not due yet
+        assert!(watchlist.poll(&client, start).unwrap().is_empty());
+        // unchanged, the interval doubles
+        assert!(watchlist.poll(&client, start + second).unwrap().is_empty());
+        assert!(watchlist.next_refresh() == Some(start + second * 3));
+
+        let updates = watchlist.poll(&client, start + second * 3).unwrap();
+        assert!(updates[0].previous == Some(5) && updates[0].current == Some(8) && updates[0].slot == 3);
+        assert!(watchlist.next_refresh() == Some(start + second * 4));
+        // closed accounts have no value
+        let updates = watchlist.poll(&client, start + second * 4).unwrap();
+        assert!(updates[0].previous == Some(8) && updates[0].current.is_none());
+        assert!(*changes.lock().unwrap() == vec![Some(5), Some(8), None]);
+        assert!(watchlist.value(&address).is_none());
+    }
+}
@@ -0,0 +1,128 @@
+//! # Compute Limit
+//!
+//! Compute unit limits sized from a simulation of the transaction, so transactions neither fail
+//! from a low guess nor reserve more units than they use.
+
+use solana_sdk::{compute_budget::ComputeBudgetInstruction, hash::Hash, transaction::Transaction};
+use crate::{constants::units::BASIS_POINTS_DIVISOR, error::TransactionBuilderError};
+use super::{priority_fees::is_same_compute_budget_instruction, transaction_builder::TransactionBuilder, utils::simulate_transaction};
+
+/// Highest compute unit limit of a transaction.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Units added on top of the simulated units. Simulated units exclude the compute budget
+/// instructions and vary slightly between simulation and execution.
+///
+/// - Units: A fixed number of units.
+/// - BasisPoints: A share of the simulated units, e.g `1_000` is 10%.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComputeLimitMargin {
+    Units(u32),
+    BasisPoints(u16),
+}
+
+impl Default for ComputeLimitMargin {
+    fn default() -> Self {
+        ComputeLimitMargin::Units(1_000)
+    }
+}
+
+impl ComputeLimitMargin {
+    /// Compute unit limit for `units_consumed` simulated units, at most `MAX_COMPUTE_UNIT_LIMIT`.
+    pub fn compute_limit(&self, units_consumed: u32) -> u32 {
+        let margin = match self {
+            ComputeLimitMargin::Units(units) => *units as u64,
+            ComputeLimitMargin::BasisPoints(basis_points) => units_consumed as u64 * *basis_points as u64 / BASIS_POINTS_DIVISOR as u64,
+        };
+        (units_consumed as u64 + margin).min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+    }
+}
+
+impl TransactionBuilder<'_> {
+    /// Simulates the instructions added so far with the highest compute limit, then builds the
+    /// transaction with the simulated units plus `margin` as its compute limit. A compute limit
+    /// set before is replaced, in the builder as well.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::SimulationError` if the transaction can not be simulated,
+    /// `TransactionBuilderError::SimulationFailed` if the simulated transaction fails, and the
+    /// errors of `build` otherwise.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use easy_solana::{
+    ///     create_rpc_client,
+    ///     write_transactions::compute_limit::ComputeLimitMargin,
+    ///     SecretKeypair,
+    ///     TransactionBuilder
+    /// };
+    ///
+    /// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+    /// let keypair = SecretKeypair::from_env("PRIVATE_KEY_1").unwrap();
+    /// let mut builder = TransactionBuilder::new(&client, keypair.keypair());
+    /// builder.transfer_sol(0.01, keypair.keypair(), "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5").unwrap();
+    /// // 10% more units than simulated
+    /// let transaction = builder.build_with_simulated_compute_limit(ComputeLimitMargin::BasisPoints(1_000)).unwrap();
+    /// ```
+    pub fn build_with_simulated_compute_limit(&mut self, margin: ComputeLimitMargin) -> Result<Transaction, TransactionBuilderError> {
+        let limit_instruction = ComputeBudgetInstruction::set_compute_unit_limit(0);
+        self.instructions.retain(|instruction| !is_same_compute_budget_instruction(instruction, &limit_instruction));
+        self.instructions.insert(0, ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT));
+
+        // The RPC replaces the blockhash when simulating
+        let simulation_result = simulate_transaction(self.client, self.build_with_blockhash(Hash::default())?)?;
+        if let Some(err) = simulation_result.error {
+            return Err(TransactionBuilderError::SimulationFailed(err));
+        }
+
+        self.instructions[0] = ComputeBudgetInstruction::set_compute_unit_limit(margin.compute_limit(simulation_result.units_consumed));
+        self.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::{pubkey::Pubkey, signer::{keypair::Keypair, Signer}};
+    use solana_system_interface::instruction::transfer;
+    use crate::test_utils::{mock_client, simulation_response};
+
+    #[test]
+    fn test_compute_limit_margin() {
+        assert!(ComputeLimitMargin::Units(1_000).compute_limit(30_000) == 31_000);
+        assert!(ComputeLimitMargin::BasisPoints(1_000).compute_limit(30_000) == 33_000);
+        assert!(ComputeLimitMargin::BasisPoints(10_000).compute_limit(MAX_COMPUTE_UNIT_LIMIT) == MAX_COMPUTE_UNIT_LIMIT);
+    }
+
+    #[test]
+    fn test_build_with_simulated_compute_limit() {
+        let client = mock_client(vec![(RpcRequest::SimulateTransaction, simulation_response(&[], 30_000))]);
+        let keypair = Keypair::new();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder.set_compute_units(5_000).set_compute_limit(200_000);
+        builder.instructions.push(transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1));
+
+        let transaction = builder.build_with_simulated_compute_limit(ComputeLimitMargin::BasisPoints(1_000)).unwrap();
+        assert!(builder.instructions.len() == 3);
+        assert!(builder.instructions[0] == ComputeBudgetInstruction::set_compute_unit_limit(33_000));
+        assert!(transaction.message.instructions.len() == 3);
+    }
+
+    #[test]
+    fn failing_test_build_with_failed_simulation() {
+        let failed_simulation = json!({
+            "context": { "slot": 1 },
+            "value": { "err": "AccountNotFound", "logs": [], "unitsConsumed": 0, "innerInstructions": [] }
+        });
+        let client = mock_client(vec![(RpcRequest::SimulateTransaction, failed_simulation)]);
+        let keypair = Keypair::new();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder.instructions.push(transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1));
+        let result = builder.build_with_simulated_compute_limit(ComputeLimitMargin::default());
+        assert!(matches!(result, Err(TransactionBuilderError::SimulationFailed(_))));
+    }
+}
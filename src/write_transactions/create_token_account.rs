@@ -62,6 +62,7 @@ impl TransactionBuilder<'_> {
         );
 
         self.instructions.push(create_associated_token_account_instruction);
+        self.apply_fee(0);
 
         Ok(self)
     }
@@ -124,6 +125,7 @@ impl TransactionBuilder<'_> {
         );
 
         self.instructions.push(create_associated_token_account_instruction);
+        self.apply_fee(0);
 
         Ok(self)
     }
@@ -0,0 +1,122 @@
+use log::warn;
+use solana_sdk::instruction::Instruction;
+
+use crate::constants::solana_programs::compute_budget_program;
+use super::{priority_fees::is_same_compute_budget_instruction, transaction_builder::TransactionBuilder};
+
+impl TransactionBuilder<'_> {
+    /// Removes exact duplicate instructions from the transaction, keeping the first occurrence.
+    /// Composing many helper calls can add the same instruction twice, e.g two create associated
+    /// token account instructions for the same token, which would fail the transaction.
+    ///
+    /// Instructions that are not identical but conflict with each other are kept and a warning
+    /// is logged instead, as there is no way to tell which one was intended. Two instructions conflict when:
+    /// - they are compute budget instructions of the same kind with different values (e.g two compute limits)
+    /// - they call the same program with the same accounts but different instruction data
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use solana_sdk::signer::keypair::Keypair;
+    /// use easy_solana::create_rpc_client;
+    /// use easy_solana::write_transactions::transaction_builder::TransactionBuilder;
+    /// use easy_solana::constants::solana_programs::token_program;
+    ///
+    /// const USDC_TOKEN_ADDRESS: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    ///
+    /// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+    /// let keypair = Keypair::new();
+    /// let mut builder = TransactionBuilder::new(&client, &keypair);
+    /// builder
+    ///     .create_associated_token_account_for_payer(USDC_TOKEN_ADDRESS, token_program())
+    ///     .unwrap()
+    ///     .create_associated_token_account_for_payer(USDC_TOKEN_ADDRESS, token_program())
+    ///     .unwrap()
+    ///     .dedupe();
+    /// assert_eq!(builder.instructions.len(), 1);
+    /// ```
+    pub fn dedupe(&mut self) -> &mut Self {
+        let mut unique_instructions: Vec<Instruction> = Vec::with_capacity(self.instructions.len());
+
+        for instruction in self.instructions.drain(..) {
+            if unique_instructions.contains(&instruction) {
+                continue;
+            }
+            if let Some(existing_instruction) = unique_instructions
+                .iter()
+                .find(|existing_instruction| is_conflicting_instruction(existing_instruction, &instruction))
+            {
+                warn!(
+                    "dedupe: conflicting instructions for program {}, {:?} and {:?}",
+                    instruction.program_id, existing_instruction.data, instruction.data
+                );
+            }
+            unique_instructions.push(instruction);
+        }
+
+        self.instructions = unique_instructions;
+        self
+    }
+}
+
+/// Two different instructions conflict when they set the same compute budget parameter, or
+/// when they call the same program on the same accounts with different data.
+fn is_conflicting_instruction(first: &Instruction, second: &Instruction) -> bool {
+    if first.program_id != second.program_id {
+        return false;
+    }
+    if first.program_id == compute_budget_program() {
+        return is_same_compute_budget_instruction(first, second);
+    }
+    first.accounts == second.accounts && first.data != second.data
+}
+
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signer::keypair::Keypair;
+    use crate::{
+        solana_programs::token_program,
+        test_utils::mock_client,
+        write_transactions::transaction_builder::TransactionBuilder
+    };
+
+    const WALLET_ADDRESS_2: &str = "joNASGVYc6ugNiUCsamrJ8i2PBoxFW9YvqNisNfFNXg";
+    const USDC_TOKEN_ADDRESS: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    #[test]
+    fn test_dedupe_removes_duplicate_instructions() {
+        let client = mock_client(vec![]);
+        let keypair = Keypair::new();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder
+            .set_compute_units(50_000)
+            .create_associated_token_account_for_payer(USDC_TOKEN_ADDRESS, token_program())
+            .unwrap()
+            .transfer_sol(0.01, &keypair, WALLET_ADDRESS_2)
+            .unwrap()
+            .create_associated_token_account_for_payer(USDC_TOKEN_ADDRESS, token_program())
+            .unwrap()
+            .set_compute_units(50_000)
+            .dedupe();
+
+        assert!(builder.instructions.len() == 3);
+    }
+
+    #[test]
+    fn test_dedupe_keeps_conflicting_instructions() {
+        let client = mock_client(vec![]);
+        let keypair = Keypair::new();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder
+            .set_compute_limit(200_000)
+            .set_compute_limit(1_000_000)
+            .transfer_sol(0.01, &keypair, WALLET_ADDRESS_2)
+            .unwrap()
+            .transfer_sol(0.02, &keypair, WALLET_ADDRESS_2)
+            .unwrap()
+            .dedupe();
+
+        assert!(builder.instructions.len() == 4);
+    }
+}
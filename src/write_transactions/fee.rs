@@ -0,0 +1,150 @@
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+use solana_system_interface::instruction::transfer;
+
+use crate::constants::units::BASIS_POINTS_DIVISOR;
+use super::transaction_builder::TransactionBuilder;
+
+/// How the integrator fee of an operation is calculated.
+///
+/// - Flat: A fixed fee in lamports for every operation.
+/// - BasisPoints: A share of the SOL value of the operation, e.g `100` is 1%.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeeAmount {
+    Flat(u64),
+    BasisPoints(u16),
+}
+
+/// Referrer receiving a share of the integrator fee.
+///
+/// ### Fields
+///
+/// - `wallet`: Wallet receiving the referral fee.
+/// - `share_bps`: Share of the fee paid to the referrer in basis points, e.g `2_000` is 20%.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Referral {
+    pub wallet: Pubkey,
+    pub share_bps: u16,
+}
+
+/// Fee charged by an application on top of the operations it builds. Once set on a
+/// `TransactionBuilder`, high level operations such as creating token accounts, pump.fun
+/// buys, sells, bumps and creations, and multi-hop swaps add the fee transfers automatically.
+///
+/// ### Fields
+///
+/// - `fee_wallet`: Wallet receiving the fee.
+/// - `amount`: Flat or value based fee.
+/// - `referral`: Optional referrer receiving a share of the fee.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeConfig {
+    pub fee_wallet: Pubkey,
+    pub amount: FeeAmount,
+    pub referral: Option<Referral>,
+}
+
+impl FeeConfig {
+    /// Returns the fee wallet and referral shares in lamports of an operation worth `value_lamports`.
+    pub fn fee_split(&self, value_lamports: u64) -> (u64, u64) {
+        let total_fee = match self.amount {
+            FeeAmount::Flat(lamports) => lamports,
+            FeeAmount::BasisPoints(basis_points) => (value_lamports as u128 * basis_points as u128 / BASIS_POINTS_DIVISOR) as u64,
+        };
+        let referral_fee = match self.referral {
+            Some(referral) => (total_fee as u128 * referral.share_bps.min(10_000) as u128 / BASIS_POINTS_DIVISOR) as u64,
+            None => 0,
+        };
+        (total_fee - referral_fee, referral_fee)
+    }
+}
+
+impl TransactionBuilder<'_> {
+    /// Sets the fee charged on high level operations added to this builder afterwards.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use solana_sdk::{pubkey::Pubkey, signer::keypair::Keypair};
+    /// use easy_solana::create_rpc_client;
+    /// use easy_solana::constants::solana_programs::token_program;
+    /// use easy_solana::write_transactions::{fee::{FeeAmount, FeeConfig}, transaction_builder::TransactionBuilder};
+    ///
+    /// const USDC_TOKEN_ADDRESS: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    ///
+    /// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+    /// let keypair = Keypair::new();
+    /// let fee_config = FeeConfig { fee_wallet: Pubkey::new_unique(), amount: FeeAmount::Flat(1_000_000), referral: None };
+    /// let mut builder = TransactionBuilder::new(&client, &keypair);
+    /// builder
+    ///     .with_fee_config(fee_config)
+    ///     .create_associated_token_account_for_payer(USDC_TOKEN_ADDRESS, token_program())
+    ///     .unwrap();
+    /// // create account and fee transfer
+    /// assert!(builder.instructions.len() == 2);
+    /// ```
+    pub fn with_fee_config(&mut self, fee_config: FeeConfig) -> &mut Self {
+        self.fee_config = Some(fee_config);
+        self
+    }
+
+    /// Adds the fee transfers of an operation worth `value_lamports` according to the fee config.
+    /// High level operations call this automatically, it does nothing without a fee config.
+    pub fn apply_fee(&mut self, value_lamports: u64) -> &mut Self {
+        let Some(fee_config) = self.fee_config else {
+            return self;
+        };
+        let payer_account = self.payer_keypair.pubkey();
+        let (fee, referral_fee) = fee_config.fee_split(value_lamports);
+        if fee > 0 {
+            self.instructions.push(transfer(&payer_account, &fee_config.fee_wallet, fee));
+        }
+        if let Some(referral) = fee_config.referral.filter(|_| referral_fee > 0) {
+            self.instructions.push(transfer(&payer_account, &referral.wallet, referral_fee));
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signer::keypair::Keypair;
+    use crate::{constants::solana_programs::token_program, utils::create_rpc_client};
+
+    const USDC_TOKEN_ADDRESS: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    #[test]
+    fn test_fee_split() {
+        let referral = Referral { wallet: Pubkey::new_unique(), share_bps: 2_000 };
+        let fee_config = FeeConfig { fee_wallet: Pubkey::new_unique(), amount: FeeAmount::BasisPoints(100), referral: Some(referral) };
+        assert!(fee_config.fee_split(1_000_000_000) == (8_000_000, 2_000_000));
+
+        let flat_fee_config = FeeConfig { amount: FeeAmount::Flat(5_000), referral: None, ..fee_config };
+        assert!(flat_fee_config.fee_split(1_000_000_000) == (5_000, 0));
+    }
+
+    #[test]
+    fn test_apply_fee_on_create_token_account() {
+        let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+        let keypair = Keypair::new();
+        let referral = Referral { wallet: Pubkey::new_unique(), share_bps: 1_000 };
+        let fee_config = FeeConfig { fee_wallet: Pubkey::new_unique(), amount: FeeAmount::Flat(20_000_000), referral: Some(referral) };
+
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder
+            .with_fee_config(fee_config)
+            .create_associated_token_account_for_payer(USDC_TOKEN_ADDRESS, token_program())
+            .unwrap();
+        assert!(builder.instructions.len() == 3);
+        assert!(builder.instructions[1].accounts[1].pubkey == fee_config.fee_wallet);
+        assert!(builder.instructions[2].accounts[1].pubkey == referral.wallet);
+
+        // operations without a value and value based fees do not add transfers
+        let basis_points_fee_config = FeeConfig { amount: FeeAmount::BasisPoints(100), ..fee_config };
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder
+            .with_fee_config(basis_points_fee_config)
+            .create_associated_token_account_for_payer(USDC_TOKEN_ADDRESS, token_program())
+            .unwrap();
+        assert!(builder.instructions.len() == 1);
+    }
+}
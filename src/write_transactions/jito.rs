@@ -0,0 +1,157 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_sdk::{signer::Signer, transaction::Transaction};
+use solana_system_interface::instruction::transfer;
+
+use crate::{
+    constants::jito_accounts::jito_tip_accounts,
+    error::{BundleError, TransactionBuilderError},
+    utils::sol_to_lamports
+};
+use super::transaction_builder::TransactionBuilder;
+
+/// Status of a landed bundle as reported by the block engine.
+#[derive(Deserialize, Debug, Clone)]
+pub struct BundleStatus {
+    pub bundle_id: String,
+    pub transactions: Vec<String>,
+    pub slot: u64,
+    pub confirmation_status: Option<String>,
+    pub err: Value,
+}
+
+impl TransactionBuilder<'_> {
+    /// Adds a transfer of `amount` SOL from the payer to one of the Jito tip accounts, see
+    /// `tip_jito_lamports`.
+    pub fn tip_jito(&mut self, amount: f64) -> Result<&mut Self, TransactionBuilderError> {
+        self.tip_jito_lamports(sol_to_lamports(amount))
+    }
+
+    /// Adds a transfer of `lamports` from the payer to one of the Jito tip accounts. Bundles are
+    /// only picked up by Jito validators if one of their transactions tips, usually the last one.
+    pub fn tip_jito_lamports(&mut self, lamports: u64) -> Result<&mut Self, TransactionBuilderError> {
+        let tip_accounts = jito_tip_accounts();
+        // Spread tips across tip accounts to reduce write lock contention
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.subsec_nanos()).unwrap_or(0);
+        let tip_account = tip_accounts[nanos as usize % tip_accounts.len()];
+        // Tip accounts are known wallets, no need to check them like other destinations
+        self.instructions.push(transfer(&self.payer_keypair.pubkey(), &tip_account, lamports));
+        Ok(self)
+    }
+}
+
+/// Sends signed transactions as a single Jito bundle, either all of them land in order or none do.
+/// Returns the bundle id.
+///
+/// ## Errors
+///
+/// Returns a `BundleError` if the transactions cannot be serialized or the block engine rejects the bundle.
+pub async fn send_bundle(block_engine_url: &str, transactions: &[Transaction]) -> Result<String, BundleError> {
+    let encoded_transactions = transactions
+        .iter()
+        .map(|transaction| {
+            bincode::serialize(transaction)
+                .map(|bytes| STANDARD.encode(bytes))
+                .map_err(|_| BundleError::SerializeError)
+        })
+        .collect::<Result<Vec<String>, BundleError>>()?;
+
+    let result = block_engine_request(
+        block_engine_url,
+        "sendBundle",
+        json!([encoded_transactions, { "encoding": "base64" }])
+    ).await?;
+
+    result
+        .as_str()
+        .map(|bundle_id| bundle_id.to_string())
+        .ok_or(BundleError::InvalidResponse(result.to_string()))
+}
+
+/// Gets the status of a bundle, returns `None` if the bundle has not landed.
+pub async fn get_bundle_status(block_engine_url: &str, bundle_id: &str) -> Result<Option<BundleStatus>, BundleError> {
+    let result = block_engine_request(block_engine_url, "getBundleStatuses", json!([[bundle_id]])).await?;
+    let status = result
+        .get("value")
+        .and_then(|statuses| statuses.get(0))
+        .filter(|status| !status.is_null())
+        .cloned();
+
+    match status {
+        Some(status) => serde_json::from_value(status)
+            .map(Some)
+            .map_err(|err| BundleError::InvalidResponse(err.to_string())),
+        None => Ok(None)
+    }
+}
+
+/// Sends a bundle and polls the block engine until it is confirmed or `timeout` has passed.
+pub async fn send_and_confirm_bundle(
+    block_engine_url: &str,
+    transactions: &[Transaction],
+    timeout: Duration
+) -> Result<BundleStatus, BundleError> {
+    let bundle_id = send_bundle(block_engine_url, transactions).await?;
+    let start_time = Instant::now();
+
+    while start_time.elapsed() < timeout {
+        if let Some(status) = get_bundle_status(block_engine_url, &bundle_id).await? {
+            // err is {"Ok": null} for successful bundles
+            if status.err.get("Ok").is_none() {
+                return Err(BundleError::BundleFailed(status.err.to_string()));
+            }
+            if matches!(status.confirmation_status.as_deref(), Some("confirmed") | Some("finalized")) {
+                return Ok(status);
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    Err(BundleError::Timeout)
+}
+
+async fn block_engine_request(block_engine_url: &str, method: &str, params: Value) -> Result<Value, BundleError> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    });
+    let response: Value = reqwest::Client::new()
+        .post(block_engine_url)
+        .json(&request)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    if let Some(error) = response.get("error") {
+        return Err(BundleError::InvalidResponse(error.to_string()));
+    }
+    response
+        .get("result")
+        .cloned()
+        .ok_or(BundleError::InvalidResponse(response.to_string()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signer::keypair::Keypair;
+    use crate::utils::create_rpc_client;
+
+    #[test]
+    fn test_tip_jito_transfers_to_tip_account() {
+        let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+        let keypair = Keypair::new();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder.tip_jito(0.001).unwrap();
+
+        let tip_instruction = &builder.instructions[0];
+        let tip_account = tip_instruction.accounts[1].pubkey;
+        assert!(jito_tip_accounts().contains(&tip_account));
+    }
+}
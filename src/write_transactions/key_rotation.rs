@@ -0,0 +1,390 @@
+//! # Key Rotation
+//!
+//! This module migrates every asset of a wallet to a new wallet, e.g after its private key may
+//! have been exposed. Tokens are sent to associated token accounts of the new wallet, the old
+//! token accounts are closed and the remaining SOL is swept last.
+
+use serde::Serialize;
+use serde_json::json;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::RpcAccountInfoConfig,
+    rpc_request::RpcRequest,
+    rpc_response::{Response, RpcKeyedAccount}
+};
+use solana_sdk::{
+    account::Account as SolanaAccount,
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer
+};
+use solana_system_interface::instruction::transfer;
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent
+};
+use spl_token_2022::{
+    extension::StateWithExtensions,
+    instruction::{close_account, transfer_checked},
+    state::{Account as Token2022Account, Mint as Token2022Mint}
+};
+use crate::{
+    constants::solana_programs::{token_2022_program, token_program},
+    error::KeyRotationError,
+    json::{serialize_display, serialize_display_vec, ToJson},
+    read_transactions::batch::MAX_MULTIPLE_ACCOUNTS,
+    utils::address_to_pubkey
+};
+use super::{transaction_builder::TransactionBuilder, utils::send_and_confirm_transaction};
+
+/// Token accounts migrated per transaction, keeping transactions below the size limit.
+const TOKEN_ACCOUNTS_PER_TRANSACTION: usize = 5;
+
+/// A token account of the old wallet to migrate.
+///
+/// ### Fields
+///
+/// - `address`: Address of the token account.
+/// - `mint`: Mint of the token.
+/// - `token_program`: Program owning the token account.
+/// - `amount`: Balance without decimals.
+/// - `decimals`: Decimals of the mint.
+/// - `is_frozen`: Frozen accounts can not be migrated or closed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RotationTokenAccount {
+    #[serde(serialize_with = "serialize_display")]
+    pub address: Pubkey,
+    #[serde(serialize_with = "serialize_display")]
+    pub mint: Pubkey,
+    #[serde(serialize_with = "serialize_display")]
+    pub token_program: Pubkey,
+    pub amount: u64,
+    pub decimals: u8,
+    pub is_frozen: bool,
+}
+
+/// Assets of the old wallet that a rotation moves to the new wallet.
+///
+/// ### Fields
+///
+/// - `old_wallet`: Wallet being rotated out.
+/// - `new_wallet`: Wallet receiving the assets.
+/// - `token_accounts`: Token accounts owned by the old wallet.
+/// - `lamports`: SOL balance of the old wallet in lamports.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RotationPlan {
+    #[serde(serialize_with = "serialize_display")]
+    pub old_wallet: Pubkey,
+    #[serde(serialize_with = "serialize_display")]
+    pub new_wallet: Pubkey,
+    pub token_accounts: Vec<RotationTokenAccount>,
+    pub lamports: u64,
+}
+
+impl RotationPlan {
+    /// Whether the old wallet holds nothing left to migrate.
+    pub fn is_empty(&self) -> bool {
+        self.token_accounts.is_empty() && self.lamports == 0
+    }
+
+    /// Instructions migrating each token account that is not frozen, grouped per transaction.
+    pub fn token_instructions(&self) -> Vec<Vec<Instruction>> {
+        let migratable_accounts: Vec<&RotationTokenAccount> = self.token_accounts
+            .iter()
+            .filter(|token_account| !token_account.is_frozen)
+            .collect();
+        migratable_accounts
+            .chunks(TOKEN_ACCOUNTS_PER_TRANSACTION)
+            .map(|token_accounts| token_accounts
+                .iter()
+                .flat_map(|token_account| self.migrate_token_account(token_account))
+                .collect())
+            .collect()
+    }
+
+    /// Creates the associated token account of the new wallet if needed, transfers the balance
+    /// and closes the old token account, returning its rent to the new wallet.
+    fn migrate_token_account(&self, token_account: &RotationTokenAccount) -> Vec<Instruction> {
+        let RotationTokenAccount { address, mint, token_program, amount, decimals, .. } = token_account;
+        let mut instructions = Vec::new();
+        if *amount > 0 {
+            let destination = get_associated_token_address_with_program_id(&self.new_wallet, mint, token_program);
+            instructions.push(create_associated_token_account_idempotent(&self.old_wallet, &self.new_wallet, mint, token_program));
+            // only fails for an unknown token program, which token accounts are never owned by
+            instructions.extend(transfer_checked(token_program, address, mint, &destination, &self.old_wallet, &[], *amount, *decimals).ok());
+        }
+        instructions.extend(close_account(token_program, address, &self.new_wallet, &self.old_wallet, &[]).ok());
+        instructions
+    }
+}
+
+/// Outcome of `rotate_wallet`.
+///
+/// ### Fields
+///
+/// - `signatures`: Signatures of the confirmed transactions, in order.
+/// - `migrated_token_accounts`: Token accounts emptied and closed.
+/// - `lamports_transferred`: SOL swept to the new wallet in lamports, excluding closed account rent.
+/// - `remaining`: What the old wallet still holds after the rotation, e.g frozen token accounts.
+#[derive(Debug, Clone, Serialize)]
+pub struct RotationReport {
+    #[serde(serialize_with = "serialize_display_vec")]
+    pub signatures: Vec<Signature>,
+    pub migrated_token_accounts: Vec<RotationTokenAccount>,
+    pub lamports_transferred: u64,
+    pub remaining: RotationPlan,
+}
+
+impl ToJson for RotationPlan {}
+impl ToJson for RotationReport {}
+
+impl RotationReport {
+    /// Whether the old wallet was verified to be empty after the rotation.
+    pub fn is_complete(&self) -> bool {
+        self.remaining.is_empty()
+    }
+}
+
+/// Reads the token accounts and SOL balance of `old_wallet_address` to migrate to
+/// `new_wallet_address`, without sending anything.
+///
+/// ## Errors
+///
+/// Returns `KeyRotationError::SameWallet` if both addresses are the same.
+pub fn plan_wallet_rotation(client: &RpcClient, old_wallet_address: &str, new_wallet_address: &str) -> Result<RotationPlan, KeyRotationError> {
+    let old_wallet = address_to_pubkey(old_wallet_address)?;
+    let new_wallet = address_to_pubkey(new_wallet_address)?;
+    if old_wallet == new_wallet {
+        return Err(KeyRotationError::SameWallet);
+    }
+
+    let mut token_accounts = Vec::new();
+    for token_program in [token_program(), token_2022_program()] {
+        for (address, account) in get_token_accounts_of_owner(client, &old_wallet, &token_program)? {
+            let token_account = StateWithExtensions::<Token2022Account>::unpack(&account.data)
+                .map_err(|_| KeyRotationError::DeserializeError)?
+                .base;
+            token_accounts.push(RotationTokenAccount {
+                address,
+                mint: token_account.mint,
+                token_program,
+                amount: token_account.amount,
+                decimals: 0,
+                is_frozen: token_account.is_frozen(),
+            });
+        }
+    }
+
+    // transfer_checked requires the decimals of each mint
+    let mints: Vec<Pubkey> = token_accounts.iter().map(|token_account| token_account.mint).collect();
+    for (token_accounts, mints) in token_accounts.chunks_mut(MAX_MULTIPLE_ACCOUNTS).zip(mints.chunks(MAX_MULTIPLE_ACCOUNTS)) {
+        let mint_accounts = client.get_multiple_accounts(mints)?;
+        for (token_account, mint_account) in token_accounts.iter_mut().zip(mint_accounts) {
+            let mint_account = mint_account.ok_or(KeyRotationError::DeserializeError)?;
+            token_account.decimals = StateWithExtensions::<Token2022Mint>::unpack(&mint_account.data)
+                .map_err(|_| KeyRotationError::DeserializeError)?
+                .base
+                .decimals;
+        }
+    }
+
+    let lamports = client.get_balance(&old_wallet)?;
+    Ok(RotationPlan { old_wallet, new_wallet, token_accounts, lamports })
+}
+
+/// Migrates all tokens and SOL of `old_keypair` to `new_wallet_address`, closing the token
+/// accounts of the old wallet, then verifies that nothing is left behind. The old wallet pays
+/// every fee, so the new wallet only needs to be an address.
+///
+/// Frozen token accounts can not be migrated and remain in `RotationReport::remaining`, as does
+/// anything received by the old wallet during the rotation. Tokens with a transfer hook are not
+/// supported, as their transfers require extra accounts.
+///
+/// ## Errors
+///
+/// Returns `KeyRotationError::SendError` if a transaction fails, assets migrated by earlier
+/// transactions stay in the new wallet and `rotate_wallet` can be called again.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_sdk::signer::Signer;
+/// use easy_solana::{create_rpc_client, SecretKeypair, write_transactions::key_rotation::rotate_wallet};
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let exposed_keypair = SecretKeypair::from_env("PRIVATE_KEY").unwrap();
+/// let new_keypair = SecretKeypair::generate();
+/// let report = rotate_wallet(&client, &exposed_keypair, &new_keypair.pubkey().to_string()).unwrap();
+/// assert!(report.is_complete());
+/// ```
+pub fn rotate_wallet(client: &RpcClient, old_keypair: &Keypair, new_wallet_address: &str) -> Result<RotationReport, KeyRotationError> {
+    let old_wallet_address = old_keypair.pubkey().to_string();
+    let plan = plan_wallet_rotation(client, &old_wallet_address, new_wallet_address)?;
+    let mut signatures = Vec::new();
+
+    for instructions in plan.token_instructions() {
+        let mut builder = TransactionBuilder::new(client, old_keypair);
+        builder.instructions.extend(instructions);
+        let confirmed_transaction = send_and_confirm_transaction(client, builder.build()?)?
+            .into_result(KeyRotationError::TransactionFailed)?;
+        signatures.push(confirmed_transaction.signature);
+    }
+
+    // sweep SOL last, once rent of the closed token accounts has gone to the new wallet
+    let lamports_transferred = match sweep_sol(client, old_keypair, &plan.new_wallet)? {
+        Some((signature, lamports)) => {
+            signatures.push(signature);
+            lamports
+        }
+        None => 0,
+    };
+
+    let migrated_token_accounts = plan.token_accounts
+        .into_iter()
+        .filter(|token_account| !token_account.is_frozen)
+        .collect();
+    let remaining = plan_wallet_rotation(client, &old_wallet_address, new_wallet_address)?;
+    Ok(RotationReport { signatures, migrated_token_accounts, lamports_transferred, remaining })
+}
+
+/// Transfers the whole balance of `keypair` minus the transaction fee, `None` if the balance
+/// does not cover the fee.
+fn sweep_sol(client: &RpcClient, keypair: &Keypair, destination: &Pubkey) -> Result<Option<(Signature, u64)>, KeyRotationError> {
+    let wallet = keypair.pubkey();
+    let balance = client.get_balance(&wallet)?;
+    let recent_blockhash = client.get_latest_blockhash()?;
+    let fee_message = Message::new_with_blockhash(&[transfer(&wallet, destination, balance)], Some(&wallet), &recent_blockhash);
+    let fee = client.get_fee_for_message(&fee_message)?;
+    let lamports = balance.saturating_sub(fee);
+    if lamports == 0 {
+        return Ok(None);
+    }
+
+    let mut builder = TransactionBuilder::new(client, keypair);
+    builder.instructions.push(transfer(&wallet, destination, lamports));
+    let confirmed_transaction = send_and_confirm_transaction(client, builder.build_with_blockhash(recent_blockhash)?)?
+        .into_result(KeyRotationError::TransactionFailed)?;
+    Ok(Some((confirmed_transaction.signature, lamports)))
+}
+
+/// Token accounts of `owner` under `token_program`, with their raw account data.
+fn get_token_accounts_of_owner(client: &RpcClient, owner: &Pubkey, token_program: &Pubkey) -> Result<Vec<(Pubkey, SolanaAccount)>, KeyRotationError> {
+    let config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        commitment: Some(client.commitment()),
+        ..RpcAccountInfoConfig::default()
+    };
+    let params = json!([owner.to_string(), { "programId": token_program.to_string() }, config]);
+    let token_accounts: Response<Vec<RpcKeyedAccount>> = client.send(RpcRequest::GetTokenAccountsByOwner, params)?;
+    token_accounts.value
+        .into_iter()
+        .map(|keyed_account| {
+            let address = address_to_pubkey(&keyed_account.pubkey)?;
+            let account = keyed_account.account.decode::<SolanaAccount>().ok_or(KeyRotationError::DeserializeError)?;
+            Ok((address, account))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::program_pack::Pack;
+    use spl_token::state::{Account as SplTokenAccount, AccountState, Mint as SplMint};
+    use crate::test_utils::{account_with_data, mock_client, multiple_accounts_response, token_accounts_by_owner_response};
+
+    fn token_account_data(mint: Pubkey, owner: Pubkey, amount: u64, state: AccountState) -> Vec<u8> {
+        let mut data = vec![0; SplTokenAccount::LEN];
+        SplTokenAccount { mint, owner, amount, state, ..SplTokenAccount::default() }.pack_into_slice(&mut data);
+        data
+    }
+
+    fn mint_data(decimals: u8) -> Vec<u8> {
+        let mut data = vec![0; SplMint::LEN];
+        SplMint { decimals, is_initialized: true, ..SplMint::default() }.pack_into_slice(&mut data);
+        data
+    }
+
+    #[test]
+    fn test_plan_wallet_rotation() {
+        let (old_wallet, new_wallet) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mint, frozen_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (token_account, frozen_token_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let client = mock_client(vec![
+            (RpcRequest::GetTokenAccountsByOwner, token_accounts_by_owner_response(&[
+                (token_account, account_with_data(token_program(), token_account_data(mint, old_wallet, 500, AccountState::Initialized))),
+                (frozen_token_account, account_with_data(token_program(), token_account_data(frozen_mint, old_wallet, 10, AccountState::Frozen))),
+            ], 1)),
+            (RpcRequest::GetTokenAccountsByOwner, token_accounts_by_owner_response(&[], 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[
+                Some(account_with_data(token_program(), mint_data(6))),
+                Some(account_with_data(token_program(), mint_data(9))),
+            ], 1)),
+        ]);
+
+        let plan = plan_wallet_rotation(&client, &old_wallet.to_string(), &new_wallet.to_string()).unwrap();
+        assert!(plan.token_accounts.len() == 2);
+        assert!(plan.token_accounts[0].amount == 500 && plan.token_accounts[0].decimals == 6);
+        assert!(plan.token_accounts[1].is_frozen && plan.token_accounts[1].decimals == 9);
+        assert!(!plan.is_empty());
+
+        // create, transfer and close for the account that is not frozen
+        let transactions = plan.token_instructions();
+        assert!(transactions.len() == 1 && transactions[0].len() == 3);
+        assert!(transactions[0].iter().all(|instruction| !instruction.accounts.iter().any(|account| account.pubkey == frozen_token_account)));
+    }
+
+    #[test]
+    fn test_token_instructions_are_batched() {
+        let (old_wallet, new_wallet) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let token_accounts = (0..7)
+            .map(|index| RotationTokenAccount {
+                address: Pubkey::new_unique(),
+                mint: Pubkey::new_unique(),
+                token_program: token_2022_program(),
+                amount: index,
+                decimals: 6,
+                is_frozen: false,
+            })
+            .collect();
+        let plan = RotationPlan { old_wallet, new_wallet, token_accounts, lamports: 0 };
+
+        let transactions = plan.token_instructions();
+        assert!(transactions.len() == 2);
+        // empty token accounts are only closed
+        assert!(transactions[0].len() == 1 + 3 * 4 && transactions[1].len() == 3 * 2);
+    }
+
+    #[test]
+    fn test_rotate_wallet_sweeps_sol_and_verifies() {
+        let old_keypair = Keypair::new();
+        let new_wallet = Pubkey::new_unique();
+        let balance_response = |lamports: u64| json!({ "context": { "slot": 1 }, "value": lamports });
+        let client = mock_client(vec![
+            (RpcRequest::GetTokenAccountsByOwner, token_accounts_by_owner_response(&[], 1)),
+            (RpcRequest::GetTokenAccountsByOwner, token_accounts_by_owner_response(&[], 1)),
+            (RpcRequest::GetTokenAccountsByOwner, token_accounts_by_owner_response(&[], 1)),
+            (RpcRequest::GetTokenAccountsByOwner, token_accounts_by_owner_response(&[], 1)),
+            (RpcRequest::GetBalance, balance_response(1_000_000)),
+            (RpcRequest::GetBalance, balance_response(1_000_000)),
+            (RpcRequest::GetBalance, balance_response(0)),
+            (RpcRequest::GetFeeForMessage, json!({ "context": { "slot": 1 }, "value": 5_000 })),
+        ]);
+
+        let report = rotate_wallet(&client, &old_keypair, &new_wallet.to_string()).unwrap();
+        assert!(report.signatures.len() == 1);
+        assert!(report.lamports_transferred == 995_000);
+        assert!(report.migrated_token_accounts.is_empty());
+        assert!(report.is_complete());
+    }
+
+    #[test]
+    fn test_plan_rotation_to_same_wallet() {
+        let client = mock_client(vec![]);
+        let wallet = Pubkey::new_unique().to_string();
+        assert!(matches!(plan_wallet_rotation(&client, &wallet, &wallet), Err(KeyRotationError::SameWallet)));
+    }
+}
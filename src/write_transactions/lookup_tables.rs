@@ -0,0 +1,205 @@
+//! # Lookup Tables
+//!
+//! Versioned (v0) transactions for the `TransactionBuilder`. Accounts stored in address lookup
+//! tables are referenced by a one byte index instead of their 32 byte address, so transactions
+//! touching many accounts, e.g swaps through several pools, fit within the transaction size limit.
+
+use solana_address_lookup_table_interface::instruction::{create_lookup_table, extend_lookup_table};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    message::{v0, AddressLookupTableAccount, VersionedMessage},
+    pubkey::Pubkey,
+    signer::Signer,
+    transaction::VersionedTransaction
+};
+use crate::{
+    error::{ReadTransactionError, TransactionBuilderError},
+    read_transactions::zero_copy::AddressLookupTableView,
+    utils::address_to_pubkey
+};
+use super::transaction_builder::TransactionBuilder;
+
+/// Fetches address lookup tables with their addresses, in the order of `lookup_tables`.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::AccountNotFound` if a table does not exist, and
+/// `ReadTransactionError::DeserializeError` if an account is not a lookup table.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_sdk::pubkey::Pubkey;
+/// use easy_solana::{create_rpc_client, write_transactions::lookup_tables::get_lookup_table_accounts};
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let lookup_tables = get_lookup_table_accounts(&client, &[Pubkey::new_unique()]).unwrap();
+/// ```
+pub fn get_lookup_table_accounts(client: &RpcClient, lookup_tables: &[Pubkey]) -> Result<Vec<AddressLookupTableAccount>, ReadTransactionError> {
+    if lookup_tables.is_empty() {
+        return Ok(Vec::new());
+    }
+    let accounts = client.get_multiple_accounts(lookup_tables)?;
+    lookup_tables
+        .iter()
+        .zip(accounts)
+        .map(|(key, account)| {
+            let account = account.ok_or(ReadTransactionError::AccountNotFound)?;
+            let lookup_table = AddressLookupTableView::try_from_account_data(&account.data)?;
+            Ok(AddressLookupTableAccount { key: *key, addresses: lookup_table.addresses().to_vec() })
+        })
+        .collect()
+}
+
+impl TransactionBuilder<'_> {
+    /// Uses an address lookup table in the transactions built with `build_versioned`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::InvalidAddress` if the address is invalid.
+    pub fn add_lookup_table(&mut self, lookup_table_address: &str) -> Result<&mut Self, TransactionBuilderError> {
+        let lookup_table = address_to_pubkey(lookup_table_address)?;
+        if !self.lookup_tables.contains(&lookup_table) {
+            self.lookup_tables.push(lookup_table);
+        }
+        Ok(self)
+    }
+
+    /// Builds and signs a v0 transaction using the lookup tables of the builder, after checking it
+    /// against the policy of the builder.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::QueryError` if the lookup tables can not be fetched,
+    /// `TransactionBuilderError::LatestBlockhashError` if the blockhash can not be fetched, and
+    /// `TransactionBuilderError::InstructionError` if the message can not be compiled or signed.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use easy_solana::{create_rpc_client, SecretKeypair, TransactionBuilder};
+    ///
+    /// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+    /// let keypair = SecretKeypair::from_env("PRIVATE_KEY_1").unwrap();
+    /// let mut builder = TransactionBuilder::new(&client, keypair.keypair());
+    /// builder.transfer_sol(0.01, keypair.keypair(), "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5").unwrap();
+    /// builder.add_lookup_table("LOOKUP_TABLE_ADDRESS").unwrap();
+    /// let transaction = builder.build_versioned().unwrap();
+    /// ```
+    pub fn build_versioned(&self) -> Result<VersionedTransaction, TransactionBuilderError> {
+        let lookup_tables = get_lookup_table_accounts(self.client, &self.lookup_tables)?;
+        let recent_blockhash = self.client.get_latest_blockhash().map_err(|_| TransactionBuilderError::LatestBlockhashError)?;
+        self.build_versioned_with_blockhash(recent_blockhash, &lookup_tables)
+    }
+
+    /// Builds and signs a v0 transaction with a blockhash and lookup tables fetched beforehand,
+    /// after checking it against the policy of the builder. The lookup tables of the builder are ignored.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::PolicyViolation` if the instructions break the policy.
+    /// Returns `TransactionBuilderError::InstructionError` if the message can not be compiled or signed.
+    pub fn build_versioned_with_blockhash(
+        &self,
+        recent_blockhash: Hash,
+        lookup_tables: &[AddressLookupTableAccount]
+    ) -> Result<VersionedTransaction, TransactionBuilderError> {
+        self.check_policy()?;
+        let message = v0::Message::try_compile(&self.fee_payer_pubkey(), &self.instructions, lookup_tables, recent_blockhash)
+            .map_err(|err| TransactionBuilderError::InstructionError(err.to_string()))?;
+        let required_signers = &message.account_keys[..message.header.num_required_signatures as usize];
+        let keypairs = self.keypairs_for_signers(required_signers);
+        VersionedTransaction::try_new(VersionedMessage::V0(message), &keypairs)
+            .map_err(|err| TransactionBuilderError::InstructionError(err.to_string()))
+    }
+
+    /// Adds the creation of an address lookup table with the payer keypair as its authority, and
+    /// returns the address of the table. The table can be extended once the transaction is
+    /// confirmed, and used a slot after it was extended.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::RpcClientError` if the current slot can not be fetched.
+    pub fn create_lookup_table(&mut self) -> Result<Pubkey, TransactionBuilderError> {
+        // The slot has to be recent, which finalized slots are for a few minutes
+        let recent_slot = self.client.get_slot_with_commitment(CommitmentConfig::finalized())?;
+        let (instruction, lookup_table) = create_lookup_table(self.payer_keypair.pubkey(), self.fee_payer_pubkey(), recent_slot);
+        self.instructions.push(instruction);
+        Ok(lookup_table)
+    }
+
+    /// Adds `addresses` to a lookup table of the payer keypair, the fee payer paying for the
+    /// extra space. About 20 addresses fit in a transaction, larger sets have to be added over
+    /// several transactions.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::InvalidAddress` if the table address is invalid.
+    pub fn extend_lookup_table(&mut self, lookup_table_address: &str, addresses: Vec<Pubkey>) -> Result<&mut Self, TransactionBuilderError> {
+        let lookup_table = address_to_pubkey(lookup_table_address)?;
+        let instruction = extend_lookup_table(lookup_table, self.payer_keypair.pubkey(), Some(self.fee_payer_pubkey()), addresses);
+        self.instructions.push(instruction);
+        Ok(self)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::{instruction::{AccountMeta, Instruction}, signer::keypair::Keypair};
+    use solana_system_interface::instruction::transfer;
+    use crate::test_utils::{account_with_data, mock_client, multiple_accounts_response};
+
+    fn lookup_table_data(addresses: &[Pubkey]) -> Vec<u8> {
+        let mut data = 1_u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&u64::MAX.to_le_bytes());
+        data.extend_from_slice(&[0; 44]);
+        for address in addresses {
+            data.extend_from_slice(address.as_ref());
+        }
+        data
+    }
+
+    #[test]
+    fn test_build_versioned_with_lookup_table() {
+        let (lookup_table, pool_accounts) = (Pubkey::new_unique(), [Pubkey::new_unique(), Pubkey::new_unique()]);
+        let client = mock_client(vec![
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(account_with_data(Pubkey::new_unique(), lookup_table_data(&pool_accounts)))], 1)),
+        ]);
+        let keypair = Keypair::new();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder.instructions.push(transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1));
+        builder.instructions.push(Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![AccountMeta::new(pool_accounts[0], false), AccountMeta::new_readonly(pool_accounts[1], false)]
+        ));
+        builder.add_lookup_table(&lookup_table.to_string()).unwrap();
+
+        let transaction = builder.build_versioned().unwrap();
+        let VersionedMessage::V0(message) = &transaction.message else {
+            panic!("not a v0 message");
+        };
+        assert!(message.address_table_lookups.len() == 1 && message.address_table_lookups[0].account_key == lookup_table);
+        assert!(!message.account_keys.contains(&pool_accounts[0]) && !message.account_keys.contains(&pool_accounts[1]));
+        assert!(transaction.verify_with_results().iter().all(|verified| *verified));
+    }
+
+    #[test]
+    fn test_create_and_extend_lookup_table() {
+        let client = mock_client(vec![]);
+        let keypair = Keypair::new();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        let lookup_table = builder.create_lookup_table().unwrap();
+        builder.extend_lookup_table(&lookup_table.to_string(), vec![Pubkey::new_unique()]).unwrap();
+        assert!(builder.instructions.len() == 2);
+        assert!(builder.instructions[0].accounts[0].pubkey == lookup_table && builder.instructions[1].accounts[0].pubkey == lookup_table);
+
+        let transaction = builder.build_with_blockhash(Hash::default()).unwrap();
+        assert!(transaction.verify().is_ok());
+    }
+}
@@ -1,5 +1,24 @@
+pub mod compute_limit;
 pub mod create_token_account;
+pub mod dedupe;
+pub mod fee;
+pub mod jito;
+pub mod key_rotation;
+pub mod lookup_tables;
+pub mod multi_hop;
 pub mod delete_token_account;
+pub mod orchestrator;
+pub mod otc_swap;
+pub mod policy;
+pub mod preview;
+pub mod priority_fees;
+pub mod relay;
+pub mod send;
+pub mod spending_limits;
+pub mod template;
+pub mod transfer_checks;
 pub mod transfer_sol;
 pub mod utils;
+pub mod withdrawals;
+pub mod wrap_sol;
 pub mod transaction_builder;
\ No newline at end of file
@@ -0,0 +1,364 @@
+//! # Multi-hop Swaps
+//!
+//! Swaps between two tokens without a shared pool, routed through SOL: the first leg sells token
+//! A for SOL and the second leg buys token B with it, both in the same transaction. Each leg
+//! trades on the pump.fun bonding curve or a Raydium CPMM pool paired with WSOL, and SOL is
+//! wrapped or unwrapped between the legs as the venues require.
+//!
+//! The second leg spends the minimum SOL of the first leg, so it never spends more than the
+//! first leg received. SOL received above the minimum stays with the payer.
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use crate::{
+    constants::{solana_programs::{sol_pubkey, token_program}, units::BASIS_POINTS_DIVISOR},
+    error::{ReadTransactionError, TransactionBuilderError},
+    pumpfun::{
+        bonding_curve::get_bonding_curve_account,
+        curve_math::{mul_div_floor, to_u64},
+        global::{get_global_account, GlobalAccount},
+        instructions::{pump_buy_instruction, pump_sell_instruction},
+        quote::{quote_buy, quote_sell}
+    },
+    raydium::cpmm::{get_cpmm_reserves, CpmmPoolKeys}
+};
+use super::transaction_builder::TransactionBuilder;
+
+/// Venue of one leg of a multi-hop swap, trading a token against SOL.
+///
+/// - PumpFun: The bonding curve of the mint.
+/// - RaydiumCpmm: A Raydium CPMM pool pairing the token with WSOL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SwapLeg {
+    PumpFun(Pubkey),
+    RaydiumCpmm(Box<CpmmPoolKeys>),
+}
+
+impl SwapLeg {
+    /// Mint traded against SOL on this leg.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `ReadTransactionError::QuoteError` if the pool is not paired with WSOL.
+    pub fn mint(&self) -> Result<Pubkey, ReadTransactionError> {
+        match self {
+            SwapLeg::PumpFun(mint) => Ok(*mint),
+            SwapLeg::RaydiumCpmm(pool) if pool.token_0_mint == sol_pubkey() => Ok(pool.token_1_mint),
+            SwapLeg::RaydiumCpmm(pool) if pool.token_1_mint == sol_pubkey() => Ok(pool.token_0_mint),
+            SwapLeg::RaydiumCpmm(pool) => Err(ReadTransactionError::QuoteError(format!("Pool {} is not paired with WSOL", pool.pool_state))),
+        }
+    }
+}
+
+/// Quote of a swap through SOL, with amounts in lamports or raw token units.
+///
+/// ### Fields
+///
+/// - `amount_in`: Tokens sold on the first leg.
+/// - `sol_amount`: Expected SOL received from the first leg, after fees.
+/// - `minimum_sol_amount`: SOL received from the first leg at its slippage, which the second
+///   leg spends.
+/// - `token_amount`: Expected tokens bought on the second leg.
+/// - `minimum_token_amount`: Tokens bought on the second leg at its slippage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MultiHopQuote {
+    pub amount_in: u64,
+    pub sol_amount: u64,
+    pub minimum_sol_amount: u64,
+    pub token_amount: u64,
+    pub minimum_token_amount: u64,
+}
+
+/// Quotes selling `amount_in` raw tokens on `sell` and buying on `buy` with the SOL received,
+/// lowering the output of each leg by its slippage.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::QuoteError` if a slippage exceeds 10000 basis points or a pool
+/// is not paired with WSOL, `ReadTransactionError::BondingCurveError` if a token has no active
+/// bonding curve, and `ReadTransactionError::RpcError` if the accounts can not be fetched.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_sdk::pubkey::Pubkey;
+/// use easy_solana::{create_rpc_client, write_transactions::multi_hop::{quote_swap_through_sol, SwapLeg}};
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let sell = SwapLeg::PumpFun(Pubkey::from_str_const("CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump"));
+/// let buy = SwapLeg::PumpFun(Pubkey::from_str_const("2zMMhcVQEXDtdE6vsFS7S7D5oUodfJHE8vd1gnBouauv"));
+/// let quote = quote_swap_through_sol(&client, &sell, &buy, 1_000_000_000, 100, 100).unwrap();
+/// println!("Receiving at least {} tokens", quote.minimum_token_amount);
+/// ```
+pub fn quote_swap_through_sol(
+    client: &RpcClient,
+    sell: &SwapLeg,
+    buy: &SwapLeg,
+    amount_in: u64,
+    sell_slippage_bps: u16,
+    buy_slippage_bps: u16
+) -> Result<MultiHopQuote, ReadTransactionError> {
+    quote_with_creators(client, sell, buy, amount_in, sell_slippage_bps, buy_slippage_bps).map(|(quote, _)| quote)
+}
+
+/// Quotes the swap like `quote_swap_through_sol`, also returning the creators of the bonding
+/// curves of the sell and buy legs, which the pump.fun instructions pay creator fees to.
+fn quote_with_creators(
+    client: &RpcClient,
+    sell: &SwapLeg,
+    buy: &SwapLeg,
+    amount_in: u64,
+    sell_slippage_bps: u16,
+    buy_slippage_bps: u16
+) -> Result<(MultiHopQuote, [Pubkey; 2]), ReadTransactionError> {
+    for slippage_bps in [sell_slippage_bps, buy_slippage_bps] {
+        if slippage_bps as u128 > BASIS_POINTS_DIVISOR {
+            return Err(ReadTransactionError::QuoteError(format!("Slippage of {} basis points exceeds 100%", slippage_bps)));
+        }
+    }
+    // Pools not paired with WSOL are rejected before fetching any account
+    let sell_mint = sell.mint()?;
+    buy.mint()?;
+    let global_state = match (sell, buy) {
+        (SwapLeg::PumpFun(_), _) | (_, SwapLeg::PumpFun(_)) => Some(get_global_account(client)?),
+        _ => None,
+    };
+
+    let (sol_amount, sell_creator) = quote_leg(client, sell, global_state.as_ref(), &sell_mint, amount_in)?;
+    let minimum_sol_amount = apply_slippage(sol_amount, sell_slippage_bps)?;
+    let (token_amount, buy_creator) = quote_leg(client, buy, global_state.as_ref(), &sol_pubkey(), minimum_sol_amount)?;
+    let quote = MultiHopQuote {
+        amount_in,
+        sol_amount,
+        minimum_sol_amount,
+        token_amount,
+        minimum_token_amount: apply_slippage(token_amount, buy_slippage_bps)?,
+    };
+    Ok((quote, [sell_creator, buy_creator]))
+}
+
+/// Output of swapping `amount_in` of `input_mint` on `leg`, SOL for `sol_pubkey`, and the creator
+/// of the bonding curve on pump.fun legs.
+fn quote_leg(
+    client: &RpcClient,
+    leg: &SwapLeg,
+    global_state: Option<&GlobalAccount>,
+    input_mint: &Pubkey,
+    amount_in: u64
+) -> Result<(u64, Pubkey), ReadTransactionError> {
+    match (leg, global_state) {
+        (SwapLeg::PumpFun(mint), Some(global_state)) => {
+            let (_, curve_state) = get_bonding_curve_account(client, &mint.to_string()).ok_or(ReadTransactionError::BondingCurveError)?;
+            let amount_out = match *input_mint == sol_pubkey() {
+                true => quote_buy(&curve_state, global_state, amount_in)?.token_amount,
+                false => quote_sell(&curve_state, global_state, amount_in)?.total_sol,
+            };
+            Ok((amount_out, curve_state.creator.unwrap_or_default()))
+        }
+        (SwapLeg::RaydiumCpmm(pool), _) => {
+            let amount_out = get_cpmm_reserves(client, pool)?.quote_swap_base_input(pool, input_mint, amount_in)?;
+            Ok((amount_out, Pubkey::default()))
+        }
+        (SwapLeg::PumpFun(_), None) => Err(ReadTransactionError::QuoteError("Global account not fetched".to_string())),
+    }
+}
+
+fn apply_slippage(amount: u64, slippage_bps: u16) -> Result<u64, ReadTransactionError> {
+    let amount = mul_div_floor(amount as u128, BASIS_POINTS_DIVISOR - slippage_bps as u128, BASIS_POINTS_DIVISOR, "minimum amount")?;
+    Ok(to_u64(amount, "minimum amount")?)
+}
+
+impl TransactionBuilder<'_> {
+    /// Adds a swap of `amount_in` raw tokens through SOL for the payer keypair: the tokens are
+    /// sold on `sell` and the minimum SOL of that leg buys tokens on `buy`, each leg failing if
+    /// its output falls below the quote lowered by its slippage. The associated token account of
+    /// the bought token is created if it does not exist.
+    ///
+    /// Raydium legs trade WSOL, which is wrapped from or unwrapped to SOL between the legs, and
+    /// the WSOL associated token account of the payer is closed at the end. WSOL held in it
+    /// before the swap is unwrapped as well. Both legs are in the same transaction, use lookup
+    /// tables with `build_versioned` if the accounts of two Raydium pools do not fit. The fee
+    /// config of the builder, if any, is charged on the minimum SOL routed between the legs.
+    ///
+    /// Returns the quote the swap was sized from.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::InstructionError` if the swap can not be quoted and
+    /// `TransactionBuilderError::QueryError` if the accounts can not be fetched.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use solana_sdk::pubkey::Pubkey;
+    /// use easy_solana::{
+    ///     constants::{raydium_accounts::raydium_cpmm_program, solana_programs::{sol_pubkey, token_program}},
+    ///     create_rpc_client,
+    ///     raydium::cpmm::CpmmPoolKeys,
+    ///     write_transactions::multi_hop::SwapLeg,
+    ///     SecretKeypair,
+    ///     TransactionBuilder
+    /// };
+    ///
+    /// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+    /// let keypair = SecretKeypair::from_env("PRIVATE_KEY_1").unwrap();
+    /// let sell = SwapLeg::PumpFun(Pubkey::from_str_const("CzAdDkkbRJnPYYjuwZ8T6tUxtD2ouCpZMXkJD7Rhpump"));
+    /// let migrated_mint = Pubkey::from_str_const("2zMMhcVQEXDtdE6vsFS7S7D5oUodfJHE8vd1gnBouauv");
+    /// let pool = CpmmPoolKeys::derive(&raydium_cpmm_program(), 0, (migrated_mint, token_program()), (sol_pubkey(), token_program()));
+    /// let buy = SwapLeg::RaydiumCpmm(Box::new(pool));
+    ///
+    /// let mut builder = TransactionBuilder::new(&client, keypair.keypair());
+    /// builder.set_compute_limit(300_000);
+    /// let quote = builder.swap_through_sol(&sell, &buy, 1_000_000_000, 100, 200).unwrap();
+    /// let transaction = builder.build().unwrap();
+    /// ```
+    pub fn swap_through_sol(
+        &mut self,
+        sell: &SwapLeg,
+        buy: &SwapLeg,
+        amount_in: u64,
+        sell_slippage_bps: u16,
+        buy_slippage_bps: u16
+    ) -> Result<MultiHopQuote, TransactionBuilderError> {
+        let quote_error = |err: ReadTransactionError| match err {
+            ReadTransactionError::RpcError(_) | ReadTransactionError::RpcForUserError(_) => TransactionBuilderError::QueryError(err),
+            err => TransactionBuilderError::InstructionError(err.to_string()),
+        };
+        let (quote, [sell_creator, buy_creator]) = quote_with_creators(self.client, sell, buy, amount_in, sell_slippage_bps, buy_slippage_bps)
+            .map_err(quote_error)?;
+        let sell_mint = sell.mint().map_err(quote_error)?;
+        let payer = self.payer_keypair.pubkey();
+
+        match sell {
+            SwapLeg::PumpFun(mint) => {
+                self.instructions.push(pump_sell_instruction(&payer, mint, &sell_creator, amount_in, quote.minimum_sol_amount));
+            }
+            SwapLeg::RaydiumCpmm(pool) => {
+                self.raydium_cpmm_swap(pool, &sell_mint, amount_in, quote.minimum_sol_amount)?;
+            }
+        }
+        match buy {
+            SwapLeg::PumpFun(mint) => {
+                if let SwapLeg::RaydiumCpmm(_) = sell {
+                    self.unwrap_sol()?;
+                }
+                self.instructions.push(create_associated_token_account_idempotent(
+                    &self.fee_payer_pubkey(),
+                    &payer,
+                    mint,
+                    &token_program(),
+                ));
+                self.instructions.push(pump_buy_instruction(&payer, mint, &buy_creator, quote.minimum_token_amount, quote.minimum_sol_amount));
+            }
+            SwapLeg::RaydiumCpmm(pool) => {
+                if let SwapLeg::PumpFun(_) = sell {
+                    self.wrap_sol(quote.minimum_sol_amount)?;
+                }
+                self.raydium_cpmm_swap(pool, &sol_pubkey(), quote.minimum_sol_amount, quote.minimum_token_amount)?;
+                // Returns the WSOL received above the minimum and the rent of the account
+                self.unwrap_sol()?;
+            }
+        }
+        self.apply_fee(quote.minimum_sol_amount);
+        Ok(quote)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::{account::Account, program_pack::Pack, signer::keypair::Keypair};
+    use spl_associated_token_account::get_associated_token_address;
+    use crate::{
+        constants::{
+            pumpfun_accounts::pumpfun_program,
+            raydium_accounts::raydium_cpmm_program
+        },
+        test_utils::{account_info_response, account_with_data, fresh_bonding_curve, global_account_data, mock_client, multiple_accounts_response}
+    };
+
+    fn curve_account() -> Account {
+        account_with_data(pumpfun_program(), fresh_bonding_curve().to_account_data())
+    }
+
+    fn global_account() -> Account {
+        account_with_data(pumpfun_program(), global_account_data())
+    }
+
+    fn cpmm_accounts(pool: &CpmmPoolKeys, token_0_amount: u64, token_1_amount: u64) -> Vec<Option<Account>> {
+        let mut amm_config = vec![0; 20];
+        amm_config[12..20].copy_from_slice(&2_500_u64.to_le_bytes());
+        let vault = |mint: Pubkey, amount: u64| {
+            let mut data = vec![0; spl_token::state::Account::LEN];
+            spl_token::state::Account { mint, owner: pool.authority, amount, state: spl_token::state::AccountState::Initialized, ..Default::default() }
+                .pack_into_slice(&mut data);
+            Some(account_with_data(token_program(), data))
+        };
+        vec![
+            Some(account_with_data(raydium_cpmm_program(), vec![0; 637])),
+            Some(account_with_data(raydium_cpmm_program(), amm_config)),
+            vault(pool.token_0_mint, token_0_amount),
+            vault(pool.token_1_mint, token_1_amount),
+        ]
+    }
+
+    #[test]
+    fn test_swap_from_pump_curve_to_cpmm_pool() {
+        let (pump_mint, raydium_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pool = CpmmPoolKeys::derive(&raydium_cpmm_program(), 0, (raydium_mint, token_program()), (sol_pubkey(), token_program()));
+        let (token_0_amount, token_1_amount) = match pool.token_0_mint == sol_pubkey() {
+            true => (100_000_000_000, 1_000_000_000_000),
+            false => (1_000_000_000_000, 100_000_000_000),
+        };
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&global_account()), 1)),
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&curve_account()), 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&cpmm_accounts(&pool, token_0_amount, token_1_amount), 1)),
+        ]);
+        let keypair = Keypair::new();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        let quote = builder.swap_through_sol(&SwapLeg::PumpFun(pump_mint), &SwapLeg::RaydiumCpmm(Box::new(pool)), 10_000_000_000, 100, 200).unwrap();
+
+        assert!(quote.minimum_sol_amount == quote.sol_amount * 9_900 / 10_000);
+        assert!(quote.minimum_token_amount == quote.token_amount * 9_800 / 10_000);
+        // sell, wrap (create, transfer, sync), create output account, swap, unwrap
+        assert!(builder.instructions.len() == 7);
+        assert!(builder.instructions[0].data[16..24] == quote.minimum_sol_amount.to_le_bytes());
+        let wsol_account = get_associated_token_address(&keypair.pubkey(), &sol_pubkey());
+        assert!(builder.instructions[2].accounts[1].pubkey == wsol_account);
+        assert!(builder.instructions[5].data[8..16] == quote.minimum_sol_amount.to_le_bytes());
+        assert!(builder.instructions[5].data[16..24] == quote.minimum_token_amount.to_le_bytes());
+        assert!(builder.instructions[6].accounts[0].pubkey == wsol_account);
+    }
+
+    #[test]
+    fn test_swap_between_pump_curves() {
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&global_account()), 1)),
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&curve_account()), 1)),
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&curve_account()), 1)),
+        ]);
+        let keypair = Keypair::new();
+        let (sell_mint, buy_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        let quote = builder.swap_through_sol(&SwapLeg::PumpFun(sell_mint), &SwapLeg::PumpFun(buy_mint), 10_000_000_000, 0, 0).unwrap();
+
+        // native SOL is passed between the legs without wrapping
+        assert!(builder.instructions.len() == 3);
+        assert!(builder.instructions[2].data[8..16] == quote.token_amount.to_le_bytes());
+        assert!(builder.instructions[2].data[16..24] == quote.sol_amount.to_le_bytes());
+    }
+
+    #[test]
+    fn failing_test_swap_through_pool_without_wsol() {
+        let client = mock_client(vec![]);
+        let pool = CpmmPoolKeys::derive(&raydium_cpmm_program(), 0, (Pubkey::new_unique(), token_program()), (Pubkey::new_unique(), token_program()));
+        let keypair = Keypair::new();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        let result = builder.swap_through_sol(&SwapLeg::RaydiumCpmm(Box::new(pool)), &SwapLeg::PumpFun(Pubkey::new_unique()), 1_000, 100, 100);
+        assert!(matches!(result, Err(TransactionBuilderError::InstructionError(_))));
+        assert!(builder.instructions.is_empty());
+    }
+}
@@ -0,0 +1,351 @@
+use std::time::Duration;
+use log::{info, warn};
+use solana_client::rpc_client::RpcClient;
+use serde::Serialize;
+use solana_sdk::{signature::Signature, transaction::Transaction};
+
+use crate::{
+    constants::jito_accounts::MAX_BUNDLE_TRANSACTIONS,
+    error::{OrchestratorError, WriteTransactionError},
+    json::{serialize_display, ToJson},
+    utils::sol_to_lamports
+};
+use super::{
+    jito::send_and_confirm_bundle,
+    transaction_builder::TransactionBuilder,
+    utils::{send_and_confirm_transaction, simulate_transaction}
+};
+
+/// Hook called on a confirmed step when a later step fails, used to undo its effects
+/// (e.g closing an associated token account that was created by an earlier step).
+pub type RollbackHook<'a> = Box<dyn Fn(&RpcClient, &Signature) -> Result<(), WriteTransactionError> + 'a>;
+
+/// A single transaction within an `Orchestrator`.
+///
+/// ### Fields
+///
+/// - `name`: Unique name of the step, used by other steps to declare dependencies.
+/// - `builder`: The transaction to be built, simulated and sent.
+/// - `depends_on`: Names of steps that must be confirmed before this step is simulated.
+/// - `rollback`: Optional hook to undo this step if a later step fails.
+pub struct OrchestratorStep<'a> {
+    pub name: String,
+    pub builder: TransactionBuilder<'a>,
+    pub depends_on: Vec<String>,
+    pub rollback: Option<RollbackHook<'a>>,
+}
+
+/// Result of a confirmed step. `units_consumed` is `None` for bundled steps that could not be
+/// simulated ahead of the bundle because they depend on other steps.
+#[derive(Debug, Serialize)]
+pub struct StepResult {
+    pub name: String,
+    #[serde(serialize_with = "serialize_display")]
+    pub signature: Signature,
+    pub units_consumed: Option<u32>,
+}
+
+impl ToJson for StepResult {}
+
+/// Sends a list of dependent transactions in order, e.g create ATA → buy → transfer.
+///
+/// Each step is simulated right before it is sent, after all of its dependencies are confirmed,
+/// so the simulation runs against the state the previous steps produced. A step is only sent
+/// if its simulation succeeds, and the orchestrator waits for confirmation before moving on.
+/// If any step fails, the rollback hooks of the already confirmed steps are called in reverse order.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use dotenv::dotenv;
+/// use std::env;
+/// use solana_sdk::signer::keypair::Keypair;
+/// use easy_solana::create_rpc_client;
+/// use easy_solana::write_transactions::{
+///     orchestrator::Orchestrator,
+///     transaction_builder::TransactionBuilder
+/// };
+/// use easy_solana::constants::solana_programs::token_program;
+///
+/// const WALLET_ADDRESS_1: &str = "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5";
+/// const USDC_TOKEN_ADDRESS: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+///
+/// dotenv().ok();
+/// let private_key_string = env::var("PRIVATE_KEY_2").unwrap();
+/// let private_key = Keypair::from_base58_string(&private_key_string);
+/// let client = create_rpc_client("RPC_URL");
+///
+/// let mut create_token_account = TransactionBuilder::new(&client, &private_key);
+/// create_token_account.create_associated_token_account_for_payer(USDC_TOKEN_ADDRESS, token_program()).unwrap();
+/// let mut transfer = TransactionBuilder::new(&client, &private_key);
+/// transfer.transfer_sol(0.001, &private_key, WALLET_ADDRESS_1).unwrap();
+///
+/// let mut orchestrator = Orchestrator::new(&client);
+/// orchestrator
+///     .add_step("create_token_account", create_token_account, &[])
+///     .add_step("transfer", transfer, &["create_token_account"]);
+/// let results = orchestrator.execute();
+/// ```
+pub struct Orchestrator<'a> {
+    pub client: &'a RpcClient,
+    pub steps: Vec<OrchestratorStep<'a>>,
+}
+
+impl<'a> Orchestrator<'a> {
+    pub fn new(client: &'a RpcClient) -> Self {
+        Self {
+            client,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Adds a step that is only sent after all the steps named in `depends_on` are confirmed.
+    pub fn add_step(&mut self, name: &str, builder: TransactionBuilder<'a>, depends_on: &[&str]) -> &mut Self {
+        self.steps.push(OrchestratorStep {
+            name: name.to_string(),
+            builder,
+            depends_on: depends_on.iter().map(|dependency| dependency.to_string()).collect(),
+            rollback: None,
+        });
+        self
+    }
+
+    /// Sets the rollback hook of the step with the given name. Unknown step names are ignored.
+    pub fn on_rollback(
+        &mut self,
+        name: &str,
+        hook: impl Fn(&RpcClient, &Signature) -> Result<(), WriteTransactionError> + 'a
+    ) -> &mut Self {
+        if let Some(step) = self.steps.iter_mut().find(|step| step.name == name) {
+            step.rollback = Some(Box::new(hook));
+        }
+        self
+    }
+
+    /// Simulates, sends and confirms every step in dependency order.
+    ///
+    /// ## Errors
+    ///
+    /// Unknown or circular dependencies are rejected before anything is sent. Otherwise the first step
+    /// that fails to build, simulate or send is returned as an error, after rollback hooks have run.
+    pub fn execute(&self) -> Result<Vec<StepResult>, OrchestratorError> {
+        let execution_order = self.execution_order()?;
+        let mut completed_steps: Vec<(usize, StepResult)> = Vec::new();
+
+        for step_index in execution_order {
+            match self.execute_step(&self.steps[step_index]) {
+                Ok(step_result) => {
+                    info!("Orchestrator: step {} confirmed with signature {}", step_result.name, step_result.signature);
+                    completed_steps.push((step_index, step_result));
+                }
+                Err(err) => {
+                    warn!("Orchestrator: {}, rolling back {} confirmed steps", err, completed_steps.len());
+                    self.rollback(&completed_steps);
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(completed_steps.into_iter().map(|(_, step_result)| step_result).collect())
+    }
+
+    fn execute_step(&self, step: &OrchestratorStep<'a>) -> Result<StepResult, OrchestratorError> {
+        let simulation_transaction = step.builder.build()
+            .map_err(|err| OrchestratorError::BuildError(step.name.clone(), err))?;
+        let simulation_result = simulate_transaction(self.client, simulation_transaction)
+            .map_err(|err| OrchestratorError::SimulationError(step.name.clone(), err))?;
+        if let Some(err) = simulation_result.error {
+            return Err(OrchestratorError::SimulationFailed(step.name.clone(), err));
+        }
+
+        // Rebuild to sign with a fresh blockhash after simulation
+        let transaction = step.builder.build()
+            .map_err(|err| OrchestratorError::BuildError(step.name.clone(), err))?;
+        let confirmed_transaction = send_and_confirm_transaction(self.client, transaction)
+            .map_err(|err| OrchestratorError::SendError(step.name.clone(), err))?;
+        if let Some(err) = confirmed_transaction.err {
+            return Err(OrchestratorError::SendError(step.name.clone(), WriteTransactionError::TransactionFailed(err)));
+        }
+        let signature = confirmed_transaction.signature;
+
+        Ok(StepResult {
+            name: step.name.clone(),
+            signature,
+            units_consumed: Some(simulation_result.units_consumed),
+        })
+    }
+
+    /// Sends every step as a single Jito bundle with a tip of `tip_amount` SOL, rounded to the
+    /// nearest lamport. See `execute_bundle_lamports`.
+    pub async fn execute_bundle(
+        &self,
+        block_engine_url: &str,
+        tip_amount: f64,
+        timeout: Duration
+    ) -> Result<Vec<StepResult>, OrchestratorError> {
+        self.execute_bundle_lamports(block_engine_url, sol_to_lamports(tip_amount), timeout).await
+    }
+
+    /// Sends every step as a single Jito bundle so that either all steps land or none do,
+    /// e.g snipe + protect or create + seed liquidity flows. At most 5 steps can be bundled.
+    ///
+    /// A tip of `tip_lamports` to a Jito tip account is added to the transaction of the last step,
+    /// leaving its builder unchanged so the bundle can be retried. Steps without dependencies are
+    /// simulated before sending, dependent steps cannot be simulated until the steps before them
+    /// land. Rollback hooks are not used, as a failed bundle leaves no state behind.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `OrchestratorError::TooManyBundleTransactions` if there are more than 5 steps, or the
+    /// first step that fails to build or simulate. Bundles that fail or time out return a `BundleError`.
+    pub async fn execute_bundle_lamports(
+        &self,
+        block_engine_url: &str,
+        tip_lamports: u64,
+        timeout: Duration
+    ) -> Result<Vec<StepResult>, OrchestratorError> {
+        if self.steps.len() > MAX_BUNDLE_TRANSACTIONS {
+            return Err(OrchestratorError::TooManyBundleTransactions(MAX_BUNDLE_TRANSACTIONS));
+        }
+        let execution_order = self.execution_order()?;
+        let last_step_index = execution_order.last().copied();
+
+        let mut transactions: Vec<Transaction> = Vec::with_capacity(execution_order.len());
+        let mut step_results: Vec<StepResult> = Vec::with_capacity(execution_order.len());
+        for step_index in execution_order {
+            let step = &self.steps[step_index];
+            let transaction = match Some(step_index) == last_step_index {
+                // The tip is added to a copy of the builder, so a retried bundle does not tip twice
+                true => {
+                    let mut builder = step.builder.clone();
+                    builder.tip_jito_lamports(tip_lamports).and_then(|builder| builder.build())
+                }
+                false => step.builder.build(),
+            }.map_err(|err| OrchestratorError::BuildError(step.name.clone(), err))?;
+
+            let mut units_consumed = None;
+            if step.depends_on.is_empty() {
+                let simulation_result = simulate_transaction(self.client, transaction.clone())
+                    .map_err(|err| OrchestratorError::SimulationError(step.name.clone(), err))?;
+                if let Some(err) = simulation_result.error {
+                    return Err(OrchestratorError::SimulationFailed(step.name.clone(), err));
+                }
+                units_consumed = Some(simulation_result.units_consumed);
+            }
+
+            step_results.push(StepResult {
+                name: step.name.clone(),
+                signature: transaction.signatures[0],
+                units_consumed,
+            });
+            transactions.push(transaction);
+        }
+
+        let bundle_status = send_and_confirm_bundle(block_engine_url, &transactions, timeout).await?;
+        info!("Orchestrator: bundle {} landed in slot {}", bundle_status.bundle_id, bundle_status.slot);
+
+        Ok(step_results)
+    }
+
+    fn rollback(&self, completed_steps: &[(usize, StepResult)]) {
+        for (step_index, step_result) in completed_steps.iter().rev() {
+            if let Some(rollback) = &self.steps[*step_index].rollback {
+                if let Err(err) = rollback(self.client, &step_result.signature) {
+                    warn!("Orchestrator: rollback of step {} failed: {}", step_result.name, err);
+                }
+            }
+        }
+    }
+
+    /// Orders steps so that each step comes after its dependencies, keeping the
+    /// insertion order between independent steps.
+    fn execution_order(&self) -> Result<Vec<usize>, OrchestratorError> {
+        for (index, step) in self.steps.iter().enumerate() {
+            if self.steps[..index].iter().any(|other_step| other_step.name == step.name) {
+                return Err(OrchestratorError::DuplicateStep(step.name.clone()));
+            }
+            for dependency in &step.depends_on {
+                if !self.steps.iter().any(|other_step| &other_step.name == dependency) {
+                    return Err(OrchestratorError::UnknownDependency(step.name.clone(), dependency.clone()));
+                }
+            }
+        }
+
+        let mut order: Vec<usize> = Vec::with_capacity(self.steps.len());
+        while order.len() < self.steps.len() {
+            let next_step = (0..self.steps.len()).find(|index| {
+                !order.contains(index) && self.steps[*index].depends_on.iter().all(|dependency| {
+                    order.iter().any(|ordered_index| &self.steps[*ordered_index].name == dependency)
+                })
+            });
+            match next_step {
+                Some(index) => order.push(index),
+                None => return Err(OrchestratorError::CircularDependency),
+            }
+        }
+
+        Ok(order)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{pubkey::Pubkey, signer::{keypair::Keypair, Signer}};
+    use solana_system_interface::instruction::transfer;
+    use crate::{test_utils::mock_client, utils::create_rpc_client};
+
+    #[test]
+    fn test_execution_order_follows_dependencies() {
+        let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+        let keypair = Keypair::new();
+        let mut orchestrator = Orchestrator::new(&client);
+        orchestrator
+            .add_step("transfer", TransactionBuilder::new(&client, &keypair), &["buy"])
+            .add_step("buy", TransactionBuilder::new(&client, &keypair), &["create_token_account"])
+            .add_step("create_token_account", TransactionBuilder::new(&client, &keypair), &[]);
+
+        let execution_order = orchestrator.execution_order().unwrap();
+        assert!(execution_order == vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn failing_test_execution_order_with_circular_dependency() {
+        let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+        let keypair = Keypair::new();
+        let mut orchestrator = Orchestrator::new(&client);
+        orchestrator
+            .add_step("buy", TransactionBuilder::new(&client, &keypair), &["sell"])
+            .add_step("sell", TransactionBuilder::new(&client, &keypair), &["buy"]);
+
+        assert!(matches!(orchestrator.execution_order(), Err(OrchestratorError::CircularDependency)));
+    }
+
+    #[test]
+    fn failing_test_execution_order_with_unknown_dependency() {
+        let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+        let keypair = Keypair::new();
+        let mut orchestrator = Orchestrator::new(&client);
+        orchestrator.add_step("buy", TransactionBuilder::new(&client, &keypair), &["create_token_account"]);
+
+        assert!(matches!(orchestrator.execution_order(), Err(OrchestratorError::UnknownDependency(_, _))));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_retried_bundle_tips_once() {
+        let client = mock_client(vec![]);
+        let keypair = Keypair::new();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder.instructions.push(transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1_000));
+        let mut orchestrator = Orchestrator::new(&client);
+        orchestrator.add_step("transfer", builder, &[]);
+
+        // no block engine listens on the port, so each attempt fails after building the bundle
+        for _ in 0..2 {
+            let result = orchestrator.execute_bundle_lamports("http://127.0.0.1:9", 1_000, Duration::from_secs(1)).await;
+            assert!(result.is_err());
+            assert!(orchestrator.steps[0].builder.instructions.len() == 1);
+        }
+    }
+}
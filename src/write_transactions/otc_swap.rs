@@ -0,0 +1,284 @@
+//! # OTC Swap
+//!
+//! Atomic swaps between two wallets without an intermediary. The maker builds one transaction
+//! moving both sides of the trade, signs it partially and sends the resulting `SwapProposal`
+//! to the taker as JSON. The taker checks that the transaction matches the agreed terms exactly
+//! before adding their signature and sending it, so either both transfers happen or neither.
+//!
+//! The maker pays the fee and the rent of missing token accounts. The proposal uses a recent
+//! blockhash, so the taker has to accept it within about a minute.
+
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signer::{keypair::Keypair, Signer},
+    transaction::Transaction
+};
+use solana_system_interface::instruction::transfer;
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent
+};
+use spl_token_2022::instruction::transfer_checked;
+use crate::{
+    error::OtcSwapError,
+    json::ToJson,
+    utils::address_to_pubkey
+};
+use super::{
+    relay::{deserialize_transaction, serialize_transaction},
+    utils::{send_and_confirm_transaction, ConfirmedTransaction}
+};
+
+/// Asset one party gives in a swap.
+///
+/// - Sol: `lamports` of SOL.
+/// - Token: `amount` raw units of `mint`, owned by `token_program`, moved between associated token accounts.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", content = "details", rename_all = "snake_case")]
+pub enum SwapAsset {
+    Sol { lamports: u64 },
+    Token { mint: String, token_program: String, amount: u64, decimals: u8 },
+}
+
+/// Terms of a swap both parties agree on before the maker proposes it.
+///
+/// ### Fields
+///
+/// - `maker`: Wallet proposing the swap and paying the fee.
+/// - `taker`: Wallet accepting the swap.
+/// - `maker_gives`: Asset sent from the maker to the taker.
+/// - `taker_gives`: Asset sent from the taker to the maker.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct OtcSwapTerms {
+    pub maker: String,
+    pub taker: String,
+    pub maker_gives: SwapAsset,
+    pub taker_gives: SwapAsset,
+}
+
+impl OtcSwapTerms {
+    /// Instructions moving both assets, with the maker paying for missing token accounts.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `OtcSwapError::InvalidAddress` if an address of the terms is invalid.
+    pub fn instructions(&self) -> Result<Vec<Instruction>, OtcSwapError> {
+        let maker = address_to_pubkey(&self.maker)?;
+        let taker = address_to_pubkey(&self.taker)?;
+        let mut instructions = asset_instructions(&self.maker_gives, &maker, &taker, &maker)?;
+        instructions.extend(asset_instructions(&self.taker_gives, &taker, &maker, &maker)?);
+        Ok(instructions)
+    }
+
+    /// Unsigned transaction of the swap with the maker as fee payer.
+    fn transaction(&self, recent_blockhash: Hash) -> Result<Transaction, OtcSwapError> {
+        let maker = address_to_pubkey(&self.maker)?;
+        let mut transaction = Transaction::new_with_payer(&self.instructions()?, Some(&maker));
+        transaction.message.recent_blockhash = recent_blockhash;
+        Ok(transaction)
+    }
+}
+
+/// Instructions sending `asset` from `from` to `to`.
+fn asset_instructions(asset: &SwapAsset, from: &Pubkey, to: &Pubkey, payer: &Pubkey) -> Result<Vec<Instruction>, OtcSwapError> {
+    match asset {
+        SwapAsset::Sol { lamports } => Ok(vec![transfer(from, to, *lamports)]),
+        SwapAsset::Token { mint, token_program, amount, decimals } => {
+            let mint = address_to_pubkey(mint)?;
+            let token_program = address_to_pubkey(token_program)?;
+            let source = get_associated_token_address_with_program_id(from, &mint, &token_program);
+            let destination = get_associated_token_address_with_program_id(to, &mint, &token_program);
+            let transfer_instruction = transfer_checked(&token_program, &source, &mint, &destination, from, &[], *amount, *decimals)
+                .map_err(|err| OtcSwapError::InstructionError(err.to_string()))?;
+            Ok(vec![
+                create_associated_token_account_idempotent(payer, to, &mint, &token_program),
+                transfer_instruction,
+            ])
+        }
+    }
+}
+
+/// Swap transaction signed by the maker, passed to the taker as JSON.
+///
+/// ### Fields
+///
+/// - `terms`: Terms the transaction was built from.
+/// - `transaction`: Base64 encoded transaction, signed by the maker only.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SwapProposal {
+    pub terms: OtcSwapTerms,
+    pub transaction: String,
+}
+
+impl ToJson for SwapProposal {}
+
+impl SwapProposal {
+    /// Parses a proposal received as JSON.
+    pub fn from_json_str(json: &str) -> Result<Self, OtcSwapError> {
+        serde_json::from_str(json).map_err(|_| OtcSwapError::SerializeError)
+    }
+}
+
+/// Builds the swap transaction of `terms` and signs it as maker.
+///
+/// ## Errors
+///
+/// Returns `OtcSwapError::WrongSigner` if `maker_keypair` is not the maker of the terms.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{
+///     create_rpc_client,
+///     json::ToJson,
+///     write_transactions::otc_swap::{accept_and_send_swap, propose_swap, OtcSwapTerms, SwapAsset, SwapProposal},
+///     SecretKeypair
+/// };
+///
+/// let client = create_rpc_client("RPC_URL");
+/// let terms = OtcSwapTerms {
+///     maker: "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5".to_string(),
+///     taker: "BCTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5".to_string(),
+///     maker_gives: SwapAsset::Token {
+///         mint: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
+///         token_program: "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA".to_string(),
+///         amount: 100_000_000,
+///         decimals: 6,
+///     },
+///     taker_gives: SwapAsset::Sol { lamports: 500_000_000 },
+/// };
+///
+/// // maker side
+/// let maker_keypair = SecretKeypair::from_env("PRIVATE_KEY_1").unwrap();
+/// let proposal = propose_swap(&terms, &maker_keypair, client.get_latest_blockhash().unwrap()).unwrap();
+/// let json = proposal.to_json_string();
+///
+/// // taker side, with the terms agreed on beforehand
+/// let taker_keypair = SecretKeypair::from_env("PRIVATE_KEY_2").unwrap();
+/// let proposal = SwapProposal::from_json_str(&json).unwrap();
+/// let confirmed_transaction = accept_and_send_swap(&client, &proposal, &terms, &taker_keypair).unwrap();
+/// ```
+pub fn propose_swap(terms: &OtcSwapTerms, maker_keypair: &Keypair, recent_blockhash: Hash) -> Result<SwapProposal, OtcSwapError> {
+    if maker_keypair.pubkey() != address_to_pubkey(&terms.maker)? {
+        return Err(OtcSwapError::WrongSigner(maker_keypair.pubkey()));
+    }
+    let mut transaction = terms.transaction(recent_blockhash)?;
+    transaction
+        .try_partial_sign(&[maker_keypair], recent_blockhash)
+        .map_err(|err| OtcSwapError::InstructionError(err.to_string()))?;
+    Ok(SwapProposal {
+        terms: terms.clone(),
+        transaction: serialize_transaction(&transaction).map_err(|_| OtcSwapError::SerializeError)?,
+    })
+}
+
+/// Verifies a proposal against the terms the taker agreed to and signs it as taker, returning
+/// the fully signed transaction. The terms are compared with the expected terms and the
+/// transaction with one rebuilt from them, so a maker can not add or change instructions.
+///
+/// ## Errors
+///
+/// - `OtcSwapError::TermsMismatch` if the proposal is for other terms.
+/// - `OtcSwapError::TransactionMismatch` if the transaction does not execute the terms.
+/// - `OtcSwapError::InvalidSignature` if the transaction is not signed by the maker.
+/// - `OtcSwapError::WrongSigner` if `taker_keypair` is not the taker of the terms.
+pub fn accept_swap(proposal: &SwapProposal, expected_terms: &OtcSwapTerms, taker_keypair: &Keypair) -> Result<Transaction, OtcSwapError> {
+    if proposal.terms != *expected_terms {
+        return Err(OtcSwapError::TermsMismatch);
+    }
+    if taker_keypair.pubkey() != address_to_pubkey(&expected_terms.taker)? {
+        return Err(OtcSwapError::WrongSigner(taker_keypair.pubkey()));
+    }
+    let mut transaction = deserialize_transaction(&proposal.transaction).map_err(|_| OtcSwapError::SerializeError)?;
+    let recent_blockhash = transaction.message.recent_blockhash;
+    if transaction.message != expected_terms.transaction(recent_blockhash)?.message {
+        return Err(OtcSwapError::TransactionMismatch);
+    }
+    // index 0 is the maker as fee payer
+    if !transaction.verify_with_results().first().copied().unwrap_or(false) {
+        return Err(OtcSwapError::InvalidSignature);
+    }
+
+    transaction
+        .try_partial_sign(&[taker_keypair], recent_blockhash)
+        .map_err(|err| OtcSwapError::InstructionError(err.to_string()))?;
+    Ok(transaction)
+}
+
+/// Verifies, signs and sends a proposal as taker. See `accept_swap`.
+///
+/// ## Errors
+///
+/// Returns `OtcSwapError::TransactionFailed` if the swap landed but failed.
+pub fn accept_and_send_swap(
+    client: &RpcClient,
+    proposal: &SwapProposal,
+    expected_terms: &OtcSwapTerms,
+    taker_keypair: &Keypair
+) -> Result<ConfirmedTransaction, OtcSwapError> {
+    let transaction = accept_swap(proposal, expected_terms, taker_keypair)?;
+    send_and_confirm_transaction(client, transaction)?.into_result(OtcSwapError::TransactionFailed)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{constants::solana_programs::token_program, test_utils::mock_client};
+
+    fn terms(maker: &Keypair, taker: &Keypair) -> OtcSwapTerms {
+        OtcSwapTerms {
+            maker: maker.pubkey().to_string(),
+            taker: taker.pubkey().to_string(),
+            maker_gives: SwapAsset::Token {
+                mint: Pubkey::new_unique().to_string(),
+                token_program: token_program().to_string(),
+                amount: 1_000,
+                decimals: 6,
+            },
+            taker_gives: SwapAsset::Sol { lamports: 5_000 },
+        }
+    }
+
+    #[test]
+    fn test_swap_round_trip() {
+        let client = mock_client(vec![]);
+        let (maker, taker) = (Keypair::new(), Keypair::new());
+        let terms = terms(&maker, &taker);
+
+        let json = propose_swap(&terms, &maker, Hash::new_unique()).unwrap().to_json_string();
+        let proposal = SwapProposal::from_json_str(&json).unwrap();
+        let transaction = accept_swap(&proposal, &terms, &taker).unwrap();
+        assert!(transaction.verify().is_ok());
+        assert!(transaction.message.instructions.len() == 3);
+
+        let confirmed_transaction = accept_and_send_swap(&client, &proposal, &terms, &taker).unwrap();
+        assert!(confirmed_transaction.signature == transaction.signatures[0]);
+    }
+
+    #[test]
+    fn failing_test_accept_tampered_swap() {
+        let (maker, taker) = (Keypair::new(), Keypair::new());
+        let terms = terms(&maker, &taker);
+        let proposal = propose_swap(&terms, &maker, Hash::new_unique()).unwrap();
+
+        // the taker agreed to receive more
+        let mut expected_terms = terms.clone();
+        expected_terms.taker_gives = SwapAsset::Sol { lamports: 1 };
+        assert!(matches!(accept_swap(&proposal, &expected_terms, &taker), Err(OtcSwapError::TermsMismatch)));
+
+        // the transaction does not match the terms of the proposal
+        let mut cheaper_terms = terms.clone();
+        cheaper_terms.maker_gives = SwapAsset::Sol { lamports: 1 };
+        let mut tampered_proposal = propose_swap(&cheaper_terms, &maker, Hash::new_unique()).unwrap();
+        tampered_proposal.terms = terms.clone();
+        assert!(matches!(accept_swap(&tampered_proposal, &terms, &taker), Err(OtcSwapError::TransactionMismatch)));
+
+        assert!(matches!(accept_swap(&proposal, &terms, &maker), Err(OtcSwapError::WrongSigner(_))));
+        assert!(matches!(propose_swap(&terms, &taker, Hash::new_unique()), Err(OtcSwapError::WrongSigner(_))));
+    }
+}
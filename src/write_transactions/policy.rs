@@ -0,0 +1,468 @@
+//! # Policy
+//!
+//! This module contains a configurable policy for outbound transactions. Policies are checked
+//! before a transaction is signed or sent, giving automated strategies a hard limit on what
+//! a bug or compromised code can do with the wallet.
+
+use std::collections::HashMap;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    transaction::Transaction
+};
+use solana_system_interface::instruction::SystemInstruction;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token::instruction::TokenInstruction;
+use spl_token_2022::{
+    extension::StateWithExtensions,
+    instruction::TokenInstruction as Token2022Instruction,
+    state::Account as Token2022Account
+};
+use crate::{
+    constants::{
+        pumpfun_accounts::{buy_instruction_data, pumpfun_program, sell_instruction_data},
+        solana_programs::compute_budget_program
+    },
+    error::{PolicyError, TransactionBuilderError},
+    read_transactions::instruction_decoder::{decode_instruction, DecodedInstruction}
+};
+use super::transaction_builder::TransactionBuilder;
+
+/// Index of the mint in a pump.fun sell instruction.
+const PUMP_SELL_MINT_INDEX: usize = 2;
+/// Index of the seller's token account in a pump.fun sell instruction.
+const PUMP_SELL_TOKEN_ACCOUNT_INDEX: usize = 5;
+
+/// Limits applied to outbound transactions, `None` fields are not checked.
+///
+/// ### Fields
+///
+/// - `max_lamports_per_transaction`: Maximum lamports sent, created accounts funded or spent on pump.fun buys.
+/// - `allowed_programs`: Programs the transaction may call, the compute budget program is always allowed.
+/// - `allowed_destinations`: Wallets that may receive SOL, tokens, authorities, delegations or the
+///   rent of closed accounts. Token transfers may also go to the associated token accounts of these
+///   wallets. Instructions that can not be decoded are rejected if they have a signer.
+/// - `max_token_sell_bps`: Maximum share of a token balance sold on pump.fun in one transaction, in basis points.
+#[derive(Debug, Clone, Default)]
+pub struct TransactionPolicy {
+    pub max_lamports_per_transaction: Option<u64>,
+    pub allowed_programs: Option<Vec<Pubkey>>,
+    pub allowed_destinations: Option<Vec<Pubkey>>,
+    pub max_token_sell_bps: Option<u16>,
+}
+
+impl TransactionPolicy {
+    /// Checks the limits that can be verified from the instructions alone.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the `PolicyError` of the first limit the instructions exceed.
+    pub fn evaluate_instructions(&self, instructions: &[Instruction]) -> Result<(), PolicyError> {
+        let mut lamports_spent: u64 = 0;
+        for instruction in instructions {
+            if let Some(allowed_programs) = &self.allowed_programs {
+                if instruction.program_id != compute_budget_program() && !allowed_programs.contains(&instruction.program_id) {
+                    return Err(PolicyError::ProgramNotAllowed(instruction.program_id));
+                }
+            }
+
+            let Some(outflow) = outflow_of_instruction(instruction) else {
+                // Instructions that can not be decoded may move funds of the signers anywhere
+                if self.allowed_destinations.is_some() && instruction.accounts.iter().any(|account| account.is_signer) {
+                    return Err(PolicyError::UncheckedInstruction(instruction.program_id));
+                }
+                continue;
+            };
+            lamports_spent = lamports_spent.saturating_add(outflow.lamports);
+            if let (Some(destination), Some(allowed_destinations)) = (outflow.destination, &self.allowed_destinations) {
+                if !is_allowed_destination(&destination, outflow.mint.as_ref(), &instruction.program_id, allowed_destinations) {
+                    return Err(PolicyError::DestinationNotAllowed(destination));
+                }
+            }
+        }
+
+        match self.max_lamports_per_transaction {
+            Some(limit) if lamports_spent > limit => Err(PolicyError::SolLimitExceeded { lamports: lamports_spent, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks every limit of the policy, fetching the token balances sold on pump.fun. Sells of
+    /// the same mint are added up, so splitting a sale over several instructions does not get
+    /// around the sell limit.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the `PolicyError` of the first limit the instructions exceed.
+    pub fn evaluate(&self, client: &RpcClient, instructions: &[Instruction]) -> Result<(), PolicyError> {
+        self.evaluate_instructions(instructions)?;
+
+        let Some(max_token_sell_bps) = self.max_token_sell_bps else {
+            return Ok(());
+        };
+        let mut sells: HashMap<Pubkey, (u64, Vec<Pubkey>)> = HashMap::new();
+        for (mint, token_account, amount) in instructions.iter().filter_map(pump_sell_of_instruction) {
+            let (sold, token_accounts) = sells.entry(mint).or_default();
+            *sold = sold.saturating_add(amount);
+            if !token_accounts.contains(&token_account) {
+                token_accounts.push(token_account);
+            }
+        }
+
+        for (amount, token_accounts) in sells.into_values() {
+            let mut balance: u64 = 0;
+            for token_account in token_accounts {
+                let account = client.get_account(&token_account)?;
+                let account_balance = StateWithExtensions::<Token2022Account>::unpack(&account.data)
+                    .map_err(|_| PolicyError::InvalidTokenAccount(token_account))?
+                    .base
+                    .amount;
+                balance = balance.saturating_add(account_balance);
+            }
+            if amount as u128 * 10_000 > balance as u128 * max_token_sell_bps as u128 {
+                return Err(PolicyError::SellLimitExceeded { amount, balance });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks every limit of the policy against a signed legacy transaction before it is sent.
+    pub fn evaluate_transaction(&self, client: &RpcClient, transaction: &Transaction) -> Result<(), PolicyError> {
+        self.evaluate(client, &instructions_of_transaction(transaction))
+    }
+}
+
+impl TransactionBuilder<'_> {
+    /// Sets the policy checked by `build`.
+    pub fn with_policy(&mut self, policy: TransactionPolicy) -> &mut Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Checks the instructions against the policy of the builder, if any.
+    pub fn check_policy(&self) -> Result<(), TransactionBuilderError> {
+        if let Some(policy) = &self.policy {
+            policy.evaluate(self.client, &self.instructions)?;
+        }
+        Ok(())
+    }
+}
+
+/// Lamports spent by an instruction, and the destination and mint of the SOL or tokens it transfers.
+/// Authorities, delegates and new owners given control of an account are destinations too.
+#[derive(Default)]
+struct Outflow {
+    lamports: u64,
+    destination: Option<Pubkey>,
+    mint: Option<Pubkey>,
+}
+
+/// Outflow of an instruction, `None` if the instruction can not be decoded.
+// Unchecked Token 2022 transfers and approvals are deprecated but still executed by the program
+#[allow(deprecated)]
+fn outflow_of_instruction(instruction: &Instruction) -> Option<Outflow> {
+    let account = |index: usize| instruction.accounts.get(index).map(|account| account.pubkey);
+    let to = |destination: Option<Pubkey>| Outflow { destination, ..Outflow::default() };
+    if instruction.program_id == pumpfun_program() && instruction.data.starts_with(&buy_instruction_data()) {
+        let max_sol_cost = instruction.data
+            .get(16..24)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_le_bytes)
+            .unwrap_or(u64::MAX);
+        return Some(Outflow { lamports: max_sol_cost, ..Outflow::default() });
+    }
+
+    let outflow = match decode_instruction(&instruction.program_id, &instruction.data) {
+        DecodedInstruction::System(SystemInstruction::Transfer { lamports }) => {
+            Outflow { lamports, destination: account(1), mint: None }
+        }
+        DecodedInstruction::System(SystemInstruction::TransferWithSeed { lamports, .. }) => {
+            Outflow { lamports, destination: account(2), mint: None }
+        }
+        DecodedInstruction::System(SystemInstruction::WithdrawNonceAccount(lamports)) => {
+            Outflow { lamports, destination: account(1), mint: None }
+        }
+        DecodedInstruction::System(SystemInstruction::CreateAccount { lamports, .. })
+        | DecodedInstruction::System(SystemInstruction::CreateAccountWithSeed { lamports, .. }) => {
+            Outflow { lamports, ..Outflow::default() }
+        }
+        DecodedInstruction::System(SystemInstruction::Assign { owner })
+        | DecodedInstruction::System(SystemInstruction::AssignWithSeed { owner, .. }) => to(Some(owner)),
+        DecodedInstruction::System(SystemInstruction::AuthorizeNonceAccount(new_authority)) => to(Some(new_authority)),
+        DecodedInstruction::Token(TokenInstruction::Transfer { .. })
+        | DecodedInstruction::Token2022(Token2022Instruction::Transfer { .. }) => {
+            Outflow { lamports: 0, destination: account(1), mint: None }
+        }
+        DecodedInstruction::Token(TokenInstruction::TransferChecked { .. })
+        | DecodedInstruction::Token2022(Token2022Instruction::TransferChecked { .. }) => {
+            Outflow { lamports: 0, destination: account(2), mint: account(1) }
+        }
+        DecodedInstruction::Token(TokenInstruction::Approve { .. })
+        | DecodedInstruction::Token2022(Token2022Instruction::Approve { .. })
+        | DecodedInstruction::Token(TokenInstruction::CloseAccount)
+        | DecodedInstruction::Token2022(Token2022Instruction::CloseAccount) => to(account(1)),
+        DecodedInstruction::Token(TokenInstruction::ApproveChecked { .. })
+        | DecodedInstruction::Token2022(Token2022Instruction::ApproveChecked { .. }) => to(account(2)),
+        DecodedInstruction::Token(TokenInstruction::SetAuthority { new_authority, .. })
+        | DecodedInstruction::Token2022(Token2022Instruction::SetAuthority { new_authority, .. }) => to(new_authority.into()),
+        DecodedInstruction::Unknown => return None,
+        _ => Outflow::default(),
+    };
+    Some(outflow)
+}
+
+/// Whether the destination is an allowed wallet, or the associated token account of an allowed
+/// wallet if the mint of the transfer is known.
+fn is_allowed_destination(destination: &Pubkey, mint: Option<&Pubkey>, token_program: &Pubkey, allowed_destinations: &[Pubkey]) -> bool {
+    if allowed_destinations.contains(destination) {
+        return true;
+    }
+    mint.is_some_and(|mint| {
+        allowed_destinations
+            .iter()
+            .any(|wallet| get_associated_token_address_with_program_id(wallet, mint, token_program) == *destination)
+    })
+}
+
+/// Mint, token account and amount sold by a pump.fun sell instruction.
+fn pump_sell_of_instruction(instruction: &Instruction) -> Option<(Pubkey, Pubkey, u64)> {
+    if instruction.program_id != pumpfun_program() || !instruction.data.starts_with(&sell_instruction_data()) {
+        return None;
+    }
+    let amount = u64::from_le_bytes(instruction.data.get(8..16)?.try_into().ok()?);
+    let mint = instruction.accounts.get(PUMP_SELL_MINT_INDEX)?.pubkey;
+    let token_account = instruction.accounts.get(PUMP_SELL_TOKEN_ACCOUNT_INDEX)?.pubkey;
+    Some((mint, token_account, amount))
+}
+
+/// Decompiles the instructions of a legacy transaction.
+pub(crate) fn instructions_of_transaction(transaction: &Transaction) -> Vec<Instruction> {
+    let message = &transaction.message;
+    message.instructions
+        .iter()
+        .filter_map(|compiled_instruction| {
+            let program_id = *message.account_keys.get(compiled_instruction.program_id_index as usize)?;
+            let accounts = compiled_instruction.accounts
+                .iter()
+                .map(|index| {
+                    let index = *index as usize;
+                    message.account_keys.get(index).map(|pubkey| AccountMeta {
+                        pubkey: *pubkey,
+                        is_signer: message.is_signer(index),
+                        is_writable: message.is_maybe_writable(index, None),
+                    })
+                })
+                .collect::<Option<Vec<AccountMeta>>>()?;
+            Some(Instruction { program_id, accounts, data: compiled_instruction.data.clone() })
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::{hash::Hash, program_option::COption, program_pack::Pack, signer::{keypair::Keypair, Signer}};
+    use spl_token::{instruction::AuthorityType, state::{Account as SplTokenAccount, AccountState}};
+    use crate::{
+        constants::solana_programs::{system_program, token_program},
+        test_utils::{account_info_response, account_with_data, mock_client}
+    };
+
+    fn pump_sell_instruction(mint: Pubkey, token_account: Pubkey, amount: u64) -> Instruction {
+        let mut accounts = vec![AccountMeta::new_readonly(Pubkey::new_unique(), false); 12];
+        accounts[PUMP_SELL_MINT_INDEX] = AccountMeta::new_readonly(mint, false);
+        accounts[PUMP_SELL_TOKEN_ACCOUNT_INDEX] = AccountMeta::new(token_account, false);
+        let mut data = sell_instruction_data();
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.extend_from_slice(&0_u64.to_le_bytes());
+        Instruction { program_id: pumpfun_program(), accounts, data }
+    }
+
+    #[test]
+    fn test_evaluate_sol_limit_and_destinations() {
+        let (wallet, allowed_wallet) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let policy = TransactionPolicy {
+            max_lamports_per_transaction: Some(1_000),
+            allowed_programs: Some(vec![system_program(), token_program()]),
+            allowed_destinations: Some(vec![allowed_wallet]),
+            ..TransactionPolicy::default()
+        };
+
+        let transfer = solana_system_interface::instruction::transfer(&wallet, &allowed_wallet, 600);
+        assert!(policy.evaluate_instructions(std::slice::from_ref(&transfer)).is_ok());
+        assert!(matches!(
+            policy.evaluate_instructions(&[transfer.clone(), transfer]),
+            Err(PolicyError::SolLimitExceeded { lamports: 1_200, limit: 1_000 })
+        ));
+
+        let other_wallet = Pubkey::new_unique();
+        let transfer = solana_system_interface::instruction::transfer(&wallet, &other_wallet, 1);
+        assert!(matches!(policy.evaluate_instructions(&[transfer]), Err(PolicyError::DestinationNotAllowed(destination)) if destination == other_wallet));
+
+        // token transfers to the associated token account of an allowed wallet
+        let mint = Pubkey::new_unique();
+        let source = get_associated_token_address_with_program_id(&wallet, &mint, &token_program());
+        let destination = get_associated_token_address_with_program_id(&allowed_wallet, &mint, &token_program());
+        let token_transfer = spl_token::instruction::transfer_checked(&token_program(), &source, &mint, &destination, &wallet, &[], 10, 6).unwrap();
+        assert!(policy.evaluate_instructions(&[token_transfer]).is_ok());
+
+        let unknown_program = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+        assert!(matches!(policy.evaluate_instructions(&[unknown_program]), Err(PolicyError::ProgramNotAllowed(_))));
+    }
+
+    fn destination_policy(allowed_wallet: Pubkey) -> TransactionPolicy {
+        TransactionPolicy { allowed_destinations: Some(vec![allowed_wallet]), ..TransactionPolicy::default() }
+    }
+
+    /// Asserts `instruction` is rejected for handing control to `destination`.
+    fn assert_destination_rejected(instruction: Instruction, destination: Pubkey) {
+        let result = destination_policy(Pubkey::new_unique()).evaluate_instructions(&[instruction]);
+        assert!(matches!(result, Err(PolicyError::DestinationNotAllowed(rejected)) if rejected == destination));
+    }
+
+    #[test]
+    fn failing_test_set_authority_to_other_wallet() {
+        let (token_account, wallet, attacker) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = spl_token::instruction::set_authority(
+            &token_program(), &token_account, Some(&attacker), AuthorityType::AccountOwner, &wallet, &[]
+        ).unwrap();
+        assert_destination_rejected(instruction, attacker);
+    }
+
+    #[test]
+    fn failing_test_approve_other_delegate() {
+        let (token_account, wallet, attacker) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = spl_token::instruction::approve(&token_program(), &token_account, &attacker, &wallet, &[], u64::MAX).unwrap();
+        assert_destination_rejected(instruction, attacker);
+    }
+
+    #[test]
+    fn failing_test_approve_checked_other_delegate() {
+        let (token_account, mint, wallet, attacker) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = spl_token::instruction::approve_checked(&token_program(), &token_account, &mint, &attacker, &wallet, &[], u64::MAX, 6).unwrap();
+        assert_destination_rejected(instruction, attacker);
+    }
+
+    #[test]
+    fn failing_test_close_account_to_other_wallet() {
+        let (token_account, wallet, attacker) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = spl_token::instruction::close_account(&token_program(), &token_account, &attacker, &wallet, &[]).unwrap();
+        assert_destination_rejected(instruction, attacker);
+
+        // closing to an allowed wallet is fine
+        let instruction = spl_token::instruction::close_account(&token_program(), &token_account, &wallet, &wallet, &[]).unwrap();
+        assert!(destination_policy(wallet).evaluate_instructions(&[instruction]).is_ok());
+    }
+
+    #[test]
+    fn failing_test_assign_to_other_program() {
+        let (wallet, attacker_program) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = solana_system_interface::instruction::assign(&wallet, &attacker_program);
+        assert_destination_rejected(instruction, attacker_program);
+    }
+
+    #[test]
+    fn failing_test_withdraw_nonce_account_to_other_wallet() {
+        let (nonce_account, wallet, attacker) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = solana_system_interface::instruction::withdraw_nonce_account(&nonce_account, &wallet, &attacker, 1_000);
+        assert_destination_rejected(instruction.clone(), attacker);
+
+        let policy = TransactionPolicy { max_lamports_per_transaction: Some(500), ..TransactionPolicy::default() };
+        assert!(matches!(policy.evaluate_instructions(&[instruction]), Err(PolicyError::SolLimitExceeded { lamports: 1_000, .. })));
+    }
+
+    #[test]
+    fn failing_test_unknown_instruction_with_signer() {
+        let wallet = Pubkey::new_unique();
+        let program = Pubkey::new_unique();
+        let signed = Instruction::new_with_bytes(program, &[1, 2, 3], vec![AccountMeta::new(wallet, true)]);
+        assert!(matches!(
+            destination_policy(wallet).evaluate_instructions(std::slice::from_ref(&signed)),
+            Err(PolicyError::UncheckedInstruction(rejected)) if rejected == program
+        ));
+        // without an allow-list, or without a signer, the instruction is not checked
+        assert!(TransactionPolicy::default().evaluate_instructions(&[signed]).is_ok());
+        let unsigned = Instruction::new_with_bytes(program, &[1, 2, 3], vec![AccountMeta::new(wallet, false)]);
+        assert!(destination_policy(wallet).evaluate_instructions(&[unsigned]).is_ok());
+    }
+
+    #[test]
+    fn test_evaluate_token_sell_limit() {
+        let (mint, token_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = vec![0; SplTokenAccount::LEN];
+        SplTokenAccount::pack(SplTokenAccount {
+            mint,
+            owner: Pubkey::new_unique(),
+            amount: 1_000,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        }, &mut data).unwrap();
+        let response = account_info_response(Some(&account_with_data(token_program(), data)), 1);
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, response.clone()),
+            (RpcRequest::GetAccountInfo, response.clone()),
+            (RpcRequest::GetAccountInfo, response),
+        ]);
+        let policy = TransactionPolicy { max_token_sell_bps: Some(5_000), ..TransactionPolicy::default() };
+
+        assert!(policy.evaluate(&client, &[pump_sell_instruction(mint, token_account, 500)]).is_ok());
+        assert!(matches!(
+            policy.evaluate(&client, &[pump_sell_instruction(mint, token_account, 501)]),
+            Err(PolicyError::SellLimitExceeded { amount: 501, balance: 1_000 })
+        ));
+
+        // sells of the same mint split over several instructions add up
+        let split_sells = [pump_sell_instruction(mint, token_account, 300), pump_sell_instruction(mint, token_account, 300)];
+        assert!(matches!(
+            policy.evaluate(&client, &split_sells),
+            Err(PolicyError::SellLimitExceeded { amount: 600, balance: 1_000 })
+        ));
+    }
+
+    #[test]
+    fn failing_test_build_with_policy_violation() {
+        let client = mock_client(vec![(RpcRequest::GetLatestBlockhash, json!(null))]);
+        let keypair = Keypair::new();
+        let policy = TransactionPolicy { max_lamports_per_transaction: Some(0), ..TransactionPolicy::default() };
+
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder
+            .with_policy(policy)
+            .transfer_sol(0.001, &keypair, &Pubkey::new_unique().to_string())
+            .unwrap();
+        assert!(matches!(builder.build(), Err(TransactionBuilderError::PolicyViolation(PolicyError::SolLimitExceeded { .. }))));
+
+        let transaction = TransactionBuilder::new(&client, &keypair)
+            .transfer_sol(0.001, &keypair, &keypair.pubkey().to_string())
+            .unwrap()
+            .build_with_blockhash(Default::default())
+            .unwrap();
+        assert!(builder.policy.as_ref().unwrap().evaluate_transaction(&client, &transaction).is_err());
+    }
+
+    #[test]
+    fn failing_test_every_build_path_checks_policy() {
+        let client = mock_client(vec![]);
+        let (keypair, relayer) = (Keypair::new(), Keypair::new());
+        let policy = TransactionPolicy { max_lamports_per_transaction: Some(0), ..TransactionPolicy::default() };
+
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder
+            .with_policy(policy)
+            .transfer_sol(0.001, &keypair, &Pubkey::new_unique().to_string())
+            .unwrap();
+        let is_policy_violation = |result: Result<(), TransactionBuilderError>| {
+            matches!(result, Err(TransactionBuilderError::PolicyViolation(PolicyError::SolLimitExceeded { .. })))
+        };
+        assert!(is_policy_violation(builder.build_with_blockhash(Hash::default()).map(|_| ())));
+        assert!(is_policy_violation(builder.build_versioned_with_blockhash(Hash::default(), &[]).map(|_| ())));
+        assert!(is_policy_violation(builder.build_for_relayer(&relayer.pubkey(), Hash::default()).map(|_| ())));
+    }
+}
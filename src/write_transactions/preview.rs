@@ -0,0 +1,369 @@
+//! # Preview
+//!
+//! This module describes transactions in human-readable form, e.g for confirmation prompts in
+//! wallet UIs, by decoding their instructions. Addresses are never shortened, so users can
+//! compare them in full.
+
+use std::fmt;
+use serde::Serialize;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    transaction::Transaction
+};
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_system_interface::instruction::SystemInstruction;
+use spl_associated_token_account::instruction::AssociatedTokenAccountInstruction;
+use spl_token::instruction::TokenInstruction;
+use spl_token_2022::instruction::TokenInstruction as Token2022Instruction;
+use crate::{
+    json::{serialize_display, serialize_display_option, ToJson},
+    constants::pumpfun_accounts::{buy_instruction_data, pumpfun_program, sell_instruction_data, PUMP_TOKEN_DECIMALS},
+    read_transactions::instruction_decoder::{decode_instruction, DecodedInstruction}
+};
+use super::{policy::instructions_of_transaction, transaction_builder::TransactionBuilder};
+
+/// An action of a transaction, amounts are in lamports or raw token units.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", content = "details", rename_all = "snake_case")]
+pub enum PreviewAction {
+    TransferSol {
+        #[serde(serialize_with = "serialize_display")]
+        from: Pubkey,
+        #[serde(serialize_with = "serialize_display")]
+        to: Pubkey,
+        lamports: u64,
+    },
+    CreateAccount {
+        #[serde(serialize_with = "serialize_display")]
+        from: Pubkey,
+        #[serde(serialize_with = "serialize_display")]
+        new_account: Pubkey,
+        lamports: u64,
+        #[serde(serialize_with = "serialize_display")]
+        owner: Pubkey,
+    },
+    /// `mint` and `decimals` are only known for checked transfers.
+    TransferToken {
+        #[serde(serialize_with = "serialize_display")]
+        source: Pubkey,
+        #[serde(serialize_with = "serialize_display")]
+        destination: Pubkey,
+        #[serde(serialize_with = "serialize_display")]
+        authority: Pubkey,
+        #[serde(serialize_with = "serialize_display_option")]
+        mint: Option<Pubkey>,
+        amount: u64,
+        decimals: Option<u8>,
+    },
+    BurnToken {
+        #[serde(serialize_with = "serialize_display")]
+        account: Pubkey,
+        #[serde(serialize_with = "serialize_display")]
+        mint: Pubkey,
+        amount: u64,
+    },
+    CloseTokenAccount {
+        #[serde(serialize_with = "serialize_display")]
+        account: Pubkey,
+        #[serde(serialize_with = "serialize_display")]
+        destination: Pubkey,
+    },
+    CreateTokenAccount {
+        #[serde(serialize_with = "serialize_display")]
+        wallet: Pubkey,
+        #[serde(serialize_with = "serialize_display")]
+        mint: Pubkey,
+    },
+    PumpBuy {
+        #[serde(serialize_with = "serialize_display")]
+        mint: Pubkey,
+        token_amount: u64,
+        max_sol_cost: u64,
+    },
+    PumpSell {
+        #[serde(serialize_with = "serialize_display")]
+        mint: Pubkey,
+        token_amount: u64,
+        min_sol_output: u64,
+    },
+    SetComputeLimit(u32),
+    /// Priority fee in micro-lamports per compute unit.
+    SetPriorityFee(u64),
+    Unknown {
+        #[serde(serialize_with = "serialize_display")]
+        program_id: Pubkey,
+    },
+}
+
+impl fmt::Display for PreviewAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PreviewAction::TransferSol { to, lamports, .. } => write!(f, "Transfer {} SOL to {}", format_sol(*lamports), to),
+            PreviewAction::CreateAccount { new_account, lamports, owner, .. } => {
+                write!(f, "Create account {} owned by {} with {} SOL", new_account, owner, format_sol(*lamports))
+            }
+            PreviewAction::TransferToken { destination, mint: Some(mint), amount, decimals, .. } => {
+                write!(f, "Transfer {} of token {} to token account {}", format_amount(*amount, decimals.unwrap_or(0)), mint, destination)
+            }
+            PreviewAction::TransferToken { destination, amount, .. } => {
+                write!(f, "Transfer {} raw units of an unknown token to token account {}", format_amount(*amount, 0), destination)
+            }
+            PreviewAction::BurnToken { mint, amount, .. } => write!(f, "Burn {} raw units of token {}", format_amount(*amount, 0), mint),
+            PreviewAction::CloseTokenAccount { account, destination } => {
+                write!(f, "Close token account {} and return its rent to {}", account, destination)
+            }
+            PreviewAction::CreateTokenAccount { wallet, mint } => write!(f, "Create token account of {} for {}", mint, wallet),
+            PreviewAction::PumpBuy { mint, token_amount, max_sol_cost } => write!(
+                f,
+                "Buy ~{} {} for max {} SOL on pump.fun",
+                format_amount(*token_amount, PUMP_TOKEN_DECIMALS as u8), mint, format_sol(*max_sol_cost)
+            ),
+            PreviewAction::PumpSell { mint, token_amount, min_sol_output } => write!(
+                f,
+                "Sell {} {} for min {} SOL on pump.fun",
+                format_amount(*token_amount, PUMP_TOKEN_DECIMALS as u8), mint, format_sol(*min_sol_output)
+            ),
+            PreviewAction::SetComputeLimit(limit) => write!(f, "Set compute limit to {}", format_amount(*limit as u64, 0)),
+            PreviewAction::SetPriorityFee(micro_lamports) => {
+                write!(f, "Set priority fee to {} micro-lamports per compute unit", format_amount(*micro_lamports, 0))
+            }
+            PreviewAction::Unknown { program_id } => write!(f, "Call program {}", program_id),
+        }
+    }
+}
+
+/// Human-readable summary of a transaction.
+///
+/// ### Fields
+///
+/// - `fee_payer`: Account paying the transaction fee, `None` for bare instructions.
+/// - `actions`: Actions of the instructions, in order.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TransactionPreview {
+    #[serde(serialize_with = "serialize_display_option")]
+    pub fee_payer: Option<Pubkey>,
+    pub actions: Vec<PreviewAction>,
+}
+
+impl ToJson for TransactionPreview {}
+
+impl TransactionPreview {
+    /// Descriptions of the actions, leaving out compute budget settings.
+    pub fn lines(&self) -> Vec<String> {
+        self.actions
+            .iter()
+            .filter(|action| !matches!(action, PreviewAction::SetComputeLimit(_) | PreviewAction::SetPriorityFee(_)))
+            .map(PreviewAction::to_string)
+            .collect()
+    }
+
+    /// Whether the transaction calls programs that can not be described, which users should be warned about.
+    pub fn has_unknown_actions(&self) -> bool {
+        self.actions.iter().any(|action| matches!(action, PreviewAction::Unknown { .. }))
+    }
+}
+
+impl fmt::Display for TransactionPreview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(fee_payer) = self.fee_payer {
+            writeln!(f, "Fee payer: {}", fee_payer)?;
+        }
+        for action in &self.actions {
+            writeln!(f, "- {}", action)?;
+        }
+        Ok(())
+    }
+}
+
+/// Transactions, builders and instructions that can be previewed.
+pub trait Describe {
+    fn describe(&self) -> TransactionPreview;
+}
+
+impl Describe for [Instruction] {
+    fn describe(&self) -> TransactionPreview {
+        TransactionPreview { fee_payer: None, actions: self.iter().map(describe_instruction).collect() }
+    }
+}
+
+impl Describe for Transaction {
+    fn describe(&self) -> TransactionPreview {
+        let actions = instructions_of_transaction(self).iter().map(describe_instruction).collect();
+        TransactionPreview { fee_payer: self.message.account_keys.first().copied(), actions }
+    }
+}
+
+impl Describe for TransactionBuilder<'_> {
+    fn describe(&self) -> TransactionPreview {
+        let actions = self.instructions.iter().map(describe_instruction).collect();
+        TransactionPreview { fee_payer: Some(self.fee_payer_pubkey()), actions }
+    }
+}
+
+/// Describes a transaction, transaction builder or instructions.
+///
+/// ## Example
+///
+/// ```rust
+/// use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+/// use solana_system_interface::instruction::transfer;
+/// use easy_solana::write_transactions::preview::describe;
+///
+/// let (wallet, destination) = (Keypair::new(), Pubkey::new_unique());
+/// let preview = describe(&[transfer(&wallet.pubkey(), &destination, 500_000_000)][..]);
+/// assert!(preview.lines()[0] == format!("Transfer 0.5 SOL to {}", destination));
+/// ```
+pub fn describe<T: Describe + ?Sized>(tx_or_builder: &T) -> TransactionPreview {
+    tx_or_builder.describe()
+}
+
+#[allow(deprecated)]
+fn describe_instruction(instruction: &Instruction) -> PreviewAction {
+    let account = |index: usize| instruction.accounts.get(index).map(|account| account.pubkey).unwrap_or_default();
+    let unknown = PreviewAction::Unknown { program_id: instruction.program_id };
+
+    if instruction.program_id == pumpfun_program() {
+        let amounts = instruction.data
+            .get(8..24)
+            .map(|bytes| (u64::from_le_bytes(bytes[..8].try_into().unwrap()), u64::from_le_bytes(bytes[8..].try_into().unwrap())));
+        return match amounts {
+            Some((token_amount, max_sol_cost)) if instruction.data.starts_with(&buy_instruction_data()) => {
+                PreviewAction::PumpBuy { mint: account(2), token_amount, max_sol_cost }
+            }
+            Some((token_amount, min_sol_output)) if instruction.data.starts_with(&sell_instruction_data()) => {
+                PreviewAction::PumpSell { mint: account(2), token_amount, min_sol_output }
+            }
+            _ => unknown,
+        };
+    }
+
+    match decode_instruction(&instruction.program_id, &instruction.data) {
+        DecodedInstruction::System(SystemInstruction::Transfer { lamports }) => {
+            PreviewAction::TransferSol { from: account(0), to: account(1), lamports }
+        }
+        DecodedInstruction::System(SystemInstruction::TransferWithSeed { lamports, .. }) => {
+            PreviewAction::TransferSol { from: account(0), to: account(2), lamports }
+        }
+        DecodedInstruction::System(SystemInstruction::CreateAccount { lamports, owner, .. })
+        | DecodedInstruction::System(SystemInstruction::CreateAccountWithSeed { lamports, owner, .. }) => {
+            PreviewAction::CreateAccount { from: account(0), new_account: account(1), lamports, owner }
+        }
+        DecodedInstruction::Token(TokenInstruction::Transfer { amount })
+        | DecodedInstruction::Token2022(Token2022Instruction::Transfer { amount }) => PreviewAction::TransferToken {
+            source: account(0), destination: account(1), authority: account(2), mint: None, amount, decimals: None
+        },
+        DecodedInstruction::Token(TokenInstruction::TransferChecked { amount, decimals })
+        | DecodedInstruction::Token2022(Token2022Instruction::TransferChecked { amount, decimals }) => PreviewAction::TransferToken {
+            source: account(0), destination: account(2), authority: account(3), mint: Some(account(1)), amount, decimals: Some(decimals)
+        },
+        DecodedInstruction::Token(TokenInstruction::Burn { amount })
+        | DecodedInstruction::Token(TokenInstruction::BurnChecked { amount, .. })
+        | DecodedInstruction::Token2022(Token2022Instruction::Burn { amount })
+        | DecodedInstruction::Token2022(Token2022Instruction::BurnChecked { amount, .. }) => {
+            PreviewAction::BurnToken { account: account(0), mint: account(1), amount }
+        }
+        DecodedInstruction::Token(TokenInstruction::CloseAccount)
+        | DecodedInstruction::Token2022(Token2022Instruction::CloseAccount) => {
+            PreviewAction::CloseTokenAccount { account: account(0), destination: account(1) }
+        }
+        DecodedInstruction::AssociatedTokenAccount(AssociatedTokenAccountInstruction::Create)
+        | DecodedInstruction::AssociatedTokenAccount(AssociatedTokenAccountInstruction::CreateIdempotent) => {
+            PreviewAction::CreateTokenAccount { wallet: account(2), mint: account(3) }
+        }
+        DecodedInstruction::ComputeBudget(ComputeBudgetInstruction::SetComputeUnitLimit(limit)) => PreviewAction::SetComputeLimit(limit),
+        DecodedInstruction::ComputeBudget(ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports)) => {
+            PreviewAction::SetPriorityFee(micro_lamports)
+        }
+        _ => unknown,
+    }
+}
+
+fn format_sol(lamports: u64) -> String {
+    format_amount(lamports, LAMPORTS_PER_SOL.ilog10() as u8)
+}
+
+/// Formats a raw amount with its decimals and thousands separators, without trailing zeros.
+fn format_amount(amount: u64, decimals: u8) -> String {
+    let divisor = 10_u128.pow(decimals as u32);
+    let whole = (amount as u128 / divisor).to_string();
+    let mut grouped_whole = String::new();
+    for (index, digit) in whole.chars().enumerate() {
+        if index > 0 && (whole.len() - index).is_multiple_of(3) {
+            grouped_whole.push(',');
+        }
+        grouped_whole.push(digit);
+    }
+
+    let fraction = amount as u128 % divisor;
+    if fraction == 0 {
+        return grouped_whole;
+    }
+    let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+    format!("{}.{}", grouped_whole, fraction.trim_end_matches('0'))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{hash::Hash, signature::Keypair, signer::Signer};
+    use solana_system_interface::instruction::transfer;
+    use crate::{
+        constants::solana_programs::token_program,
+        pumpfun::instructions::pump_buy_instruction,
+        test_utils::mock_client
+    };
+
+    #[test]
+    fn test_format_amount() {
+        assert!(format_amount(0, 9) == "0");
+        assert!(format_amount(12_345_000_000, 6) == "12,345");
+        assert!(format_amount(1_234_567_890, 9) == "1.23456789");
+        assert!(format_amount(30_000_000, 9) == "0.03");
+        assert!(format_amount(999, 0) == "999");
+        assert!(format_amount(u64::MAX, 0) == "18,446,744,073,709,551,615");
+    }
+
+    #[test]
+    fn test_describe_builder() {
+        let client = mock_client(vec![]);
+        let keypair = Keypair::new();
+        let (destination, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder.set_compute_limit(200_000).transfer_sol(0.5, &keypair, &destination.to_string()).unwrap();
+        builder.instructions.push(pump_buy_instruction(&keypair.pubkey(), &mint, &Pubkey::new_unique(), 12_345_000_000, 30_000_000));
+        builder.instructions.push(spl_token::instruction::transfer_checked(
+            &token_program(), &Pubkey::new_unique(), &mint, &destination, &keypair.pubkey(), &[], 1_500_000, 6
+        ).unwrap());
+
+        let preview = describe(&builder);
+        assert!(preview.fee_payer == Some(keypair.pubkey()));
+        assert!(preview.actions[0] == PreviewAction::SetComputeLimit(200_000));
+        assert!(preview.lines() == vec![
+            format!("Transfer 0.5 SOL to {}", destination),
+            format!("Buy ~12,345 {} for max 0.03 SOL on pump.fun", mint),
+            format!("Transfer 1.5 of token {} to token account {}", mint, destination),
+        ]);
+        assert!(!preview.has_unknown_actions());
+
+        // signed transactions describe the same actions
+        let transaction = builder.build_with_blockhash(Hash::default()).unwrap();
+        assert!(describe(&transaction) == preview);
+    }
+
+    #[test]
+    fn test_describe_unknown_program() {
+        let program_id = Pubkey::new_unique();
+        let instructions = [
+            Instruction::new_with_bytes(program_id, &[1, 2, 3], vec![]),
+            transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1),
+        ];
+        let preview = describe(&instructions[..]);
+        assert!(preview.has_unknown_actions());
+        assert!(preview.lines()[0] == format!("Call program {}", program_id));
+        assert!(preview.fee_payer.is_none());
+    }
+}
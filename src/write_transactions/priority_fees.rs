@@ -0,0 +1,152 @@
+//! # Priority Fees
+//!
+//! Priority fee estimation from the prioritization fees paid in recent slots by transactions
+//! writing to the same accounts.
+
+use solana_client::{rpc_client::RpcClient, rpc_response::RpcPrioritizationFee};
+use solana_sdk::{compute_budget::ComputeBudgetInstruction, instruction::Instruction, pubkey::Pubkey};
+use crate::{
+    constants::solana_programs::compute_budget_program,
+    error::{ReadTransactionError, TransactionBuilderError}
+};
+use super::transaction_builder::TransactionBuilder;
+
+/// Returns the prioritization fees paid in recent slots by transactions writing to any of
+/// `accounts`, in micro-lamports per compute unit. Without accounts, the fees of all
+/// transactions are returned.
+///
+/// ## Errors
+///
+/// Returns `ReadTransactionError::RpcError` if the fees can not be fetched.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_sdk::pubkey::Pubkey;
+/// use easy_solana::{create_rpc_client, write_transactions::priority_fees::get_recent_priority_fees};
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let fees = get_recent_priority_fees(&client, &[Pubkey::new_unique()]).unwrap();
+/// ```
+pub fn get_recent_priority_fees(client: &RpcClient, accounts: &[Pubkey]) -> Result<Vec<RpcPrioritizationFee>, ReadTransactionError> {
+    Ok(client.get_recent_prioritization_fees(accounts)?)
+}
+
+/// Fee at `percentile` of `fees`, e.g `50` for the median, or 0 without fees.
+pub fn priority_fee_percentile(fees: &[RpcPrioritizationFee], percentile: u8) -> u64 {
+    let mut fees: Vec<u64> = fees.iter().map(|fee| fee.prioritization_fee).collect();
+    fees.sort_unstable();
+    let index = (fees.len() * percentile.min(100) as usize / 100).min(fees.len().saturating_sub(1));
+    fees.get(index).copied().unwrap_or(0)
+}
+
+/// Writable accounts of `instructions`, without duplicates.
+pub(crate) fn writable_accounts(instructions: &[Instruction]) -> Vec<Pubkey> {
+    let mut writable_accounts: Vec<Pubkey> = Vec::new();
+    for account in instructions.iter().flat_map(|instruction| &instruction.accounts) {
+        if account.is_writable && !writable_accounts.contains(&account.pubkey) {
+            writable_accounts.push(account.pubkey);
+        }
+    }
+    writable_accounts
+}
+
+/// Whether `instruction` and `other` are compute budget instructions of the same type, e.g both
+/// set the compute unit price.
+pub(crate) fn is_same_compute_budget_instruction(instruction: &Instruction, other: &Instruction) -> bool {
+    // The first byte of a compute budget instruction is its type
+    instruction.program_id == compute_budget_program()
+        && other.program_id == compute_budget_program()
+        && instruction.data.first() == other.data.first()
+}
+
+impl TransactionBuilder<'_> {
+    /// Sets the compute unit price to the `percentile` of the fees recently paid for the writable
+    /// accounts of the instructions added so far, so call it after adding them. A price set
+    /// before is replaced, and no price is set if recent transactions paid none.
+    ///
+    /// Returns the compute unit price in micro-lamports.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::InstructionError` if `percentile` is above 100, and
+    /// `TransactionBuilderError::QueryError` if the recent fees can not be fetched.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use easy_solana::{create_rpc_client, SecretKeypair, TransactionBuilder};
+    ///
+    /// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+    /// let keypair = SecretKeypair::from_env("PRIVATE_KEY_1").unwrap();
+    /// let mut builder = TransactionBuilder::new(&client, keypair.keypair());
+    /// builder.transfer_sol(0.01, keypair.keypair(), "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5").unwrap();
+    /// let compute_unit_price = builder.set_auto_priority_fee(75).unwrap();
+    /// let transaction = builder.build().unwrap();
+    /// ```
+    pub fn set_auto_priority_fee(&mut self, percentile: u8) -> Result<u64, TransactionBuilderError> {
+        if percentile > 100 {
+            return Err(TransactionBuilderError::InstructionError(format!("Percentile {} exceeds 100", percentile)));
+        }
+        let fees = get_recent_priority_fees(self.client, &writable_accounts(&self.instructions))?;
+        let compute_unit_price = priority_fee_percentile(&fees, percentile);
+
+        let price_instruction = ComputeBudgetInstruction::set_compute_unit_price(0);
+        self.instructions.retain(|instruction| !is_same_compute_budget_instruction(instruction, &price_instruction));
+        if compute_unit_price > 0 {
+            self.instructions.insert(0, ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price));
+        }
+        Ok(compute_unit_price)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::signer::keypair::Keypair;
+    use solana_system_interface::instruction::transfer;
+    use crate::test_utils::mock_client;
+
+    fn recent_fees_response() -> serde_json::Value {
+        json!([
+            { "slot": 1, "prioritizationFee": 0 },
+            { "slot": 2, "prioritizationFee": 100_000 },
+            { "slot": 3, "prioritizationFee": 5_000 },
+            { "slot": 4, "prioritizationFee": 20_000 },
+        ])
+    }
+
+    #[test]
+    fn test_priority_fee_percentile() {
+        let fees: Vec<RpcPrioritizationFee> = serde_json::from_value(recent_fees_response()).unwrap();
+        assert!(priority_fee_percentile(&fees, 0) == 0);
+        assert!(priority_fee_percentile(&fees, 50) == 20_000);
+        assert!(priority_fee_percentile(&fees, 100) == 100_000);
+        assert!(priority_fee_percentile(&[], 50) == 0);
+    }
+
+    #[test]
+    fn test_set_auto_priority_fee_replaces_price() {
+        let client = mock_client(vec![(RpcRequest::GetRecentPrioritizationFees, recent_fees_response())]);
+        let keypair = Keypair::new();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder.set_compute_units(1).set_compute_limit(10_000);
+        builder.instructions.push(transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1));
+
+        assert!(builder.set_auto_priority_fee(75).unwrap() == 100_000);
+        assert!(builder.instructions.len() == 3);
+        assert!(builder.instructions[0] == ComputeBudgetInstruction::set_compute_unit_price(100_000));
+        assert!(builder.instructions[1] == ComputeBudgetInstruction::set_compute_unit_limit(10_000));
+    }
+
+    #[test]
+    fn failing_test_set_auto_priority_fee_above_100() {
+        let client = mock_client(vec![]);
+        let keypair = Keypair::new();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        assert!(matches!(builder.set_auto_priority_fee(101), Err(TransactionBuilderError::InstructionError(_))));
+    }
+}
@@ -0,0 +1,278 @@
+//! # Relay
+//!
+//! This module supports sponsored (gasless) transactions. The user builds a transaction whose
+//! fee payer is a relayer and signs it partially, then sends it serialized to the relayer. The
+//! relayer verifies the transaction against its `RelayPolicy` before co-signing and sending it.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use borsh::BorshDeserialize;
+use solana_client::rpc_client::RpcClient;
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_sdk::{
+    hash::Hash,
+    pubkey::Pubkey,
+    signer::{keypair::Keypair, Signer},
+    transaction::Transaction
+};
+use crate::{
+    constants::solana_programs::compute_budget_program,
+    error::{RelayError, TransactionBuilderError}
+};
+use super::{
+    compute_limit::MAX_COMPUTE_UNIT_LIMIT,
+    transaction_builder::TransactionBuilder,
+    utils::{send_and_confirm_transaction, ConfirmedTransaction}
+};
+
+/// Rules a relayer applies before paying for a transaction.
+///
+/// ### Fields
+///
+/// - `allowed_programs`: Programs the transaction may call, compute budget instructions included.
+/// - `max_instructions`: Maximum number of instructions in the transaction.
+/// - `max_signatures`: Maximum number of signatures, which each add to the fee paid by the relayer.
+/// - `max_compute_unit_price`: Maximum compute unit price in micro-lamports.
+/// - `max_compute_unit_limit`: Maximum compute unit limit, the default limit of the instructions
+///   if the transaction sets none. With the price, it caps the priority fee paid by the relayer.
+#[derive(Debug, Clone)]
+pub struct RelayPolicy {
+    pub allowed_programs: Vec<Pubkey>,
+    pub max_instructions: usize,
+    pub max_signatures: usize,
+    pub max_compute_unit_price: u64,
+    pub max_compute_unit_limit: u32,
+}
+
+// Compute unit limit of each instruction when a transaction sets no limit
+const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
+impl TransactionBuilder<'_> {
+    /// Builds the transaction with `relayer` as fee payer, signed by every keypair of the
+    /// builder but not by the relayer, after checking it against the policy of the builder.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::PolicyViolation` if the instructions break the policy.
+    /// Returns `TransactionBuilderError::InstructionError` if a keypair of the builder cannot sign the transaction.
+    pub fn build_for_relayer(&self, relayer: &Pubkey, recent_blockhash: Hash) -> Result<Transaction, TransactionBuilderError> {
+        self.check_policy()?;
+        let mut transaction = Transaction::new_with_payer(&self.instructions, Some(relayer));
+        let keypairs = self.required_keypairs(&transaction);
+        transaction
+            .try_partial_sign(&keypairs, recent_blockhash)
+            .map_err(|err| TransactionBuilderError::InstructionError(err.to_string()))?;
+        Ok(transaction)
+    }
+}
+
+/// Serializes a transaction to base64, to be sent to the relayer.
+pub fn serialize_transaction(transaction: &Transaction) -> Result<String, RelayError> {
+    let bytes = bincode::serialize(transaction).map_err(|_| RelayError::SerializeError)?;
+    Ok(STANDARD.encode(bytes))
+}
+
+/// Deserializes a base64 transaction received by the relayer.
+pub fn deserialize_transaction(encoded_transaction: &str) -> Result<Transaction, RelayError> {
+    let bytes = STANDARD.decode(encoded_transaction).map_err(|_| RelayError::SerializeError)?;
+    bincode::deserialize(&bytes).map_err(|_| RelayError::SerializeError)
+}
+
+/// Verifies a transaction before the relayer pays for it. The relayer has to be the fee payer
+/// and may not be used by any instruction, so its funds can only be spent on the fee, which the
+/// compute unit price and limit caps of the policy bound. Every signature except the relayer's
+/// must already be valid.
+///
+/// ## Errors
+///
+/// Returns the `RelayError` of the first rule of the policy the transaction breaks.
+pub fn verify_relay_transaction(transaction: &Transaction, relayer: &Pubkey, policy: &RelayPolicy) -> Result<(), RelayError> {
+    let message = &transaction.message;
+    if message.account_keys.first() != Some(relayer) {
+        return Err(RelayError::WrongFeePayer);
+    }
+    if message.instructions.len() > policy.max_instructions {
+        return Err(RelayError::TooManyInstructions(message.instructions.len()));
+    }
+    if transaction.signatures.len() > policy.max_signatures {
+        return Err(RelayError::TooManySignatures(transaction.signatures.len()));
+    }
+
+    let mut compute_unit_limit = None;
+    let mut other_instructions = 0;
+    for instruction in &message.instructions {
+        let program_id = message.account_keys
+            .get(instruction.program_id_index as usize)
+            .ok_or(RelayError::InvalidTransaction)?;
+        if !policy.allowed_programs.contains(program_id) {
+            return Err(RelayError::ProgramNotAllowed(*program_id));
+        }
+        // index 0 is the relayer
+        if instruction.accounts.contains(&0) {
+            return Err(RelayError::RelayerAccountUsed);
+        }
+        if *program_id != compute_budget_program() {
+            other_instructions += 1;
+            continue;
+        }
+        match ComputeBudgetInstruction::try_from_slice(&instruction.data).map_err(|_| RelayError::InvalidTransaction)? {
+            ComputeBudgetInstruction::SetComputeUnitPrice(price) if price > policy.max_compute_unit_price => {
+                return Err(RelayError::ComputeUnitPriceTooHigh(price));
+            }
+            ComputeBudgetInstruction::SetComputeUnitLimit(limit) => compute_unit_limit = Some(limit),
+            _ => {}
+        }
+    }
+    let compute_unit_limit = compute_unit_limit
+        .unwrap_or(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT.saturating_mul(other_instructions).min(MAX_COMPUTE_UNIT_LIMIT));
+    if compute_unit_limit > policy.max_compute_unit_limit {
+        return Err(RelayError::ComputeUnitLimitTooHigh(compute_unit_limit));
+    }
+
+    let signature_results = transaction.verify_with_results();
+    if signature_results.len() != transaction.signatures.len() || !signature_results.iter().skip(1).all(|verified| *verified) {
+        return Err(RelayError::InvalidSignature);
+    }
+    Ok(())
+}
+
+/// Verifies a transaction against the policy and signs it as fee payer.
+pub fn cosign_relay_transaction(mut transaction: Transaction, relayer_keypair: &Keypair, policy: &RelayPolicy) -> Result<Transaction, RelayError> {
+    verify_relay_transaction(&transaction, &relayer_keypair.pubkey(), policy)?;
+    let recent_blockhash = transaction.message.recent_blockhash;
+    transaction
+        .try_partial_sign(&[relayer_keypair], recent_blockhash)
+        .map_err(|_| RelayError::InvalidSignature)?;
+    Ok(transaction)
+}
+
+/// Deserializes, verifies, co-signs and sends a transaction received from a user.
+///
+/// ## Errors
+///
+/// Returns `RelayError::TransactionFailed` if the transaction landed but failed.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_sdk::signer::{keypair::Keypair, Signer};
+/// use easy_solana::{
+///     create_rpc_client,
+///     constants::solana_programs::system_program,
+///     write_transactions::{relay::{relay_transaction, serialize_transaction, RelayPolicy}, transaction_builder::TransactionBuilder}
+/// };
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let (user_keypair, relayer_keypair) = (Keypair::new(), Keypair::new());
+///
+/// // user side
+/// let transaction = TransactionBuilder::new(&client, &user_keypair)
+///     .transfer_sol(0.001, &user_keypair, "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5")
+///     .unwrap()
+///     .build_for_relayer(&relayer_keypair.pubkey(), client.get_latest_blockhash().unwrap())
+///     .unwrap();
+/// let encoded_transaction = serialize_transaction(&transaction).unwrap();
+///
+/// // relayer side
+/// let policy = RelayPolicy {
+///     allowed_programs: vec![system_program()],
+///     max_instructions: 4,
+///     max_signatures: 2,
+///     max_compute_unit_price: 0,
+///     max_compute_unit_limit: 200_000,
+/// };
+/// let confirmed_transaction = relay_transaction(&client, &encoded_transaction, &relayer_keypair, &policy).unwrap();
+/// ```
+pub fn relay_transaction(
+    client: &RpcClient,
+    encoded_transaction: &str,
+    relayer_keypair: &Keypair,
+    policy: &RelayPolicy
+) -> Result<ConfirmedTransaction, RelayError> {
+    let transaction = deserialize_transaction(encoded_transaction)?;
+    let transaction = cosign_relay_transaction(transaction, relayer_keypair, policy)?;
+    send_and_confirm_transaction(client, transaction)?.into_result(RelayError::TransactionFailed)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signature;
+    use crate::{constants::solana_programs::system_program, test_utils::mock_client};
+
+    const WALLET_ADDRESS_1: &str = "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5";
+
+    fn policy() -> RelayPolicy {
+        RelayPolicy {
+            allowed_programs: vec![system_program(), compute_budget_program()],
+            max_instructions: 3,
+            max_signatures: 2,
+            max_compute_unit_price: 10_000,
+            max_compute_unit_limit: 200_000,
+        }
+    }
+
+    #[test]
+    fn test_relay_round_trip() {
+        let client = mock_client(vec![]);
+        let (user_keypair, relayer_keypair) = (Keypair::new(), Keypair::new());
+
+        let mut builder = TransactionBuilder::new(&client, &user_keypair);
+        builder.transfer_sol(0.001, &user_keypair, WALLET_ADDRESS_1).unwrap();
+        let transaction = builder.build_for_relayer(&relayer_keypair.pubkey(), Hash::new_unique()).unwrap();
+        assert!(transaction.signatures[0] == Signature::default());
+
+        let encoded_transaction = serialize_transaction(&transaction).unwrap();
+        let received_transaction = deserialize_transaction(&encoded_transaction).unwrap();
+        let cosigned_transaction = cosign_relay_transaction(received_transaction, &relayer_keypair, &policy()).unwrap();
+        assert!(cosigned_transaction.verify().is_ok());
+
+        let confirmed_transaction = relay_transaction(&client, &encoded_transaction, &relayer_keypair, &policy()).unwrap();
+        assert!(confirmed_transaction.signature == cosigned_transaction.signatures[0]);
+    }
+
+    #[test]
+    fn failing_test_relay_transaction_spending_relayer_funds() {
+        let client = mock_client(vec![]);
+        let (user_keypair, relayer_keypair) = (Keypair::new(), Keypair::new());
+
+        // the relayer is the source of the transfer
+        let mut builder = TransactionBuilder::new(&client, &user_keypair);
+        builder.signing_keypairs.push(&relayer_keypair);
+        builder.instructions.push(solana_system_interface::instruction::transfer(&relayer_keypair.pubkey(), &user_keypair.pubkey(), 1_000));
+        let transaction = builder.build_for_relayer(&relayer_keypair.pubkey(), Hash::new_unique()).unwrap();
+        let result = verify_relay_transaction(&transaction, &relayer_keypair.pubkey(), &policy());
+        assert!(matches!(result, Err(RelayError::RelayerAccountUsed)));
+
+        let result = verify_relay_transaction(&transaction, &user_keypair.pubkey(), &policy());
+        assert!(matches!(result, Err(RelayError::WrongFeePayer)));
+    }
+
+    #[test]
+    fn failing_test_relay_transaction_with_high_priority_fee() {
+        let client = mock_client(vec![]);
+        let (user_keypair, relayer_keypair) = (Keypair::new(), Keypair::new());
+        let build = |compute_units: u64, compute_limit: Option<u32>, transfers: usize| {
+            let mut builder = TransactionBuilder::new(&client, &user_keypair);
+            builder.set_compute_units(compute_units);
+            if let Some(compute_limit) = compute_limit {
+                builder.set_compute_limit(compute_limit);
+            }
+            for _ in 0..transfers {
+                builder.transfer_sol(0.001, &user_keypair, WALLET_ADDRESS_1).unwrap();
+            }
+            builder.build_for_relayer(&relayer_keypair.pubkey(), Hash::new_unique()).unwrap()
+        };
+
+        assert!(verify_relay_transaction(&build(10_000, Some(200_000), 1), &relayer_keypair.pubkey(), &policy()).is_ok());
+        // without a limit, each instruction gets the default limit
+        assert!(verify_relay_transaction(&build(10_000, None, 1), &relayer_keypair.pubkey(), &policy()).is_ok());
+
+        let result = verify_relay_transaction(&build(10_001, Some(200_000), 1), &relayer_keypair.pubkey(), &policy());
+        assert!(matches!(result, Err(RelayError::ComputeUnitPriceTooHigh(10_001))));
+        let result = verify_relay_transaction(&build(10_000, Some(200_001), 1), &relayer_keypair.pubkey(), &policy());
+        assert!(matches!(result, Err(RelayError::ComputeUnitLimitTooHigh(200_001))));
+        let result = verify_relay_transaction(&build(10_000, None, 2), &relayer_keypair.pubkey(), &policy());
+        assert!(matches!(result, Err(RelayError::ComputeUnitLimitTooHigh(400_000))));
+    }
+}
@@ -0,0 +1,259 @@
+//! # Send
+//!
+//! One call functions to send SOL or tokens. They create the destination token account if it
+//! does not exist, simulate the transaction to set its compute limit, add a priority fee from
+//! recent prioritization fees, then send it and wait for confirmation.
+//!
+//! Use the `TransactionBuilder` directly for control over fees, signers or extra instructions.
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer
+};
+use solana_system_interface::instruction::transfer;
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent
+};
+use spl_token_2022::{extension::StateWithExtensions, instruction::transfer_checked, state::Mint};
+use crate::{
+    constants::solana_programs::{sol_pubkey, token_2022_program, token_program},
+    error::{SendError, TransactionBuilderError},
+    utils::{address_to_pubkey, sol_to_lamports}
+};
+use super::{
+    priority_fees::{get_recent_priority_fees, priority_fee_percentile, writable_accounts},
+    transaction_builder::TransactionBuilder,
+    transfer_checks::{check_destination_token_account, check_transfer_destination, recipient_kind, DestinationTokenAccount, RecipientKind},
+    utils::{send_and_confirm_transaction, simulate_transaction, ConfirmedTransaction}
+};
+
+/// Compute units added on top of the simulated units, which exclude the compute budget
+/// instructions and vary slightly between simulation and execution.
+const COMPUTE_LIMIT_MARGIN: u32 = 1_000;
+
+/// Sends `amount` SOL, rounded to the nearest lamport, from `keypair` to `to`. See `send_lamports`.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, send_sol, SecretKeypair};
+///
+/// let client = create_rpc_client("RPC_URL");
+/// let keypair = SecretKeypair::from_env("PRIVATE_KEY_1").unwrap();
+/// let confirmed_transaction = send_sol(&client, &keypair, "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5", 0.01).unwrap();
+/// println!("Sent in slot {}", confirmed_transaction.slot);
+/// ```
+pub fn send_sol(client: &RpcClient, keypair: &Keypair, to: &str, amount: f64) -> Result<ConfirmedTransaction, SendError> {
+    send_lamports(client, keypair, to, sol_to_lamports(amount))
+}
+
+/// Sends exactly `lamports` from `keypair` to `to` and waits for confirmation.
+///
+/// ## Errors
+///
+/// Returns `SendError::DestinationCheckFailed` if `to` is a mint, a program or a token account
+/// other than wrapped SOL, `SendError::SimulationFailed` if the transfer would fail, e.g for
+/// insufficient funds, and `SendError::TransactionFailed` if it landed but failed.
+pub fn send_lamports(client: &RpcClient, keypair: &Keypair, to: &str, lamports: u64) -> Result<ConfirmedTransaction, SendError> {
+    let destination = address_to_pubkey(to)?;
+    let destination_account = client.get_account_with_commitment(&destination, client.commitment())?.value;
+    check_transfer_destination(&destination, destination_account.as_ref(), &sol_pubkey())?;
+    let instructions = vec![transfer(&keypair.pubkey(), &destination, lamports)];
+    send_instructions(client, keypair, instructions)
+}
+
+/// Sends `amount` of the token `mint`, in whole tokens rounded to the decimals of the mint, from
+/// the associated token account of `keypair` to the one of `to`. See `send_token_amount`.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, send_token, SecretKeypair};
+///
+/// const USDC_TOKEN_ADDRESS: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+///
+/// let client = create_rpc_client("RPC_URL");
+/// let keypair = SecretKeypair::from_env("PRIVATE_KEY_1").unwrap();
+/// send_token(&client, &keypair, USDC_TOKEN_ADDRESS, "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5", 2.5).unwrap();
+/// ```
+pub fn send_token(client: &RpcClient, keypair: &Keypair, mint: &str, to: &str, amount: f64) -> Result<ConfirmedTransaction, SendError> {
+    let (token_program, decimals) = mint_details(client, mint)?;
+    let raw_amount = (amount * 10_f64.powi(decimals as i32)).round() as u64;
+    send_token_with_mint(client, keypair, mint, to, raw_amount, (token_program, decimals))
+}
+
+/// Sends `raw_amount` of the token `mint`, without decimals, from the associated token account
+/// of `keypair` to the one of `to`, or to `to` itself if it is a token account of `mint`. The
+/// destination token account is checked before sending and created, paid by `keypair`, if it
+/// does not exist. Token 2022 mints are supported.
+///
+/// ## Errors
+///
+/// - `SendError::InvalidMint` if `mint` is not owned by a token program.
+/// - `SendError::DestinationCheckFailed` if `to` is a mint, a program or a token account of
+///   another mint, or if the destination token account is frozen or was hijacked.
+/// - `SendError::SimulationFailed` if the transfer would fail, e.g for an insufficient balance.
+/// - `SendError::TransactionFailed` if the transaction landed but failed.
+pub fn send_token_amount(client: &RpcClient, keypair: &Keypair, mint: &str, to: &str, raw_amount: u64) -> Result<ConfirmedTransaction, SendError> {
+    let mint_details = mint_details(client, mint)?;
+    send_token_with_mint(client, keypair, mint, to, raw_amount, mint_details)
+}
+
+fn send_token_with_mint(
+    client: &RpcClient,
+    keypair: &Keypair,
+    mint: &str,
+    to: &str,
+    raw_amount: u64,
+    (token_program, decimals): (Pubkey, u8)
+) -> Result<ConfirmedTransaction, SendError> {
+    let owner = keypair.pubkey();
+    let mint_pubkey = address_to_pubkey(mint)?;
+    let destination_wallet = address_to_pubkey(to)?;
+    let source = get_associated_token_address_with_program_id(&owner, &mint_pubkey, &token_program);
+    let destination_account = client.get_account_with_commitment(&destination_wallet, client.commitment())?.value;
+    check_transfer_destination(&destination_wallet, destination_account.as_ref(), &mint_pubkey)?;
+    let destination = match recipient_kind(destination_account.as_ref()) {
+        // Deriving an associated token account from a token account would lose the tokens
+        RecipientKind::TokenAccount { .. } => DestinationTokenAccount { address: destination_wallet, exists: true },
+        _ => check_destination_token_account(client, to, mint, token_program)?,
+    };
+
+    let mut instructions = Vec::with_capacity(2);
+    if !destination.exists {
+        instructions.push(create_associated_token_account_idempotent(&owner, &destination_wallet, &mint_pubkey, &token_program));
+    }
+    let transfer_instruction = transfer_checked(&token_program, &source, &mint_pubkey, &destination.address, &owner, &[], raw_amount, decimals)
+        .map_err(|err| TransactionBuilderError::InstructionError(err.to_string()))?;
+    instructions.push(transfer_instruction);
+    send_instructions(client, keypair, instructions)
+}
+
+/// Token program owning `mint` and the decimals of the mint.
+fn mint_details(client: &RpcClient, mint: &str) -> Result<(Pubkey, u8), SendError> {
+    let mint_pubkey = address_to_pubkey(mint)?;
+    let account = client.get_account(&mint_pubkey)?;
+    if account.owner != token_program() && account.owner != token_2022_program() {
+        return Err(SendError::InvalidMint(mint_pubkey));
+    }
+    let mint_state = StateWithExtensions::<Mint>::unpack(&account.data)
+        .map_err(|_| SendError::InvalidMint(mint_pubkey))?;
+    Ok((account.owner, mint_state.base.decimals))
+}
+
+/// Simulates `instructions` for their compute units, then sends them with a matching compute
+/// limit and the median recent priority fee of their writable accounts.
+fn send_instructions(client: &RpcClient, keypair: &Keypair, instructions: Vec<Instruction>) -> Result<ConfirmedTransaction, SendError> {
+    let mut simulation_builder = TransactionBuilder::new(client, keypair);
+    simulation_builder.instructions = instructions.clone();
+    // The RPC replaces the blockhash when simulating
+    let simulation_result = simulate_transaction(client, simulation_builder.build_with_blockhash(Hash::default())?)?;
+    if let Some(err) = simulation_result.error {
+        return Err(SendError::SimulationFailed(err));
+    }
+    let compute_unit_price = priority_fee(client, &instructions)?;
+
+    let mut builder = TransactionBuilder::new(client, keypair);
+    builder.set_compute_limit(simulation_result.units_consumed.saturating_add(COMPUTE_LIMIT_MARGIN));
+    if compute_unit_price > 0 {
+        builder.set_compute_units(compute_unit_price);
+    }
+    builder.instructions.extend(instructions);
+
+    send_and_confirm_transaction(client, builder.build()?)?.into_result(SendError::TransactionFailed)
+}
+
+/// Median compute unit price paid in recent slots by transactions writing to the writable
+/// accounts of `instructions`, in micro-lamports.
+fn priority_fee(client: &RpcClient, instructions: &[Instruction]) -> Result<u64, SendError> {
+    let fees = get_recent_priority_fees(client, &writable_accounts(instructions))
+        .map_err(|err| SendError::RpcError(err.to_string()))?;
+    Ok(priority_fee_percentile(&fees, 50))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::program_pack::Pack;
+    use spl_token::state::Mint as SplMint;
+    use crate::{
+        constants::solana_programs::system_program,
+        error::TransferCheckError,
+        test_utils::{account_info_response, account_with_data, mock_client, simulation_response}
+    };
+
+    const WALLET_ADDRESS_1: &str = "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5";
+
+    fn mint_account(decimals: u8) -> solana_sdk::account::Account {
+        let mut data = vec![0; SplMint::LEN];
+        let mint = SplMint { decimals, is_initialized: true, ..Default::default() };
+        SplMint::pack(mint, &mut data).unwrap();
+        account_with_data(token_program(), data)
+    }
+
+    #[test]
+    fn test_send_sol() {
+        let client = mock_client(vec![
+            (RpcRequest::SimulateTransaction, simulation_response(&[], 150)),
+        ]);
+        let keypair = Keypair::new();
+        let confirmed_transaction = send_sol(&client, &keypair, WALLET_ADDRESS_1, 0.01).unwrap();
+        assert!(confirmed_transaction.err.is_none());
+    }
+
+    #[test]
+    fn test_send_token_creates_destination_account() {
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&mint_account(6)), 1)),
+            // new destination wallet without a token account
+            (RpcRequest::GetAccountInfo, account_info_response(None, 1)),
+            (RpcRequest::GetAccountInfo, account_info_response(None, 1)),
+            (RpcRequest::SimulateTransaction, simulation_response(&[], 30_000)),
+        ]);
+        let keypair = Keypair::new();
+        let mint = Pubkey::new_unique().to_string();
+        let confirmed_transaction = send_token(&client, &keypair, &mint, WALLET_ADDRESS_1, 1.5).unwrap();
+        assert!(confirmed_transaction.err.is_none());
+    }
+
+    #[test]
+    fn failing_test_send_token_of_non_mint() {
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&account_with_data(system_program(), vec![])), 1)),
+        ]);
+        let keypair = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let result = send_token_amount(&client, &keypair, &mint.to_string(), WALLET_ADDRESS_1, 1);
+        assert!(matches!(result, Err(SendError::InvalidMint(invalid_mint)) if invalid_mint == mint));
+    }
+
+    #[test]
+    fn failing_test_send_sol_to_mint() {
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&mint_account(6)), 1)),
+        ]);
+        let keypair = Keypair::new();
+        let result = send_sol(&client, &keypair, WALLET_ADDRESS_1, 0.01);
+        assert!(matches!(result, Err(SendError::DestinationCheckFailed(TransferCheckError::DestinationIsMint(_)))));
+    }
+
+    #[test]
+    fn test_priority_fee_is_median_of_recent_fees() {
+        let fees = json!([
+            { "slot": 1, "prioritizationFee": 0 },
+            { "slot": 2, "prioritizationFee": 5_000 },
+            { "slot": 3, "prioritizationFee": 100_000 },
+        ]);
+        let client = mock_client(vec![(RpcRequest::GetRecentPrioritizationFees, fees)]);
+        let instructions = [transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1)];
+        assert!(priority_fee(&client, &instructions).unwrap() == 5_000);
+    }
+}
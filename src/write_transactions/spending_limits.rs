@@ -0,0 +1,281 @@
+//! # Spending Limits
+//!
+//! This module tracks the SOL and token outflows of each keypair within a rolling window and
+//! blocks sends that exceed the configured budgets. Outflows can be persisted to a file so that
+//! restarting a bot does not reset its limits.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration
+};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use solana_system_interface::instruction::SystemInstruction;
+use spl_token::instruction::TokenInstruction;
+use spl_token_2022::instruction::TokenInstruction as Token2022Instruction;
+use crate::{
+    constants::pumpfun_accounts::{buy_instruction_data, pumpfun_program},
+    error::{PersistenceError, SpendingLimitError},
+    read_transactions::instruction_decoder::{decode_instruction, DecodedInstruction}
+};
+
+/// Index of the buyer in a pump.fun buy instruction.
+const PUMP_BUY_USER_INDEX: usize = 6;
+
+/// Asset a budget applies to, tokens are identified by their mint address.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Asset {
+    Sol,
+    Token(String),
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Asset::Sol => write!(f, "SOL"),
+            Asset::Token(mint) => write!(f, "{}", mint),
+        }
+    }
+}
+
+/// An amount of an asset sent by a keypair.
+///
+/// ### Fields
+///
+/// - `owner`: Address of the keypair that sent the asset.
+/// - `asset`: The asset sent.
+/// - `amount`: Amount sent in lamports or without token decimals.
+/// - `timestamp`: Unix timestamp in milliseconds.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Outflow {
+    pub owner: String,
+    pub asset: Asset,
+    pub amount: u64,
+    pub timestamp: u64,
+}
+
+/// Tracks outflows per keypair and enforces a budget per asset within a rolling window.
+/// Assets without a budget are not limited.
+///
+/// ## Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use solana_sdk::pubkey::Pubkey;
+/// use easy_solana::write_transactions::spending_limits::{Asset, SpendingTracker};
+///
+/// let mut daily_budget = SpendingTracker::new(Duration::from_secs(24 * 60 * 60));
+/// daily_budget.set_budget(Asset::Sol, 5_000_000_000);
+///
+/// let wallet = Pubkey::new_unique();
+/// assert!(daily_budget.check_and_record(&wallet, Asset::Sol, 4_000_000_000, 0).is_ok());
+/// assert!(daily_budget.check_and_record(&wallet, Asset::Sol, 2_000_000_000, 1_000).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpendingTracker {
+    window_ms: u64,
+    budgets: HashMap<Asset, u64>,
+    outflows: VecDeque<Outflow>,
+    path: Option<PathBuf>,
+}
+
+impl SpendingTracker {
+    pub fn new(window: Duration) -> Self {
+        SpendingTracker {
+            window_ms: window.as_millis() as u64,
+            budgets: HashMap::new(),
+            outflows: VecDeque::new(),
+            path: None,
+        }
+    }
+
+    /// Sets the maximum amount of an asset each keypair can send within the window.
+    pub fn set_budget(&mut self, asset: Asset, amount: u64) -> &mut Self {
+        self.budgets.insert(asset, amount);
+        self
+    }
+
+    /// Saves the outflows to `path` every time an outflow is recorded.
+    pub fn persist_to(&mut self, path: impl AsRef<Path>) -> &mut Self {
+        self.path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Amount of an asset sent by `owner` within the window ending at `timestamp`.
+    pub fn spent(&self, owner: &Pubkey, asset: &Asset, timestamp: u64) -> u64 {
+        let owner = owner.to_string();
+        self.outflows
+            .iter()
+            .filter(|outflow| self.is_in_window(outflow, timestamp) && outflow.owner == owner && outflow.asset == *asset)
+            .fold(0_u64, |spent, outflow| spent.saturating_add(outflow.amount))
+    }
+
+    fn is_in_window(&self, outflow: &Outflow, timestamp: u64) -> bool {
+        outflow.timestamp <= timestamp && outflow.timestamp.saturating_add(self.window_ms) > timestamp
+    }
+
+    /// Amount of an asset `owner` can still send at `timestamp`, `None` if the asset has no budget.
+    pub fn remaining(&self, owner: &Pubkey, asset: &Asset, timestamp: u64) -> Option<u64> {
+        self.budgets
+            .get(asset)
+            .map(|budget| budget.saturating_sub(self.spent(owner, asset, timestamp)))
+    }
+
+    /// Checks that sending `amount` stays within the budget, without recording it.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `SpendingLimitError::BudgetExceeded` if the amount exceeds the remaining budget.
+    pub fn check(&self, owner: &Pubkey, asset: &Asset, amount: u64, timestamp: u64) -> Result<(), SpendingLimitError> {
+        match self.remaining(owner, asset, timestamp) {
+            Some(remaining) if amount > remaining => Err(SpendingLimitError::BudgetExceeded {
+                owner: owner.to_string(),
+                asset: asset.to_string(),
+                amount,
+                remaining,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks an outflow against the budget and records it if it is within the budget.
+    pub fn check_and_record(&mut self, owner: &Pubkey, asset: Asset, amount: u64, timestamp: u64) -> Result<(), SpendingLimitError> {
+        self.check(owner, &asset, amount, timestamp)?;
+        self.record(vec![Outflow { owner: owner.to_string(), asset, amount, timestamp }])
+    }
+
+    /// Checks every outflow of the instructions, e.g of a `TransactionBuilder`, and records them
+    /// only if all of them are within the budgets.
+    pub fn check_and_record_instructions(&mut self, instructions: &[Instruction], timestamp: u64) -> Result<(), SpendingLimitError> {
+        let outflows = outflows_of_instructions(instructions, timestamp);
+
+        // outflows of the same owner and asset are checked together
+        let mut totals: HashMap<(&str, &Asset), u64> = HashMap::new();
+        for outflow in &outflows {
+            let total = totals.entry((&outflow.owner, &outflow.asset)).or_default();
+            *total = total.saturating_add(outflow.amount);
+        }
+        for ((owner, asset), amount) in totals {
+            let owner = owner.parse::<Pubkey>().map_err(|_| SpendingLimitError::InvalidOwner(owner.to_string()))?;
+            self.check(&owner, asset, amount, timestamp)?;
+        }
+
+        self.record(outflows)
+    }
+
+    /// Records outflows, dropping those outside of the window and saving if persistence is enabled.
+    fn record(&mut self, outflows: Vec<Outflow>) -> Result<(), SpendingLimitError> {
+        self.outflows.extend(outflows);
+        if let Some(latest_timestamp) = self.outflows.iter().map(|outflow| outflow.timestamp).max() {
+            let window_ms = self.window_ms;
+            self.outflows.retain(|outflow| outflow.timestamp.saturating_add(window_ms) > latest_timestamp);
+        }
+        if let Some(path) = &self.path {
+            self.save(path)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the outflows to a JSON file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PersistenceError> {
+        let json = serde_json::to_string(&self.outflows)?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Creates a tracker from outflows previously saved with `save`, budgets have to be set again.
+    pub fn load(path: impl AsRef<Path>, window: Duration) -> Result<Self, PersistenceError> {
+        let json = fs::read_to_string(path)?;
+        let mut tracker = SpendingTracker::new(window);
+        tracker.outflows = serde_json::from_str(&json)?;
+        Ok(tracker)
+    }
+}
+
+/// Outflows of SOL transfers, funded account creations, pump.fun buys and checked token transfers.
+/// Unchecked token transfers do not reference their mint and are not tracked.
+pub fn outflows_of_instructions(instructions: &[Instruction], timestamp: u64) -> Vec<Outflow> {
+    instructions
+        .iter()
+        .filter_map(|instruction| {
+            let account = |index: usize| instruction.accounts.get(index).map(|account| account.pubkey.to_string());
+            let (owner, asset, amount) = if instruction.program_id == pumpfun_program() && instruction.data.starts_with(&buy_instruction_data()) {
+                let max_sol_cost = u64::from_le_bytes(instruction.data.get(16..24)?.try_into().ok()?);
+                (account(PUMP_BUY_USER_INDEX)?, Asset::Sol, max_sol_cost)
+            } else {
+                match decode_instruction(&instruction.program_id, &instruction.data) {
+                    DecodedInstruction::System(SystemInstruction::Transfer { lamports })
+                    | DecodedInstruction::System(SystemInstruction::CreateAccount { lamports, .. }) => (account(0)?, Asset::Sol, lamports),
+                    DecodedInstruction::Token(TokenInstruction::TransferChecked { amount, .. })
+                    | DecodedInstruction::Token2022(Token2022Instruction::TransferChecked { amount, .. }) => {
+                        (account(3)?, Asset::Token(account(1)?), amount)
+                    }
+                    _ => return None,
+                }
+            };
+            Some(Outflow { owner, asset, amount, timestamp })
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::solana_programs::token_program;
+
+    #[test]
+    fn test_budget_within_rolling_window() {
+        let wallet = Pubkey::new_unique();
+        let mut tracker = SpendingTracker::new(Duration::from_secs(60));
+        tracker.set_budget(Asset::Sol, 1_000);
+
+        assert!(tracker.check_and_record(&wallet, Asset::Sol, 600, 0).is_ok());
+        assert!(matches!(
+            tracker.check_and_record(&wallet, Asset::Sol, 500, 30_000),
+            Err(SpendingLimitError::BudgetExceeded { remaining: 400, .. })
+        ));
+        // other keypairs and assets without a budget are not limited
+        assert!(tracker.check_and_record(&Pubkey::new_unique(), Asset::Sol, 1_000, 30_000).is_ok());
+        assert!(tracker.check_and_record(&wallet, Asset::Token(Pubkey::new_unique().to_string()), u64::MAX, 30_000).is_ok());
+        // the first outflow leaves the window
+        assert!(tracker.check_and_record(&wallet, Asset::Sol, 1_000, 60_000).is_ok());
+    }
+
+    #[test]
+    fn test_check_and_record_instructions() {
+        let (wallet, destination, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut tracker = SpendingTracker::new(Duration::from_secs(60));
+        tracker.set_budget(Asset::Sol, 1_200).set_budget(Asset::Token(mint.to_string()), 50);
+
+        let transfer = solana_system_interface::instruction::transfer(&wallet, &destination, 600);
+        let token_transfer = spl_token::instruction::transfer_checked(
+            &token_program(), &Pubkey::new_unique(), &mint, &Pubkey::new_unique(), &wallet, &[], 40, 6
+        ).unwrap();
+        assert!(tracker.check_and_record_instructions(&[transfer.clone(), token_transfer.clone()], 0).is_ok());
+        assert!(tracker.spent(&wallet, &Asset::Token(mint.to_string()), 0) == 40);
+
+        // nothing is recorded when any outflow exceeds its budget
+        assert!(tracker.check_and_record_instructions(&[token_transfer], 1_000).is_err());
+        assert!(tracker.check_and_record_instructions(&[transfer], 1_000).is_ok());
+        assert!(tracker.spent(&wallet, &Asset::Token(mint.to_string()), 1_000) == 40);
+        assert!(tracker.spent(&wallet, &Asset::Sol, 1_000) == 1_200);
+    }
+
+    #[test]
+    fn test_save_and_load_outflows() {
+        let wallet = Pubkey::new_unique();
+        let path = std::env::temp_dir().join(format!("spending_limits_{}.json", wallet));
+        let mut tracker = SpendingTracker::new(Duration::from_secs(60));
+        tracker.set_budget(Asset::Sol, 1_000).persist_to(&path);
+        tracker.check_and_record(&wallet, Asset::Sol, 700, 0).unwrap();
+
+        let mut restarted_tracker = SpendingTracker::load(&path, Duration::from_secs(60)).unwrap();
+        restarted_tracker.set_budget(Asset::Sol, 1_000);
+        assert!(restarted_tracker.remaining(&wallet, &Asset::Sol, 1_000) == Some(300));
+        fs::remove_file(path).unwrap();
+    }
+}
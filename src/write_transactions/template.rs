@@ -0,0 +1,281 @@
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signer::Signer
+};
+
+use crate::{
+    constants::solana_programs::{associated_token_account_program, token_2022_program, token_program},
+    error::TransactionBuilderError
+};
+use super::transaction_builder::TransactionBuilder;
+
+/// Values substituted into the placeholders of a `TransactionTemplate`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TemplateValues {
+    pub mint: Option<Pubkey>,
+    pub amount: Option<u64>,
+    pub recipient: Option<Pubkey>,
+}
+
+/// A value of an instruction that changes between instances of a template.
+///
+/// - Mint: The token mint.
+/// - Recipient: The receiving wallet.
+/// - PayerTokenAccount: Associated token account of the payer for the mint, under the given token program.
+/// - RecipientTokenAccount: Associated token account of the recipient for the mint, under the given token program.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Placeholder {
+    Mint,
+    Recipient,
+    PayerTokenAccount(Pubkey),
+    RecipientTokenAccount(Pubkey),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TemplateAccount {
+    Static(AccountMeta),
+    Placeholder { placeholder: Placeholder, is_signer: bool, is_writable: bool },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TemplateInstruction {
+    program_id: Pubkey,
+    accounts: Vec<TemplateAccount>,
+    data: Vec<u8>,
+    // Offsets of little endian u64 amounts in the data
+    amount_offsets: Vec<usize>,
+}
+
+/// A captured instruction sequence whose mint, amount and recipient can be replaced without
+/// rebuilding the instructions. Static accounts such as programs and fee wallets are kept as is,
+/// associated token accounts of the payer and recipient are derived again for each instance.
+///
+/// ## Example
+///
+/// ```rust
+/// use solana_client::rpc_client::RpcClient;
+/// use solana_sdk::{pubkey::Pubkey, signer::keypair::Keypair};
+/// use easy_solana::write_transactions::{
+///     template::{TemplateValues, TransactionTemplate},
+///     transaction_builder::TransactionBuilder
+/// };
+///
+/// // Transfers look up their destination, answered here by a mock client
+/// let client = RpcClient::new_mock("succeeds".to_string());
+/// let keypair = Keypair::new();
+/// // Capture the template from a builder filled with sample values
+/// let sample_recipient = Pubkey::new_unique();
+/// let mut builder = TransactionBuilder::new(&client, &keypair);
+/// builder.transfer_sol(0.123456789, &keypair, &sample_recipient.to_string()).unwrap();
+/// let sample_values = TemplateValues { recipient: Some(sample_recipient), amount: Some(123_456_789), mint: None };
+/// let template = TransactionTemplate::capture(&builder, &sample_values);
+///
+/// // Reuse the template with other values
+/// let values = TemplateValues { recipient: Some(Pubkey::new_unique()), amount: Some(1_000_000), mint: None };
+/// let mut builder = TransactionBuilder::new(&client, &keypair);
+/// builder.add_template(&template, &values).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionTemplate {
+    payer: Pubkey,
+    instructions: Vec<TemplateInstruction>,
+}
+
+impl TransactionTemplate {
+    /// Captures the instructions of a builder. Accounts and data matching the sample values are
+    /// turned into placeholders, so sample values should be distinctive, e.g an unusual amount.
+    pub fn capture(builder: &TransactionBuilder<'_>, sample_values: &TemplateValues) -> Self {
+        let payer = builder.payer_keypair.pubkey();
+        let placeholders = sample_placeholders(&payer, sample_values);
+
+        let instructions = builder.instructions
+            .iter()
+            .map(|instruction| {
+                let accounts = instruction.accounts
+                    .iter()
+                    .map(|account_meta| {
+                        match placeholders.iter().find(|(pubkey, _)| *pubkey == account_meta.pubkey) {
+                            Some((_, placeholder)) => TemplateAccount::Placeholder {
+                                placeholder: *placeholder,
+                                is_signer: account_meta.is_signer,
+                                is_writable: account_meta.is_writable,
+                            },
+                            None => TemplateAccount::Static(account_meta.clone()),
+                        }
+                    })
+                    .collect();
+                let amount_offsets = match sample_values.amount {
+                    Some(amount) => find_amount_offsets(&instruction.data, amount),
+                    None => vec![],
+                };
+                TemplateInstruction {
+                    program_id: instruction.program_id,
+                    accounts,
+                    data: instruction.data.clone(),
+                    amount_offsets,
+                }
+            })
+            .collect();
+
+        TransactionTemplate { payer, instructions }
+    }
+
+    /// Creates the instructions of the template with the given values.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::InstructionError` if a value used by the template is missing.
+    pub fn instantiate(&self, values: &TemplateValues) -> Result<Vec<Instruction>, TransactionBuilderError> {
+        self.instructions
+            .iter()
+            .map(|template_instruction| {
+                let accounts = template_instruction.accounts
+                    .iter()
+                    .map(|account| match account {
+                        TemplateAccount::Static(account_meta) => Ok(account_meta.clone()),
+                        TemplateAccount::Placeholder { placeholder, is_signer, is_writable } => {
+                            let pubkey = self.resolve_placeholder(placeholder, values)?;
+                            Ok(AccountMeta { pubkey, is_signer: *is_signer, is_writable: *is_writable })
+                        }
+                    })
+                    .collect::<Result<Vec<AccountMeta>, TransactionBuilderError>>()?;
+
+                let mut data = template_instruction.data.clone();
+                if !template_instruction.amount_offsets.is_empty() {
+                    let amount = values.amount.ok_or(missing_value("amount"))?;
+                    for offset in &template_instruction.amount_offsets {
+                        data[*offset..*offset + 8].copy_from_slice(&amount.to_le_bytes());
+                    }
+                }
+
+                Ok(Instruction {
+                    program_id: template_instruction.program_id,
+                    accounts,
+                    data,
+                })
+            })
+            .collect()
+    }
+
+    fn resolve_placeholder(&self, placeholder: &Placeholder, values: &TemplateValues) -> Result<Pubkey, TransactionBuilderError> {
+        let mint = values.mint.ok_or(missing_value("mint"));
+        let recipient = values.recipient.ok_or(missing_value("recipient"));
+        match placeholder {
+            Placeholder::Mint => mint,
+            Placeholder::Recipient => recipient,
+            Placeholder::PayerTokenAccount(token_program) => Ok(associated_token_account(&self.payer, &mint?, token_program)),
+            Placeholder::RecipientTokenAccount(token_program) => Ok(associated_token_account(&recipient?, &mint?, token_program)),
+        }
+    }
+}
+
+impl TransactionBuilder<'_> {
+    /// Adds the instructions of a template instantiated with the given values.
+    pub fn add_template(&mut self, template: &TransactionTemplate, values: &TemplateValues) -> Result<&mut Self, TransactionBuilderError> {
+        let instructions = template.instantiate(values)?;
+        self.instructions.extend(instructions);
+        Ok(self)
+    }
+}
+
+/// Accounts that correspond to sample values, derived accounts of both token programs included.
+fn sample_placeholders(payer: &Pubkey, sample_values: &TemplateValues) -> Vec<(Pubkey, Placeholder)> {
+    let mut placeholders = Vec::new();
+    if let Some(mint) = sample_values.mint {
+        placeholders.push((mint, Placeholder::Mint));
+        for token_program in [token_program(), token_2022_program()] {
+            placeholders.push((associated_token_account(payer, &mint, &token_program), Placeholder::PayerTokenAccount(token_program)));
+            if let Some(recipient) = sample_values.recipient {
+                placeholders.push((associated_token_account(&recipient, &mint, &token_program), Placeholder::RecipientTokenAccount(token_program)));
+            }
+        }
+    }
+    if let Some(recipient) = sample_values.recipient {
+        placeholders.push((recipient, Placeholder::Recipient));
+    }
+    placeholders
+}
+
+fn find_amount_offsets(data: &[u8], amount: u64) -> Vec<usize> {
+    let amount_bytes = amount.to_le_bytes();
+    data.windows(8)
+        .enumerate()
+        .filter(|(_, window)| *window == amount_bytes)
+        .map(|(offset, _)| offset)
+        .collect()
+}
+
+fn associated_token_account(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    let (associated_token_account, _nonce) = Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &associated_token_account_program()
+    );
+    associated_token_account
+}
+
+fn missing_value(name: &str) -> TransactionBuilderError {
+    TransactionBuilderError::InstructionError(format!("Template value {} is missing", name))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signer::keypair::Keypair;
+    use spl_associated_token_account::get_associated_token_address;
+    use crate::test_utils::mock_client;
+
+    #[test]
+    fn test_instantiate_template() {
+        let client = mock_client(vec![]);
+        let keypair = Keypair::new();
+        let sample_values = TemplateValues {
+            mint: Some(Pubkey::new_unique()),
+            amount: Some(987_654_321),
+            recipient: Some(Pubkey::new_unique()),
+        };
+
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder
+            .set_compute_limit(200_000)
+            .create_associated_token_account_for_others(
+                &sample_values.mint.unwrap().to_string(),
+                &sample_values.recipient.unwrap().to_string(),
+                token_program()
+            )
+            .unwrap()
+            .transfer_sol(0.987654321, &keypair, &sample_values.recipient.unwrap().to_string())
+            .unwrap();
+        let template = TransactionTemplate::capture(&builder, &sample_values);
+
+        let values = TemplateValues {
+            mint: Some(Pubkey::new_unique()),
+            amount: Some(5_000),
+            recipient: Some(Pubkey::new_unique()),
+        };
+        let instructions = template.instantiate(&values).unwrap();
+        assert!(instructions[0] == builder.instructions[0]);
+        let create_instruction = &instructions[1];
+        let recipient_token_account = get_associated_token_address(&values.recipient.unwrap(), &values.mint.unwrap());
+        assert!(create_instruction.accounts[1].pubkey == recipient_token_account);
+        assert!(create_instruction.accounts[2].pubkey == values.recipient.unwrap());
+        assert!(create_instruction.accounts[3].pubkey == values.mint.unwrap());
+        let transfer_instruction = &instructions[2];
+        assert!(transfer_instruction.accounts[1].pubkey == values.recipient.unwrap());
+        assert!(transfer_instruction.data[4..12] == 5_000_u64.to_le_bytes());
+    }
+
+    #[test]
+    fn failing_test_instantiate_template_with_missing_value() {
+        let client = mock_client(vec![]);
+        let keypair = Keypair::new();
+        let recipient = Pubkey::new_unique();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder.transfer_sol(0.1, &keypair, &recipient.to_string()).unwrap();
+
+        let template = TransactionTemplate::capture(&builder, &TemplateValues { recipient: Some(recipient), ..Default::default() });
+        let result = template.instantiate(&TemplateValues::default());
+        assert!(matches!(result, Err(TransactionBuilderError::InstructionError(_))));
+    }
+}
@@ -1,19 +1,26 @@
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
-    compute_budget::ComputeBudgetInstruction, signer::{
+    compute_budget::ComputeBudgetInstruction, hash::Hash, pubkey::Pubkey, signer::{
         keypair::Keypair,
         Signer
     }, transaction::Transaction, instruction::Instruction
 };
 
 use crate::error::TransactionBuilderError;
+use super::{fee::FeeConfig, policy::TransactionPolicy};
 
 
+#[derive(Clone)]
 pub struct TransactionBuilder<'a> {
     pub client: &'a RpcClient,
     pub payer_keypair: &'a Keypair,
     pub instructions: Vec<Instruction>,
     pub signing_keypairs: Vec<&'a Keypair>,
+    pub fee_config: Option<FeeConfig>,
+    pub fee_payer: Option<&'a Keypair>,
+    pub policy: Option<TransactionPolicy>,
+    pub lookup_tables: Vec<Pubkey>,
+    pub allow_unsafe_destinations: bool,
 }
 
 impl<'a> TransactionBuilder<'a> {
@@ -23,6 +30,11 @@ impl<'a> TransactionBuilder<'a> {
             payer_keypair,
             instructions: Vec::new(),
             signing_keypairs: Vec::new(),
+            fee_config: None,
+            fee_payer: None,
+            policy: None,
+            lookup_tables: Vec::new(),
+            allow_unsafe_destinations: false,
         }
     }
 
@@ -38,12 +50,102 @@ impl<'a> TransactionBuilder<'a> {
         self
     }
 
+    /// Sets a fee payer different from `payer_keypair`, which then only signs the instructions
+    /// that require it, e.g for sponsored transactions.
+    pub fn set_fee_payer(&mut self, fee_payer: &'a Keypair) -> &mut Self {
+        self.fee_payer = Some(fee_payer);
+        self
+    }
+
+    /// Allows transfers to destinations rejected by default: mints, programs and token accounts,
+    /// e.g to fund a program owned account on purpose. Funds sent to such accounts are usually lost.
+    pub fn allow_unsafe_destinations(&mut self) -> &mut Self {
+        self.allow_unsafe_destinations = true;
+        self
+    }
+
+    /// Account paying the transaction fee, `payer_keypair` unless a fee payer was set.
+    pub fn fee_payer_pubkey(&self) -> Pubkey {
+        self.fee_payer.unwrap_or(self.payer_keypair).pubkey()
+    }
+
+    /// Builds and signs the transaction, after checking it against the policy of the builder.
     pub fn build(&self) -> Result<Transaction, TransactionBuilderError> {
-        let mut transaction = Transaction::new_with_payer(&self.instructions, Some(&self.payer_keypair.pubkey()));
+        self.check_policy()?;
         let recent_blockhash = self.client.get_latest_blockhash().map_err(|_| TransactionBuilderError::LatestBlockhashError)?;
-        let mut all_keypairs: Vec<&'a Keypair> = vec![self.payer_keypair];
-        all_keypairs.append(&mut self.signing_keypairs.clone());
+        Ok(self.sign_with_blockhash(recent_blockhash))
+    }
+
+    /// Builds and signs the transaction with a blockhash fetched beforehand, e.g from an `AccountCache`,
+    /// after checking it against the policy of the builder.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::PolicyViolation` if the instructions break the policy.
+    pub fn build_with_blockhash(&self, recent_blockhash: Hash) -> Result<Transaction, TransactionBuilderError> {
+        self.check_policy()?;
+        Ok(self.sign_with_blockhash(recent_blockhash))
+    }
+
+    /// Builds and signs the transaction without checking the policy.
+    fn sign_with_blockhash(&self, recent_blockhash: Hash) -> Transaction {
+        let mut transaction = Transaction::new_with_payer(&self.instructions, Some(&self.fee_payer_pubkey()));
+        let all_keypairs = self.required_keypairs(&transaction);
         transaction.sign(&all_keypairs, recent_blockhash);
-        Ok(transaction)
+        transaction
+    }
+
+    /// Keypairs of the builder required to sign the message of `transaction`, in signing order.
+    /// The payer keypair may not be required once a separate fee payer is set.
+    pub(crate) fn required_keypairs(&self, transaction: &Transaction) -> Vec<&'a Keypair> {
+        self.keypairs_for_signers(&transaction.message.account_keys[..transaction.message.header.num_required_signatures as usize])
+    }
+
+    /// Keypairs of the builder among `required_signers`, in signing order.
+    pub(crate) fn keypairs_for_signers(&self, required_signers: &[Pubkey]) -> Vec<&'a Keypair> {
+        let mut keypairs: Vec<&'a Keypair> = Vec::new();
+        let builder_keypairs = [self.fee_payer, Some(self.payer_keypair)]
+            .into_iter()
+            .flatten()
+            .chain(self.signing_keypairs.iter().copied());
+        for keypair in builder_keypairs {
+            let pubkey = keypair.pubkey();
+            if required_signers.contains(&pubkey) && !keypairs.iter().any(|signer| signer.pubkey() == pubkey) {
+                keypairs.push(keypair);
+            }
+        }
+        keypairs
     }
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::mock_client;
+
+    const WALLET_ADDRESS_1: &str = "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5";
+
+    #[test]
+    fn test_build_with_separate_fee_payer() {
+        let client = mock_client(vec![]);
+        let (payer_keypair, fee_payer) = (Keypair::new(), Keypair::new());
+
+        let mut builder = TransactionBuilder::new(&client, &payer_keypair);
+        builder
+            .set_fee_payer(&fee_payer)
+            .transfer_sol(0.001, &payer_keypair, WALLET_ADDRESS_1)
+            .unwrap();
+        let transaction = builder.build_with_blockhash(Hash::default()).unwrap();
+        assert!(transaction.message.account_keys[0] == fee_payer.pubkey());
+        assert!(transaction.signatures.len() == 2);
+        assert!(transaction.verify().is_ok());
+
+        // the payer keypair does not sign when no instruction requires it
+        let mut builder = TransactionBuilder::new(&client, &payer_keypair);
+        builder.set_fee_payer(&fee_payer).set_compute_limit(200_000);
+        let transaction = builder.build_with_blockhash(Hash::default()).unwrap();
+        assert!(transaction.signatures.len() == 1);
+        assert!(transaction.verify().is_ok());
+    }
+}
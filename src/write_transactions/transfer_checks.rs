@@ -0,0 +1,435 @@
+//! # Transfer Checks
+//!
+//! This module validates the destination of a transfer before any funds are sent, so that
+//! tokens are not sent to hijacked or frozen accounts, or to addresses made to look like
+//! a recent counterparty.
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::{
+    extension::{memo_transfer::MemoTransfer, transfer_hook::TransferHook, BaseStateWithExtensions, StateWithExtensions},
+    state::{Account as Token2022Account, AccountState, Mint as Token2022Mint}
+};
+use crate::{
+    constants::{
+        exchange_accounts::known_exchange_wallets,
+        solana_programs::{system_program, token_2022_program, token_program}
+    },
+    error::TransferCheckError,
+    utils::address_to_pubkey
+};
+
+/// Number of leading and trailing characters a poisoning address shares with the address it
+/// imitates. Wallets commonly shorten addresses to their first and last 4 characters.
+const MIN_SIMILAR_CHARS: usize = 3;
+
+/// Destination token account of a transfer that passed the checks.
+///
+/// ### Fields
+///
+/// - `address`: The associated token account of the destination wallet.
+/// - `exists`: Whether the account exists, it has to be created before the transfer if not.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DestinationTokenAccount {
+    pub address: Pubkey,
+    pub exists: bool,
+}
+
+/// Checks that the associated token account of `destination_wallet`, if it exists, belongs to the
+/// token program, holds `token_address`, is owned by the destination wallet and is not frozen.
+///
+/// ## Errors
+///
+/// - `TransferCheckError::WrongProgram` if the account is not owned by the token program.
+/// - `TransferCheckError::WrongOwner` if the token account owner was changed from the destination wallet.
+/// - `TransferCheckError::WrongMint` if the account holds another token.
+/// - `TransferCheckError::Frozen` if the account is frozen.
+pub fn check_destination_token_account(
+    client: &RpcClient,
+    destination_wallet: &str,
+    token_address: &str,
+    token_program: Pubkey
+) -> Result<DestinationTokenAccount, TransferCheckError> {
+    let wallet_pubkey = address_to_pubkey(destination_wallet)?;
+    let mint_pubkey = address_to_pubkey(token_address)?;
+    let address = get_associated_token_address_with_program_id(&wallet_pubkey, &mint_pubkey, &token_program);
+
+    let account = client.get_account_with_commitment(&address, client.commitment())?.value;
+    let Some(account) = account else {
+        return Ok(DestinationTokenAccount { address, exists: false });
+    };
+    if account.owner != token_program {
+        return Err(TransferCheckError::WrongProgram(account.owner));
+    }
+    validate_token_account_data(&account.data, &wallet_pubkey, &mint_pubkey)?;
+
+    Ok(DestinationTokenAccount { address, exists: true })
+}
+
+/// Validates the owner, mint and state of SPL Token or Token 2022 account data.
+pub fn validate_token_account_data(data: &[u8], expected_owner: &Pubkey, expected_mint: &Pubkey) -> Result<(), TransferCheckError> {
+    let token_account = StateWithExtensions::<Token2022Account>::unpack(data)
+        .map_err(|_| TransferCheckError::InvalidTokenAccount)?
+        .base;
+    if token_account.owner != *expected_owner {
+        return Err(TransferCheckError::WrongOwner { expected: *expected_owner, actual: token_account.owner });
+    }
+    if token_account.mint != *expected_mint {
+        return Err(TransferCheckError::WrongMint { expected: *expected_mint, actual: token_account.mint });
+    }
+    if token_account.state == AccountState::Frozen {
+        return Err(TransferCheckError::Frozen);
+    }
+    Ok(())
+}
+
+/// Kind of account at the address of a recipient.
+///
+/// - Unfunded: No account exists at the address yet, e.g a new wallet.
+/// - Wallet: A system account.
+/// - TokenAccount: A token account of `mint`, held by `owner`.
+/// - Mint: A token mint, which can not hold tokens.
+/// - Program: An executable program.
+/// - ProgramAccount: A data account owned by `program`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecipientKind {
+    Unfunded,
+    Wallet,
+    TokenAccount { owner: Pubkey, mint: Pubkey },
+    Mint,
+    Program,
+    ProgramAccount { program: Pubkey },
+}
+
+/// Report of `validate_recipient`.
+///
+/// ### Fields
+///
+/// - `address`: The recipient.
+/// - `kind`: Kind of account at the address.
+/// - `exchange`: Name of the exchange the address belongs to, if known. Exchanges may not credit
+///   transfers to their hot wallets.
+/// - `token_account`: Associated token account of the address for the mint.
+/// - `token_account_exists`: Whether the associated token account exists.
+/// - `token_2022`: Whether the mint is a Token 2022 mint.
+/// - `memo_required`: Whether the token account credited by a transfer requires a memo.
+/// - `transfer_hook_program`: Program the mint calls on every transfer, if any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecipientReport {
+    pub address: Pubkey,
+    pub kind: RecipientKind,
+    pub exchange: Option<String>,
+    pub token_account: Pubkey,
+    pub token_account_exists: bool,
+    pub token_2022: bool,
+    pub memo_required: bool,
+    pub transfer_hook_program: Option<Pubkey>,
+}
+
+impl RecipientReport {
+    /// Whether the address is a wallet, new or existing, to which tokens are sent through its
+    /// associated token account.
+    pub fn is_wallet(&self) -> bool {
+        matches!(self.kind, RecipientKind::Unfunded | RecipientKind::Wallet)
+    }
+}
+
+/// Reports what the recipient of a transfer of `token_address` is before anything is sent, to
+/// catch transfers to mints, programs or token accounts instead of wallets, transfers to exchange
+/// hot wallets, and tokens requiring a memo or a transfer hook. The accounts are read in a
+/// single request.
+///
+/// ## Errors
+///
+/// Returns `TransferCheckError::InvalidMint` if `token_address` is not a token mint, and
+/// `TransferCheckError::RpcClientError` if the accounts can not be fetched.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, write_transactions::transfer_checks::validate_recipient};
+///
+/// const USDC_TOKEN_ADDRESS: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let report = validate_recipient(&client, "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5", USDC_TOKEN_ADDRESS).unwrap();
+/// if !report.is_wallet() || report.exchange.is_some() {
+///     println!("Check the recipient: {:?}", report);
+/// }
+/// ```
+pub fn validate_recipient(client: &RpcClient, address: &str, token_address: &str) -> Result<RecipientReport, TransferCheckError> {
+    let address = address_to_pubkey(address)?;
+    let mint = address_to_pubkey(token_address)?;
+    // Both possible associated token accounts, so everything is fetched at once
+    let pubkeys = [
+        address,
+        mint,
+        get_associated_token_address_with_program_id(&address, &mint, &token_program()),
+        get_associated_token_address_with_program_id(&address, &mint, &token_2022_program()),
+    ];
+    let mut accounts = client.get_multiple_accounts(&pubkeys)?.into_iter();
+    let mut next_account = || accounts.next().flatten();
+    let [address_account, mint_account, token_account, token_2022_account]: [Option<Account>; 4] = std::array::from_fn(|_| next_account());
+
+    let mint_account = mint_account
+        .filter(|account| account.owner == token_program() || account.owner == token_2022_program())
+        .ok_or(TransferCheckError::InvalidMint(mint))?;
+    let mint_state = StateWithExtensions::<Token2022Mint>::unpack(&mint_account.data)
+        .map_err(|_| TransferCheckError::InvalidMint(mint))?;
+    let transfer_hook_program = mint_state
+        .get_extension::<TransferHook>()
+        .ok()
+        .and_then(|extension| Option::<Pubkey>::from(extension.program_id));
+    let token_2022 = mint_account.owner == token_2022_program();
+    let (token_account_address, token_account) = match token_2022 {
+        true => (pubkeys[3], token_2022_account),
+        false => (pubkeys[2], token_account),
+    };
+
+    let kind = recipient_kind(address_account.as_ref());
+    // Transfers to a token account credit it directly, otherwise its associated token account
+    let credited_account = match kind {
+        RecipientKind::TokenAccount { .. } => address_account.as_ref(),
+        _ => token_account.as_ref(),
+    };
+    let memo_required = credited_account
+        .and_then(|account| StateWithExtensions::<Token2022Account>::unpack(&account.data).ok())
+        .and_then(|token_account| token_account.get_extension::<MemoTransfer>().ok().map(|extension| bool::from(extension.require_incoming_transfer_memos)))
+        .unwrap_or(false);
+    let exchange = known_exchange_wallets()
+        .into_iter()
+        .find(|(wallet, _)| *wallet == address)
+        .map(|(_, exchange)| exchange.to_string());
+
+    Ok(RecipientReport {
+        address,
+        kind,
+        exchange,
+        token_account: token_account_address,
+        token_account_exists: token_account.is_some(),
+        token_2022,
+        memo_required,
+        transfer_hook_program,
+    })
+}
+
+/// Rejects transfer destinations that can not use `mint`: mints, programs and token accounts of
+/// another mint. `account` is the account at `destination`, `None` if it does not exist. Use the
+/// wrapped SOL mint for SOL transfers, so only wrapped SOL token accounts pass.
+///
+/// ## Errors
+///
+/// - `TransferCheckError::DestinationIsMint` if the destination is a mint.
+/// - `TransferCheckError::DestinationIsProgram` if the destination is a program.
+/// - `TransferCheckError::WrongMint` if the destination is a token account of another mint.
+pub fn check_transfer_destination(destination: &Pubkey, account: Option<&Account>, mint: &Pubkey) -> Result<(), TransferCheckError> {
+    match recipient_kind(account) {
+        RecipientKind::Mint => Err(TransferCheckError::DestinationIsMint(*destination)),
+        RecipientKind::Program => Err(TransferCheckError::DestinationIsProgram(*destination)),
+        RecipientKind::TokenAccount { mint: actual, .. } if actual != *mint => {
+            Err(TransferCheckError::WrongMint { expected: *mint, actual })
+        }
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn recipient_kind(account: Option<&Account>) -> RecipientKind {
+    let Some(account) = account else {
+        return RecipientKind::Unfunded;
+    };
+    if account.executable {
+        return RecipientKind::Program;
+    }
+    if account.owner == system_program() {
+        return RecipientKind::Wallet;
+    }
+    if account.owner == token_program() || account.owner == token_2022_program() {
+        if let Ok(token_account) = StateWithExtensions::<Token2022Account>::unpack(&account.data) {
+            return RecipientKind::TokenAccount { owner: token_account.base.owner, mint: token_account.base.mint };
+        }
+        if StateWithExtensions::<Token2022Mint>::unpack(&account.data).is_ok() {
+            return RecipientKind::Mint;
+        }
+    }
+    RecipientKind::ProgramAccount { program: account.owner }
+}
+
+/// A destination address resembling a recent counterparty without being the same address.
+///
+/// ### Fields
+///
+/// - `counterparty`: The recent counterparty the destination resembles.
+/// - `matching_prefix`: Number of leading characters both addresses share.
+/// - `matching_suffix`: Number of trailing characters both addresses share.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimilarAddress {
+    pub counterparty: String,
+    pub matching_prefix: usize,
+    pub matching_suffix: usize,
+}
+
+/// Flags a destination that differs from a recent counterparty only in its middle characters,
+/// which is how address poisoning attacks trick users copying addresses from their history.
+/// Returns the most similar counterparty, or `None` if the destination does not resemble any
+/// counterparty or is one of them.
+///
+/// ## Example
+///
+/// ```rust
+/// use easy_solana::write_transactions::transfer_checks::check_address_similarity;
+///
+/// let recent_counterparties = ["ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5"];
+/// let poisoned_address = "ACTCr9pDxe3Ss7cHtTXHRLmrGEvzM4fQKsqPYJ8W4Fa5";
+/// let similar_address = check_address_similarity(poisoned_address, &recent_counterparties);
+/// assert!(similar_address.is_some());
+/// ```
+pub fn check_address_similarity(destination: &str, recent_counterparties: &[&str]) -> Option<SimilarAddress> {
+    if recent_counterparties.contains(&destination) {
+        return None;
+    }
+
+    recent_counterparties
+        .iter()
+        .map(|counterparty| SimilarAddress {
+            counterparty: counterparty.to_string(),
+            matching_prefix: destination.chars().zip(counterparty.chars()).take_while(|(a, b)| a == b).count(),
+            matching_suffix: destination.chars().rev().zip(counterparty.chars().rev()).take_while(|(a, b)| a == b).count(),
+        })
+        .filter(|similar| similar.matching_prefix >= MIN_SIMILAR_CHARS && similar.matching_suffix >= MIN_SIMILAR_CHARS)
+        .max_by_key(|similar| similar.matching_prefix + similar.matching_suffix)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::{program_option::COption, program_pack::Pack};
+    use spl_token::state::{Account as SplTokenAccount, AccountState as SplAccountState};
+    use spl_token::state::Mint as SplMint;
+    use crate::test_utils::{account_info_response, account_with_data, mock_client, multiple_accounts_response};
+
+    fn token_account_data(owner: Pubkey, mint: Pubkey, state: SplAccountState) -> Vec<u8> {
+        let token_account = SplTokenAccount {
+            mint,
+            owner,
+            amount: 0,
+            delegate: COption::None,
+            state,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        };
+        let mut data = vec![0; SplTokenAccount::LEN];
+        SplTokenAccount::pack(token_account, &mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_validate_token_account_data() {
+        let (owner, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let data = token_account_data(owner, mint, SplAccountState::Initialized);
+        assert!(validate_token_account_data(&data, &owner, &mint).is_ok());
+
+        let hijacked_data = token_account_data(Pubkey::new_unique(), mint, SplAccountState::Initialized);
+        assert!(matches!(validate_token_account_data(&hijacked_data, &owner, &mint), Err(TransferCheckError::WrongOwner { .. })));
+
+        let frozen_data = token_account_data(owner, mint, SplAccountState::Frozen);
+        assert!(matches!(validate_token_account_data(&frozen_data, &owner, &mint), Err(TransferCheckError::Frozen)));
+    }
+
+    #[test]
+    fn test_check_destination_token_account() {
+        let (wallet, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let account = account_with_data(token_program(), token_account_data(wallet, mint, SplAccountState::Initialized));
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&account), 1)),
+            (RpcRequest::GetAccountInfo, json!({ "context": { "slot": 1 }, "value": null })),
+        ]);
+
+        let destination = check_destination_token_account(&client, &wallet.to_string(), &mint.to_string(), token_program()).unwrap();
+        assert!(destination.exists);
+        assert!(destination.address == get_associated_token_address_with_program_id(&wallet, &mint, &token_program()));
+
+        let destination = check_destination_token_account(&client, &wallet.to_string(), &mint.to_string(), token_program()).unwrap();
+        assert!(!destination.exists);
+    }
+
+    fn mint_account() -> Account {
+        let mut data = vec![0; SplMint::LEN];
+        SplMint::pack(SplMint { decimals: 6, is_initialized: true, ..Default::default() }, &mut data).unwrap();
+        account_with_data(token_program(), data)
+    }
+
+    #[test]
+    fn test_validate_recipient() {
+        let (wallet, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let token_account = account_with_data(token_program(), token_account_data(wallet, mint, SplAccountState::Initialized));
+        let (exchange_wallet, exchange) = known_exchange_wallets()[0];
+        let client = mock_client(vec![
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(account_with_data(system_program(), vec![])), Some(mint_account()), Some(token_account.clone()), None], 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(token_account), Some(mint_account()), None, None], 1)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[None, Some(mint_account()), None, None], 1)),
+        ]);
+
+        let report = validate_recipient(&client, &wallet.to_string(), &mint.to_string()).unwrap();
+        assert!(report.is_wallet() && report.exchange.is_none());
+        assert!(report.token_account == get_associated_token_address_with_program_id(&wallet, &mint, &token_program()));
+        assert!(report.token_account_exists && !report.token_2022 && !report.memo_required);
+
+        // sending to a token account instead of its owner
+        let address = Pubkey::new_unique();
+        let report = validate_recipient(&client, &address.to_string(), &mint.to_string()).unwrap();
+        assert!(report.kind == RecipientKind::TokenAccount { owner: wallet, mint } && !report.token_account_exists);
+
+        let report = validate_recipient(&client, &exchange_wallet.to_string(), &mint.to_string()).unwrap();
+        assert!(report.kind == RecipientKind::Unfunded && report.exchange.as_deref() == Some(exchange));
+    }
+
+    #[test]
+    fn failing_test_validate_recipient_of_non_mint() {
+        let mint = Pubkey::new_unique();
+        let client = mock_client(vec![
+            (RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[None, Some(account_with_data(system_program(), vec![])), None, None], 1)),
+        ]);
+        let result = validate_recipient(&client, &Pubkey::new_unique().to_string(), &mint.to_string());
+        assert!(matches!(result, Err(TransferCheckError::InvalidMint(invalid_mint)) if invalid_mint == mint));
+    }
+
+    #[test]
+    fn test_check_transfer_destination() {
+        let (destination, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let token_account = account_with_data(token_program(), token_account_data(Pubkey::new_unique(), mint, SplAccountState::Initialized));
+        let program = Account { executable: true, ..account_with_data(Pubkey::new_unique(), vec![]) };
+        assert!(check_transfer_destination(&destination, None, &mint).is_ok());
+        assert!(check_transfer_destination(&destination, Some(&account_with_data(system_program(), vec![])), &mint).is_ok());
+        assert!(check_transfer_destination(&destination, Some(&token_account), &mint).is_ok());
+
+        let other_mint = Pubkey::new_unique();
+        let result = check_transfer_destination(&destination, Some(&token_account), &other_mint);
+        assert!(matches!(result, Err(TransferCheckError::WrongMint { expected, actual }) if expected == other_mint && actual == mint));
+        let result = check_transfer_destination(&destination, Some(&mint_account()), &mint);
+        assert!(matches!(result, Err(TransferCheckError::DestinationIsMint(address)) if address == destination));
+        let result = check_transfer_destination(&destination, Some(&program), &mint);
+        assert!(matches!(result, Err(TransferCheckError::DestinationIsProgram(_))));
+    }
+
+    #[test]
+    fn test_check_address_similarity() {
+        const COUNTERPARTY: &str = "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5";
+        const POISONED_ADDRESS: &str = "ACTCr9pDxe3Ss7cHtTXHRLmrGEvzM4fQKsqPYJ8W4Fa5";
+        const UNRELATED_ADDRESS: &str = "joNASGVYc6ugNiUCsamrJ8i2PBoxFW9YvqNisNfFNXg";
+        let recent_counterparties = [UNRELATED_ADDRESS, COUNTERPARTY];
+
+        let similar_address = check_address_similarity(POISONED_ADDRESS, &recent_counterparties).unwrap();
+        assert!(similar_address.counterparty == COUNTERPARTY);
+        assert!(similar_address.matching_prefix == 4);
+        assert!(similar_address.matching_suffix == 4);
+
+        // known counterparties and unrelated addresses are not flagged
+        assert!(check_address_similarity(COUNTERPARTY, &recent_counterparties).is_none());
+        assert!(check_address_similarity(&Pubkey::new_unique().to_string(), &[COUNTERPARTY]).is_none());
+    }
+}
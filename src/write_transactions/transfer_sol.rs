@@ -1,18 +1,66 @@
+use solana_client::rpc_client::RpcClient;
 use solana_program::system_instruction;
 use solana_sdk::{
-    native_token::LAMPORTS_PER_SOL,
+    message::Message,
+    pubkey::Pubkey,
     signature::{
         Keypair, 
         Signer
     }
 };
-use crate::{error::TransactionBuilderError, utils::address_to_pubkey};
-use super::transaction_builder::TransactionBuilder;
+use crate::{
+    constants::solana_programs::sol_pubkey,
+    error::TransactionBuilderError,
+    utils::{address_to_pubkey, sol_to_lamports}
+};
+use super::{transaction_builder::TransactionBuilder, transfer_checks::check_transfer_destination};
+
+/// Gets the lamports a wallet can transfer in a single SOL transfer paid by itself, which is
+/// its balance minus the transaction fee and the minimum balance for rent exemption.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, write_transactions::transfer_sol::max_transferable_sol};
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let lamports = max_transferable_sol(&client, "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5").unwrap();
+/// ```
+pub fn max_transferable_sol(client: &RpcClient, wallet_address: &str) -> Result<u64, TransactionBuilderError> {
+    let wallet_pubkey = address_to_pubkey(wallet_address)?;
+    let transfer_instruction = system_instruction::transfer(&wallet_pubkey, &wallet_pubkey, 0);
+    let recent_blockhash = client.get_latest_blockhash()?;
+    let message = Message::new_with_blockhash(&[transfer_instruction], Some(&wallet_pubkey), &recent_blockhash);
+    transferable_lamports(client, &wallet_pubkey, Some(&message))
+}
+
+/// Balance of `wallet` above the rent exempt minimum, minus the fee of `fee_message` if the
+/// wallet pays for it.
+fn transferable_lamports(client: &RpcClient, wallet_pubkey: &Pubkey, fee_message: Option<&Message>) -> Result<u64, TransactionBuilderError> {
+    let account = client.get_account(wallet_pubkey)?;
+    let minimum_sol_for_rent_exemption = client.get_minimum_balance_for_rent_exemption(account.data.len())?;
+    let fee = match fee_message {
+        Some(message) => client.get_fee_for_message(message)?,
+        None => 0,
+    };
+    Ok(account.lamports.saturating_sub(minimum_sol_for_rent_exemption).saturating_sub(fee))
+}
 
 impl<'a> TransactionBuilder<'a> {
+    /// Transfers `amount` SOL, rounded to the nearest lamport. See `transfer_lamports`.
     pub fn transfer_sol(&mut self, amount: f64, from_keypair: &'a Keypair, destination_address: &str) -> Result<&mut Self, TransactionBuilderError> {
+        self.transfer_lamports(sol_to_lamports(amount), from_keypair, destination_address)
+    }
+
+    /// Transfers exactly `lamports` from `from_keypair` to `destination_address`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::UnsafeDestination` if the destination is a mint, a
+    /// program or a token account other than wrapped SOL, see `allow_unsafe_destinations`.
+    pub fn transfer_lamports(&mut self, lamports: u64, from_keypair: &'a Keypair, destination_address: &str) -> Result<&mut Self, TransactionBuilderError> {
         let destination_pubkey = address_to_pubkey(destination_address)?;
-        let lamports = (amount * LAMPORTS_PER_SOL as f64) as u64;
+        self.check_sol_destination(&destination_pubkey)?;
         let instruction = system_instruction::transfer(&from_keypair.pubkey(), &destination_pubkey, lamports);
         self.instructions.push(instruction);
         
@@ -22,16 +70,68 @@ impl<'a> TransactionBuilder<'a> {
         }
         Ok(self)
     }
+
+    /// Transfers all SOL of `from_keypair` except the rent exempt minimum. The fee of the
+    /// transaction, including the instructions already added, is left in the account if
+    /// `from_keypair` is the fee payer.
+    ///
+    /// The amount is fixed when this is called, so the priority fee and any other instruction
+    /// have to be added before. A compute unit price set afterwards raises the fee above what was
+    /// left in the account and the transaction fails.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::InsufficientFunds` if there is nothing to transfer, and
+    /// `TransactionBuilderError::UnsafeDestination` like `transfer_lamports`.
+    pub fn transfer_all_sol(&mut self, from_keypair: &'a Keypair, destination_address: &str) -> Result<&mut Self, TransactionBuilderError> {
+        let destination_pubkey = address_to_pubkey(destination_address)?;
+        self.check_sol_destination(&destination_pubkey)?;
+        let from_pubkey = from_keypair.pubkey();
+
+        let fee_message = if from_pubkey == self.fee_payer_pubkey() {
+            let mut instructions = self.instructions.clone();
+            instructions.push(system_instruction::transfer(&from_pubkey, &destination_pubkey, 0));
+            let recent_blockhash = self.client.get_latest_blockhash()?;
+            Some(Message::new_with_blockhash(&instructions, Some(&from_pubkey), &recent_blockhash))
+        } else {
+            None
+        };
+        let lamports = transferable_lamports(self.client, &from_pubkey, fee_message.as_ref())?;
+        if lamports == 0 {
+            return Err(TransactionBuilderError::InsufficientFunds);
+        }
+
+        self.instructions.push(system_instruction::transfer(&from_pubkey, &destination_pubkey, lamports));
+        if from_pubkey != self.payer_keypair.pubkey() {
+            self.signing_keypairs.push(from_keypair);
+        }
+        Ok(self)
+    }
+
+    /// Checks that SOL can be sent to `destination`, unless unsafe destinations are allowed.
+    fn check_sol_destination(&self, destination: &Pubkey) -> Result<(), TransactionBuilderError> {
+        if self.allow_unsafe_destinations {
+            return Ok(());
+        }
+        let account = self.client.get_account_with_commitment(destination, self.client.commitment())?.value;
+        check_transfer_destination(destination, account.as_ref(), &sol_pubkey()).map_err(TransactionBuilderError::UnsafeDestination)
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use regex::Regex;
     use dotenv::dotenv;
     use std::env;
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::account::Account;
+    use solana_system_interface::instruction::SystemInstruction;
     use crate::{
+        constants::solana_programs::system_program,
+        error::TransferCheckError,
+        test_utils::{account_info_response, account_with_data, mock_client},
         utils::create_rpc_client,
         write_transactions::utils::simulate_transaction
     };
@@ -66,40 +166,89 @@ mod tests {
         let private_key = env::var("PRIVATE_KEY_1").expect("Cannot find PRIVATE_KEY_1 env var");
         let client = create_rpc_client("RPC_URL");
         let keypair = Keypair::from_base58_string(&private_key);
-        let simulated_transaction = TransactionBuilder::new(&client, &keypair)
-            .set_compute_units(50_000)
-            .set_compute_limit(1_000_000)
-            .transfer_sol(1_000_000.0, &keypair, WALLET_ADDRESS_2)
-            .unwrap() // transaction builder error
-            .build()
-            .unwrap();
-        let simulation_result = simulate_transaction(&client, simulated_transaction).unwrap();
-        // 134359540.0
-        let mut transfer_amount = 0.0;
-        let re = Regex::new(r"Transfer: insufficient lamports (\d+), need \d+").unwrap();
-        for log in simulation_result.transaction_logs {
-            if let Some(caps) = re.captures(&log) {
-                // Extract the first capture group and parse it as f64.
-                if let Some(lamports_str) = caps.get(1) {
-                    if let Ok(lamports) = lamports_str.as_str().parse::<f64>() {
-                        transfer_amount = lamports;
-                    }
-                }
-            }
+        let max_lamports = max_transferable_sol(&client, &keypair.pubkey().to_string()).unwrap();
+
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder.transfer_all_sol(&keypair, WALLET_ADDRESS_2).unwrap();
+        match bincode::deserialize(&builder.instructions[0].data).unwrap() {
+            SystemInstruction::Transfer { lamports } => assert!(lamports == max_lamports),
+            instruction => panic!("Expected transfer, but got {:?}", instruction),
         }
-        let wallet_data_length = client.get_account_data(&keypair.pubkey()).unwrap().len();
-        let minimum_sol_for_rent_exemption = client.get_minimum_balance_for_rent_exemption(wallet_data_length).unwrap();
-        transfer_amount -= minimum_sol_for_rent_exemption as f64;
-        
-        let transfer_transaction = TransactionBuilder::new(&client, &keypair)
-            .set_compute_units(50_000)
-            .set_compute_limit(simulation_result.units_consumed)
-            .transfer_sol(transfer_amount / LAMPORTS_PER_SOL as f64, &keypair, WALLET_ADDRESS_2)
-            .unwrap() // transaction builder error
-            .build()
+
+        let simulation_result = simulate_transaction(&client, builder.build().unwrap()).unwrap();
+        assert!(simulation_result.error.is_none());
+    }
+
+    #[test]
+    fn test_max_transferable_sol() {
+        let wallet_account = account_with_data(system_program(), vec![]);
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&wallet_account), 1)),
+            (RpcRequest::GetMinimumBalanceForRentExemption, json!(890_880)),
+            (RpcRequest::GetFeeForMessage, json!({ "context": { "slot": 1 }, "value": 5_000 })),
+        ]);
+        let lamports = max_transferable_sol(&client, WALLET_ADDRESS_1).unwrap();
+        assert!(lamports == wallet_account.lamports - 890_880 - 5_000);
+    }
+
+    #[test]
+    fn test_transfer_sol_rounds_to_lamports() {
+        let client = mock_client(vec![]);
+        let keypair = Keypair::new();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder
+            .transfer_sol(1.005, &keypair, WALLET_ADDRESS_1)
+            .unwrap()
+            .transfer_lamports(1, &keypair, WALLET_ADDRESS_1)
             .unwrap();
+        let transferred_lamports: Vec<u64> = builder.instructions
+            .iter()
+            .map(|instruction| match bincode::deserialize(&instruction.data).unwrap() {
+                SystemInstruction::Transfer { lamports } => lamports,
+                instruction => panic!("Expected transfer, but got {:?}", instruction),
+            })
+            .collect();
+        assert!(transferred_lamports == vec![1_005_000_000, 1]);
+    }
+
+    #[test]
+    fn test_transfer_all_sol_builder() {
+        let keypair = Keypair::new();
+        let wallet_account = account_with_data(system_program(), vec![]);
+        let client = mock_client(vec![
+            // the destination is an unfunded wallet
+            (RpcRequest::GetAccountInfo, account_info_response(None, 1)),
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&wallet_account), 1)),
+            (RpcRequest::GetMinimumBalanceForRentExemption, json!(0)),
+            (RpcRequest::GetFeeForMessage, json!({ "context": { "slot": 1 }, "value": 5_000 })),
+            (RpcRequest::GetAccountInfo, account_info_response(None, 1)),
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&account_with_data(system_program(), vec![])), 1)),
+            (RpcRequest::GetMinimumBalanceForRentExemption, json!(wallet_account.lamports)),
+        ]);
+
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder.transfer_all_sol(&keypair, WALLET_ADDRESS_2).unwrap();
+        let transfer_data = &builder.instructions[0].data;
+        match bincode::deserialize(transfer_data).unwrap() {
+            SystemInstruction::Transfer { lamports } => assert!(lamports == wallet_account.lamports - 5_000),
+            instruction => panic!("Expected transfer, but got {:?}", instruction),
+        }
+
+        // nothing left above the rent exempt minimum
+        let result = builder.transfer_all_sol(&keypair, WALLET_ADDRESS_2);
+        assert!(matches!(result, Err(TransactionBuilderError::InsufficientFunds)));
+    }
+
+    #[test]
+    fn failing_test_transfer_sol_to_program() {
+        let program = Account { executable: true, ..account_with_data(Pubkey::new_unique(), vec![]) };
+        let client = mock_client(vec![(RpcRequest::GetAccountInfo, account_info_response(Some(&program), 1))]);
+        let keypair = Keypair::new();
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        let result = builder.transfer_sol(0.01, &keypair, WALLET_ADDRESS_2);
+        assert!(matches!(result, Err(TransactionBuilderError::UnsafeDestination(TransferCheckError::DestinationIsProgram(_)))));
 
-        let new_simulation_result = simulate_transaction(&client, transfer_transaction).unwrap();
-        assert!(new_simulation_result.error.is_none())
+        // the check is skipped once unsafe destinations are allowed
+        assert!(builder.allow_unsafe_destinations().transfer_sol(0.01, &keypair, WALLET_ADDRESS_2).is_ok());
     }
 }
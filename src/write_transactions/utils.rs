@@ -4,21 +4,36 @@ use solana_client::{
     rpc_config::{RpcSimulateTransactionConfig, RpcSendTransactionConfig}
 };
 use solana_sdk::{
-    signature::Signature, transaction::Transaction, transaction::TransactionError
+    bs58, commitment_config::{CommitmentConfig, CommitmentLevel}, instruction::Instruction, message::Message, pubkey::Pubkey, signature::Signature, transaction::Transaction, transaction::TransactionError
 };
-use solana_transaction_status_client_types::{UiInstruction, UiParsedInstruction};
+use solana_transaction_status_client_types::{option_serializer::OptionSerializer, TransactionStatus, UiInnerInstructions, UiInstruction, UiParsedInstruction};
+use serde::Serialize;
 use serde_json::{Value, Map};
-use crate::error::{WriteTransactionError, SimulationError};
+use std::{thread, time::{Duration, Instant}};
+use log::{info, warn};
+use crate::{
+    error::{WriteTransactionError, SimulationError},
+    json::{serialize_display, serialize_millis, serialize_millis_option, ToJson},
+    read_transactions::{
+        cpi_tree::{build_cpi_tree, CpiNode},
+        transaction::{fetch_transaction, InspectedInnerInstructions, InspectedInstruction}
+    },
+    utils::{address_to_pubkey, addresses_to_pubkeys, create_rpc_client}
+};
+
+/// Time between signature status checks while waiting for confirmation.
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_millis(500);
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct SimulationResult {
     pub transaction_logs: Vec<String>,
     pub units_consumed: u32,
     pub instructions: Vec<ParsedInstruction>,
+    pub cpi_tree: Vec<CpiNode>,
     pub error: Option<TransactionError>
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct ParsedInstruction {
     pub program: String,
     pub program_id: String, 
@@ -39,10 +54,31 @@ pub fn simulate_transaction(client: &RpcClient, transaction: Transaction) -> Res
         }
     )?;
     
-    parse_simulation_result(simulation_result.value)
+    parse_simulation_result(simulation_result.value, &transaction.message)
+}
+
+/// Simulates instructions with `wallet_address` as the fee payer without its keypair, e.g for a
+/// watch-only wallet. The transaction is left unsigned, as signatures are not verified when simulating.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use solana_sdk::pubkey::Pubkey;
+/// use solana_system_interface::instruction::transfer;
+/// use easy_solana::{create_rpc_client, write_transactions::utils::simulate_instructions_as};
+///
+/// let client = create_rpc_client("https://api.mainnet-beta.solana.com");
+/// let watched_wallet: Pubkey = "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5".parse().unwrap();
+/// let instructions = [transfer(&watched_wallet, &Pubkey::new_unique(), 1_000_000)];
+/// let simulation_result = simulate_instructions_as(&client, &instructions, &watched_wallet.to_string()).unwrap();
+/// ```
+pub fn simulate_instructions_as(client: &RpcClient, instructions: &[Instruction], wallet_address: &str) -> Result<SimulationResult, SimulationError> {
+    let wallet_pubkey = address_to_pubkey(wallet_address)?;
+    let transaction = Transaction::new_unsigned(Message::new(instructions, Some(&wallet_pubkey)));
+    simulate_transaction(client, transaction)
 }
 
-fn parse_simulation_result(simulation_result: RpcSimulateTransactionResult) -> Result<SimulationResult, SimulationError> {
+fn parse_simulation_result(simulation_result: RpcSimulateTransactionResult, message: &Message) -> Result<SimulationResult, SimulationError> {
     let logs = &simulation_result.logs.ok_or(SimulationError::NoLogsAvailable)?;
 
     let units_consumed = simulation_result.units_consumed.ok_or(SimulationError::NoUnitsConsumedAvailable)?;
@@ -73,33 +109,613 @@ fn parse_simulation_result(simulation_result: RpcSimulateTransactionResult) -> R
     })
     .collect();
 
+    let cpi_tree = build_simulation_cpi_tree(message, inner_instructions);
+
     Ok(SimulationResult {
         transaction_logs: logs.to_vec(),
         units_consumed: units_consumed as u32,
         instructions: parsed_instructions,
+        cpi_tree,
         error: simulation_result.err
     })
 }
 
-pub fn send_transaction_unchecked(client: &RpcClient, transaction: Transaction) -> Result<Signature, WriteTransactionError> {
-    let signature = client.send_transaction_with_config(
-        &transaction,
+/// Builds the CPI tree of a simulated transaction. Inner instructions parsed by the RPC
+/// only keep their program id, as their accounts and data are replaced by the parsed info.
+fn build_simulation_cpi_tree(message: &Message, inner_instructions: &[UiInnerInstructions]) -> Vec<CpiNode> {
+    let resolve_accounts = |indexes: &[u8]| -> Vec<Pubkey> {
+        indexes.iter().filter_map(|index| message.account_keys.get(*index as usize).copied()).collect()
+    };
+
+    let instructions: Vec<InspectedInstruction> = message.instructions
+        .iter()
+        .map(|instruction| InspectedInstruction {
+            program_id: message.account_keys[instruction.program_id_index as usize],
+            accounts: resolve_accounts(&instruction.accounts),
+            data: instruction.data.clone(),
+            stack_height: Some(1),
+        })
+        .collect();
+
+    let inspected_inner_instructions: Vec<InspectedInnerInstructions> = inner_instructions
+        .iter()
+        .map(|inner_instruction| InspectedInnerInstructions {
+            index: inner_instruction.index,
+            instructions: inner_instruction.instructions
+                .iter()
+                .filter_map(|instruction| match instruction {
+                    UiInstruction::Compiled(compiled_instruction) => Some(InspectedInstruction {
+                        program_id: *message.account_keys.get(compiled_instruction.program_id_index as usize)?,
+                        accounts: resolve_accounts(&compiled_instruction.accounts),
+                        data: bs58::decode(&compiled_instruction.data).into_vec().unwrap_or_default(),
+                        stack_height: compiled_instruction.stack_height,
+                    }),
+                    UiInstruction::Parsed(UiParsedInstruction::Parsed(parsed_instruction)) => Some(InspectedInstruction {
+                        program_id: address_to_pubkey(&parsed_instruction.program_id).ok()?,
+                        accounts: vec![],
+                        data: vec![],
+                        stack_height: parsed_instruction.stack_height,
+                    }),
+                    UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(decoded_instruction)) => Some(InspectedInstruction {
+                        program_id: address_to_pubkey(&decoded_instruction.program_id).ok()?,
+                        accounts: addresses_to_pubkeys(decoded_instruction.accounts.iter().map(String::as_str).collect()),
+                        data: bs58::decode(&decoded_instruction.data).into_vec().unwrap_or_default(),
+                        stack_height: decoded_instruction.stack_height,
+                    }),
+                })
+                .collect(),
+        })
+        .collect();
+
+    build_cpi_tree(&instructions, &inspected_inner_instructions)
+}
+
+/// Configuration for the send helpers.
+///
+/// ### Fields
+///
+/// - `skip_preflight`: Skips the simulation the RPC runs before forwarding the transaction.
+/// - `preflight_commitment`: Commitment of the preflight simulation, defaults to `commitment`.
+/// - `max_retries`: Number of times the RPC re-sends the transaction, `None` for the RPC default.
+/// - `commitment`: Commitment to wait for when confirming, `None` for the client's commitment.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SendConfig {
+    pub skip_preflight: bool,
+    pub preflight_commitment: Option<CommitmentLevel>,
+    pub max_retries: Option<usize>,
+    pub commitment: Option<CommitmentConfig>,
+}
+
+impl SendConfig {
+    fn rpc_send_config(&self) -> RpcSendTransactionConfig {
         RpcSendTransactionConfig {
-            skip_preflight: true,
-            preflight_commitment: None,
+            skip_preflight: self.skip_preflight,
+            preflight_commitment: self.preflight_commitment.or(self.commitment.map(|commitment| commitment.commitment)),
             encoding: None,
-            max_retries: None,
+            max_retries: self.max_retries,
             min_context_slot: None
         }
-    )?;
-    
-    Ok(signature)
+    }
 }
 
-pub fn send_and_confirm_transaction(client: &RpcClient, transaction: Transaction) -> Result<Signature, WriteTransactionError> {
-    let signature = client.send_and_confirm_transaction(
-        &transaction,
-    )?;
-    
+/// Sends a transaction without preflight checks or waiting for confirmation.
+pub fn send_transaction_unchecked(client: &RpcClient, transaction: Transaction) -> Result<Signature, WriteTransactionError> {
+    let config = SendConfig {
+        skip_preflight: true,
+        ..Default::default()
+    };
+    send_transaction_unchecked_with_config(client, transaction, &config)
+}
+
+/// Sends a transaction without waiting for confirmation.
+pub fn send_transaction_unchecked_with_config(client: &RpcClient, transaction: Transaction, config: &SendConfig) -> Result<Signature, WriteTransactionError> {
+    let signature = client.send_transaction_with_config(&transaction, config.rpc_send_config())?;
+
     Ok(signature)
-}
\ No newline at end of file
+}
+
+/// Outcome of a confirmed transaction.
+///
+/// ### Fields
+///
+/// - `signature`: Signature of the transaction.
+/// - `slot`: Slot the transaction landed in.
+/// - `fee`: Fee paid in lamports, `None` if the transaction details could not be fetched.
+/// - `compute_units`: Compute units consumed, `None` if unavailable.
+/// - `err`: The error of the transaction if it landed but failed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfirmedTransaction {
+    #[serde(serialize_with = "serialize_display")]
+    pub signature: Signature,
+    pub slot: u64,
+    pub fee: Option<u64>,
+    pub compute_units: Option<u64>,
+    pub err: Option<TransactionError>,
+}
+
+impl ConfirmedTransaction {
+    /// Returns the transaction if it succeeded, or the error built by `failed` from its
+    /// signature and error if it landed but failed.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// use easy_solana::{error::KeyRotationError, write_transactions::utils::ConfirmedTransaction};
+    ///
+    /// fn signature_of(confirmed_transaction: ConfirmedTransaction) -> Result<String, KeyRotationError> {
+    ///     let confirmed_transaction = confirmed_transaction.into_result(KeyRotationError::TransactionFailed)?;
+    ///     Ok(confirmed_transaction.signature.to_string())
+    /// }
+    /// ```
+    pub fn into_result<E>(self, failed: impl FnOnce(Signature, TransactionError) -> E) -> Result<Self, E> {
+        match self.err {
+            Some(err) => Err(failed(self.signature, err)),
+            None => Ok(self),
+        }
+    }
+}
+
+/// Sends a transaction and waits until it is confirmed with the client's commitment.
+pub fn send_and_confirm_transaction(client: &RpcClient, transaction: Transaction) -> Result<ConfirmedTransaction, WriteTransactionError> {
+    send_and_confirm_transaction_with_config(client, transaction, &SendConfig::default())
+}
+
+/// Sends a transaction and waits until it reaches the commitment of the config, then fetches
+/// its fee and compute units. Transactions that land but fail are returned with their `err` set.
+///
+/// ## Errors
+///
+/// Returns `WriteTransactionError::TransactionExpired` if the blockhash expired before the
+/// transaction was confirmed.
+pub fn send_and_confirm_transaction_with_config(
+    client: &RpcClient,
+    transaction: Transaction,
+    config: &SendConfig
+) -> Result<ConfirmedTransaction, WriteTransactionError> {
+    let signature = client.send_transaction_with_config(&transaction, config.rpc_send_config())?;
+    let commitment = config.commitment.unwrap_or(client.commitment());
+
+    let status = loop {
+        let status = client.get_signature_statuses(&[signature])?.value.remove(0);
+        match status {
+            Some(status) if status.err.is_some() || status.satisfies_commitment(commitment) => break status,
+            Some(_) => {}
+            None => {
+                if !client.is_blockhash_valid(&transaction.message.recent_blockhash, CommitmentConfig::processed())? {
+                    return Err(WriteTransactionError::TransactionExpired);
+                }
+            }
+        }
+        thread::sleep(CONFIRMATION_POLL_INTERVAL);
+    };
+
+    // Transaction details are best effort, the RPC may not serve the transaction yet
+    let meta = fetch_transaction(client, &signature)
+        .ok()
+        .and_then(|confirmed_transaction| confirmed_transaction.transaction.meta);
+    let compute_units = meta.as_ref().and_then(|meta| match meta.compute_units_consumed {
+        OptionSerializer::Some(compute_units) => Some(compute_units),
+        _ => None,
+    });
+
+    Ok(ConfirmedTransaction {
+        signature,
+        slot: status.slot,
+        fee: meta.map(|meta| meta.fee),
+        compute_units,
+        err: status.err,
+    })
+}
+
+/// Configuration for `send_transaction_idempotent`.
+///
+/// ### Fields
+///
+/// - `max_submissions`: Maximum number of times the transaction is submitted.
+/// - `retry_interval`: Time between status checks, and between submissions while the transaction has not landed.
+#[derive(Debug, Clone)]
+pub struct IdempotentSendConfig {
+    pub max_submissions: u32,
+    pub retry_interval: Duration,
+}
+
+impl Default for IdempotentSendConfig {
+    fn default() -> Self {
+        Self {
+            max_submissions: 5,
+            retry_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Result of `send_transaction_idempotent`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IdempotentSendResult {
+    #[serde(serialize_with = "serialize_display")]
+    pub signature: Signature,
+    pub slot: u64,
+    pub submissions: u32,
+}
+
+/// Sends a signed transaction with retries, checking the status of its signature before every
+/// submission so that a transaction which already landed is never re-sent. Unlike
+/// `send_transaction_unchecked`, send errors such as timeouts are retried instead of returned.
+///
+/// The function only returns once the outcome is certain: either the transaction was confirmed,
+/// or its blockhash expired and it can no longer land. Once the blockhash expired the status is
+/// looked up again in the transaction history, in case the transaction landed in the meantime.
+/// After `WriteTransactionError::TransactionExpired` it is safe to rebuild the transaction with a
+/// new blockhash and send it again.
+///
+/// ## Errors
+///
+/// Returns `WriteTransactionError::TransactionFailed` if the transaction landed with an error, and
+/// `WriteTransactionError::TransactionExpired` if its blockhash expired before it landed.
+pub fn send_transaction_idempotent(
+    client: &RpcClient,
+    transaction: &Transaction,
+    config: &IdempotentSendConfig
+) -> Result<IdempotentSendResult, WriteTransactionError> {
+    let signature = transaction.signatures[0];
+    let send_config = RpcSendTransactionConfig {
+        skip_preflight: true,
+        preflight_commitment: None,
+        encoding: None,
+        max_retries: Some(0),
+        min_context_slot: None
+    };
+    let mut submissions = 0;
+
+    loop {
+        let status = client.get_signature_statuses(&[signature])?.value.remove(0);
+        if let Some(result) = status.as_ref().and_then(|status| idempotent_send_result(signature, status, submissions)) {
+            return result;
+        }
+        if !client.is_blockhash_valid(&transaction.message.recent_blockhash, CommitmentConfig::processed())? {
+            // The transaction may have landed between the status check and the blockhash check
+            match client.get_signature_statuses_with_history(&[signature])?.value.remove(0) {
+                Some(status) => if let Some(result) = idempotent_send_result(signature, &status, submissions) {
+                    return result;
+                },
+                None => return Err(WriteTransactionError::TransactionExpired),
+            }
+        } else if status.is_none() && submissions < config.max_submissions {
+            // Once out of submissions keep waiting for the outcome of the transactions already sent
+            submissions += 1;
+            if let Err(err) = client.send_transaction_with_config(transaction, send_config) {
+                warn!("send_transaction_idempotent: submission {} of {} failed: {}", submissions, signature, err);
+            }
+        }
+        thread::sleep(config.retry_interval);
+    }
+}
+
+/// Result of a transaction whose status was found, its error if it landed but failed. `None`
+/// while the transaction is only processed, as it can still be dropped on a fork.
+fn idempotent_send_result(signature: Signature, status: &TransactionStatus, submissions: u32) -> Option<Result<IdempotentSendResult, WriteTransactionError>> {
+    if !status.satisfies_commitment(CommitmentConfig::confirmed()) {
+        return None;
+    }
+    if let Some(err) = &status.err {
+        return Some(Err(WriteTransactionError::TransactionFailed(err.clone())));
+    }
+    Some(Ok(IdempotentSendResult {
+        signature,
+        slot: status.slot,
+        submissions,
+    }))
+}
+
+/// Configuration for `send_aggressive`.
+///
+/// ### Fields
+///
+/// - `endpoints`: Additional RPC URLs or environment variable names to submit the transaction to.
+/// - `resend_interval`: Time between each round of submissions.
+/// - `max_submissions`: Maximum number of submissions across all endpoints, `None` for no limit.
+/// - `timeout`: Maximum time to wait for confirmation, the transaction also stops being sent once its blockhash expires.
+#[derive(Debug, Clone)]
+pub struct AggressiveSendConfig {
+    pub endpoints: Vec<String>,
+    pub resend_interval: Duration,
+    pub max_submissions: Option<u32>,
+    pub timeout: Duration,
+}
+
+impl Default for AggressiveSendConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            resend_interval: Duration::from_millis(500),
+            max_submissions: None,
+            timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Submission statistics of a single endpoint.
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointSubmissions {
+    pub url: String,
+    pub submissions: u32,
+    pub errors: u32,
+    /// Time from the start of sending until the endpoint first accepted the transaction.
+    #[serde(serialize_with = "serialize_millis_option")]
+    pub first_accepted_after: Option<Duration>,
+}
+
+/// Result of `send_aggressive`.
+///
+/// ### Fields
+///
+/// - `signature`: Signature of the confirmed transaction.
+/// - `slot`: Slot the transaction landed in.
+/// - `landed_endpoint`: The endpoint that accepted the transaction first, the most likely one to have landed it.
+/// - `endpoints`: Submission statistics of every endpoint, the primary client is always first.
+/// - `elapsed`: Time taken until confirmation.
+#[derive(Debug, Clone, Serialize)]
+pub struct AggressiveSendResult {
+    #[serde(serialize_with = "serialize_display")]
+    pub signature: Signature,
+    pub slot: u64,
+    pub landed_endpoint: Option<String>,
+    pub endpoints: Vec<EndpointSubmissions>,
+    #[serde(serialize_with = "serialize_millis")]
+    pub elapsed: Duration,
+}
+
+impl ToJson for SimulationResult {}
+impl ToJson for ConfirmedTransaction {}
+impl ToJson for IdempotentSendResult {}
+impl ToJson for AggressiveSendResult {}
+
+/// Re-submits the same signed transaction every `resend_interval` to the client and every
+/// endpoint in the config until it is confirmed, the blockhash expires or the timeout passes.
+/// Commonly used to land transactions during congested token launches.
+///
+/// As the same signed transaction is submitted, it can only be processed once no matter how
+/// many times it is sent. `max_submissions` caps the number of submissions to limit RPC usage.
+///
+/// ## Errors
+///
+/// Returns `WriteTransactionError::TransactionFailed` if the transaction landed with an error, and
+/// `WriteTransactionError::TransactionExpired` if it was not confirmed in time.
+pub fn send_aggressive(client: &RpcClient, transaction: Transaction, config: &AggressiveSendConfig) -> Result<AggressiveSendResult, WriteTransactionError> {
+    let signature = transaction.signatures[0];
+    let endpoint_clients: Vec<RpcClient> = config.endpoints
+        .iter()
+        .map(|endpoint| create_rpc_client(endpoint))
+        .collect();
+    let mut clients: Vec<&RpcClient> = vec![client];
+    clients.extend(endpoint_clients.iter());
+
+    let mut endpoints: Vec<EndpointSubmissions> = clients
+        .iter()
+        .map(|client| EndpointSubmissions {
+            url: client.url(),
+            submissions: 0,
+            errors: 0,
+            first_accepted_after: None,
+        })
+        .collect();
+
+    let send_config = RpcSendTransactionConfig {
+        skip_preflight: true,
+        preflight_commitment: None,
+        encoding: None,
+        max_retries: Some(0),
+        min_context_slot: None
+    };
+    let start_time = Instant::now();
+    let mut total_submissions: u32 = 0;
+
+    while start_time.elapsed() < config.timeout {
+        for (endpoint_client, endpoint) in clients.iter().zip(endpoints.iter_mut()) {
+            if config.max_submissions.is_some_and(|max_submissions| total_submissions >= max_submissions) {
+                break;
+            }
+            total_submissions += 1;
+            endpoint.submissions += 1;
+            match endpoint_client.send_transaction_with_config(&transaction, send_config) {
+                Ok(_) => {
+                    endpoint.first_accepted_after.get_or_insert(start_time.elapsed());
+                }
+                Err(err) => {
+                    endpoint.errors += 1;
+                    warn!("send_aggressive: {} rejected {}: {}", endpoint.url, signature, err);
+                }
+            }
+        }
+
+        thread::sleep(config.resend_interval);
+
+        let status = client.get_signature_statuses(&[signature])?.value.remove(0);
+        if let Some(status) = status {
+            if let Some(err) = status.err {
+                return Err(WriteTransactionError::TransactionFailed(err));
+            }
+            if status.satisfies_commitment(CommitmentConfig::confirmed()) {
+                let landed_endpoint = endpoints
+                    .iter()
+                    .filter_map(|endpoint| endpoint.first_accepted_after.map(|accepted_after| (accepted_after, endpoint)))
+                    .min_by_key(|(accepted_after, _)| *accepted_after)
+                    .map(|(_, endpoint)| endpoint.url.clone());
+                info!("send_aggressive: {} confirmed in slot {} after {} submissions", signature, status.slot, total_submissions);
+                return Ok(AggressiveSendResult {
+                    signature,
+                    slot: status.slot,
+                    landed_endpoint,
+                    endpoints,
+                    elapsed: start_time.elapsed(),
+                });
+            }
+        } else if !client.is_blockhash_valid(&transaction.message.recent_blockhash, CommitmentConfig::processed())? {
+            return Err(WriteTransactionError::TransactionExpired);
+        }
+    }
+
+    Err(WriteTransactionError::TransactionExpired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::{hash::Hash, signature::Keypair, signer::Signer};
+    use solana_system_interface::instruction::transfer;
+    use crate::test_utils::{mock_client, simulation_response};
+
+    fn signed_transaction() -> Transaction {
+        let keypair = Keypair::new();
+        let instruction = transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1_000);
+        Transaction::new_signed_with_payer(&[instruction], Some(&keypair.pubkey()), &[&keypair], Hash::new_unique())
+    }
+
+    fn unknown_status() -> Value {
+        json!({ "context": { "slot": 1 }, "value": [null] })
+    }
+
+    fn blockhash_valid(is_valid: bool) -> Value {
+        json!({ "context": { "slot": 1 }, "value": is_valid })
+    }
+
+    #[test]
+    fn test_simulate_instructions_as_watch_only_wallet() {
+        let watched_wallet = Pubkey::new_unique();
+        let client = mock_client(vec![(RpcRequest::SimulateTransaction, simulation_response(&["Program log: ok"], 150))]);
+        let instructions = [transfer(&watched_wallet, &Pubkey::new_unique(), 1_000)];
+
+        let simulation_result = simulate_instructions_as(&client, &instructions, &watched_wallet.to_string()).unwrap();
+        assert!(simulation_result.error.is_none());
+        assert!(simulation_result.units_consumed == 150);
+        assert!(simulation_result.transaction_logs == vec!["Program log: ok".to_string()]);
+        assert!(matches!(
+            simulate_instructions_as(&client, &instructions, "not an address"),
+            Err(SimulationError::InvalidAddress(_))
+        ));
+    }
+
+    #[test]
+    fn test_send_config_defaults_preflight_commitment() {
+        let config = SendConfig {
+            max_retries: Some(3),
+            commitment: Some(CommitmentConfig::finalized()),
+            ..Default::default()
+        };
+        let rpc_send_config = config.rpc_send_config();
+        assert!(!rpc_send_config.skip_preflight);
+        assert!(rpc_send_config.preflight_commitment == Some(CommitmentLevel::Finalized));
+        assert!(rpc_send_config.max_retries == Some(3));
+    }
+
+    #[test]
+    fn test_send_and_confirm_transaction_with_config() {
+        let config = SendConfig {
+            skip_preflight: true,
+            commitment: Some(CommitmentConfig::finalized()),
+            ..Default::default()
+        };
+        let transaction = signed_transaction();
+        // the default mock status is a finalized transaction
+        let client = mock_client(vec![]);
+        let confirmed_transaction = send_and_confirm_transaction_with_config(&client, transaction.clone(), &config).unwrap();
+        assert!(confirmed_transaction.signature == transaction.signatures[0]);
+        assert!(confirmed_transaction.slot == 1);
+        assert!(confirmed_transaction.fee == Some(0));
+        assert!(confirmed_transaction.err.is_none());
+    }
+
+    #[test]
+    fn test_send_and_confirm_failed_transaction() {
+        let failed_status = json!({ "context": { "slot": 1 }, "value": [{
+            "slot": 7,
+            "confirmations": 0,
+            "err": { "InstructionError": [0, { "Custom": 1 }] },
+            "status": { "Err": { "InstructionError": [0, { "Custom": 1 }] } },
+            "confirmationStatus": "processed"
+        }]});
+        let client = mock_client(vec![(RpcRequest::GetSignatureStatuses, failed_status)]);
+        let confirmed_transaction = send_and_confirm_transaction(&client, signed_transaction()).unwrap();
+        assert!(confirmed_transaction.slot == 7);
+        assert!(confirmed_transaction.err.is_some());
+
+        let signature = confirmed_transaction.signature;
+        let result = confirmed_transaction.into_result(|failed_signature, err| (failed_signature, err));
+        assert!(matches!(result, Err((failed_signature, TransactionError::InstructionError(0, _))) if failed_signature == signature));
+    }
+
+    #[test]
+    fn test_send_transaction_idempotent_retries_until_landed() {
+        let config = IdempotentSendConfig { max_submissions: 5, retry_interval: Duration::from_millis(1) };
+        // first submission times out and the transaction is not found, the second lands
+        let client = mock_client(vec![
+            (RpcRequest::GetSignatureStatuses, unknown_status()),
+            (RpcRequest::IsBlockhashValid, blockhash_valid(true)),
+            (RpcRequest::SendTransaction, json!(null)),
+            (RpcRequest::GetSignatureStatuses, unknown_status()),
+            (RpcRequest::IsBlockhashValid, blockhash_valid(true)),
+        ]);
+
+        let result = send_transaction_idempotent(&client, &signed_transaction(), &config).unwrap();
+        assert!(result.submissions == 2);
+        assert!(result.slot == 1);
+    }
+
+    #[test]
+    fn test_send_transaction_idempotent_skips_landed_transaction() {
+        // the default mock status is a finalized transaction
+        let client = mock_client(vec![]);
+        let result = send_transaction_idempotent(&client, &signed_transaction(), &IdempotentSendConfig::default()).unwrap();
+        assert!(result.submissions == 0);
+    }
+
+    #[test]
+    fn test_send_transaction_idempotent_finds_transaction_landed_before_expiry() {
+        // the blockhash expires right after the transaction landed, the history lookup finds it
+        let client = mock_client(vec![
+            (RpcRequest::GetSignatureStatuses, unknown_status()),
+            (RpcRequest::IsBlockhashValid, blockhash_valid(false)),
+            (RpcRequest::GetSignatureStatuses, json!({ "context": { "slot": 9 }, "value": [{
+                "slot": 8,
+                "confirmations": null,
+                "err": null,
+                "status": { "Ok": null },
+                "confirmationStatus": "finalized"
+            }]})),
+        ]);
+        let result = send_transaction_idempotent(&client, &signed_transaction(), &IdempotentSendConfig::default()).unwrap();
+        assert!(result.slot == 8);
+        assert!(result.submissions == 0);
+    }
+
+    #[test]
+    fn test_send_transaction_idempotent_waits_for_processed_transaction() {
+        let config = IdempotentSendConfig { max_submissions: 5, retry_interval: Duration::from_millis(1) };
+        let processed_status = json!({ "context": { "slot": 9 }, "value": [{
+            "slot": 8,
+            "confirmations": 0,
+            "err": null,
+            "status": { "Ok": null },
+            "confirmationStatus": "processed"
+        }]});
+        // the processed transaction is not re-sent, it is reported once confirmed
+        let client = mock_client(vec![
+            (RpcRequest::GetSignatureStatuses, processed_status),
+            (RpcRequest::IsBlockhashValid, blockhash_valid(true)),
+        ]);
+        let result = send_transaction_idempotent(&client, &signed_transaction(), &config).unwrap();
+        assert!(result.submissions == 0);
+        assert!(result.slot == 1);
+    }
+
+    #[test]
+    fn failing_test_send_transaction_idempotent_expired() {
+        let client = mock_client(vec![
+            (RpcRequest::GetSignatureStatuses, unknown_status()),
+            (RpcRequest::IsBlockhashValid, blockhash_valid(false)),
+            (RpcRequest::GetSignatureStatuses, unknown_status()),
+        ]);
+        let result = send_transaction_idempotent(&client, &signed_transaction(), &IdempotentSendConfig::default());
+        assert!(matches!(result, Err(WriteTransactionError::TransactionExpired)));
+    }
+}
@@ -0,0 +1,599 @@
+//! # Withdrawals
+//!
+//! This module processes a queue of SOL withdrawals from a custodial wallet, the counterpart of
+//! `deposits`. Every withdrawal is checked against an optional `TransactionPolicy`, signed, and
+//! persisted with its signed transaction before it is sent for the first time. After a crash the
+//! same transaction is resent instead of a new one, and a withdrawal is only signed again once
+//! its previous transaction can provably no longer land, so a withdrawal is never paid twice.
+//!
+//! Transactions use a recent blockhash by default. Withdrawals whose transactions keep expiring,
+//! e.g during congestion, fall back to a durable nonce account if one is configured.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use solana_client::{
+    nonce_utils::{data_from_account, get_account_with_commitment},
+    rpc_client::RpcClient,
+    rpc_config::RpcSendTransactionConfig
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::{Transaction, TransactionError}
+};
+use solana_system_interface::instruction::{advance_nonce_account, transfer};
+use crate::{
+    error::{PersistenceError, WithdrawalError, WriteTransactionError},
+    utils::address_to_pubkey
+};
+use super::{
+    policy::TransactionPolicy,
+    transaction_builder::TransactionBuilder,
+    utils::{send_transaction_idempotent, IdempotentSendConfig}
+};
+
+/// Configuration of a `WithdrawalQueue`.
+///
+/// ### Fields
+///
+/// - `send`: Submissions and retry interval of each send.
+/// - `nonce_fallback_after`: Number of expired transactions after which a withdrawal is signed
+///   with the durable nonce account, if the queue has one.
+#[derive(Debug, Clone)]
+pub struct WithdrawalConfig {
+    pub send: IdempotentSendConfig,
+    pub nonce_fallback_after: u32,
+}
+
+impl Default for WithdrawalConfig {
+    fn default() -> Self {
+        Self {
+            send: IdempotentSendConfig::default(),
+            nonce_fallback_after: 2,
+        }
+    }
+}
+
+/// State of a withdrawal. `Confirmed`, `Failed` and `Rejected` are final.
+///
+/// - Queued: Waiting to be signed, either new or after its last transaction expired.
+/// - Signed: A transaction was signed and may have been sent. `transaction` is the base64
+///   encoded transaction, `nonce_account` is set if it uses a durable nonce.
+/// - Confirmed: The transaction landed in `slot`.
+/// - Failed: The transaction landed with an error, the funds were not sent.
+/// - Rejected: The withdrawal violates the policy and was never signed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum WithdrawalStatus {
+    Queued,
+    Signed { signature: String, transaction: String, nonce_account: Option<String> },
+    Confirmed { signature: String, slot: u64 },
+    Failed { signature: String, error: String },
+    Rejected(String),
+}
+
+impl WithdrawalStatus {
+    /// Whether the withdrawal reached a final state.
+    pub fn is_final(&self) -> bool {
+        matches!(self, WithdrawalStatus::Confirmed { .. } | WithdrawalStatus::Failed { .. } | WithdrawalStatus::Rejected(_))
+    }
+}
+
+/// A withdrawal of `lamports` to `destination`.
+///
+/// ### Fields
+///
+/// - `id`: Id assigned when the withdrawal was enqueued.
+/// - `destination`: Address receiving the SOL.
+/// - `lamports`: Amount withdrawn.
+/// - `expired_transactions`: Number of signed transactions that expired before landing.
+/// - `status`: Current state of the withdrawal.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Withdrawal {
+    pub id: u64,
+    pub destination: String,
+    pub lamports: u64,
+    pub expired_transactions: u32,
+    pub status: WithdrawalStatus,
+}
+
+#[derive(Serialize, Deserialize)]
+struct QueueSnapshot {
+    next_id: u64,
+    withdrawals: Vec<Withdrawal>,
+}
+
+/// Outcome of sending a signed withdrawal transaction.
+enum SendOutcome {
+    Landed(u64),
+    Failed(TransactionError),
+    /// The transaction can no longer land.
+    Expired,
+    /// The outcome is unknown, the transaction is sent again on the next `process`.
+    Pending,
+}
+
+/// Queue of SOL withdrawals paid by a single wallet.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use easy_solana::{create_rpc_client, write_transactions::withdrawals::WithdrawalQueue, SecretKeypair};
+///
+/// let client = create_rpc_client("RPC_URL");
+/// let hot_wallet = SecretKeypair::from_env("PRIVATE_KEY_1").unwrap();
+/// let mut queue = WithdrawalQueue::load("withdrawals.json").unwrap_or_else(|_| WithdrawalQueue::new());
+/// queue.persist_to("withdrawals.json");
+///
+/// let id = queue.enqueue("ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5", 10_000_000).unwrap();
+/// for withdrawal in queue.process(&client, &hot_wallet).unwrap() {
+///     println!("Withdrawal {} is {:?}", withdrawal.id, withdrawal.status);
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WithdrawalQueue {
+    next_id: u64,
+    withdrawals: Vec<Withdrawal>,
+    config: WithdrawalConfig,
+    policy: Option<TransactionPolicy>,
+    nonce_account: Option<Pubkey>,
+    path: Option<PathBuf>,
+}
+
+impl WithdrawalQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(&mut self, config: WithdrawalConfig) -> &mut Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets the policy every withdrawal is checked against before it is signed.
+    pub fn with_policy(&mut self, policy: TransactionPolicy) -> &mut Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Sets the durable nonce account used by withdrawals whose transactions keep expiring. The
+    /// paying wallet has to be its nonce authority.
+    pub fn with_nonce_account(&mut self, nonce_account: Pubkey) -> &mut Self {
+        self.nonce_account = Some(nonce_account);
+        self
+    }
+
+    /// Saves the queue to `path` on every state transition. A withdrawal is only sent once its
+    /// signed transaction was saved.
+    pub fn persist_to(&mut self, path: impl AsRef<Path>) -> &mut Self {
+        self.path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Adds a withdrawal of `lamports` to `destination`, returning its id.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `WithdrawalError::InvalidAddress` for an invalid destination and
+    /// `WithdrawalError::PersistenceError` if the queue can not be saved.
+    pub fn enqueue(&mut self, destination: &str, lamports: u64) -> Result<u64, WithdrawalError> {
+        address_to_pubkey(destination)?;
+        let id = self.next_id;
+        self.next_id += 1;
+        self.withdrawals.push(Withdrawal {
+            id,
+            destination: destination.to_string(),
+            lamports,
+            expired_transactions: 0,
+            status: WithdrawalStatus::Queued,
+        });
+        self.persist()?;
+        Ok(id)
+    }
+
+    /// Every withdrawal in the order it was enqueued.
+    pub fn withdrawals(&self) -> &[Withdrawal] {
+        &self.withdrawals
+    }
+
+    pub fn withdrawal(&self, id: u64) -> Option<&Withdrawal> {
+        self.withdrawals.iter().find(|withdrawal| withdrawal.id == id)
+    }
+
+    /// Signs and sends every withdrawal that is not final, paid by `keypair`. Signed withdrawals,
+    /// e.g from before a crash, are resent with their saved transaction. Returns the withdrawals
+    /// that reached a final state.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `WithdrawalError::PersistenceError` if a transition can not be saved, no withdrawal
+    /// is sent after that. Withdrawals that can not be signed or sent because of RPC errors are
+    /// left as is and retried on the next call.
+    pub fn process(&mut self, client: &RpcClient, keypair: &Keypair) -> Result<Vec<Withdrawal>, WithdrawalError> {
+        let mut finalized = Vec::new();
+        for index in 0..self.withdrawals.len() {
+            if self.withdrawals[index].status == WithdrawalStatus::Queued {
+                match self.sign(client, keypair, &self.withdrawals[index]) {
+                    Ok(WithdrawalStatus::Rejected(reason)) => {
+                        self.transition(index, WithdrawalStatus::Rejected(reason))?;
+                        finalized.push(self.withdrawals[index].clone());
+                        continue;
+                    }
+                    Ok(status) => self.transition(index, status)?,
+                    Err(err) => {
+                        warn!("Unable to sign withdrawal {}: {}", self.withdrawals[index].id, err);
+                        continue;
+                    }
+                }
+            }
+
+            let WithdrawalStatus::Signed { signature, transaction, nonce_account } = &self.withdrawals[index].status else {
+                continue;
+            };
+            let signature = signature.clone();
+            let outcome = decode_transaction(transaction)
+                .and_then(|transaction| self.send(client, &transaction, nonce_account.as_deref()));
+            let status = match outcome {
+                Ok(SendOutcome::Landed(slot)) => WithdrawalStatus::Confirmed { signature, slot },
+                Ok(SendOutcome::Failed(err)) => WithdrawalStatus::Failed { signature, error: err.to_string() },
+                Ok(SendOutcome::Expired) => {
+                    self.withdrawals[index].expired_transactions += 1;
+                    WithdrawalStatus::Queued
+                }
+                Ok(SendOutcome::Pending) => continue,
+                Err(err) => {
+                    warn!("Unable to send withdrawal {}: {}", self.withdrawals[index].id, err);
+                    continue;
+                }
+            };
+            self.transition(index, status)?;
+            if self.withdrawals[index].status.is_final() {
+                finalized.push(self.withdrawals[index].clone());
+            }
+        }
+        Ok(finalized)
+    }
+
+    /// Checks the withdrawal against the policy and signs its transfer, using the durable nonce
+    /// once enough transactions expired.
+    fn sign(&self, client: &RpcClient, keypair: &Keypair, withdrawal: &Withdrawal) -> Result<WithdrawalStatus, WithdrawalError> {
+        let destination = address_to_pubkey(&withdrawal.destination)?;
+        let transfer_instruction = transfer(&keypair.pubkey(), &destination, withdrawal.lamports);
+        if let Some(policy) = &self.policy {
+            if let Err(err) = policy.evaluate_instructions(std::slice::from_ref(&transfer_instruction)) {
+                return Ok(WithdrawalStatus::Rejected(err.to_string()));
+            }
+        }
+
+        let mut builder = TransactionBuilder::new(client, keypair);
+        let nonce_account = self.nonce_account.filter(|_| withdrawal.expired_transactions >= self.config.nonce_fallback_after);
+        let transaction = match nonce_account {
+            Some(nonce_account) => {
+                // Advancing the nonce has to be the first instruction
+                builder.instructions.push(advance_nonce_account(&nonce_account, &keypair.pubkey()));
+                builder.instructions.push(transfer_instruction);
+                builder.build_with_blockhash(nonce_blockhash(client, &nonce_account)?)?
+            }
+            None => {
+                builder.instructions.push(transfer_instruction);
+                builder.build()?
+            }
+        };
+        let encoded_transaction = bincode::serialize(&transaction)
+            .map_err(|err| WithdrawalError::InvalidTransaction(err.to_string()))?;
+        Ok(WithdrawalStatus::Signed {
+            signature: transaction.signatures[0].to_string(),
+            transaction: STANDARD.encode(encoded_transaction),
+            nonce_account: nonce_account.map(|nonce_account| nonce_account.to_string()),
+        })
+    }
+
+    fn send(&self, client: &RpcClient, transaction: &Transaction, nonce_account: Option<&str>) -> Result<SendOutcome, WithdrawalError> {
+        if let Some(nonce_account) = nonce_account {
+            return send_nonce_transaction(client, transaction, &address_to_pubkey(nonce_account)?, &self.config.send);
+        }
+        match send_transaction_idempotent(client, transaction, &self.config.send) {
+            Ok(result) => Ok(SendOutcome::Landed(result.slot)),
+            Err(WriteTransactionError::TransactionFailed(err)) => Ok(SendOutcome::Failed(err)),
+            // Only returned once the transaction history has no status for the signature either,
+            // so a withdrawal that landed just before its blockhash expired is not paid again
+            Err(WriteTransactionError::TransactionExpired) => Ok(SendOutcome::Expired),
+            Err(err) => Err(WithdrawalError::SendError(Box::new(err))),
+        }
+    }
+
+    fn transition(&mut self, index: usize, status: WithdrawalStatus) -> Result<(), WithdrawalError> {
+        self.withdrawals[index].status = status;
+        self.persist()
+    }
+
+    fn persist(&self) -> Result<(), WithdrawalError> {
+        if let Some(path) = &self.path {
+            self.save(path)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the withdrawals to a JSON file. The file is replaced in one step, so a crash while
+    /// saving leaves the previous state rather than a partial file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PersistenceError> {
+        let snapshot = QueueSnapshot { next_id: self.next_id, withdrawals: self.withdrawals.clone() };
+        let json = serde_json::to_string(&snapshot)?;
+        let temporary_path = path.as_ref().with_extension("tmp");
+        fs::write(&temporary_path, json)?;
+        fs::rename(temporary_path, path)?;
+        Ok(())
+    }
+
+    /// Creates a queue from withdrawals previously saved with `save`. The config, policy, nonce
+    /// account and persistence path have to be set again.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PersistenceError> {
+        let json = fs::read_to_string(path)?;
+        let snapshot: QueueSnapshot = serde_json::from_str(&json)?;
+        Ok(Self {
+            next_id: snapshot.next_id,
+            withdrawals: snapshot.withdrawals,
+            ..Self::default()
+        })
+    }
+}
+
+/// Sends a durable nonce transaction until it is confirmed or the nonce advances without it. Unlike
+/// blockhash transactions, a nonce transaction stays valid until the nonce is advanced, so it is
+/// reported `Pending` if it is not confirmed after the configured submissions.
+fn send_nonce_transaction(
+    client: &RpcClient,
+    transaction: &Transaction,
+    nonce_account: &Pubkey,
+    config: &IdempotentSendConfig
+) -> Result<SendOutcome, WithdrawalError> {
+    let signature: Signature = transaction.signatures[0];
+    let send_config = RpcSendTransactionConfig {
+        skip_preflight: true,
+        max_retries: Some(0),
+        ..RpcSendTransactionConfig::default()
+    };
+
+    for submission in 0..=config.max_submissions {
+        // The nonce is read before the status, if our transaction advanced it its status is found
+        let current_nonce = nonce_blockhash(client, nonce_account)?;
+        let nonce_advanced = current_nonce != transaction.message.recent_blockhash;
+        let mut status = client.get_signature_statuses(&[signature])?.value.remove(0);
+        if status.is_none() && nonce_advanced {
+            // A transaction that landed before a restart is only found in the history
+            status = client.get_signature_statuses_with_history(&[signature])?.value.remove(0);
+        }
+        match status {
+            Some(status) if status.satisfies_commitment(CommitmentConfig::confirmed()) => {
+                return Ok(match status.err {
+                    Some(err) => SendOutcome::Failed(err),
+                    None => SendOutcome::Landed(status.slot),
+                });
+            }
+            // Processed transactions can still be dropped on a fork, wait for confirmation
+            Some(_) => {}
+            None if nonce_advanced => return Ok(SendOutcome::Expired),
+            None if submission < config.max_submissions => {
+                if let Err(err) = client.send_transaction_with_config(transaction, send_config) {
+                    warn!("Nonce withdrawal submission {} of {} failed: {}", submission + 1, signature, err);
+                }
+            }
+            None => {}
+        }
+        if submission < config.max_submissions {
+            thread::sleep(config.retry_interval);
+        }
+    }
+    Ok(SendOutcome::Pending)
+}
+
+/// Blockhash currently stored in a durable nonce account.
+fn nonce_blockhash(client: &RpcClient, nonce_account: &Pubkey) -> Result<Hash, WithdrawalError> {
+    let account = get_account_with_commitment(client, nonce_account, client.commitment())
+        .map_err(|err| WithdrawalError::NonceError(err.to_string()))?;
+    let data = data_from_account(&account).map_err(|err| WithdrawalError::NonceError(err.to_string()))?;
+    Ok(data.blockhash())
+}
+
+fn decode_transaction(transaction: &str) -> Result<Transaction, WithdrawalError> {
+    let bytes = STANDARD.decode(transaction).map_err(|err| WithdrawalError::InvalidTransaction(err.to_string()))?;
+    bincode::deserialize(&bytes).map_err(|err| WithdrawalError::InvalidTransaction(err.to_string()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, time::Duration};
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_nonce::{state::{Data, DurableNonce, State}, versions::Versions};
+    use crate::{
+        constants::solana_programs::system_program,
+        test_utils::{account_info_response, account_with_data, mock_client}
+    };
+
+    const WALLET_ADDRESS_1: &str = "ACTC9k56rLB1Z6cUBKToptXrEXussVkiASJeh8p74Fa5";
+
+    fn signature_not_found() -> (RpcRequest, serde_json::Value) {
+        (RpcRequest::GetSignatureStatuses, json!({ "context": { "slot": 1 }, "value": [null] }))
+    }
+
+    fn nonce_account(authority: &Pubkey, durable_nonce: DurableNonce) -> solana_sdk::account::Account {
+        let data = Data::new(*authority, durable_nonce, 5_000);
+        let state = bincode::serialize(&Versions::new(State::Initialized(data))).unwrap();
+        account_with_data(system_program(), state)
+    }
+
+    fn single_submission() -> WithdrawalConfig {
+        WithdrawalConfig {
+            send: IdempotentSendConfig { max_submissions: 0, retry_interval: Duration::ZERO },
+            nonce_fallback_after: 1,
+        }
+    }
+
+    #[test]
+    fn test_process_confirms_and_persists_withdrawals() {
+        let path = env::temp_dir().join(format!("withdrawals_{}.json", Pubkey::new_unique()));
+        let client = mock_client(vec![]);
+        let keypair = Keypair::new();
+
+        let mut queue = WithdrawalQueue::new();
+        queue.persist_to(&path);
+        let id = queue.enqueue(WALLET_ADDRESS_1, 1_000).unwrap();
+        assert!(matches!(queue.enqueue("invalid", 1_000), Err(WithdrawalError::InvalidAddress(_))));
+
+        let finalized = queue.process(&client, &keypair).unwrap();
+        assert!(finalized.len() == 1);
+        assert!(matches!(finalized[0].status, WithdrawalStatus::Confirmed { slot: 1, .. }));
+
+        // confirmed withdrawals are not processed again after a restart
+        let mut loaded_queue = WithdrawalQueue::load(&path).unwrap();
+        assert!(loaded_queue.withdrawal(id) == queue.withdrawal(id));
+        assert!(loaded_queue.process(&client, &keypair).unwrap().is_empty());
+        assert!(loaded_queue.enqueue(WALLET_ADDRESS_1, 1).unwrap() == id + 1);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_signed_withdrawal_is_resent_not_signed_again() {
+        let path = env::temp_dir().join(format!("withdrawals_{}.json", Pubkey::new_unique()));
+        let keypair = Keypair::new();
+        let client = mock_client(vec![
+            signature_not_found(),
+            // rpc error, the outcome of the transaction is unknown
+            (RpcRequest::IsBlockhashValid, json!(null)),
+        ]);
+        let mut queue = WithdrawalQueue::new();
+        queue.persist_to(&path).with_config(single_submission());
+        let id = queue.enqueue(WALLET_ADDRESS_1, 1_000).unwrap();
+        assert!(queue.process(&client, &keypair).unwrap().is_empty());
+        let WithdrawalStatus::Signed { signature, .. } = queue.withdrawal(id).unwrap().status.clone() else {
+            panic!("Expected a signed withdrawal");
+        };
+
+        // the restarted queue confirms the saved transaction
+        let mut loaded_queue = WithdrawalQueue::load(&path).unwrap();
+        let finalized = loaded_queue.process(&mock_client(vec![]), &keypair).unwrap();
+        assert!(matches!(&finalized[0].status, WithdrawalStatus::Confirmed { signature: confirmed, .. } if *confirmed == signature));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_withdrawal_landed_before_expiry_is_not_paid_again() {
+        let keypair = Keypair::new();
+        // the blockhash has expired, but the default status found in the history is a finalized transaction
+        let client = mock_client(vec![
+            signature_not_found(),
+            (RpcRequest::IsBlockhashValid, json!({ "context": { "slot": 1 }, "value": false })),
+        ]);
+        let mut queue = WithdrawalQueue::new();
+        queue.with_config(single_submission());
+        let id = queue.enqueue(WALLET_ADDRESS_1, 1_000).unwrap();
+
+        let finalized = queue.process(&client, &keypair).unwrap();
+        assert!(matches!(finalized[0].status, WithdrawalStatus::Confirmed { slot: 1, .. }));
+        assert!(queue.withdrawal(id).unwrap().expired_transactions == 0);
+    }
+
+    #[test]
+    fn test_policy_violations_are_rejected() {
+        let client = mock_client(vec![]);
+        let mut queue = WithdrawalQueue::new();
+        queue.with_policy(TransactionPolicy { max_lamports_per_transaction: Some(500), ..Default::default() });
+        queue.enqueue(WALLET_ADDRESS_1, 1_000).unwrap();
+
+        let finalized = queue.process(&client, &Keypair::new()).unwrap();
+        assert!(matches!(finalized[0].status, WithdrawalStatus::Rejected(_)));
+    }
+
+    #[test]
+    fn test_expired_withdrawal_falls_back_to_durable_nonce() {
+        let keypair = Keypair::new();
+        let nonce_pubkey = Pubkey::new_unique();
+        let durable_nonce = DurableNonce::from_blockhash(&Hash::new_unique());
+        let nonce = account_info_response(Some(&nonce_account(&keypair.pubkey(), durable_nonce)), 1);
+        let client = mock_client(vec![
+            signature_not_found(),
+            (RpcRequest::IsBlockhashValid, json!({ "context": { "slot": 1 }, "value": false })),
+            // not found in the transaction history either
+            signature_not_found(),
+            // signed with the nonce, which has not advanced yet
+            (RpcRequest::GetAccountInfo, nonce.clone()),
+            (RpcRequest::GetAccountInfo, nonce),
+            signature_not_found(),
+        ]);
+        let mut queue = WithdrawalQueue::new();
+        queue.with_config(single_submission()).with_nonce_account(nonce_pubkey);
+        let id = queue.enqueue(WALLET_ADDRESS_1, 1_000).unwrap();
+
+        // expires with a blockhash, then stays pending with the nonce
+        assert!(queue.process(&client, &keypair).unwrap().is_empty());
+        assert!(queue.withdrawal(id).unwrap().expired_transactions == 1);
+        assert!(queue.withdrawal(id).unwrap().status == WithdrawalStatus::Queued);
+        assert!(queue.process(&client, &keypair).unwrap().is_empty());
+
+        let WithdrawalStatus::Signed { transaction, nonce_account, .. } = &queue.withdrawal(id).unwrap().status else {
+            panic!("Expected a signed withdrawal");
+        };
+        let transaction = decode_transaction(transaction).unwrap();
+        assert!(*nonce_account == Some(nonce_pubkey.to_string()));
+        assert!(transaction.message.recent_blockhash == *durable_nonce.as_hash());
+        assert!(transaction.message.instructions.len() == 2);
+    }
+
+    #[test]
+    fn test_nonce_withdrawal_found_in_history_after_nonce_advanced() {
+        let keypair = Keypair::new();
+        let nonce_pubkey = Pubkey::new_unique();
+        let signed_nonce = Hash::new_unique();
+        let advanced_nonce = DurableNonce::from_blockhash(&Hash::new_unique());
+        let mut transaction = Transaction::new_with_payer(&[transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1_000)], Some(&keypair.pubkey()));
+        transaction.sign(&[&keypair], signed_nonce);
+
+        // the nonce advanced while the queue was stopped, the status cache no longer has the signature
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&nonce_account(&keypair.pubkey(), advanced_nonce)), 1)),
+            signature_not_found(),
+        ]);
+        let outcome = send_nonce_transaction(&client, &transaction, &nonce_pubkey, &single_submission().send).unwrap();
+        assert!(matches!(outcome, SendOutcome::Landed(1)));
+
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&nonce_account(&keypair.pubkey(), advanced_nonce)), 1)),
+            signature_not_found(),
+            signature_not_found(),
+        ]);
+        let outcome = send_nonce_transaction(&client, &transaction, &nonce_pubkey, &single_submission().send).unwrap();
+        assert!(matches!(outcome, SendOutcome::Expired));
+    }
+
+    #[test]
+    fn test_processed_nonce_withdrawal_is_pending() {
+        let keypair = Keypair::new();
+        let durable_nonce = DurableNonce::from_blockhash(&Hash::new_unique());
+        let mut transaction = Transaction::new_with_payer(&[transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1_000)], Some(&keypair.pubkey()));
+        transaction.sign(&[&keypair], *durable_nonce.as_hash());
+
+        let client = mock_client(vec![
+            (RpcRequest::GetAccountInfo, account_info_response(Some(&nonce_account(&keypair.pubkey(), durable_nonce)), 1)),
+            (RpcRequest::GetSignatureStatuses, json!({ "context": { "slot": 2 }, "value": [{
+                "slot": 2,
+                "confirmations": 0,
+                "err": null,
+                "status": { "Ok": null },
+                "confirmationStatus": "processed"
+            }]})),
+        ]);
+        let outcome = send_nonce_transaction(&client, &transaction, &Pubkey::new_unique(), &single_submission().send).unwrap();
+        assert!(matches!(outcome, SendOutcome::Pending));
+    }
+}
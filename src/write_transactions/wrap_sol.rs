@@ -0,0 +1,75 @@
+use solana_sdk::signer::Signer;
+use solana_system_interface::instruction::transfer;
+use spl_associated_token_account::{
+    get_associated_token_address,
+    instruction::create_associated_token_account_idempotent
+};
+use spl_token::instruction::{close_account, sync_native};
+use crate::{
+    constants::solana_programs::{sol_pubkey, token_program},
+    error::TransactionBuilderError
+};
+use super::transaction_builder::TransactionBuilder;
+
+impl TransactionBuilder<'_> {
+    /// Wraps `lamports` of the payer keypair into its WSOL associated token account, creating
+    /// the account if it does not exist. Swap programs only accept WSOL token accounts.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::InstructionError` if the sync instruction can not be created.
+    pub fn wrap_sol(&mut self, lamports: u64) -> Result<&mut Self, TransactionBuilderError> {
+        let payer = self.payer_keypair.pubkey();
+        let wsol_account = get_associated_token_address(&payer, &sol_pubkey());
+        let sync_instruction = sync_native(&token_program(), &wsol_account)
+            .map_err(|err| TransactionBuilderError::InstructionError(err.to_string()))?;
+
+        self.instructions.push(create_associated_token_account_idempotent(
+            &self.fee_payer_pubkey(),
+            &payer,
+            &sol_pubkey(),
+            &token_program(),
+        ));
+        self.instructions.push(transfer(&payer, &wsol_account, lamports));
+        self.instructions.push(sync_instruction);
+        Ok(self)
+    }
+
+    /// Closes the WSOL associated token account of the payer keypair, returning the wrapped
+    /// SOL and the rent to the payer.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `TransactionBuilderError::InstructionError` if the close instruction can not be created.
+    pub fn unwrap_sol(&mut self) -> Result<&mut Self, TransactionBuilderError> {
+        let payer = self.payer_keypair.pubkey();
+        let wsol_account = get_associated_token_address(&payer, &sol_pubkey());
+        let close_instruction = close_account(&token_program(), &wsol_account, &payer, &payer, &[])
+            .map_err(|err| TransactionBuilderError::InstructionError(err.to_string()))?;
+        self.instructions.push(close_instruction);
+        Ok(self)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::mock_client;
+    use super::*;
+    use solana_sdk::signature::Keypair;
+
+    #[test]
+    fn test_wrap_and_unwrap_sol() {
+        let client = mock_client(Vec::new());
+        let keypair = Keypair::new();
+        let wsol_account = get_associated_token_address(&keypair.pubkey(), &sol_pubkey());
+        let mut builder = TransactionBuilder::new(&client, &keypair);
+        builder.wrap_sol(1_000).unwrap().unwrap_sol().unwrap();
+
+        assert!(builder.instructions.len() == 4);
+        assert!(builder.instructions[1] == transfer(&keypair.pubkey(), &wsol_account, 1_000));
+        assert!(builder.instructions[2].accounts[0].pubkey == wsol_account);
+        assert!(builder.instructions[3].accounts[0].pubkey == wsol_account);
+        assert!(builder.instructions[3].accounts[1].pubkey == keypair.pubkey());
+    }
+}